@@ -0,0 +1,60 @@
+//! 漫画压缩包（CBZ/CBR）相关的 Tauri 命令
+
+use crate::formats::comic::ComicEngine;
+use crate::formats::{BookEngine, PageContent, RenderOptions, TocItem};
+use serde::{Deserialize, Serialize};
+use tokio::task;
+
+/// 加载漫画文档的结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComicLoadResult {
+    /// 标题（文件名）
+    pub title: Option<String>,
+    /// 页数（图片数量）
+    pub page_count: u32,
+    /// 按子目录生成的目录
+    pub toc: Vec<TocItem>,
+}
+
+/// 加载漫画文档（只读取压缩包目录）
+#[tauri::command]
+pub async fn comic_load_document(file_path: String) -> Result<ComicLoadResult, String> {
+    task::spawn_blocking(move || {
+        let engine = ComicEngine::from_file(&file_path).map_err(|e| e.to_string())?;
+        Ok(ComicLoadResult {
+            title: engine.get_title(),
+            page_count: engine.get_page_count(),
+            toc: engine.get_toc().map_err(|e| e.to_string())?,
+        })
+    })
+    .await
+    .map_err(|e| format!("漫画解析任务失败: {}", e))?
+}
+
+/// 渲染漫画指定页（页码从 1 开始），可通过 options 控制缩放
+#[tauri::command]
+pub async fn comic_render_page(
+    file_path: String,
+    page: u32,
+    options: Option<RenderOptions>,
+) -> Result<PageContent, String> {
+    task::spawn_blocking(move || {
+        let engine = ComicEngine::from_file(&file_path).map_err(|e| e.to_string())?;
+        engine
+            .render_page(page, &options.unwrap_or_default())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("漫画渲染任务失败: {}", e))?
+}
+
+/// 获取漫画封面（第一张图片的缩略图）
+#[tauri::command]
+pub async fn comic_get_cover(file_path: String) -> Result<Option<Vec<u8>>, String> {
+    task::spawn_blocking(move || {
+        let engine = ComicEngine::from_file(&file_path).map_err(|e| e.to_string())?;
+        Ok(engine.get_cover())
+    })
+    .await
+    .map_err(|e| format!("漫画封面任务失败: {}", e))?
+}
@@ -0,0 +1,387 @@
+//! 从 ZIP 压缩包批量导入
+//! 逐条流式解压受支持格式的书籍文件到指定目录，再走常规的批量入库流程；
+//! 解压大小按实际写出的字节计数，不信任压缩包头部声明的大小
+
+use crate::commands::book::DbState;
+use crate::commands::import::{batch_import_books, find_or_create_group, resolve_book_title, PdfMetadata};
+use crate::formats::{self, BookFormat};
+use crate::models::Book;
+use crate::pdf::doc_cache::with_cached_document;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Emitter};
+use zip::ZipArchive;
+
+/// 进度事件名
+const ARCHIVE_IMPORT_EVENT: &str = "goread:archive:progress";
+
+/// 单个文件解压后的大小上限
+const MAX_ENTRY_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 整个压缩包解压后的总大小上限
+const MAX_TOTAL_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 压缩包条目数上限，防止海量空文件拖慢遍历
+const MAX_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveImportProgress {
+    /// `extract` 解压中，`import` 入库中
+    pub phase: String,
+    pub current: usize,
+    pub total: usize,
+    pub current_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSkippedEntry {
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ArchiveImportResult {
+    pub books: Vec<Book>,
+    /// 压缩包内被跳过的条目（非书籍、路径非法、超过大小限制、所在批次入库失败）
+    pub skipped: Vec<ArchiveSkippedEntry>,
+}
+
+/// 解压出的书籍文件及其在压缩包内的条目名、上级目录名
+struct ExtractedBook {
+    name: String,
+    path: String,
+    folder: Option<String>,
+}
+
+/// 校验压缩包条目路径：拒绝绝对路径、盘符和 `..`，返回规范化的相对路径
+fn sanitize_entry_path(name: &str) -> Option<PathBuf> {
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for part in normalized.split('/') {
+        match part {
+            "" | "." => continue,
+            ".." => return None,
+            // Windows 盘符（C:）
+            p if p.contains(':') => return None,
+            p => path.push(p),
+        }
+    }
+    if path.components().any(|c| !matches!(c, Component::Normal(_))) || path.as_os_str().is_empty() {
+        return None;
+    }
+    Some(path)
+}
+
+/// 跳过目录、macOS 元数据和隐藏文件
+fn is_ignored_entry(path: &Path) -> bool {
+    path.components().any(|c| {
+        let name = c.as_os_str().to_string_lossy();
+        name == "__MACOSX" || name.starts_with('.')
+    })
+}
+
+/// 目标文件已存在时追加序号，避免覆盖已有书籍
+fn unique_target_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|s| format!(".{}", s.to_string_lossy())).unwrap_or_default();
+    let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    (1..)
+        .map(|i| parent.join(format!("{} ({}){}", stem, i, ext)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or(path)
+}
+
+fn emit_progress(app: &AppHandle, phase: &str, current: usize, total: usize, current_file: &str) {
+    let _ = app.emit(
+        ARCHIVE_IMPORT_EVENT,
+        ArchiveImportProgress {
+            phase: phase.to_string(),
+            current,
+            total,
+            current_file: current_file.to_string(),
+        },
+    );
+}
+
+/// 本次解压写出的文件与新建的目录，失败时据此清理
+#[derive(Default)]
+struct ExtractedFiles {
+    books: Vec<ExtractedBook>,
+    /// 按创建顺序记录，清理时倒序删除（只删除空目录）
+    created_dirs: Vec<PathBuf>,
+}
+
+impl ExtractedFiles {
+    /// 创建目录并记录此前不存在的各级目录
+    fn create_dir_all(&mut self, dir: &Path) -> std::io::Result<()> {
+        let missing: Vec<PathBuf> = dir
+            .ancestors()
+            .take_while(|p| !p.as_os_str().is_empty() && !p.exists())
+            .map(Path::to_path_buf)
+            .collect();
+        std::fs::create_dir_all(dir)?;
+        self.created_dirs.extend(missing.into_iter().rev());
+        Ok(())
+    }
+}
+
+/// 删除解压出的文件和新建的空目录
+fn remove_extracted<'a>(paths: impl IntoIterator<Item = &'a str>, created_dirs: &[PathBuf]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+    for dir in created_dirs.iter().rev() {
+        let _ = std::fs::remove_dir(dir);
+    }
+}
+
+/// 解压压缩包中的书籍文件；任何一步失败都会删除本次已解压的文件和新建的目录后返回错误
+fn extract_books(
+    app: &AppHandle,
+    zip_path: &str,
+    extract_dir: &Path,
+    skipped: &mut Vec<ArchiveSkippedEntry>,
+) -> Result<ExtractedFiles, String> {
+    let mut files = ExtractedFiles::default();
+    match extract_entries(app, zip_path, extract_dir, skipped, &mut files) {
+        Ok(()) => Ok(files),
+        Err(e) => {
+            remove_extracted(files.books.iter().map(|b| b.path.as_str()), &files.created_dirs);
+            Err(e)
+        }
+    }
+}
+
+fn extract_entries(
+    app: &AppHandle,
+    zip_path: &str,
+    extract_dir: &Path,
+    skipped: &mut Vec<ArchiveSkippedEntry>,
+    files: &mut ExtractedFiles,
+) -> Result<(), String> {
+    files
+        .create_dir_all(extract_dir)
+        .map_err(|e| format!("创建解压目录失败: {}", e))?;
+    let file = File::open(zip_path).map_err(|e| format!("打开压缩包失败: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| format!("读取压缩包失败: {}", e))?;
+    if archive.len() > MAX_ENTRIES {
+        return Err(format!("压缩包条目过多: {}", archive.len()));
+    }
+
+    let total = archive.len();
+    let mut total_bytes = 0u64;
+
+    for i in 0..total {
+        let mut entry = archive.by_index(i).map_err(|e| format!("读取压缩包条目失败: {}", e))?;
+        let name = entry.name().to_string();
+        emit_progress(app, "extract", i + 1, total, &name);
+
+        if entry.is_dir() {
+            continue;
+        }
+        let mut skip = |reason: &str| {
+            skipped.push(ArchiveSkippedEntry {
+                name: name.clone(),
+                reason: reason.to_string(),
+            })
+        };
+        let Some(relative) = sanitize_entry_path(&name) else {
+            skip("路径非法");
+            continue;
+        };
+        if is_ignored_entry(&relative) {
+            continue;
+        }
+        let supported = relative
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(formats::is_scan_supported_extension);
+        if !supported {
+            skip("不支持的格式");
+            continue;
+        }
+        if entry.size() > MAX_ENTRY_BYTES {
+            skip("文件过大");
+            continue;
+        }
+
+        let target = unique_target_path(extract_dir.join(&relative));
+        if let Some(parent) = target.parent() {
+            files.create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+        }
+        let mut out = File::create(&target).map_err(|e| format!("创建文件失败: {}", e))?;
+        // 多读 1 字节用于判断是否超限
+        let limit = MAX_ENTRY_BYTES.min(MAX_TOTAL_BYTES - total_bytes) + 1;
+        let written = std::io::copy(&mut (&mut entry).take(limit), &mut out);
+        drop(out);
+        let written = match written {
+            Ok(n) => n,
+            Err(e) => {
+                let _ = std::fs::remove_file(&target);
+                skip(&format!("解压失败: {}", e));
+                continue;
+            }
+        };
+
+        if written >= limit {
+            let _ = std::fs::remove_file(&target);
+            if total_bytes + written > MAX_TOTAL_BYTES {
+                return Err(format!("压缩包解压后超过 {} MB 上限", MAX_TOTAL_BYTES / 1024 / 1024));
+            }
+            skip("文件过大");
+            continue;
+        }
+
+        total_bytes += written;
+        files.books.push(ExtractedBook {
+            name,
+            path: target.to_string_lossy().to_string(),
+            folder: relative
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|s| s.to_string_lossy().to_string()),
+        });
+    }
+
+    Ok(())
+}
+
+/// 入库前补齐书名与页数；PDF 读取真实页数，其余格式与前端导入一致记为 1
+pub(crate) async fn build_book_metadata(path: String) -> PdfMetadata {
+    let title = resolve_book_title(path.clone()).await.unwrap_or_else(|_| {
+        Path::new(&path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+    let total_pages = if BookFormat::from_path(&path) == Some(BookFormat::Pdf) {
+        let pdf_path = path.clone();
+        tokio::task::spawn_blocking(move || {
+            with_cached_document(&pdf_path, |_, document| Ok(document.pages().len() as u32)).ok()
+        })
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or(1)
+    } else {
+        1
+    };
+    PdfMetadata {
+        path,
+        title,
+        total_pages,
+        cover_base64: None,
+        // 系列与语言由 batch_import_books 从书籍元数据中提取
+        series: None,
+        series_index: None,
+        language: None,
+    }
+}
+
+/// 从 ZIP 压缩包导入书籍：解压到 `extract_dir` 后批量入库，过程中发送 `goread:archive:progress` 事件
+/// `folders_as_groups` 为 true 时，压缩包内子目录中的书籍按所在目录名归入同名分组，根目录的书籍归入 `group_id`
+/// 按分组分批入库，某一批失败时该批条目记入 `skipped`，其余批次照常导入
+#[tauri::command]
+pub async fn import_from_archive(
+    app_handle: AppHandle,
+    zip_path: String,
+    extract_dir: String,
+    group_id: Option<i64>,
+    folders_as_groups: Option<bool>,
+    db: DbState<'_>,
+) -> Result<ArchiveImportResult, String> {
+    let extract_root = PathBuf::from(&extract_dir);
+
+    let app = app_handle.clone();
+    let (extracted, mut skipped) = tokio::task::spawn_blocking(move || {
+        let mut skipped = Vec::new();
+        extract_books(&app, &zip_path, &extract_root, &mut skipped).map(|books| (books, skipped))
+    })
+    .await
+    .map_err(|e| format!("解压任务失败: {}", e))??;
+
+    println!("[Import] 压缩包解压完成: books={}, skipped={}", extracted.books.len(), skipped.len());
+    let ExtractedFiles { books: extracted, created_dirs } = extracted;
+
+    // 按目标分组归类，每个分组一次事务
+    let folders_as_groups = folders_as_groups.unwrap_or(false);
+    let mut batches: BTreeMap<Option<String>, Vec<ExtractedBook>> = BTreeMap::new();
+    for book in extracted {
+        let key = if folders_as_groups { book.folder.clone() } else { None };
+        batches.entry(key).or_default().push(book);
+    }
+
+    let total: usize = batches.values().map(Vec::len).sum();
+    let mut current = 0usize;
+    let mut books = Vec::with_capacity(total);
+    for (folder, batch) in batches {
+        let imported: Result<Vec<Book>, String> = async {
+            let target_group = match &folder {
+                Some(name) => Some(find_or_create_group(&db, name).await?),
+                None => group_id,
+            };
+            let mut metas = Vec::with_capacity(batch.len());
+            for book in &batch {
+                current += 1;
+                emit_progress(&app_handle, "import", current, total, &book.path);
+                metas.push(build_book_metadata(book.path.clone()).await);
+            }
+            batch_import_books(app_handle.clone(), metas, target_group, None, None, db.clone()).await
+        }
+        .await;
+        match imported {
+            Ok(imported) => books.extend(imported),
+            Err(e) => {
+                // 本批事务已回滚，本批文件不会被书库引用，删除以免残留；之前的批次已提交，继续导入之后的批次
+                eprintln!("[Import] 压缩包批次入库失败: folder={:?}, {}", folder, e);
+                remove_extracted(batch.iter().map(|book| book.path.as_str()), &created_dirs);
+                skipped.extend(batch.into_iter().map(|book| ArchiveSkippedEntry {
+                    name: book.name,
+                    reason: format!("导入失败: {}", e),
+                }));
+            }
+        }
+    }
+
+    Ok(ArchiveImportResult { books, skipped })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_entry_path() {
+        assert_eq!(sanitize_entry_path("books/三体.epub"), Some(PathBuf::from("books").join("三体.epub")));
+        assert_eq!(sanitize_entry_path("./a\\b.txt"), Some(PathBuf::from("a").join("b.txt")));
+        assert_eq!(sanitize_entry_path("../evil.epub"), None);
+        assert_eq!(sanitize_entry_path("a/../../evil.epub"), None);
+        assert_eq!(sanitize_entry_path("/etc/passwd"), None);
+        assert_eq!(sanitize_entry_path("C:\\Windows\\a.txt"), None);
+        assert_eq!(sanitize_entry_path("./"), None);
+    }
+
+    #[test]
+    fn test_remove_extracted() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("archive");
+        let mut files = ExtractedFiles::default();
+        files.create_dir_all(&root.join("a").join("b")).unwrap();
+        let book = root.join("a").join("b").join("1.txt");
+        std::fs::write(&book, "正文").unwrap();
+
+        // 新建的各级目录都被记录，删除文件后目录随之清理
+        assert_eq!(files.created_dirs, vec![root.clone(), root.join("a"), root.join("a").join("b")]);
+        remove_extracted([book.to_string_lossy().as_ref()], &files.created_dirs);
+        assert!(!root.exists());
+    }
+}
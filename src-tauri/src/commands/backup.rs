@@ -1,4 +1,4 @@
-use crate::commands::book::DbState;
+use crate::commands::book::{attach_book_tags, refresh_legacy_chapter_layout, DbState};
 use crate::models::{Book, Bookmark, Group, ReadingSession};
 use chrono::{Local, Utc};
 use serde_json::{json, Value};
@@ -9,10 +9,12 @@ use tauri::{AppHandle, Manager};
 async fn load_tables(
     pool: &SqlitePool,
 ) -> Result<(Vec<Book>, Vec<Group>, Vec<Bookmark>, Vec<ReadingSession>), String> {
-    let books: Vec<Book> = sqlx::query_as::<_, Book>("SELECT * FROM books ORDER BY id")
+    let mut books: Vec<Book> = sqlx::query_as::<_, Book>("SELECT * FROM books ORDER BY id")
         .fetch_all(pool)
         .await
         .map_err(|e| e.to_string())?;
+    // 标签单独存表，随书籍一起导出
+    attach_book_tags(pool, &mut books).await.map_err(|e| e.to_string())?;
     let groups: Vec<Group> = sqlx::query_as::<_, Group>("SELECT * FROM groups ORDER BY id")
         .fetch_all(pool)
         .await
@@ -220,20 +222,24 @@ pub async fn import_app_data(
     for group in groups {
         if let Some(id) = group.id {
             sqlx::query(
-                "INSERT INTO groups (id, name, book_count, created_at) VALUES (?, ?, ?, ?)",
+                "INSERT INTO groups (id, name, book_count, created_at, color, icon) VALUES (?, ?, ?, ?, ?, ?)",
             )
             .bind(id)
             .bind(group.name)
             .bind(group.book_count as i64)
             .bind(group.created_at)
+            .bind(group.color)
+            .bind(group.icon)
             .execute(&mut *tx)
             .await
             .map_err(|e| format!("恢复 groups 表失败: {}", e))?;
         } else {
-            sqlx::query("INSERT INTO groups (name, book_count, created_at) VALUES (?, ?, ?)")
+            sqlx::query("INSERT INTO groups (name, book_count, created_at, color, icon) VALUES (?, ?, ?, ?, ?)")
                 .bind(group.name)
                 .bind(group.book_count as i64)
                 .bind(group.created_at)
+                .bind(group.color)
+                .bind(group.icon)
                 .execute(&mut *tx)
                 .await
                 .map_err(|e| format!("恢复 groups 表失败: {}", e))?;
@@ -241,9 +247,10 @@ pub async fn import_app_data(
     }
 
     for book in books {
-        if let Some(id) = book.id {
+        let tags = book.tags;
+        let book_id = if let Some(id) = book.id {
             sqlx::query(
-                "INSERT INTO books (id, title, file_path, cover_image, current_page, total_pages, last_read_time, group_id, position_in_group, created_at, status, finished_at, recent_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO books (id, title, file_path, cover_image, current_page, total_pages, last_read_time, last_progress_time, group_id, position_in_group, created_at, status, finished_at, recent_order, series, series_index, language, author, reading_position, txt_chapter_normalize) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(id)
             .bind(book.title)
@@ -252,18 +259,27 @@ pub async fn import_app_data(
             .bind(book.current_page as i64)
             .bind(book.total_pages as i64)
             .bind(book.last_read_time)
+            // 旧版备份没有进度推进时间，沿用阅读时间
+            .bind(book.last_progress_time.or(book.last_read_time))
             .bind(book.group_id)
             .bind(book.position_in_group)
             .bind(book.created_at)
             .bind(book.status.unwrap_or(0))
             .bind(book.finished_at)
             .bind(book.recent_order)
+            .bind(book.series)
+            .bind(book.series_index)
+            .bind(book.language)
+            .bind(book.author)
+            .bind(book.reading_position)
+            .bind(book.txt_chapter_normalize.unwrap_or(false))
             .execute(&mut *tx)
             .await
-            .map_err(|e| format!("恢复 books 表失败: {}", e))?;
+            .map_err(|e| format!("恢复 books 表失败: {}", e))?
+            .last_insert_rowid()
         } else {
             sqlx::query(
-                "INSERT INTO books (title, file_path, cover_image, current_page, total_pages, last_read_time, group_id, position_in_group, created_at, status, finished_at, recent_order) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                "INSERT INTO books (title, file_path, cover_image, current_page, total_pages, last_read_time, last_progress_time, group_id, position_in_group, created_at, status, finished_at, recent_order, series, series_index, language, author, reading_position, txt_chapter_normalize) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(book.title)
             .bind(book.file_path)
@@ -271,39 +287,60 @@ pub async fn import_app_data(
             .bind(book.current_page as i64)
             .bind(book.total_pages as i64)
             .bind(book.last_read_time)
+            // 旧版备份没有进度推进时间，沿用阅读时间
+            .bind(book.last_progress_time.or(book.last_read_time))
             .bind(book.group_id)
             .bind(book.position_in_group)
             .bind(book.created_at)
             .bind(book.status.unwrap_or(0))
             .bind(book.finished_at)
             .bind(book.recent_order)
+            .bind(book.series)
+            .bind(book.series_index)
+            .bind(book.language)
+            .bind(book.author)
+            .bind(book.reading_position)
+            .bind(book.txt_chapter_normalize.unwrap_or(false))
             .execute(&mut *tx)
             .await
-            .map_err(|e| format!("恢复 books 表失败: {}", e))?;
+            .map_err(|e| format!("恢复 books 表失败: {}", e))?
+            .last_insert_rowid()
+        };
+        for tag in tags {
+            sqlx::query("INSERT OR IGNORE INTO book_tags (book_id, tag) VALUES (?, ?)")
+                .bind(book_id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| format!("恢复 book_tags 表失败: {}", e))?;
         }
     }
 
     for bookmark in bookmarks {
         if let Some(id) = bookmark.id {
             sqlx::query(
-                "INSERT INTO bookmarks (id, book_id, page_number, title, created_at) VALUES (?, ?, ?, ?, ?)",
+                "INSERT INTO bookmarks (id, book_id, page_number, title, note, created_at, char_offset) VALUES (?, ?, ?, ?, ?, ?, ?)",
             )
             .bind(id)
             .bind(bookmark.book_id)
             .bind(bookmark.page_number as i64)
             .bind(bookmark.title)
+            .bind(bookmark.note)
             .bind(bookmark.created_at)
+            .bind(bookmark.char_offset)
             .execute(&mut *tx)
             .await
             .map_err(|e| format!("恢复 bookmarks 表失败: {}", e))?;
         } else {
             sqlx::query(
-                "INSERT INTO bookmarks (book_id, page_number, title, created_at) VALUES (?, ?, ?, ?)",
+                "INSERT INTO bookmarks (book_id, page_number, title, note, created_at, char_offset) VALUES (?, ?, ?, ?, ?, ?)",
             )
             .bind(bookmark.book_id)
             .bind(bookmark.page_number as i64)
             .bind(bookmark.title)
+            .bind(bookmark.note)
             .bind(bookmark.created_at)
+            .bind(bookmark.char_offset)
             .execute(&mut *tx)
             .await
             .map_err(|e| format!("恢复 bookmarks 表失败: {}", e))?;
@@ -344,6 +381,7 @@ pub async fn import_app_data(
     tx.commit()
         .await
         .map_err(|e| format!("提交事务失败: {}", e))?;
+    refresh_legacy_chapter_layout(pool).await;
 
     let settings_value = data
         .get("settings")
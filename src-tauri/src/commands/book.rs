@@ -1,5 +1,11 @@
+use crate::commands::import::{extract_language_candidate, extract_series_candidate};
 use crate::cover;
-use crate::models::Book;
+use crate::formats::common::normalize_language_tag;
+use crate::models::{
+    BatchBookUpdate, BatchUpdateResult, Book, BookFileStatus, BookMetadataUpdate, LanguageCount, ProgressHistoryPoint,
+    ReadingPosition,
+};
+use crate::txt_commands::set_legacy_chapter_layout;
 use sqlx::SqlitePool;
 use std::sync::Arc;
 use tauri::{AppHandle, State};
@@ -94,6 +100,7 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
             book_id INTEGER NOT NULL,
             page_number INTEGER NOT NULL,
             title TEXT NOT NULL,
+            note TEXT,
             created_at INTEGER DEFAULT (strftime('%s', 'now')),
             FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
         )",
@@ -117,6 +124,55 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
     .execute(&*pool)
     .await?;
 
+    // 阅读进度历史表：按时间记录书籍进度，用于绘制进度曲线
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS progress_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            book_id INTEGER NOT NULL,
+            timestamp INTEGER NOT NULL,
+            position_value REAL NOT NULL,
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&*pool)
+    .await?;
+
+    // 扫描结果增量缓存表：按目录记录 mtime 与直接子项
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS scan_cache (
+            dir_path TEXT PRIMARY KEY,
+            mtime INTEGER NOT NULL,
+            entries_json TEXT NOT NULL,
+            updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+        )",
+    )
+    .execute(&*pool)
+    .await?;
+
+    sqlx::query(crate::commands::settings::CREATE_SETTINGS_TABLE)
+        .execute(&*pool)
+        .await?;
+
+    sqlx::query(crate::commands::theme::CREATE_THEMES_TABLE)
+        .execute(&*pool)
+        .await?;
+
+    sqlx::query(crate::commands::virtual_book::CREATE_VIRTUAL_BOOK_PARTS_TABLE)
+        .execute(&*pool)
+        .await?;
+
+    // 书籍标签表，一本书可有多个标签，删除书籍时级联删除
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS book_tags (
+            book_id INTEGER NOT NULL,
+            tag TEXT NOT NULL,
+            PRIMARY KEY (book_id, tag),
+            FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+        )",
+    )
+    .execute(&*pool)
+    .await?;
+
     // Migrations
     let _ = sqlx::query("ALTER TABLE books ADD COLUMN position_in_group INTEGER")
         .execute(&*pool)
@@ -162,6 +218,57 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
         .execute(&*pool)
         .await;
 
+    // 系列信息字段迁移
+    let _ = sqlx::query("ALTER TABLE books ADD COLUMN series TEXT")
+        .execute(&*pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE books ADD COLUMN series_index REAL")
+        .execute(&*pool)
+        .await;
+
+    // 书籍语言字段迁移（老书为 NULL）
+    let _ = sqlx::query("ALTER TABLE books ADD COLUMN language TEXT")
+        .execute(&*pool)
+        .await;
+
+    // 作者字段迁移（老书为 NULL）
+    let _ = sqlx::query("ALTER TABLE books ADD COLUMN author TEXT")
+        .execute(&*pool)
+        .await;
+
+    // 文件可用状态字段迁移，老书默认可用，由 refresh_book_availability 按需刷新
+    let _ = sqlx::query("ALTER TABLE books ADD COLUMN file_status TEXT DEFAULT 'available'")
+        .execute(&*pool)
+        .await;
+
+    // 取消已读标记字段迁移：用户手动取消后不再因停在末页被自动标记，离开末页后重置。
+    // 首次添加时把停在末页的老书补记为已读完，保持“已读完”只看 status 后的统计口径不变
+    let finished_dismissed_added = sqlx::query("ALTER TABLE books ADD COLUMN finished_dismissed INTEGER DEFAULT 0")
+        .execute(&*pool)
+        .await
+        .is_ok();
+    if finished_dismissed_added {
+        let _ = sqlx::query(
+            "UPDATE books SET status = 1, finished_at = COALESCE(finished_at, last_read_time)
+             WHERE total_pages > 1 AND current_page >= total_pages AND COALESCE(status, 0) != 1",
+        )
+        .execute(&*pool)
+        .await;
+    }
+
+    // 进度推进时间字段迁移：首次添加时沿用已有的阅读时间，保持「在读」列表不变
+    let progress_time_added = sqlx::query("ALTER TABLE books ADD COLUMN last_progress_time INTEGER")
+        .execute(&*pool)
+        .await
+        .is_ok();
+    if progress_time_added {
+        let _ = sqlx::query(
+            "UPDATE books SET last_progress_time = last_read_time WHERE last_read_time IS NOT NULL",
+        )
+        .execute(&*pool)
+        .await;
+    }
+
     let _ = sqlx::query(
         "UPDATE books SET precise_progress = current_page WHERE precise_progress IS NULL",
     )
@@ -193,11 +300,80 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
         .await?;
     }
 
+    // 统一阅读位置字段迁移：首次添加时按格式把已有进度转成对应的位置
+    let position_added = sqlx::query("ALTER TABLE books ADD COLUMN reading_position TEXT")
+        .execute(&*pool)
+        .await
+        .is_ok();
+    if position_added {
+        let rows: Vec<(i64, String, i64, Option<f64>)> = sqlx::query_as(
+            "SELECT id, file_path, current_page, precise_progress FROM books WHERE current_page > 0",
+        )
+        .fetch_all(&*pool)
+        .await?;
+        for (id, file_path, current_page, precise_progress) in rows {
+            let position = ReadingPosition::from_legacy(&file_path, current_page, precise_progress);
+            if let Ok(json) = serde_json::to_string(&position) {
+                let _ = sqlx::query("UPDATE books SET reading_position = ? WHERE id = ?")
+                    .bind(json)
+                    .bind(id)
+                    .execute(&*pool)
+                    .await;
+            }
+        }
+    }
+
+    // 书签备注字段迁移
+    let _ = sqlx::query("ALTER TABLE bookmarks ADD COLUMN note TEXT")
+        .execute(&*pool)
+        .await;
+
+    // TXT 书签全文字符偏移字段迁移
+    let _ = sqlx::query("ALTER TABLE bookmarks ADD COLUMN char_offset INTEGER")
+        .execute(&*pool)
+        .await;
+
     // groups 表 sort_order 字段迁移
     let _ = sqlx::query("ALTER TABLE groups ADD COLUMN sort_order INTEGER")
         .execute(&*pool)
         .await;
 
+    // groups 表颜色与图标字段迁移，老数据保持 NULL，由前端使用默认样式
+    let _ = sqlx::query("ALTER TABLE groups ADD COLUMN color TEXT")
+        .execute(&*pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE groups ADD COLUMN icon TEXT")
+        .execute(&*pool)
+        .await;
+
+    // groups 表拼贴封面字段迁移：cover_stale 为 1 表示组内拼贴变化后需重建，老数据保持未标记
+    let _ = sqlx::query("ALTER TABLE groups ADD COLUMN cover_image TEXT")
+        .execute(&*pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE groups ADD COLUMN cover_stale INTEGER NOT NULL DEFAULT 0")
+        .execute(&*pool)
+        .await;
+
+    // TXT 章节规整标记迁移：新书默认规整，首次添加时把已有书籍记为不规整，保持已保存的章节进度与书签不错位
+    let normalize_added = sqlx::query("ALTER TABLE books ADD COLUMN txt_chapter_normalize INTEGER NOT NULL DEFAULT 1")
+        .execute(&*pool)
+        .await
+        .is_ok();
+    if normalize_added {
+        let _ = sqlx::query("UPDATE books SET txt_chapter_normalize = 0")
+            .execute(&*pool)
+            .await;
+    }
+    refresh_legacy_chapter_layout(&pool).await;
+
+    // 虚拟书成员字符数、章节数迁移：成员文件缺失时按此预留全局字符范围与章节索引
+    let _ = sqlx::query("ALTER TABLE virtual_book_parts ADD COLUMN char_count INTEGER")
+        .execute(&*pool)
+        .await;
+    let _ = sqlx::query("ALTER TABLE virtual_book_parts ADD COLUMN chapter_count INTEGER")
+        .execute(&*pool)
+        .await;
+
     // 为老数据初始化 sort_order（按 created_at 倒序）
     let needs_group_order: i64 =
         sqlx::query_scalar("SELECT COUNT(*) FROM groups WHERE sort_order IS NOT NULL")
@@ -229,9 +405,20 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
     )
     .execute(&*pool)
     .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_books_last_progress_time ON books(last_progress_time)")
+        .execute(&*pool)
+        .await?;
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_books_recent_order ON books(recent_order)")
         .execute(&*pool)
         .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_books_series ON books(series, series_index)",
+    )
+    .execute(&*pool)
+    .await?;
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_books_language ON books(language)")
+        .execute(&*pool)
+        .await?;
 
     // 分组排序索引
     sqlx::query("CREATE INDEX IF NOT EXISTS idx_groups_sort_order ON groups(sort_order)")
@@ -250,6 +437,11 @@ pub async fn init_database(db: DbState<'_>) -> Result<(), Error> {
     )
     .execute(&*pool)
     .await?;
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_progress_history_book ON progress_history(book_id, timestamp)",
+    )
+    .execute(&*pool)
+    .await?;
 
     sqlx::query(
         "UPDATE groups SET book_count = (SELECT COUNT(*) FROM books WHERE group_id = groups.id)",
@@ -271,32 +463,48 @@ pub async fn add_book(
     title: String,
     cover_image: Option<String>,
     total_pages: u32,
+    language: Option<String>,
     db: DbState<'_>,
 ) -> Result<Book, Error> {
+    // 未提供语言时从书籍元数据中提取，系列同样来自元数据（需要打开文件，放在获取数据库锁之前）
+    let language = match language.as_deref().and_then(normalize_language_tag) {
+        Some(language) => Some(language),
+        None => {
+            let file_path = path.clone();
+            tokio::task::spawn_blocking(move || extract_language_candidate(&file_path))
+                .await
+                .unwrap_or(None)
+        }
+    };
+
+    let series_path = path.clone();
+    let (series, series_index) = tokio::task::spawn_blocking(move || extract_series_candidate(&series_path))
+        .await
+        .unwrap_or((None, None));
+
     let pool = db.lock().await;
 
-    // 处理封面：如果是 Base64 则保存为文件
-    let processed_cover = match cover_image.as_deref() {
-        Some(data) if !data.is_empty() => {
-            match cover::process_cover_for_storage(&app_handle, &path, Some(data)).await {
-                Ok(path) => path,
-                Err(e) => {
-                    // 记录错误但不影响导入
-                    eprintln!("[add_book] Failed to save cover: {}", e);
-                    None
-                }
+    // 处理封面：如果是 Base64 则保存为文件，文本类书籍无封面时自动生成
+    let processed_cover =
+        match cover::process_cover_for_storage(&app_handle, &path, cover_image.as_deref(), Some(&title)).await {
+            Ok(path) => path,
+            Err(e) => {
+                // 记录错误但不影响导入
+                eprintln!("[add_book] Failed to save cover: {}", e);
+                None
             }
-        }
-        _ => None,
-    };
+        };
 
     let result = sqlx::query(
-        "INSERT OR IGNORE INTO books (title, file_path, cover_image, total_pages) VALUES (?, ?, ?, ?)"
+        "INSERT OR IGNORE INTO books (title, file_path, cover_image, total_pages, series, series_index, language) VALUES (?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(&title)
     .bind(&path)
     .bind(&processed_cover)
     .bind(total_pages as i64)
+    .bind(&series)
+    .bind(series_index)
+    .bind(&language)
     .execute(&*pool).await?;
 
     let book = if result.rows_affected() == 0 {
@@ -319,30 +527,139 @@ pub async fn add_book(
 pub async fn get_all_books(db: DbState<'_>) -> Result<Vec<Book>, Error> {
     let pool = db.lock().await;
 
-    let books = sqlx::query_as::<_, Book>(
+    let mut books = sqlx::query_as::<_, Book>(
         "SELECT * FROM books ORDER BY last_read_time DESC NULLS LAST, created_at DESC",
     )
     .fetch_all(&*pool)
     .await?;
+    attach_book_tags(&pool, &mut books).await?;
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
+}
+
+/// 获取同一系列的书籍（按系列序号升序，无序号的排在最后）
+#[tauri::command]
+pub async fn get_books_by_series(name: String, db: DbState<'_>) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
+
+    let books = sqlx::query_as::<_, Book>(
+        "SELECT * FROM books WHERE series = ? ORDER BY series_index IS NULL, series_index ASC, created_at ASC",
+    )
+    .bind(name.trim())
+    .fetch_all(&*pool)
+    .await?;
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
+}
+
+/// 按语言筛选书籍：传主语言标签（如 `zh`）时包含所有地区变体（`zh-CN`、`zh-TW`），
+/// 传完整标签时精确匹配；传空字符串返回语言未知的书籍
+#[tauri::command]
+pub async fn get_books_by_language(lang: String, db: DbState<'_>) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
+
+    let books = match normalize_language_tag(&lang) {
+        Some(tag) => {
+            // 完整标签只匹配自身，主标签额外匹配其地区/文字变体
+            let variants = if tag.contains('-') { tag.clone() } else { format!("{}-%", tag) };
+            sqlx::query_as::<_, Book>(
+                "SELECT * FROM books WHERE language = ? OR language LIKE ? ORDER BY last_read_time DESC NULLS LAST, created_at DESC",
+            )
+            .bind(&tag)
+            .bind(&variants)
+            .fetch_all(&*pool)
+            .await?
+        }
+        None if lang.trim().is_empty() => {
+            sqlx::query_as::<_, Book>(
+                "SELECT * FROM books WHERE language IS NULL OR language = '' ORDER BY last_read_time DESC NULLS LAST, created_at DESC",
+            )
+            .fetch_all(&*pool)
+            .await?
+        }
+        None => return Err(Error::Message(format!("无效的语言标签: {}", lang))),
+    };
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
+}
+
+/// 获取书库中的语言及各语言书籍数（按主语言标签汇总，未知语言的 language 为空），按数量降序
+#[tauri::command]
+pub async fn get_library_languages(db: DbState<'_>) -> Result<Vec<LanguageCount>, Error> {
+    let pool = db.lock().await;
+
+    let languages = sqlx::query_as::<_, LanguageCount>(
+        "SELECT CASE WHEN instr(language, '-') > 0 THEN substr(language, 1, instr(language, '-') - 1) ELSE NULLIF(language, '') END AS language, COUNT(*) AS count \
+         FROM books GROUP BY 1 ORDER BY count DESC, language IS NULL, language ASC",
+    )
+    .fetch_all(&*pool)
+    .await?;
+
+    Ok(languages)
+}
+
+/// 已读完判定条件，与统计中的口径保持一致：只看 status，翻到末页时由进度更新自动标记
+const FINISHED_CONDITION: &str = "(status = 1)";
+
+/// 获取已读完的书籍（按完成时间倒序）
+#[tauri::command]
+pub async fn get_finished_books(db: DbState<'_>) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
 
-    Ok(books)
+    let sql = format!(
+        "SELECT * FROM books WHERE {} ORDER BY finished_at DESC NULLS LAST, last_read_time DESC NULLS LAST",
+        FINISHED_CONDITION
+    );
+    let books = sqlx::query_as::<_, Book>(&sql).fetch_all(&*pool).await?;
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
+}
+
+/// 获取未读完的书籍
+#[tauri::command]
+pub async fn get_unfinished_books(db: DbState<'_>) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
+
+    let sql = format!(
+        "SELECT * FROM books WHERE NOT {} ORDER BY last_read_time DESC NULLS LAST, created_at DESC",
+        FINISHED_CONDITION
+    );
+    let books = sqlx::query_as::<_, Book>(&sql).fetch_all(&*pool).await?;
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
 }
 
+/// 获取「在读」书籍：只包含进度真正推进过的书籍
 #[tauri::command]
 pub async fn get_recent_books(limit: u32, db: DbState<'_>) -> Result<Vec<Book>, Error> {
     let pool = db.lock().await;
 
-    // 仅根据 recent_order 维护最近阅读列表，last_read_time 用于排序兜底
+    // 仅根据 recent_order 维护最近阅读列表，last_progress_time 用于排序兜底
     // 这样在清除最近记录时可以保留 last_read_time，不影响已读状态展示
     let books = sqlx::query_as::<_, Book>(
-        "SELECT * FROM books WHERE last_read_time IS NOT NULL 
-         ORDER BY recent_order IS NULL, recent_order DESC, last_read_time DESC LIMIT ?",
+        "SELECT * FROM books WHERE last_progress_time IS NOT NULL 
+         ORDER BY recent_order IS NULL, recent_order DESC, last_progress_time DESC LIMIT ?",
     )
     .bind(limit as i64)
     .fetch_all(&*pool)
     .await?;
 
-    Ok(books)
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
+}
+
+/// 获取「最近打开」书籍：按打开时间倒序，包含只打开未推进进度的书籍
+#[tauri::command]
+pub async fn get_recently_opened_books(limit: u32, db: DbState<'_>) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
+
+    let books = sqlx::query_as::<_, Book>(
+        "SELECT * FROM books WHERE last_read_time IS NOT NULL ORDER BY last_read_time DESC LIMIT ?",
+    )
+    .bind(limit as i64)
+    .fetch_all(&*pool)
+    .await?;
+
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
 }
 
 #[tauri::command]
@@ -352,30 +669,201 @@ pub async fn update_book_progress(
     db: DbState<'_>,
 ) -> Result<(), Error> {
     let pool = db.lock().await;
+    let previous: Option<(String, Option<f64>)> =
+        sqlx::query_as("SELECT file_path, precise_progress FROM books WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&*pool)
+            .await?;
+    apply_book_progress(&pool, id, current_page).await?;
+
+    // 旧接口只有页码：进度变化时按页码重写统一阅读位置，避免 get_reading_position 返回过期位置
+    if let Some((file_path, previous_progress)) = previous {
+        let unchanged = matches!(previous_progress, Some(p) if (p - current_page).abs() < f64::EPSILON);
+        if !unchanged {
+            let position = ReadingPosition::from_legacy(&file_path, current_page.floor() as i64, Some(current_page));
+            let json =
+                serde_json::to_string(&position).map_err(|e| Error::Message(format!("序列化阅读位置失败: {}", e)))?;
+            sqlx::query("UPDATE books SET reading_position = ? WHERE id = ?")
+                .bind(json)
+                .bind(id)
+                .execute(&*pool)
+                .await?;
+        }
+    }
+    Ok(())
+}
 
+/// 更新页码进度，同时维护阅读时间、最近阅读排序和自动读完标记
+async fn apply_book_progress(pool: &SqlitePool, id: i64, current_page: f64) -> Result<(), Error> {
     let page_int = current_page.floor() as i64;
 
+    // 页码/偏移没有变化时不视为阅读推进，避免只打开的书籍顶掉真正在读的
+    let previous: Option<Option<f64>> =
+        sqlx::query_scalar("SELECT precise_progress FROM books WHERE id = ?")
+            .bind(id)
+            .fetch_optional(pool)
+            .await?;
+    if let Some(Some(previous)) = previous {
+        if (previous - current_page).abs() < f64::EPSILON {
+            return Ok(());
+        }
+    }
+
     // 获取当前最大 recent_order
     let max_order: Option<i64> =
         sqlx::query_scalar("SELECT MAX(recent_order) FROM books WHERE last_read_time IS NOT NULL")
-            .fetch_one(&*pool)
+            .fetch_one(pool)
             .await?;
     let next_order = max_order.unwrap_or(0) + 1;
 
-    // 同时更新进度、阅读时间和排序
+    // 同时更新进度、阅读时间、进度推进时间和排序
     sqlx::query(
-        "UPDATE books SET current_page = ?, precise_progress = ?, last_read_time = strftime('%s', 'now'), recent_order = ? WHERE id = ?",
+        "UPDATE books SET current_page = ?, precise_progress = ?, last_read_time = strftime('%s', 'now'),
+         last_progress_time = strftime('%s', 'now'), recent_order = ? WHERE id = ?",
     )
     .bind(page_int)
     .bind(current_page)
     .bind(next_order)
     .bind(id)
-    .execute(&*pool)
+    .execute(pool)
+    .await?;
+
+    // 离开末页后重置手动取消标记，之后再次读到末页时重新自动标记
+    sqlx::query("UPDATE books SET finished_dismissed = 0 WHERE id = ? AND current_page < total_pages")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    // 翻到末页时自动标记为已读完（保留首次完成时间）；虚拟单页书籍与用户手动取消过的书籍不自动标记
+    sqlx::query(
+        "UPDATE books SET status = 1, finished_at = COALESCE(finished_at, strftime('%s', 'now'))
+         WHERE id = ? AND total_pages > 1 AND current_page >= total_pages AND COALESCE(status, 0) != 1
+         AND COALESCE(finished_dismissed, 0) = 0",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    // 最近阅读顺序变化可能改变分组拼贴，仅在拼贴所用书籍或顺序变化时打需重建标记
+    let group_id = sqlx::query_scalar::<_, Option<i64>>("SELECT group_id FROM books WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .flatten();
+    if let Some(group_id) = group_id {
+        super::cover::mark_group_cover_stale(pool, group_id).await?;
+    }
+
+    record_progress_history(pool, id, current_page).await?;
+
+    Ok(())
+}
+
+/// 同一本书两条进度历史的最小时间间隔（秒）
+const PROGRESS_HISTORY_INTERVAL_SECS: i64 = 3600;
+
+/// 页码变化超过该值时不受时间间隔限制，直接记录
+const PROGRESS_HISTORY_MIN_DELTA: f64 = 10.0;
+
+/// 每本书保留的进度历史条数上限，超出时删除最早的记录
+const MAX_PROGRESS_HISTORY: i64 = 500;
+
+/// 追加一条进度历史：距上一条不足一小时且页码变化较小时跳过，写入后裁剪超长历史
+async fn record_progress_history(pool: &SqlitePool, id: i64, position_value: f64) -> Result<(), Error> {
+    let now = chrono::Utc::now().timestamp();
+    let last: Option<(i64, f64)> = sqlx::query_as(
+        "SELECT timestamp, position_value FROM progress_history WHERE book_id = ? ORDER BY timestamp DESC, id DESC LIMIT 1",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    if let Some((last_time, last_value)) = last {
+        if now - last_time < PROGRESS_HISTORY_INTERVAL_SECS
+            && (position_value - last_value).abs() < PROGRESS_HISTORY_MIN_DELTA
+        {
+            return Ok(());
+        }
+    }
+
+    sqlx::query("INSERT INTO progress_history (book_id, timestamp, position_value) VALUES (?, ?, ?)")
+        .bind(id)
+        .bind(now)
+        .bind(position_value)
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "DELETE FROM progress_history WHERE book_id = ? AND id NOT IN (
+            SELECT id FROM progress_history WHERE book_id = ? ORDER BY timestamp DESC, id DESC LIMIT ?
+        )",
+    )
+    .bind(id)
+    .bind(id)
+    .bind(MAX_PROGRESS_HISTORY)
+    .execute(pool)
     .await?;
 
     Ok(())
 }
 
+/// 获取书籍的进度历史，按时间升序返回
+#[tauri::command]
+pub async fn get_progress_history(book_id: i64, db: DbState<'_>) -> Result<Vec<ProgressHistoryPoint>, Error> {
+    let pool = db.lock().await;
+    let points = sqlx::query_as::<_, ProgressHistoryPoint>(
+        "SELECT timestamp, position_value FROM progress_history WHERE book_id = ? ORDER BY timestamp ASC, id ASC",
+    )
+    .bind(book_id)
+    .fetch_all(&*pool)
+    .await?;
+    Ok(points)
+}
+
+/// 更新统一阅读位置；`current_page` 为前端换算好的兼容页码，未提供时由位置推导（CFI 无法推导时不更新旧进度）
+#[tauri::command]
+pub async fn update_reading_position(
+    id: i64,
+    position: ReadingPosition,
+    current_page: Option<f64>,
+    db: DbState<'_>,
+) -> Result<(), Error> {
+    let json = serde_json::to_string(&position).map_err(|e| Error::Message(format!("序列化阅读位置失败: {}", e)))?;
+    let pool = db.lock().await;
+    sqlx::query("UPDATE books SET reading_position = ? WHERE id = ?")
+        .bind(json)
+        .bind(id)
+        .execute(&*pool)
+        .await?;
+
+    match current_page.or_else(|| position.legacy_page()) {
+        Some(page) => apply_book_progress(&pool, id, page).await,
+        None => {
+            sqlx::query("UPDATE books SET last_read_time = strftime('%s', 'now') WHERE id = ?")
+                .bind(id)
+                .execute(&*pool)
+                .await?;
+            Ok(())
+        }
+    }
+}
+
+/// 读取统一阅读位置；未记录过时按旧进度字段推导，书籍不存在时返回 None
+#[tauri::command]
+pub async fn get_reading_position(id: i64, db: DbState<'_>) -> Result<Option<ReadingPosition>, Error> {
+    let pool = db.lock().await;
+    let row: Option<(Option<String>, String, i64, Option<f64>)> = sqlx::query_as(
+        "SELECT reading_position, file_path, current_page, precise_progress FROM books WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(&*pool)
+    .await?;
+
+    Ok(row.map(|(json, file_path, current_page, precise_progress)| {
+        json.and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_else(|| ReadingPosition::from_legacy(&file_path, current_page, precise_progress))
+    }))
+}
+
 #[tauri::command]
 pub async fn update_book_total_pages(
     id: i64,
@@ -398,7 +886,7 @@ pub async fn update_book_theme(id: i64, theme: Option<String>, db: DbState<'_>)
     let pool = db.lock().await;
 
     if let Some(t) = theme.as_deref() {
-        if t != "light" && t != "dark" {
+        if !matches!(t, "light" | "dark" | "dark_smart") {
             return Err(Error::Message("Invalid theme".to_string()));
         }
     }
@@ -509,21 +997,11 @@ pub async fn mark_book_opened(
 ) -> Result<bool, Error> {
     let pool = db.lock().await;
 
-    // 获取当前最大 recent_order
-    let max_order: Option<i64> =
-        sqlx::query_scalar("SELECT MAX(recent_order) FROM books WHERE last_read_time IS NOT NULL")
-            .fetch_one(&*pool)
-            .await?;
-    let next_order = max_order.unwrap_or(0) + 1;
-
-    // 同时更新阅读时间和排序，使该书移到最前
-    sqlx::query(
-        "UPDATE books SET last_read_time = strftime('%s', 'now'), recent_order = ? WHERE id = ?",
-    )
-    .bind(next_order)
-    .bind(id)
-    .execute(&*pool)
-    .await?;
+    // 只记录打开时间（「最近打开」），「在读」排序由进度推进时更新
+    sqlx::query("UPDATE books SET last_read_time = strftime('%s', 'now') WHERE id = ?")
+        .bind(id)
+        .execute(&*pool)
+        .await?;
 
     // 封面兜底检查：判断封面文件是否存在，返回是否需要重建
     let book: Option<Book> = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
@@ -561,20 +1039,6 @@ pub async fn delete_book(
         .await?;
 
     if let Some(ref book) = book {
-        // 删除封面文件
-        if let Some(ref cover_image) = book.cover_image {
-            if cover::is_file_path(cover_image) {
-                match cover::delete_cover_file(&app_handle, cover_image).await {
-                    Ok(_) => {
-                        println!("[delete_book] Successfully deleted cover file: {}", cover_image);
-                    }
-                    Err(e) => {
-                        eprintln!("[delete_book] Failed to delete cover file {}: {}", cover_image, e);
-                    }
-                }
-            }
-        }
-
         // 删除本地书籍文件
         if delete_local {
             match tokio::fs::remove_file(&book.file_path).await {
@@ -597,6 +1061,7 @@ pub async fn delete_book(
         .bind(id)
         .execute(&*pool)
         .await?;
+    crate::commands::virtual_book::evict_merged_meta(id);
 
     if let Some(gid) = old_group {
         sqlx::query(
@@ -609,6 +1074,16 @@ pub async fn delete_book(
             .bind(gid)
             .execute(&*pool)
             .await?;
+        super::cover::invalidate_group_cover(&app_handle, &pool, gid).await?;
+    }
+
+    // 记录删除后再清理封面文件，避免误删仍被其他书籍引用的封面
+    if let Some(cover_image) = book.and_then(|b| b.cover_image) {
+        if let Err(e) =
+            super::cover::remove_book_cover_if_unreferenced(&app_handle, &pool, &cover_image).await
+        {
+            eprintln!("[delete_book] Failed to clean up cover {}: {}", cover_image, e);
+        }
     }
     Ok(())
 }
@@ -677,6 +1152,28 @@ pub async fn reorder_recent_books(ordered_ids: Vec<i64>, db: DbState<'_>) -> Res
     Ok(())
 }
 
+/// 更新书籍系列信息（系列名为空时清除）
+#[tauri::command]
+pub async fn update_book_series(
+    id: i64,
+    series: Option<String>,
+    series_index: Option<f32>,
+    db: DbState<'_>,
+) -> Result<(), Error> {
+    let series = series
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty());
+    let series_index = series.as_ref().and(series_index);
+    let pool = db.lock().await;
+    sqlx::query("UPDATE books SET series = ?, series_index = ? WHERE id = ?")
+        .bind(&series)
+        .bind(series_index)
+        .bind(id)
+        .execute(&*pool)
+        .await?;
+    Ok(())
+}
+
 /// 修改书籍标题
 #[tauri::command]
 pub async fn rename_book(id: i64, new_title: String, db: DbState<'_>) -> Result<(), Error> {
@@ -692,3 +1189,341 @@ pub async fn rename_book(id: i64, new_title: String, db: DbState<'_>) -> Result<
         .await?;
     Ok(())
 }
+
+/// 手动编辑书名、作者与封面，返回更新后的书籍
+/// `cover_data` 为 Base64 时存盘并替换旧封面，旧封面文件不再被引用时删除
+#[tauri::command]
+pub async fn update_book_metadata(
+    app_handle: AppHandle,
+    id: i64,
+    metadata: BookMetadataUpdate,
+    db: DbState<'_>,
+) -> Result<Book, Error> {
+    let title = metadata.title.map(|t| t.trim().to_string());
+    if title.as_deref().is_some_and(str::is_empty) {
+        return Err(Error::Message("书名不能为空".to_string()));
+    }
+
+    let pool = db.lock().await;
+    let book = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&*pool)
+        .await?
+        .ok_or_else(|| Error::Message(format!("书籍不存在: {}", id)))?;
+
+    let cover_image = match metadata.cover_data.as_deref().filter(|data| !data.is_empty()) {
+        Some(data) => cover::process_cover_for_storage(&app_handle, &book.file_path, Some(data), None)
+            .await
+            .map_err(Error::Message)?,
+        None => book.cover_image.clone(),
+    };
+    let author = match metadata.author {
+        Some(author) => Some(author.trim().to_string()).filter(|a| !a.is_empty()),
+        None => book.author.clone(),
+    };
+
+    sqlx::query("UPDATE books SET title = ?, author = ?, cover_image = ? WHERE id = ?")
+        .bind(title.as_deref().unwrap_or(&book.title))
+        .bind(&author)
+        .bind(&cover_image)
+        .bind(id)
+        .execute(&*pool)
+        .await?;
+
+    // 新封面与旧封面路径相同时已原地覆盖，否则清理不再引用的旧封面
+    if let Some(old_cover) = book.cover_image.as_deref().filter(|old| Some(*old) != cover_image.as_deref()) {
+        super::cover::remove_book_cover_if_unreferenced(&app_handle, &pool, old_cover).await?;
+    }
+
+    let updated = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
+        .bind(id)
+        .fetch_one(&*pool)
+        .await?;
+    println!("[book] 已更新书籍信息: id={}, title={}", id, updated.title);
+    Ok(updated.with_progress_percent())
+}
+
+/// 规整标签：去除首尾空白，丢弃空标签并去重（保持原顺序）
+fn normalize_tags(tags: Option<Vec<String>>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.unwrap_or_default() {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// 读取书籍的全部标签（按名称排序）
+pub(crate) async fn load_book_tags<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    book_id: i64,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT tag FROM book_tags WHERE book_id = ? ORDER BY tag")
+        .bind(book_id)
+        .fetch_all(executor)
+        .await
+}
+
+/// 从数据库重新加载不做章节规整的 TXT 路径（书籍本身及其所属虚拟书的成员文件）
+pub(crate) async fn refresh_legacy_chapter_layout(pool: &SqlitePool) {
+    let paths: Vec<String> = sqlx::query_scalar(
+        "SELECT file_path FROM books WHERE txt_chapter_normalize = 0
+         UNION SELECT p.file_path FROM virtual_book_parts p JOIN books b ON b.id = p.book_id
+         WHERE b.txt_chapter_normalize = 0",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+    set_legacy_chapter_layout(paths);
+}
+
+/// 一次查询填充多本书籍的标签
+pub(crate) async fn attach_book_tags(pool: &SqlitePool, books: &mut [Book]) -> Result<(), sqlx::Error> {
+    let rows: Vec<(i64, String)> = sqlx::query_as("SELECT book_id, tag FROM book_tags ORDER BY book_id, tag")
+        .fetch_all(pool)
+        .await?;
+    let mut tags: std::collections::HashMap<i64, Vec<String>> = std::collections::HashMap::new();
+    for (book_id, tag) in rows {
+        tags.entry(book_id).or_default().push(tag);
+    }
+    for book in books.iter_mut() {
+        book.tags = book.id.and_then(|id| tags.remove(&id)).unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// 获取书籍的标签
+#[tauri::command]
+pub async fn get_book_tags(book_id: i64, db: DbState<'_>) -> Result<Vec<String>, Error> {
+    let pool = db.lock().await;
+    Ok(load_book_tags(&*pool, book_id).await?)
+}
+
+/// 获取书库中出现过的全部标签（按名称排序）
+#[tauri::command]
+pub async fn get_all_tags(db: DbState<'_>) -> Result<Vec<String>, Error> {
+    let pool = db.lock().await;
+    let tags = sqlx::query_scalar("SELECT DISTINCT tag FROM book_tags ORDER BY tag")
+        .fetch_all(&*pool)
+        .await?;
+    Ok(tags)
+}
+
+/// 在一个事务中批量移动分组、增删标签、标记已读完，返回更新后的书籍（含标签）
+/// 不存在的 id 记录在 `skipped` 中；`atomic` 为 true 时出现不存在的 id 则整体不做修改并返回错误
+#[tauri::command]
+pub async fn batch_update_books(
+    app_handle: AppHandle,
+    book_ids: Vec<i64>,
+    update: BatchBookUpdate,
+    atomic: Option<bool>,
+    db: DbState<'_>,
+) -> Result<BatchUpdateResult, Error> {
+    let add_tags = normalize_tags(update.add_tags);
+    let remove_tags = normalize_tags(update.remove_tags);
+
+    let mut ids = Vec::with_capacity(book_ids.len());
+    for id in book_ids {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    let pool = db.lock().await;
+    let mut tx = pool.begin().await?;
+
+    // 区分存在与不存在的书籍，同时记下原分组用于之后重算计数
+    let mut existing: Vec<(i64, Option<i64>)> = Vec::with_capacity(ids.len());
+    let mut skipped = Vec::new();
+    for &id in &ids {
+        let group: Option<Option<i64>> = sqlx::query_scalar("SELECT group_id FROM books WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        match group {
+            Some(group) => existing.push((id, group)),
+            None => skipped.push(id),
+        }
+    }
+    if atomic.unwrap_or(false) && !skipped.is_empty() {
+        return Err(Error::Message(format!("书籍不存在: {:?}", skipped)));
+    }
+
+    let mut touched_groups: Vec<i64> = Vec::new();
+    if let Some(gid) = update.group_id {
+        let group_exists: Option<i64> = sqlx::query_scalar("SELECT id FROM groups WHERE id = ?")
+            .bind(gid)
+            .fetch_optional(&mut *tx)
+            .await?;
+        if group_exists.is_none() {
+            return Err(Error::Message(format!("分组不存在: {}", gid)));
+        }
+
+        let max_pos: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(position_in_group) FROM books WHERE group_id = ?")
+                .bind(gid)
+                .fetch_one(&mut *tx)
+                .await?;
+        let mut next_pos = max_pos.unwrap_or(0);
+        for &(id, prev_group) in &existing {
+            if prev_group == Some(gid) {
+                continue;
+            }
+            next_pos += 1;
+            sqlx::query("UPDATE books SET group_id = ?, position_in_group = ? WHERE id = ?")
+                .bind(gid)
+                .bind(next_pos)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            touched_groups.extend(prev_group);
+        }
+        touched_groups.push(gid);
+    }
+
+    for &(id, _) in &existing {
+        for tag in add_tags.iter().filter(|t| !remove_tags.contains(t)) {
+            sqlx::query("INSERT OR IGNORE INTO book_tags (book_id, tag) VALUES (?, ?)")
+                .bind(id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for tag in &remove_tags {
+            sqlx::query("DELETE FROM book_tags WHERE book_id = ? AND tag = ?")
+                .bind(id)
+                .bind(tag)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    if let Some(finished) = update.finished {
+        let now = chrono::Local::now().timestamp();
+        for &(id, _) in &existing {
+            if finished {
+                sqlx::query("UPDATE books SET status = 1, finished_at = ?, finished_dismissed = 0 WHERE id = ?")
+                    .bind(now)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            } else {
+                sqlx::query("UPDATE books SET status = 0, finished_at = NULL, finished_dismissed = 1 WHERE id = ?")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+        }
+    }
+
+    // 重算涉及分组的书籍数量，原分组被移空时删除
+    touched_groups.sort_unstable();
+    touched_groups.dedup();
+    for &gid in &touched_groups {
+        sqlx::query("UPDATE groups SET book_count = (SELECT COUNT(*) FROM books WHERE group_id = ?) WHERE id = ?")
+            .bind(gid)
+            .bind(gid)
+            .execute(&mut *tx)
+            .await?;
+        if Some(gid) != update.group_id {
+            sqlx::query("DELETE FROM groups WHERE id = ? AND book_count = 0")
+                .bind(gid)
+                .execute(&mut *tx)
+                .await?;
+        }
+    }
+
+    let mut books = Vec::with_capacity(existing.len());
+    for &(id, _) in &existing {
+        let mut book = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        book.tags = load_book_tags(&mut *tx, id).await?;
+        books.push(book.with_progress_percent());
+    }
+    tx.commit().await?;
+
+    // 移出和移入的分组拼贴可能变化（已删除的空分组失效操作为空操作）
+    for gid in touched_groups {
+        super::cover::invalidate_group_cover(&app_handle, &pool, gid).await?;
+    }
+
+    println!(
+        "[batch_update_books] Updated {} book(s), skipped {}",
+        books.len(),
+        skipped.len()
+    );
+    Ok(BatchUpdateResult { books, skipped })
+}
+
+/// 检查书籍文件当前是否可访问：文件存在为可用；不存在但同目录下有 iCloud 占位文件时为下载中；否则为缺失；
+/// 虚拟书没有对应的实体文件，始终视为可用（成员文件缺失在打开时单独提示）
+fn detect_file_status(file_path: &str) -> BookFileStatus {
+    let path = std::path::Path::new(file_path);
+    if path.is_file() || super::virtual_book::is_virtual_book_path(file_path) {
+        return BookFileStatus::Available;
+    }
+    let placeholder = path
+        .file_name()
+        .map(|name| path.with_file_name(format!(".{}.icloud", name.to_string_lossy())));
+    if placeholder.is_some_and(|p| p.is_file()) {
+        BookFileStatus::Downloading
+    } else {
+        BookFileStatus::Missing
+    }
+}
+
+/// 刷新书籍文件可用状态（外置存储挂载、云端下载完成后调用），`book_ids` 为空时检查全部书籍
+/// 返回被检查书籍的最新数据
+#[tauri::command]
+pub async fn refresh_book_availability(
+    book_ids: Option<Vec<i64>>,
+    db: DbState<'_>,
+) -> Result<Vec<Book>, Error> {
+    let pool = db.lock().await;
+
+    let books = match book_ids {
+        Some(ids) => {
+            let mut books = Vec::with_capacity(ids.len());
+            for id in ids {
+                if let Some(book) = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
+                    .bind(id)
+                    .fetch_optional(&*pool)
+                    .await?
+                {
+                    books.push(book);
+                }
+            }
+            books
+        }
+        None => sqlx::query_as::<_, Book>("SELECT * FROM books").fetch_all(&*pool).await?,
+    };
+
+    let mut tx = pool.begin().await?;
+    let mut result = Vec::with_capacity(books.len());
+    let mut changed = 0;
+    for mut book in books {
+        let status = detect_file_status(&book.file_path).as_str();
+        if book.file_status.as_deref() != Some(status) {
+            sqlx::query("UPDATE books SET file_status = ? WHERE id = ?")
+                .bind(status)
+                .bind(book.id)
+                .execute(&mut *tx)
+                .await?;
+            book.file_status = Some(status.to_string());
+            changed += 1;
+        }
+        result.push(book.with_progress_percent());
+    }
+    tx.commit().await?;
+
+    println!(
+        "[refresh_book_availability] Checked {} book(s), {} changed",
+        result.len(),
+        changed
+    );
+    Ok(result)
+}
@@ -1,22 +1,37 @@
 use crate::models::Bookmark;
 use crate::commands::book::{DbState, Error};
+use crate::commands::virtual_book::{cached_merged_meta, is_virtual_book_path};
+use crate::formats::{epub, BookFormat};
+use crate::pdf::{Bookmark as OutlineItem, RenderOptions, RenderQuality, MAX_THUMBNAIL_BATCH_PAGES};
+use crate::pdf_commands::PdfManagerState;
+use crate::txt_commands::txt_load_metadata;
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::State;
 
+/// 添加书签；TXT 书籍可同时传入全文字符偏移 `char_offset`，打开时按偏移精确定位
 #[tauri::command]
 pub async fn add_bookmark(
     book_id: i64,
     page_number: u32,
     title: String,
+    note: Option<String>,
+    char_offset: Option<i64>,
     db: DbState<'_>,
 ) -> Result<Bookmark, Error> {
     let pool = db.lock().await;
 
-    let result =
-        sqlx::query("INSERT INTO bookmarks (book_id, page_number, title) VALUES (?, ?, ?)")
-            .bind(book_id)
-            .bind(page_number as i64)
-            .bind(&title)
-            .execute(&*pool)
-            .await?;
+    let result = sqlx::query(
+        "INSERT INTO bookmarks (book_id, page_number, title, note, char_offset) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(book_id)
+    .bind(page_number as i64)
+    .bind(&title)
+    .bind(&note)
+    .bind(char_offset.filter(|offset| *offset >= 0))
+    .execute(&*pool)
+    .await?;
 
     let bookmark_id = result.last_insert_rowid();
 
@@ -28,20 +43,325 @@ pub async fn add_bookmark(
     Ok(bookmark)
 }
 
+/// 修改书签标题和备注
 #[tauri::command]
-pub async fn get_bookmarks(book_id: i64, db: DbState<'_>) -> Result<Vec<Bookmark>, Error> {
+pub async fn update_bookmark(
+    id: i64,
+    title: String,
+    note: Option<String>,
+    db: DbState<'_>,
+) -> Result<Bookmark, Error> {
     let pool = db.lock().await;
 
-    let bookmarks = sqlx::query_as::<_, Bookmark>(
-        "SELECT * FROM bookmarks WHERE book_id = ? ORDER BY page_number",
-    )
-    .bind(book_id)
-    .fetch_all(&*pool)
-    .await?;
+    let result = sqlx::query("UPDATE bookmarks SET title = ?, note = ? WHERE id = ?")
+        .bind(&title)
+        .bind(&note)
+        .bind(id)
+        .execute(&*pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(Error::Message(format!("书签不存在: {}", id)));
+    }
+
+    let bookmark = sqlx::query_as::<_, Bookmark>("SELECT * FROM bookmarks WHERE id = ?")
+        .bind(id)
+        .fetch_one(&*pool)
+        .await?;
+
+    Ok(bookmark)
+}
+
+/// 获取书签（按页码排序）
+/// PDF、EPUB 按目录、TXT 按字符偏移所在章节为每个书签附带所属章节标题；`with_thumbnail` 为 true 时附带所在页缩略图，
+/// 单次最多渲染 `MAX_THUMBNAIL_BATCH_PAGES` 个不同页面，其余书签的缩略图为空，由 `get_bookmark_thumbnail` 懒加载
+/// `force_encoding` 为阅读器当前手动指定的 TXT 编码，章节划分与阅读时保持一致
+#[tauri::command]
+pub async fn get_bookmarks(
+    book_id: i64,
+    with_thumbnail: Option<bool>,
+    force_encoding: Option<String>,
+    db: DbState<'_>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Vec<Bookmark>, Error> {
+    let (mut bookmarks, file_path) = {
+        let pool = db.lock().await;
+
+        let bookmarks = sqlx::query_as::<_, Bookmark>(
+            "SELECT * FROM bookmarks WHERE book_id = ? ORDER BY page_number",
+        )
+        .bind(book_id)
+        .fetch_all(&*pool)
+        .await?;
+
+        let file_path: Option<String> =
+            sqlx::query_scalar("SELECT file_path FROM books WHERE id = ?")
+                .bind(book_id)
+                .fetch_optional(&*pool)
+                .await?;
+
+        (bookmarks, file_path)
+    };
+
+    let file_path = match file_path {
+        Some(path) if !bookmarks.is_empty() => path,
+        _ => return Ok(bookmarks),
+    };
+
+    match BookFormat::from_path(&file_path) {
+        Some(BookFormat::Epub) => {
+            let chapters = load_epub_chapters(&file_path).await;
+            for bookmark in bookmarks.iter_mut() {
+                bookmark.chapter_title = chapter_at(&chapters, bookmark.page_number);
+            }
+        }
+        Some(BookFormat::Txt) => {
+            let chapters = load_txt_chapters(book_id, &file_path, force_encoding, &db).await;
+            for bookmark in bookmarks.iter_mut() {
+                bookmark.chapter_title = bookmark
+                    .char_offset
+                    .filter(|offset| *offset >= 0)
+                    .and_then(|offset| chapter_at(&chapters, offset as u64));
+            }
+        }
+        _ => {}
+    }
+
+    if BookFormat::from_path(&file_path) == Some(BookFormat::Pdf) {
+        let chapters = load_pdf_chapters(&file_path, &manager).await;
+        for bookmark in bookmarks.iter_mut() {
+            bookmark.chapter_title = chapter_at(&chapters, bookmark.page_number);
+        }
+
+        if with_thumbnail.unwrap_or(false) {
+            let mut pages: Vec<u32> = bookmarks.iter().map(|b| b.page_number).collect();
+            pages.dedup();
+            pages.truncate(MAX_THUMBNAIL_BATCH_PAGES as usize);
+            let thumbnails = render_page_thumbnails(&file_path, pages, &manager).await;
+            for bookmark in bookmarks.iter_mut() {
+                bookmark.thumbnail = thumbnails.get(&bookmark.page_number).cloned();
+            }
+        }
+    }
 
     Ok(bookmarks)
 }
 
+/// 获取单条 PDF 书签所在页的缩略图（data URL），用于列表懒加载；非 PDF 书籍返回 None
+#[tauri::command]
+pub async fn get_bookmark_thumbnail(
+    bookmark_id: i64,
+    db: DbState<'_>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Option<String>, Error> {
+    let row: Option<(u32, String)> = {
+        let pool = db.lock().await;
+        sqlx::query_as(
+            "SELECT bookmarks.page_number, books.file_path FROM bookmarks JOIN books ON books.id = bookmarks.book_id WHERE bookmarks.id = ?",
+        )
+        .bind(bookmark_id)
+        .fetch_optional(&*pool)
+        .await?
+    };
+
+    let (page_number, file_path) = row.ok_or_else(|| Error::Message(format!("书签不存在: {}", bookmark_id)))?;
+    if BookFormat::from_path(&file_path) != Some(BookFormat::Pdf) {
+        return Ok(None);
+    }
+    Ok(render_page_thumbnails(&file_path, vec![page_number], &manager)
+        .await
+        .remove(&page_number))
+}
+
+/// 渲染 PDF 指定页的缩略图（走引擎缩略图缓存，缺失时才渲染），返回 页码 -> data URL；失败时返回空表，不影响书签本身
+async fn render_page_thumbnails(
+    file_path: &str,
+    pages: Vec<u32>,
+    manager: &State<'_, PdfManagerState>,
+) -> HashMap<u32, String> {
+    let (engine_arc, render_flags) = {
+        let manager = manager.lock().await;
+        match manager.get_or_create_engine(file_path).await {
+            Ok(engine) => (engine, manager.render_flags()),
+            Err(e) => {
+                eprintln!("[Bookmark] 加载 PDF 失败: {}", e);
+                return HashMap::new();
+            }
+        }
+    };
+    let engine = engine_arc.read().await;
+    let page_count = engine.get_page_count();
+    let pages: Vec<u32> = pages.into_iter().filter(|p| (1..=page_count).contains(p)).collect();
+
+    let options = RenderOptions {
+        quality: RenderQuality::Thumbnail,
+        width: None,
+        height: None,
+        background_color: Some([255, 255, 255, 255]),
+        fit_to_width: false,
+        fit_to_height: false,
+        theme: None,
+        format: None,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
+    };
+    match engine.render_thumbnail_pages(pages, options).await {
+        Ok(thumbnails) => thumbnails
+            .into_iter()
+            .map(|thumb| {
+                let data = base64::engine::general_purpose::STANDARD.encode(&thumb.result.image_data);
+                (thumb.page, format!("data:{};base64,{}", thumb.result.format.mime_type(), data))
+            })
+            .collect(),
+        Err(e) => {
+            eprintln!("[Bookmark] 渲染书签缩略图失败: {}", e);
+            HashMap::new()
+        }
+    }
+}
+
+/// 读取 PDF 大纲并展开为按页码排序的 (页码, 标题) 列表；失败时返回空列表，不影响书签本身
+async fn load_pdf_chapters(file_path: &str, manager: &State<'_, PdfManagerState>) -> Vec<(u32, String)> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        match manager.get_or_create_engine(file_path).await {
+            Ok(engine) => engine,
+            Err(e) => {
+                eprintln!("[Bookmark] 加载 PDF 大纲失败: {}", e);
+                return Vec::new();
+            }
+        }
+    };
+    let engine = engine_arc.read().await;
+    let outline = match engine.get_outline() {
+        Ok(outline) => outline,
+        Err(e) => {
+            eprintln!("[Bookmark] 加载 PDF 大纲失败: {}", e);
+            return Vec::new();
+        }
+    };
+
+    fn flatten(items: &[OutlineItem], out: &mut Vec<(u32, String)>) {
+        for item in items {
+            if item.page_number > 0 {
+                out.push((item.page_number, item.title.clone()));
+            }
+            flatten(&item.children, out);
+        }
+    }
+
+    let mut chapters = Vec::new();
+    flatten(&outline.bookmarks, &mut chapters);
+    // 稳定排序：同页码时保持父节点在前，查找时取到最深一级章节
+    chapters.sort_by_key(|(page, _)| *page);
+    chapters
+}
+
+/// 读取 EPUB 目录，返回按章节序号排序的 (章节序号, 标题)；失败时返回空列表
+async fn load_epub_chapters(file_path: &str) -> Vec<(u32, String)> {
+    let path = file_path.to_string();
+    let sections = tokio::task::spawn_blocking(move || epub::engine::read_toc_sections(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|result| result);
+    match sections {
+        Ok(mut chapters) => {
+            chapters.sort_by_key(|(section, _)| *section);
+            chapters
+        }
+        Err(e) => {
+            eprintln!("[Bookmark] 读取 EPUB 目录失败: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// 读取 TXT 章节划分，返回按起始偏移排序的 (全文字符偏移, 标题)；虚拟书使用合并后的全局偏移
+async fn load_txt_chapters(
+    book_id: i64,
+    file_path: &str,
+    force_encoding: Option<String>,
+    db: &tokio::sync::Mutex<sqlx::SqlitePool>,
+) -> Vec<(u64, String)> {
+    let chapters = if is_virtual_book_path(file_path) {
+        cached_merged_meta(book_id, db)
+            .await
+            .map(|meta| meta.chapters.into_iter().map(|c| (c.char_start, c.title)).collect())
+            .map_err(|e| e.to_string())
+    } else {
+        txt_load_metadata(file_path.to_string(), force_encoding, None)
+            .await
+            .map(|meta| meta.chapters.into_iter().map(|c| (c.char_start, c.title)).collect())
+    };
+    chapters.unwrap_or_else(|e| {
+        eprintln!("[Bookmark] 读取 TXT 章节失败: {}", e);
+        Vec::new()
+    })
+}
+
+/// 取起点不超过 position 的最后一个章节
+fn chapter_at<T: PartialOrd + Copy>(chapters: &[(T, String)], position: T) -> Option<String> {
+    let idx = chapters.partition_point(|(start, _)| *start <= position);
+    idx.checked_sub(1).map(|i| chapters[i].1.clone())
+}
+
+/// TXT 书签的章节内位置
+#[derive(Debug, Clone, Serialize)]
+pub struct TxtBookmarkPosition {
+    pub char_offset: u64,
+    pub chapter_index: u32,
+    /// 章内字符偏移
+    pub chapter_char_offset: u64,
+}
+
+/// 打开 TXT 书签：用当前章节划分把全文字符偏移换算为章节索引和章内偏移；
+/// 虚拟书的偏移为全书全局值，按合并元数据换算为全局章节索引。
+/// 书签没有记录字符偏移（旧书签或非 TXT 书籍）时返回 None，由前端按页码跳转；`force_encoding` 同 `get_bookmarks`
+#[tauri::command]
+pub async fn get_txt_bookmark_position(
+    id: i64,
+    force_encoding: Option<String>,
+    db: DbState<'_>,
+) -> Result<Option<TxtBookmarkPosition>, Error> {
+    locate_txt_bookmark(id, force_encoding, &db).await
+}
+
+async fn locate_txt_bookmark(
+    id: i64,
+    force_encoding: Option<String>,
+    db: &tokio::sync::Mutex<sqlx::SqlitePool>,
+) -> Result<Option<TxtBookmarkPosition>, Error> {
+    let row: Option<(Option<i64>, i64, String)> = {
+        let pool = db.lock().await;
+        sqlx::query_as(
+            "SELECT bookmarks.char_offset, books.id, books.file_path FROM bookmarks JOIN books ON books.id = bookmarks.book_id WHERE bookmarks.id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&*pool)
+        .await?
+    };
+
+    let (char_offset, book_id, file_path) = row.ok_or_else(|| Error::Message(format!("书签不存在: {}", id)))?;
+    let char_offset = match char_offset {
+        Some(offset) if offset >= 0 && BookFormat::from_path(&file_path) == Some(BookFormat::Txt) => offset as u64,
+        _ => return Ok(None),
+    };
+
+    let located = if is_virtual_book_path(&file_path) {
+        cached_merged_meta(book_id, db).await?.locate_char_offset(char_offset)
+    } else {
+        txt_load_metadata(file_path, force_encoding, None).await?.locate_char_offset(char_offset)
+    };
+    Ok(located
+        .map(|(chapter_index, chapter_char_offset)| TxtBookmarkPosition {
+            char_offset,
+            chapter_index,
+            chapter_char_offset,
+        }))
+}
+
 #[tauri::command]
 pub async fn delete_bookmark(id: i64, db: DbState<'_>) -> Result<(), Error> {
     let pool = db.lock().await;
@@ -52,3 +372,329 @@ pub async fn delete_bookmark(id: i64, db: DbState<'_>) -> Result<(), Error> {
         .await?;
     Ok(())
 }
+
+/// 书签交换格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BookmarkFormat {
+    /// JSON：`{"version":1,"bookmarks":[{"page_number","title","note","created_at"}]}`
+    Json,
+    /// 文本大纲：每行 `页码<TAB>标题[<TAB>备注]`，`#` 开头的行为注释
+    Text,
+}
+
+impl BookmarkFormat {
+    fn parse(format: &str) -> Result<Self, Error> {
+        match format.trim().to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "text" | "txt" | "outline" => Ok(Self::Text),
+            other => Err(Error::Message(format!("不支持的书签格式: {}", other))),
+        }
+    }
+}
+
+/// 导出/导入的单条书签
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct BookmarkEntry {
+    page_number: u32,
+    title: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    created_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    char_offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BookmarkDocument {
+    version: u32,
+    #[serde(default)]
+    book_title: Option<String>,
+    bookmarks: Vec<BookmarkEntry>,
+}
+
+/// 备注中的换行和制表符转义，保证一条书签占一行
+fn escape_outline_field(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape_outline_field(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn serialize_bookmarks(
+    format: BookmarkFormat,
+    book_title: Option<String>,
+    entries: Vec<BookmarkEntry>,
+) -> Result<String, Error> {
+    match format {
+        BookmarkFormat::Json => {
+            let document = BookmarkDocument {
+                version: 1,
+                book_title,
+                bookmarks: entries,
+            };
+            serde_json::to_string_pretty(&document)
+                .map_err(|e| Error::Message(format!("序列化书签失败: {}", e)))
+        }
+        BookmarkFormat::Text => {
+            let mut out = String::new();
+            if let Some(title) = book_title {
+                out.push_str(&format!("# {}\n", title));
+            }
+            for entry in entries {
+                out.push_str(&format!("{}\t{}", entry.page_number, escape_outline_field(&entry.title)));
+                if let Some(note) = entry.note.filter(|n| !n.is_empty()) {
+                    out.push('\t');
+                    out.push_str(&escape_outline_field(&note));
+                }
+                out.push('\n');
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// 解析文本大纲的一行；除标准的 `页码<TAB>标题` 外，也接受 `标题 ...... 页码` 形式
+fn parse_outline_line(line: &str) -> Option<BookmarkEntry> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut fields = line.split('\t');
+    if let Some(page) = fields.next().and_then(|f| f.trim().parse::<u32>().ok()) {
+        let title = unescape_outline_field(fields.next().unwrap_or("").trim());
+        let note = fields
+            .next()
+            .map(|n| unescape_outline_field(n.trim()))
+            .filter(|n| !n.is_empty());
+        return Some(BookmarkEntry { page_number: page, title, note, created_at: None, char_offset: None });
+    }
+
+    let (title, page) = line.rsplit_once(|c: char| c.is_whitespace() || c == '.')?;
+    let page = page.parse::<u32>().ok()?;
+    let title = title.trim_end_matches(|c: char| c.is_whitespace() || c == '.' || c == '…');
+    Some(BookmarkEntry {
+        page_number: page,
+        title: unescape_outline_field(title.trim()),
+        note: None,
+        created_at: None,
+        char_offset: None,
+    })
+}
+
+fn parse_bookmarks(format: BookmarkFormat, data: &str) -> Result<Vec<BookmarkEntry>, Error> {
+    let entries = match format {
+        BookmarkFormat::Json => {
+            let value: serde_json::Value = serde_json::from_str(data)
+                .map_err(|e| Error::Message(format!("解析书签 JSON 失败: {}", e)))?;
+            // 兼容直接导出的书签数组
+            let list = match value {
+                serde_json::Value::Array(_) => value,
+                serde_json::Value::Object(mut map) => map
+                    .remove("bookmarks")
+                    .ok_or_else(|| Error::Message("书签 JSON 缺少 bookmarks 字段".to_string()))?,
+                _ => return Err(Error::Message("书签 JSON 格式无效".to_string())),
+            };
+            serde_json::from_value::<Vec<BookmarkEntry>>(list)
+                .map_err(|e| Error::Message(format!("解析书签 JSON 失败: {}", e)))?
+        }
+        BookmarkFormat::Text => data.lines().filter_map(parse_outline_line).collect(),
+    };
+
+    Ok(entries
+        .into_iter()
+        .filter(|entry| entry.page_number > 0)
+        .map(|mut entry| {
+            entry.title = entry.title.trim().to_string();
+            if entry.title.is_empty() {
+                entry.title = format!("第 {} 页", entry.page_number);
+            }
+            entry
+        })
+        .collect())
+}
+
+/// 导出书籍的书签，`format` 为 `json` 或 `text`（文本大纲），返回文件内容由前端保存
+/// pdfium 不提供写入大纲（outline）的接口，PDF 书签只能以这两种格式迁移
+#[tauri::command]
+pub async fn export_bookmarks(book_id: i64, format: String, db: DbState<'_>) -> Result<String, Error> {
+    let format = BookmarkFormat::parse(&format)?;
+    let pool = db.lock().await;
+
+    let bookmarks = sqlx::query_as::<_, Bookmark>(
+        "SELECT * FROM bookmarks WHERE book_id = ? ORDER BY page_number, created_at",
+    )
+    .bind(book_id)
+    .fetch_all(&*pool)
+    .await?;
+    let book_title: Option<String> = sqlx::query_scalar("SELECT title FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_optional(&*pool)
+        .await?;
+
+    let entries = bookmarks
+        .into_iter()
+        .map(|b| BookmarkEntry {
+            page_number: b.page_number,
+            title: b.title,
+            note: b.note,
+            created_at: b.created_at,
+            char_offset: b.char_offset,
+        })
+        .collect();
+    serialize_bookmarks(format, book_title, entries)
+}
+
+/// 导入书签，同页同标题的书签（包括本次导入内部的重复项）跳过；返回实际导入数量
+#[tauri::command]
+pub async fn import_bookmarks(
+    book_id: i64,
+    data: String,
+    format: String,
+    db: DbState<'_>,
+) -> Result<u32, Error> {
+    let format = BookmarkFormat::parse(&format)?;
+    let entries = parse_bookmarks(format, &data)?;
+    let pool = db.lock().await;
+
+    let existing: Vec<(i64, String)> =
+        sqlx::query_as("SELECT page_number, title FROM bookmarks WHERE book_id = ?")
+            .bind(book_id)
+            .fetch_all(&*pool)
+            .await?;
+    let mut seen: HashSet<(u32, String)> = existing
+        .into_iter()
+        .map(|(page, title)| (page as u32, title))
+        .collect();
+
+    let mut tx = pool.begin().await?;
+    let mut imported = 0u32;
+    for entry in entries {
+        if !seen.insert((entry.page_number, entry.title.clone())) {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO bookmarks (book_id, page_number, title, note, created_at, char_offset) VALUES (?, ?, ?, ?, COALESCE(?, strftime('%s', 'now')), ?)",
+        )
+        .bind(book_id)
+        .bind(entry.page_number as i64)
+        .bind(&entry.title)
+        .bind(&entry.note)
+        .bind(entry.created_at)
+        .bind(entry.char_offset)
+        .execute(&mut *tx)
+        .await?;
+        imported += 1;
+    }
+    tx.commit().await?;
+
+    println!("[Bookmark] 导入书签: book_id={}, imported={}", book_id, imported);
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_outline_round_trip() {
+        let entries = vec![
+            BookmarkEntry {
+                page_number: 3,
+                title: "第一章".to_string(),
+                note: Some("多行\n备注".to_string()),
+                created_at: None,
+                char_offset: None,
+            },
+            BookmarkEntry {
+                page_number: 12,
+                title: "附录\tA".to_string(),
+                note: None,
+                created_at: None,
+                char_offset: None,
+            },
+        ];
+        let text = serialize_bookmarks(BookmarkFormat::Text, Some("书名".to_string()), entries.clone()).unwrap();
+        assert_eq!(parse_bookmarks(BookmarkFormat::Text, &text).unwrap(), entries);
+
+        let loose = parse_bookmarks(BookmarkFormat::Text, "序言 ........ 1\n第二章 25\n0\t无效页").unwrap();
+        let pages: Vec<(u32, &str)> = loose.iter().map(|e| (e.page_number, e.title.as_str())).collect();
+        assert_eq!(pages, vec![(1, "序言"), (25, "第二章")]);
+    }
+
+    #[tokio::test]
+    async fn test_virtual_book_bookmark_position() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let first = dir.join("上.txt");
+        let second = dir.join("下.txt");
+        std::fs::write(&first, "第一章 风起\n正文一。\n第二章 云涌\n正文二。\n").unwrap();
+        std::fs::write(&second, "第三章 落幕\n正文三。\n").unwrap();
+
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        for sql in [
+            "CREATE TABLE books (id INTEGER PRIMARY KEY, title TEXT NOT NULL, file_path TEXT NOT NULL)",
+            "CREATE TABLE bookmarks (id INTEGER PRIMARY KEY, book_id INTEGER NOT NULL, char_offset INTEGER)",
+            crate::commands::virtual_book::CREATE_VIRTUAL_BOOK_PARTS_TABLE,
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+        sqlx::query("INSERT INTO books (id, title, file_path) VALUES (386, '合集', 'virtual://386/合集.txt')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        for (position, path) in [&first, &second].into_iter().enumerate() {
+            sqlx::query("INSERT INTO virtual_book_parts (book_id, position, file_path) VALUES (386, ?, ?)")
+                .bind(position as i64)
+                .bind(path.to_string_lossy().to_string())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let db = tokio::sync::Mutex::new(pool);
+        let merged = cached_merged_meta(386, &db).await.unwrap();
+
+        // 书签记录的是全书全局偏移，落在第二个成员文件内
+        let offset = merged.parts[1].char_start + 2;
+        sqlx::query("INSERT INTO bookmarks (id, book_id, char_offset) VALUES (1, 386, ?)")
+            .bind(offset as i64)
+            .execute(&*db.lock().await)
+            .await
+            .unwrap();
+        let position = locate_txt_bookmark(1, None, &db).await.unwrap().unwrap();
+        assert_eq!(position.char_offset, offset);
+        assert!(position.chapter_index >= merged.parts[1].chapter_start);
+        assert_eq!(
+            Some((position.chapter_index, position.chapter_char_offset)),
+            merged.locate_char_offset(offset)
+        );
+    }
+
+    #[test]
+    fn test_json_accepts_plain_array() {
+        let data = r#"[{"page_number": 5, "title": " 标记 "}, {"page_number": 8, "title": ""}]"#;
+        let entries = parse_bookmarks(BookmarkFormat::Json, data).unwrap();
+        assert_eq!(entries[0].title, "标记");
+        assert_eq!(entries[1].title, "第 8 页");
+    }
+}
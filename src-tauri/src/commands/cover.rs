@@ -3,7 +3,79 @@
 use crate::cover;
 use crate::models::Book;
 use super::book::{DbState, Error};
-use tauri::AppHandle;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sqlx::SqlitePool;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// 分组拼贴封面后台重建完成事件
+const GROUP_COVER_UPDATED_EVENT: &str = "goread:group-cover:updated";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupCoverUpdatedEvent {
+    pub group_id: i64,
+    /// 分组为空时为 None
+    pub cover_image: Option<String>,
+}
+
+/// 正在后台重建拼贴封面的分组，避免重复请求分组列表时并发重建同一分组
+static REBUILDING_GROUP_COVERS: Lazy<Mutex<HashSet<i64>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 书籍记录删除后清理其封面文件；仍有其他书籍引用同一文件时保留
+pub(crate) async fn remove_book_cover_if_unreferenced(
+    app_handle: &AppHandle,
+    pool: &SqlitePool,
+    cover_image: &str,
+) -> Result<(), Error> {
+    if cover_image.is_empty() || !cover::is_file_path(cover_image) {
+        return Ok(());
+    }
+
+    let references: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM books WHERE cover_image = ?")
+        .bind(cover_image)
+        .fetch_one(pool)
+        .await?;
+    if references > 0 {
+        println!("[cover] Cover still referenced by {} book(s), keep: {}", references, cover_image);
+        return Ok(());
+    }
+
+    match cover::delete_cover_file(app_handle, cover_image).await {
+        Ok(_) => println!("[cover] Successfully deleted cover file: {}", cover_image),
+        Err(e) => eprintln!("[cover] Failed to delete cover file {}: {}", cover_image, e),
+    }
+    Ok(())
+}
+
+/// 清理没有任何书籍引用的封面文件，返回删除数量
+#[tauri::command]
+pub async fn cleanup_orphan_covers(app_handle: AppHandle, db: DbState<'_>) -> Result<u32, Error> {
+    let referenced: Vec<String> = {
+        let pool = db.lock().await;
+        sqlx::query_scalar("SELECT cover_image FROM books WHERE cover_image IS NOT NULL AND cover_image != ''")
+            .fetch_all(&*pool)
+            .await?
+    };
+
+    let files = cover::list_book_cover_files(&app_handle).await;
+    let orphans = cover::find_orphan_covers(&files, referenced.iter().map(String::as_str));
+
+    let mut removed = 0u32;
+    for relative_path in &orphans {
+        match cover::delete_cover_file(&app_handle, relative_path).await {
+            Ok(_) => removed += 1,
+            Err(e) => eprintln!("[cleanup_orphan_covers] Failed to delete {}: {}", relative_path, e),
+        }
+    }
+    println!(
+        "[cleanup_orphan_covers] Scanned {} cover files, removed {} orphans",
+        files.len(),
+        removed
+    );
+    Ok(removed)
+}
 
 /// 获取封面文件的可访问 URL
 /// 如果封面是路径格式，返回转换后的完整路径
@@ -374,3 +446,193 @@ pub async fn get_mobi_books_without_cover(
     
     Ok(result)
 }
+
+/// 为文本类书籍（TXT/Markdown/HTML）生成书名封面，返回相对封面目录的路径
+#[tauri::command]
+pub async fn generate_text_cover(
+    app_handle: AppHandle,
+    file_path: String,
+    title: String,
+    author: Option<String>,
+) -> Result<String, Error> {
+    let title = if title.trim().is_empty() {
+        std::path::Path::new(&file_path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default()
+    } else {
+        title
+    };
+    cover::generate_text_cover(&app_handle, &file_path, &title, author.as_deref())
+        .await
+        .map_err(Error::Message)
+}
+
+/// 将封面按目标比例（宽/高，默认 2:3）中心裁剪并缩放到目标宽度（默认 300），生成 `xxx_thumb.jpg`
+/// 返回缩略图相对封面目录的路径；书架列表使用缩略图，详情页仍使用原图
+#[tauri::command]
+pub async fn make_cover_thumbnail(
+    app_handle: AppHandle,
+    relative_path: String,
+    target_ratio: Option<f32>,
+    target_width: Option<u32>,
+) -> Result<String, Error> {
+    if !cover::is_file_path(&relative_path) {
+        return Err(Error::Message("封面不是文件路径，无法生成缩略图".to_string()));
+    }
+    cover::make_cover_thumbnail(
+        &app_handle,
+        &relative_path,
+        target_ratio.unwrap_or(cover::DEFAULT_THUMBNAIL_RATIO),
+        target_width.unwrap_or(cover::DEFAULT_THUMBNAIL_WIDTH),
+    )
+    .await
+    .map_err(Error::Message)
+}
+
+/// 生成分组拼贴封面：取组内最近阅读的 1-4 本书封面合成 2x2 拼贴
+/// 返回相对封面目录的路径；组内书籍或封面未变化时直接复用已生成的文件；分组为空时返回 None
+#[tauri::command]
+pub async fn generate_group_cover(
+    app_handle: AppHandle,
+    group_id: i64,
+    db: DbState<'_>,
+) -> Result<Option<String>, Error> {
+    // 拼贴合成较慢，复制连接池后释放锁
+    let pool = db.lock().await.clone();
+    build_group_cover(&app_handle, &pool, group_id).await
+}
+
+/// 拼贴使用的书籍（组内最近阅读的前几本，按拼贴顺序）及其封面字段
+async fn group_collage_books(pool: &SqlitePool, group_id: i64) -> Result<Vec<(i64, Option<String>)>, Error> {
+    let books = sqlx::query_as(
+        "SELECT id, cover_image FROM books WHERE group_id = ? ORDER BY last_read_time DESC NULLS LAST, created_at DESC LIMIT ?",
+    )
+    .bind(group_id)
+    .bind(cover::GROUP_COLLAGE_MAX_BOOKS as i64)
+    .fetch_all(pool)
+    .await?;
+    Ok(books)
+}
+
+/// 拼贴封面路径：签名由书籍 id 和封面字段组成，任一变化都会得到新路径；分组为空时为 None
+fn group_collage_path(group_id: i64, books: &[(i64, Option<String>)]) -> Option<String> {
+    if books.is_empty() {
+        return None;
+    }
+    let signature = books
+        .iter()
+        .map(|(id, cover_image)| format!("{}:{}", id, cover_image.as_deref().unwrap_or("")))
+        .collect::<Vec<_>>()
+        .join("|");
+    Some(cover::group_cover_relative_path(group_id, &signature))
+}
+
+/// 生成或复用分组拼贴封面，并将结果记录到分组上、清除需重建标记
+pub(crate) async fn build_group_cover(
+    app_handle: &AppHandle,
+    pool: &SqlitePool,
+    group_id: i64,
+) -> Result<Option<String>, Error> {
+    let books = group_collage_books(pool, group_id).await?;
+    let Some(relative_path) = group_collage_path(group_id, &books) else {
+        cover::remove_stale_group_covers(app_handle, group_id, None).await;
+        mark_group_cover_built(pool, group_id, None).await?;
+        return Ok(None);
+    };
+
+    if cover::cover_file_exists(app_handle, &relative_path).await {
+        mark_group_cover_built(pool, group_id, Some(&relative_path)).await?;
+        return Ok(Some(relative_path));
+    }
+
+    let mut covers = Vec::with_capacity(books.len());
+    for (_, cover_image) in &books {
+        let bytes = match cover_image.as_deref() {
+            Some(data) => cover::load_cover_bytes(app_handle, data).await,
+            None => None,
+        };
+        covers.push(bytes);
+    }
+
+    let collage = tokio::task::spawn_blocking(move || cover::compose_group_collage(&covers))
+        .await
+        .map_err(|e| Error::Message(format!("生成分组封面失败: {}", e)))?
+        .map_err(Error::Message)?;
+
+    let full_path = cover::get_cover_full_path(app_handle, &relative_path);
+    if let Some(parent) = full_path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| Error::Message(format!("创建封面目录失败: {}", e)))?;
+    }
+    tokio::fs::write(&full_path, &collage)
+        .await
+        .map_err(|e| Error::Message(format!("写入分组封面失败: {}", e)))?;
+
+    cover::remove_stale_group_covers(app_handle, group_id, Some(&relative_path)).await;
+    mark_group_cover_built(pool, group_id, Some(&relative_path)).await?;
+    println!("[generate_group_cover] Group {} collage saved: {}", group_id, relative_path);
+
+    Ok(Some(relative_path))
+}
+
+async fn mark_group_cover_built(pool: &SqlitePool, group_id: i64, relative_path: Option<&str>) -> Result<(), Error> {
+    sqlx::query("UPDATE groups SET cover_image = ?, cover_stale = 0 WHERE id = ?")
+        .bind(relative_path)
+        .bind(group_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// 组内拼贴使用的书籍或其顺序变化时打上需重建标记并清空记录的封面，返回是否发生变化
+/// 只比对拼贴路径，不做渲染；重建时会清理旧文件
+pub(crate) async fn mark_group_cover_stale(pool: &SqlitePool, group_id: i64) -> Result<bool, Error> {
+    let books = group_collage_books(pool, group_id).await?;
+    let result = sqlx::query("UPDATE groups SET cover_image = NULL, cover_stale = 1 WHERE id = ? AND cover_image IS NOT ?")
+        .bind(group_id)
+        .bind(group_collage_path(group_id, &books))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 组内书籍增删后使分组拼贴封面失效：拼贴变化时删除已生成的文件并打上需重建标记，
+/// 由下次 `get_all_groups` 在后台重建，写操作本身不做渲染
+pub(crate) async fn invalidate_group_cover(
+    app_handle: &AppHandle,
+    pool: &SqlitePool,
+    group_id: i64,
+) -> Result<(), Error> {
+    if mark_group_cover_stale(pool, group_id).await? {
+        cover::remove_stale_group_covers(app_handle, group_id, None).await;
+    }
+    Ok(())
+}
+
+/// 在后台重建分组拼贴封面，每个分组完成后推送 `goread:group-cover:updated` 事件；已在重建中的分组跳过
+pub(crate) fn spawn_group_cover_rebuild(app_handle: AppHandle, pool: SqlitePool, group_ids: Vec<i64>) {
+    let group_ids: Vec<i64> = match REBUILDING_GROUP_COVERS.lock() {
+        Ok(mut rebuilding) => group_ids.into_iter().filter(|id| rebuilding.insert(*id)).collect(),
+        Err(_) => return,
+    };
+    if group_ids.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        for group_id in group_ids {
+            match build_group_cover(&app_handle, &pool, group_id).await {
+                Ok(cover_image) => {
+                    let payload = GroupCoverUpdatedEvent { group_id, cover_image };
+                    let _ = app_handle.emit(GROUP_COVER_UPDATED_EVENT, payload);
+                }
+                Err(e) => eprintln!("[group_cover] Failed to rebuild cover for group {}: {}", group_id, e),
+            }
+            if let Ok(mut rebuilding) = REBUILDING_GROUP_COVERS.lock() {
+                rebuilding.remove(&group_id);
+            }
+        }
+    });
+}
@@ -3,6 +3,9 @@ use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tauri::{Emitter, Manager, State};
+use crate::commands::book::DbState;
+use crate::commands::scan_cache::{CachedDirEntries, CachedScanFile, ScanCacheSession};
+use crate::commands::scan_exclude::ScanExcludes;
 use crate::formats;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -606,6 +609,8 @@ async fn scan_supported_files_recursive(
     cancel_flag: &Arc<AtomicBool>,
     seen_paths: &mut std::collections::HashSet<String>,
     formats: &Option<Vec<formats::BookFormat>>,
+    cache: &mut ScanCacheSession,
+    excludes: &ScanExcludes,
 ) -> std::io::Result<()> {
     use std::collections::VecDeque;
 
@@ -613,17 +618,90 @@ async fn scan_supported_files_recursive(
     dirs_to_scan.push_back(dir.to_path_buf());
     let mut last_emit_time = std::time::Instant::now();
 
+    let emit_progress = |app: &tauri::AppHandle, scanned: u32, found: u32, cache: &ScanCacheSession| {
+        let _ = app.emit(
+            "goread:scan:progress",
+            serde_json::json!({
+                "scanned": scanned,
+                "found": found,
+                "cached_dirs": cache.cached_dirs,
+                "scanned_dirs": cache.scanned_dirs
+            }),
+        );
+    };
+
     while let Some(current_dir) = dirs_to_scan.pop_front() {
         if cancel_flag.load(Ordering::Relaxed) { break; }
-        if !current_dir.is_dir() { continue; }
+        let dir_meta = match tokio::fs::metadata(&current_dir).await {
+            Ok(m) if m.is_dir() => m,
+            _ => continue,
+        };
+        let dir_key = current_dir.to_string_lossy().to_string();
+        let dir_mtime = dir_meta.modified().ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+
+        // 目录 mtime 未变化：直接使用缓存的直接子项
+        if let Some(mut entries) = cache.lookup(&dir_key, dir_mtime).cloned() {
+            cache.cached_dirs += 1;
+            *scanned_count += (entries.files.len() + entries.dirs.len()) as u32;
+
+            // 原地改写文件不会更新目录 mtime，逐个刷新缓存文件的大小和修改时间
+            let mut changed = false;
+            for file in entries.files.iter_mut() {
+                let Ok(metadata) = tokio::fs::metadata(&file.path).await else { continue };
+                let size = metadata.len();
+                let mtime = metadata.modified().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64 * 1000);
+                if file.size != size || file.mtime != mtime {
+                    file.size = size;
+                    file.mtime = mtime;
+                    changed = true;
+                }
+            }
+            if changed {
+                cache.record(dir_key.clone(), dir_mtime, entries.clone());
+            }
+
+            for file in entries.files {
+                if !is_file_in_formats(Path::new(&file.path), formats) { continue; }
+                if seen_paths.insert(file.path.clone()) {
+                    results.push(FileEntry {
+                        name: file.name,
+                        path: file.path,
+                        entry_type: "file".to_string(),
+                        size: Some(file.size),
+                        mtime: file.mtime,
+                        children_count: None,
+                    });
+                }
+            }
+            for sub in entries.dirs {
+                if excludes.is_excluded(&sub) { continue; }
+                dirs_to_scan.push_back(PathBuf::from(sub));
+            }
+
+            if let Some(app) = app_handle {
+                if last_emit_time.elapsed().as_millis() > 100 {
+                    emit_progress(app, *scanned_count, results.len() as u32, cache);
+                    last_emit_time = std::time::Instant::now();
+                }
+            }
+            continue;
+        }
 
         let mut entries = match tokio::fs::read_dir(&current_dir).await {
             Ok(entries) => entries,
             Err(_) => continue,
         };
+        cache.scanned_dirs += 1;
+        let mut dir_entries = CachedDirEntries::default();
+        let mut interrupted = false;
 
         while let Some(entry) = entries.next_entry().await? {
-            if cancel_flag.load(Ordering::Relaxed) { break; }
+            if cancel_flag.load(Ordering::Relaxed) { interrupted = true; break; }
             let path = entry.path();
 
             *scanned_count += 1;
@@ -631,12 +709,7 @@ async fn scan_supported_files_recursive(
             if let Some(app) = app_handle {
                 let should_emit = last_emit_time.elapsed().as_millis() > 100;
                 if should_emit {
-                    let count = results.len() as u32;
-                    let payload = serde_json::json!({
-                        "scanned": *scanned_count,
-                        "found": count
-                    });
-                    let _ = app.emit("goread:scan:progress", payload);
+                    emit_progress(app, *scanned_count, results.len() as u32, cache);
                     last_emit_time = std::time::Instant::now();
                 }
             }
@@ -644,16 +717,35 @@ async fn scan_supported_files_recursive(
             let metadata = match entry.metadata().await { Ok(m) => m, Err(_) => continue };
 
             if metadata.is_dir() {
-                dirs_to_scan.push_back(path);
+                // 缓存记录完整的子目录列表，排除规则只在入队时应用，修改规则后无需重建缓存
+                let dir_path = path.to_string_lossy().to_string();
+                let excluded = excludes.is_excluded(&dir_path);
+                dir_entries.dirs.push(dir_path);
+                if !excluded {
+                    dirs_to_scan.push_back(path);
+                }
             } else if metadata.is_file() {
-                if is_file_in_formats(&path, formats) {
-                    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
-                    let path_str = normalize_android_path(&path);
-                    let size = metadata.len();
-                    let mtime = metadata.modified().ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs() as i64 * 1000);
+                let is_book = path.extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(|ext| formats::BookFormat::from_extension(&ext.to_lowercase()))
+                    .is_some();
+                if !is_book { continue; }
+
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("").to_string();
+                let path_str = normalize_android_path(&path);
+                let size = metadata.len();
+                let mtime = metadata.modified().ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs() as i64 * 1000);
+
+                dir_entries.files.push(CachedScanFile {
+                    name: name.clone(),
+                    path: path_str.clone(),
+                    size,
+                    mtime,
+                });
 
+                if is_file_in_formats(&path, formats) {
                     if seen_paths.insert(path_str.clone()) {
                         results.push(FileEntry {
                             name,
@@ -666,18 +758,16 @@ async fn scan_supported_files_recursive(
                     }
 
                     if let Some(app) = app_handle {
-                        let count = results.len() as u32;
-                        let _ = app.emit(
-                            "goread:scan:progress",
-                            serde_json::json!({
-                                "scanned": *scanned_count as u32,
-                                "found": count
-                            }),
-                        );
+                        emit_progress(app, *scanned_count, results.len() as u32, cache);
                     }
                 }
             }
         }
+
+        // 被取消时目录内容不完整，不写入缓存
+        if !interrupted {
+            cache.record(dir_key, dir_mtime, dir_entries);
+        }
     }
 
     Ok(())
@@ -687,8 +777,10 @@ async fn scan_supported_files_recursive(
 pub async fn scan_book_files(
     root_path: Option<String>,
     formats: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>,
     window: tauri::Window,
     cancel_flag: State<'_, Arc<AtomicBool>>,
+    db: DbState<'_>,
 ) -> Result<Vec<FileEntry>, String> {
     let app_handle = window.app_handle();
     let mut roots = Vec::new();
@@ -749,19 +841,48 @@ pub async fn scan_book_files(
             .collect()
     });
 
+    // 排除规则：未传入时使用内置默认规则，传入空列表表示不排除
+    let excludes = match exclude_patterns {
+        Some(patterns) => ScanExcludes::new(&patterns),
+        None => ScanExcludes::defaults(),
+    };
+
+    // 复制连接池句柄后立即释放锁，避免长时间扫描阻塞其他数据库操作
+    let pool = db.lock().await.clone();
+    let mut cache = ScanCacheSession::load(&pool).await;
+    let mut scanned_roots = Vec::new();
+
     for root in roots {
         if !root.exists() { continue; }
-        let _ = scan_supported_files_recursive(&root, &mut results, &mut scanned_count, Some(&app_handle), &cancel_flag, &mut seen_paths, &format_filters).await;
+        scanned_roots.push(root.to_string_lossy().to_string());
+        let _ = scan_supported_files_recursive(&root, &mut results, &mut scanned_count, Some(&app_handle), &cancel_flag, &mut seen_paths, &format_filters, &mut cache, &excludes).await;
     }
 
     let _ = app_handle.emit(
         "goread:scan:progress",
         serde_json::json!({
             "scanned": scanned_count as u32,
-            "found": results.len() as u32
+            "found": results.len() as u32,
+            "cached_dirs": cache.cached_dirs,
+            "scanned_dirs": cache.scanned_dirs
         }),
     );
 
+    println!(
+        "[Scan] 扫描完成: found={}, cached_dirs={}, scanned_dirs={}",
+        results.len(),
+        cache.cached_dirs,
+        cache.scanned_dirs
+    );
+
+    // 取消时访问记录不完整，仅写回已扫描目录，不清理未访问目录
+    if cancel_flag.load(Ordering::Relaxed) {
+        scanned_roots.clear();
+    }
+    if let Err(e) = cache.persist(&pool, &scanned_roots).await {
+        eprintln!("[Scan] 写入扫描缓存失败: {}", e);
+    }
+
     Ok(results)
 }
 
@@ -1,9 +1,11 @@
 use crate::models::{Book, Group};
 use crate::commands::book::{DbState, Error};
+use crate::commands::cover as cover_commands;
 use crate::cover;
 use sqlx::SqlitePool;
 use tauri::AppHandle;
 use futures::future::join_all;
+use std::collections::HashSet;
 
 #[tauri::command]
 pub async fn add_group(name: String, db: DbState<'_>) -> Result<Group, Error> {
@@ -47,24 +49,64 @@ pub async fn add_group(name: String, db: DbState<'_>) -> Result<Group, Error> {
     Ok(group)
 }
 
+/// 获取所有分组；被标记为需重建的拼贴封面在后台重建，完成后通过 `goread:group-cover:updated` 事件推送
 #[tauri::command]
-pub async fn get_all_groups(db: DbState<'_>) -> Result<Vec<Group>, Error> {
-    let pool = db.lock().await;
+pub async fn get_all_groups(app_handle: AppHandle, db: DbState<'_>) -> Result<Vec<Group>, Error> {
+    let pool = db.lock().await.clone();
 
     let groups = sqlx::query_as::<_, Group>(
         "SELECT * FROM groups WHERE book_count > 0 ORDER BY sort_order DESC, created_at DESC",
     )
-    .fetch_all(&*pool)
+    .fetch_all(&pool)
     .await?;
 
+    let stale: Vec<i64> = groups.iter().filter(|g| g.cover_stale).filter_map(|g| g.id).collect();
+    if !stale.is_empty() {
+        cover_commands::spawn_group_cover_rebuild(app_handle, pool, stale);
+    }
+
     Ok(groups)
 }
 
+/// 校验分组颜色：支持 `#RGB`/`#RRGGBB`，统一转为小写；空字符串表示清除
+fn normalize_group_color(color: &str) -> Result<Option<String>, Error> {
+    let color = color.trim();
+    if color.is_empty() {
+        return Ok(None);
+    }
+    let hex = color.strip_prefix('#').unwrap_or(color);
+    if !matches!(hex.len(), 3 | 6) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::from(format!("分组颜色格式无效: {}", color)));
+    }
+    Ok(Some(format!("#{}", hex.to_ascii_lowercase())))
+}
+
+/// 校验分组图标：图标名或 emoji，最长 32 个字符；空字符串表示清除
+fn normalize_group_icon(icon: &str) -> Result<Option<String>, Error> {
+    let icon = icon.trim();
+    if icon.is_empty() {
+        return Ok(None);
+    }
+    if icon.chars().count() > 32 {
+        return Err(Error::from("分组图标过长".to_string()));
+    }
+    Ok(Some(icon.to_string()))
+}
+
+/// 更新分组名称，以及可选的颜色和图标（不传保持不变，传空字符串清除）
 #[tauri::command]
-pub async fn update_group(group_id: i64, name: String, db: DbState<'_>) -> Result<(), Error> {
+pub async fn update_group(
+    group_id: i64,
+    name: String,
+    color: Option<String>,
+    icon: Option<String>,
+    db: DbState<'_>,
+) -> Result<(), Error> {
     if name.trim().is_empty() {
         return Err(Error::from("分组名称不能为空".to_string()));
     }
+    let color = color.as_deref().map(normalize_group_color).transpose()?;
+    let icon = icon.as_deref().map(normalize_group_icon).transpose()?;
 
     let pool = db.lock().await;
 
@@ -88,6 +130,21 @@ pub async fn update_group(group_id: i64, name: String, db: DbState<'_>) -> Resul
         .execute(&*pool)
         .await?;
 
+    if let Some(color) = color {
+        sqlx::query("UPDATE groups SET color = ? WHERE id = ?")
+            .bind(color)
+            .bind(group_id)
+            .execute(&*pool)
+            .await?;
+    }
+    if let Some(icon) = icon {
+        sqlx::query("UPDATE groups SET icon = ? WHERE id = ?")
+            .bind(icon)
+            .bind(group_id)
+            .execute(&*pool)
+            .await?;
+    }
+
     Ok(())
 }
 
@@ -116,28 +173,7 @@ pub async fn delete_group(app_handle: AppHandle, group_id: i64, delete_local: bo
         }
     }
 
-    // 批量并发删除封面图片文件
-    let cover_delete_futures: Vec<_> = books
-        .iter()
-        .filter_map(|(_, cover_image)| {
-            cover_image.as_ref().and_then(|cover_path| {
-                // 只删除文件路径格式的封面（非 base64/data URL）
-                if !cover_path.is_empty() && !cover_path.starts_with("data:") {
-                    Some((app_handle.clone(), cover_path.clone()))
-                } else {
-                    None
-                }
-            })
-        })
-        .map(|(handle, cover_path)| async move {
-            match cover::delete_cover_file(&handle, &cover_path).await {
-                Ok(_) => println!("[delete_group] Successfully deleted cover: {}", cover_path),
-                Err(e) => eprintln!("[delete_group] Failed to delete cover {}: {}", cover_path, e),
-            }
-        })
-        .collect();
-    
-    join_all(cover_delete_futures).await;
+    cover::remove_stale_group_covers(&app_handle, group_id, None).await;
 
     let mut tx = (&*pool).begin().await?;
     sqlx::query("DELETE FROM books WHERE group_id = ?")
@@ -149,6 +185,20 @@ pub async fn delete_group(app_handle: AppHandle, group_id: i64, delete_local: bo
         .execute(&mut *tx)
         .await?;
     tx.commit().await?;
+
+    // 记录删除后批量并发清理封面文件，仍被其他书籍引用的封面保留
+    let cover_delete_futures: Vec<_> = books
+        .iter()
+        .filter_map(|(_, cover_image)| cover_image.as_deref())
+        .map(|cover_path| {
+            super::cover::remove_book_cover_if_unreferenced(&app_handle, &pool, cover_path)
+        })
+        .collect();
+    for result in join_all(cover_delete_futures).await {
+        if let Err(e) = result {
+            eprintln!("[delete_group] Failed to clean up cover: {}", e);
+        }
+    }
     Ok(())
 }
 
@@ -162,11 +212,12 @@ pub async fn get_books_by_group(group_id: i64, db: DbState<'_>) -> Result<Vec<Bo
     .bind(group_id)
     .fetch_all(&*pool).await?;
 
-    Ok(books)
+    Ok(books.into_iter().map(Book::with_progress_percent).collect())
 }
 
 #[tauri::command]
 pub async fn move_book_to_group(
+    app_handle: AppHandle,
     book_id: i64,
     group_id: Option<i64>,
     db: DbState<'_>,
@@ -217,19 +268,54 @@ pub async fn move_book_to_group(
         .bind(ng)
         .execute(&*pool).await?;
     }
+
+    // 移出和移入的分组拼贴可能变化，变化时封面失效
+    for gid in prev_group.into_iter().chain(group_id).filter(|_| prev_group != group_id) {
+        cover_commands::invalidate_group_cover(&app_handle, &pool, gid).await?;
+    }
     Ok(())
 }
 
+/// 合并拖拽顺序与分组现有书籍：`current_ids` 为组内全部书籍（按当前顺序），
+/// 未出现在 `ordered_ids` 中的书按原顺序排到末尾，重复 id 只保留第一次；包含外组 id 时返回该 id
+fn merge_group_order(current_ids: &[i64], ordered_ids: &[i64]) -> Result<Vec<i64>, i64> {
+    let members: HashSet<i64> = current_ids.iter().copied().collect();
+    let mut seen = HashSet::with_capacity(current_ids.len());
+    let mut merged = Vec::with_capacity(current_ids.len());
+    for &id in ordered_ids {
+        if !members.contains(&id) {
+            return Err(id);
+        }
+        if seen.insert(id) {
+            merged.push(id);
+        }
+    }
+    merged.extend(current_ids.iter().copied().filter(|id| !seen.contains(id)));
+    Ok(merged)
+}
+
+/// 重排分组内书籍：校验 id 均属于该分组，漏传的组内书籍排到末尾，最终 position 连续无重复
 #[tauri::command]
 pub async fn reorder_group_books(
+    app_handle: AppHandle,
     group_id: i64,
     ordered_ids: Vec<i64>,
     db: DbState<'_>,
 ) -> Result<(), Error> {
     let pool = db.lock().await;
     let mut tx = (&*pool).begin().await?;
-    let total = ordered_ids.len() as i64;
-    for (idx, bid) in ordered_ids.iter().enumerate() {
+
+    let current_ids: Vec<i64> = sqlx::query_scalar(
+        "SELECT id FROM books WHERE group_id = ? ORDER BY position_in_group IS NULL, position_in_group DESC, created_at DESC"
+    )
+    .bind(group_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let merged = merge_group_order(&current_ids, &ordered_ids)
+        .map_err(|id| Error::from(format!("书籍 {} 不属于分组 {}", id, group_id)))?;
+
+    let total = merged.len() as i64;
+    for (idx, bid) in merged.iter().enumerate() {
         let pos_desc = total - (idx as i64); // 让列表前面的书具有更大的 position 值
         sqlx::query("UPDATE books SET position_in_group = ? WHERE id = ? AND group_id = ?")
             .bind(pos_desc)
@@ -239,6 +325,8 @@ pub async fn reorder_group_books(
             .await?;
     }
     tx.commit().await?;
+
+    cover_commands::invalidate_group_cover(&app_handle, &pool, group_id).await?;
     Ok(())
 }
 
@@ -273,3 +361,18 @@ pub async fn reorder_groups(
     tx.commit().await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_group_order() {
+        let current = [5, 3, 8, 1];
+        assert_eq!(merge_group_order(&current, &[8, 5, 3, 1]), Ok(vec![8, 5, 3, 1]));
+        // 漏传的书按原顺序排到末尾，重复 id 只保留一次
+        assert_eq!(merge_group_order(&current, &[1, 8, 1]), Ok(vec![1, 8, 5, 3]));
+        assert_eq!(merge_group_order(&current, &[]), Ok(vec![5, 3, 8, 1]));
+        assert_eq!(merge_group_order(&current, &[3, 42]), Err(42));
+    }
+}
@@ -1,16 +1,49 @@
 use crate::commands::book::DbState;
+use crate::commands::log::write_log;
 use crate::cover;
+use crate::formats::common::{clean_title, normalize_language_tag};
+use crate::formats::html::HtmlEngine;
+use crate::formats::markdown::MarkdownEngine;
+use crate::formats::txt::TxtEngine;
+use crate::formats::{epub, mobi, BookFormat};
+use crate::pdf::doc_cache::with_cached_document;
+use crate::pdf::engine::render_concurrency;
 use crate::models::Book;
+use once_cell::sync::Lazy;
+use base64::Engine as _;
+use pdfium_render::prelude::{
+    PdfDocument, PdfDocumentMetadataTagType, PdfPageRenderRotation, PdfRenderConfig,
+};
+use regex::bytes::Regex as BytesRegex;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Emitter, Manager};
 
+/// 批量读取的文件总大小超过该值时写入告警日志（移动端内存不足闪退的前兆）
+const LARGE_BATCH_READ_BYTES: usize = 256 * 1024 * 1024;
+
+/// 导入时前端传入的封面数据超过该值时写入告警日志
+const LARGE_COVER_PAYLOAD_BYTES: usize = 8 * 1024 * 1024;
+
+/// 数据库初始化时创建的默认分组
+const DEFAULT_GROUP_ID: i64 = 1;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PdfMetadata {
     pub path: String,
     pub title: String,
     pub total_pages: u32,
     pub cover_base64: Option<String>,
+    /// 所属系列名称
+    #[serde(default)]
+    pub series: Option<String>,
+    /// 在系列中的序号
+    #[serde(default)]
+    pub series_index: Option<f32>,
+    /// 书籍语言，未提供时由后端从元数据中提取
+    #[serde(default)]
+    pub language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,50 +78,216 @@ pub async fn batch_read_files(paths: Vec<String>) -> Result<Vec<(String, Vec<u8>
     while let Some(result) = tasks.join_next().await {
         match result {
             Ok(Ok(data)) => results.push(data),
-            Ok(Err(e)) => return Err(e),
+            Ok(Err(e)) => {
+                write_log("error", "Import", &e);
+                return Err(e);
+            }
             Err(e) => return Err(format!("任务执行失败: {}", e)),
         }
     }
+
+    let total_bytes: usize = results.iter().map(|(_, data)| data.len()).sum();
+    if total_bytes > LARGE_BATCH_READ_BYTES {
+        write_log(
+            "warn",
+            "Import",
+            &format!("批量读取文件占用内存较大: files={}, bytes={}", results.len(), total_bytes),
+        );
+    }
     
     Ok(results)
 }
 
+/// 按名称查找分组，不存在时创建
+pub(crate) async fn find_or_create_group(db: &DbState<'_>, name: &str) -> Result<i64, String> {
+    let pool = db.lock().await;
+    let existing: Option<i64> =
+        sqlx::query_scalar("SELECT id FROM groups WHERE name = ? ORDER BY book_count DESC, id LIMIT 1")
+            .bind(name)
+            .fetch_optional(&*pool)
+            .await
+            .map_err(|e| format!("查询分组失败: {}", e))?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let max_order: Option<i64> = sqlx::query_scalar("SELECT MAX(sort_order) FROM groups WHERE book_count > 0")
+        .fetch_one(&*pool)
+        .await
+        .map_err(|e| format!("查询分组排序失败: {}", e))?;
+    let result = sqlx::query("INSERT INTO groups (name, sort_order) VALUES (?, ?)")
+        .bind(name)
+        .bind(max_order.unwrap_or(0) + 1)
+        .execute(&*pool)
+        .await
+        .map_err(|e| format!("创建分组失败: {}", e))?;
+    Ok(result.last_insert_rowid())
+}
+
+/// 按文件夹结构规划分组：以每个文件的直接父目录名作为分组名，
+/// 父目录就是导入根目录（即根目录下的散文件）时返回 None；
+/// `import_root` 为用户选择的文件夹，未提供时取所有文件父目录的公共前缀；
+/// 同名但路径不同的目录由层级最浅的沿用目录名，其余追加上级目录名区分，仍重名时再追加序号
+fn plan_folder_groups(paths: &[String], import_root: Option<&Path>) -> Vec<Option<String>> {
+    let parents: Vec<Option<&Path>> = paths.iter().map(|p| Path::new(p).parent()).collect();
+    let root: PathBuf = match import_root {
+        Some(root) => root.components().collect(),
+        None => parents
+            .iter()
+            .flatten()
+            .map(|parent| parent.components().collect::<Vec<_>>())
+            .reduce(|common, components| {
+                let len = common.iter().zip(&components).take_while(|(a, b)| a == b).count();
+                common[..len].to_vec()
+            })
+            .unwrap_or_default()
+            .iter()
+            .collect(),
+    };
+
+    // 需要建分组的目录：层级浅的优先沿用目录名，同层按路径排序保证命名稳定
+    let mut folders: Vec<&Path> = parents
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|parent| *parent != root && parent.file_name().is_some())
+        .collect();
+    folders.sort_by_key(|folder| (folder.components().count(), *folder));
+    folders.dedup();
+
+    let mut used: HashSet<String> = HashSet::new();
+    let mut names: HashMap<&Path, String> = HashMap::new();
+    for folder in folders {
+        let base = folder.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let mut name = base.clone();
+        if used.contains(&name) {
+            if let Some(grandparent) = folder.parent().and_then(Path::file_name) {
+                name = format!("{} ({})", base, grandparent.to_string_lossy());
+            }
+        }
+        let candidate = name.clone();
+        let mut suffix = 2;
+        while used.contains(&name) {
+            name = format!("{} {}", candidate, suffix);
+            suffix += 1;
+        }
+        used.insert(name.clone());
+        names.insert(folder, name);
+    }
+
+    parents
+        .iter()
+        .map(|parent| parent.and_then(|parent| names.get(parent).cloned()))
+        .collect()
+}
+
 /// 批量导入书籍到数据库（使用事务）
+/// `group_by_folder` 为 true 时按文件所在目录名归入同名分组（已存在则复用），
+/// 导入根目录下的散文件归入 `group_id`，未指定时归入默认分组；返回的书籍带有所属分组名。
+/// `import_root` 为前端选择的导入文件夹，分组以它为根计算，只选了一个分类文件夹时也能按该文件夹建组
 #[tauri::command]
 pub async fn batch_import_books(
     app_handle: AppHandle,
     books: Vec<PdfMetadata>,
     group_id: Option<i64>,
+    group_by_folder: Option<bool>,
+    import_root: Option<String>,
     db: DbState<'_>,
 ) -> Result<Vec<Book>, String> {
+    // 按目录建分组需要单独加锁，放在事务之前完成
+    let target_groups: Vec<Option<i64>> = if group_by_folder.unwrap_or(false) {
+        let paths: Vec<String> = books.iter().map(|b| b.path.clone()).collect();
+        let import_root = import_root.as_deref().map(str::trim).filter(|root| !root.is_empty());
+        let folders = plan_folder_groups(&paths, import_root.map(Path::new));
+        if group_id.is_none() && folders.iter().any(Option::is_none) {
+            // 默认分组在变空时会被删除，散文件归入前确保它存在
+            let pool = db.lock().await;
+            sqlx::query("INSERT OR IGNORE INTO groups (id, name, book_count) VALUES (?, '默认分组', 0)")
+                .bind(DEFAULT_GROUP_ID)
+                .execute(&*pool)
+                .await
+                .map_err(|e| format!("创建默认分组失败: {}", e))?;
+        }
+        let mut group_ids: HashMap<String, i64> = HashMap::new();
+        let mut targets = Vec::with_capacity(paths.len());
+        for folder in folders {
+            let target = match folder {
+                Some(name) => match group_ids.get(&name) {
+                    Some(&id) => id,
+                    None => {
+                        let id = find_or_create_group(&db, &name).await?;
+                        group_ids.insert(name, id);
+                        id
+                    }
+                },
+                None => group_id.unwrap_or(DEFAULT_GROUP_ID),
+            };
+            targets.push(Some(target));
+        }
+        println!("[Import] 按文件夹分组: books={}, groups={}", paths.len(), group_ids.len());
+        targets
+    } else {
+        vec![group_id; books.len()]
+    };
+
+    // 语言与系列提取需要打开文件，放在事务之前完成
+    let mut books = books;
+    for book_meta in books.iter_mut() {
+        if book_meta.series.is_none() {
+            let path = book_meta.path.clone();
+            let (series, series_index) = tokio::task::spawn_blocking(move || extract_series_candidate(&path))
+                .await
+                .unwrap_or((None, None));
+            book_meta.series = series;
+            book_meta.series_index = book_meta.series_index.or(series_index);
+        }
+        book_meta.language = match book_meta.language.as_deref().and_then(normalize_language_tag) {
+            Some(language) => Some(language),
+            None => {
+                let path = book_meta.path.clone();
+                tokio::task::spawn_blocking(move || extract_language_candidate(&path))
+                    .await
+                    .unwrap_or(None)
+            }
+        };
+    }
+
     let pool = db.lock().await;
     let mut tx = pool.begin().await.map_err(|e| format!("开始事务失败: {}", e))?;
     
     let mut imported_books = Vec::new();
     
-    for book_meta in books {
-        let processed_cover = match book_meta.cover_base64.as_deref() {
-            Some(data) if !data.is_empty() => {
-                match cover::process_cover_for_storage(&app_handle, &book_meta.path, Some(data)).await {
-                    Ok(path_opt) => path_opt.or_else(|| Some(data.to_string())),
-                    Err(e) => {
-                        eprintln!("[batch_import_books] Failed to save cover: {}", e);
-                        Some(data.to_string())
-                    }
+    for (book_meta, target_group) in books.into_iter().zip(&target_groups) {
+        let cover_data = book_meta.cover_base64.as_deref().filter(|data| !data.is_empty());
+        if cover_data.is_some_and(|data| data.len() > LARGE_COVER_PAYLOAD_BYTES) {
+            write_log(
+                "warn",
+                "Import",
+                &format!("封面数据过大: file={}, bytes={}", book_meta.path, cover_data.map_or(0, str::len)),
+            );
+        }
+        let processed_cover =
+            match cover::process_cover_for_storage(&app_handle, &book_meta.path, cover_data, Some(&book_meta.title)).await {
+                Ok(path_opt) => path_opt.or_else(|| cover_data.map(str::to_string)),
+                Err(e) => {
+                    eprintln!("[batch_import_books] Failed to save cover: {}", e);
+                    write_log("error", "Import", &format!("保存封面失败: file={}, {}", book_meta.path, e));
+                    cover_data.map(str::to_string)
                 }
-            }
-            _ => None,
-        };
+            };
 
         // 插入书籍
         let result = sqlx::query(
-            "INSERT OR IGNORE INTO books (title, file_path, cover_image, total_pages, group_id) VALUES (?, ?, ?, ?, ?)"
+            "INSERT OR IGNORE INTO books (title, file_path, cover_image, total_pages, group_id, series, series_index, language) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&book_meta.title)
         .bind(&book_meta.path)
         .bind(&processed_cover)
         .bind(book_meta.total_pages as i64)
-        .bind(group_id)
+        .bind(target_group)
+        .bind(&book_meta.series)
+        .bind(book_meta.series_index)
+        .bind(&book_meta.language)
         .execute(&mut *tx)
         .await
         .map_err(|e| format!("插入书籍失败: {}", e))?;
@@ -137,7 +336,8 @@ pub async fn batch_import_books(
     }
     
     // 更新分组书籍计数
-    if let Some(gid) = group_id {
+    let affected_groups: BTreeSet<i64> = target_groups.iter().flatten().copied().collect();
+    for &gid in &affected_groups {
         sqlx::query(
             "UPDATE groups SET book_count = (SELECT COUNT(*) FROM books WHERE group_id = ?) WHERE id = ?"
         )
@@ -149,40 +349,362 @@ pub async fn batch_import_books(
     }
     
     tx.commit().await.map_err(|e| format!("提交事务失败: {}", e))?;
+
+    // 组内新增书籍进入拼贴时封面需重建
+    for gid in affected_groups {
+        crate::commands::cover::mark_group_cover_stale(&pool, gid)
+            .await
+            .map_err(|e| format!("更新分组封面标记失败: {}", e))?;
+    }
+
+    let group_names: HashMap<i64, String> = sqlx::query_as("SELECT id, name FROM groups")
+        .fetch_all(&*pool)
+        .await
+        .map_err(|e| format!("查询分组失败: {}", e))?
+        .into_iter()
+        .collect();
+    for book in imported_books.iter_mut() {
+        book.group_name = book.group_id.and_then(|gid| group_names.get(&gid).cloned());
+    }
     
     Ok(imported_books)
 }
 
-/// 批量处理PDF元数据（前端调用此命令获取元数据，然后在前端生成封面）
-/// 这样可以利用前端的PDF.js和Canvas API
+/// 从元数据（或正文）中提取书名，不含文件名回退
+/// 优先元数据 title（EPUB/MOBI/PDF），其次第一章标题（TXT）、`<title>`（HTML）、第一个 H1（Markdown）
+fn extract_title_candidate(file_path: &str) -> Option<String> {
+    let format = BookFormat::from_path(file_path)?;
+    let title = match format {
+        BookFormat::Epub => epub::engine::read_title(file_path).ok().flatten(),
+        BookFormat::Mobi | BookFormat::Azw3 => mobi::engine::read_title(file_path).ok().flatten(),
+        BookFormat::Pdf => with_cached_document(file_path, |_, document| {
+            Ok(document
+                .metadata()
+                .get(PdfDocumentMetadataTagType::Title)
+                .map(|tag| tag.value().to_string()))
+        })
+        .ok()
+        .flatten(),
+        BookFormat::Txt => TxtEngine::load_metadata(file_path, None)
+            .ok()
+            .and_then(|meta| meta.chapters.first().map(|chapter| chapter.title.clone()))
+            // 跳过目录解析器生成的占位章节（“开始”、“第 N 部分”）
+            .filter(|title| title != "开始" && !(title.starts_with("第 ") && title.ends_with(" 部分"))),
+        BookFormat::Html => HtmlEngine::from_file(file_path)
+            .ok()
+            .and_then(|engine| engine.extract_title_from_content()),
+        BookFormat::Markdown => MarkdownEngine::from_file(file_path)
+            .ok()
+            .and_then(|engine| engine.extract_title_from_content()),
+        BookFormat::Fb2 | BookFormat::Cbz | BookFormat::Cbr => None,
+    };
+    title
+        .map(|t| clean_title(&t))
+        .filter(|t| !t.trim().is_empty())
+}
+
+/// 文档目录（Catalog）对象
+static PDF_CATALOG_RE: Lazy<BytesRegex> = Lazy::new(|| BytesRegex::new(r"/Type\s*/Catalog\b").unwrap());
+/// `/Lang (zh-CN)` 或 `/Lang <FEFF007A0068>`
+static PDF_LANG_RE: Lazy<BytesRegex> =
+    Lazy::new(|| BytesRegex::new(r"(?-u)/Lang\s*(?:\(([^)]{1,64})\)|<([0-9A-Fa-f\s]{2,260})>)").unwrap());
+
+/// 从 PDF 原始字节中查找文档目录的 `/Lang`；目录位于压缩对象流中时找不到，返回 None
+fn parse_pdf_catalog_language(data: &[u8]) -> Option<String> {
+    PDF_CATALOG_RE.find_iter(data).find_map(|catalog| {
+        // 只在目录对象自身的 obj ... endobj 范围内查找，避免取到结构树元素上的 /Lang
+        let window_start = catalog.start().saturating_sub(1024);
+        let start = data[window_start..catalog.start()]
+            .windows(3)
+            .rposition(|w| w == b"obj")
+            .map_or(window_start, |pos| window_start + pos);
+        let window_end = (catalog.end() + 4096).min(data.len());
+        let end = data[catalog.end()..window_end]
+            .windows(6)
+            .position(|w| w == b"endobj")
+            .map_or(window_end, |pos| catalog.end() + pos);
+
+        let caps = PDF_LANG_RE.captures(&data[start..end])?;
+        let raw = match (caps.get(1), caps.get(2)) {
+            (Some(literal), _) => literal.as_bytes().to_vec(),
+            (_, Some(hex)) => {
+                let digits: Vec<u8> = hex.as_bytes().iter().copied().filter(u8::is_ascii_hexdigit).collect();
+                digits
+                    .chunks(2)
+                    .filter_map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+                    .collect()
+            }
+            _ => return None,
+        };
+        // 带 BOM 的是 UTF-16BE 文本字符串
+        let text = match raw.strip_prefix(&[0xFE, 0xFF]) {
+            Some(utf16) => String::from_utf16_lossy(
+                &utf16
+                    .chunks_exact(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect::<Vec<_>>(),
+            ),
+            None => String::from_utf8_lossy(&raw).to_string(),
+        };
+        normalize_language_tag(&text)
+    })
+}
+
+/// 读取 PDF 文档目录声明的语言，大文件使用内存映射避免整体读入
+fn read_pdf_language(file_path: &str) -> Option<String> {
+    let file = std::fs::File::open(file_path).ok()?;
+    let mmap = unsafe { memmap2::MmapOptions::new().map(&file).ok()? };
+    parse_pdf_catalog_language(&mmap)
+}
+
+/// 从书籍元数据中提取语言并规范化：EPUB 的 dc:language、MOBI 的 EXTH 524、PDF 目录的 /Lang
+/// 其他格式没有语言声明，返回 None
+pub(crate) fn extract_language_candidate(file_path: &str) -> Option<String> {
+    let language = match BookFormat::from_path(file_path)? {
+        BookFormat::Epub => epub::engine::read_language(file_path).ok().flatten(),
+        BookFormat::Mobi | BookFormat::Azw3 => mobi::engine::read_language(file_path).ok().flatten(),
+        BookFormat::Pdf => return read_pdf_language(file_path),
+        _ => None,
+    };
+    language.as_deref().and_then(normalize_language_tag)
+}
+
+/// 从书籍元数据中提取系列名称与序号：目前只有 EPUB 声明系列（calibre:series / belongs-to-collection）
+/// MOBI 的 EXTH 没有系列记录，其他格式同样返回 (None, None)
+pub(crate) fn extract_series_candidate(file_path: &str) -> (Option<String>, Option<f32>) {
+    match BookFormat::from_path(file_path) {
+        Some(BookFormat::Epub) => epub::engine::read_series(file_path).unwrap_or((None, None)),
+        _ => (None, None),
+    }
+}
+
+/// 解析导入时使用的书名：元数据/正文标题 > 文件名，并去掉常见的噪音后缀
+#[tauri::command]
+pub async fn resolve_book_title(file_path: String) -> Result<String, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.is_file() {
+        return Err(format!("文件不存在或不是文件: {}", file_path));
+    }
+
+    let candidate_path = file_path.clone();
+    let candidate = tokio::task::spawn_blocking(move || extract_title_candidate(&candidate_path))
+        .await
+        .map_err(|e| format!("解析书名任务失败: {}", e))?;
+
+    let title = candidate.unwrap_or_else(|| {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(&file_path);
+        clean_title(stem)
+    });
+    println!("[Import] 解析书名: {} -> {}", file_path, title);
+    Ok(title)
+}
+
+/// 批量读取 PDF 信息时的进度事件名，每完成一个文件发送一次
+const PDF_INFO_PROGRESS_EVENT: &str = "goread:import:pdf-info";
+
+/// 导入封面缩略图宽度（像素）
+const PDF_COVER_WIDTH: i32 = 360;
+
+const PDF_COVER_JPEG_QUALITY: u8 = 80;
+
+/// 单个 PDF 的导入信息；读取失败时 `error` 有值，其余字段为默认值
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfImportInfo {
+    pub path: String,
+    pub file_size: u64,
+    pub title: Option<String>,
+    pub total_pages: u32,
+    /// 首页缩略图，`data:image/jpeg;base64,...`，可直接作为导入时的封面数据
+    pub cover_base64: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfInfoProgress {
+    /// 已完成的文件数（完成顺序，不是输入顺序）
+    pub current: usize,
+    pub total: usize,
+    pub info: PdfImportInfo,
+}
+
+/// 渲染首页缩略图并编码为 JPEG data URL
+fn render_pdf_cover(document: &PdfDocument<'_>) -> Result<String, String> {
+    let page = document.pages().get(0).map_err(|e| format!("获取首页失败: {}", e))?;
+    let config = PdfRenderConfig::new()
+        .set_target_width(PDF_COVER_WIDTH)
+        .rotate_if_landscape(PdfPageRenderRotation::None, false);
+    let bitmap = page.render_with_config(&config).map_err(|e| format!("渲染首页失败: {}", e))?;
+    let rgb = bitmap.as_image().to_rgb8();
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, PDF_COVER_JPEG_QUALITY)
+        .encode(rgb.as_raw(), rgb.width(), rgb.height(), image::ColorType::Rgb8)
+        .map_err(|e| format!("编码封面失败: {}", e))?;
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(&buffer)
+    ))
+}
+
+/// 打开一次文档，同时读取页数、元数据标题和首页封面
+fn read_pdf_import_info(path: String) -> PdfImportInfo {
+    let mut info = PdfImportInfo {
+        path,
+        file_size: 0,
+        title: None,
+        total_pages: 0,
+        cover_base64: None,
+        error: None,
+    };
+    match std::fs::metadata(&info.path) {
+        Ok(metadata) if metadata.is_file() => info.file_size = metadata.len(),
+        _ => {
+            info.error = Some(format!("文件不存在: {}", info.path));
+            return info;
+        }
+    }
+
+    let result = with_cached_document(&info.path, |_, document| {
+        let title = document
+            .metadata()
+            .get(PdfDocumentMetadataTagType::Title)
+            .map(|tag| clean_title(tag.value()))
+            .filter(|t| !t.trim().is_empty());
+        let cover = render_pdf_cover(document);
+        Ok((document.pages().len() as u32, title, cover))
+    });
+    match result {
+        Ok((total_pages, title, cover)) => {
+            info.total_pages = total_pages;
+            info.title = title;
+            match cover {
+                Ok(cover) => info.cover_base64 = Some(cover),
+                // 封面失败不影响导入，前端可回退为默认封面
+                Err(e) => eprintln!("[Import] 生成 PDF 封面失败: {}, {}", info.path, e),
+            }
+        }
+        Err(e) => info.error = Some(format!("打开 PDF 失败: {}", e)),
+    }
+    info
+}
+
+/// 批量读取 PDF 的页数、标题和首页封面，供导入前预览
+/// 以受限并发并行处理（默认取 PDF 渲染并发上限，可由 `concurrency` 覆盖），每个文件只打开一次文档；
+/// 每完成一个文件发送 `goread:import:pdf-info` 事件，单个文件失败只记录在其 `error` 中。返回结果保持输入顺序
 #[tauri::command]
-pub async fn batch_get_pdf_info(paths: Vec<String>) -> Result<Vec<(String, u64)>, String> {
+pub async fn batch_get_pdf_info(
+    app_handle: AppHandle,
+    paths: Vec<String>,
+    concurrency: Option<usize>,
+) -> Result<Vec<PdfImportInfo>, String> {
+    use std::sync::Arc;
+    use tokio::sync::Semaphore;
     use tokio::task::JoinSet;
-    
+
+    let total = paths.len();
+    let permits = concurrency.unwrap_or_else(render_concurrency).clamp(1, 16);
+    let semaphore = Arc::new(Semaphore::new(permits));
+    println!("[Import] 批量读取 PDF 信息: files={}, concurrency={}", total, permits);
+
     let mut tasks = JoinSet::new();
-    
-    for path in paths {
+    for (index, path) in paths.into_iter().enumerate() {
+        let semaphore = semaphore.clone();
         tasks.spawn(async move {
-            let file_path = PathBuf::from(&path);
-            if !file_path.exists() || !file_path.is_file() {
-                return Err(format!("文件不存在: {}", path));
-            }
-            
-            match tokio::fs::metadata(&file_path).await {
-                Ok(metadata) => Ok((path, metadata.len())),
-                Err(e) => Err(format!("获取文件信息失败 {}: {}", path, e)),
-            }
+            let _permit = semaphore.acquire_owned().await;
+            let info = tokio::task::spawn_blocking({
+                let path = path.clone();
+                move || read_pdf_import_info(path)
+            })
+            .await
+            .unwrap_or_else(|e| PdfImportInfo {
+                path,
+                file_size: 0,
+                title: None,
+                total_pages: 0,
+                cover_base64: None,
+                error: Some(format!("任务执行失败: {}", e)),
+            });
+            (index, info)
         });
     }
-    
-    let mut results = Vec::new();
-    while let Some(result) = tasks.join_next().await {
-        match result {
-            Ok(Ok(data)) => results.push(data),
-            Ok(Err(e)) => return Err(e),
-            Err(e) => return Err(format!("任务执行失败: {}", e)),
+
+    let mut results: Vec<Option<PdfImportInfo>> = vec![None; total];
+    let mut current = 0;
+    while let Some(joined) = tasks.join_next().await {
+        let (index, info) = joined.map_err(|e| format!("任务执行失败: {}", e))?;
+        current += 1;
+        if let Some(error) = &info.error {
+            write_log("warn", "Import", &format!("读取 PDF 信息失败: file={}, {}", info.path, error));
         }
+        let _ = app_handle.emit(
+            PDF_INFO_PROGRESS_EVENT,
+            PdfInfoProgress {
+                current,
+                total,
+                info: info.clone(),
+            },
+        );
+        results[index] = Some(info);
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_folder_groups() {
+        let paths: Vec<String> = [
+            "/books/科幻/三体.epub",
+            "/books/散文.txt",
+            "/books/科幻/基地.epub",
+            "/books/历史/明朝.pdf",
+            "/books/旧书/科幻/沙丘.epub",
+            "/books/外文/科幻/Dune.epub",
+        ]
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+        let groups = plan_folder_groups(&paths, None);
+        assert_eq!(
+            groups,
+            vec![
+                Some("科幻".to_string()),
+                None,
+                Some("科幻".to_string()),
+                Some("历史".to_string()),
+                Some("科幻 (旧书)".to_string()),
+                Some("科幻 (外文)".to_string()),
+            ]
+        );
+
+        // 所有文件在同一目录时都是根目录散文件
+        let flat = vec!["/books/a.txt".to_string(), "/books/b.txt".to_string()];
+        assert_eq!(plan_folder_groups(&flat, None), vec![None, None]);
+
+        // 用户选择的是上级文件夹时，单个分类文件夹也按目录名建组
+        let single = vec!["/books/科幻/三体.epub".to_string(), "/books/科幻/基地.epub".to_string()];
+        assert_eq!(plan_folder_groups(&single, None), vec![None, None]);
+        assert_eq!(
+            plan_folder_groups(&single, Some(Path::new("/books"))),
+            vec![Some("科幻".to_string()), Some("科幻".to_string())]
+        );
+        assert_eq!(plan_folder_groups(&single, Some(Path::new("/books/科幻/"))), vec![None, None]);
+    }
+
+    #[test]
+    fn test_parse_pdf_catalog_language() {
+        let pdf = b"%PDF-1.7\n1 0 obj\n<< /Type /StructElem /Lang (fr) >>\nendobj\n\
+            2 0 obj\n<< /Type /Catalog /Pages 3 0 R /Lang (zh_cn) >>\nendobj\n";
+        assert_eq!(parse_pdf_catalog_language(pdf).as_deref(), Some("zh-CN"));
+
+        let hex = b"1 0 obj << /Lang <FEFF0065006E002D00550053> /Type /Catalog >> endobj";
+        assert_eq!(parse_pdf_catalog_language(hex).as_deref(), Some("en-US"));
+
+        assert_eq!(parse_pdf_catalog_language(b"1 0 obj << /Type /Catalog >> endobj"), None);
     }
-    
-    Ok(results)
 }
@@ -1,32 +1,297 @@
+//! 应用日志持久化
+//! 前端日志与后端关键路径的异常写入应用数据目录下的 `logs/goread.log`，
+//! 单文件超过大小上限或跨天时轮转为带时间戳的归档文件，只保留最近几份归档，避免日志占满空间
+
 use tauri::State;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use chrono::{DateTime, Local, NaiveDate};
+use once_cell::sync::Lazy;
 use tokio::sync::Mutex;
 use sqlx::SqlitePool;
 
+/// 日志目录名（位于应用数据目录下）
+const LOG_DIR_NAME: &str = "logs";
+
+/// 当前写入的日志文件
+const ACTIVE_LOG_FILE: &str = "goread.log";
+
+/// 归档文件名前缀，后接轮转时间戳
+const ARCHIVE_PREFIX: &str = "goread-";
+
+/// 单个日志文件大小上限
+const MAX_LOG_FILE_BYTES: u64 = 1024 * 1024;
+
+/// 保留的归档文件数量上限
+const MAX_ARCHIVED_LOGS: usize = 5;
+
+/// 归档文件保留天数
+const MAX_LOG_AGE_DAYS: i64 = 7;
+
+/// 单条日志长度上限，防止前端把大对象整段写入
+const MAX_ENTRY_CHARS: usize = 4000;
+
+/// get_logs 默认与最多返回的行数
+const DEFAULT_LOG_LINES: usize = 200;
+const MAX_LOG_LINES: usize = 5000;
+
+struct LogWriter {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+    date: NaiveDate,
+    max_file_bytes: u64,
+}
+
+static LOG_WRITER: Lazy<std::sync::Mutex<Option<LogWriter>>> = Lazy::new(|| std::sync::Mutex::new(None));
+
+impl LogWriter {
+    fn open(dir: PathBuf, max_file_bytes: u64) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(ACTIVE_LOG_FILE);
+        let metadata = std::fs::metadata(&path).ok();
+        let date = metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .map(|t| DateTime::<Local>::from(t).date_naive())
+            .unwrap_or_else(|| Local::now().date_naive());
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            dir,
+            file: Some(file),
+            size: metadata.map_or(0, |m| m.len()),
+            date,
+            max_file_bytes,
+        })
+    }
+
+    /// 当前文件归档为 `goread-{时间戳}.log` 并新建空文件，同时清理过期归档
+    fn rotate(&mut self) -> std::io::Result<()> {
+        self.file = None;
+        let active = self.dir.join(ACTIVE_LOG_FILE);
+        if active.exists() {
+            // 同一秒内多次轮转时用序号区分，保证文件名按时间排序
+            let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+            let archive = (0..)
+                .map(|seq| self.dir.join(format!("{}{}-{:04}.log", ARCHIVE_PREFIX, stamp, seq)))
+                .find(|path| !path.exists())
+                .unwrap_or_default();
+            std::fs::rename(&active, archive)?;
+        }
+        prune_archives(&self.dir);
+        self.file = Some(OpenOptions::new().create(true).append(true).open(&active)?);
+        self.size = 0;
+        self.date = Local::now().date_naive();
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let today = Local::now().date_naive();
+        if self.size > 0 && (self.size + line.len() as u64 > self.max_file_bytes || self.date != today) {
+            self.rotate()?;
+        }
+        if let Some(file) = self.file.as_mut() {
+            file.write_all(line.as_bytes())?;
+            self.size += line.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+/// 归档文件按时间从旧到新排列（文件名中的时间戳可直接按字典序比较）
+fn archived_logs(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with(ARCHIVE_PREFIX) && n.ends_with(".log"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 删除超出数量上限或超过保留天数的归档
+fn prune_archives(dir: &Path) {
+    let archives = archived_logs(dir);
+    let excess = archives.len().saturating_sub(MAX_ARCHIVED_LOGS);
+    let expire_before = Local::now() - chrono::Duration::days(MAX_LOG_AGE_DAYS);
+    for (i, path) in archives.iter().enumerate() {
+        let expired = std::fs::metadata(path)
+            .and_then(|m| m.modified())
+            .is_ok_and(|t| DateTime::<Local>::from(t) < expire_before);
+        if i < excess || expired {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// 全部日志文件，从旧到新
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = archived_logs(dir);
+    let active = dir.join(ACTIVE_LOG_FILE);
+    if active.exists() {
+        files.push(active);
+    }
+    files
+}
+
+/// 从新到旧读取日志文件，返回最后 `lines` 行（按时间顺序）
+fn tail_lines(files: &[PathBuf], lines: usize) -> Vec<String> {
+    let mut collected: Vec<String> = Vec::new();
+    for path in files.iter().rev() {
+        let Ok(bytes) = std::fs::read(path) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let mut file_lines: Vec<String> = text.lines().map(str::to_string).collect();
+        let take = (lines - collected.len()).min(file_lines.len());
+        file_lines.drain(..file_lines.len() - take);
+        file_lines.append(&mut collected);
+        collected = file_lines;
+        if collected.len() >= lines {
+            break;
+        }
+    }
+    collected
+}
+
+/// 在应用数据目录下初始化日志文件，应用启动时调用
+pub fn init_log_dir(app_data_dir: &Path) {
+    match LogWriter::open(app_data_dir.join(LOG_DIR_NAME), MAX_LOG_FILE_BYTES) {
+        Ok(writer) => {
+            prune_archives(&writer.dir);
+            if let Ok(mut guard) = LOG_WRITER.lock() {
+                *guard = Some(writer);
+            }
+        }
+        Err(e) => eprintln!("[Log] 初始化日志文件失败: {}", e),
+    }
+}
+
+/// 写入一条日志；日志未初始化或写入失败时静默忽略，不影响业务流程
+pub fn write_log(level: &str, source: &str, message: &str) {
+    let Ok(mut guard) = LOG_WRITER.lock() else {
+        return;
+    };
+    let Some(writer) = guard.as_mut() else {
+        return;
+    };
+    let message = match message.char_indices().nth(MAX_ENTRY_CHARS) {
+        Some((end, _)) => format!("{}...", &message[..end]),
+        None => message.to_string(),
+    };
+    let line = format!(
+        "{} [{}] [{}] {}\n",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        level.to_uppercase(),
+        source,
+        message
+    );
+    if let Err(e) = writer.write_line(&line) {
+        eprintln!("[Log] 写入日志失败: {}", e);
+    }
+}
+
+/// 当前日志目录
+fn log_dir() -> Result<PathBuf, String> {
+    LOG_WRITER
+        .lock()
+        .map_err(|e| format!("获取日志锁失败: {}", e))?
+        .as_ref()
+        .map(|w| w.dir.clone())
+        .ok_or_else(|| "日志未初始化".to_string())
+}
+
 #[tauri::command]
 pub async fn frontend_log(level: String, message: String, context: Option<String>, _db: State<'_, Arc<Mutex<SqlitePool>>>) -> Result<(), String> {
     match level.as_str() {
         "error" => {
-            if let Some(ctx) = context {
+            if let Some(ctx) = &context {
                 eprintln!("[frontend][error] {} :: {}", message, ctx);
             } else {
                 eprintln!("[frontend][error] {}", message);
             }
         }
         "warn" => {
-            if let Some(ctx) = context {
+            if let Some(ctx) = &context {
                 println!("[frontend][warn] {} :: {}", message, ctx);
             } else {
                 println!("[frontend][warn] {}", message);
             }
         }
         _ => {
-            if let Some(ctx) = context {
+            if let Some(ctx) = &context {
                 println!("[frontend][info] {} :: {}", message, ctx);
             } else {
                 println!("[frontend][info] {}", message);
             }
         }
     }
+    let level = match level.as_str() {
+        "error" | "warn" => level.as_str(),
+        _ => "info",
+    };
+    match context {
+        Some(ctx) => write_log(level, "frontend", &format!("{} :: {}", message, ctx)),
+        None => write_log(level, "frontend", &message),
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// 读取最近的日志，`lines` 默认 200 行，最多 5000 行
+#[tauri::command]
+pub async fn get_logs(lines: Option<usize>) -> Result<Vec<String>, String> {
+    let dir = log_dir()?;
+    let lines = lines.unwrap_or(DEFAULT_LOG_LINES).clamp(1, MAX_LOG_LINES);
+    tokio::task::spawn_blocking(move || tail_lines(&log_files(&dir), lines))
+        .await
+        .map_err(|e| format!("读取日志失败: {}", e))
+}
+
+/// 把全部日志（归档在前）合并导出到 `out_path`，返回写入的字节数
+#[tauri::command]
+pub async fn export_logs(out_path: String) -> Result<u64, String> {
+    let dir = log_dir()?;
+    tokio::task::spawn_blocking(move || {
+        let mut out = File::create(&out_path).map_err(|e| format!("创建导出文件失败: {}", e))?;
+        let mut written = 0u64;
+        for path in log_files(&dir) {
+            let mut file = File::open(&path).map_err(|e| format!("读取日志文件失败: {}", e))?;
+            written += std::io::copy(&mut file, &mut out).map_err(|e| format!("写入导出文件失败: {}", e))?;
+        }
+        println!("[Log] 导出日志: {} ({} bytes)", out_path, written);
+        Ok(written)
+    })
+    .await
+    .map_err(|e| format!("导出日志失败: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_and_tail() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        let mut writer = LogWriter::open(dir.clone(), 64).unwrap();
+        for i in 0..10 {
+            writer.write_line(&format!("line {:02} ..........\n", i)).unwrap();
+        }
+
+        let files = log_files(&dir);
+        assert!(files.len() > 1 && files.len() <= MAX_ARCHIVED_LOGS + 1);
+        assert!(files.iter().all(|f| std::fs::metadata(f).unwrap().len() <= 64));
+        let tail = tail_lines(&files, 3);
+        assert_eq!(tail, vec!["line 07 ..........", "line 08 ..........", "line 09 .........."]);
+    }
+}
@@ -1,3 +1,4 @@
+pub mod archive;
 pub mod book;
 pub mod bookmark;
 pub mod cover;
@@ -5,10 +6,18 @@ pub mod filesystem;
 pub mod group;
 pub mod import;
 pub mod log;
+pub mod opds;
+pub mod scan_cache;
+pub mod scan_exclude;
+pub mod search;
+pub mod settings;
 pub mod stats;
+pub mod theme;
+pub mod virtual_book;
 pub mod backup;
 
 // Re-export all commands
+pub use archive::*;
 pub use book::*;
 pub use bookmark::*;
 pub use cover::*;
@@ -16,5 +25,11 @@ pub use filesystem::*;
 pub use group::*;
 pub use import::*;
 pub use log::*;
+pub use opds::*;
+pub use scan_cache::*;
+pub use scan_exclude::*;
+pub use search::*;
 pub use stats::*;
+pub use theme::*;
+pub use virtual_book::*;
 pub use backup::*;
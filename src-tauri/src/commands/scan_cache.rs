@@ -0,0 +1,151 @@
+//! 扫描结果增量缓存
+//! 以目录为单位记录 mtime 和直接子项，目录未变化时扫描直接复用缓存，
+//! 复用时仍会刷新各文件的大小和修改时间（原地改写文件不改变目录 mtime）
+
+use crate::commands::book::DbState;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+
+/// 缓存的书籍文件（只记录可识别的书籍格式，筛选条件在读取时应用）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedScanFile {
+    pub name: String,
+    pub path: String,
+    pub size: u64,
+    pub mtime: Option<i64>,
+}
+
+/// 单个目录的缓存内容
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedDirEntries {
+    pub files: Vec<CachedScanFile>,
+    /// 直接子目录的完整路径
+    pub dirs: Vec<String>,
+}
+
+/// 单个目录的缓存记录
+#[derive(Debug, Clone)]
+pub struct ScanCacheRow {
+    pub mtime: i64,
+    pub entries: CachedDirEntries,
+}
+
+/// 一次扫描过程中的缓存状态：启动时整表读入，结束时批量写回
+#[derive(Debug, Default)]
+pub struct ScanCacheSession {
+    /// 命中缓存的目录数
+    pub cached_dirs: u32,
+    /// 实际读取的目录数
+    pub scanned_dirs: u32,
+    rows: HashMap<String, ScanCacheRow>,
+    updated: HashMap<String, ScanCacheRow>,
+    visited: HashSet<String>,
+}
+
+impl ScanCacheSession {
+    /// 从数据库加载全部缓存行
+    pub async fn load(pool: &SqlitePool) -> Self {
+        let rows: Vec<(String, i64, String)> =
+            sqlx::query_as("SELECT dir_path, mtime, entries_json FROM scan_cache")
+                .fetch_all(pool)
+                .await
+                .unwrap_or_default();
+
+        let rows = rows
+            .into_iter()
+            .filter_map(|(dir_path, mtime, json)| {
+                let entries = serde_json::from_str(&json).ok()?;
+                Some((dir_path, ScanCacheRow { mtime, entries }))
+            })
+            .collect();
+
+        Self {
+            rows,
+            ..Default::default()
+        }
+    }
+
+    /// 目录 mtime 未变化时返回缓存内容
+    pub fn lookup(&mut self, dir_path: &str, mtime: i64) -> Option<&CachedDirEntries> {
+        self.visited.insert(dir_path.to_string());
+        self.rows
+            .get(dir_path)
+            .filter(|row| row.mtime == mtime)
+            .map(|row| &row.entries)
+    }
+
+    /// 记录重新扫描或刷新了文件信息的目录内容，结束时写回
+    pub fn record(&mut self, dir_path: String, mtime: i64, entries: CachedDirEntries) {
+        self.visited.insert(dir_path.clone());
+        self.updated.insert(dir_path, ScanCacheRow { mtime, entries });
+    }
+
+    /// 写回变化的目录，并清理扫描根目录下已不存在（本次未访问到）的目录缓存
+    pub async fn persist(self, pool: &SqlitePool, roots: &[String]) -> Result<(), sqlx::Error> {
+        let stale: Vec<&String> = self
+            .rows
+            .keys()
+            .filter(|path| !self.visited.contains(*path))
+            .filter(|path| roots.iter().any(|root| is_same_or_descendant(path, root)))
+            .collect();
+
+        let mut tx = pool.begin().await?;
+        for path in stale {
+            sqlx::query("DELETE FROM scan_cache WHERE dir_path = ?")
+                .bind(path)
+                .execute(&mut *tx)
+                .await?;
+        }
+        for (path, row) in self.updated {
+            let json = serde_json::to_string(&row.entries).unwrap_or_else(|_| "{}".to_string());
+            sqlx::query(
+                "INSERT OR REPLACE INTO scan_cache (dir_path, mtime, entries_json, updated_at) VALUES (?, ?, ?, strftime('%s', 'now'))",
+            )
+            .bind(&path)
+            .bind(row.mtime)
+            .bind(json)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await
+    }
+}
+
+/// 判断 path 是否为 root 本身或其子路径
+fn is_same_or_descendant(path: &str, root: &str) -> bool {
+    let root = root.trim_end_matches(['/', '\\']);
+    if root.is_empty() {
+        // 根目录 "/"
+        return true;
+    }
+    path == root
+        || (path.starts_with(root)
+            && matches!(path.as_bytes().get(root.len()), Some(b'/') | Some(b'\\')))
+}
+
+/// 清除扫描缓存；指定路径时清除该目录及其所有子目录，否则清空全部
+#[tauri::command]
+pub async fn invalidate_scan_cache(path: Option<String>, db: DbState<'_>) -> Result<u64, String> {
+    let pool = db.lock().await;
+
+    let result = match path {
+        Some(path) => {
+            let root = path.trim_end_matches(['/', '\\']).to_string();
+            sqlx::query(
+                "DELETE FROM scan_cache WHERE dir_path = ?
+                 OR substr(dir_path, 1, length(?) + 1) IN (? || '/', ? || '\\')",
+            )
+            .bind(&root)
+            .bind(&root)
+            .bind(&root)
+            .bind(&root)
+            .execute(&*pool)
+            .await
+        }
+        None => sqlx::query("DELETE FROM scan_cache").execute(&*pool).await,
+    }
+    .map_err(|e| e.to_string())?;
+
+    Ok(result.rows_affected())
+}
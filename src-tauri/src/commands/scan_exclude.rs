@@ -0,0 +1,164 @@
+//! 扫描排除规则
+//! 规则支持两种写法：
+//! - 通配符：`*`、`?` 不跨越目录层级，`**` 可跨越多级；不含 `/` 时只匹配目录名（如 `.*` 匹配所有隐藏目录）
+//! - 前缀：绝对路径匹配该目录及其子目录；相对路径匹配以这些层级结尾的目录（如 `Android/data`）
+
+/// 内置默认排除规则
+pub const DEFAULT_SCAN_EXCLUDES: &[&str] = &[".*", "Android/data", "Android/obb"];
+
+#[derive(Debug, Clone)]
+enum ExcludeRule {
+    /// 只匹配目录名的通配符
+    NameGlob(String),
+    /// 匹配完整路径的通配符
+    PathGlob(String),
+    /// 绝对路径前缀
+    AbsolutePrefix(String),
+    /// 相对路径层级后缀
+    RelativeSuffix(Vec<String>),
+}
+
+/// 编译后的排除规则集合
+#[derive(Debug, Clone, Default)]
+pub struct ScanExcludes {
+    rules: Vec<ExcludeRule>,
+}
+
+impl ScanExcludes {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let rules = patterns
+            .iter()
+            .filter_map(|pattern| compile_rule(pattern.as_ref()))
+            .collect();
+        Self { rules }
+    }
+
+    /// 内置默认规则
+    pub fn defaults() -> Self {
+        Self::new(DEFAULT_SCAN_EXCLUDES)
+    }
+
+    /// 目录是否应被跳过
+    pub fn is_excluded(&self, dir: &str) -> bool {
+        if self.rules.is_empty() {
+            return false;
+        }
+        let path = normalize_separators(dir);
+        let path = path.trim_end_matches('/');
+        let name = path.rsplit('/').next().unwrap_or(path);
+
+        self.rules.iter().any(|rule| match rule {
+            ExcludeRule::NameGlob(pattern) => wildcard_match(pattern, name),
+            ExcludeRule::PathGlob(pattern) => wildcard_match(pattern, path),
+            ExcludeRule::AbsolutePrefix(prefix) => {
+                let lowered = path.to_lowercase();
+                lowered == *prefix || lowered.starts_with(&format!("{}/", prefix))
+            }
+            ExcludeRule::RelativeSuffix(segments) => {
+                let components: Vec<String> = path
+                    .split('/')
+                    .filter(|s| !s.is_empty())
+                    .map(|s| s.to_lowercase())
+                    .collect();
+                components.ends_with(segments)
+            }
+        })
+    }
+}
+
+fn normalize_separators(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn compile_rule(pattern: &str) -> Option<ExcludeRule> {
+    let pattern = normalize_separators(pattern.trim());
+    let pattern = pattern.trim_end_matches('/');
+    if pattern.is_empty() {
+        return None;
+    }
+
+    if pattern.contains(['*', '?']) {
+        let pattern = pattern.to_lowercase();
+        return Some(if pattern.contains('/') {
+            ExcludeRule::PathGlob(pattern)
+        } else {
+            ExcludeRule::NameGlob(pattern)
+        });
+    }
+
+    let is_absolute = pattern.starts_with('/') || pattern.as_bytes().get(1) == Some(&b':');
+    if is_absolute {
+        Some(ExcludeRule::AbsolutePrefix(pattern.to_lowercase()))
+    } else {
+        let segments: Vec<String> = pattern
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.to_lowercase())
+            .collect();
+        Some(ExcludeRule::RelativeSuffix(segments))
+    }
+}
+
+/// 通配符匹配（不区分大小写，调用方需传入小写的 pattern）
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    match_from(&pattern, &text)
+}
+
+fn match_from(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') => {
+            let cross_dirs = pattern.get(1) == Some(&'*');
+            let rest = if cross_dirs { &pattern[2..] } else { &pattern[1..] };
+            // `**/` 也允许匹配零层目录
+            if cross_dirs && rest.first() == Some(&'/') && match_from(&rest[1..], text) {
+                return true;
+            }
+            for i in 0..=text.len() {
+                if match_from(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && !cross_dirs && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => !text.is_empty() && text[0] != '/' && match_from(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && match_from(&pattern[1..], &text[1..]),
+    }
+}
+
+/// 获取内置默认排除规则，供前端展示并允许用户增减
+#[tauri::command]
+pub async fn get_default_scan_excludes() -> Result<Vec<String>, String> {
+    Ok(DEFAULT_SCAN_EXCLUDES.iter().map(|s| s.to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_excludes() {
+        let excludes = ScanExcludes::defaults();
+        assert!(excludes.is_excluded("/storage/emulated/0/.thumbnails"));
+        assert!(excludes.is_excluded("/storage/emulated/0/Android/data"));
+        assert!(excludes.is_excluded("/storage/emulated/0/Android/obb/"));
+        assert!(!excludes.is_excluded("/storage/emulated/0/Android/media"));
+        assert!(!excludes.is_excluded("/storage/emulated/0/Books"));
+    }
+
+    #[test]
+    fn test_glob_and_prefix_rules() {
+        let excludes = ScanExcludes::new(&["**/cache", "/home/user/tmp", "*_bak"]);
+        assert!(excludes.is_excluded("/data/app/cache"));
+        assert!(excludes.is_excluded("C:\\Users\\me\\Cache"));
+        assert!(excludes.is_excluded("/home/user/tmp/sub"));
+        assert!(!excludes.is_excluded("/home/user/tmpfiles"));
+        assert!(excludes.is_excluded("/books/novel_bak"));
+        assert!(!excludes.is_excluded("/books/novel_bak/x"));
+    }
+}
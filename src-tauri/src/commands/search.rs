@@ -0,0 +1,155 @@
+//! 书库全局搜索
+//! 按空白拆分关键词，每个关键词都需在任一所选字段中出现（子串匹配，中文无需分词）。
+//! SQLite 的 LIKE 只对 ASCII 不区分大小写，因此在 Rust 侧对两边做 Unicode 小写折叠后匹配，
+//! 命中位置随结果返回，供前端高亮
+
+use crate::commands::book::{DbState, Error};
+use crate::models::{Book, LibrarySearchResult, SearchField, SearchHighlight};
+use sqlx::FromRow;
+
+/// 未指定字段时默认搜索的范围
+const DEFAULT_SEARCH_FIELDS: &[SearchField] = &[
+    SearchField::Title,
+    SearchField::Author,
+    SearchField::FilePath,
+    SearchField::Series,
+    SearchField::Group,
+];
+
+#[derive(FromRow)]
+struct SearchRow {
+    #[sqlx(flatten)]
+    book: Book,
+    group_name: Option<String>,
+}
+
+impl SearchRow {
+    fn field_value(&self, field: SearchField) -> Option<&str> {
+        match field {
+            SearchField::Title => Some(self.book.title.as_str()),
+            SearchField::Author => self.book.author.as_deref(),
+            SearchField::FilePath => Some(self.book.file_path.as_str()),
+            SearchField::Series => self.book.series.as_deref(),
+            SearchField::Group => self.group_name.as_deref(),
+        }
+    }
+}
+
+/// 搜索书库：fields 为空时搜索书名、作者、路径、系列和分组名；结果中书名命中的排在前面
+#[tauri::command]
+pub async fn search_library(
+    query: String,
+    fields: Vec<SearchField>,
+    db: DbState<'_>,
+) -> Result<Vec<LibrarySearchResult>, Error> {
+    let terms: Vec<String> = query.split_whitespace().map(str::to_string).collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let fields = if fields.is_empty() { DEFAULT_SEARCH_FIELDS.to_vec() } else { fields };
+    let fields: Vec<SearchField> = fields
+        .iter()
+        .enumerate()
+        .filter(|(i, field)| !fields[..*i].contains(field))
+        .map(|(_, &field)| field)
+        .collect();
+
+    let rows = {
+        let pool = db.lock().await;
+        sqlx::query_as::<_, SearchRow>(
+            "SELECT books.*, groups.name AS group_name FROM books LEFT JOIN groups ON groups.id = books.group_id \
+             ORDER BY books.last_read_time DESC NULLS LAST, books.created_at DESC",
+        )
+        .fetch_all(&*pool)
+        .await?
+    };
+
+    let mut results: Vec<LibrarySearchResult> = rows
+        .into_iter()
+        .filter(|row| {
+            terms.iter().all(|term| {
+                fields
+                    .iter()
+                    .filter_map(|&field| row.field_value(field))
+                    .any(|value| !find_matches(value, std::slice::from_ref(term)).is_empty())
+            })
+        })
+        .map(|row| {
+            let highlights = fields
+                .iter()
+                .flat_map(|&field| {
+                    row.field_value(field)
+                        .map(|value| {
+                            find_matches(value, &terms)
+                                .into_iter()
+                                .map(|(start, end)| SearchHighlight {
+                                    field,
+                                    value: value.to_string(),
+                                    start,
+                                    end,
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default()
+                })
+                .collect();
+            LibrarySearchResult {
+                book: row.book.with_progress_percent(),
+                group_name: row.group_name,
+                highlights,
+            }
+        })
+        .collect();
+
+    results.sort_by_key(|result| !result.highlights.iter().any(|h| h.field == SearchField::Title));
+    Ok(results)
+}
+
+/// 不区分大小写地查找所有关键词在 value 中的位置，重叠或相邻的区间合并，返回字符区间
+fn find_matches(value: &str, terms: &[String]) -> Vec<(usize, usize)> {
+    let fold = |s: &str| -> Vec<char> { s.chars().map(|c| c.to_lowercase().next().unwrap_or(c)).collect() };
+    let haystack = fold(value);
+
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for term in terms {
+        let needle = fold(term);
+        if needle.is_empty() || needle.len() > haystack.len() {
+            continue;
+        }
+        let mut i = 0;
+        while i + needle.len() <= haystack.len() {
+            if haystack[i..i + needle.len()] == needle[..] {
+                ranges.push((i, i + needle.len()));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_matches() {
+        let terms = vec!["rust".to_string(), "编程".to_string()];
+        assert_eq!(find_matches("Rust 编程之道：rust", &terms), vec![(0, 4), (5, 7), (10, 14)]);
+        assert_eq!(find_matches("abcabc", &["bc".to_string(), "ca".to_string()]), vec![(1, 6)]);
+        assert!(find_matches("三体", &terms).is_empty());
+        // 非 ASCII 字母同样不区分大小写
+        assert_eq!(find_matches("ÉMILE ZOLA", &["émile".to_string()]), vec![(0, 5)]);
+        assert_eq!(find_matches("Достоевский", &["ДОСТ".to_string()]), vec![(0, 4)]);
+    }
+}
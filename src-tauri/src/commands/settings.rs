@@ -0,0 +1,42 @@
+//! 应用设置表
+//! 以键值对保存需要在启动时读取的后端设置（如 PDF 缓存与渲染并发），值为 JSON 字符串
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::SqlitePool;
+
+pub(crate) const CREATE_SETTINGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS app_settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+)";
+
+/// 读取设置；表不存在、未保存过或解析失败时返回 None
+pub(crate) async fn load_setting<T: DeserializeOwned>(pool: &SqlitePool, key: &str) -> Option<T> {
+    let value: Option<String> = sqlx::query_scalar("SELECT value FROM app_settings WHERE key = ?")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .ok()
+        .flatten();
+    value.and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// 保存设置（覆盖旧值）
+pub(crate) async fn save_setting<T: Serialize>(pool: &SqlitePool, key: &str, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string(value).map_err(|e| format!("序列化设置失败: {}", e))?;
+    sqlx::query(CREATE_SETTINGS_TABLE)
+        .execute(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO app_settings (key, value, updated_at) VALUES (?, ?, strftime('%s', 'now'))
+         ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+    )
+    .bind(key)
+    .bind(json)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -46,10 +46,9 @@ pub async fn get_stats_summary(db: DbState<'_>) -> Result<StatsSummary, String>
         .await
         .map_err(|e| e.to_string())?;
 
-    // 已读完书籍数：status=1（手动标记）或 进度达到100%（current_page >= total_pages 且 total_pages > 1）
-    // 注：total_pages = 1 的书籍（如 Markdown/HTML）使用虚拟页，仅依赖 status 字段判断
+    // 已读完书籍数：status=1（手动标记，或翻到末页时由进度更新自动标记）
     let finished_count: (i64,) =
-    sqlx::query_as("SELECT COUNT(*) FROM books WHERE status = 1 OR (total_pages > 1 AND current_page >= total_pages)")            .fetch_one(&*pool)
+    sqlx::query_as("SELECT COUNT(*) FROM books WHERE status = 1")            .fetch_one(&*pool)
             .await
             .map_err(|e| e.to_string())?;
 
@@ -474,7 +473,7 @@ pub async fn mark_book_finished(book_id: i64, db: DbState<'_>) -> Result<(), Str
 
     let now = chrono::Local::now().timestamp();
     
-    sqlx::query("UPDATE books SET status = 1, finished_at = ? WHERE id = ?")
+    sqlx::query("UPDATE books SET status = 1, finished_at = ?, finished_dismissed = 0 WHERE id = ?")
         .bind(now)
         .bind(book_id)
         .execute(&*pool)
@@ -484,12 +483,12 @@ pub async fn mark_book_finished(book_id: i64, db: DbState<'_>) -> Result<(), Str
     Ok(())
 }
 
-/// 取消书籍已读完状态
+/// 取消书籍已读完状态；停留在末页时也不会被自动重新标记，直到离开末页
 #[tauri::command]
 pub async fn unmark_book_finished(book_id: i64, db: DbState<'_>) -> Result<(), String> {
     let pool = db.lock().await;
 
-    sqlx::query("UPDATE books SET status = 0, finished_at = NULL WHERE id = ?")
+    sqlx::query("UPDATE books SET status = 0, finished_at = NULL, finished_dismissed = 1 WHERE id = ?")
         .bind(book_id)
         .execute(&*pool)
         .await
@@ -0,0 +1,198 @@
+//! 阅读主题预设
+//! 全局保存字体、字号、行距、背景色、前景色等排版参数，设置内容为前端定义的 JSON 对象，后端原样存取；
+//! 同时根据背景/前景色推导 PDF 渲染使用的主题（`light` / `dark`），前端可直接作为 `RenderOptions.theme` 传入
+
+use crate::commands::book::DbState;
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::SqlitePool;
+
+pub(crate) const CREATE_THEMES_TABLE: &str = "CREATE TABLE IF NOT EXISTS reading_themes (
+    name TEXT PRIMARY KEY,
+    settings TEXT NOT NULL,
+    is_active INTEGER DEFAULT 0,
+    created_at INTEGER DEFAULT (strftime('%s', 'now')),
+    updated_at INTEGER DEFAULT (strftime('%s', 'now'))
+)";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadingTheme {
+    pub name: String,
+    pub settings: Value,
+    /// 由设置推导出的 PDF 渲染主题
+    pub pdf_theme: String,
+    pub active: bool,
+    pub updated_at: i64,
+}
+
+/// 解析 `#rgb`、`#rrggbb`、`#rrggbbaa`、`rgb(r, g, b)` / `rgba(...)` 颜色
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let value = value.trim().to_ascii_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        // 先排除非十六进制字符，多字节字符会让按字节切片越过字符边界
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 => {
+                let digits: Vec<u8> = hex.chars().map(|c| channel(&c.to_string())).collect::<Option<_>>()?;
+                Some([digits[0] * 17, digits[1] * 17, digits[2] * 17])
+            }
+            6 | 8 => Some([channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?]),
+            _ => None,
+        };
+    }
+    let inner = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let parts: Vec<u8> = inner
+        .split([',', ' ', '/'])
+        .filter(|p| !p.is_empty())
+        .take(3)
+        .map(|p| p.parse::<f32>().ok().map(|v| v.clamp(0.0, 255.0) as u8))
+        .collect::<Option<_>>()?;
+    (parts.len() == 3).then(|| [parts[0], parts[1], parts[2]])
+}
+
+/// 相对亮度（0~1）
+fn luminance([r, g, b]: [u8; 3]) -> f32 {
+    (0.2126 * r as f32 + 0.7152 * g as f32 + 0.0722 * b as f32) / 255.0
+}
+
+/// 推导 PDF 渲染主题：设置中显式的 `pdfTheme` 优先，否则背景比前景暗（或背景本身较暗）时使用 `dark`
+pub fn pdf_theme_for(settings: &Value) -> String {
+    if let Some(theme) = settings.get("pdfTheme").and_then(Value::as_str).filter(|t| !t.is_empty()) {
+        return theme.to_string();
+    }
+    let color = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| settings.get(*key).and_then(Value::as_str))
+            .and_then(parse_color)
+            .map(luminance)
+    };
+    let background = color(&["backgroundColor", "background", "bgColor"]);
+    let foreground = color(&["foregroundColor", "textColor", "color", "fgColor"]);
+    let dark = match (background, foreground) {
+        (Some(bg), Some(fg)) => bg < fg,
+        (Some(bg), None) => bg < 0.5,
+        _ => false,
+    };
+    if dark { "dark" } else { "light" }.to_string()
+}
+
+fn to_theme((name, settings, is_active, updated_at): (String, String, i64, i64)) -> ReadingTheme {
+    let settings: Value = serde_json::from_str(&settings).unwrap_or(Value::Null);
+    ReadingTheme {
+        name,
+        pdf_theme: pdf_theme_for(&settings),
+        settings,
+        active: is_active != 0,
+        updated_at,
+    }
+}
+
+async fn fetch_theme(pool: &SqlitePool, name: &str) -> Result<Option<ReadingTheme>, String> {
+    let row: Option<(String, String, i64, i64)> =
+        sqlx::query_as("SELECT name, settings, is_active, updated_at FROM reading_themes WHERE name = ?")
+            .bind(name)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(row.map(to_theme))
+}
+
+fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("主题名称不能为空".to_string());
+    }
+    Ok(name.to_string())
+}
+
+/// 保存主题预设（同名覆盖），`settings_json` 须为 JSON 对象
+#[tauri::command]
+pub async fn save_theme(name: String, settings_json: String, db: DbState<'_>) -> Result<ReadingTheme, String> {
+    let name = normalize_name(&name)?;
+    let settings: Value = serde_json::from_str(&settings_json).map_err(|e| format!("主题设置不是有效的 JSON: {}", e))?;
+    if !settings.is_object() {
+        return Err("主题设置必须是 JSON 对象".to_string());
+    }
+
+    let pool = db.lock().await;
+    sqlx::query(
+        "INSERT INTO reading_themes (name, settings) VALUES (?, ?)
+         ON CONFLICT(name) DO UPDATE SET settings = excluded.settings, updated_at = strftime('%s', 'now')",
+    )
+    .bind(&name)
+    .bind(settings.to_string())
+    .execute(&*pool)
+    .await
+    .map_err(|e| format!("保存主题失败: {}", e))?;
+
+    fetch_theme(&pool, &name).await?.ok_or_else(|| "保存主题失败".to_string())
+}
+
+/// 获取所有主题预设，按最近修改排序
+#[tauri::command]
+pub async fn get_themes(db: DbState<'_>) -> Result<Vec<ReadingTheme>, String> {
+    let pool = db.lock().await;
+    let rows: Vec<(String, String, i64, i64)> =
+        sqlx::query_as("SELECT name, settings, is_active, updated_at FROM reading_themes ORDER BY updated_at DESC, name")
+            .fetch_all(&*pool)
+            .await
+            .map_err(|e| e.to_string())?;
+    Ok(rows.into_iter().map(to_theme).collect())
+}
+
+/// 删除主题预设，返回是否存在；删除当前主题后不再有激活的主题
+#[tauri::command]
+pub async fn delete_theme(name: String, db: DbState<'_>) -> Result<bool, String> {
+    let pool = db.lock().await;
+    let result = sqlx::query("DELETE FROM reading_themes WHERE name = ?")
+        .bind(name.trim())
+        .execute(&*pool)
+        .await
+        .map_err(|e| format!("删除主题失败: {}", e))?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// 设为当前主题（同时只有一个激活的主题），返回该主题
+#[tauri::command]
+pub async fn set_active_theme(name: String, db: DbState<'_>) -> Result<ReadingTheme, String> {
+    let name = normalize_name(&name)?;
+    let pool = db.lock().await;
+    if fetch_theme(&pool, &name).await?.is_none() {
+        return Err(format!("主题不存在: {}", name));
+    }
+    sqlx::query("UPDATE reading_themes SET is_active = (name = ?)")
+        .bind(&name)
+        .execute(&*pool)
+        .await
+        .map_err(|e| format!("设置当前主题失败: {}", e))?;
+    println!("[Theme] 当前主题: {}", name);
+
+    fetch_theme(&pool, &name).await?.ok_or_else(|| format!("主题不存在: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_pdf_theme_for() {
+        assert_eq!(parse_color("#1e1e1e"), Some([30, 30, 30]));
+        assert_eq!(parse_color("#fff"), Some([255, 255, 255]));
+        assert_eq!(parse_color("rgb(244, 236, 216)"), Some([244, 236, 216]));
+        assert_eq!(parse_color("teal"), None);
+        assert_eq!(parse_color("#a深bc"), None);
+        assert_eq!(parse_color("#+f+f+f"), None);
+
+        assert_eq!(pdf_theme_for(&json!({"backgroundColor": "#121212", "textColor": "#d0d0d0"})), "dark");
+        assert_eq!(pdf_theme_for(&json!({"backgroundColor": "#f4ecd8", "textColor": "#5b4636"})), "light");
+        assert_eq!(pdf_theme_for(&json!({"backgroundColor": "#000", "pdfTheme": "dark_smart"})), "dark_smart");
+        assert_eq!(pdf_theme_for(&json!({"fontSize": 18})), "light");
+    }
+}
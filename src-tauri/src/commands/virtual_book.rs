@@ -0,0 +1,251 @@
+//! 虚拟书（多文件合并阅读）
+//! 书籍表中登记一条 `virtual://` 开头路径的记录，成员文件顺序保存在 `virtual_book_parts`；
+//! 打开时按顺序拼接各成员的章节为统一目录，章节索引与字符偏移均为全局值。目前只支持 TXT 文件合并
+
+use crate::commands::book::{DbState, Error};
+use crate::formats::txt::{MergedBookMeta, TxtChapterContent, TxtEngine, TxtFormatOptions};
+use crate::models::Book;
+use crate::txt_commands::load_cached_meta;
+use once_cell::sync::Lazy;
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+pub(crate) const CREATE_VIRTUAL_BOOK_PARTS_TABLE: &str = "CREATE TABLE IF NOT EXISTS virtual_book_parts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    book_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    file_path TEXT NOT NULL,
+    char_count INTEGER,
+    chapter_count INTEGER,
+    UNIQUE (book_id, position),
+    FOREIGN KEY (book_id) REFERENCES books(id) ON DELETE CASCADE
+)";
+
+/// 虚拟书在书籍表中的路径前缀，路径以 `.txt` 结尾以便前端按扩展名识别格式
+pub const VIRTUAL_BOOK_PREFIX: &str = "virtual://";
+
+/// 成员文件指纹：大小 + 修改时间，文件不存在时为 None
+type PartStamp = Option<(u64, Option<SystemTime>)>;
+
+/// 合并元数据缓存（打开时重建，加载章节时复用），成员文件指纹变化后失效
+static MERGED_META_CACHE: Lazy<Mutex<HashMap<i64, (Vec<PartStamp>, MergedBookMeta)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn part_stamp(path: &str) -> PartStamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()))
+}
+
+fn part_stamps(merged: &MergedBookMeta) -> Vec<PartStamp> {
+    merged.parts.iter().map(|part| part_stamp(&part.file_path)).collect()
+}
+
+/// 删除书籍时移除对应的合并元数据缓存
+pub(crate) fn evict_merged_meta(book_id: i64) {
+    if let Ok(mut cache) = MERGED_META_CACHE.lock() {
+        cache.remove(&book_id);
+    }
+}
+
+/// 是否为虚拟书路径
+pub fn is_virtual_book_path(path: &str) -> bool {
+    path.starts_with(VIRTUAL_BOOK_PREFIX)
+}
+
+fn is_txt_file(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("txt"))
+}
+
+/// 创建虚拟书：按 `file_paths` 的顺序记录成员文件，返回新建的书籍记录
+#[tauri::command]
+pub async fn create_virtual_book(title: String, file_paths: Vec<String>, db: DbState<'_>) -> Result<Book, Error> {
+    let title = title.trim().to_string();
+    if title.is_empty() {
+        return Err(Error::Message("书名不能为空".to_string()));
+    }
+    if file_paths.is_empty() {
+        return Err(Error::Message("至少需要一个成员文件".to_string()));
+    }
+    if let Some(path) = file_paths.iter().find(|p| !is_txt_file(p)) {
+        return Err(Error::Message(format!("目前只支持合并 TXT 文件: {}", path)));
+    }
+
+    let virtual_path = format!(
+        "{}{}/{}.txt",
+        VIRTUAL_BOOK_PREFIX,
+        chrono::Utc::now().timestamp_millis(),
+        title
+    );
+
+    let pool = db.lock().await;
+    let mut tx = pool.begin().await?;
+    let book_id = sqlx::query("INSERT INTO books (title, file_path, total_pages) VALUES (?, ?, ?)")
+        .bind(&title)
+        .bind(&virtual_path)
+        .bind(file_paths.len() as i64)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+    for (position, path) in file_paths.iter().enumerate() {
+        sqlx::query("INSERT INTO virtual_book_parts (book_id, position, file_path) VALUES (?, ?, ?)")
+            .bind(book_id)
+            .bind(position as i64)
+            .bind(path)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    println!("[virtual_book] 创建虚拟书: id={}, title={}, parts={}", book_id, title, file_paths.len());
+    let book = sqlx::query_as::<_, Book>("SELECT * FROM books WHERE id = ?")
+        .bind(book_id)
+        .fetch_one(&*pool)
+        .await?;
+    Ok(book)
+}
+
+/// 读取成员文件并拼接元数据（缺失或无法解析的成员跳过），结果写入缓存
+async fn load_merged_meta(book_id: i64, db: &tokio::sync::Mutex<SqlitePool>) -> Result<MergedBookMeta, Error> {
+    let (title, paths) = {
+        let pool = db.lock().await;
+        let title: Option<String> = sqlx::query_scalar("SELECT title FROM books WHERE id = ?")
+            .bind(book_id)
+            .fetch_optional(&*pool)
+            .await?;
+        let paths: Vec<(String, Option<i64>, Option<i64>)> = sqlx::query_as(
+            "SELECT file_path, char_count, chapter_count FROM virtual_book_parts WHERE book_id = ? ORDER BY position",
+        )
+        .bind(book_id)
+        .fetch_all(&*pool)
+        .await?;
+        (title, paths)
+    };
+    let Some(title) = title.filter(|_| !paths.is_empty()) else {
+        return Err(Error::Message(format!("虚拟书不存在: {}", book_id)));
+    };
+
+    let (stamps, merged) = tokio::task::spawn_blocking(move || {
+        let parts = paths
+            .into_iter()
+            .map(|(path, char_count, chapter_count)| {
+                let meta = if Path::new(&path).is_file() {
+                    load_cached_meta(&path, None)
+                        .inspect_err(|e| eprintln!("[virtual_book] 成员文件解析失败，已跳过: {}, {}", path, e))
+                        .ok()
+                } else {
+                    None
+                };
+                let known = (
+                    char_count.unwrap_or(0).max(0) as u64,
+                    chapter_count.unwrap_or(0).max(0) as u32,
+                );
+                (path, meta, known)
+            })
+            .collect();
+        let merged = MergedBookMeta::build(title, parts);
+        (part_stamps(&merged), merged)
+    })
+    .await
+    .map_err(|e| Error::Message(format!("合并元数据任务失败: {}", e)))?;
+
+    // 记录成功解析的成员字符数与章节数，成员之后缺失时据此预留字符范围与章节索引
+    {
+        let pool = db.lock().await;
+        for part in merged.parts.iter().filter(|part| !part.missing) {
+            sqlx::query(
+                "UPDATE virtual_book_parts SET char_count = ?, chapter_count = ? WHERE book_id = ? AND position = ?",
+            )
+            .bind((part.char_end - part.char_start) as i64)
+            .bind(part.chapter_count as i64)
+            .bind(book_id)
+            .bind(part.position as i64)
+            .execute(&*pool)
+            .await?;
+        }
+    }
+
+    if !merged.missing_files.is_empty() {
+        println!(
+            "[virtual_book] 成员文件缺失，已跳过: id={}, missing={:?}",
+            book_id, merged.missing_files
+        );
+    }
+    if merged.parts.iter().all(|part| part.missing) {
+        return Err(Error::Message("虚拟书的成员文件均不存在或无法读取".to_string()));
+    }
+    MERGED_META_CACHE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(book_id, (stamps, merged.clone()));
+    Ok(merged)
+}
+
+/// 取缓存的合并元数据，未缓存或成员文件变化时重新拼接；供书签等按全局偏移定位的功能复用
+pub(crate) async fn cached_merged_meta(
+    book_id: i64,
+    db: &tokio::sync::Mutex<SqlitePool>,
+) -> Result<MergedBookMeta, Error> {
+    let cached = MERGED_META_CACHE.lock().map_err(|e| e.to_string())?.get(&book_id).cloned();
+    match cached {
+        Some((stamps, merged)) if stamps == part_stamps(&merged) => Ok(merged),
+        _ => load_merged_meta(book_id, db).await,
+    }
+}
+
+/// 打开虚拟书：按成员顺序拼接章节，返回统一目录与全局偏移；缺失的成员文件列在 `missing_files` 中供前端提示
+#[tauri::command]
+pub async fn virtual_book_load_metadata(book_id: i64, db: DbState<'_>) -> Result<MergedBookMeta, Error> {
+    load_merged_meta(book_id, &db).await
+}
+
+/// 按全局章节索引加载虚拟书章节，返回内容的索引与字符偏移为全局值
+/// `format` 为排版选项，含义同 `txt_load_chapter`
+#[tauri::command]
+pub async fn virtual_book_load_chapter(
+    book_id: i64,
+    chapter_index: u32,
+    extra_chapters: Option<Vec<u32>>,
+    format: Option<TxtFormatOptions>,
+    db: DbState<'_>,
+) -> Result<Vec<TxtChapterContent>, Error> {
+    let merged = cached_merged_meta(book_id, &db).await?;
+
+    let mut indices = vec![chapter_index];
+    for idx in extra_chapters.unwrap_or_default() {
+        if !indices.contains(&idx) {
+            indices.push(idx);
+        }
+    }
+
+    let chapters = tokio::task::spawn_blocking(move || -> Result<Vec<TxtChapterContent>, String> {
+        let mut chapters = Vec::with_capacity(indices.len());
+        for idx in indices {
+            let Some((part, local_index)) = merged.resolve_chapter(idx) else {
+                println!(
+                    "[virtual_book] 忽略越界或成员缺失的章节索引: index={}, total_chapters={}",
+                    idx,
+                    merged.chapters.len()
+                );
+                continue;
+            };
+            let meta = load_cached_meta(&part.file_path, None)?;
+            let mut chapter = TxtEngine::load_chapter(&part.file_path, local_index, &meta, None)
+                .map_err(|e| e.to_string())?;
+            if let Some(options) = &format {
+                chapter.apply_format(options);
+            }
+            chapters.push(merged.to_global(part, chapter));
+        }
+        Ok(chapters)
+    })
+    .await
+    .map_err(|e| Error::Message(format!("加载章节任务失败: {}", e)))??;
+
+    Ok(chapters)
+}
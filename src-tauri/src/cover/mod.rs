@@ -1,9 +1,13 @@
 //! 封面存储模块
 //! 负责将书籍封面以文件形式存储到磁盘
 
+use crate::formats::BookFormat;
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
 use base64::{engine::general_purpose::STANDARD, Engine};
+use once_cell::sync::Lazy;
 use sha2::{Digest, Sha256};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 use tokio::fs;
 
@@ -147,7 +151,7 @@ pub fn get_cover_full_path(app_handle: &AppHandle, relative_path: &str) -> PathB
     cover_root(app_handle).join(relative_path)
 }
 
-/// 删除封面文件
+/// 删除封面文件（连同缩略图）
 pub async fn delete_cover_file(app_handle: &AppHandle, relative_path: &str) -> Result<(), String> {
     let full_path = cover_root(app_handle).join(relative_path);
     if full_path.exists() {
@@ -155,16 +159,185 @@ pub async fn delete_cover_file(app_handle: &AppHandle, relative_path: &str) -> R
             .await
             .map_err(|e| format!("Failed to delete cover file: {}", e))?;
     }
+    if !relative_path.ends_with(THUMBNAIL_SUFFIX) {
+        let _ = fs::remove_file(cover_root(app_handle).join(thumbnail_relative_path(relative_path))).await;
+    }
     Ok(())
 }
 
+/// 列出封面目录下的书籍封面文件（相对路径），不包含分组拼贴封面
+pub async fn list_book_cover_files(app_handle: &AppHandle) -> Vec<String> {
+    let root = cover_root(app_handle);
+    let Ok(mut subdirs) = fs::read_dir(&root).await else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    while let Ok(Some(subdir)) = subdirs.next_entry().await {
+        let subdir_name = subdir.file_name().to_string_lossy().to_string();
+        if subdir_name == GROUP_COVER_SUBDIR || !subdir.path().is_dir() {
+            continue;
+        }
+        let Ok(mut entries) = fs::read_dir(subdir.path()).await else {
+            continue;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.path().is_file() {
+                files.push(format!("{}/{}", subdir_name, entry.file_name().to_string_lossy()));
+            }
+        }
+    }
+    files
+}
+
+/// 找出没有被任何书籍引用的封面文件；引用路径中的反斜杠按正斜杠比较
+/// 缩略图跟随原图：原图仍被引用时保留对应的 `_thumb.jpg`
+pub fn find_orphan_covers<'a, I>(files: &[String], referenced: I) -> Vec<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let referenced: HashSet<String> = referenced
+        .into_iter()
+        .filter(|cover| is_file_path(cover))
+        .map(|cover| cover.replace('\\', "/"))
+        .flat_map(|cover| [thumbnail_relative_path(&cover), cover])
+        .collect();
+    files
+        .iter()
+        .filter(|file| !referenced.contains(file.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// 默认缩略图比例（宽:高 = 2:3）与宽度
+pub const DEFAULT_THUMBNAIL_RATIO: f32 = 2.0 / 3.0;
+pub const DEFAULT_THUMBNAIL_WIDTH: u32 = 300;
+
+/// 缩略图宽度允许范围
+const THUMBNAIL_MIN_WIDTH: u32 = 32;
+const THUMBNAIL_MAX_WIDTH: u32 = 1200;
+
+/// 缩略图比例（宽/高）允许范围，避免极端比例生成超大图片
+const THUMBNAIL_MIN_RATIO: f32 = 0.25;
+const THUMBNAIL_MAX_RATIO: f32 = 4.0;
+
+/// 缩略图文件名后缀
+const THUMBNAIL_SUFFIX: &str = "_thumb.jpg";
+
+/// 封面对应的缩略图相对路径
+/// 返回格式如：epub/a1b2c3d4e5f6_thumb.jpg
+pub fn thumbnail_relative_path(relative_path: &str) -> String {
+    let stem = match relative_path.rfind('.') {
+        Some(dot) if !relative_path[dot..].contains('/') => &relative_path[..dot],
+        _ => relative_path,
+    };
+    format!("{}{}", stem, THUMBNAIL_SUFFIX)
+}
+
+/// 按目标比例（宽/高）中心裁剪后缩放到目标宽度，编码为 JPG
+pub fn render_cover_thumbnail(image_bytes: &[u8], target_ratio: f32, target_width: u32) -> Result<Vec<u8>, String> {
+    use image::imageops::FilterType;
+
+    if !(target_ratio.is_finite() && target_ratio > 0.0) {
+        return Err(format!("Invalid thumbnail ratio: {}", target_ratio));
+    }
+    let target_ratio = target_ratio.clamp(THUMBNAIL_MIN_RATIO, THUMBNAIL_MAX_RATIO);
+    let img = image::load_from_memory(image_bytes).map_err(|e| format!("Failed to decode cover: {}", e))?;
+    let (width, height) = (img.width(), img.height());
+    if width == 0 || height == 0 {
+        return Err("Cover image is empty".to_string());
+    }
+
+    // 原图比目标更宽时裁左右，更高时裁上下
+    let (crop_width, crop_height) = if width as f32 / height as f32 > target_ratio {
+        (((height as f32 * target_ratio).round() as u32).clamp(1, width), height)
+    } else {
+        (width, ((width as f32 / target_ratio).round() as u32).clamp(1, height))
+    };
+    let cropped = img.crop_imm((width - crop_width) / 2, (height - crop_height) / 2, crop_width, crop_height);
+
+    let target_width = target_width.clamp(THUMBNAIL_MIN_WIDTH, THUMBNAIL_MAX_WIDTH);
+    let target_height = ((target_width as f32 / target_ratio).round() as u32).max(1);
+    let thumbnail = cropped
+        .resize_exact(target_width, target_height, FilterType::Triangle)
+        .to_rgb8();
+
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, 85)
+        .encode(thumbnail.as_raw(), target_width, target_height, image::ColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode thumbnail: {}", e))?;
+    Ok(buffer)
+}
+
+/// 规范化封面相对路径：解析 `..` 与符号链接后必须仍位于封面目录内，返回以 `/` 分隔的相对路径
+async fn canonical_cover_path(root: &Path, relative_path: &str) -> Result<String, String> {
+    let root = fs::canonicalize(root)
+        .await
+        .map_err(|e| format!("Failed to resolve cover directory: {}", e))?;
+    let path = fs::canonicalize(root.join(relative_path))
+        .await
+        .map_err(|e| format!("Failed to resolve cover file: {}", e))?;
+    let relative = path
+        .strip_prefix(&root)
+        .map_err(|_| format!("Cover path is outside the cover directory: {}", relative_path))?;
+    Ok(relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+/// 为封面目录中的封面生成统一比例缩略图（已存在时覆盖），返回缩略图相对路径
+pub async fn make_cover_thumbnail(
+    app_handle: &AppHandle,
+    relative_path: &str,
+    target_ratio: f32,
+    target_width: u32,
+) -> Result<String, String> {
+    let root = cover_root(app_handle);
+    let relative_path = canonical_cover_path(&root, relative_path).await?;
+    let image_bytes = fs::read(root.join(&relative_path))
+        .await
+        .map_err(|e| format!("Failed to read cover file: {}", e))?;
+    let thumbnail = tokio::task::spawn_blocking(move || render_cover_thumbnail(&image_bytes, target_ratio, target_width))
+        .await
+        .map_err(|e| format!("Thumbnail task failed: {}", e))??;
+
+    let thumb_path = thumbnail_relative_path(&relative_path);
+    fs::write(root.join(&thumb_path), &thumbnail)
+        .await
+        .map_err(|e| format!("Failed to write thumbnail file: {}", e))?;
+    Ok(thumb_path)
+}
+
+/// 保存封面后顺带生成默认缩略图；失败只记录日志，不影响封面本身
+async fn make_default_thumbnail(app_handle: &AppHandle, relative_path: &str) {
+    if let Err(e) =
+        make_cover_thumbnail(app_handle, relative_path, DEFAULT_THUMBNAIL_RATIO, DEFAULT_THUMBNAIL_WIDTH).await
+    {
+        eprintln!("[cover] Failed to generate thumbnail for {}: {}", relative_path, e);
+    }
+}
+
 /// 处理封面数据：如果是 Base64 则保存为文件并返回路径，否则直接返回
+/// 文本类书籍（TXT/Markdown/HTML）没有封面时按书名自动生成一张；title 为空时使用文件名
 pub async fn process_cover_for_storage(
     app_handle: &AppHandle,
     file_path: &str,
     cover_data: Option<&str>,
+    title: Option<&str>,
 ) -> Result<Option<String>, String> {
     match cover_data {
+        None | Some("") if is_text_format(file_path) => {
+            let title = title
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .unwrap_or_else(|| file_stem_title(file_path));
+            let relative_path = generate_text_cover(app_handle, file_path, &title, None).await?;
+            make_default_thumbnail(app_handle, &relative_path).await;
+            Ok(Some(relative_path))
+        }
         None => Ok(None),
         Some(data) if data.is_empty() => Ok(None),
         Some(data) => {
@@ -175,6 +348,7 @@ pub async fn process_cover_for_storage(
             
             // Base64 或 data URL，保存为文件
             let relative_path = save_cover_from_base64(app_handle, file_path, data).await?;
+            make_default_thumbnail(app_handle, &relative_path).await;
             Ok(Some(relative_path))
         }
     }
@@ -215,6 +389,369 @@ pub fn get_book_format(file_path: &str) -> &'static str {
     }
 }
 
+/// 分组拼贴封面子目录
+const GROUP_COVER_SUBDIR: &str = "groups";
+
+/// 拼贴中单本封面的尺寸（2:3 书封比例）
+const COLLAGE_TILE_WIDTH: u32 = 200;
+const COLLAGE_TILE_HEIGHT: u32 = 300;
+
+/// 拼贴格子间距及背景色
+const COLLAGE_GAP: u32 = 6;
+const COLLAGE_BACKGROUND: [u8; 3] = [236, 236, 236];
+
+/// 封面缺失时的占位色块
+const PLACEHOLDER_COLORS: [[u8; 3]; 4] = [
+    [176, 190, 197],
+    [188, 170, 164],
+    [165, 184, 170],
+    [179, 174, 200],
+];
+
+/// 拼贴最多使用的封面数量
+pub const GROUP_COLLAGE_MAX_BOOKS: usize = 4;
+
+/// 分组拼贴封面的相对路径，文件名带上组内书籍签名，书籍或封面变化时自动换新文件
+/// 返回格式如：groups/3_a1b2c3d4e5f6a7b8.jpg
+pub fn group_cover_relative_path(group_id: i64, signature: &str) -> String {
+    format!("{}/{}_{}.jpg", GROUP_COVER_SUBDIR, group_id, compute_path_hash(signature))
+}
+
+/// 读取封面原始字节：文件路径从封面目录读取，Base64/data URL 直接解码
+pub async fn load_cover_bytes(app_handle: &AppHandle, cover_image: &str) -> Option<Vec<u8>> {
+    if cover_image.is_empty() {
+        return None;
+    }
+    if is_file_path(cover_image) {
+        fs::read(cover_root(app_handle).join(cover_image)).await.ok()
+    } else {
+        extract_image_data(cover_image).ok()
+    }
+}
+
+/// 将 1-4 张封面合成为 2x2 拼贴 JPG；无法解码的封面以纯色块占位，空余格子留白
+pub fn compose_group_collage(covers: &[Option<Vec<u8>>]) -> Result<Vec<u8>, String> {
+    use image::imageops::{self, FilterType};
+    use image::{Rgb, RgbImage};
+
+    let width = COLLAGE_TILE_WIDTH * 2 + COLLAGE_GAP * 3;
+    let height = COLLAGE_TILE_HEIGHT * 2 + COLLAGE_GAP * 3;
+    let mut canvas = RgbImage::from_pixel(width, height, Rgb(COLLAGE_BACKGROUND));
+
+    for (index, cover) in covers.iter().take(GROUP_COLLAGE_MAX_BOOKS).enumerate() {
+        let tile = cover
+            .as_deref()
+            .and_then(|bytes| image::load_from_memory(bytes).ok())
+            .map(|img| {
+                img.resize_to_fill(COLLAGE_TILE_WIDTH, COLLAGE_TILE_HEIGHT, FilterType::Triangle)
+                    .to_rgb8()
+            })
+            .unwrap_or_else(|| {
+                RgbImage::from_pixel(
+                    COLLAGE_TILE_WIDTH,
+                    COLLAGE_TILE_HEIGHT,
+                    Rgb(PLACEHOLDER_COLORS[index % PLACEHOLDER_COLORS.len()]),
+                )
+            });
+
+        let (col, row) = ((index % 2) as u32, (index / 2) as u32);
+        let x = COLLAGE_GAP + col * (COLLAGE_TILE_WIDTH + COLLAGE_GAP);
+        let y = COLLAGE_GAP + row * (COLLAGE_TILE_HEIGHT + COLLAGE_GAP);
+        imageops::replace(&mut canvas, &tile, x as i64, y as i64);
+    }
+
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, 85)
+        .encode(canvas.as_raw(), width, height, image::ColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode collage: {}", e))?;
+    Ok(buffer)
+}
+
+/// 删除同一分组的旧拼贴封面（保留 keep 指定的文件）
+pub async fn remove_stale_group_covers(app_handle: &AppHandle, group_id: i64, keep: Option<&str>) {
+    let dir = cover_root(app_handle).join(GROUP_COVER_SUBDIR);
+    let Ok(mut entries) = fs::read_dir(&dir).await else {
+        return;
+    };
+    let prefix = format!("{}_", group_id);
+    let keep_name = keep.and_then(|path| path.rsplit('/').next());
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix) && Some(name.as_str()) != keep_name {
+            let _ = fs::remove_file(entry.path()).await;
+        }
+    }
+}
+
+/// 文本封面尺寸（2:3 书封比例）
+const TEXT_COVER_WIDTH: u32 = 600;
+const TEXT_COVER_HEIGHT: u32 = 900;
+
+/// 文本封面背景色，按书名哈希选取
+const TEXT_COVER_PALETTE: [[u8; 3]; 8] = [
+    [69, 90, 100],
+    [93, 64, 55],
+    [56, 94, 76],
+    [74, 64, 110],
+    [121, 68, 59],
+    [40, 83, 107],
+    [110, 84, 40],
+    [96, 56, 86],
+];
+
+/// 文本封面前景色
+const TEXT_COVER_FOREGROUND: [u8; 3] = [250, 247, 240];
+
+/// 书名字号范围（像素），放不下时逐级缩小
+const TEXT_COVER_TITLE_MAX_SIZE: f32 = 64.0;
+const TEXT_COVER_TITLE_MIN_SIZE: f32 = 36.0;
+/// 书名最多显示的行数，超出部分以省略号结尾
+const TEXT_COVER_TITLE_MAX_LINES: usize = 5;
+const TEXT_COVER_AUTHOR_SIZE: f32 = 30.0;
+
+/// 文本封面字体候选：优先覆盖中文的系统字体，依次为 Android、Windows、macOS、Linux
+const TEXT_COVER_FONT_CANDIDATES: &[&str] = &[
+    "/system/fonts/NotoSansCJK-Regular.ttc",
+    "/system/fonts/NotoSerifCJK-Regular.ttc",
+    "/system/fonts/DroidSansFallback.ttf",
+    "/system/fonts/DroidSansFallbackFull.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Medium.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-zenhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+];
+
+/// 字体能渲染常用汉字时才可用，否则中文标题会变成豆腐块；ttc 取第一个字体
+fn load_cjk_font(data: Vec<u8>) -> Option<FontVec> {
+    FontVec::try_from_vec_and_index(data, 0)
+        .ok()
+        .filter(|font| font.glyph_id('书').0 != 0)
+}
+
+/// 进程内只加载一次的封面字体；找不到可用的系统中文字体时为 None，封面只绘制色块与装饰线
+static TEXT_COVER_FONT: Lazy<Option<FontVec>> = Lazy::new(|| {
+    for path in TEXT_COVER_FONT_CANDIDATES {
+        let Some(font) = std::fs::read(path).ok().and_then(load_cjk_font) else {
+            continue;
+        };
+        println!("[cover] Text cover font: {}", path);
+        return Some(font);
+    }
+    eprintln!("[cover] No CJK font found, text covers will be drawn without title");
+    None
+});
+
+/// 文本封面使用的中文字体，也供其他需要绘制提示文字的位图（如 PDF 渲染失败占位图）复用
+pub(crate) fn cjk_font() -> Option<&'static FontVec> {
+    TEXT_COVER_FONT.as_ref()
+}
+
+/// 是否为没有内嵌封面的文本类格式
+pub fn is_text_format(file_path: &str) -> bool {
+    matches!(
+        BookFormat::from_path(file_path),
+        Some(BookFormat::Txt | BookFormat::Markdown | BookFormat::Html)
+    )
+}
+
+/// 用文件名（不含扩展名）作为书名
+fn file_stem_title(file_path: &str) -> String {
+    std::path::Path::new(file_path)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 为文本类书籍生成封面并保存到封面目录，返回相对路径
+pub async fn generate_text_cover(
+    app_handle: &AppHandle,
+    file_path: &str,
+    title: &str,
+    author: Option<&str>,
+) -> Result<String, String> {
+    let title = title.to_string();
+    let author = author.map(str::to_string);
+    let image_bytes = tokio::task::spawn_blocking(move || {
+        render_text_cover(&title, author.as_deref(), TEXT_COVER_FONT.as_ref())
+    })
+    .await
+    .map_err(|e| format!("Failed to render text cover: {}", e))??;
+
+    let relative_path = generate_cover_relative_path(file_path);
+    let full_path = cover_root(app_handle).join(&relative_path);
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create cover directory: {}", e))?;
+    }
+    fs::write(&full_path, &image_bytes)
+        .await
+        .map_err(|e| format!("Failed to write cover file: {}", e))?;
+
+    Ok(relative_path)
+}
+
+/// 根据书名哈希选取背景色，同名书籍颜色稳定
+fn text_cover_background(title: &str) -> [u8; 3] {
+    let digest = Sha256::digest(title.as_bytes());
+    TEXT_COVER_PALETTE[digest[0] as usize % TEXT_COVER_PALETTE.len()]
+}
+
+/// 绘制文本封面 JPG：纯色背景、内边框、居中书名、底部作者
+/// font 为 None 时只绘制背景与装饰
+pub fn render_text_cover(title: &str, author: Option<&str>, font: Option<&FontVec>) -> Result<Vec<u8>, String> {
+    use image::{Rgb, RgbImage};
+
+    let background = text_cover_background(title);
+    let mut canvas = RgbImage::from_pixel(TEXT_COVER_WIDTH, TEXT_COVER_HEIGHT, Rgb(background));
+
+    // 内边框与书名下方的分隔线
+    let frame = blend(background, TEXT_COVER_FOREGROUND, 0.35);
+    let inset = 28;
+    for x in inset..TEXT_COVER_WIDTH - inset {
+        for t in 0..2 {
+            canvas.put_pixel(x, inset + t, Rgb(frame));
+            canvas.put_pixel(x, TEXT_COVER_HEIGHT - inset - 1 - t, Rgb(frame));
+        }
+    }
+    for y in inset..TEXT_COVER_HEIGHT - inset {
+        for t in 0..2 {
+            canvas.put_pixel(inset + t, y, Rgb(frame));
+            canvas.put_pixel(TEXT_COVER_WIDTH - inset - 1 - t, y, Rgb(frame));
+        }
+    }
+    let rule_y = TEXT_COVER_HEIGHT * 2 / 3;
+    for x in TEXT_COVER_WIDTH / 2 - 60..TEXT_COVER_WIDTH / 2 + 60 {
+        canvas.put_pixel(x, rule_y, Rgb(frame));
+        canvas.put_pixel(x, rule_y + 1, Rgb(frame));
+    }
+
+    if let Some(font) = font {
+        let max_width = (TEXT_COVER_WIDTH - inset * 2 - 64) as f32;
+
+        // 书名：从大字号开始尝试，放不下时缩小，最小字号仍放不下则截断
+        let mut size = TEXT_COVER_TITLE_MAX_SIZE;
+        let mut lines = wrap_text(font, title, size, max_width);
+        while lines.len() > TEXT_COVER_TITLE_MAX_LINES && size > TEXT_COVER_TITLE_MIN_SIZE {
+            size = (size - 4.0).max(TEXT_COVER_TITLE_MIN_SIZE);
+            lines = wrap_text(font, title, size, max_width);
+        }
+        if lines.len() > TEXT_COVER_TITLE_MAX_LINES {
+            lines.truncate(TEXT_COVER_TITLE_MAX_LINES);
+            if let Some(last) = lines.last_mut() {
+                last.pop();
+                last.push('…');
+            }
+        }
+
+        // 书名块在分隔线上方的区域内垂直居中
+        let line_height = size * 1.3;
+        let block_height = line_height * lines.len() as f32;
+        let mut baseline = (rule_y as f32 - block_height) / 2.0 + size;
+        for line in &lines {
+            draw_text_line(&mut canvas, font, line, size, baseline, TEXT_COVER_FOREGROUND);
+            baseline += line_height;
+        }
+
+        if let Some(author) = author.map(str::trim).filter(|a| !a.is_empty()) {
+            let author_lines = wrap_text(font, author, TEXT_COVER_AUTHOR_SIZE, max_width);
+            if let Some(line) = author_lines.first() {
+                let baseline = rule_y as f32 + 40.0 + TEXT_COVER_AUTHOR_SIZE;
+                draw_text_line(&mut canvas, font, line, TEXT_COVER_AUTHOR_SIZE, baseline, author_text_color(background));
+            }
+        }
+    }
+
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, 85)
+        .encode(canvas.as_raw(), TEXT_COVER_WIDTH, TEXT_COVER_HEIGHT, image::ColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode text cover: {}", e))?;
+    Ok(buffer)
+}
+
+/// 作者行使用略暗于书名的颜色
+fn author_text_color(background: [u8; 3]) -> [u8; 3] {
+    blend(background, TEXT_COVER_FOREGROUND, 0.8)
+}
+
+fn blend(from: [u8; 3], to: [u8; 3], alpha: f32) -> [u8; 3] {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * alpha).round() as u8;
+    [mix(from[0], to[0]), mix(from[1], to[1]), mix(from[2], to[2])]
+}
+
+/// 按宽度折行：中文逐字断行，西文尽量在空格处断开；字体缺字的字符直接跳过
+fn wrap_text(font: &FontVec, text: &str, size: f32, max_width: f32) -> Vec<String> {
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut width = 0.0;
+
+    for c in text.split_whitespace().collect::<Vec<_>>().join(" ").chars() {
+        if c != ' ' && font.glyph_id(c).0 == 0 {
+            continue;
+        }
+        let advance = scaled.h_advance(font.glyph_id(c));
+        if width + advance > max_width && !current.is_empty() {
+            // 西文单词整体移到下一行
+            let carry = match current.rfind(' ') {
+                Some(pos) if c != ' ' && current[pos + 1..].chars().all(|ch| ch.is_ascii_alphanumeric()) => {
+                    let word = current[pos + 1..].to_string();
+                    current.truncate(pos);
+                    word
+                }
+                _ => String::new(),
+            };
+            lines.push(current.trim_end().to_string());
+            current = carry;
+            width = current.chars().map(|ch| scaled.h_advance(font.glyph_id(ch))).sum();
+            if c == ' ' {
+                continue;
+            }
+        }
+        current.push(c);
+        width += advance;
+    }
+    if !current.trim().is_empty() {
+        lines.push(current.trim_end().to_string());
+    }
+    lines
+}
+
+/// 在给定基线上水平居中绘制一行文字，按覆盖率与背景混合
+pub(crate) fn draw_text_line(canvas: &mut image::RgbImage, font: &FontVec, line: &str, size: f32, baseline: f32, color: [u8; 3]) {
+    let scale = PxScale::from(size);
+    let scaled = font.as_scaled(scale);
+    let line_width: f32 = line.chars().map(|c| scaled.h_advance(font.glyph_id(c))).sum();
+    let mut x = (canvas.width() as f32 - line_width) / 2.0;
+
+    for c in line.chars() {
+        let glyph_id = font.glyph_id(c);
+        let glyph = glyph_id.with_scale_and_position(scale, point(x, baseline));
+        x += scaled.h_advance(glyph_id);
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= canvas.width() as i32 || py >= canvas.height() as i32 {
+                return;
+            }
+            let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+            pixel.0 = blend(pixel.0, color, coverage.min(1.0));
+        });
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,4 +779,59 @@ mod tests {
         let path2 = generate_cover_relative_path("/path/to/book.pdf");
         assert!(path2.starts_with("pdf/"));
     }
+
+    #[test]
+    fn test_find_orphan_covers() {
+        let files = vec!["epub/a.jpg".to_string(), "pdf/b.jpg".to_string(), "txt/c.jpg".to_string()];
+        let long_base64 = "A".repeat(300);
+        let referenced = ["epub/a.jpg", "pdf\\b.jpg", "data:image/png;base64,xx", long_base64.as_str()];
+        assert_eq!(find_orphan_covers(&files, referenced), vec!["txt/c.jpg".to_string()]);
+    }
+
+    #[test]
+    fn test_render_cover_thumbnail() {
+        use image::ImageEncoder;
+
+        assert_eq!(thumbnail_relative_path("epub/abc.jpg"), "epub/abc_thumb.jpg");
+        assert_eq!(thumbnail_relative_path("v1.2/abc"), "v1.2/abc_thumb.jpg");
+        let files = vec!["epub/a.jpg".to_string(), "epub/a_thumb.jpg".to_string(), "epub/b_thumb.jpg".to_string()];
+        assert_eq!(find_orphan_covers(&files, ["epub/a.jpg"]), vec!["epub/b_thumb.jpg".to_string()]);
+
+        // 横向原图裁成 2:3 竖向缩略图
+        let wide = image::RgbImage::from_pixel(400, 200, image::Rgb([200, 100, 50]));
+        let mut source = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut source)
+            .write_image(wide.as_raw(), 400, 200, image::ColorType::Rgb8)
+            .unwrap();
+        let data = render_cover_thumbnail(&source, DEFAULT_THUMBNAIL_RATIO, 120).unwrap();
+        let img = image::load_from_memory(&data).unwrap();
+        assert_eq!((img.width(), img.height()), (120, 180));
+        assert!(render_cover_thumbnail(&source, 0.0, 120).is_err());
+
+        // 极端比例收敛到 4:1
+        let data = render_cover_thumbnail(&source, 100.0, 120).unwrap();
+        let img = image::load_from_memory(&data).unwrap();
+        assert_eq!((img.width(), img.height()), (120, 30));
+    }
+
+    #[test]
+    fn test_compose_group_collage_with_placeholders() {
+        let data = compose_group_collage(&[None, Some(vec![1, 2, 3])]).unwrap();
+        let img = image::load_from_memory(&data).unwrap();
+        assert_eq!(img.width(), COLLAGE_TILE_WIDTH * 2 + COLLAGE_GAP * 3);
+        assert_eq!(img.height(), COLLAGE_TILE_HEIGHT * 2 + COLLAGE_GAP * 3);
+    }
+
+    #[test]
+    fn test_render_text_cover_without_font() {
+        assert!(is_text_format("/books/novel.TXT"));
+        assert!(is_text_format("/books/notes.md"));
+        assert!(!is_text_format("/books/book.epub"));
+        assert_eq!(file_stem_title("/books/三体.txt"), "三体");
+        assert_eq!(text_cover_background("三体"), text_cover_background("三体"));
+
+        let data = render_text_cover("三体", Some("刘慈欣"), None).unwrap();
+        let img = image::load_from_memory(&data).unwrap();
+        assert_eq!((img.width(), img.height()), (TEXT_COVER_WIDTH, TEXT_COVER_HEIGHT));
+    }
 }
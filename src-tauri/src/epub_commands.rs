@@ -1,7 +1,12 @@
 use crate::formats::epub::{
-    BookInfo, EpubCacheManager, EpubInspectResult, MetadataCacheEntry, SectionCacheData, TocItem,
+    prepare_book, BookInfo, EpubCacheManager, EpubFixedLayout, EpubInspectResult, EpubPreparedBook,
+    MetadataCacheEntry, SectionCacheData, TocItem,
 };
-use crate::formats::epub::engine::{inspect_epub, prepare_book, EpubPreparedBook};
+use crate::formats::epub::engine::inspect_epub;
+use crate::prefetch_commands::{invalidate_prefetch, take_prefetched_section};
+use crate::formats::pagination::{paginate_html, SectionPagination, TypographyOptions};
+use crate::formats::text_offset::{locate_text_offset, SectionTextAnchor};
+use crate::resource_protocol::rewrite_resource_placeholders;
 use serde::Serialize;
 use serde_json::Value;
 use std::sync::Arc;
@@ -18,6 +23,8 @@ pub struct EpubPrepareResult {
     pub toc: Vec<TocItem>,
     pub section_count: u32,
     pub spine: Vec<String>,
+    /// 固定布局书籍的每页视口尺寸与整页图片，流式排版书籍为 None
+    pub fixed_layout: Option<EpubFixedLayout>,
 }
 
 /// 保存章节缓存到磁盘（包含完整的样式和资源引用信息）
@@ -30,6 +37,7 @@ pub async fn epub_save_section(
     resource_refs: Vec<String>,
     state: State<'_, EpubCacheState>,
 ) -> Result<bool, String> {
+    invalidate_prefetch(&book_id);
     let manager = state.lock().await;
     match manager
         .save_section(&book_id, section_index, &html_content, styles, resource_refs)
@@ -47,20 +55,88 @@ pub async fn epub_save_section(
 }
 
 /// 从磁盘加载章节缓存（返回完整的 HTML、样式和资源引用）
+/// `resource_urls` 为 true 时将资源占位符改写为 `goread-res` 协议地址，由 WebView 按需加载
 #[tauri::command]
 pub async fn epub_load_section(
     book_id: String,
     section_index: u32,
+    resource_urls: Option<bool>,
     state: State<'_, EpubCacheState>,
 ) -> Result<Option<SectionCacheData>, String> {
+    let section = match take_prefetched_section(&book_id, section_index) {
+        Some(section) => Some(section),
+        None => {
+            let manager = state.lock().await;
+            manager.load_section(&book_id, section_index).await.map_err(|e| {
+                eprintln!(
+                    "[EPUB缓存] 加载章节失败: book_id={}, section_index={}, error={}",
+                    book_id, section_index, e
+                );
+                e
+            })?
+        }
+    };
+
+    if !resource_urls.unwrap_or(false) {
+        return Ok(section);
+    }
+    Ok(section.map(|mut data| {
+        data.html = rewrite_resource_placeholders(&data.html, &book_id);
+        data.styles = data
+            .styles
+            .iter()
+            .map(|css| rewrite_resource_placeholders(css, &book_id))
+            .collect();
+        data
+    }))
+}
+
+/// 按排版参数对已缓存章节分页（结果按字号/行距/视口尺寸分别缓存）
+/// 章节尚未缓存时返回 None
+#[tauri::command]
+pub async fn epub_paginate_section(
+    book_id: String,
+    section_index: u32,
+    typography: TypographyOptions,
+    state: State<'_, EpubCacheState>,
+) -> Result<Option<SectionPagination>, String> {
+    typography.validate().map_err(|e| e.to_string())?;
+    let typography_key = typography.cache_key();
+
     let manager = state.lock().await;
-    manager.load_section(&book_id, section_index).await.map_err(|e| {
+    if let Some(cached) = manager
+        .load_section_pages(&book_id, section_index, &typography_key)
+        .await
+    {
+        return Ok(Some(cached));
+    }
+
+    let Some(section) = manager.load_section(&book_id, section_index).await? else {
+        return Ok(None);
+    };
+
+    let pagination = paginate_html(section_index, &section.html, &typography);
+    if let Err(e) = manager.save_section_pages(&book_id, &pagination).await {
         eprintln!(
-            "[EPUB缓存] 加载章节失败: book_id={}, section_index={}, error={}",
+            "[EPUB缓存] 保存分页结果失败: book_id={}, section_index={}, error={}",
             book_id, section_index, e
         );
-        e
-    })
+    }
+    Ok(Some(pagination))
+}
+
+/// 把章节纯文本偏移换算为 HTML 中的大致位置（用于恢复 `section` 阅读进度）
+/// 章节尚未缓存时返回 None
+#[tauri::command]
+pub async fn epub_locate_text_offset(
+    book_id: String,
+    section_index: u32,
+    char_offset: usize,
+    state: State<'_, EpubCacheState>,
+) -> Result<Option<SectionTextAnchor>, String> {
+    let manager = state.lock().await;
+    let section = manager.load_section(&book_id, section_index).await?;
+    Ok(section.map(|section| locate_text_offset(&section.html, char_offset)))
 }
 
 /// 保存资源缓存到磁盘
@@ -124,6 +200,7 @@ pub async fn epub_clear_book_cache(
     book_id: String,
     state: State<'_, EpubCacheState>,
 ) -> Result<bool, String> {
+    invalidate_prefetch(&book_id);
     let manager = state.lock().await;
     manager.clear_book_cache(&book_id).await?;
     Ok(true)
@@ -178,7 +255,7 @@ pub async fn epub_save_metadata(
     };
 
     manager
-        .save_metadata(&book_id, book_info, toc, section_count, spine)
+        .save_frontend_metadata(&book_id, book_info, toc, section_count, spine)
         .await?;
     
     println!("[backend] EPUB 元数据保存成功: {}", book_id);
@@ -212,6 +289,7 @@ pub async fn epub_prepare_book(
         .await
         .map_err(|e| format!("EPUB 解析任务失败: {}", e))??;
 
+    invalidate_prefetch(&book_id);
     let manager = state.lock().await;
 
     manager
@@ -239,6 +317,11 @@ pub async fn epub_prepare_book(
             .map_err(|e| format!("保存资源缓存失败: {}", e))?;
     }
 
+    manager
+        .save_footnotes(&book_id, &prepared.footnotes)
+        .await
+        .map_err(|e| format!("保存脚注缓存失败: {}", e))?;
+
     manager
         .save_metadata(
             &book_id,
@@ -246,6 +329,7 @@ pub async fn epub_prepare_book(
             prepared.toc.clone(),
             prepared.section_count,
             prepared.spine.clone(),
+            prepared.fixed_layout.clone(),
         )
         .await
         .map_err(|e| format!("保存元数据失败: {}", e))?;
@@ -265,6 +349,7 @@ pub async fn epub_prepare_book(
         toc: prepared.toc,
         section_count: prepared.section_count,
         spine: prepared.spine,
+        fixed_layout: prepared.fixed_layout,
     })
 }
 
@@ -0,0 +1,38 @@
+//! 脚注弹窗数据
+//! EPUB/MOBI 在 prepare 阶段建立脚注映射并写入章节缓存目录，章节 HTML 中的引用链接带有
+//! `data-footnote-id`，前端点击时用该 ID 查询脚注内容
+
+use crate::epub_commands::EpubCacheState;
+use crate::formats::common::Footnote;
+use crate::formats::sanitize::sanitize_html_if_enabled;
+use crate::mobi_commands::MobiCacheState;
+use crate::resource_protocol::rewrite_resource_placeholders;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// 按脚注 ID 读取脚注 HTML 片段，书籍未缓存或 ID 不存在时返回 None
+/// `resource_urls` 为 true 时将资源占位符改写为 `goread-res` 协议地址
+#[tauri::command]
+pub async fn get_footnote<R: Runtime>(
+    app: AppHandle<R>,
+    book_id: String,
+    note_id: String,
+    resource_urls: Option<bool>,
+) -> Result<Option<Footnote>, String> {
+    let mut found = None;
+    if let Some(state) = app.try_state::<EpubCacheState>() {
+        found = state.lock().await.load_footnote(&book_id, &note_id).await;
+    }
+    if found.is_none() {
+        if let Some(state) = app.try_state::<MobiCacheState>() {
+            found = state.lock().await.load_footnote(&book_id, &note_id).await;
+        }
+    }
+
+    Ok(found.map(|mut footnote| {
+        footnote.html = sanitize_html_if_enabled(footnote.html);
+        if resource_urls.unwrap_or(false) {
+            footnote.html = rewrite_resource_placeholders(&footnote.html, &book_id);
+        }
+        footnote
+    }))
+}
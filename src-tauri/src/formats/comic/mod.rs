@@ -0,0 +1,324 @@
+//! 漫画压缩包格式引擎（CBZ/CBR）
+//! 打开时只读取压缩包目录，渲染某页时才解出对应图片，避免整包解压进内存
+
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read};
+use std::path::Path;
+
+use image::imageops::FilterType;
+use image::ImageOutputFormat;
+use zip::ZipArchive;
+
+use crate::formats::{
+    BookEngine, BookError, BookErrorCode, BookFormat, BookMetadata, ImageFormat, PageContent,
+    RenderOptions, SearchResult, TocItem, TocLocation,
+};
+
+/// 支持的图片扩展名
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif", "bmp"];
+
+/// 单张图片允许解出的最大字节数，防止异常压缩包撑爆内存
+const MAX_IMAGE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 封面缩略图宽度
+const COVER_WIDTH: u32 = 400;
+
+/// 漫画引擎
+pub struct ComicEngine {
+    file_path: String,
+    format: BookFormat,
+    /// 按文件名自然排序后的图片条目名
+    pages: Vec<String>,
+}
+
+impl ComicEngine {
+    /// 从文件创建漫画引擎实例
+    pub fn from_file(path: &str) -> Result<Self, BookError> {
+        if !Path::new(path).exists() {
+            return Err(BookError::file_not_found(path));
+        }
+
+        let format = BookFormat::from_path(path).unwrap_or(BookFormat::Cbz);
+        if !is_zip_file(path)? {
+            // 目前没有可用的纯 Rust RAR 解码实现，RAR 格式的 CBR 明确降级为不支持
+            return Err(BookError::new(
+                BookErrorCode::UnsupportedFeature,
+                "暂不支持 RAR 压缩的漫画文件",
+            )
+            .with_details("可将 CBR 转换为 CBZ 后再导入"));
+        }
+
+        let archive = open_archive(path)?;
+        let mut pages: Vec<String> = archive
+            .file_names()
+            .filter(|name| is_image_entry(name))
+            .map(|name| name.to_string())
+            .collect();
+        pages.sort_by(|a, b| natural_cmp(a, b));
+
+        if pages.is_empty() {
+            return Err(BookError::parse_error("压缩包中没有可显示的图片"));
+        }
+
+        Ok(Self {
+            file_path: path.to_string(),
+            format,
+            pages,
+        })
+    }
+
+    /// 获取标题（使用文件名）
+    pub fn get_title(&self) -> Option<String> {
+        Path::new(&self.file_path)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .map(|s| s.to_string())
+    }
+
+    /// 读取第 page 页（从 1 开始）的原始图片数据
+    fn read_page_bytes(&self, page: u32) -> Result<Vec<u8>, BookError> {
+        let name = page
+            .checked_sub(1)
+            .and_then(|i| self.pages.get(i as usize))
+            .ok_or_else(|| BookError::page_not_found(page, self.get_page_count()))?;
+
+        let mut archive = open_archive(&self.file_path)?;
+        let entry = archive.by_name(name).map_err(|e| {
+            BookError::parse_error(format!("读取图片失败: {}", name)).with_details(e.to_string())
+        })?;
+        if entry.size() > MAX_IMAGE_BYTES {
+            return Err(BookError::new(
+                BookErrorCode::RenderError,
+                format!("图片过大: {} ({} bytes)", name, entry.size()),
+            ));
+        }
+
+        let mut data = Vec::with_capacity(entry.size() as usize);
+        entry.take(MAX_IMAGE_BYTES).read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// 获取封面（第一张图片的缩略图）
+    pub fn get_cover(&self) -> Option<Vec<u8>> {
+        let options = RenderOptions {
+            width: Some(COVER_WIDTH),
+            ..Default::default()
+        };
+        match self.render_page(1, &options) {
+            Ok(PageContent::Image { data, .. }) => Some(data),
+            _ => None,
+        }
+    }
+}
+
+impl BookEngine for ComicEngine {
+    fn get_metadata(&self) -> Result<BookMetadata, BookError> {
+        Ok(BookMetadata {
+            title: self.get_title(),
+            cover_image: self.get_cover(),
+            page_count: self.get_page_count(),
+            format: Some(self.format),
+            ..Default::default()
+        })
+    }
+
+    fn get_toc(&self) -> Result<Vec<TocItem>, BookError> {
+        // 按图片所在子目录生成目录（常见于按话分文件夹的漫画包）
+        let mut toc: Vec<TocItem> = Vec::new();
+        let mut last_dir: Option<&str> = None;
+        for (index, name) in self.pages.iter().enumerate() {
+            let dir = name.rsplit_once('/').map(|(dir, _)| dir);
+            if let Some(dir) = dir {
+                if last_dir != Some(dir) {
+                    toc.push(TocItem {
+                        title: dir.rsplit('/').next().unwrap_or(dir).to_string(),
+                        location: TocLocation::Page(index as u32 + 1),
+                        level: 0,
+                        children: Vec::new(),
+                    });
+                }
+            }
+            last_dir = dir;
+        }
+        Ok(toc)
+    }
+
+    fn get_page_count(&self) -> u32 {
+        self.pages.len() as u32
+    }
+
+    fn render_page(&self, page: u32, options: &RenderOptions) -> Result<PageContent, BookError> {
+        let data = self.read_page_bytes(page)?;
+        let source_format = image::guess_format(&data).map_err(|e| {
+            BookError::new(BookErrorCode::RenderError, "无法识别图片格式").with_details(e.to_string())
+        })?;
+        let img = image::load_from_memory_with_format(&data, source_format).map_err(|e| {
+            BookError::new(BookErrorCode::RenderError, "图片解码失败").with_details(e.to_string())
+        })?;
+
+        let (width, height) = (img.width(), img.height());
+        let target = match (options.width, options.height) {
+            (Some(w), Some(h)) => Some((w, h)),
+            (Some(w), None) => Some((w, scale_dimension(height, w, width))),
+            (None, Some(h)) => Some((scale_dimension(width, h, height), h)),
+            (None, None) => {
+                let scale = options.quality.scale_factor();
+                if (scale - 1.0).abs() < f32::EPSILON {
+                    None
+                } else {
+                    Some((
+                        ((width as f32 * scale) as u32).max(1),
+                        ((height as f32 * scale) as u32).max(1),
+                    ))
+                }
+            }
+        };
+
+        // 无需缩放且原图为前端可直接显示的格式时，直接返回原始数据
+        let passthrough = match source_format {
+            image::ImageFormat::Jpeg => Some(ImageFormat::Jpeg),
+            image::ImageFormat::Png => Some(ImageFormat::Png),
+            image::ImageFormat::WebP => Some(ImageFormat::WebP),
+            _ => None,
+        };
+        if let (None, Some(format)) = (target, passthrough) {
+            return Ok(PageContent::Image { data, width, height, format });
+        }
+
+        let img = match target {
+            Some((w, h)) => img.resize(w, h, FilterType::Triangle),
+            None => img,
+        };
+        let (out_format, format) = match source_format {
+            image::ImageFormat::Jpeg => (ImageOutputFormat::Jpeg(85), ImageFormat::Jpeg),
+            _ => (ImageOutputFormat::Png, ImageFormat::Png),
+        };
+        let mut buffer = Cursor::new(Vec::new());
+        let img = if matches!(format, ImageFormat::Jpeg) {
+            image::DynamicImage::ImageRgb8(img.to_rgb8())
+        } else {
+            img
+        };
+        img.write_to(&mut buffer, out_format).map_err(|e| {
+            BookError::new(BookErrorCode::RenderError, "图片编码失败").with_details(e.to_string())
+        })?;
+
+        Ok(PageContent::Image {
+            data: buffer.into_inner(),
+            width: img.width(),
+            height: img.height(),
+            format,
+        })
+    }
+
+    fn search_text(&self, _query: &str, _case_sensitive: bool) -> Result<Vec<SearchResult>, BookError> {
+        Ok(Vec::new())
+    }
+
+    fn extract_text(&self, _page: u32) -> Result<String, BookError> {
+        Err(BookError::new(
+            BookErrorCode::UnsupportedFeature,
+            "漫画格式不包含文本",
+        ))
+    }
+
+    fn close(&mut self) {
+        self.pages.clear();
+    }
+}
+
+/// 通过文件头判断是否为 ZIP 压缩包（部分 CBR 实际是 ZIP）
+fn is_zip_file(path: &str) -> Result<bool, BookError> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path)?;
+    let n = file.read(&mut magic)?;
+    Ok(n == 4 && magic == *b"PK\x03\x04")
+}
+
+fn open_archive(path: &str) -> Result<ZipArchive<BufReader<File>>, BookError> {
+    let file = File::open(path)?;
+    ZipArchive::new(BufReader::new(file)).map_err(|e| {
+        BookError::parse_error("打开漫画压缩包失败").with_details(e.to_string())
+    })
+}
+
+/// 按比例计算另一边长度
+fn scale_dimension(other: u32, target: u32, base: u32) -> u32 {
+    ((other as u64 * target as u64) / base.max(1) as u64).max(1) as u32
+}
+
+/// 是否为图片条目（忽略目录和 macOS 生成的隐藏文件）
+fn is_image_entry(name: &str) -> bool {
+    if name.ends_with('/') || name.starts_with("__MACOSX/") {
+        return false;
+    }
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    if file_name.starts_with('.') {
+        return false;
+    }
+    file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// 自然排序：数字部分按数值比较（page2 < page10）
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let mut na = String::new();
+                while let Some(c) = a_chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+                    na.push(c);
+                    a_chars.next();
+                }
+                let mut nb = String::new();
+                while let Some(c) = b_chars.peek().copied().filter(|c| c.is_ascii_digit()) {
+                    nb.push(c);
+                    b_chars.next();
+                }
+                let ta = na.trim_start_matches('0');
+                let tb = nb.trim_start_matches('0');
+                let ord = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb));
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                let ord = ca.to_lowercase().cmp(cb.to_lowercase());
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_sort() {
+        let mut names = vec!["p10.jpg", "p2.jpg", "p1.jpg", "P3.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["p1.jpg", "p2.jpg", "P3.png", "p10.jpg"]);
+    }
+
+    #[test]
+    fn test_image_entry_filter() {
+        assert!(is_image_entry("ch01/001.JPG"));
+        assert!(!is_image_entry("ch01/"));
+        assert!(!is_image_entry("__MACOSX/ch01/._001.jpg"));
+        assert!(!is_image_entry("ComicInfo.xml"));
+    }
+}
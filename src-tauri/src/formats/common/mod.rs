@@ -1,5 +1,7 @@
 //! 书籍格式公共工具
 
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::path::Path;
 
 /// 读取文件字节
@@ -43,6 +45,132 @@ pub fn generate_cache_key(path: &str, page: u32, quality: &str) -> String {
     format!("{:x}", hasher.finish())
 }
 
+/// 书名中常见的版本/来源噪音词
+const TITLE_NOISE_WORDS: &str = "校对版|精校版|精校|校对|完整版|完结版|完本|全本|全集|已完结|完结|精排版|精排|无删减|未删减|修订版|典藏版|插图版|txt|epub|mobi";
+
+/// 结尾被括号包裹的噪音，如 `（校对版）`、`[精校]`、`【全本】`
+static BRACKET_NOISE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r"(?i)\s*[（(\[【〔]\s*(?:{})[^（）()\[\]【】〔〕]*[）)\]】〕]\s*$",
+        TITLE_NOISE_WORDS
+    ))
+    .unwrap()
+});
+
+/// 结尾用分隔符连接的噪音，如 `_完整版`、`-精校版`、` 全本`
+static SEPARATED_NOISE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(r"(?i)[\s_\-—·.]+(?:{})\s*$", TITLE_NOISE_WORDS)).unwrap()
+});
+
+/// 清理书名：去掉结尾的版本噪音和包裹的书名号；清理后为空时保留原标题
+pub fn clean_title(raw: &str) -> String {
+    let original = raw.trim();
+    let mut title = original.to_string();
+
+    loop {
+        let stripped = BRACKET_NOISE_RE.replace(&title, "");
+        let stripped = SEPARATED_NOISE_RE.replace(&stripped, "").trim().to_string();
+        if stripped == title {
+            break;
+        }
+        title = stripped;
+    }
+
+    if let Some(inner) = title.strip_prefix('《').and_then(|t| t.strip_suffix('》')) {
+        if !inner.contains(['《', '》']) {
+            title = inner.trim().to_string();
+        }
+    }
+
+    if title.is_empty() {
+        original.to_string()
+    } else {
+        title
+    }
+}
+
+/// 元数据中常见的非标准语言写法（ISO 639-2 三字母代码、英文名称）到 ISO 639-1 的映射
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("chi", "zh"),
+    ("zho", "zh"),
+    ("chinese", "zh"),
+    ("eng", "en"),
+    ("english", "en"),
+    ("jpn", "ja"),
+    ("japanese", "ja"),
+    ("kor", "ko"),
+    ("korean", "ko"),
+    ("fre", "fr"),
+    ("fra", "fr"),
+    ("french", "fr"),
+    ("ger", "de"),
+    ("deu", "de"),
+    ("german", "de"),
+    ("spa", "es"),
+    ("spanish", "es"),
+    ("rus", "ru"),
+    ("russian", "ru"),
+    ("ita", "it"),
+    ("por", "pt"),
+];
+
+/// 规范化语言标签为 BCP 47 形式：主标签小写、文字代码首字母大写、地区大写
+/// 如 `zh_cn` -> `zh-CN`、`ZH-hans` -> `zh-Hans`、`eng` -> `en`；空值和 `und` 等未定语言返回 None
+pub fn normalize_language_tag(raw: &str) -> Option<String> {
+    let mut parts = raw.trim().split(['-', '_']).filter(|p| !p.is_empty());
+    let primary = parts.next()?.to_ascii_lowercase();
+    if !primary.chars().all(|c| c.is_ascii_alphabetic()) || primary.len() < 2 {
+        return None;
+    }
+    if matches!(primary.as_str(), "und" | "mul" | "zxx" | "mis") {
+        return None;
+    }
+    let primary = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == primary)
+        .map_or(primary.clone(), |(_, code)| code.to_string());
+    if primary.len() > 3 {
+        return None;
+    }
+
+    let mut tag = primary;
+    for part in parts {
+        tag.push('-');
+        match part.len() {
+            2 if part.chars().all(|c| c.is_ascii_alphabetic()) => tag.push_str(&part.to_ascii_uppercase()),
+            4 if part.chars().all(|c| c.is_ascii_alphabetic()) => {
+                tag.push_str(&part[..1].to_ascii_uppercase());
+                tag.push_str(&part[1..].to_ascii_lowercase());
+            }
+            _ => tag.push_str(&part.to_ascii_lowercase()),
+        }
+    }
+    Some(tag)
+}
+
+/// 章节 HTML 中脚注引用（noteref）上标注的属性，值为 `get_footnote` 使用的注释 ID
+pub const FOOTNOTE_ID_ATTR: &str = "data-footnote-id";
+
+/// 脚注/尾注内容，章节解析时与正文中的引用关联
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Footnote {
+    /// 注释 ID：EPUB 为 `章节路径#元素 id`，MOBI 为 `filepos{偏移}`
+    pub id: String,
+    /// 注释所在章节（MOBI 未知时为空）
+    pub section_index: Option<u32>,
+    /// 注释的 HTML 片段
+    pub html: String,
+}
+
+/// 在开始标签 `<a` 之后插入脚注 ID 属性
+pub fn mark_noteref_tag(tag: &str, note_id: &str) -> String {
+    let escaped = note_id.replace('&', "&amp;").replace('"', "&quot;");
+    match tag.get(..2) {
+        Some(open) => format!(r#"{} {}="{}"{}"#, open, FOOTNOTE_ID_ATTR, escaped, &tag[2..]),
+        None => tag.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -59,4 +187,24 @@ mod tests {
         assert_eq!(normalize_path("C:\\Books\\novel.pdf"), "C:/Books/novel.pdf");
         assert_eq!(normalize_path("/home/user/book.pdf"), "/home/user/book.pdf");
     }
+
+    #[test]
+    fn test_clean_title() {
+        assert_eq!(clean_title("三体（校对版）"), "三体");
+        assert_eq!(clean_title("三体_完整版"), "三体");
+        assert_eq!(clean_title("《三体》【精校】(全本)"), "三体");
+        assert_eq!(clean_title("鲁迅全集"), "鲁迅全集");
+        assert_eq!(clean_title("(完结)"), "(完结)");
+    }
+
+    #[test]
+    fn test_normalize_language_tag() {
+        assert_eq!(normalize_language_tag("zh_cn").as_deref(), Some("zh-CN"));
+        assert_eq!(normalize_language_tag(" ZH-hans-cn ").as_deref(), Some("zh-Hans-CN"));
+        assert_eq!(normalize_language_tag("eng").as_deref(), Some("en"));
+        assert_eq!(normalize_language_tag("Chinese").as_deref(), Some("zh"));
+        assert_eq!(normalize_language_tag("und"), None);
+        assert_eq!(normalize_language_tag("中文"), None);
+        assert_eq!(normalize_language_tag(""), None);
+    }
 }
@@ -1,6 +1,11 @@
 //! EPUB 缓存管理器
 //! 负责将 EPUB 章节内容和资源持久化到磁盘
 
+use super::EpubFixedLayout;
+use crate::formats::common::Footnote;
+use crate::formats::pagination::SectionPagination;
+use crate::formats::sanitize::{sanitize_css_if_enabled, sanitize_html_if_enabled};
+use crate::formats::text_offset::section_text_length;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -92,6 +97,24 @@ pub struct SectionCacheData {
     pub styles: Vec<String>,
     /// 资源引用列表
     pub resource_refs: Vec<String>,
+    /// 章节纯文本长度（字符数），前端据此把滚动位置换算为纯文本偏移
+    #[serde(default)]
+    pub text_length: usize,
+}
+
+impl SectionCacheData {
+    /// 清洗开关开启时在此统一清洗，加载、分页与进度定位看到的是同一份 HTML
+    fn new(html: String, styles: Vec<String>, resource_refs: Vec<String>) -> Self {
+        let html = sanitize_html_if_enabled(html);
+        let styles = styles.into_iter().map(sanitize_css_if_enabled).collect();
+        let text_length = section_text_length(&html);
+        Self {
+            html,
+            styles,
+            resource_refs,
+            text_length,
+        }
+    }
 }
 
 /// 资源缓存元数据
@@ -126,6 +149,26 @@ pub struct BookInfo {
     pub page_count: i32,
     pub format: String,
     pub cover_image: Option<String>,
+    /// 所属系列名称
+    #[serde(default)]
+    pub series: Option<String>,
+    /// 在系列中的序号
+    #[serde(default)]
+    pub series_index: Option<f32>,
+    /// 书写模式：horizontal-tb / vertical-rl / vertical-lr
+    #[serde(default = "default_writing_mode")]
+    pub writing_mode: String,
+    /// 翻页方向：ltr / rtl
+    #[serde(default = "default_reading_direction")]
+    pub reading_direction: String,
+}
+
+fn default_writing_mode() -> String {
+    super::layout::DEFAULT_WRITING_MODE.to_string()
+}
+
+fn default_reading_direction() -> String {
+    super::layout::DEFAULT_READING_DIRECTION.to_string()
 }
 
 /// EPUB 元数据缓存条目
@@ -140,11 +183,20 @@ pub struct MetadataCacheEntry {
     /// 缓存结构版本，用于在解析能力升级后主动淘汰旧缓存
     #[serde(default)]
     pub schema_version: u32,
+    /// 固定布局信息，流式排版书籍为 None
+    #[serde(default)]
+    pub fixed_layout: Option<EpubFixedLayout>,
 }
 
 /// 当前 EPUB 元数据缓存版本号。
 /// 1：新增 EPUB3 nav.xhtml 目录解析能力；老版本（0）缓存若目录为空需要重建。
-pub const EPUB_METADATA_SCHEMA_VERSION: u32 = 1;
+/// 2：新增固定布局识别；旧缓存缺少布局信息，需要重建。
+/// 3：新增脚注关联；旧缓存章节缺少脚注标注，需要重建。
+/// 4：新增书写模式与翻页方向；旧缓存一律为默认横排，需要重建。
+pub const EPUB_METADATA_SCHEMA_VERSION: u32 = 4;
+
+/// 脚注映射缓存文件名，位于章节缓存目录
+const FOOTNOTES_FILE_NAME: &str = "footnotes.json";
 
 /// 默认磁盘缓存上限（字节），前端未下发时的 fallback
 const DEFAULT_DISK_CACHE_MAX_BYTES: usize = 256 * 1024 * 1024;
@@ -236,6 +288,16 @@ impl EpubCacheManager {
             .await
             .map_err(|e| format!("写入元数据失败: {}", e))?;
 
+        // 章节内容变化后，旧的分页结果全部失效
+        let pages_prefix = format!("{}.pages.", section_index);
+        if let Ok(mut entries) = fs::read_dir(&cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_name().to_string_lossy().starts_with(&pages_prefix) {
+                    let _ = fs::remove_file(entry.path()).await;
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -291,11 +353,7 @@ impl EpubCacheManager {
                 let _ = fs::write(&meta_path, updated_meta_json).await;
             }
 
-            return Ok(Some(SectionCacheData {
-                html: html_content,
-                styles: meta.styles,
-                resource_refs: meta.resource_refs,
-            }));
+            return Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)));
         }
 
         let legacy_cache_dir = {
@@ -328,11 +386,7 @@ impl EpubCacheManager {
             let meta_path = cache_dir.join(format!("{}.meta.json", section_index));
             let _ = fs::write(&meta_path, updated_meta_json).await;
 
-            return Ok(Some(SectionCacheData {
-                html: html_content,
-                styles: meta.styles,
-                resource_refs: meta.resource_refs,
-            }));
+            return Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)));
         }
 
         let _ = fs::create_dir_all(&cache_dir).await;
@@ -349,11 +403,68 @@ impl EpubCacheManager {
             let _ = fs::remove_file(&legacy_meta_path).await;
         }
 
-        Ok(Some(SectionCacheData {
-            html: html_content,
-            styles: meta.styles,
-            resource_refs: meta.resource_refs,
-        }))
+        Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)))
+    }
+
+    /// 保存章节分页结果，缓存键包含排版参数，换字号/行距后不会命中旧分页
+    pub async fn save_section_pages(
+        &self,
+        book_id: &str,
+        pagination: &SectionPagination,
+    ) -> Result<(), String> {
+        let book_hash = compute_book_hash(book_id);
+        let cache_dir = epub_section_cache_dir(&book_hash);
+        fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+        let pages_path = cache_dir.join(format!(
+            "{}.pages.{}.json",
+            pagination.section_index, pagination.typography_key
+        ));
+        let json = serde_json::to_string(pagination).map_err(|e| format!("序列化分页结果失败: {}", e))?;
+        fs::write(&pages_path, json)
+            .await
+            .map_err(|e| format!("写入分页缓存失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 读取章节分页结果（按排版参数区分）
+    pub async fn load_section_pages(
+        &self,
+        book_id: &str,
+        section_index: u32,
+        typography_key: &str,
+    ) -> Option<SectionPagination> {
+        let book_hash = compute_book_hash(book_id);
+        let pages_path = epub_section_cache_dir(&book_hash)
+            .join(format!("{}.pages.{}.json", section_index, typography_key));
+        let json = fs::read_to_string(&pages_path).await.ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// 保存整本书的脚注映射
+    pub async fn save_footnotes(&self, book_id: &str, footnotes: &[Footnote]) -> Result<(), String> {
+        let book_hash = compute_book_hash(book_id);
+        let cache_dir = epub_section_cache_dir(&book_hash);
+        fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+        let json = serde_json::to_string(footnotes).map_err(|e| format!("序列化脚注失败: {}", e))?;
+        fs::write(cache_dir.join(FOOTNOTES_FILE_NAME), json)
+            .await
+            .map_err(|e| format!("写入脚注缓存失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 按注释 ID 读取单条脚注
+    pub async fn load_footnote(&self, book_id: &str, note_id: &str) -> Option<Footnote> {
+        let book_hash = compute_book_hash(book_id);
+        let path = epub_section_cache_dir(&book_hash).join(FOOTNOTES_FILE_NAME);
+        let json = fs::read_to_string(&path).await.ok()?;
+        let footnotes: Vec<Footnote> = serde_json::from_str(&json).ok()?;
+        footnotes.into_iter().find(|f| f.id == note_id)
     }
 
     /// 保存资源缓存到磁盘
@@ -694,7 +805,7 @@ impl EpubCacheManager {
 
     // ====================== 元数据缓存 ======================
 
-    /// 保存书籍元数据到磁盘
+    /// 保存书籍元数据到磁盘（由后端完整解析得到，写入当前版本号）
     pub async fn save_metadata(
         &self,
         book_id: &str,
@@ -702,24 +813,67 @@ impl EpubCacheManager {
         toc: Vec<TocItem>,
         section_count: u32,
         spine: Vec<String>,
+        fixed_layout: Option<EpubFixedLayout>,
     ) -> Result<(), String> {
-        let book_hash = compute_book_hash(book_id);
-        let cache_dir = epub_metadata_cache_dir();
+        self.write_metadata_entry(MetadataCacheEntry {
+            book_id: book_id.to_string(),
+            book_info,
+            toc,
+            section_count,
+            spine,
+            last_access_time: Self::now_millis(),
+            schema_version: EPUB_METADATA_SCHEMA_VERSION,
+            fixed_layout,
+        })
+        .await
+    }
 
-        // 创建目录
-        fs::create_dir_all(&cache_dir)
+    /// 保存前端回传的元数据。前端拿不到固定布局、脚注等由后端解析的信息，
+    /// 只有磁盘上已有当前版本的条目且脚注映射仍在时才沿用其版本号与布局信息；否则按版本 0 写入，
+    /// 下次加载视为旧缓存，由后端重新解析，避免不完整的条目被当作当前版本长期沿用
+    pub async fn save_frontend_metadata(
+        &self,
+        book_id: &str,
+        book_info: BookInfo,
+        toc: Vec<TocItem>,
+        section_count: u32,
+        spine: Vec<String>,
+    ) -> Result<(), String> {
+        let book_hash = compute_book_hash(book_id);
+        let meta_path = epub_metadata_cache_dir().join(format!("{}.json", book_hash));
+        // 脚注映射与章节缓存放在一起，可能被单独清理，缺失时同样不能沿用当前版本
+        let has_footnotes = epub_section_cache_dir(&book_hash).join(FOOTNOTES_FILE_NAME).exists();
+        let existing = fs::read_to_string(&meta_path)
             .await
-            .map_err(|e| format!("创建元数据缓存目录失败: {}", e))?;
+            .ok()
+            .and_then(|json| serde_json::from_str::<MetadataCacheEntry>(&json).ok())
+            .filter(|entry| has_footnotes && !Self::needs_rebuild(entry));
+        let (schema_version, fixed_layout) = match existing {
+            Some(entry) => (entry.schema_version, entry.fixed_layout),
+            None => (0, None),
+        };
 
-        let entry = MetadataCacheEntry {
+        self.write_metadata_entry(MetadataCacheEntry {
             book_id: book_id.to_string(),
             book_info,
             toc,
             section_count,
             spine,
             last_access_time: Self::now_millis(),
-            schema_version: EPUB_METADATA_SCHEMA_VERSION,
-        };
+            schema_version,
+            fixed_layout,
+        })
+        .await
+    }
+
+    async fn write_metadata_entry(&self, entry: MetadataCacheEntry) -> Result<(), String> {
+        let book_hash = compute_book_hash(&entry.book_id);
+        let cache_dir = epub_metadata_cache_dir();
+
+        // 创建目录
+        fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(|e| format!("创建元数据缓存目录失败: {}", e))?;
 
         let meta_path = cache_dir.join(format!("{}.json", book_hash));
         let meta_json = serde_json::to_string(&entry)
@@ -731,6 +885,11 @@ impl EpubCacheManager {
         Ok(())
     }
 
+    /// 旧版本缓存是否需要重建：早于固定布局识别的缓存都缺少布局信息（也涵盖版本 0 目录为空的情况）
+    fn needs_rebuild(entry: &MetadataCacheEntry) -> bool {
+        entry.schema_version < EPUB_METADATA_SCHEMA_VERSION
+    }
+
     /// 从磁盘加载书籍元数据
     pub async fn load_metadata(
         &self,
@@ -752,8 +911,8 @@ impl EpubCacheManager {
                 return Ok(None);
             }
 
-            // 解析能力升级兜底：旧版本缓存且目录为空时主动淘汰，触发下次重建
-            if entry.schema_version < EPUB_METADATA_SCHEMA_VERSION && entry.toc.is_empty() {
+            // 解析能力升级兜底：旧版本缓存主动淘汰，触发下次重建
+            if Self::needs_rebuild(&entry) {
                 let _ = fs::remove_file(&meta_path).await;
                 return Ok(None);
             }
@@ -790,8 +949,8 @@ impl EpubCacheManager {
             return Ok(None);
         }
 
-        // 与主分支保持一致：旧版本缓存 + 空目录直接淘汰
-        if entry.schema_version < EPUB_METADATA_SCHEMA_VERSION && entry.toc.is_empty() {
+        // 与主分支保持一致：旧版本缓存直接淘汰
+        if Self::needs_rebuild(&entry) {
             let _ = fs::remove_file(&legacy_meta_path).await;
             return Ok(None);
         }
@@ -1034,7 +1193,7 @@ mod tests {
         let manager = EpubCacheManager::new();
         let book_id = "test_book_123";
         let section_index = 1;
-        let html_content = "<html><body>Test content</body></html>";
+        let html_content = "<p>Test content</p>";
 
         // 保存（添加空的 styles 和 resource_refs）
         manager
@@ -1055,6 +1214,36 @@ mod tests {
         assert!(loaded_after_clear.is_none());
     }
 
+    #[tokio::test]
+    async fn test_frontend_metadata_version() {
+        let manager = EpubCacheManager::new();
+        let book_id = "test_book_frontend_meta";
+        let book_info: BookInfo =
+            serde_json::from_str(r#"{"title":"书","page_count":1,"format":"epub"}"#).unwrap();
+
+        // 没有后端解析过的条目时，前端回写不能成为当前版本
+        manager
+            .save_frontend_metadata(book_id, book_info.clone(), vec![], 1, vec![])
+            .await
+            .unwrap();
+        assert!(manager.load_metadata(book_id).await.unwrap().is_none());
+
+        // 后端完整解析过（含脚注映射）后，前端回写沿用当前版本
+        manager
+            .save_metadata(book_id, book_info.clone(), vec![], 1, vec![], None)
+            .await
+            .unwrap();
+        manager.save_footnotes(book_id, &[]).await.unwrap();
+        manager
+            .save_frontend_metadata(book_id, book_info, vec![], 1, vec![])
+            .await
+            .unwrap();
+        let loaded = manager.load_metadata(book_id).await.unwrap().unwrap();
+        assert_eq!(loaded.schema_version, EPUB_METADATA_SCHEMA_VERSION);
+
+        manager.clear_book_cache(book_id).await.unwrap();
+    }
+
     #[tokio::test]
     async fn test_resource_cache() {
         let manager = EpubCacheManager::new();
@@ -5,7 +5,9 @@ use epub::doc::{EpubDoc, NavPoint};
 use regex::Regex;
 use serde::Serialize;
 
-use super::{BookInfo, TocItem};
+use super::{BookInfo, EpubFixedLayout, TocItem};
+use crate::formats::common::Footnote;
+use crate::formats::parse_series_index;
 
 #[derive(Debug, Serialize)]
 pub struct EpubInspectResult {
@@ -36,6 +38,10 @@ pub struct EpubPreparedBook {
     pub spine: Vec<String>,
     pub sections: Vec<PreparedSection>,
     pub resources: Vec<PreparedResource>,
+    /// 固定布局信息，流式排版书籍为 None
+    pub fixed_layout: Option<EpubFixedLayout>,
+    /// 被引用的脚注/尾注，章节 HTML 中已标注引用关系
+    pub footnotes: Vec<Footnote>,
 }
 
 fn extract_metadata<R: std::io::Read + std::io::Seek>(
@@ -59,6 +65,33 @@ fn extract_metadata<R: std::io::Read + std::io::Seek>(
     (title, author, description, publisher, language)
 }
 
+/// 提取系列信息：优先 calibre 写入的 `calibre:series`/`calibre:series_index` meta，
+/// 其次 EPUB3 的 `belongs-to-collection`（序号位于 refines 中，这里不解析）
+fn extract_series<R: std::io::Read + std::io::Seek>(
+    doc: &EpubDoc<R>,
+) -> (Option<String>, Option<f32>) {
+    let non_empty = |value: &str| {
+        let value = value.trim();
+        if value.is_empty() {
+            None
+        } else {
+            Some(value.to_string())
+        }
+    };
+
+    if let Some(series) = doc.mdata("calibre:series").and_then(|m| non_empty(&m.value)) {
+        let index = doc
+            .mdata("calibre:series_index")
+            .and_then(|m| parse_series_index(&m.value));
+        return (Some(series), index);
+    }
+
+    let series = doc
+        .mdata("belongs-to-collection")
+        .and_then(|m| non_empty(&m.value));
+    (series, None)
+}
+
 fn estimate_page_count<R: std::io::Read + std::io::Seek>(doc: &EpubDoc<R>) -> i32 {
     let chapters = doc.get_num_chapters() as i32;
     if chapters <= 0 {
@@ -127,6 +160,85 @@ fn extract_cover_data<R: std::io::Read + std::io::Seek>(
     find_cover_from_resources(doc)
 }
 
+/// 只读取元数据中的书名，不解析封面和章节
+pub fn read_title(file_path: &str) -> Result<Option<String>, String> {
+    let mut doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    let (title, ..) = extract_metadata(&mut doc);
+    Ok(title)
+}
+
+/// 只读取 dc:language
+pub fn read_language(file_path: &str) -> Result<Option<String>, String> {
+    let mut doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    let (.., language) = extract_metadata(&mut doc);
+    Ok(language)
+}
+
+/// 只读取系列名称与序号，不解析章节
+pub fn read_series(file_path: &str) -> Result<(Option<String>, Option<f32>), String> {
+    let doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    Ok(extract_series(&doc))
+}
+
+/// 读取目录并映射到 spine 章节序号（从 1 开始，与前端页码一致），按目录顺序展开为 (章节序号, 标题)；
+/// 指向 spine 之外文件或没有标题的目录项跳过
+pub fn read_toc_sections(file_path: &str) -> Result<Vec<(u32, String)>, String> {
+    let mut doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    let toc = resolve_toc(&mut doc);
+    let mut spine = Vec::with_capacity(doc.get_num_chapters());
+    for index in 0..doc.get_num_chapters() {
+        if doc.set_current_page(index) {
+            spine.push(doc.get_current_path().unwrap_or_default().to_string_lossy().to_string());
+        }
+    }
+
+    fn walk(items: &[TocItem], spine: &[String], out: &mut Vec<(u32, String)>) {
+        for item in items {
+            let section = item.location.as_deref().and_then(|location| {
+                let path = percent_decode_path(location.split('#').next().unwrap_or(location));
+                spine.iter().position(|p| *p == path)
+            });
+            if let (Some(index), Some(title)) = (section, item.title.as_deref().map(str::trim)) {
+                if !title.is_empty() {
+                    out.push((index as u32 + 1, title.to_string()));
+                }
+            }
+            walk(&item.children, spine, out);
+        }
+    }
+
+    let mut sections = Vec::new();
+    walk(&toc, &spine, &mut sections);
+    Ok(sections)
+}
+
+/// 只读取单个 spine 章节的原始 HTML，不提取资源与样式
+pub fn read_section_html(file_path: &str, index: u32) -> Result<String, String> {
+    let mut doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    if !doc.set_current_page(index as usize) {
+        return Err(format!("EPUB 章节不存在: {}", index));
+    }
+    let section_path = doc.get_current_path().unwrap_or_default().to_string_lossy().to_string();
+    match doc.get_current_str() {
+        Some((html, _mime)) => Ok(html),
+        None => try_raw_fallback(&mut doc, &section_path)
+            .ok_or_else(|| format!("读取 EPUB 章节失败: {}", index)),
+    }
+}
+
+/// 读取 spine 中各章节在包内的路径（按阅读顺序），不解析章节内容
+pub fn read_spine(file_path: &str) -> Result<Vec<String>, String> {
+    let mut doc = EpubDoc::new(file_path).map_err(|e| format!("打开 EPUB 失败: {}", e))?;
+    let total = doc.get_num_chapters();
+    let mut spine = Vec::with_capacity(total);
+    for index in 0..total {
+        if doc.set_current_page(index) {
+            spine.push(doc.get_current_path().unwrap_or_default().to_string_lossy().to_string());
+        }
+    }
+    Ok(spine)
+}
+
 pub fn inspect_epub(file_path: &str) -> Result<EpubInspectResult, String> {
     let path = Path::new(file_path);
     if !path.exists() {
@@ -138,6 +250,8 @@ pub fn inspect_epub(file_path: &str) -> Result<EpubInspectResult, String> {
     let (title, author, description, publisher, language) = extract_metadata(&mut doc);
     let page_count = estimate_page_count(&doc);
     let cover_image = extract_cover_data(&mut doc);
+    let (series, series_index) = extract_series(&doc);
+    let (writing_mode, reading_direction) = super::layout::detect_reading_direction(&mut doc);
 
     let book_info = BookInfo {
         title,
@@ -148,6 +262,10 @@ pub fn inspect_epub(file_path: &str) -> Result<EpubInspectResult, String> {
         page_count,
         format: "epub".to_string(),
         cover_image,
+        series,
+        series_index,
+        writing_mode,
+        reading_direction,
     };
 
     Ok(EpubInspectResult { book_info })
@@ -279,8 +397,38 @@ fn try_raw_fallback<R: std::io::Read + std::io::Seek>(
     String::from_utf8(bytes).ok()
 }
 
+/// 解码路径中的百分号转义（如 `%20`），非法序列按原样保留
+pub fn percent_decode_path(path: &str) -> String {
+    if !path.contains('%') {
+        return path.to_string();
+    }
+    let bytes = path.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(v) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(v);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8(out).unwrap_or_else(|_| path.to_string())
+}
+
+/// 去掉资源引用中的锚点与查询参数，得到可在 EPUB 包内查找的路径
+fn strip_fragment(path: &str) -> &str {
+    let end = path.find(['#', '?']).unwrap_or(path.len());
+    &path[..end]
+}
+
 /// 基于章节路径将相对资源路径解析为 EPUB 内绝对路径
-fn resolve_relative_path(section_path: &str, relative: &str) -> String {
+pub(super) fn resolve_relative_path(section_path: &str, relative: &str) -> String {
+    let relative = percent_decode_path(relative);
     // 取章节所在目录
     let base_dir = match section_path.rfind('/') {
         Some(pos) => &section_path[..pos],
@@ -312,6 +460,10 @@ fn collect_resource<R: std::io::Read + std::io::Seek>(
     resources: &mut Vec<PreparedResource>,
     refs: &mut Vec<String>,
 ) {
+    let path = strip_fragment(path);
+    if path.is_empty() {
+        return;
+    }
     if !seen.contains(path) {
         if let Some(data) = doc.get_resource_by_path(path) {
             let mime = doc
@@ -465,6 +617,8 @@ pub fn prepare_book(file_path: &str) -> Result<EpubPreparedBook, String> {
     let (title, author, description, publisher, language) = extract_metadata(&mut doc);
     let page_count = estimate_page_count(&doc);
     let cover_image = extract_cover_data(&mut doc);
+    let (series, series_index) = extract_series(&doc);
+    let (writing_mode, reading_direction) = super::layout::detect_reading_direction(&mut doc);
 
     let book_info = BookInfo {
         title,
@@ -475,11 +629,16 @@ pub fn prepare_book(file_path: &str) -> Result<EpubPreparedBook, String> {
         page_count,
         format: "epub".to_string(),
         cover_image,
+        series,
+        series_index,
+        writing_mode,
+        reading_direction,
     };
 
     let toc = resolve_toc(&mut doc);
 
-    let (sections, spine, resources, section_count) = extract_sections_and_resources(&mut doc)?;
+    let (mut sections, spine, resources, section_count) = extract_sections_and_resources(&mut doc)?;
+    let footnotes = super::footnotes::link_footnotes(&mut sections);
 
     // 若 nav/ncx 均无目录，基于 spine 生成伪目录作为最后兜底，保证目录抽屉可用
     let toc = if toc.is_empty() {
@@ -488,6 +647,8 @@ pub fn prepare_book(file_path: &str) -> Result<EpubPreparedBook, String> {
         toc
     };
 
+    let fixed_layout = super::layout::detect_fixed_layout(&mut doc, &sections, &resources);
+
     Ok(EpubPreparedBook {
         book_info,
         toc,
@@ -495,5 +656,7 @@ pub fn prepare_book(file_path: &str) -> Result<EpubPreparedBook, String> {
         spine,
         sections,
         resources,
+        fixed_layout,
+        footnotes,
     })
 }
\ No newline at end of file
@@ -0,0 +1,277 @@
+//! EPUB 脚注/尾注关联
+//!
+//! EPUB3 用 `<a epub:type="noteref">` 指向 `<aside epub:type="footnote">` 等注释元素，
+//! 也有书籍只在注释元素上声明语义、引用处是普通链接。本模块在章节解析后建立
+//! 「注释 ID → 注释 HTML」映射，并在引用处标注 `data-footnote-id`、在注释元素上标注
+//! `data-footnote`，供前端点击弹窗而不跳转。
+
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::engine::{resolve_relative_path, PreparedSection};
+use super::nav::extract_attr;
+use crate::formats::common::{mark_noteref_tag, Footnote};
+
+/// 注释元素可能使用的标签
+static NOTE_OPEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(aside|div|section|li|p|dd|span)\b([^>]*)>").unwrap());
+
+static LINK_OPEN_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<a\b([^>]*)>").unwrap());
+
+/// 行内元素作为跳转目标时，向外扩展到所在的块级元素
+static BLOCK_OPEN_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(p|li|div|aside|dd|section|blockquote)\b[^>]*>").unwrap());
+
+/// 单条注释 HTML 的长度上限，防止误把整章当成注释
+const MAX_FOOTNOTE_HTML_LEN: usize = 16 * 1024;
+
+/// 是否为脚注/尾注元素（`epub:type` 或 DPUB-ARIA `role`）
+fn is_note_element(attrs: &str) -> bool {
+    let types = extract_attr(attrs, "epub:type").unwrap_or_default();
+    let role = extract_attr(attrs, "role").unwrap_or_default();
+    types
+        .split_whitespace()
+        .any(|t| matches!(t, "footnote" | "endnote" | "rearnote" | "note"))
+        || matches!(role.trim(), "doc-footnote" | "doc-endnote")
+}
+
+/// 是否为脚注引用
+fn is_noteref(attrs: &str) -> bool {
+    let types = extract_attr(attrs, "epub:type").unwrap_or_default();
+    let role = extract_attr(attrs, "role").unwrap_or_default();
+    types.split_whitespace().any(|t| t == "noteref") || role.trim() == "doc-noteref"
+}
+
+/// 把链接地址解析为 `章节路径#id`；不含锚点的链接返回 None
+fn resolve_note_key(section_path: &str, href: &str) -> Option<String> {
+    let href = href.trim();
+    let (path, fragment) = href.split_once('#')?;
+    if fragment.is_empty() {
+        return None;
+    }
+    let path = if path.is_empty() {
+        section_path.to_string()
+    } else if let Some(full) = path.strip_prefix("__EPUB_RES__:").or_else(|| path.strip_prefix("epub://")) {
+        full.to_string()
+    } else if path.contains("://") {
+        return None;
+    } else {
+        resolve_relative_path(section_path, path)
+    };
+    Some(format!("{}#{}", path, fragment))
+}
+
+/// 从开始标签之后查找同名元素的结束位置（含结束标签），处理同名嵌套
+fn find_element_end(html: &str, tag: &str, content_start: usize) -> Option<usize> {
+    let lower = html[content_start..].to_ascii_lowercase();
+    let open = format!("<{}", tag);
+    let close = format!("</{}", tag);
+    let mut depth = 1usize;
+    let mut cursor = 0usize;
+    loop {
+        let next_close = lower[cursor..].find(&close)? + cursor;
+        let next_open = lower[cursor..].find(&open).map(|p| p + cursor);
+        match next_open {
+            // 只计入真正的同名标签（`<p` 不应匹配 `<pre`）
+            Some(p) if p < next_close => {
+                let after = lower.as_bytes().get(p + open.len()).copied();
+                if matches!(after, Some(b'>' | b' ' | b'\t' | b'\n' | b'\r' | b'/')) {
+                    depth += 1;
+                }
+                cursor = p + open.len();
+            }
+            _ => {
+                depth -= 1;
+                let tag_end = lower[next_close..].find('>')? + next_close + 1;
+                if depth == 0 {
+                    return Some(content_start + tag_end);
+                }
+                cursor = tag_end;
+            }
+        }
+    }
+}
+
+/// 章节内带 id 的脚注元素：id -> 外层 HTML
+fn collect_note_elements(html: &str) -> HashMap<String, String> {
+    let mut notes = HashMap::new();
+    for caps in NOTE_OPEN_RE.captures_iter(html) {
+        let attrs = &caps[2];
+        if !is_note_element(attrs) {
+            continue;
+        }
+        let Some(id) = extract_attr(attrs, "id") else {
+            continue;
+        };
+        let whole = caps.get(0).unwrap();
+        if let Some(end) = find_element_end(html, &caps[1].to_ascii_lowercase(), whole.end()) {
+            if end - whole.start() <= MAX_FOOTNOTE_HTML_LEN {
+                notes.insert(id, html[whole.start()..end].to_string());
+            }
+        }
+    }
+    notes
+}
+
+/// 按 id 查找任意元素；目标为行内元素（常见于 EPUB2 的 `<a id="fn1">`）时取所在块级元素
+fn find_element_by_id(html: &str, id: &str) -> Option<String> {
+    let pattern = format!(r#"(?is)<([a-z][a-z0-9]*)\b[^>]*\bid\s*=\s*["']{}["'][^>]*>"#, regex::escape(id));
+    let re = Regex::new(&pattern).ok()?;
+    let caps = re.captures(html)?;
+    let whole = caps.get(0).unwrap();
+    let tag = caps[1].to_ascii_lowercase();
+
+    let (start, tag, content_start) = if matches!(tag.as_str(), "a" | "span" | "sup" | "sub" | "em" | "strong" | "b" | "i") {
+        let block = BLOCK_OPEN_RE
+            .captures_iter(&html[..whole.start()])
+            .last()?;
+        let open = block.get(0).unwrap();
+        (open.start(), block[1].to_ascii_lowercase(), open.end())
+    } else {
+        (whole.start(), tag, whole.end())
+    };
+
+    let end = find_element_end(html, &tag, content_start)?;
+    (end > whole.start() && end - start <= MAX_FOOTNOTE_HTML_LEN).then(|| html[start..end].to_string())
+}
+
+/// 建立脚注映射并标注章节 HTML：引用处加 `data-footnote-id`，注释元素加 `data-footnote`
+pub fn link_footnotes(sections: &mut [PreparedSection]) -> Vec<Footnote> {
+    let path_to_index: HashMap<String, usize> =
+        sections.iter().enumerate().map(|(i, s)| (s.path.clone(), i)).collect();
+
+    let mut note_elements: HashMap<String, (u32, String)> = HashMap::new();
+    for section in sections.iter() {
+        for (id, html) in collect_note_elements(&section.html) {
+            note_elements.insert(format!("{}#{}", section.path, id), (section.index, html));
+        }
+    }
+
+    let mut footnotes: HashMap<String, Footnote> = HashMap::new();
+    for section in sections.iter() {
+        for caps in LINK_OPEN_RE.captures_iter(&section.html) {
+            let attrs = &caps[1];
+            let Some(key) = extract_attr(attrs, "href").and_then(|href| resolve_note_key(&section.path, &href)) else {
+                continue;
+            };
+            if footnotes.contains_key(&key) {
+                continue;
+            }
+            if let Some((section_index, html)) = note_elements.get(&key) {
+                footnotes.insert(
+                    key.clone(),
+                    Footnote {
+                        id: key,
+                        section_index: Some(*section_index),
+                        html: html.clone(),
+                    },
+                );
+                continue;
+            }
+            // 只有明确声明为 noteref 的链接才按 id 查找普通元素作为注释
+            if !is_noteref(attrs) {
+                continue;
+            }
+            let Some((path, id)) = key.split_once('#') else {
+                continue;
+            };
+            let Some(&target) = path_to_index.get(path) else {
+                continue;
+            };
+            if let Some(html) = find_element_by_id(&sections[target].html, id) {
+                footnotes.insert(
+                    key.clone(),
+                    Footnote {
+                        id: key,
+                        section_index: Some(sections[target].index),
+                        html,
+                    },
+                );
+            }
+        }
+    }
+
+    if footnotes.is_empty() {
+        return Vec::new();
+    }
+
+    for section in sections.iter_mut() {
+        let path = section.path.clone();
+        let html = LINK_OPEN_RE.replace_all(&section.html, |caps: &regex::Captures| {
+            let key = extract_attr(&caps[1], "href").and_then(|href| resolve_note_key(&path, &href));
+            match key.filter(|k| footnotes.contains_key(k)) {
+                Some(key) => mark_noteref_tag(&caps[0], &key),
+                None => caps[0].to_string(),
+            }
+        });
+        let html = NOTE_OPEN_RE.replace_all(&html, |caps: &regex::Captures| {
+            let key = extract_attr(&caps[2], "id")
+                .filter(|_| is_note_element(&caps[2]))
+                .map(|id| format!("{}#{}", path, id));
+            match key.filter(|k| footnotes.contains_key(k)) {
+                Some(key) => {
+                    let tag_len = caps[1].len() + 1;
+                    format!(r#"{} data-footnote="{}"{}"#, &caps[0][..tag_len], key.replace('&', "&amp;").replace('"', "&quot;"), &caps[0][tag_len..])
+                }
+                None => caps[0].to_string(),
+            }
+        });
+        section.html = html.into_owned();
+    }
+
+    let mut footnotes: Vec<Footnote> = footnotes.into_values().collect();
+    footnotes.sort_by(|a, b| a.section_index.cmp(&b.section_index).then_with(|| a.id.cmp(&b.id)));
+    println!("[EPUB] 关联脚注: {} 条", footnotes.len());
+    footnotes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(index: u32, path: &str, html: &str) -> PreparedSection {
+        PreparedSection {
+            index,
+            path: path.to_string(),
+            html: html.to_string(),
+            styles: Vec::new(),
+            resource_refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_link_footnotes() {
+        let mut sections = vec![
+            section(
+                0,
+                "OEBPS/ch1.xhtml",
+                r##"<p>正文<a epub:type="noteref" href="#fn1">1</a>，尾注<a href="__EPUB_RES__:OEBPS/notes.xhtml#en1">2</a>，旧式<a epub:type="noteref" href="notes.xhtml#old">3</a>，<a href="#sec">普通链接</a></p>
+                <aside epub:type="footnote" id="fn1"><p>脚注<aside>嵌套</aside>内容</p></aside><h2 id="sec">小节</h2>"##,
+            ),
+            section(
+                1,
+                "OEBPS/notes.xhtml",
+                r#"<ol><li epub:type="endnote" id="en1"><p>尾注内容</p></li></ol><p class="note"><a id="old" href="ch1.xhtml#r3">3</a> 旧式注释</p>"#,
+            ),
+        ];
+        let footnotes = link_footnotes(&mut sections);
+
+        let ids: Vec<&str> = footnotes.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["OEBPS/ch1.xhtml#fn1", "OEBPS/notes.xhtml#en1", "OEBPS/notes.xhtml#old"]);
+        assert_eq!(
+            footnotes[0].html,
+            r#"<aside epub:type="footnote" id="fn1"><p>脚注<aside>嵌套</aside>内容</p></aside>"#
+        );
+        assert_eq!(footnotes[1].html, r#"<li epub:type="endnote" id="en1"><p>尾注内容</p></li>"#);
+        assert_eq!(footnotes[2].html, r#"<p class="note"><a id="old" href="ch1.xhtml#r3">3</a> 旧式注释</p>"#);
+
+        let html = &sections[0].html;
+        assert!(html.contains(r##"<a data-footnote-id="OEBPS/ch1.xhtml#fn1" epub:type="noteref" href="#fn1">"##));
+        assert!(html.contains(r#"<a data-footnote-id="OEBPS/notes.xhtml#old" epub:type="noteref""#));
+        assert!(html.contains(r#"<aside data-footnote="OEBPS/ch1.xhtml#fn1" epub:type="footnote""#));
+        assert!(html.contains(r##"<a href="#sec">"##));
+        assert!(sections[1].html.contains(r#"<li data-footnote="OEBPS/notes.xhtml#en1""#));
+    }
+}
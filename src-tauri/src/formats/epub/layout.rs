@@ -0,0 +1,391 @@
+//! EPUB 固定布局（fixed-layout）识别
+//!
+//! 漫画、绘本等书籍在 OPF 中声明 `rendition:layout=pre-paginated`，
+//! 每个 spine 项是一张按绝对尺寸排版的整页，不能按流式 HTML 重排。
+//! 本模块读取 OPF 的全局与逐项布局声明，并从页面 `<meta name="viewport">`、
+//! SVG `viewBox` 或整页图片尺寸推断每页视口，供前端按固定比例显示。
+//! 同时识别竖排、从右到左等书写模式与翻页方向。
+
+use epub::doc::EpubDoc;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::engine::{PreparedResource, PreparedSection};
+use super::nav::extract_attr;
+
+/// 缺少任何尺寸声明时使用的默认视口（3:4 竖版）
+pub const DEFAULT_FIXED_VIEWPORT: (u32, u32) = (1200, 1600);
+
+/// 固定布局单页信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixedLayoutPage {
+    /// spine 序号
+    pub index: u32,
+    /// 该页是否按固定布局显示（混排书籍中部分页面可能为流式）
+    pub fixed: bool,
+    /// 视口宽度（CSS 像素）
+    pub width: u32,
+    /// 视口高度（CSS 像素）
+    pub height: u32,
+    /// 整页只有一张图片时的资源路径，前端可直接按图片显示
+    pub image: Option<String>,
+}
+
+/// 固定布局书籍信息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpubFixedLayout {
+    /// OPF 全局声明为 pre-paginated
+    pub pre_paginated: bool,
+    /// 跨页展示方式（`rendition:spread`），如 `auto`、`landscape`、`none`
+    pub spread: Option<String>,
+    /// 书籍级默认视口，页面未声明尺寸时使用
+    pub default_width: u32,
+    pub default_height: u32,
+    /// 按 spine 顺序排列的页面信息
+    pub pages: Vec<FixedLayoutPage>,
+}
+
+/// OPF 中的布局声明
+#[derive(Debug, Default)]
+struct OpfLayout {
+    pre_paginated: bool,
+    spread: Option<String>,
+    /// `<meta name="original-resolution" content="WxH">`（iBooks/Kobo 约定）
+    original_resolution: Option<(u32, u32)>,
+    /// 逐个 itemref 的布局覆盖：Some(true) 为固定，Some(false) 为流式
+    itemref_layouts: Vec<Option<bool>>,
+}
+
+/// 未声明书写模式时的默认值
+pub const DEFAULT_WRITING_MODE: &str = "horizontal-tb";
+/// 未声明翻页方向时的默认值
+pub const DEFAULT_READING_DIRECTION: &str = "ltr";
+
+/// 读取 OPF 根文件文本
+fn read_opf_text<R: std::io::Read + std::io::Seek>(doc: &mut EpubDoc<R>) -> Option<String> {
+    let root_file = doc.root_file.to_string_lossy().to_string();
+    let opf_bytes = doc.get_resource_by_path(&root_file)?;
+    String::from_utf8(opf_bytes).ok()
+}
+
+/// 识别书写模式与翻页方向，返回 (`writing_mode`, `reading_direction`)
+/// 日文竖排、阿拉伯文等从右到左的书籍据此切换布局；无法识别时为 horizontal-tb / ltr
+pub fn detect_reading_direction<R: std::io::Read + std::io::Seek>(doc: &mut EpubDoc<R>) -> (String, String) {
+    let (writing_mode, direction) = read_opf_text(doc)
+        .map(|opf| parse_opf_direction(&opf))
+        .unwrap_or((None, None));
+    (
+        writing_mode.unwrap_or(DEFAULT_WRITING_MODE).to_string(),
+        direction.unwrap_or(DEFAULT_READING_DIRECTION).to_string(),
+    )
+}
+
+/// 解析 spine 的 `page-progression-direction` 与 `<meta name="primary-writing-mode">`
+/// spine 明确声明的方向优先，否则由书写模式推断（竖排右起、横排右起为 rtl）
+fn parse_opf_direction(opf: &str) -> (Option<&'static str>, Option<&'static str>) {
+    let mut writing_mode = None;
+    let mut implied_direction = None;
+    let meta_re = Regex::new(r#"(?is)<meta\b([^>]*?)/?>"#).unwrap();
+    for caps in meta_re.captures_iter(opf) {
+        let attrs = &caps[1];
+        if extract_attr(attrs, "name").as_deref() != Some("primary-writing-mode") {
+            continue;
+        }
+        let content = extract_attr(attrs, "content").unwrap_or_default();
+        (writing_mode, implied_direction) = match content.trim().to_ascii_lowercase().as_str() {
+            "horizontal-tb" | "horizontal-lr" => (Some("horizontal-tb"), Some("ltr")),
+            "horizontal-rl" => (Some("horizontal-tb"), Some("rtl")),
+            "vertical-rl" => (Some("vertical-rl"), Some("rtl")),
+            "vertical-lr" => (Some("vertical-lr"), Some("ltr")),
+            _ => continue,
+        };
+        break;
+    }
+
+    let spine_re = Regex::new(r#"(?is)<spine\b([^>]*)>"#).unwrap();
+    let spine_direction = spine_re
+        .captures(opf)
+        .and_then(|caps| extract_attr(&caps[1], "page-progression-direction"))
+        .and_then(|value| match value.trim().to_ascii_lowercase().as_str() {
+            "rtl" => Some("rtl"),
+            "ltr" => Some("ltr"),
+            _ => None,
+        });
+
+    (writing_mode, spine_direction.or(implied_direction))
+}
+
+/// 识别固定布局；OPF 未声明任何固定布局页面时返回 None
+pub fn detect_fixed_layout<R: std::io::Read + std::io::Seek>(
+    doc: &mut EpubDoc<R>,
+    sections: &[PreparedSection],
+    resources: &[PreparedResource],
+) -> Option<EpubFixedLayout> {
+    let opf_text = read_opf_text(doc)?;
+    let opf = parse_opf_layout(&opf_text);
+
+    let layout = build_fixed_layout(&opf, sections, |path| {
+        resources
+            .iter()
+            .find(|res| res.path == path)
+            .and_then(|res| image_dimensions(&res.data))
+    })?;
+
+    println!(
+        "[EPUB] 识别为固定布局: pre_paginated={}, pages={}, viewport={}x{}",
+        layout.pre_paginated,
+        layout.pages.iter().filter(|p| p.fixed).count(),
+        layout.default_width,
+        layout.default_height
+    );
+    Some(layout)
+}
+
+fn build_fixed_layout(
+    opf: &OpfLayout,
+    sections: &[PreparedSection],
+    image_size: impl Fn(&str) -> Option<(u32, u32)>,
+) -> Option<EpubFixedLayout> {
+    let is_fixed = |index: u32| {
+        opf.itemref_layouts
+            .get(index as usize)
+            .copied()
+            .flatten()
+            .unwrap_or(opf.pre_paginated)
+    };
+    if !sections.iter().any(|s| is_fixed(s.index)) {
+        return None;
+    }
+
+    let (default_width, default_height) = opf.original_resolution.unwrap_or(DEFAULT_FIXED_VIEWPORT);
+    let pages = sections
+        .iter()
+        .map(|section| {
+            let image = find_page_image(&section.html);
+            let (width, height) = parse_viewport(&section.html)
+                .or_else(|| image.as_deref().and_then(&image_size))
+                .unwrap_or((default_width, default_height));
+            FixedLayoutPage {
+                index: section.index,
+                fixed: is_fixed(section.index),
+                width,
+                height,
+                image,
+            }
+        })
+        .collect();
+
+    Some(EpubFixedLayout {
+        pre_paginated: opf.pre_paginated,
+        spread: opf.spread.clone(),
+        default_width,
+        default_height,
+        pages,
+    })
+}
+
+fn parse_opf_layout(opf: &str) -> OpfLayout {
+    let mut layout = OpfLayout::default();
+
+    // EPUB3：<meta property="rendition:layout">pre-paginated</meta>
+    let property_re = Regex::new(r#"(?is)<meta\b([^>]*)>([^<]*)</meta>"#).unwrap();
+    for caps in property_re.captures_iter(opf) {
+        let value = caps[2].trim();
+        match extract_attr(&caps[1], "property").as_deref() {
+            Some("rendition:layout") => layout.pre_paginated = value == "pre-paginated",
+            Some("rendition:spread") if !value.is_empty() => layout.spread = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    // EPUB2 扩展：<meta name="fixed-layout" content="true"/>、<meta name="original-resolution" content="WxH"/>
+    let name_re = Regex::new(r#"(?is)<meta\b([^>]*?)/?>"#).unwrap();
+    for caps in name_re.captures_iter(opf) {
+        let attrs = &caps[1];
+        let (Some(name), Some(content)) = (extract_attr(attrs, "name"), extract_attr(attrs, "content")) else {
+            continue;
+        };
+        match name.as_str() {
+            "fixed-layout" if content.trim().eq_ignore_ascii_case("true") => layout.pre_paginated = true,
+            "original-resolution" => layout.original_resolution = parse_resolution(&content),
+            _ => {}
+        }
+    }
+
+    let itemref_re = Regex::new(r#"(?is)<itemref\b([^>]*?)/?>"#).unwrap();
+    layout.itemref_layouts = itemref_re
+        .captures_iter(opf)
+        .map(|caps| {
+            let properties = extract_attr(&caps[1], "properties").unwrap_or_default();
+            properties.split_whitespace().find_map(|p| match p {
+                "rendition:layout-pre-paginated" => Some(true),
+                "rendition:layout-reflowable" => Some(false),
+                _ => None,
+            })
+        })
+        .collect();
+
+    layout
+}
+
+/// 解析 `1200x1600` 形式的分辨率
+fn parse_resolution(value: &str) -> Option<(u32, u32)> {
+    let (w, h) = value.trim().split_once(['x', 'X'])?;
+    valid_size(w.trim().parse().ok()?, h.trim().parse().ok()?)
+}
+
+fn valid_size(width: u32, height: u32) -> Option<(u32, u32)> {
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+/// 从页面 `<meta name="viewport" content="width=..., height=...">` 或 SVG `viewBox` 读取尺寸
+fn parse_viewport(html: &str) -> Option<(u32, u32)> {
+    let meta_re = Regex::new(r#"(?is)<meta\b([^>]*?)/?>"#).unwrap();
+    for caps in meta_re.captures_iter(html) {
+        let attrs = &caps[1];
+        if !extract_attr(attrs, "name").is_some_and(|n| n.eq_ignore_ascii_case("viewport")) {
+            continue;
+        }
+        let content = extract_attr(attrs, "content").unwrap_or_default();
+        let mut width = None;
+        let mut height = None;
+        for pair in content.split([',', ';']) {
+            let Some((key, value)) = pair.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_end_matches("px").parse::<f32>().ok();
+            match key.trim() {
+                "width" => width = value,
+                "height" => height = value,
+                _ => {}
+            }
+        }
+        if let (Some(w), Some(h)) = (width, height) {
+            return valid_size(w.round() as u32, h.round() as u32);
+        }
+    }
+
+    let svg_re = Regex::new(r#"(?is)<svg\b([^>]*)>"#).unwrap();
+    let view_box = extract_attr(&svg_re.captures(html)?[1], "viewBox")?;
+    let numbers: Vec<f32> = view_box
+        .split([' ', ','])
+        .filter_map(|n| n.trim().parse().ok())
+        .collect();
+    match numbers[..] {
+        [_, _, w, h] => valid_size(w.round() as u32, h.round() as u32),
+        _ => None,
+    }
+}
+
+/// 页面正文没有文字且只引用一张图片时返回该图片的资源路径
+fn find_page_image(html: &str) -> Option<String> {
+    let body_re = Regex::new(r"(?is)<body\b[^>]*>(.*)</body>").unwrap();
+    let body = body_re.captures(html).map_or(html, |c| c.get(1).unwrap().as_str());
+
+    let tag_re = Regex::new(r"(?s)<[^>]*>").unwrap();
+    if !tag_re.replace_all(body, "").trim().is_empty() {
+        return None;
+    }
+
+    let image_re =
+        Regex::new(r#"(?is)<(?:img|image)\b[^>]*?(?:src|href)\s*=\s*["']__EPUB_RES__:([^"']+)["']"#)
+            .unwrap();
+    let mut images = image_re.captures_iter(body).map(|c| c[1].to_string());
+    let first = images.next()?;
+    images.next().is_none().then_some(first)
+}
+
+/// 只读取图片头部获取尺寸
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::io::Reader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(index: u32, html: &str) -> PreparedSection {
+        PreparedSection {
+            index,
+            path: format!("page{}.xhtml", index),
+            html: html.to_string(),
+            styles: Vec::new(),
+            resource_refs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_opf_layout() {
+        let opf = r#"
+        <package><metadata>
+          <meta property="rendition:layout">pre-paginated</meta>
+          <meta property="rendition:spread">landscape</meta>
+          <meta name="original-resolution" content="1072x1448"/>
+        </metadata><spine>
+          <itemref idref="p1"/>
+          <itemref idref="p2" properties="page-spread-left rendition:layout-reflowable"/>
+        </spine></package>
+        "#;
+        let layout = parse_opf_layout(opf);
+        assert!(layout.pre_paginated);
+        assert_eq!(layout.spread.as_deref(), Some("landscape"));
+        assert_eq!(layout.original_resolution, Some((1072, 1448)));
+        assert_eq!(layout.itemref_layouts, vec![None, Some(false)]);
+    }
+
+    #[test]
+    fn test_parse_opf_direction() {
+        let vertical = r#"<package><metadata>
+          <meta name="primary-writing-mode" content="vertical-rl"/>
+        </metadata><spine toc="ncx"><itemref idref="p1"/></spine></package>"#;
+        assert_eq!(parse_opf_direction(vertical), (Some("vertical-rl"), Some("rtl")));
+
+        let arabic = r#"<package><spine page-progression-direction="rtl"><itemref idref="p1"/></spine></package>"#;
+        assert_eq!(parse_opf_direction(arabic), (None, Some("rtl")));
+
+        let explicit_ltr = r#"<package><metadata><meta name="primary-writing-mode" content="vertical-rl"/></metadata>
+          <spine page-progression-direction="ltr"></spine></package>"#;
+        assert_eq!(parse_opf_direction(explicit_ltr), (Some("vertical-rl"), Some("ltr")));
+
+        let plain = r#"<package><spine page-progression-direction="default"></spine></package>"#;
+        assert_eq!(parse_opf_direction(plain), (None, None));
+    }
+
+    #[test]
+    fn test_build_fixed_layout_page_sizes() {
+        let opf = OpfLayout {
+            pre_paginated: true,
+            ..Default::default()
+        };
+        let sections = vec![
+            section(
+                0,
+                r#"<html><head><meta name="viewport" content="width=800, height=1200"/></head>
+                <body><div><img src="__EPUB_RES__:images/p1.jpg" alt=""/></div></body></html>"#,
+            ),
+            section(
+                1,
+                r#"<html><body><svg viewBox="0 0 600 900"><image xlink:href="__EPUB_RES__:images/p2.jpg"/></svg></body></html>"#,
+            ),
+            section(2, r#"<html><body><img src="__EPUB_RES__:images/p3.jpg"/></body></html>"#),
+            section(3, r#"<html><body><p>版权页</p></body></html>"#),
+        ];
+        let layout = build_fixed_layout(&opf, &sections, |path| {
+            (path == "images/p3.jpg").then_some((1000, 1500))
+        })
+        .unwrap();
+
+        let sizes: Vec<(u32, u32)> = layout.pages.iter().map(|p| (p.width, p.height)).collect();
+        assert_eq!(sizes, vec![(800, 1200), (600, 900), (1000, 1500), DEFAULT_FIXED_VIEWPORT]);
+        assert_eq!(layout.pages[0].image.as_deref(), Some("images/p1.jpg"));
+        assert_eq!(layout.pages[1].image.as_deref(), Some("images/p2.jpg"));
+        assert!(layout.pages[3].image.is_none());
+
+        let reflowable = OpfLayout::default();
+        assert!(build_fixed_layout(&reflowable, &sections, |_| None).is_none());
+    }
+}
@@ -1,8 +1,11 @@
 pub mod cache;
 pub mod engine;
+pub mod footnotes;
+pub mod layout;
 pub mod nav;
 
 pub use cache::{
     BookInfo, CacheStats, EpubCacheManager, MetadataCacheEntry, SectionCacheData, TocItem,
 };
-pub use engine::EpubInspectResult;
+pub use engine::{percent_decode_path, prepare_book, read_spine, EpubInspectResult, EpubPreparedBook};
+pub use layout::EpubFixedLayout;
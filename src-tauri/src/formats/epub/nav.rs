@@ -124,7 +124,7 @@ fn attr_contains_nav_property(attrs: &str) -> bool {
 }
 
 /// 从属性字符串中提取指定属性的值，兼容单双引号
-fn extract_attr(attrs: &str, name: &str) -> Option<String> {
+pub(crate) fn extract_attr(attrs: &str, name: &str) -> Option<String> {
     let pattern = format!(r#"(?is)\b{}\s*=\s*(?:"([^"]*)"|'([^']*)')"#, regex::escape(name));
     let re = Regex::new(&pattern).ok()?;
     let caps = re.captures(attrs)?;
@@ -7,6 +7,10 @@ use chardetng::EncodingDetector;
 use encoding_rs::Encoding;
 use crate::formats::{BookError, BookErrorCode};
 
+pub mod resources;
+
+pub use resources::{HtmlContentWithResources, HtmlResource};
+
 /// HTML 引擎
 pub struct HtmlEngine {
     /// 文件内容
@@ -40,6 +44,12 @@ impl HtmlEngine {
         &self.content
     }
 
+    /// 获取处理过本地资源的 HTML 内容：同目录小图内联为 data URL，大图和 CSS 改写为 `goread-res` 协议地址，
+    /// 外部 http(s) 链接保持原样
+    pub fn get_content_with_resources(&self) -> HtmlContentWithResources {
+        resources::inline_resources(&self.file_path, &self.content)
+    }
+
     /// 获取检测到的编码
     pub fn get_encoding(&self) -> &str {
         &self.encoding
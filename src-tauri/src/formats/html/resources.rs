@@ -0,0 +1,230 @@
+//! HTML 本地资源处理
+//! 单文件 HTML 引用的同目录图片/CSS 在 WebView 中可能因权限或路径拿不到：
+//! 小图直接内联为 data URL，大图和 CSS 改写为 `goread-res` 协议地址，由协议处理器从 HTML 所在目录读取
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose, Engine as _};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::formats::epub::percent_decode_path;
+use crate::resource_protocol::resource_url;
+
+/// 单张图片内联的大小上限，更大的走自定义协议
+const MAX_INLINE_BYTES: u64 = 256 * 1024;
+
+/// 单个文档内联资源的总大小上限，超出后其余图片改走自定义协议
+const MAX_TOTAL_INLINE_BYTES: u64 = 8 * 1024 * 1024;
+
+/// 通过协议提供的单个资源大小上限，超过的保持原样不处理
+const MAX_RESOURCE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// 引用资源的属性：src、href、xlink:href
+static RESOURCE_ATTR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)(\s(?:src|href|xlink:href)\s*=\s*)(?:"([^"]*)"|'([^']*)')"#).unwrap()
+});
+
+/// 已登记的 HTML 资源目录：协议中的 book_id（即 HTML 文件路径）-> 规范化后的所在目录
+static HTML_RESOURCE_ROOTS: Lazy<Mutex<HashMap<String, PathBuf>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// 资源的提供方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HtmlResourceMode {
+    /// 内联为 data URL
+    Inline,
+    /// 通过 `goread-res` 协议按需加载
+    Protocol,
+}
+
+/// 资源表中的单项
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlResource {
+    /// HTML 中的原始引用
+    pub path: String,
+    pub mime_type: String,
+    pub size: u64,
+    pub mode: HtmlResourceMode,
+}
+
+/// 处理后的 HTML 与资源表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlContentWithResources {
+    pub content: String,
+    pub resources: Vec<HtmlResource>,
+}
+
+/// 按扩展名判断可处理的资源类型，其他引用（如链接到别的 HTML）保持原样
+fn resource_mime(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "avif" => "image/avif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "css" => "text/css",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        _ => return None,
+    })
+}
+
+/// 把 HTML 中的引用解析为相对路径；外部链接、data URL、页内锚点等返回 None
+fn local_reference(reference: &str) -> Option<String> {
+    let reference = reference.trim();
+    let lower = reference.to_ascii_lowercase();
+    if reference.is_empty()
+        || reference.starts_with('#')
+        || reference.starts_with("//")
+        || reference.starts_with('/')
+        || lower.contains("://")
+        || ["data:", "mailto:", "javascript:", "blob:", "tel:"].iter().any(|p| lower.starts_with(p))
+    {
+        return None;
+    }
+    let path = reference.split(['?', '#']).next().unwrap_or_default();
+    let path = percent_decode_path(path);
+    (!path.is_empty()).then_some(path)
+}
+
+/// 在 `root` 内解析相对路径，越出目录（`..`、符号链接）时返回 None
+fn resolve_within(root: &Path, relative: &str) -> Option<PathBuf> {
+    let resolved = root.join(relative).canonicalize().ok()?;
+    (resolved.starts_with(root) && resolved.is_file()).then_some(resolved)
+}
+
+/// 为 HTML 文件登记资源目录，返回协议中使用的 book_id
+fn register_resource_root(file_path: &str, root: PathBuf) -> String {
+    if let Ok(mut roots) = HTML_RESOURCE_ROOTS.lock() {
+        roots.insert(file_path.to_string(), root);
+    }
+    file_path.to_string()
+}
+
+/// 协议处理器调用：读取已登记 HTML 目录下的资源，未登记或不在目录内时返回 None
+pub fn load_registered_resource(book_id: &str, resource_path: &str) -> Option<(Vec<u8>, String)> {
+    let root = HTML_RESOURCE_ROOTS.lock().ok()?.get(book_id).cloned()?;
+    let path = resolve_within(&root, resource_path)?;
+    let mime = resource_mime(&path)?;
+    if std::fs::metadata(&path).ok()?.len() > MAX_RESOURCE_BYTES {
+        return None;
+    }
+    let data = std::fs::read(&path).ok()?;
+    Some((data, mime.to_string()))
+}
+
+/// 改写 HTML 中引用的同目录资源，返回处理后的 HTML 与资源表
+pub(super) fn inline_resources(file_path: &str, content: &str) -> HtmlContentWithResources {
+    let Some(root) = Path::new(file_path).parent().and_then(|dir| dir.canonicalize().ok()) else {
+        return HtmlContentWithResources {
+            content: content.to_string(),
+            resources: Vec::new(),
+        };
+    };
+
+    let mut resources: Vec<HtmlResource> = Vec::new();
+    let mut replacements: HashMap<String, Option<String>> = HashMap::new();
+    let mut inline_total = 0u64;
+    let mut book_id: Option<String> = None;
+
+    let html = RESOURCE_ATTR_RE.replace_all(content, |caps: &regex::Captures| {
+        let original = caps.get(2).or_else(|| caps.get(3)).map_or("", |m| m.as_str());
+        let quote = if caps.get(2).is_some() { '"' } else { '\'' };
+
+        let replacement = replacements
+            .entry(original.to_string())
+            .or_insert_with(|| {
+                let relative = local_reference(original)?;
+                let path = resolve_within(&root, &relative)?;
+                let mime = resource_mime(&path)?;
+                let size = std::fs::metadata(&path).ok()?.len();
+                if size > MAX_RESOURCE_BYTES {
+                    return None;
+                }
+
+                let inline = mime.starts_with("image/")
+                    && size <= MAX_INLINE_BYTES
+                    && inline_total + size <= MAX_TOTAL_INLINE_BYTES;
+                let data = if inline { std::fs::read(&path).ok() } else { None };
+                let (url, mode) = match data {
+                    Some(data) => {
+                        inline_total += size;
+                        (
+                            format!("data:{};base64,{}", mime, general_purpose::STANDARD.encode(&data)),
+                            HtmlResourceMode::Inline,
+                        )
+                    }
+                    None => {
+                        let id = book_id.get_or_insert_with(|| register_resource_root(file_path, root.clone()));
+                        let relative_path = path.strip_prefix(&root).ok()?.to_string_lossy().replace('\\', "/");
+                        (resource_url(id, &relative_path), HtmlResourceMode::Protocol)
+                    }
+                };
+                resources.push(HtmlResource {
+                    path: original.to_string(),
+                    mime_type: mime.to_string(),
+                    size,
+                    mode,
+                });
+                Some(url)
+            })
+            .clone();
+
+        match replacement {
+            Some(url) => format!("{}{}{}{}", &caps[1], quote, url, quote),
+            None => caps[0].to_string(),
+        }
+    });
+
+    println!(
+        "[HTML] 资源处理: 共 {} 个，内联 {} 字节",
+        resources.len(),
+        inline_total
+    );
+    HtmlContentWithResources {
+        content: html.into_owned(),
+        resources,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_resources() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("img")).unwrap();
+        std::fs::write(dir.join("img/a b.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        std::fs::write(dir.join("style.css"), "body{}").unwrap();
+        let html_path = dir.join("index.html");
+        let file_path = html_path.to_string_lossy().to_string();
+
+        let html = r#"<link rel="stylesheet" href="style.css"><img src="img/a%20b.png"><img src='https://example.com/x.png'><a href="next.html">下一页</a><img src="../secret.png">"#;
+        let result = inline_resources(&file_path, html);
+
+        assert!(result.content.contains(r#"src="data:image/png;base64,iVBORw==""#));
+        assert!(result.content.contains("https://example.com/x.png"));
+        assert!(result.content.contains(r#"href="next.html""#));
+        assert!(result.content.contains(r#"src="../secret.png""#));
+        assert!(!result.content.contains(r#"href="style.css""#));
+        assert_eq!(result.resources.len(), 2);
+        assert_eq!(result.resources[0].mode, HtmlResourceMode::Protocol);
+        assert_eq!(result.resources[1].mode, HtmlResourceMode::Inline);
+
+        let (data, mime) = load_registered_resource(&file_path, "style.css").unwrap();
+        assert_eq!((data.as_slice(), mime.as_str()), (b"body{}".as_slice(), "text/css"));
+        assert!(load_registered_resource(&file_path, "../index.html").is_none());
+    }
+}
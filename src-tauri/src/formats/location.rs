@@ -0,0 +1,209 @@
+//! 流式书籍（EPUB/MOBI）位置字符串的生成与解析
+//! 目录与书签使用同一套位置格式：
+//! - `section:{章节索引}` 或 `section:{章节索引}#{锚点}`（MOBI 目录，锚点如 `filepos12345`）
+//! - 包内路径加可选锚点，如 `OEBPS/Text/ch01.xhtml#note1`（EPUB 目录）
+//! - EPUB CFI，如 `epubcfi(/6/4[chap01]!/4/2[para05]/1:10)`
+//!
+//! 解析结果统一为“章节索引 + 章内锚点”，无法精确解析时退回最接近的章节起始
+
+use serde::{Deserialize, Serialize};
+
+use crate::formats::epub::percent_decode_path;
+
+/// 解析后的位置：前端加载 `section_index` 章节后滚动到 `anchor_id` 元素
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolvedLocation {
+    pub section_index: u32,
+    pub anchor_id: Option<String>,
+    /// CFI 末尾的字符偏移（相对于 `anchor_id` 元素内的目标文本节点）
+    pub char_offset: Option<u64>,
+    /// 是否精确解析；为 false 时表示已退回到最接近的章节起始
+    pub exact: bool,
+}
+
+impl ResolvedLocation {
+    fn section_start(section_index: u32) -> Self {
+        Self {
+            section_index,
+            anchor_id: None,
+            char_offset: None,
+            exact: false,
+        }
+    }
+}
+
+/// 生成 `section:` 位置字符串
+pub fn section_location(section_index: impl std::fmt::Display, anchor: Option<&str>) -> String {
+    match anchor.filter(|a| !a.is_empty()) {
+        Some(anchor) => format!("section:{}#{}", section_index, anchor),
+        None => format!("section:{}", section_index),
+    }
+}
+
+/// 解析位置字符串；`spine` 为 EPUB 的章节路径列表（MOBI 传空，此时不校验章节范围）
+pub fn resolve_location(location: &str, spine: &[String]) -> ResolvedLocation {
+    let location = location.trim();
+    let clamp = |index: u32| -> u32 {
+        if spine.is_empty() {
+            index
+        } else {
+            index.min(spine.len() as u32 - 1)
+        }
+    };
+
+    if let Some(rest) = location.strip_prefix("section:") {
+        let (index, anchor) = split_fragment(rest);
+        return match index.trim().parse::<u32>() {
+            Ok(index) => ResolvedLocation {
+                section_index: clamp(index),
+                anchor_id: anchor.map(str::to_string),
+                char_offset: None,
+                exact: clamp(index) == index,
+            },
+            Err(_) => ResolvedLocation::section_start(0),
+        };
+    }
+
+    if let Some(cfi) = location.strip_prefix("epubcfi(").and_then(|c| c.strip_suffix(')')) {
+        return match parse_cfi(cfi) {
+            Some(mut resolved) => {
+                let index = resolved.section_index;
+                resolved.section_index = clamp(index);
+                resolved.exact &= resolved.section_index == index;
+                resolved
+            }
+            None => ResolvedLocation::section_start(0),
+        };
+    }
+
+    if let Ok(index) = location.parse::<u32>() {
+        return ResolvedLocation {
+            section_index: clamp(index),
+            anchor_id: None,
+            char_offset: None,
+            exact: clamp(index) == index,
+        };
+    }
+
+    resolve_href(location, spine)
+}
+
+fn split_fragment(value: &str) -> (&str, Option<&str>) {
+    match value.split_once('#') {
+        Some((head, fragment)) if !fragment.is_empty() => (head, Some(fragment)),
+        Some((head, _)) => (head, None),
+        None => (value, None),
+    }
+}
+
+/// 解析 CFI：第二步（package 下的 spine 引用）换算章节索引，`!` 之后最后一个带 id 断言的步骤作为锚点
+fn parse_cfi(cfi: &str) -> Option<ResolvedLocation> {
+    let (package_path, content_path) = match cfi.split_once('!') {
+        Some((package, content)) => (package, Some(content)),
+        None => (cfi, None),
+    };
+    let spine_step: u32 = package_path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .nth(1)
+        .map(|step| step.split(['[', ':']).next().unwrap_or(step))?
+        .parse()
+        .ok()?;
+    // spine 引用的步骤为偶数，第 n 个 itemref 为 2n
+    if spine_step < 2 {
+        return None;
+    }
+    let section_index = spine_step / 2 - 1;
+
+    let mut anchor_id = None;
+    let mut char_offset = None;
+    if let Some(content) = content_path {
+        for step in content.split('/').filter(|s| !s.is_empty()) {
+            if let Some(id) = step.split_once('[').and_then(|(_, rest)| rest.split_once(']')).map(|(id, _)| id) {
+                if !id.is_empty() {
+                    anchor_id = Some(id.to_string());
+                    char_offset = None;
+                }
+            }
+            if let Some((_, offset)) = step.split_once(':') {
+                char_offset = offset.split(['[', '~', '@']).next().and_then(|o| o.parse().ok());
+            }
+        }
+    }
+
+    Some(ResolvedLocation {
+        section_index,
+        anchor_id,
+        char_offset,
+        exact: true,
+    })
+}
+
+/// 包内路径匹配 spine：先比较完整路径，再允许一方是另一方的后缀（目录里常见相对路径）；
+/// 都不匹配时取文件名公共前缀最长的章节（如被拆分的 `ch05_split_001.xhtml`）
+fn resolve_href(href: &str, spine: &[String]) -> ResolvedLocation {
+    let (path, anchor) = split_fragment(href);
+    let path = percent_decode_path(path.trim_start_matches("./"));
+    let anchor_id = anchor.map(percent_decode_path);
+    if spine.is_empty() || path.is_empty() {
+        return ResolvedLocation::section_start(0);
+    }
+
+    let is_suffix = |long: &str, short: &str| long.ends_with(short) && long[..long.len() - short.len()].ends_with('/');
+    let matched = spine
+        .iter()
+        .position(|s| *s == path)
+        .or_else(|| spine.iter().position(|s| is_suffix(s, &path) || is_suffix(&path, s)));
+    if let Some(index) = matched {
+        return ResolvedLocation {
+            section_index: index as u32,
+            anchor_id,
+            char_offset: None,
+            exact: true,
+        };
+    }
+
+    let file_name = |p: &str| p.rsplit('/').next().unwrap_or(p).to_string();
+    let target = file_name(&path);
+    let nearest = spine
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let common = file_name(s).chars().zip(target.chars()).take_while(|(a, b)| a == b).count();
+            (i, common)
+        })
+        .filter(|&(_, common)| common > 0)
+        .max_by_key(|&(i, common)| (common, std::cmp::Reverse(i)))
+        .map_or(0, |(i, _)| i as u32);
+    ResolvedLocation::section_start(nearest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_location() {
+        let spine: Vec<String> = ["OEBPS/Text/cover.xhtml", "OEBPS/Text/ch01.xhtml", "OEBPS/Text/ch02_split_000.xhtml"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let mobi = resolve_location(&section_location(3, Some("filepos12345")), &[]);
+        assert_eq!((mobi.section_index, mobi.anchor_id.as_deref(), mobi.exact), (3, Some("filepos12345"), true));
+
+        let href = resolve_location("Text/ch01.xhtml#note%201", &spine);
+        assert_eq!((href.section_index, href.anchor_id.as_deref(), href.exact), (1, Some("note 1"), true));
+        // 拆分后的文件找不到原路径时退回文件名最接近的章节起始
+        let nearest = resolve_location("OEBPS/Text/ch02.xhtml#p3", &spine);
+        assert_eq!((nearest.section_index, nearest.anchor_id, nearest.exact), (2, None, false));
+
+        let cfi = resolve_location("epubcfi(/6/4[ch01]!/4[body01]/10[para05]/3:10)", &spine);
+        assert_eq!((cfi.section_index, cfi.anchor_id.as_deref(), cfi.char_offset), (1, Some("para05"), Some(10)));
+
+        // 超出范围的章节夹到最后一章
+        let clamped = resolve_location("section:9", &spine);
+        assert_eq!((clamped.section_index, clamped.exact), (2, false));
+        assert_eq!(resolve_location("garbage", &[]).section_index, 0);
+    }
+}
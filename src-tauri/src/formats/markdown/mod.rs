@@ -102,6 +102,8 @@ impl MarkdownEngine {
             cover_image: None,
             page_count: 1, // Markdown 视为单页滚动
             format: Some(BookFormat::Markdown),
+            series: None,
+            series_index: None,
         }
     }
 
@@ -164,6 +166,151 @@ impl MarkdownEngine {
 
         results
     }
+
+    /// 结构分析：标注代码块语言、数学公式、表格与 Mermaid 图，供前端按需加载高亮/公式等重型依赖
+    pub fn analyze_content(&self) -> MarkdownContentAnalysis {
+        let mut analysis = MarkdownContentAnalysis::default();
+        // 当前所在围栏代码块的围栏字符与长度
+        let mut fence: Option<(char, usize)> = None;
+        let mut prev_line = "";
+
+        for line in self.content.lines() {
+            let trimmed = line.trim();
+            if let Some((fence_char, fence_len)) = fence {
+                // 闭合围栏：同种字符、长度不短于开启围栏，且后面没有其他内容
+                let run = trimmed.chars().take_while(|&c| c == fence_char).count();
+                if run >= fence_len && trimmed[run..].trim().is_empty() {
+                    fence = None;
+                }
+                continue;
+            }
+
+            if let Some((fence_char, fence_len, info)) = parse_fence_open(trimmed) {
+                fence = Some((fence_char, fence_len));
+                let lang = info.split_whitespace().next().unwrap_or("").trim_matches(|c| c == '{' || c == '}');
+                let lang = lang.trim_start_matches('.').to_lowercase();
+                match lang.as_str() {
+                    "mermaid" => analysis.has_mermaid = true,
+                    "math" | "katex" => analysis.has_math = true,
+                    _ => {
+                        analysis.has_code = true;
+                        if !lang.is_empty() && !analysis.code_languages.contains(&lang) {
+                            analysis.code_languages.push(lang);
+                        }
+                    }
+                }
+                prev_line = "";
+                continue;
+            }
+
+            if !analysis.has_tables && prev_line.contains('|') && is_table_delimiter(trimmed) {
+                analysis.has_tables = true;
+            }
+            if !analysis.has_math && contains_math(line) {
+                analysis.has_math = true;
+            }
+            prev_line = line;
+        }
+
+        analysis
+    }
+}
+
+/// 解析围栏代码块起始行（``` 或 ~~~，至少 3 个），返回围栏字符、长度与信息串
+fn parse_fence_open(trimmed: &str) -> Option<(char, usize, &str)> {
+    let fence_char = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let fence_len = trimmed.chars().take_while(|&c| c == fence_char).count();
+    if fence_len < 3 {
+        return None;
+    }
+    let info = trimmed[fence_len..].trim();
+    // 反引号围栏的信息串中不能再出现反引号
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, fence_len, info))
+}
+
+/// GFM 表格分隔行，如 `|---|:--:|` 或 `--- | ---`
+fn is_table_delimiter(trimmed: &str) -> bool {
+    if !trimmed.contains('-') {
+        return false;
+    }
+    let inner = trimmed.trim_start_matches('|').trim_end_matches('|');
+    let cells: Vec<&str> = inner.split('|').map(str::trim).collect();
+    if cells.len() < 2 && !trimmed.contains('|') {
+        return false;
+    }
+    cells.iter().all(|cell| {
+        let cell = cell.trim_start_matches(':').trim_end_matches(':');
+        !cell.is_empty() && cell.chars().all(|c| c == '-')
+    })
+}
+
+/// 行内是否含有 `$$...$$` 或 `$...$` 公式；行内代码与转义的 `\$` 不计入，
+/// `$` 内侧紧邻空白或闭合 `$` 后紧跟数字（如 `$5 和 $10`）时视为普通金额
+fn contains_math(line: &str) -> bool {
+    let mut text = String::with_capacity(line.len());
+    let mut in_code = false;
+    let mut escaped = false;
+    for c in line.chars() {
+        if escaped {
+            escaped = false;
+            if !in_code {
+                text.push(' ');
+            }
+            continue;
+        }
+        match c {
+            '\\' if !in_code => escaped = true,
+            '`' => in_code = !in_code,
+            _ if !in_code => text.push(c),
+            _ => {}
+        }
+    }
+    if text.contains("$$") {
+        return true;
+    }
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut open: Option<usize> = None;
+    for (i, &c) in chars.iter().enumerate() {
+        if c != '$' {
+            continue;
+        }
+        match open {
+            None => {
+                if chars.get(i + 1).is_some_and(|n| !n.is_whitespace()) {
+                    open = Some(i);
+                }
+            }
+            Some(start) => {
+                let closes = i > start + 1
+                    && !chars[i - 1].is_whitespace()
+                    && !chars.get(i + 1).is_some_and(|n| n.is_ascii_digit());
+                if closes {
+                    return true;
+                }
+                open = chars.get(i + 1).is_some_and(|n| !n.is_whitespace()).then_some(i);
+            }
+        }
+    }
+    false
+}
+
+/// Markdown 内容结构分析结果
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MarkdownContentAnalysis {
+    /// 是否含有需要语法高亮的围栏代码块
+    pub has_code: bool,
+    /// 代码块语言标签（小写、去重，按首次出现顺序）
+    pub code_languages: Vec<String>,
+    /// 是否含有数学公式（`$...$`、`$$...$$` 或 math 代码块）
+    pub has_math: bool,
+    /// 是否含有 GFM 表格
+    pub has_tables: bool,
+    /// 是否含有 Mermaid 图
+    pub has_mermaid: bool,
 }
 
 /// Markdown 搜索结果
@@ -202,4 +349,56 @@ mod tests {
         assert_eq!(toc[1].title, "Section 1");
         assert_eq!(toc[1].level, 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_analyze_content() {
+        let content = [
+            "# Title",
+            "价格 $5 和 $10，转义 \\$x\\$，行内代码 `$a$`",
+            "```Rust",
+            "let s = \"$x$ | --- |\";",
+            "```",
+            "~~~ {.python}",
+            "print(1)",
+            "~~~",
+            "```mermaid",
+            "graph TD; A-->B",
+            "```",
+            "```rust",
+            "fn main() {}",
+            "```",
+        ]
+        .join("\n");
+        let engine = MarkdownEngine {
+            content,
+            encoding: "UTF-8".to_string(),
+            file_path: "/test/file.md".to_string(),
+        };
+        assert_eq!(
+            engine.analyze_content(),
+            MarkdownContentAnalysis {
+                has_code: true,
+                code_languages: vec!["rust".to_string(), "python".to_string()],
+                has_math: false,
+                has_tables: false,
+                has_mermaid: true,
+            }
+        );
+
+        let engine = MarkdownEngine {
+            content: "质能方程 $E=mc^2$\n\n| a | b |\n|:--|--:|\n| 1 | 2 |".to_string(),
+            encoding: "UTF-8".to_string(),
+            file_path: "/test/file.md".to_string(),
+        };
+        let analysis = engine.analyze_content();
+        assert!(analysis.has_math && analysis.has_tables);
+        assert!(!analysis.has_code && analysis.code_languages.is_empty());
+
+        let engine = MarkdownEngine {
+            content: "$$\n\\int_0^1 x\\,dx\n$$".to_string(),
+            encoding: "UTF-8".to_string(),
+            file_path: "/test/file.md".to_string(),
+        };
+        assert!(engine.analyze_content().has_math);
+    }
+}
@@ -1,6 +1,10 @@
 //! MOBI 缓存管理器
 //! 负责将 MOBI 章节内容和资源持久化到磁盘
 
+use crate::formats::common::Footnote;
+use crate::formats::pagination::SectionPagination;
+use crate::formats::sanitize::{sanitize_css_if_enabled, sanitize_html_if_enabled};
+use crate::formats::text_offset::section_text_length;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -92,6 +96,24 @@ pub struct SectionCacheData {
     pub styles: Vec<String>,
     /// 资源引用列表
     pub resource_refs: Vec<String>,
+    /// 章节纯文本长度（字符数），前端据此把滚动位置换算为纯文本偏移
+    #[serde(default)]
+    pub text_length: usize,
+}
+
+impl SectionCacheData {
+    /// 清洗开关开启时在此统一清洗，加载、分页与进度定位看到的是同一份 HTML
+    fn new(html: String, styles: Vec<String>, resource_refs: Vec<String>) -> Self {
+        let html = sanitize_html_if_enabled(html);
+        let styles = styles.into_iter().map(sanitize_css_if_enabled).collect();
+        let text_length = section_text_length(&html);
+        Self {
+            html,
+            styles,
+            resource_refs,
+            text_length,
+        }
+    }
 }
 
 /// 资源缓存元数据
@@ -126,6 +148,12 @@ pub struct BookInfo {
     pub page_count: i32,
     pub format: String,
     pub cover_image: Option<String>,
+    /// 所属系列名称
+    #[serde(default)]
+    pub series: Option<String>,
+    /// 在系列中的序号
+    #[serde(default)]
+    pub series_index: Option<f32>,
 }
 
 /// MOBI 元数据缓存条目
@@ -136,11 +164,21 @@ pub struct MetadataCacheEntry {
     pub toc: Vec<TocItem>,
     pub section_count: u32,
     pub last_access_time: u64,
+    /// 缓存结构版本，用于在解析能力升级后主动淘汰旧缓存
+    #[serde(default)]
+    pub schema_version: u32,
 }
 
+/// MOBI 元数据缓存结构版本（未记录版本的旧缓存视为 0）
+/// 1：KF8 提取脚注；旧缓存章节缺少脚注标注，需要重建。
+pub const MOBI_METADATA_SCHEMA_VERSION: u32 = 1;
+
 /// 默认磁盘缓存上限（字节），前端未下发时的 fallback
 const DEFAULT_DISK_CACHE_MAX_BYTES: usize = 256 * 1024 * 1024;
 
+/// 脚注映射缓存文件名，位于章节缓存目录
+const FOOTNOTES_FILE_NAME: &str = "footnotes.json";
+
 /// MOBI 缓存管理器
 pub struct MobiCacheManager {
     /// 缓存有效期（天），0 表示不限
@@ -233,6 +271,16 @@ impl MobiCacheManager {
             .await
             .map_err(|e| format!("写入元数据失败: {}", e))?;
 
+        // 章节内容变化后，旧的分页结果全部失效
+        let pages_prefix = format!("{}.pages.", section_index);
+        if let Ok(mut entries) = fs::read_dir(&cache_dir).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_name().to_string_lossy().starts_with(&pages_prefix) {
+                    let _ = fs::remove_file(entry.path()).await;
+                }
+            }
+        }
+
         Ok(())
     }
 
@@ -288,11 +336,7 @@ impl MobiCacheManager {
                 let _ = fs::write(&meta_path, updated_meta_json).await;
             }
 
-            return Ok(Some(SectionCacheData {
-                html: html_content,
-                styles: meta.styles,
-                resource_refs: meta.resource_refs,
-            }));
+            return Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)));
         }
 
         let legacy_cache_dir = {
@@ -325,11 +369,7 @@ impl MobiCacheManager {
             let meta_path = cache_dir.join(format!("{}.meta.json", section_index));
             let _ = fs::write(&meta_path, updated_meta_json).await;
 
-            return Ok(Some(SectionCacheData {
-                html: html_content,
-                styles: meta.styles,
-                resource_refs: meta.resource_refs,
-            }));
+            return Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)));
         }
 
         let _ = fs::create_dir_all(&cache_dir).await;
@@ -346,11 +386,68 @@ impl MobiCacheManager {
             let _ = fs::remove_file(&legacy_meta_path).await;
         }
 
-        Ok(Some(SectionCacheData {
-            html: html_content,
-            styles: meta.styles,
-            resource_refs: meta.resource_refs,
-        }))
+        Ok(Some(SectionCacheData::new(html_content, meta.styles, meta.resource_refs)))
+    }
+
+    /// 保存章节分页结果，缓存键包含排版参数，换字号/行距后不会命中旧分页
+    pub async fn save_section_pages(
+        &self,
+        book_id: &str,
+        pagination: &SectionPagination,
+    ) -> Result<(), String> {
+        let book_hash = compute_book_hash(book_id);
+        let cache_dir = mobi_section_cache_dir(&book_hash);
+        fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+        let pages_path = cache_dir.join(format!(
+            "{}.pages.{}.json",
+            pagination.section_index, pagination.typography_key
+        ));
+        let json = serde_json::to_string(pagination).map_err(|e| format!("序列化分页结果失败: {}", e))?;
+        fs::write(&pages_path, json)
+            .await
+            .map_err(|e| format!("写入分页缓存失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 读取章节分页结果（按排版参数区分）
+    pub async fn load_section_pages(
+        &self,
+        book_id: &str,
+        section_index: u32,
+        typography_key: &str,
+    ) -> Option<SectionPagination> {
+        let book_hash = compute_book_hash(book_id);
+        let pages_path = mobi_section_cache_dir(&book_hash)
+            .join(format!("{}.pages.{}.json", section_index, typography_key));
+        let json = fs::read_to_string(&pages_path).await.ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    /// 保存整本书的脚注映射
+    pub async fn save_footnotes(&self, book_id: &str, footnotes: &[Footnote]) -> Result<(), String> {
+        let book_hash = compute_book_hash(book_id);
+        let cache_dir = mobi_section_cache_dir(&book_hash);
+        fs::create_dir_all(&cache_dir)
+            .await
+            .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+        let json = serde_json::to_string(footnotes).map_err(|e| format!("序列化脚注失败: {}", e))?;
+        fs::write(cache_dir.join(FOOTNOTES_FILE_NAME), json)
+            .await
+            .map_err(|e| format!("写入脚注缓存失败: {}", e))?;
+        Ok(())
+    }
+
+    /// 按注释 ID 读取单条脚注
+    pub async fn load_footnote(&self, book_id: &str, note_id: &str) -> Option<Footnote> {
+        let book_hash = compute_book_hash(book_id);
+        let path = mobi_section_cache_dir(&book_hash).join(FOOTNOTES_FILE_NAME);
+        let json = fs::read_to_string(&path).await.ok()?;
+        let footnotes: Vec<Footnote> = serde_json::from_str(&json).ok()?;
+        footnotes.into_iter().find(|f| f.id == note_id)
     }
 
     /// 保存资源缓存到磁盘
@@ -361,20 +458,21 @@ impl MobiCacheManager {
         data: &[u8],
         mime_type: &str,
     ) -> Result<(), String> {
+        Self::write_resource(book_id, resource_path, data, mime_type)
+    }
+
+    /// 同步写入资源缓存，供解析线程在提取资源时直接落盘
+    pub fn write_resource(book_id: &str, resource_path: &str, data: &[u8], mime_type: &str) -> Result<(), String> {
         let book_hash = compute_book_hash(book_id);
         let cache_dir = mobi_resource_cache_dir(&book_hash);
         let resource_hash = compute_resource_hash(resource_path);
 
         // 创建目录
-        fs::create_dir_all(&cache_dir)
-            .await
-            .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+        std::fs::create_dir_all(&cache_dir).map_err(|e| format!("创建缓存目录失败: {}", e))?;
 
         // 保存资源数据
         let data_path = cache_dir.join(format!("{}.data", resource_hash));
-        fs::write(&data_path, data)
-            .await
-            .map_err(|e| format!("写入资源缓存失败: {}", e))?;
+        std::fs::write(&data_path, data).map_err(|e| format!("写入资源缓存失败: {}", e))?;
 
         // 保存元数据
         let meta = ResourceCacheMeta {
@@ -386,9 +484,7 @@ impl MobiCacheManager {
         };
         let meta_path = cache_dir.join(format!("{}.meta.json", resource_hash));
         let meta_json = serde_json::to_string(&meta).map_err(|e| format!("序列化元数据失败: {}", e))?;
-        fs::write(&meta_path, meta_json)
-            .await
-            .map_err(|e| format!("写入元数据失败: {}", e))?;
+        std::fs::write(&meta_path, meta_json).map_err(|e| format!("写入元数据失败: {}", e))?;
 
         Ok(())
     }
@@ -713,6 +809,7 @@ impl MobiCacheManager {
             toc,
             section_count,
             last_access_time: Self::now_millis(),
+            schema_version: MOBI_METADATA_SCHEMA_VERSION,
         };
 
         let meta_path = cache_dir.join(format!("{}.json", book_hash));
@@ -725,6 +822,11 @@ impl MobiCacheManager {
         Ok(())
     }
 
+    /// 旧版本缓存是否需要重建
+    fn needs_rebuild(entry: &MetadataCacheEntry) -> bool {
+        entry.schema_version < MOBI_METADATA_SCHEMA_VERSION
+    }
+
     /// 从磁盘加载书籍元数据
     pub async fn load_metadata(
         &self,
@@ -746,6 +848,12 @@ impl MobiCacheManager {
                 return Ok(None);
             }
 
+            // 解析能力升级兜底：旧版本缓存主动淘汰，触发下次重建
+            if Self::needs_rebuild(&entry) {
+                let _ = fs::remove_file(&meta_path).await;
+                return Ok(None);
+            }
+
             let updated_entry = MetadataCacheEntry {
                 last_access_time: Self::now_millis(),
                 ..entry.clone()
@@ -773,7 +881,7 @@ impl MobiCacheManager {
             Err(_) => return Ok(None),
         };
 
-        if self.is_expired(entry.last_access_time) {
+        if self.is_expired(entry.last_access_time) || Self::needs_rebuild(&entry) {
             let _ = fs::remove_file(&legacy_meta_path).await;
             return Ok(None);
         }
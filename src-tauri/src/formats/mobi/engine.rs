@@ -2,6 +2,10 @@
 //! 策略：所有 filepos/offset 操作直接在 &[u8] 上完成，避免字节偏移与字符索引不一致
 //! 流程：提取原始文本字节 → 在字节流上拆分章节 → 按段解码为 UTF-8
 
+#[path = "engine/footnote.rs"]
+mod footnote;
+#[path = "engine/kf8.rs"]
+mod kf8;
 #[path = "engine/patterns.rs"]
 mod patterns;
 #[path = "engine/pdb.rs"]
@@ -13,11 +17,16 @@ mod section;
 #[path = "engine/utils.rs"]
 mod utils;
 
+use std::collections::HashMap;
 use std::path::Path;
 use std::time::Instant;
 
+use encoding_rs::Encoding;
 use mobi::Mobi;
+use serde::Serialize;
 use super::cache::{BookInfo, TocItem};
+use crate::formats::common::Footnote;
+use crate::formats::BookError;
 
 // ====================== 数据结构 ======================
 
@@ -29,11 +38,12 @@ pub struct PreparedSection {
     pub resource_refs: Vec<String>,
 }
 
-#[derive(Debug)]
+/// 资源清单项；图片字节在解析时交给调用方写入磁盘缓存，不随解析结果返回
+#[derive(Debug, Clone, Serialize)]
 pub struct PreparedResource {
     pub path: String,
-    pub data: Vec<u8>,
     pub mime_type: String,
+    pub size: usize,
 }
 
 #[derive(Debug)]
@@ -43,12 +53,68 @@ pub struct MobiPreparedBook {
     pub section_count: u32,
     pub sections: Vec<PreparedSection>,
     pub resources: Vec<PreparedResource>,
+    /// 指向 filepos 的脚注，ID 为 `filepos{N}`
+    pub footnotes: Vec<Footnote>,
 }
 
 // ====================== 入口 ======================
 
-/// 解析 MOBI 文件并返回预处理数据
+/// 只读取 EXTH 中的书名，不拆分章节和提取资源
+pub fn read_title(file_path: &str) -> Result<Option<String>, String> {
+    let raw_bytes = std::fs::read(file_path).map_err(|e| format!("读取 MOBI 文件字节失败: {}", e))?;
+    Ok(resource::extract_title(&raw_bytes))
+}
+
+/// 只读取 EXTH 中的语言（record 524）
+pub fn read_language(file_path: &str) -> Result<Option<String>, String> {
+    let raw_bytes = std::fs::read(file_path).map_err(|e| format!("读取 MOBI 文件字节失败: {}", e))?;
+    Ok(resource::extract_language(&raw_bytes))
+}
+
+/// 检测 DRM 加密，返回检测依据
+fn detect_drm(raw_bytes: &[u8]) -> Option<String> {
+    pdb::detect_header_drm(raw_bytes).or_else(|| resource::has_drm_exth_record(raw_bytes).then(|| "exth_209".to_string()))
+}
+
+/// 章节、目录与脚注
+type PreparedContent = (Vec<PreparedSection>, Vec<TocItem>, Vec<Footnote>);
+
+/// 旧版 MOBI：解压整段文本后按分页标记拆分章节，并提取目录和脚注
+fn prepare_legacy_content(
+    raw_bytes: &[u8],
+    image_map: &HashMap<usize, String>,
+    encoding: &'static Encoding,
+) -> Result<PreparedContent, String> {
+    // 提取原始文本字节（解压后的字节流，保持 filepos 偏移一致）
+    let text_start = Instant::now();
+    let raw_text = match pdb::extract_raw_text_bytes(raw_bytes) {
+        Some(t) if !t.is_empty() => t,
+        _ => return Err("无法提取 MOBI 文本内容：原始字节解压失败".to_string()),
+    };
+    println!("[mobi-engine] 原始文本字节长度: {} bytes", raw_text.len());
+    println!("[mobi-engine] 解压文本耗时: {}ms", text_start.elapsed().as_millis());
+
+    let split_start = Instant::now();
+    let (mut sections, toc) = section::split_into_sections(&raw_text, raw_bytes, image_map, encoding);
+    println!("[mobi-engine] 拆分耗时: {}ms", split_start.elapsed().as_millis());
+
+    let footnotes = footnote::extract_footnotes(&raw_text, encoding, image_map);
+    footnote::mark_noterefs(&mut sections, &footnotes);
+    println!("[mobi-engine] 脚注数量: {}", footnotes.len());
+    Ok((sections, toc, footnotes))
+}
+
+/// 解析 MOBI 文件并返回预处理数据，不保存图片资源
 pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
+    prepare_book_with(file_path, |_, _| Ok(()))
+}
+
+/// 解析 MOBI 文件，每个图片资源提取后立即交给 `on_resource` 处理（通常写入磁盘缓存），
+/// 返回结果中只保留资源清单，避免图多的书把所有图片字节留在内存中
+pub fn prepare_book_with<F>(file_path: &str, mut on_resource: F) -> Result<MobiPreparedBook, String>
+where
+    F: FnMut(&PreparedResource, &[u8]) -> Result<(), String>,
+{
     let overall_start = Instant::now();
     println!("[mobi-engine] 开始解析: {}", file_path);
 
@@ -64,6 +130,12 @@ pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
     println!("[mobi-engine] 文件大小: {} bytes", raw_bytes.len());
     println!("[mobi-engine] 读取文件耗时: {}ms", read_ms);
 
+    // 加密内容解压出来只会是乱码，直接返回明确的错误
+    if let Some(reason) = detect_drm(&raw_bytes) {
+        println!("[mobi-engine] 检测到 DRM: {}", reason);
+        return Err(BookError::drm_protected().with_details(reason).to_string());
+    }
+
     // mobi crate 在部分文件上存在卡死风险，这里直接跳过，统一走字节流 + EXTH 元数据解析
     let mobi_start = Instant::now();
     println!("[mobi-engine] 跳过 mobi::Mobi::from_path，使用 EXTH 元数据解析");
@@ -74,6 +146,9 @@ pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
     let resource_start = Instant::now();
     let image_records = resource::extract_image_records_from_bytes(&raw_bytes);
     let (resources, image_map) = resource::build_image_resources(&image_records);
+    for (res, (_, data)) in resources.iter().zip(&image_records) {
+        on_resource(res, data)?;
+    }
     let resource_ms = resource_start.elapsed().as_millis();
     println!("[mobi-engine] 资源解析耗时: {}ms", resource_ms);
 
@@ -83,21 +158,18 @@ pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
     let encoding_ms = encoding_start.elapsed().as_millis();
     println!("[mobi-engine] 编码检测耗时: {}ms", encoding_ms);
 
-    // 提取原始文本字节（解压后的字节流，保持 filepos 偏移一致）
-    let text_start = Instant::now();
-    let raw_text = match pdb::extract_raw_text_bytes(&raw_bytes) {
-        Some(t) if !t.is_empty() => t,
-        _ => return Err("无法提取 MOBI 文本内容：原始字节解压失败".to_string()),
+    // KF8（AZW3 及 KF7/KF8 混合文件）按骨架/片段表还原 XHTML，非 KF8 或索引不完整时走旧版 MOBI 流程
+    let content_start = Instant::now();
+    let (sections, toc, footnotes) = match kf8::prepare_kf8(&raw_bytes, &image_records, &image_map, encoding) {
+        Some((mut sections, toc)) => {
+            let footnotes = footnote::extract_kf8_footnotes(&mut sections);
+            println!("[mobi-engine] 脚注数量: {}", footnotes.len());
+            (sections, toc, footnotes)
+        }
+        None => prepare_legacy_content(&raw_bytes, &image_map, encoding)?,
     };
-    let text_ms = text_start.elapsed().as_millis();
-
-    println!("[mobi-engine] 原始文本字节长度: {} bytes", raw_text.len());
-    println!("[mobi-engine] 解压文本耗时: {}ms", text_ms);
-
-    let split_start = Instant::now();
-    let (sections, toc) = section::split_into_sections(&raw_text, &raw_bytes, &image_map, encoding);
     let section_count = sections.len() as u32;
-    let split_ms = split_start.elapsed().as_millis();
+    let content_ms = content_start.elapsed().as_millis();
 
     let meta_start = Instant::now();
     let mut book_info = resource::extract_metadata_safe(mobi_opt.as_ref(), file_path, &raw_bytes, &image_records);
@@ -106,13 +178,12 @@ pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
 
     println!("[mobi-engine] 拆分结果: sections={}, toc={}", section_count, toc.len());
     println!(
-        "[mobi-engine] 阶段耗时: read={}ms, mobi_init={}ms, resource={}ms, encoding={}ms, text={}ms, split={}ms, meta={}ms, total={}ms",
+        "[mobi-engine] 阶段耗时: read={}ms, mobi_init={}ms, resource={}ms, encoding={}ms, content={}ms, meta={}ms, total={}ms",
         read_ms,
         mobi_ms,
         resource_ms,
         encoding_ms,
-        text_ms,
-        split_ms,
+        content_ms,
         meta_ms,
         overall_start.elapsed().as_millis()
     );
@@ -123,5 +194,6 @@ pub fn prepare_book(file_path: &str) -> Result<MobiPreparedBook, String> {
         section_count,
         sections,
         resources,
+        footnotes,
     })
 }
\ No newline at end of file
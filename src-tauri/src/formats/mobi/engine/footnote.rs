@@ -0,0 +1,168 @@
+//! 脚注提取
+//! MOBI 没有脚注语义标记，引用处是指向 filepos 的普通链接；链接文字形如 `[1]`、`①` 时视为脚注，
+//! 从目标 filepos 截取到所在段落结束作为脚注内容
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use encoding_rs::Encoding;
+use regex::Regex;
+
+use super::patterns::{
+    ANCHOR_BYTES_RE, ANCHOR_OPEN_RE, BLOCK_CLOSE_BYTES_RE, BLOCK_CLOSE_RE, HREF_FILEPOS_ANCHOR_RE,
+    HREF_FILEPOS_OPEN_RE, NOTE_LABEL_RE, SPLIT_BYTES_RE,
+};
+use super::pdb::align_to_char_boundary;
+use super::utils::{replace_recindex, strip_html_tags};
+use super::PreparedSection;
+use crate::formats::common::{mark_noteref_tag, Footnote};
+
+/// 单条脚注最多截取的字节数
+const MAX_FOOTNOTE_BYTES: usize = 4096;
+
+/// 扫描脚注链接并截取目标位置的内容，返回 filepos 对应的脚注
+pub(super) fn extract_footnotes(
+    raw_text: &[u8],
+    encoding: &'static Encoding,
+    image_map: &HashMap<usize, String>,
+) -> Vec<Footnote> {
+    let targets: BTreeSet<usize> = ANCHOR_BYTES_RE
+        .captures_iter(raw_text)
+        .filter_map(|caps| {
+            let (label, _, _) = encoding.decode(&caps[2]);
+            let label = strip_html_tags(&label);
+            if !NOTE_LABEL_RE.is_match(label.trim()) {
+                return None;
+            }
+            std::str::from_utf8(&caps[1]).ok()?.parse().ok()
+        })
+        .filter(|&fp: &usize| fp < raw_text.len())
+        .collect();
+
+    targets
+        .into_iter()
+        .filter_map(|fp| {
+            let start = align_to_char_boundary(raw_text, fp, encoding);
+            let limit = align_to_char_boundary(raw_text, (start + MAX_FOOTNOTE_BYTES).min(raw_text.len()), encoding);
+            let region = &raw_text[start..limit];
+            let end = [
+                BLOCK_CLOSE_BYTES_RE.find(region).map(|m| m.end()),
+                SPLIT_BYTES_RE.find(region).map(|m| m.start()),
+            ]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(region.len());
+
+            let (decoded, _, _) = encoding.decode(&region[..end]);
+            let html = replace_recindex(decoded.trim(), image_map);
+            if strip_html_tags(&html).trim().is_empty() {
+                return None;
+            }
+            Some(Footnote {
+                id: format!("filepos{}", fp),
+                section_index: None,
+                html,
+            })
+        })
+        .collect()
+}
+
+/// KF8：链接已改写为 `#filepos{N}`，目标位置注入了 `<span id="filepos{N}">`，
+/// 在还原后的章节 HTML 上截取目标位置到所在段落结束作为脚注内容，并标注引用处
+pub(super) fn extract_kf8_footnotes(sections: &mut [PreparedSection]) -> Vec<Footnote> {
+    let targets: BTreeSet<usize> = sections
+        .iter()
+        .flat_map(|section| HREF_FILEPOS_ANCHOR_RE.captures_iter(&section.html))
+        .filter_map(|caps| {
+            let label = strip_html_tags(&caps[2]);
+            if !NOTE_LABEL_RE.is_match(label.trim()) {
+                return None;
+            }
+            caps[1].parse().ok()
+        })
+        .collect();
+
+    let footnotes: Vec<Footnote> = targets
+        .into_iter()
+        .filter_map(|pos| {
+            let marker = format!(r#"<span id="filepos{}"></span>"#, pos);
+            sections.iter().find_map(|section| {
+                let start = section.html.find(&marker)? + marker.len();
+                let region = &section.html[start..];
+                let mut limit = region.len().min(MAX_FOOTNOTE_BYTES);
+                while !region.is_char_boundary(limit) {
+                    limit -= 1;
+                }
+                let region = &region[..limit];
+                let end = BLOCK_CLOSE_RE.find(region).map(|m| m.end()).unwrap_or(region.len());
+                let html = region[..end].trim();
+                if strip_html_tags(html).trim().is_empty() {
+                    return None;
+                }
+                Some(Footnote {
+                    id: format!("filepos{}", pos),
+                    section_index: Some(section.index),
+                    html: html.to_string(),
+                })
+            })
+        })
+        .collect();
+
+    mark_links(sections, &footnotes, &HREF_FILEPOS_OPEN_RE);
+    footnotes
+}
+
+/// 在章节 HTML 中为指向脚注的链接标注脚注 ID
+pub(super) fn mark_noterefs(sections: &mut [PreparedSection], footnotes: &[Footnote]) {
+    mark_links(sections, footnotes, &ANCHOR_OPEN_RE);
+}
+
+/// 为 `link_re` 匹配到的 <a> 开始标签（第 1 组为 filepos）标注脚注 ID
+fn mark_links(sections: &mut [PreparedSection], footnotes: &[Footnote], link_re: &Regex) {
+    if footnotes.is_empty() {
+        return;
+    }
+    let ids: HashSet<&str> = footnotes.iter().map(|f| f.id.as_str()).collect();
+    for section in sections.iter_mut() {
+        let html = link_re.replace_all(&section.html, |caps: &regex::Captures| {
+            let id = format!("filepos{}", &caps[1]);
+            if ids.contains(id.as_str()) {
+                mark_noteref_tag(&caps[0], &id)
+            } else {
+                caps[0].to_string()
+            }
+        });
+        if let std::borrow::Cow::Owned(html) = html {
+            section.html = html;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::mobi::engine::utils::build_section;
+
+    #[test]
+    fn test_extract_kf8_footnotes() {
+        let mut sections = vec![
+            build_section(
+                r##"<p>正文<a href="#filepos120">[1]</a>，另见<a href="#filepos300">第二章</a></p>"##.to_string(),
+                0,
+            ),
+            build_section(
+                r#"<h2>注释</h2><span id="filepos120"></span><p>注一内容</p><p>其他</p><span id="filepos300"></span><p>第二章</p>"#
+                    .to_string(),
+                1,
+            ),
+        ];
+        let footnotes = extract_kf8_footnotes(&mut sections);
+
+        assert_eq!(footnotes.len(), 1);
+        assert_eq!(footnotes[0].id, "filepos120");
+        assert_eq!(footnotes[0].section_index, Some(1));
+        assert_eq!(footnotes[0].html, "<p>注一内容</p>");
+        assert!(sections[0].html.contains(r##"<a data-footnote-id="filepos120" href="#filepos120">"##));
+        assert!(sections[0].html.contains(r##"<a href="#filepos300">"##));
+    }
+}
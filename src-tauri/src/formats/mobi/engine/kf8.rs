@@ -0,0 +1,529 @@
+//! KF8（AZW3）容器解析
+//! KF8 不再把整本书拼成一段 HTML，而是把每个 XHTML 文件拆成骨架（skeleton）和片段（fragment）两张索引表存储，
+//! 按表把片段插回骨架即可还原原文件；CSS 等内容存放在 FDST 划分的其他 flow 中，
+//! 正文通过 `kindle:flow`、`kindle:embed`、`kindle:pos` 链接引用
+//! 独立 AZW3 从 record 0 开始即为 KF8；KF7/KF8 混合文件的 KF8 部分位于 BOUNDARY 记录之后（EXTH 121）
+
+use std::collections::{BTreeMap, HashMap};
+
+use encoding_rs::Encoding;
+
+use super::patterns::{
+    BODY_CLOSE_RE, BODY_OPEN_RE, KINDLE_EMBED_RE, KINDLE_FLOW_CSS_RE, KINDLE_POS_BYTES_RE, KINDLE_POS_RE,
+    STYLE_BLOCK_RE,
+};
+use super::pdb::{
+    align_to_char_boundary, extract_raw_text_bytes_at, get_record, parse_record_offsets, read_index, read_u32_safe,
+    IndexEntry,
+};
+use super::resource::kf8_boundary;
+use super::section::build_toc_from_sections;
+use super::utils::{build_section, extract_resource_refs};
+use super::PreparedSection;
+use crate::formats::location::section_location;
+use crate::formats::mobi::cache::TocItem;
+
+/// MOBI header 版本号为 8 表示 KF8
+const KF8_VERSION: u32 = 8;
+
+/// 头部中表示「不存在」的记录索引
+const NULL_INDEX: u32 = u32::MAX;
+
+/// 资源区起始记录可能的类型标记（图片之外）
+const RESOURCE_MAGICS: [&[u8]; 3] = [b"RESC", b"FONT", b"CRES"];
+
+/// KF8 record 0 中用到的字段，记录索引均相对于 KF8 的 record 0
+struct Kf8Header {
+    first_resource: u32,
+    fdst: u32,
+    ncx: u32,
+    fragment: u32,
+    skeleton: u32,
+}
+
+/// 骨架表条目：一个 XHTML 文件
+struct Skeleton {
+    fragment_count: usize,
+    start: usize,
+    length: usize,
+}
+
+/// 片段表条目：插入位置为还原后文本中的绝对偏移
+struct Fragment {
+    insert_pos: usize,
+    length: usize,
+}
+
+/// 还原后的 XHTML 文件，start..end 为其在正文 flow 中覆盖的范围
+struct Part {
+    html: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+/// 解析 KF8 record 0；版本号不是 8 时返回 None
+fn parse_kf8_header(data: &[u8], record0: usize) -> Option<Kf8Header> {
+    if !data.get(record0 + 16..)?.starts_with(b"MOBI") || read_u32_safe(data, record0 + 0x24) != KF8_VERSION {
+        return None;
+    }
+    // 只读取 MOBI header 长度范围内的字段
+    let header_end = record0 + 16 + read_u32_safe(data, record0 + 20) as usize;
+    let field = |offset: usize| {
+        if record0 + offset + 4 <= header_end {
+            read_u32_safe(data, record0 + offset)
+        } else {
+            NULL_INDEX
+        }
+    };
+    Some(Kf8Header {
+        first_resource: field(0x6C),
+        fdst: field(0xC0),
+        ncx: field(0xF4),
+        fragment: field(0xF8),
+        skeleton: field(0xFC),
+    })
+}
+
+/// 定位 KF8 的 record 0：独立 AZW3 为 0，混合文件取 BOUNDARY 记录之后的一条
+fn locate_kf8(data: &[u8], offsets: &[usize]) -> Option<(usize, Kf8Header)> {
+    if let Some(header) = parse_kf8_header(data, offsets[0]) {
+        return Some((0, header));
+    }
+    let boundary = kf8_boundary(data)?;
+    let is_boundary = |index: usize| get_record(data, offsets, index).is_some_and(|r| r.starts_with(b"BOUNDARY"));
+    // EXTH 121 通常指向 KF8 record 0，部分生成工具写的是 BOUNDARY 记录本身
+    let base = if boundary > 0 && is_boundary(boundary - 1) {
+        boundary
+    } else if is_boundary(boundary) {
+        boundary + 1
+    } else {
+        return None;
+    };
+    let header = parse_kf8_header(data, *offsets.get(base)?)?;
+    Some((base, header))
+}
+
+/// kindle 链接中的 base32 编号（0-9、A-V）
+fn from_base32(text: &str) -> Option<usize> {
+    usize::from_str_radix(text, 32).ok()
+}
+
+/// 读取 FDST 表，返回每个 flow 在文本中的范围；缺失时整段文本作为唯一的 flow
+fn parse_fdst(data: &[u8], offsets: &[usize], index: u32, text_len: usize) -> Vec<(usize, usize)> {
+    let record = (index != NULL_INDEX)
+        .then(|| get_record(data, offsets, index as usize))
+        .flatten()
+        .filter(|r| r.starts_with(b"FDST"));
+    let flows: Vec<(usize, usize)> = record
+        .map(|rec| {
+            let count = read_u32_safe(rec, 8) as usize;
+            (0..count)
+                .take_while(|i| 12 + i * 8 + 8 <= rec.len())
+                .map(|i| {
+                    let start = (read_u32_safe(rec, 12 + i * 8) as usize).min(text_len);
+                    let end = (read_u32_safe(rec, 16 + i * 8) as usize).clamp(start, text_len);
+                    (start, end)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if flows.is_empty() {
+        vec![(0, text_len)]
+    } else {
+        flows
+    }
+}
+
+fn read_skeletons(entries: &[IndexEntry]) -> Vec<Skeleton> {
+    entries
+        .iter()
+        .map(|entry| Skeleton {
+            fragment_count: entry.value(1, 0).unwrap_or(0),
+            start: entry.value(6, 0).unwrap_or(0),
+            length: entry.value(6, 1).unwrap_or(0),
+        })
+        .collect()
+}
+
+/// 片段表的条目名即十进制插入位置
+fn read_fragments(entries: &[IndexEntry]) -> Option<Vec<Fragment>> {
+    entries
+        .iter()
+        .map(|entry| {
+            let insert_pos = std::str::from_utf8(&entry.name).ok()?.trim().parse().ok()?;
+            Some(Fragment {
+                insert_pos,
+                length: entry.value(6, 1).unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// 插入位置落在标签内部时（部分文件的片段表不准确）顺延到该标签结束之后
+fn fix_insert_pos(html: &[u8], pos: usize) -> usize {
+    let head = &html[..pos];
+    match (head.iter().rposition(|&b| b == b'<'), head.iter().rposition(|&b| b == b'>')) {
+        (Some(open), close) if close < Some(open) => html[pos..]
+            .iter()
+            .position(|&b| b == b'>')
+            .map_or(pos, |p| pos + p + 1),
+        _ => pos,
+    }
+}
+
+/// 按骨架/片段表还原各 XHTML 文件：片段数据顺序紧跟在所属骨架之后，逐个插回骨架
+fn assemble_parts(text: &[u8], skeletons: &[Skeleton], fragments: &[Fragment]) -> Vec<Part> {
+    let mut parts = Vec::with_capacity(skeletons.len());
+    let mut fragment_iter = fragments.iter();
+    for skeleton in skeletons {
+        let skeleton_end = (skeleton.start + skeleton.length).min(text.len());
+        let mut html = text.get(skeleton.start..skeleton_end).unwrap_or_default().to_vec();
+        let mut cursor = skeleton_end;
+        for fragment in fragment_iter.by_ref().take(skeleton.fragment_count) {
+            let fragment_end = (cursor + fragment.length).min(text.len());
+            let insert_at = fix_insert_pos(&html, fragment.insert_pos.saturating_sub(skeleton.start).min(html.len()));
+            html.splice(insert_at..insert_at, text[cursor.min(fragment_end)..fragment_end].iter().copied());
+            cursor = fragment_end;
+        }
+        parts.push(Part {
+            html,
+            start: skeleton.start,
+            end: cursor,
+        });
+    }
+    parts
+}
+
+/// 把 `kindle:pos` 的 (fid, off) 解析为 (文件序号, 文件内偏移, 绝对位置)
+fn resolve_pos(parts: &[Part], fragments: &[Fragment], fid: usize, off: usize) -> Option<(usize, usize, usize)> {
+    let pos = fragments.get(fid)?.insert_pos + off;
+    let part = parts.iter().position(|p| p.start <= pos && pos < p.end)?;
+    Some((part, pos - parts[part].start, pos))
+}
+
+/// 正文在文件中的字节范围（<body> 内部），没有 <body> 时取整个文件
+fn body_range(html: &[u8]) -> (usize, usize) {
+    let start = BODY_OPEN_RE.find(html).map_or(0, |m| m.end());
+    let end = BODY_CLOSE_RE
+        .find_at(html, start)
+        .map_or(html.len(), |m| m.start());
+    (start, end.max(start))
+}
+
+/// 在锚点位置插入 `<span id="filepos{N}">`，与旧版 MOBI 的锚点格式一致，前端可直接定位和高亮
+/// 锚点落在标签内部时前移到标签起始处，落在正文之外时收拢到正文边界
+fn inject_anchors(html: &[u8], anchors: &BTreeMap<usize, Vec<usize>>, encoding: &'static Encoding) -> Vec<u8> {
+    let (body_start, body_end) = body_range(html);
+    let mut result = Vec::with_capacity(html.len() + anchors.len() * 32);
+    let mut cursor = 0usize;
+    for (&offset, positions) in anchors {
+        let mut at = offset.clamp(body_start, body_end);
+        let head = &html[body_start..at];
+        if let Some(open) = head.iter().rposition(|&b| b == b'<') {
+            if !head[open..].contains(&b'>') {
+                at = body_start + open;
+            }
+        }
+        let at = align_to_char_boundary(html, at, encoding).max(cursor);
+        result.extend_from_slice(&html[cursor..at]);
+        for pos in positions {
+            result.extend_from_slice(format!(r#"<span id="filepos{}"></span>"#, pos).as_bytes());
+        }
+        cursor = at;
+    }
+    result.extend_from_slice(&html[cursor..]);
+    result
+}
+
+/// KF8 解析上下文：还原后的文件与链接解析所需的映射
+struct Kf8Book<'a> {
+    parts: Vec<Part>,
+    fragments: Vec<Fragment>,
+    flows: Vec<String>,
+    /// 第一个资源记录的绝对索引（kindle:embed 编号从 1 开始）
+    first_resource: Option<usize>,
+    image_records: &'a [(usize, Vec<u8>)],
+    image_map: &'a HashMap<usize, String>,
+}
+
+impl Kf8Book<'_> {
+    /// kindle:embed 编号 → 图片资源路径
+    fn embed_path(&self, embed: usize) -> Option<&String> {
+        let absolute = self.first_resource? + embed.checked_sub(1)?;
+        let first_image = self.image_records.first()?.0;
+        self.image_map.get(&(absolute.checked_sub(first_image)? + 1))
+    }
+
+    /// 改写 kindle:embed / kindle:pos 链接为资源占位符与 filepos 锚点
+    fn rewrite_links(&self, html: &str) -> String {
+        let html = KINDLE_EMBED_RE.replace_all(html, |caps: &regex::Captures| {
+            match from_base32(&caps[1]).and_then(|n| self.embed_path(n)) {
+                Some(path) => format!("__MOBI_RES__:{}", path),
+                None => caps[0].to_string(),
+            }
+        });
+        KINDLE_POS_RE
+            .replace_all(&html, |caps: &regex::Captures| {
+                let resolved = from_base32(&caps[1])
+                    .zip(from_base32(&caps[2]))
+                    .and_then(|(fid, off)| resolve_pos(&self.parts, &self.fragments, fid, off));
+                match resolved {
+                    Some((_, _, pos)) => format!("#filepos{}", pos),
+                    None => caps[0].to_string(),
+                }
+            })
+            .into_owned()
+    }
+
+    /// 收集文件 <head> 中引用的 CSS flow 与内联样式
+    fn collect_styles(&self, head: &str) -> Vec<String> {
+        let mut styles = Vec::new();
+        for caps in KINDLE_FLOW_CSS_RE.captures_iter(head) {
+            if let Some(css) = from_base32(&caps[1]).and_then(|n| self.flows.get(n)) {
+                styles.push(self.rewrite_links(css));
+            }
+        }
+        for caps in STYLE_BLOCK_RE.captures_iter(head) {
+            let css = caps[1].trim();
+            if !css.is_empty() {
+                styles.push(self.rewrite_links(css));
+            }
+        }
+        styles
+    }
+}
+
+/// 第一个资源记录的绝对索引：KF8 头部的值在混合文件中可能相对于 KF8 record 0，逐个候选校验
+fn locate_first_resource(
+    data: &[u8],
+    offsets: &[usize],
+    base: usize,
+    header: &Kf8Header,
+    image_records: &[(usize, Vec<u8>)],
+) -> Option<usize> {
+    let is_resource = |index: usize| {
+        image_records.iter().any(|(i, _)| *i == index)
+            || get_record(data, offsets, index).is_some_and(|r| RESOURCE_MAGICS.iter().any(|m| r.starts_with(m)))
+    };
+    let declared = (header.first_resource != NULL_INDEX).then_some(header.first_resource as usize);
+    declared
+        .into_iter()
+        .flat_map(|index| [base + index, index])
+        .find(|&index| is_resource(index))
+        .or_else(|| image_records.first().map(|(i, _)| *i))
+}
+
+/// 从 KF8 NCX 构建目录树：tag 3 = 标题，tag 4 = 层级，tag 6 = (fid, off)，tag 21 = 父条目
+fn build_ncx_toc(
+    entries: &[IndexEntry],
+    cncx: &HashMap<usize, String>,
+    targets: &[Option<(u32, usize)>],
+) -> Vec<TocItem> {
+    let mut items: Vec<TocItem> = entries
+        .iter()
+        .zip(targets)
+        .map(|(entry, target)| TocItem {
+            title: entry.value(3, 0).and_then(|i| cncx.get(&i)).map(|s| s.trim().to_string()),
+            location: target.map(|(section, pos)| section_location(section, Some(&format!("filepos{}", pos)))),
+            level: entry.value(4, 0).unwrap_or(0) as i32,
+            children: Vec::new(),
+        })
+        .collect();
+
+    // 父条目总在子条目之前，倒序把子条目移入父条目即可保持原有顺序
+    let parents: Vec<Option<usize>> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| entry.value(21, 0).filter(|&p| p < i))
+        .collect();
+    let mut roots = Vec::new();
+    for i in (0..items.len()).rev() {
+        let item = std::mem::replace(
+            &mut items[i],
+            TocItem { title: None, location: None, level: 0, children: Vec::new() },
+        );
+        match parents[i] {
+            Some(parent) => items[parent].children.insert(0, item),
+            None => roots.insert(0, item),
+        }
+    }
+    roots.retain(|item| item.title.as_deref().is_some_and(|t| !t.is_empty()));
+    roots
+}
+
+/// 解析 KF8 内容，返回章节与目录；不是 KF8 或索引表不完整时返回 None，由调用方走旧版 MOBI 流程
+pub(super) fn prepare_kf8(
+    data: &[u8],
+    image_records: &[(usize, Vec<u8>)],
+    image_map: &HashMap<usize, String>,
+    encoding: &'static Encoding,
+) -> Option<(Vec<PreparedSection>, Vec<TocItem>)> {
+    let all_offsets = parse_record_offsets(data)?;
+    let (base, header) = locate_kf8(data, &all_offsets)?;
+    println!("[mobi-engine] 检测到 KF8: base_record={}", base);
+    let offsets = &all_offsets[base..];
+
+    let raw_ml = extract_raw_text_bytes_at(data, base)?;
+    let flow_ranges = parse_fdst(data, offsets, header.fdst, raw_ml.len());
+    let text = &raw_ml[flow_ranges[0].0..flow_ranges[0].1];
+
+    if header.skeleton == NULL_INDEX || header.fragment == NULL_INDEX {
+        println!("[mobi-engine] KF8 缺少骨架/片段索引");
+        return None;
+    }
+    let (skeleton_entries, _) = read_index(data, offsets, header.skeleton as usize, encoding)?;
+    let (fragment_entries, _) = read_index(data, offsets, header.fragment as usize, encoding)?;
+    let skeletons = read_skeletons(&skeleton_entries);
+    let fragments = read_fragments(&fragment_entries)?;
+    if skeletons.is_empty() {
+        println!("[mobi-engine] KF8 骨架表为空");
+        return None;
+    }
+    let parts = assemble_parts(text, &skeletons, &fragments);
+    println!(
+        "[mobi-engine] KF8 还原: files={}, fragments={}, flows={}",
+        parts.len(),
+        fragments.len(),
+        flow_ranges.len()
+    );
+
+    let ncx = (header.ncx != NULL_INDEX)
+        .then(|| read_index(data, offsets, header.ncx as usize, encoding))
+        .flatten();
+
+    // 收集所有跳转目标：正文中的 kindle:pos 链接与目录条目
+    let mut anchors: Vec<BTreeMap<usize, Vec<usize>>> = vec![BTreeMap::new(); parts.len()];
+    let mut add_anchor = |(part, offset, pos): (usize, usize, usize)| {
+        let positions = anchors[part].entry(offset).or_default();
+        if !positions.contains(&pos) {
+            positions.push(pos);
+        }
+        (part, pos)
+    };
+    for part in &parts {
+        for caps in KINDLE_POS_BYTES_RE.captures_iter(&part.html) {
+            let fid = std::str::from_utf8(&caps[1]).ok().and_then(from_base32);
+            let off = std::str::from_utf8(&caps[2]).ok().and_then(from_base32);
+            if let Some(target) = fid.zip(off).and_then(|(fid, off)| resolve_pos(&parts, &fragments, fid, off)) {
+                add_anchor(target);
+            }
+        }
+    }
+    let ncx_targets: Vec<Option<(usize, usize)>> = ncx
+        .as_ref()
+        .map(|(entries, _)| {
+            entries
+                .iter()
+                .map(|entry| {
+                    let target = resolve_pos(&parts, &fragments, entry.value(6, 0)?, entry.value(6, 1)?)?;
+                    Some(add_anchor(target))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let book = Kf8Book {
+        // flow 0 为正文，已还原为各文件，只解码其余 flow（CSS 等）
+        flows: flow_ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end))| {
+                if i == 0 {
+                    String::new()
+                } else {
+                    encoding.decode(&raw_ml[start..end]).0.into_owned()
+                }
+            })
+            .collect(),
+        first_resource: locate_first_resource(data, &all_offsets, base, &header, image_records),
+        parts,
+        fragments,
+        image_records,
+        image_map,
+    };
+
+    let mut sections: Vec<PreparedSection> = Vec::with_capacity(book.parts.len());
+    // 文件序号 → 章节序号；空文件不生成章节，指向它的目录落到后一个章节
+    let mut section_of_part: Vec<u32> = Vec::with_capacity(book.parts.len());
+    for (part, part_anchors) in book.parts.iter().zip(&anchors) {
+        section_of_part.push(sections.len() as u32);
+        let html = inject_anchors(&part.html, part_anchors, encoding);
+        let (body_start, body_end) = body_range(&html);
+        let (head, _, _) = encoding.decode(&html[..body_start]);
+        let (body, _, _) = encoding.decode(&html[body_start..body_end]);
+        let body = book.rewrite_links(body.trim());
+        if body.is_empty() {
+            continue;
+        }
+        let mut section = build_section(body, sections.len() as u32);
+        section.styles = book.collect_styles(&head);
+        for style in &section.styles {
+            for path in extract_resource_refs(style) {
+                if !section.resource_refs.contains(&path) {
+                    section.resource_refs.push(path);
+                }
+            }
+        }
+        sections.push(section);
+    }
+    if sections.is_empty() {
+        println!("[mobi-engine] KF8 还原后正文为空");
+        return None;
+    }
+    let last_section = sections.len() as u32 - 1;
+
+    let mut toc = match &ncx {
+        Some((entries, cncx)) => {
+            let targets: Vec<Option<(u32, usize)>> = ncx_targets
+                .iter()
+                .map(|t| t.map(|(part, pos)| (section_of_part[part].min(last_section), pos)))
+                .collect();
+            build_ncx_toc(entries, cncx, &targets)
+        }
+        None => Vec::new(),
+    };
+    if toc.is_empty() {
+        println!("[mobi-engine] KF8 NCX 目录为空，按标题生成目录");
+        toc = build_toc_from_sections(&mut sections);
+    }
+
+    Some((sections, toc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assemble_parts() {
+        // 两个文件，第二个文件有两个片段
+        let text = b"<html><body></body></html><p>a</p><html><body></body></html><p>b</p><p>c</p>";
+        let skeletons = [
+            Skeleton { fragment_count: 1, start: 0, length: 26 },
+            Skeleton { fragment_count: 2, start: 34, length: 26 },
+        ];
+        let fragments = [
+            Fragment { insert_pos: 12, length: 8 },
+            Fragment { insert_pos: 46, length: 8 },
+            Fragment { insert_pos: 54, length: 8 },
+        ];
+        let parts = assemble_parts(text, &skeletons, &fragments);
+
+        assert_eq!(parts[0].html, b"<html><body><p>a</p></body></html>");
+        assert_eq!((parts[0].start, parts[0].end), (0, 34));
+        assert_eq!(parts[1].html, b"<html><body><p>b</p><p>c</p></body></html>");
+        assert_eq!((parts[1].start, parts[1].end), (34, 76));
+
+        assert_eq!(from_base32("000A"), Some(10));
+        assert_eq!(resolve_pos(&parts, &fragments, 1, 0), Some((1, 12, 46)));
+        assert_eq!(resolve_pos(&parts, &fragments, 9, 0), None);
+
+        let mut anchors = BTreeMap::new();
+        anchors.insert(14, vec![46]);
+        let html = inject_anchors(&parts[1].html, &anchors, encoding_rs::UTF_8);
+        assert_eq!(
+            String::from_utf8(html).unwrap(),
+            r#"<html><body><span id="filepos46"></span><p>b</p><p>c</p></body></html>"#
+        );
+    }
+}
@@ -92,3 +92,60 @@ pub(super) static RES_RE: Lazy<Regex> = Lazy::new(|| {
 pub(super) static TAG_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"<[^>]*>").unwrap()
 });
+
+/// 匹配块级结束标签（字节级，截取脚注内容用）
+pub(super) static BLOCK_CLOSE_BYTES_RE: Lazy<regex::bytes::Regex> = Lazy::new(|| {
+    regex::bytes::Regex::new(r"(?i)</(?:p|div|blockquote)\s*>").unwrap()
+});
+
+/// 匹配带 filepos 的 <a> 开始标签（用于解码后的 HTML）
+pub(super) static ANCHOR_OPEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?filepos\s*=\s*["']?(\d+)["']?[^>]*>"#).unwrap()
+});
+
+/// 匹配脚注编号样式的链接文字：[1]、(2)、3、*、†、注1、①
+pub(super) static NOTE_LABEL_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^[\[(（〔【]?(?:\d{1,3}|[*†‡§]{1,3}|注\d{0,3}|[①-⑳])[\])）〕】]?$").unwrap()
+});
+
+// ====================== KF8 链接 ======================
+
+/// 匹配 KF8 内部跳转链接 kindle:pos:fid:XXXX:off:YYYYYYYYYY（字节级，用于收集锚点）
+pub(super) static KINDLE_POS_BYTES_RE: Lazy<regex::bytes::Regex> = Lazy::new(|| {
+    regex::bytes::Regex::new(r"kindle:pos:fid:([0-9A-Va-v]{4}):off:([0-9A-Va-v]{10})").unwrap()
+});
+
+/// 匹配 KF8 内部跳转链接（用于解码后的 HTML）
+pub(super) static KINDLE_POS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"kindle:pos:fid:([0-9A-Va-v]{4}):off:([0-9A-Va-v]{10})").unwrap()
+});
+
+/// 匹配 KF8 资源引用 kindle:embed:XXXX（可带 ?mime= 参数）
+pub(super) static KINDLE_EMBED_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"kindle:embed:([0-9A-Va-v]{4})(?:\?mime=[^"'()\s]*)?"#).unwrap()
+});
+
+/// 匹配引用 CSS flow 的 <link> 标签
+pub(super) static KINDLE_FLOW_CSS_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<link\b[^>]*?href\s*=\s*["']kindle:flow:([0-9A-Va-v]{4})\?mime=text/css["'][^>]*>"#).unwrap()
+});
+
+/// 匹配改写后指向 #fileposN 的链接及文字（用于解码后的 HTML，提取 KF8 脚注）
+pub(super) static HREF_FILEPOS_ANCHOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?href\s*=\s*["']#filepos(\d+)["'][^>]*>(.*?)</a>"#).unwrap()
+});
+
+/// 匹配改写后指向 #fileposN 的 <a> 开始标签（用于解码后的 HTML）
+pub(super) static HREF_FILEPOS_OPEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?href\s*=\s*["']#filepos(\d+)["'][^>]*>"#).unwrap()
+});
+
+/// 匹配块级结束标签（用于解码后的 HTML，截取 KF8 脚注内容）
+pub(super) static BLOCK_CLOSE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</(?:p|div|blockquote|aside)\s*>").unwrap()
+});
+
+/// 匹配内联 <style> 块
+pub(super) static STYLE_BLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<style[^>]*>(.*?)</style>").unwrap()
+});
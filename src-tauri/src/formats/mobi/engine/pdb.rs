@@ -31,6 +31,8 @@ struct PalmdocHeader {
     compression: u16,
     text_record_count: usize,
     text_length: usize,
+    /// 单条文本记录解压后的标准长度（通常为 4096）
+    record_size: usize,
 }
 
 struct MobiHeader {
@@ -62,6 +64,7 @@ fn parse_headers_with_offsets(data: &[u8], offsets: &[usize]) -> Option<(Palmdoc
         data[record0_start + 7],
     ]) as usize;
     let text_record_count = u16::from_be_bytes([data[record0_start + 8], data[record0_start + 9]]) as usize;
+    let record_size = u16::from_be_bytes([data[record0_start + 10], data[record0_start + 11]]) as usize;
 
     let mobi_start = record0_start + 16;
     if mobi_start + 4 > data.len() {
@@ -102,6 +105,7 @@ fn parse_headers_with_offsets(data: &[u8], offsets: &[usize]) -> Option<(Palmdoc
         compression,
         text_record_count,
         text_length,
+        record_size,
     };
     let mobi = MobiHeader {
         start: mobi_start,
@@ -122,7 +126,47 @@ fn parse_headers(data: &[u8]) -> Option<(PalmdocHeader, MobiHeader)> {
     parse_headers_with_offsets(data, &offsets)
 }
 
-fn map_mobi_encoding(val: u32) -> Option<&'static Encoding> {
+/// 从 record 0 头部检测 DRM：PalmDOC 加密类型非 0，或 MOBI header 的 DRM offset/count 有效
+/// （offset 为 0xFFFFFFFF 表示无 DRM）；返回检测依据，未加密时返回 None
+pub(super) fn detect_header_drm(data: &[u8]) -> Option<String> {
+    let offsets = parse_record_offsets(data)?;
+    let record0_start = offsets[0];
+    if record0_start + 16 > data.len() {
+        return None;
+    }
+
+    let encryption = u16::from_be_bytes([data[record0_start + 12], data[record0_start + 13]]);
+    if encryption != 0 {
+        return Some(format!("encryption_type={}", encryption));
+    }
+
+    let mobi_start = record0_start + 16;
+    if data.get(mobi_start..mobi_start + 8).is_none_or(|magic| &magic[..4] != b"MOBI") {
+        return None;
+    }
+    let header_len = u32::from_be_bytes([
+        data[mobi_start + 4],
+        data[mobi_start + 5],
+        data[mobi_start + 6],
+        data[mobi_start + 7],
+    ]) as usize;
+    // DRM offset/count 位于 record 0 偏移 0xA8/0xAC，需在 MOBI header 范围内
+    if 16 + header_len < 0xB0 || record0_start + 0xB0 > data.len() {
+        return None;
+    }
+    let read_u32 = |offset: usize| {
+        let p = record0_start + offset;
+        u32::from_be_bytes([data[p], data[p + 1], data[p + 2], data[p + 3]])
+    };
+    let drm_offset = read_u32(0xA8);
+    let drm_count = read_u32(0xAC);
+    if drm_offset != 0 && drm_offset != u32::MAX && drm_count > 0 && drm_count != u32::MAX {
+        return Some(format!("drm_offset={}, drm_count={}", drm_offset, drm_count));
+    }
+    None
+}
+
+pub(super) fn map_mobi_encoding(val: u32) -> Option<&'static Encoding> {
     match val {
         65001 => Some(encoding_rs::UTF_8),
         1252 | 28591 => Some(encoding_rs::WINDOWS_1252),
@@ -492,10 +536,35 @@ fn huffdic_decompress_record(
     huffdic_decompress_inner(ctx, input, out, 0)
 }
 
+/// 单条记录解压失败时插入的占位内容
+const DECODE_FAILED_PLACEHOLDER: &str = "<p>[本段内容解码失败]</p>";
+
+/// 解压成功的记录占比低于该值时判定整本书解压失败
+const MIN_DECODED_RECORD_RATIO: f32 = 0.5;
+
+/// 生成占位内容：按书籍编码写入（无法编码的字符转为 HTML 数字实体），
+/// 并用空格补足到标准记录长度，使后续记录的 filepos 偏移保持不变
+fn decode_failed_placeholder(encoding: &'static Encoding, record_size: usize) -> Vec<u8> {
+    let (encoded, _, _) = encoding.encode(DECODE_FAILED_PLACEHOLDER);
+    let mut placeholder = encoded.into_owned();
+    if placeholder.len() < record_size {
+        placeholder.resize(record_size, b' ');
+    }
+    placeholder
+}
+
 /// 提取并解压所有文本记录，返回原始字节流
+/// HuffDic 单条记录损坏时以占位内容代替并继续，只有大部分记录都失败时才返回 None
 pub(super) fn extract_raw_text_bytes(data: &[u8]) -> Option<Vec<u8>> {
+    extract_raw_text_bytes_at(data, 0)
+}
+
+/// 以第 `base` 条记录作为 record 0 提取文本（KF8 混合文件的 KF8 部分从 BOUNDARY 之后开始，
+/// 其头部中的记录索引均相对于该位置）
+pub(super) fn extract_raw_text_bytes_at(data: &[u8], base: usize) -> Option<Vec<u8>> {
     let offsets = parse_record_offsets(data)?;
-    let (palmdoc, mobi) = parse_headers_with_offsets(data, &offsets)?;
+    let offsets = offsets.get(base..)?;
+    let (palmdoc, mobi) = parse_headers_with_offsets(data, offsets)?;
 
     let compression_kind = compression_kind_from_u16(palmdoc.compression);
     let text_record_count = palmdoc.text_record_count;
@@ -516,7 +585,7 @@ pub(super) fn extract_raw_text_bytes(data: &[u8]) -> Option<Vec<u8>> {
 
     let mut huff_ctx = None;
     if let CompressionKind::HuffDic = compression_kind {
-        huff_ctx = build_huffdic_context(data, &offsets, &mobi);
+        huff_ctx = build_huffdic_context(data, offsets, &mobi);
         if huff_ctx.is_none() {
             println!("[mobi-engine] HuffDic 上下文解析失败，终止解压");
             return None;
@@ -524,6 +593,8 @@ pub(super) fn extract_raw_text_bytes(data: &[u8]) -> Option<Vec<u8>> {
     }
 
     let mut all_text = Vec::with_capacity(text_length.min(1024 * 1024));
+    let mut decoded_records = 0usize;
+    let mut failed_records = 0usize;
     for i in 1..=text_record_count {
         if i >= offsets.len() {
             break;
@@ -549,8 +620,13 @@ pub(super) fn extract_raw_text_bytes(data: &[u8]) -> Option<Vec<u8>> {
             CompressionKind::HuffDic => {
                 if let Some(ref mut ctx) = huff_ctx {
                     if huffdic_decompress_record(ctx, record_data, &mut all_text).is_err() {
-                        println!("[mobi-engine] HuffDic 解压失败，终止解压");
-                        return None;
+                        // 丢弃该记录已输出的半截内容，用占位提示代替，继续解压后续记录
+                        println!("[mobi-engine] HuffDic 解压记录 {} 失败，插入占位内容", i);
+                        all_text.truncate(before_len);
+                        let record_size = if palmdoc.record_size > 0 { palmdoc.record_size } else { 4096 };
+                        all_text.extend(decode_failed_placeholder(read_encoding_from_header(data), record_size));
+                        failed_records += 1;
+                        continue;
                     }
                 } else {
                     return None;
@@ -561,7 +637,22 @@ pub(super) fn extract_raw_text_bytes(data: &[u8]) -> Option<Vec<u8>> {
                 return None;
             }
         }
+        decoded_records += 1;
+    }
 
+    if failed_records > 0 {
+        let total = decoded_records + failed_records;
+        let ratio = decoded_records as f32 / total as f32;
+        println!(
+            "[mobi-engine] HuffDic 部分记录解压失败: failed={}, total={}, 有效占比={:.1}%",
+            failed_records,
+            total,
+            ratio * 100.0
+        );
+        if ratio < MIN_DECODED_RECORD_RATIO {
+            println!("[mobi-engine] 有效文本占比过低，判定解压失败");
+            return None;
+        }
     }
 
     // 用 text_length 截断，裁掉解压后多余的填充字节
@@ -863,7 +954,7 @@ fn count_unset_end(mut x: u8) -> u32 {
     count
 }
 
-fn read_u32_safe(data: &[u8], offset: usize) -> u32 {
+pub(super) fn read_u32_safe(data: &[u8], offset: usize) -> u32 {
     if offset + 4 > data.len() { return 0; }
     u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
 }
@@ -874,7 +965,7 @@ fn read_u16_safe(data: &[u8], offset: usize) -> u16 {
 }
 
 /// 获取 record 的字节切片
-fn get_record<'a>(data: &'a [u8], offsets: &[usize], index: usize) -> Option<&'a [u8]> {
+pub(super) fn get_record<'a>(data: &'a [u8], offsets: &[usize], index: usize) -> Option<&'a [u8]> {
     if index >= offsets.len() { return None; }
     let start = offsets[index];
     let end = if index + 1 < offsets.len() { offsets[index + 1] } else { data.len() };
@@ -916,11 +1007,21 @@ fn parse_cncx(
     cncx
 }
 
-/// 从 INDX data record 中解析每个条目的 tag map
-fn parse_indx_data_entries(
-    rec: &[u8],
-    tagx: &TagxInfo,
-) -> Vec<std::collections::HashMap<u8, Vec<usize>>> {
+/// INDX 索引条目：条目名（KF8 fragment 表中为十进制插入位置）与 tag map
+pub(super) struct IndexEntry {
+    pub name: Vec<u8>,
+    pub tags: std::collections::HashMap<u8, Vec<usize>>,
+}
+
+impl IndexEntry {
+    /// 读取 tag 的第 n 个值
+    pub fn value(&self, tag: u8, n: usize) -> Option<usize> {
+        self.tags.get(&tag).and_then(|v| v.get(n).copied())
+    }
+}
+
+/// 从 INDX data record 中解析每个条目的名称和 tag map
+fn parse_indx_data_entries(rec: &[u8], tagx: &TagxInfo) -> Vec<IndexEntry> {
     let indx = match parse_indx_header(rec) {
         Some(h) => h,
         None => return vec![],
@@ -933,6 +1034,7 @@ fn parse_indx_data_entries(
 
         let name_len = rec.get(entry_offset).copied().unwrap_or(0) as usize;
         let start_pos = entry_offset + 1 + name_len;
+        let name = rec.get(entry_offset + 1..start_pos).unwrap_or_default().to_vec();
 
         let mut tag_map: std::collections::HashMap<u8, Vec<usize>> = std::collections::HashMap::new();
         let mut control_byte_index = 0usize;
@@ -988,18 +1090,18 @@ fn parse_indx_data_entries(
                 tag_map.insert(tag, values);
             }
         }
-        entries.push(tag_map);
+        entries.push(IndexEntry { name, tags: tag_map });
     }
     entries
 }
 
-/// 从 INDX 记录解析 NCX 目录
-fn parse_ncx_from_indx(
+/// 读取整个 INDX 索引：主记录之后的 data record 中的全部条目，以及 CNCX 字符串表
+pub(super) fn read_index(
     data: &[u8],
     offsets: &[usize],
     indx_index: usize,
     encoding: &'static Encoding,
-) -> Option<Vec<NcxEntry>> {
+) -> Option<(Vec<IndexEntry>, std::collections::HashMap<usize, String>)> {
     // 读取 INDX 主记录（索引头）
     let indx_rec = get_record(data, offsets, indx_index)?;
     let indx_header = parse_indx_header(indx_rec)?;
@@ -1025,17 +1127,27 @@ fn parse_ncx_from_indx(
             Some(r) => r,
             None => continue,
         };
-        let entries = parse_indx_data_entries(rec, &tagx);
-        all_entries.extend(entries);
+        all_entries.extend(parse_indx_data_entries(rec, &tagx));
     }
+    Some((all_entries, cncx))
+}
 
+/// 从 INDX 记录解析 NCX 目录
+fn parse_ncx_from_indx(
+    data: &[u8],
+    offsets: &[usize],
+    indx_index: usize,
+    encoding: &'static Encoding,
+) -> Option<Vec<NcxEntry>> {
+    let (all_entries, cncx) = read_index(data, offsets, indx_index, encoding)?;
     if all_entries.is_empty() {
         return None;
     }
 
     // 将 tag map 转换为 NcxEntry
     let mut ncx = Vec::with_capacity(all_entries.len());
-    for tag_map in &all_entries {
+    for entry in &all_entries {
+        let tag_map = &entry.tags;
         // tag 1 = offset, tag 3 = cncx label index, tag 4 = heading level
         // tag 21 = parent, tag 22 = first child, tag 23 = last child
         let offset = tag_map.get(&1).and_then(|v| v.first().copied()).unwrap_or(0);
@@ -1066,4 +1178,39 @@ fn parse_ncx_from_indx(
 
     println!("[mobi-engine] INDX/NCX 解析成功: {} 项", ncx.len());
     Some(ncx)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 构造只含 record 0 和一条空记录的最小 MOBI 文件
+    fn minimal_mobi(encryption: u16, drm_offset: u32, drm_count: u32) -> Vec<u8> {
+        let record0_start = 78 + 2 * 8;
+        let mut data = vec![0u8; record0_start];
+        data[76..78].copy_from_slice(&2u16.to_be_bytes());
+        data[78..82].copy_from_slice(&(record0_start as u32).to_be_bytes());
+        data[86..90].copy_from_slice(&((record0_start + 0xE8) as u32).to_be_bytes());
+
+        let mut record0 = vec![0u8; 0xE8];
+        record0[0..2].copy_from_slice(&1u16.to_be_bytes());
+        record0[12..14].copy_from_slice(&encryption.to_be_bytes());
+        record0[16..20].copy_from_slice(b"MOBI");
+        record0[20..24].copy_from_slice(&(0xE8u32 - 16).to_be_bytes());
+        record0[0xA8..0xAC].copy_from_slice(&drm_offset.to_be_bytes());
+        record0[0xAC..0xB0].copy_from_slice(&drm_count.to_be_bytes());
+        data.extend(record0);
+        data
+    }
+
+    #[test]
+    fn test_detect_header_drm() {
+        assert_eq!(detect_header_drm(&minimal_mobi(0, u32::MAX, 0)), None);
+        assert_eq!(detect_header_drm(&minimal_mobi(2, u32::MAX, 0)), Some("encryption_type=2".to_string()));
+        assert_eq!(
+            detect_header_drm(&minimal_mobi(0, 0x100, 1)),
+            Some("drm_offset=256, drm_count=1".to_string())
+        );
+        assert_eq!(detect_header_drm(b"not a mobi"), None);
+    }
+}
@@ -44,7 +44,7 @@ fn is_image_data(data: &[u8]) -> bool {
         || (data.len() > 12 && &data[8..12] == b"WEBP") // WEBP
 }
 
-/// 将图片记录构建为资源列表和 recindex 映射
+/// 将图片记录构建为资源清单和 recindex 映射，清单与 `image_records` 一一对应
 pub(super) fn build_image_resources(image_records: &[(usize, Vec<u8>)]) -> (Vec<PreparedResource>, HashMap<usize, String>) {
     let mut resources = Vec::new();
     let mut image_map = HashMap::new();
@@ -63,8 +63,8 @@ pub(super) fn build_image_resources(image_records: &[(usize, Vec<u8>)]) -> (Vec<
 
         resources.push(PreparedResource {
             path: path.clone(),
-            data: img_data.clone(),
             mime_type: mime,
+            size: img_data.len(),
         });
         image_map.insert(recindex, path);
     }
@@ -205,11 +205,58 @@ fn extract_cover_from_guide(raw_text: &[u8], image_records: &[(usize, Vec<u8>)])
         .map(|(_, img_data)| img_data.clone())
 }
 
-/// 启发式封面选择：排除小图标，取最大图片
+/// 书籍封面常见的高宽比（约 2:3）
+const COVER_ASPECT_RATIO: f32 = 1.5;
+/// 只在前几张图片中寻找封面
+const COVER_CANDIDATE_LIMIT: usize = 8;
+
+/// 只解码图片头读取宽高
+fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    image::io::Reader::new(std::io::Cursor::new(data))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()
+}
+
+/// 封面候选打分：位置越靠前越好，高宽比越接近 2:3 越好，面积作为次要因素
+/// `area_ratio` 为该图面积（无法读取宽高时为字节数）与候选中最大值的比例
+fn cover_score(position: usize, dimensions: Option<(u32, u32)>, area_ratio: f32) -> f32 {
+    let position_score = 1.0 / (1.0 + position as f32);
+    let aspect_score = dimensions
+        .filter(|&(w, h)| w > 0 && h > 0)
+        .map(|(w, h)| (1.0 - (h as f32 / w as f32 - COVER_ASPECT_RATIO).abs()).max(0.0))
+        .unwrap_or(0.0);
+    position_score * 3.0 + aspect_score * 2.0 + area_ratio.sqrt()
+}
+
+/// 启发式封面选择（EXTH 与 guide 均未命中时使用）
+/// 排除小图标后，在前几张图片中综合位置、宽高比和尺寸打分，避免误选内文的大幅插图
 fn extract_cover_heuristic(image_records: &[(usize, Vec<u8>)]) -> Option<Vec<u8>> {
-    image_records.iter()
+    let candidates: Vec<_> = image_records
+        .iter()
         .filter(|(_, data)| data.len() > 1024) // 排除 < 1KB 的小图标
-        .max_by_key(|(_, data)| data.len())
+        .map(|(_, data)| (data, image_dimensions(data)))
+        .filter(|(_, dims)| dims.is_none_or(|(w, h)| w.min(h) >= 100))
+        .take(COVER_CANDIDATE_LIMIT)
+        .collect();
+
+    let size_of = |data: &Vec<u8>, dims: Option<(u32, u32)>| {
+        dims.map_or(data.len() as f32, |(w, h)| w as f32 * h as f32)
+    };
+    let max_size = candidates
+        .iter()
+        .map(|(data, dims)| size_of(data, *dims))
+        .fold(0.0f32, f32::max);
+
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(position, (data, dims))| {
+            let area_ratio = if max_size > 0.0 { size_of(data, *dims) / max_size } else { 0.0 };
+            (cover_score(position, *dims, area_ratio), *data)
+        })
+        .max_by(|a, b| a.0.total_cmp(&b.0))
         .map(|(_, data)| data.clone())
 }
 
@@ -240,6 +287,35 @@ fn extract_metadata_from_exth(data: &[u8], encoding: &'static Encoding) -> (Opti
     (title, author, description, publisher)
 }
 
+/// EXTH 中是否带 DRM 记录（record type 209 = Tamper-proof keys，仅出现在 DRM 书籍中）
+pub(super) fn has_drm_exth_record(data: &[u8]) -> bool {
+    parse_mobi_header(data)
+        .and_then(|info| find_exth_record(data, &info, 209))
+        .is_some_and(|record| !record.is_empty())
+}
+
+/// EXTH 中的 KF8 边界记录（record type 121 = KF8 BOUNDARY Offset），即 BOUNDARY 记录的索引
+pub(super) fn kf8_boundary(data: &[u8]) -> Option<usize> {
+    let info = parse_mobi_header(data)?;
+    let bytes = find_exth_record(data, &info, 121)?;
+    let value = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?);
+    (value != u32::MAX).then_some(value as usize)
+}
+
+/// 只从 EXTH 提取书名
+pub(super) fn extract_title(data: &[u8]) -> Option<String> {
+    extract_metadata_from_exth(data, detect_encoding(data)).0
+}
+
+/// 从 EXTH 提取语言（record type 524 = Language）
+pub(super) fn extract_language(data: &[u8]) -> Option<String> {
+    let info = parse_mobi_header(data)?;
+    let bytes = find_exth_record(data, &info, 524)?;
+    let (decoded, _, _) = detect_encoding(data).decode(&bytes);
+    let language = decoded.trim().trim_end_matches('\0').to_string();
+    (!language.is_empty()).then_some(language)
+}
+
 /// 提取元数据（含 mobi crate 回退 + 三层封面策略）
 pub(super) fn extract_metadata_safe(
     mobi_opt: Option<&Mobi>,
@@ -296,9 +372,39 @@ pub(super) fn extract_metadata_safe(
         author,
         description,
         publisher,
-        language: None,
+        language: extract_language(raw_bytes),
         page_count: 1,
         format,
         cover_image,
+        // EXTH 没有标准的系列记录，待识别到系列元数据后再填充
+        series: None,
+        series_index: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = image::RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 7 % 256) as u8, (y * 13 % 256) as u8, ((x ^ y) % 256) as u8])
+        });
+        let mut bytes = Vec::new();
+        image::DynamicImage::ImageRgb8(image)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_cover_heuristic_prefers_early_portrait_image() {
+        let cover = png(200, 300);
+        let records = vec![
+            (10, cover.clone()),
+            (11, png(600, 400)),
+            (12, png(640, 480)),
+        ];
+        assert_eq!(extract_cover_heuristic(&records), Some(cover));
     }
 }
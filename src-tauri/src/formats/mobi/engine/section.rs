@@ -13,6 +13,7 @@ use super::patterns::{
 use super::pdb::{align_to_char_boundary, extract_ncx_toc};
 use super::utils::{build_section, is_title_like, replace_recindex, strip_html_tags};
 use super::PreparedSection;
+use crate::formats::location::section_location;
 use crate::formats::mobi::cache::TocItem;
 
 // ====================== 字节级拆分 ======================
@@ -234,7 +235,7 @@ fn build_toc_from_ncx(
             })
             .unwrap_or(0) as u32;
 
-        let location = format!("section:{}#filepos{}", section_index, fp);
+        let location = section_location(section_index, Some(&format!("filepos{}", fp)));
         filepos_anchors.push((section_index, fp));
 
         items.push((i, TocItem {
@@ -368,7 +369,7 @@ fn build_toc_with_hierarchy(
             })
             .unwrap_or(0) as u32;
 
-        let location = format!("section:{}#filepos{}", section_index, filepos);
+        let location = section_location(section_index, Some(&format!("filepos{}", filepos)));
         filepos_anchors.push((section_index, *filepos));
 
         toc.push(TocItem {
@@ -456,7 +457,7 @@ fn nest_toc_by_level(toc: &mut Vec<TocItem>) {
     *toc = result;
 }
 
-fn build_toc_from_sections(sections: &mut [PreparedSection]) -> Vec<TocItem> {
+pub(super) fn build_toc_from_sections(sections: &mut [PreparedSection]) -> Vec<TocItem> {
     let mut toc = Vec::new();
     let section_count = sections.len();
 
@@ -490,7 +491,7 @@ fn build_toc_from_sections(sections: &mut [PreparedSection]) -> Vec<TocItem> {
 
             toc.push(TocItem {
                 title: Some(title),
-                location: Some(format!("section:{}#{}", section.index, anchor_id)),
+                location: Some(section_location(section.index, Some(&anchor_id))),
                 level: (level - 1).min(2),
                 children: vec![],
             });
@@ -519,14 +520,14 @@ fn build_toc_from_sections(sections: &mut [PreparedSection]) -> Vec<TocItem> {
             };
             toc.push(TocItem {
                 title: Some(display),
-                location: Some(format!("section:{}", section.index)),
+                location: Some(section_location(section.index, None)),
                 level: 0,
                 children: vec![],
             });
         } else {
             toc.push(TocItem {
                 title: Some(format!("第 {} 章", i + 1)),
-                location: Some(format!("section:{}", section.index)),
+                location: Some(section_location(section.index, None)),
                 level: 0,
                 children: vec![],
             });
@@ -580,7 +581,7 @@ fn split_by_chapter_pattern(html: &str) -> (Vec<PreparedSection>, Vec<TocItem>)
         let index = sections.len() as u32;
         toc.push(TocItem {
             title: Some(chapter_name.clone()),
-            location: Some(format!("section:{}", index)),
+            location: Some(section_location(index, None)),
             level: 0,
             children: vec![],
         });
@@ -638,7 +639,7 @@ fn split_by_length(html: &str, chunk_size: usize) -> (Vec<PreparedSection>, Vec<
             let index = sections.len() as u32;
             toc.push(TocItem {
                 title: Some(format!("第 {} 页", index + 1)),
-                location: Some(format!("section:{}", index)),
+                location: Some(section_location(index, None)),
                 level: 0,
                 children: vec![],
             });
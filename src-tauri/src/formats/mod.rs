@@ -2,10 +2,15 @@ use serde::{Deserialize, Serialize};
 use std::future::Future;
 use std::pin::Pin;
 
+pub mod comic;
 pub mod common;
 pub mod epub;
 pub mod html;
+pub mod location;
 pub mod markdown;
+pub mod pagination;
+pub mod sanitize;
+pub mod text_offset;
 pub mod txt;
 pub mod mobi;
 
@@ -35,8 +40,8 @@ pub trait BookRenderCache {
     fn cache_remove<'a>(&'a self, key: &'a Self::Key) -> BoxFuture<'a, Option<Self::Value>>;
     /// 清空所有缓存数据
     fn cache_clear_all<'a>(&'a self) -> BoxFuture<'a, ()>;
-    /// 清除指定文件某一页的缓存
-    fn cache_clear_page<'a>(&'a self, file_path: &'a str, page_number: u32) -> BoxFuture<'a, ()>;
+    /// 清除指定文件某一页的缓存，返回清除的条目数
+    fn cache_clear_page<'a>(&'a self, file_path: &'a str, page_number: u32) -> BoxFuture<'a, usize>;
     /// 获取当前缓存统计信息
     fn cache_stats<'a>(&'a self) -> BoxFuture<'a, Self::Stats>;
 }
@@ -52,6 +57,8 @@ pub enum BookFormat {
     Fb2,
     Html,
     Txt,
+    Cbz,
+    Cbr,
 }
 
 impl BookFormat {
@@ -66,6 +73,8 @@ impl BookFormat {
             BookFormat::Fb2 => &[".fb2"],
             BookFormat::Html => &[".html", ".htm"],
             BookFormat::Txt => &[".txt"],
+            BookFormat::Cbz => &[".cbz"],
+            BookFormat::Cbr => &[".cbr"],
         }
     }
 
@@ -87,6 +96,8 @@ impl BookFormat {
             ".fb2" => Some(BookFormat::Fb2),
             ".html" | ".htm" => Some(BookFormat::Html),
             ".txt" => Some(BookFormat::Txt),
+            ".cbz" => Some(BookFormat::Cbz),
+            ".cbr" => Some(BookFormat::Cbr),
             _ => None,
         }
     }
@@ -113,6 +124,10 @@ pub struct BookMetadata {
     pub cover_image: Option<Vec<u8>>,
     pub page_count: u32,
     pub format: Option<BookFormat>,
+    /// 所属系列名称
+    pub series: Option<String>,
+    /// 在系列中的序号（允许 1.5 这类外传序号）
+    pub series_index: Option<f32>,
 }
 
 /// 目录项
@@ -213,9 +228,10 @@ pub enum PageContent {
         format: ImageFormat,
     },
     /// HTML 内容，用于 EPUB/MOBI/FB2
+    /// 图片等资源应通过 `goread-res` 协议地址按需加载，`resources` 仅用于少量无法缓存的内联资源
     Html {
         content: String,
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "std::collections::HashMap::is_empty")]
         resources: std::collections::HashMap<String, Vec<u8>>,
     },
     /// 纯文本，用于 TXT
@@ -264,6 +280,8 @@ pub enum BookErrorCode {
     EncodingError,
     IoError,
     UnsupportedFeature,
+    /// 文件受 DRM 保护
+    DrmProtected,
     Unknown,
 }
 
@@ -325,6 +343,10 @@ impl BookError {
     pub fn encoding_error(encoding: &str) -> Self {
         Self::new(BookErrorCode::EncodingError, format!("编码错误: {}", encoding))
     }
+
+    pub fn drm_protected() -> Self {
+        Self::new(BookErrorCode::DrmProtected, "该文件受 DRM 保护，无法打开")
+    }
 }
 
 impl From<std::io::Error> for BookError {
@@ -382,6 +404,15 @@ pub fn is_scan_supported_format(format: &BookFormat) -> bool {
     SCAN_SUPPORTED_FORMATS.contains(format)
 }
 
+/// 解析系列序号文本（如 "2"、"1.5"），非法或非有限值返回 None
+pub fn parse_series_index(value: &str) -> Option<f32> {
+    value
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .filter(|index| index.is_finite() && *index >= 0.0)
+}
+
 /// 检查扩展名是否支持
 pub fn is_extension_supported(ext: &str) -> bool {
     BookFormat::from_extension(ext).is_some()
@@ -396,6 +427,8 @@ mod tests {
         assert_eq!(BookFormat::from_extension(".pdf"), Some(BookFormat::Pdf));
         assert_eq!(BookFormat::from_extension("pdf"), Some(BookFormat::Pdf));
         assert_eq!(BookFormat::from_extension(".epub"), Some(BookFormat::Epub));
+        assert_eq!(BookFormat::from_extension("CBZ"), Some(BookFormat::Cbz));
+        assert_eq!(BookFormat::from_extension(".cbr"), Some(BookFormat::Cbr));
         assert_eq!(BookFormat::from_extension(".unknown"), None);
     }
 
@@ -405,4 +438,12 @@ mod tests {
         assert_eq!(BookFormat::from_path("C:\\Books\\novel.epub"), Some(BookFormat::Epub));
         assert_eq!(BookFormat::from_path("README"), None);
     }
+
+    #[test]
+    fn test_parse_series_index() {
+        assert_eq!(parse_series_index(" 2 "), Some(2.0));
+        assert_eq!(parse_series_index("1.5"), Some(1.5));
+        assert_eq!(parse_series_index("第二卷"), None);
+        assert_eq!(parse_series_index("NaN"), None);
+    }
 }
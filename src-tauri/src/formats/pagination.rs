@@ -0,0 +1,217 @@
+//! 流式章节（EPUB/MOBI）的后端分页估算
+//! 根据字号、行距和视口尺寸估算每页可容纳的行数，按块级元素边界切分章节 HTML
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{BookError, BookErrorCode};
+
+/// 排版参数，与前端阅读器设置对应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypographyOptions {
+    /// 正文字号（px）
+    pub font_size_px: f32,
+    /// 行高倍数（如 1.6）
+    pub line_height: f32,
+    /// 内容区域宽度（px）
+    pub viewport_width: f32,
+    /// 内容区域高度（px）
+    pub viewport_height: f32,
+}
+
+impl TypographyOptions {
+    /// 校验参数是否可用于分页
+    pub fn validate(&self) -> Result<(), BookError> {
+        let fields = [
+            ("font_size_px", self.font_size_px),
+            ("line_height", self.line_height),
+            ("viewport_width", self.viewport_width),
+            ("viewport_height", self.viewport_height),
+        ];
+        for (name, value) in fields {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(BookError::new(
+                    BookErrorCode::InvalidParameter,
+                    format!("排版参数 {} 无效: {}", name, value),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 生成缓存键：参数按显示精度取整，避免浮点抖动导致缓存失效
+    /// 关闭 HTML 清洗时分页基于原始 HTML，追加 `_raw` 与清洗后的结果分开缓存
+    pub fn cache_key(&self) -> String {
+        let key = format!(
+            "f{}_l{}_{}x{}",
+            (self.font_size_px * 10.0).round() as i64,
+            (self.line_height * 100.0).round() as i64,
+            self.viewport_width.round() as i64,
+            self.viewport_height.round() as i64
+        );
+        if crate::formats::sanitize::sanitize_enabled() {
+            key
+        } else {
+            format!("{}_raw", key)
+        }
+    }
+
+    /// 单行可容纳的全角字符宽度数
+    fn units_per_line(&self) -> f32 {
+        (self.viewport_width / self.font_size_px).max(1.0)
+    }
+
+    /// 单页可容纳的行数
+    fn lines_per_page(&self) -> usize {
+        ((self.viewport_height / (self.font_size_px * self.line_height)).floor() as usize).max(1)
+    }
+}
+
+/// 单页在章节 HTML 中的字节区间（均位于块级元素边界）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionPage {
+    pub index: u32,
+    pub html_start: usize,
+    pub html_end: usize,
+    pub char_count: usize,
+}
+
+/// 章节分页结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionPagination {
+    pub section_index: u32,
+    /// 对应的排版参数缓存键
+    pub typography_key: String,
+    pub page_count: u32,
+    pub pages: Vec<SectionPage>,
+}
+
+/// 块级元素结束位置
+static BLOCK_END_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)</(p|div|h[1-6]|li|blockquote|pre|tr|table|section|figure)\s*>|<br\s*/?>|<hr\s*/?>").unwrap()
+});
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static IMG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)<(img|image|svg)\b").unwrap());
+static BODY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<body[^>]*>(.*)</body>").unwrap());
+
+/// 估算文本占用的宽度（以全角字符为 1，半角字符按 0.5 计）
+fn text_width_units(text: &str) -> f32 {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .map(|c| if (c as u32) < 0x2E80 { 0.5 } else { 1.0 })
+        .sum()
+}
+
+/// 按排版参数对章节 HTML 分页
+/// 不在块级元素内部截断；单个块超过一页时独占一页
+pub fn paginate_html(section_index: u32, html: &str, typography: &TypographyOptions) -> SectionPagination {
+    let (body_start, body_end) = BODY_RE
+        .captures(html)
+        .and_then(|c| c.get(1))
+        .map(|m| (m.start(), m.end()))
+        .unwrap_or((0, html.len()));
+
+    let units_per_line = typography.units_per_line();
+    let lines_per_page = typography.lines_per_page();
+
+    // 块边界（字节偏移，相对全文）
+    let body = &html[body_start..body_end];
+    let mut boundaries: Vec<usize> = BLOCK_END_RE
+        .find_iter(body)
+        .map(|m| body_start + m.end())
+        .collect();
+    if boundaries.last().copied() != Some(body_end) {
+        boundaries.push(body_end);
+    }
+
+    let mut pages = Vec::new();
+    let mut page_start = body_start;
+    let mut page_lines = 0usize;
+    let mut page_chars = 0usize;
+    let mut block_start = body_start;
+
+    for block_end in boundaries {
+        let block = &html[block_start..block_end];
+        let text = TAG_RE.replace_all(block, "");
+        let text = text.trim();
+        let char_count = text.chars().count();
+
+        let mut lines = if text.is_empty() {
+            0
+        } else {
+            (text_width_units(text) / units_per_line).ceil() as usize
+        };
+        // 图片按半页估算
+        lines += IMG_RE.find_iter(block).count() * (lines_per_page / 2).max(1);
+
+        if page_lines > 0 && page_lines + lines > lines_per_page {
+            pages.push(SectionPage {
+                index: pages.len() as u32,
+                html_start: page_start,
+                html_end: block_start,
+                char_count: page_chars,
+            });
+            page_start = block_start;
+            page_lines = 0;
+            page_chars = 0;
+        }
+
+        page_lines += lines;
+        page_chars += char_count;
+        block_start = block_end;
+    }
+
+    if page_start < body_end || pages.is_empty() {
+        pages.push(SectionPage {
+            index: pages.len() as u32,
+            html_start: page_start,
+            html_end: body_end,
+            char_count: page_chars,
+        });
+    }
+
+    SectionPagination {
+        section_index,
+        typography_key: typography.cache_key(),
+        page_count: pages.len() as u32,
+        pages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typography(font_size_px: f32) -> TypographyOptions {
+        TypographyOptions {
+            font_size_px,
+            line_height: 1.5,
+            viewport_width: 320.0,
+            viewport_height: 480.0,
+        }
+    }
+
+    #[test]
+    fn test_larger_font_produces_more_pages() {
+        let para = format!("<p>{}</p>", "中文段落内容".repeat(20));
+        let html = format!("<html><body>{}</body></html>", para.repeat(10));
+
+        let small = paginate_html(0, &html, &typography(14.0));
+        let large = paginate_html(0, &html, &typography(24.0));
+
+        assert!(large.page_count > small.page_count);
+        assert_ne!(small.typography_key, large.typography_key);
+        // 页面区间首尾相接且覆盖 body
+        for pair in large.pages.windows(2) {
+            assert_eq!(pair[0].html_end, pair[1].html_start);
+        }
+    }
+
+    #[test]
+    fn test_invalid_typography_rejected() {
+        let mut t = typography(16.0);
+        t.viewport_height = 0.0;
+        assert!(t.validate().is_err());
+    }
+}
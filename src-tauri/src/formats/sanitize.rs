@@ -0,0 +1,252 @@
+//! 章节 HTML 清洗
+//! EPUB/MOBI/HTML 章节直接注入 WebView，书中可能夹带脚本、事件属性、`javascript:` 链接以及追踪像素等外部请求。
+//! 返回前端前统一用 ammonia 按白名单重新解析并序列化：只保留排版相关的标签与属性，移除脚本类元素及其内容、
+//! `on*` 事件属性、脚本协议地址和加载外部 http 资源的属性/样式，class、内联样式与书内资源占位符原样保留。
+//! 高级用户可通过开关关闭
+
+use ammonia::Builder;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 清洗开关，默认开启
+static SANITIZE_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// 在 ammonia 默认白名单之外保留的排版标签；`style` 的内容另按 CSS 清洗
+const EXTRA_TAGS: &[&str] = &[
+    "section", "main", "font", "big", "style", "link", "audio", "video", "source", "track",
+];
+
+/// 书中常见的 SVG 插图与 MathML 公式元素
+const SVG_TAGS: &[&str] = &[
+    "svg", "g", "defs", "use", "image", "path", "rect", "circle", "ellipse", "line", "polyline",
+    "polygon", "text", "tspan",
+];
+const SVG_ATTRS: &[&str] = &[
+    "viewBox", "preserveAspectRatio", "xmlns", "version", "href", "x", "y", "x1", "y1", "x2", "y2",
+    "cx", "cy", "r", "rx", "ry", "d", "points", "transform", "fill", "fill-opacity", "stroke",
+    "stroke-width", "opacity", "font-size", "text-anchor",
+];
+const MATH_TAGS: &[&str] = &[
+    "math", "mrow", "mi", "mo", "mn", "ms", "mtext", "mspace", "msup", "msub", "msubsup", "mfrac",
+    "msqrt", "mroot", "mover", "munder", "munderover", "mtable", "mtr", "mtd", "semantics",
+];
+const MATH_ATTRS: &[&str] = &["display", "mathvariant", "xmlns"];
+
+/// 所有保留标签上都允许的属性
+const GENERIC_ATTRS: &[&str] = &[
+    "class", "id", "style", "dir", "align", "width", "height", "epub:type", "xml:lang", "role",
+];
+const GENERIC_ATTR_PREFIXES: &[&str] = &["data-", "aria-"];
+
+/// 连同内容整体移除的元素：脚本、嵌入对象，以及内容按原始文本解析的 RAWTEXT 元素
+const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "noscript", "iframe", "frame", "frameset", "object", "embed", "applet", "portal",
+    "xmp", "noembed", "noframes", "textarea", "title", "plaintext",
+];
+
+/// 加载资源或发起请求的地址属性，外部地址会被移除；`<a href>` 由用户点击触发，不在此列
+/// （ammonia 传入的是去掉命名空间前缀的属性名，`xlink:href` 按 `href` 处理）
+const RESOURCE_ATTRS: &[&str] = &[
+    "src", "srcset", "poster", "background", "action", "formaction", "data", "longdesc", "lowsrc",
+    "dynsrc", "manifest", "icon",
+];
+
+/// 按白名单清洗的 ammonia 配置，进程内只构建一次
+static CLEANER: Lazy<Builder<'static>> = Lazy::new(|| {
+    let mut builder = Builder::default();
+    builder
+        .add_tags(EXTRA_TAGS)
+        .add_tags(SVG_TAGS)
+        .add_tags(MATH_TAGS)
+        .rm_clean_content_tags(&["style"])
+        .add_clean_content_tags(DROPPED_ELEMENTS)
+        .add_generic_attributes(GENERIC_ATTRS)
+        .add_generic_attribute_prefixes(GENERIC_ATTR_PREFIXES)
+        .add_tag_attributes("a", &["name"])
+        .add_tag_attributes("img", &["srcset"])
+        .add_tag_attributes("style", &["type", "media"])
+        .add_tag_attributes("link", &["rel", "href", "type", "media"])
+        .add_tag_attributes("audio", &["src", "controls"])
+        .add_tag_attributes("video", &["src", "controls", "poster"])
+        .add_tag_attributes("source", &["src", "srcset", "type", "media"])
+        .add_tag_attributes("track", &["src", "kind", "srclang", "label"])
+        .add_url_schemes(&["data"])
+        .link_rel(None)
+        .attribute_filter(filter_attr);
+    for tag in SVG_TAGS {
+        builder.add_tag_attributes(tag, SVG_ATTRS);
+    }
+    for tag in MATH_TAGS {
+        builder.add_tag_attributes(tag, MATH_ATTRS);
+    }
+    builder
+});
+
+/// `<style>` 元素的内容，在交给 ammonia 之前先按 CSS 清洗外部请求
+static STYLE_ELEMENT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)(<style\b[^>]*>)(.*?)(</style\s*>)").unwrap());
+/// CSS 中的 `url(...)`
+static CSS_URL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^)]*?))\s*\)"#).unwrap());
+/// CSS 中引用外部地址的 `@import`
+static CSS_IMPORT_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?i)@import\s+(?:url\(\s*)?["']?\s*(?:https?:|ftp:|//)[^;]*;?"#).unwrap()
+});
+/// IE 的 CSS 表达式
+static CSS_EXPRESSION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)expression\s*\(").unwrap());
+static NUMERIC_ENTITY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"&#(?:[xX]([0-9a-fA-F]+)|([0-9]+));?").unwrap());
+
+/// 设置是否清洗章节 HTML
+pub fn set_sanitize_enabled(enabled: bool) {
+    SANITIZE_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// 当前是否清洗章节 HTML
+pub fn sanitize_enabled() -> bool {
+    SANITIZE_ENABLED.load(Ordering::Relaxed)
+}
+
+/// 开关开启时清洗 HTML，否则原样返回
+pub fn sanitize_html_if_enabled(html: String) -> String {
+    if sanitize_enabled() {
+        sanitize_html(&html)
+    } else {
+        html
+    }
+}
+
+/// 开关开启时清洗样式表中的外部请求，否则原样返回
+pub fn sanitize_css_if_enabled(css: String) -> String {
+    if sanitize_enabled() {
+        sanitize_css(&css)
+    } else {
+        css
+    }
+}
+
+/// 规整地址用于判断协议：解码数字实体与常见命名实体，去掉空白与控制字符并转小写，
+/// 防止 `jav&#x61;script:`、`java\tscript:` 之类的绕过
+fn normalize_url(value: &str) -> String {
+    let decoded = NUMERIC_ENTITY_RE.replace_all(value, |caps: &Captures| {
+        let code = match (caps.get(1), caps.get(2)) {
+            (Some(hex), _) => u32::from_str_radix(hex.as_str(), 16).ok(),
+            (_, Some(dec)) => dec.as_str().parse().ok(),
+            _ => None,
+        };
+        code.and_then(char::from_u32).map(String::from).unwrap_or_default()
+    });
+    decoded
+        .replace("&colon;", ":")
+        .replace("&Tab;", "")
+        .replace("&NewLine;", "")
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// 脚本协议，出现在任何属性中都移除
+fn is_script_url(normalized: &str) -> bool {
+    normalized.starts_with("javascript:")
+        || normalized.starts_with("vbscript:")
+        || normalized.starts_with("data:text/html")
+        || normalized.starts_with("data:application/")
+}
+
+/// 外部网络地址；书内资源协议（Windows/Android 上为 `http://goread-res.localhost/`）不算外部
+fn is_external_url(normalized: &str) -> bool {
+    let external = normalized.starts_with("http:")
+        || normalized.starts_with("https:")
+        || normalized.starts_with("ftp:")
+        || normalized.starts_with("//");
+    external && !normalized.contains(".localhost/") && !normalized.starts_with("//localhost")
+}
+
+/// 清洗样式：外部 `url()` 替换为 `none`，移除外部 `@import` 与 IE 的 `expression()`
+pub fn sanitize_css(css: &str) -> String {
+    let css = CSS_IMPORT_RE.replace_all(css, "");
+    let css = CSS_URL_RE.replace_all(&css, |caps: &Captures| {
+        let url = caps.get(1).or(caps.get(2)).or(caps.get(3)).map_or("", |m| m.as_str());
+        let normalized = normalize_url(url);
+        if is_external_url(&normalized) || is_script_url(&normalized) {
+            "none".to_string()
+        } else {
+            caps[0].to_string()
+        }
+    });
+    CSS_EXPRESSION_RE.replace_all(&css, "none(").into_owned()
+}
+
+/// 过滤白名单内的属性（值已由解析器解码），返回 None 表示移除
+fn filter_attr<'u>(element: &str, attribute: &str, value: &'u str) -> Option<Cow<'u, str>> {
+    let normalized = normalize_url(value);
+    if is_script_url(&normalized) {
+        return None;
+    }
+    if attribute == "style" {
+        return Some(Cow::Owned(sanitize_css(value)));
+    }
+    let loads_resource = RESOURCE_ATTRS.contains(&attribute)
+        || (attribute == "href" && !matches!(element, "a" | "area"));
+    if loads_resource {
+        let external = if attribute == "srcset" {
+            value.split(',').any(|candidate| {
+                let url = candidate.split_whitespace().next().unwrap_or("");
+                is_external_url(&normalize_url(url))
+            })
+        } else {
+            is_external_url(&normalized)
+        };
+        if external {
+            return None;
+        }
+    }
+    Some(Cow::Borrowed(value))
+}
+
+/// 清洗 HTML：`<style>` 内容先按 CSS 清洗，再由 ammonia 按白名单重新解析并序列化。
+/// 预处理只影响样式中的外部请求，最终结构以 ammonia 的解析为准（SVG/MathML 内的 `<style>` 按标签处理）
+pub fn sanitize_html(html: &str) -> String {
+    let html = STYLE_ELEMENT_RE.replace_all(html, |caps: &Captures| {
+        format!("{}{}{}", &caps[1], sanitize_css(&caps[2]), &caps[3])
+    });
+    CLEANER.clean(&html).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_html() {
+        let html = r#"<p class="note" style="color:red;background:url('https://t.example/p.gif')" onclick="steal()">正文<script>alert(1)</script></p><img src="https://tracker.example/pixel.gif" width="1"/><img src="__EPUB_RES__:images/a.png" alt="图"><a href="jav&#x61;script:alert(1)">x</a><a href="https://example.com/">链接</a><iframe src="https://ads.example"><p>广告</p></iframe><!-- 注释 -->"#;
+        let cleaned = sanitize_html(html);
+        assert_eq!(
+            cleaned,
+            r#"<p class="note" style="color:red;background:none">正文</p><img width="1"><img src="__EPUB_RES__:images/a.png" alt="图"><a>x</a><a href="https://example.com/">链接</a>"#
+        );
+
+        let style = "<style>@import url(https://fonts.example/a.css);p{background:url(__EPUB_RES__:bg.png)}</style><p>a < b</p>";
+        assert_eq!(sanitize_html(style), "<style>p{background:url(__EPUB_RES__:bg.png)}</style><p>a &lt; b</p>");
+
+        // 书内资源协议地址与 data 图片保留
+        let local = r#"<img src="http://goread-res.localhost/book/a.png"><img src="data:image/png;base64,AAAA">"#;
+        assert_eq!(sanitize_html(local), local);
+    }
+
+    #[test]
+    fn test_sanitize_html_parser_differentials() {
+        // SVG 中的 `<style>` 按标签解析，内部的 img 不能带着事件属性漏出
+        let svg = "<svg><style><img src=x onerror=alert(1)></style></svg>";
+        assert_eq!(sanitize_html(svg), r#"<svg><style></style></svg><img src="x">"#);
+
+        // RAWTEXT 元素连同内容移除，属性值中的伪结束标签不会让后面的 img 逃逸出事件属性
+        let xmp = r#"<xmp><p title="</xmp><img src=x onerror=alert(1)>">"#;
+        let cleaned = sanitize_html(xmp);
+        assert!(!cleaned.contains("onerror"));
+        assert!(!cleaned.contains("<p"));
+    }
+}
@@ -0,0 +1,140 @@
+//! 流式章节（EPUB/MOBI）纯文本偏移与 HTML 位置的换算
+//! 阅读进度按章节内纯文本字符偏移保存，与字号、视口宽度无关，换设备或换字号后仍能回到同一段落。
+//! 纯文本与 DOM 的 textContent 一致：只取 body 内容，去除标签，字符实体计为一个字符，空白原样计入
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+static BODY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<body[^>]*>(.*)</body>").unwrap());
+static ENTITY_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^&(#[0-9]+|#[xX][0-9a-fA-F]+|[A-Za-z][A-Za-z0-9]*);").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static ID_ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\sid\s*=\s*["']([^"']+)["']"#).unwrap());
+
+/// 纯文本偏移在章节 HTML 中对应的位置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionTextAnchor {
+    /// 偏移处字符在 HTML 中的字节位置，可与分页结果的 html_start/html_end 比较得到所在页
+    pub html_offset: usize,
+    /// 该位置之前最近的带 id 元素，前端可先滚动到该元素再按剩余文本二次定位
+    pub anchor_id: Option<String>,
+    /// anchor_id 元素起点到偏移处之间的纯文本字符数
+    pub chars_after_anchor: usize,
+}
+
+fn body_range(html: &str) -> (usize, usize) {
+    BODY_RE
+        .captures(html)
+        .and_then(|c| c.get(1))
+        .map(|m| (m.start(), m.end()))
+        .unwrap_or((0, html.len()))
+}
+
+/// 按顺序遍历正文纯文本字符，回调参数为字符在 HTML 中的字节位置，返回 false 时停止
+fn for_each_text_char(html: &str, mut f: impl FnMut(usize) -> bool) {
+    let (start, end) = body_range(html);
+    let mut pos = start;
+    while pos < end {
+        let rest = &html[pos..end];
+        if rest.starts_with('<') {
+            pos += rest.find('>').map(|i| i + 1).unwrap_or(rest.len());
+            continue;
+        }
+        let len = ENTITY_RE
+            .find(rest)
+            .map(|m| m.end())
+            .unwrap_or_else(|| rest.chars().next().map_or(1, char::len_utf8));
+        if !f(pos) {
+            return;
+        }
+        pos += len;
+    }
+}
+
+/// 章节纯文本长度（按 Unicode 字符计）
+pub fn section_text_length(html: &str) -> usize {
+    let mut count = 0;
+    for_each_text_char(html, |_| {
+        count += 1;
+        true
+    });
+    count
+}
+
+/// 把纯文本偏移换算为 HTML 中的位置，超出正文长度时定位到正文末尾
+pub fn locate_text_offset(html: &str, char_offset: usize) -> SectionTextAnchor {
+    let (_, body_end) = body_range(html);
+    let mut html_offset = body_end;
+    let mut index = 0;
+    for_each_text_char(html, |pos| {
+        if index == char_offset {
+            html_offset = pos;
+            return false;
+        }
+        index += 1;
+        true
+    });
+
+    // 最近的带 id 的开始标签，以及它到目标位置之间的纯文本字符数
+    let (body_start, _) = body_range(html);
+    let anchor = TAG_RE
+        .find_iter(&html[body_start..html_offset])
+        .filter_map(|tag| {
+            ID_ATTR_RE
+                .captures(tag.as_str())
+                .map(|c| (body_start + tag.start(), c[1].to_string()))
+        })
+        .last();
+    let (anchor_id, chars_after_anchor) = match anchor {
+        Some((anchor_pos, id)) => {
+            let mut count = 0;
+            for_each_text_char(html, |pos| {
+                if pos >= html_offset {
+                    return false;
+                }
+                if pos > anchor_pos {
+                    count += 1;
+                }
+                true
+            });
+            (Some(id), count)
+        }
+        None => (None, index),
+    };
+
+    SectionTextAnchor {
+        html_offset,
+        anchor_id,
+        chars_after_anchor,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locate_text_offset() {
+        let html = r#"<html><head><title>标题</title></head><body>
+<h1 id="c1">第一章</h1><p>A&amp;B 段落</p><p id="p2">第二段<b>加粗</b></p></body></html>"#;
+        // "\n" + "第一章" + "A&B 段落" + "第二段加粗"
+        assert_eq!(section_text_length(html), 1 + 3 + 6 + 5);
+
+        let anchor = locate_text_offset(html, 4);
+        assert!(html[anchor.html_offset..].starts_with("A&amp;B"));
+        assert_eq!((anchor.anchor_id.as_deref(), anchor.chars_after_anchor), (Some("c1"), 3));
+
+        // 实体计为一个字符：偏移 6 落在 "B" 上
+        assert!(html[locate_text_offset(html, 6).html_offset..].starts_with("B 段落"));
+
+        let anchor = locate_text_offset(html, 14);
+        assert!(html[anchor.html_offset..].starts_with("粗"));
+        assert_eq!((anchor.anchor_id.as_deref(), anchor.chars_after_anchor), (Some("p2"), 4));
+
+        // 超出长度时定位到正文末尾
+        let end = locate_text_offset(html, 100);
+        assert!(html[end.html_offset..].starts_with("</body>"));
+    }
+}
@@ -0,0 +1,207 @@
+//! 字符偏移到字节偏移的精确映射
+//! 章节的字符偏移基于规范化后的全文（统一换行、压缩空行），而章节加载按原文件字节偏移 seek，
+//! 这里按原文逐行解码并重放规范化规则，记录每行在规范化文本中的起始字符偏移与原文字节偏移，
+//! 查询时二分定位所在行，行内再逐字节解码累计，任意编码下都能得到精确的字节边界
+
+use encoding_rs::Encoding;
+
+/// 原文中的一行
+struct RawLine {
+    /// 原文行号（从 0 开始）
+    line: u64,
+    /// 规范化文本中的行首字符偏移
+    char_start: u64,
+    /// 原文行首字节偏移
+    byte_start: usize,
+    /// 原文行内容结束字节偏移（不含换行符）
+    byte_end: usize,
+}
+
+pub(super) struct CharByteIndex<'a> {
+    raw_bytes: &'a [u8],
+    encoding: &'static Encoding,
+    lines: Vec<RawLine>,
+    total_chars: u64,
+}
+
+impl<'a> CharByteIndex<'a> {
+    /// 按原文字节与编码名称建立索引，规则与 `TxtEngine::normalize_text` 保持一致
+    pub(super) fn build(raw_bytes: &'a [u8], encoding: &str) -> Self {
+        let encoding = Encoding::for_label(encoding.as_bytes()).unwrap_or(encoding_rs::UTF_8);
+        let body_start = Self::bom_len(raw_bytes, encoding);
+
+        let mut lines = Vec::new();
+        let mut char_offset: u64 = 0;
+        let mut consecutive_empty = 0;
+        for (line, (byte_start, byte_end)) in Self::split_lines(raw_bytes, body_start, encoding).into_iter().enumerate() {
+            let (text, _) = encoding.decode_without_bom_handling(&raw_bytes[byte_start..byte_end]);
+            let line_chars = if text.trim().is_empty() {
+                consecutive_empty += 1;
+                // 超过 2 行的连续空行在规范化文本中被丢弃
+                if consecutive_empty > 2 {
+                    continue;
+                }
+                1
+            } else {
+                consecutive_empty = 0;
+                text.chars().count() as u64 + 1
+            };
+            lines.push(RawLine {
+                line: line as u64,
+                char_start: char_offset,
+                byte_start,
+                byte_end,
+            });
+            char_offset += line_chars;
+        }
+
+        Self {
+            raw_bytes,
+            encoding,
+            lines,
+            total_chars: char_offset,
+        }
+    }
+
+    /// 规范化文本的总字符数
+    pub(super) fn total_chars(&self) -> u64 {
+        self.total_chars
+    }
+
+    /// 原文总字节数
+    pub(super) fn total_bytes(&self) -> u64 {
+        self.raw_bytes.len() as u64
+    }
+
+    /// 将原文行号（从 0 开始）换算为该行在规范化文本中的起始字符偏移
+    /// 被压缩掉的空行定位到其后第一个保留行，超出末行时返回总字符数
+    pub(super) fn line_char_offset(&self, line: u64) -> u64 {
+        let index = self.lines.partition_point(|raw| raw.line < line);
+        self.lines.get(index).map_or(self.total_chars, |raw| raw.char_start)
+    }
+
+    /// 将规范化文本中的字符偏移换算为原文字节偏移
+    pub(super) fn byte_offset(&self, char_offset: u64) -> u64 {
+        if char_offset >= self.total_chars {
+            return self.total_bytes();
+        }
+        let line_index = self.lines.partition_point(|line| line.char_start <= char_offset);
+        let Some(line) = line_index.checked_sub(1).and_then(|i| self.lines.get(i)) else {
+            return 0;
+        };
+        let within = char_offset - line.char_start;
+        if within == 0 {
+            return line.byte_start as u64;
+        }
+
+        // 行内逐字节解码，直到已解码的字符数达到目标
+        let mut decoder = self.encoding.new_decoder_without_bom_handling();
+        let mut decoded = String::with_capacity(8);
+        let mut chars: u64 = 0;
+        for pos in line.byte_start..line.byte_end {
+            let _ = decoder.decode_to_string(&self.raw_bytes[pos..pos + 1], &mut decoded, false);
+            chars += decoded.chars().count() as u64;
+            decoded.clear();
+            if chars >= within {
+                return (pos + 1) as u64;
+            }
+        }
+        line.byte_end as u64
+    }
+
+    /// 与指定编码匹配的 BOM 长度
+    fn bom_len(raw_bytes: &[u8], encoding: &'static Encoding) -> usize {
+        match Encoding::for_bom(raw_bytes) {
+            Some((bom_encoding, len)) if bom_encoding == encoding => len,
+            _ => 0,
+        }
+    }
+
+    /// 按 `\n`、`\r\n`、`\r` 切分原文行，返回每行内容的字节区间
+    /// UTF-16 以两字节为单位识别换行，其余编码中换行字节不会出现在多字节字符内部
+    fn split_lines(raw_bytes: &[u8], body_start: usize, encoding: &'static Encoding) -> Vec<(usize, usize)> {
+        let unit = if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE {
+            2
+        } else {
+            1
+        };
+        let unit_at = |pos: usize| -> Option<u16> {
+            let bytes = raw_bytes.get(pos..pos + unit)?;
+            Some(match (unit, encoding == encoding_rs::UTF_16BE) {
+                (1, _) => bytes[0] as u16,
+                (_, true) => u16::from_be_bytes([bytes[0], bytes[1]]),
+                _ => u16::from_le_bytes([bytes[0], bytes[1]]),
+            })
+        };
+
+        let mut lines = Vec::new();
+        let mut line_start = body_start;
+        let mut pos = body_start;
+        while let Some(value) = unit_at(pos) {
+            match value {
+                0x0A => {
+                    lines.push((line_start, pos));
+                    pos += unit;
+                    line_start = pos;
+                }
+                0x0D => {
+                    lines.push((line_start, pos));
+                    pos += unit;
+                    if unit_at(pos) == Some(0x0A) {
+                        pos += unit;
+                    }
+                    line_start = pos;
+                }
+                _ => pos += unit,
+            }
+        }
+        // 末尾没有换行符的最后一行
+        if line_start < raw_bytes.len() {
+            lines.push((line_start, raw_bytes.len()));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_char_byte_index_exact_offsets() {
+        let text = "第一章 開端\r\n\r\n\r\n\r\n正文abc內容\r\n第二章 發展\r\n更多正文";
+        // 规范化后："第一章 開端\n\n\n正文abc內容\n第二章 發展\n更多正文\n"
+        let normalized = "第一章 開端\n\n\n正文abc內容\n第二章 發展\n更多正文\n";
+        for encoding in [encoding_rs::GBK, encoding_rs::BIG5, encoding_rs::UTF_8, encoding_rs::UTF_16LE] {
+            let raw = if encoding == encoding_rs::UTF_16LE {
+                text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect::<Vec<u8>>()
+            } else {
+                encoding.encode(text).0.into_owned()
+            };
+            let index = CharByteIndex::build(&raw, encoding.name());
+            assert_eq!(index.total_chars(), normalized.chars().count() as u64);
+
+            let chapter_two = normalized.find("第二章").unwrap();
+            let char_offset = normalized[..chapter_two].chars().count() as u64;
+            let byte_offset = index.byte_offset(char_offset) as usize;
+            let (rest, _) = encoding.decode_without_bom_handling(&raw[byte_offset..]);
+            assert!(rest.starts_with("第二章 發展"), "encoding={}", encoding.name());
+
+            // 行内偏移：“正文”之后
+            let inline = normalized.find("abc").unwrap();
+            let char_offset = normalized[..inline].chars().count() as u64;
+            let byte_offset = index.byte_offset(char_offset) as usize;
+            let (rest, _) = encoding.decode_without_bom_handling(&raw[byte_offset..]);
+            assert!(rest.starts_with("abc內容"), "encoding={}", encoding.name());
+
+            // 原文第 6 行（行号 5）为第二章标题；被压缩的第 4 行空行定位到其后的正文行
+            assert_eq!(index.line_char_offset(5), normalized[..chapter_two].chars().count() as u64);
+            let body = normalized[..normalized.find("正文").unwrap()].chars().count() as u64;
+            assert_eq!(index.line_char_offset(3), body);
+            assert_eq!(index.line_char_offset(100), index.total_chars());
+
+            assert_eq!(index.byte_offset(0), 0);
+            assert_eq!(index.byte_offset(u64::MAX), raw.len() as u64);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! 多文件合并阅读
+//! 连载小说拆成多个 TXT 文件时，按成员顺序把各文件的章节拼接为统一目录：章节索引与字符偏移在全书范围内连续编号，
+//! 进度、书签直接使用全局偏移；加载章节时再换算回所在文件的局部索引。缺失的成员文件跳过并记录，
+//! 其字符范围与章节索引按上次成功解析时的字符数、章节数预留（以占位章节填充），保证后续成员的全局偏移与章节索引不变
+
+use super::{TocItem, TocLocation, TxtBookMeta, TxtChapterContent};
+
+/// 合并书中的一个成员文件
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MergedPart {
+    /// 成员顺序（从 0 开始）
+    pub position: u32,
+    pub file_path: String,
+    pub title: String,
+    /// 该文件在全书中的字符范围，文件缺失时为按上次字符数预留的范围（从未解析过则为空范围）
+    pub char_start: u64,
+    pub char_end: u64,
+    /// 该文件首个章节的全局索引
+    pub chapter_start: u32,
+    /// 章节数，文件缺失时为按上次章节数预留的占位章节数
+    pub chapter_count: u32,
+    /// 文件缺失或无法解析，已跳过
+    pub missing: bool,
+}
+
+/// 合并后的章节元信息，索引与字符偏移均为全书全局值
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MergedChapterMeta {
+    pub index: u32,
+    pub title: String,
+    pub level: u32,
+    pub char_start: u64,
+    pub char_end: u64,
+    pub char_count: u64,
+    pub word_count: u64,
+    /// 所属成员文件的顺序
+    pub part: u32,
+    /// 在所属文件内的章节索引
+    pub part_chapter_index: u32,
+    /// 成员文件缺失时预留的占位章节，无法加载
+    #[serde(default)]
+    pub missing: bool,
+}
+
+/// 合并书元数据
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MergedBookMeta {
+    pub title: String,
+    pub total_chars: u64,
+    pub total_words: u64,
+    pub parts: Vec<MergedPart>,
+    pub chapters: Vec<MergedChapterMeta>,
+    /// 统一目录：每个成员文件为一级目录项，其下为该文件的章节，定位为全局章节序号（从 1 开始）
+    pub toc: Vec<TocItem>,
+    /// 缺失或无法解析而被跳过的成员文件
+    pub missing_files: Vec<String>,
+}
+
+/// 目录定位平移到全局章节序号，层级整体下移一级挂到成员目录项下
+fn shift_toc(items: &[TocItem], chapter_start: u32) -> Vec<TocItem> {
+    items
+        .iter()
+        .map(|item| TocItem {
+            title: item.title.clone(),
+            location: match &item.location {
+                // 无章节时的“全文”目录项定位为 0，同样指向该文件首章
+                TocLocation::Page(page) => TocLocation::Page((*page).max(1) + chapter_start),
+                other => other.clone(),
+            },
+            level: item.level + 1,
+            children: shift_toc(&item.children, chapter_start),
+        })
+        .collect()
+}
+
+impl MergedBookMeta {
+    /// 按成员顺序拼接各文件的元数据，`parts` 中元数据为 None 的文件视为缺失，
+    /// 按第三项（上次成功解析时的字符数、章节数）预留字符范围与章节索引
+    pub fn build(title: String, parts: Vec<(String, Option<TxtBookMeta>, (u64, u32))>) -> Self {
+        let mut merged = Self {
+            title,
+            total_chars: 0,
+            total_words: 0,
+            parts: Vec::with_capacity(parts.len()),
+            chapters: Vec::new(),
+            toc: Vec::new(),
+            missing_files: Vec::new(),
+        };
+
+        for (position, (file_path, meta, (known_chars, known_chapters))) in parts.into_iter().enumerate() {
+            let char_start = merged.total_chars;
+            let chapter_start = merged.chapters.len() as u32;
+            let Some(meta) = meta else {
+                let title = super::TxtEngine::extract_title_from_path(&file_path);
+                merged.missing_files.push(file_path.clone());
+                merged.total_chars += known_chars;
+                // 占位章节：首个占满预留的字符范围，其余为文件末尾的空范围
+                merged.chapters.extend((0..known_chapters).map(|local| MergedChapterMeta {
+                    index: chapter_start + local,
+                    title: title.clone(),
+                    level: 1,
+                    char_start: if local == 0 { char_start } else { merged.total_chars },
+                    char_end: merged.total_chars,
+                    char_count: if local == 0 { known_chars } else { 0 },
+                    word_count: 0,
+                    part: position as u32,
+                    part_chapter_index: local,
+                    missing: true,
+                }));
+                merged.parts.push(MergedPart {
+                    position: position as u32,
+                    title,
+                    file_path,
+                    char_start,
+                    char_end: merged.total_chars,
+                    chapter_start,
+                    chapter_count: known_chapters,
+                    missing: true,
+                });
+                continue;
+            };
+
+            merged.chapters.extend(meta.chapters.iter().map(|chapter| MergedChapterMeta {
+                index: chapter_start + chapter.index,
+                title: chapter.title.clone(),
+                level: chapter.level,
+                char_start: char_start + chapter.char_start,
+                char_end: char_start + chapter.char_end,
+                char_count: chapter.char_count,
+                word_count: chapter.word_count,
+                part: position as u32,
+                part_chapter_index: chapter.index,
+                missing: false,
+            }));
+            merged.toc.push(TocItem {
+                title: meta.title.clone(),
+                location: TocLocation::Page(chapter_start + 1),
+                level: 0,
+                children: shift_toc(&meta.toc, chapter_start),
+            });
+            merged.total_chars += meta.total_chars;
+            merged.total_words += meta.total_words;
+            merged.parts.push(MergedPart {
+                position: position as u32,
+                file_path,
+                title: meta.title,
+                char_start,
+                char_end: merged.total_chars,
+                chapter_start,
+                chapter_count: meta.chapters.len() as u32,
+                missing: false,
+            });
+        }
+        merged
+    }
+
+    /// 全局章节索引换算为 (成员文件, 文件内章节索引)；缺失成员的占位章节返回 None
+    pub fn resolve_chapter(&self, index: u32) -> Option<(&MergedPart, u32)> {
+        let chapter = self.chapters.get(index as usize).filter(|chapter| !chapter.missing)?;
+        let part = self.parts.get(chapter.part as usize)?;
+        Some((part, chapter.part_chapter_index))
+    }
+
+    /// 把成员文件中加载的章节内容改写为全局索引与全局字符偏移
+    pub fn to_global(&self, part: &MergedPart, mut chapter: TxtChapterContent) -> TxtChapterContent {
+        chapter.index += part.chapter_start;
+        chapter.char_start += part.char_start;
+        chapter.char_end += part.char_start;
+        chapter
+    }
+
+    /// 把全书字符偏移换算为 (全局章节索引, 章内字符偏移)；超出全书时定位到末章末尾，没有章节时返回 None
+    pub fn locate_char_offset(&self, char_offset: u64) -> Option<(u32, u64)> {
+        let idx = self
+            .chapters
+            .partition_point(|c| c.char_start <= char_offset)
+            .saturating_sub(1);
+        let chapter = self.chapters.get(idx)?;
+        let local = char_offset
+            .saturating_sub(chapter.char_start)
+            .min(chapter.char_end.saturating_sub(chapter.char_start));
+        Some((chapter.index, local))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{TxtChapterMeta, TxtChapterNormalizeOptions};
+    use super::*;
+
+    fn part_meta(title: &str, chapter_chars: &[u64]) -> TxtBookMeta {
+        let mut offset = 0;
+        let chapters: Vec<TxtChapterMeta> = chapter_chars
+            .iter()
+            .enumerate()
+            .map(|(index, &chars)| {
+                let chapter = TxtChapterMeta {
+                    index: index as u32,
+                    title: format!("{} 第{}章", title, index + 1),
+                    level: 1,
+                    byte_start: offset,
+                    byte_end: offset + chars,
+                    char_start: offset,
+                    char_end: offset + chars,
+                    char_count: chars,
+                    word_count: chars,
+                };
+                offset += chars;
+                chapter
+            })
+            .collect();
+        TxtBookMeta {
+            title: title.to_string(),
+            encoding: "UTF-8".to_string(),
+            total_bytes: offset,
+            total_chars: offset,
+            total_words: offset,
+            toc: chapters
+                .iter()
+                .map(|c| TocItem {
+                    title: c.title.clone(),
+                    location: TocLocation::Page(c.index + 1),
+                    level: 1,
+                    children: vec![],
+                })
+                .collect(),
+            chapters,
+            chapter_normalize: TxtChapterNormalizeOptions::default(),
+        }
+    }
+
+    #[test]
+    fn test_merge_parts() {
+        let merged = MergedBookMeta::build(
+            "合集".to_string(),
+            vec![
+                ("/books/上.txt".to_string(), Some(part_meta("上", &[100, 50])), (150, 2)),
+                ("/books/缺失.txt".to_string(), None, (0, 0)),
+                ("/books/下.txt".to_string(), Some(part_meta("下", &[30])), (30, 1)),
+            ],
+        );
+        assert_eq!(merged.total_chars, 180);
+        assert_eq!(merged.chapters.len(), 3);
+        assert_eq!(merged.missing_files, vec!["/books/缺失.txt".to_string()]);
+        assert!(merged.parts[1].missing);
+
+        let last = &merged.chapters[2];
+        assert_eq!((last.index, last.char_start, last.char_end), (2, 150, 180));
+        assert_eq!((last.part, last.part_chapter_index), (2, 0));
+        assert_eq!(merged.toc[1].location, TocLocation::Page(3));
+        assert_eq!(merged.toc[1].children[0].location, TocLocation::Page(3));
+
+        let (part, local) = merged.resolve_chapter(2).unwrap();
+        assert_eq!((part.file_path.as_str(), local), ("/books/下.txt", 0));
+        assert_eq!(merged.locate_char_offset(160), Some((2, 10)));
+        assert_eq!(merged.locate_char_offset(120), Some((1, 20)));
+    }
+
+    #[test]
+    fn test_missing_part_keeps_later_offsets() {
+        let parts = |middle: Option<TxtBookMeta>| {
+            vec![
+                ("/books/上.txt".to_string(), Some(part_meta("上", &[100])), (100, 1)),
+                ("/books/中.txt".to_string(), middle, (40, 2)),
+                ("/books/下.txt".to_string(), Some(part_meta("下", &[30])), (30, 1)),
+            ]
+        };
+        let complete = MergedBookMeta::build("合集".to_string(), parts(Some(part_meta("中", &[25, 15]))));
+        let missing = MergedBookMeta::build("合集".to_string(), parts(None));
+
+        // 缺失的成员按上次的字符数、章节数预留，之后成员的全局偏移与章节索引与完整时一致
+        assert_eq!((missing.parts[1].char_start, missing.parts[1].char_end), (100, 140));
+        assert_eq!(missing.parts[2].char_start, complete.parts[2].char_start);
+        assert_eq!(missing.parts[2].chapter_start, complete.parts[2].chapter_start);
+        assert_eq!(missing.total_chars, complete.total_chars);
+        assert_eq!(missing.chapters.len(), complete.chapters.len());
+        let last = missing.chapters.last().unwrap();
+        assert_eq!(last.index, complete.chapters.last().unwrap().index);
+        assert_eq!((last.char_start, last.char_end), (140, 170));
+        assert_eq!(missing.locate_char_offset(150), Some((last.index, 10)));
+        assert_eq!(missing.toc[1].location, complete.toc[2].location);
+
+        // 占位章节不可加载，偏移落在缺失范围内时定位到首个占位章节
+        assert!(missing.chapters[1].missing && missing.resolve_chapter(1).is_none());
+        assert_eq!(missing.locate_char_offset(120), Some((1, 20)));
+        let (part, local) = missing.resolve_chapter(last.index).unwrap();
+        assert_eq!((part.file_path.as_str(), local), ("/books/下.txt", 0));
+    }
+}
@@ -1,7 +1,11 @@
 //! TXT 格式引擎
 //! 负责文件读取、编码检测和章节识别
 
+mod char_index;
+mod merge;
+mod repair;
 mod toc_parser;
+mod vertical;
 
 use chardetng::EncodingDetector;
 use memmap2::MmapOptions;
@@ -13,7 +17,12 @@ use std::path::Path;
 use std::sync::Mutex;
 
 use super::{BookError, BookErrorCode, BookFormat, BookMetadata, TocItem, TocLocation};
-use toc_parser::TocParser;
+use char_index::CharByteIndex;
+use toc_parser::{TocParser, TocParserConfig, SUBSECTION_LEVEL};
+pub use merge::{MergedBookMeta, MergedChapterMeta, MergedPart};
+pub use repair::{repair_garbled_text, TxtRepairResult, TxtRepairSegment};
+pub use toc_parser::TocDiagnostics;
+pub use vertical::to_vertical_text;
 
 #[derive(Clone)]
 struct FullTextCacheEntry {
@@ -43,6 +52,12 @@ pub struct TxtChapterMeta {
     pub char_start: u64,
     /// 字符结束位置（解码后）
     pub char_end: u64,
+    /// 章节字符数
+    #[serde(default)]
+    pub char_count: u64,
+    /// 章节字数（中日韩文字按字、其他文字按词计）
+    #[serde(default)]
+    pub word_count: u64,
 }
 
 /// 章节内容
@@ -56,6 +71,137 @@ pub struct TxtChapterContent {
     pub char_start: u64,
     /// 在全文中的字符结束位置
     pub char_end: u64,
+    /// 结构化段落信息（仅在请求时返回）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paragraphs: Option<Vec<TxtParagraph>>,
+}
+
+/// 章节段落（每个非空行视为一个段落）
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TxtParagraph {
+    /// 段落文本（不含首行缩进）
+    pub text: String,
+    /// 首行缩进宽度（单位 em：全角空格计 1，半角空格计 0.5，制表符计 2）
+    pub indent: f32,
+}
+
+/// 章节排版选项
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TxtFormatOptions {
+    /// 保留段首缩进（全角空格等）；关闭时去除每行行首空白
+    pub preserve_indent: bool,
+    /// 去除每行行尾空白
+    pub trim_trailing_whitespace: bool,
+    /// 返回结构化段落信息
+    pub with_paragraphs: bool,
+    /// 竖排模式：破折号、省略号、括号、引号转为竖排字形（逐字符替换，不改变字符数）
+    pub vertical: bool,
+}
+
+impl Default for TxtFormatOptions {
+    fn default() -> Self {
+        Self {
+            preserve_indent: true,
+            trim_trailing_whitespace: false,
+            with_paragraphs: false,
+            vertical: false,
+        }
+    }
+}
+
+/// 章节规整选项：合并相邻的过短章节、在段落边界拆分过长章节，默认开启。
+/// 规整会改变章节划分，升级前已导入的书籍按书记录为不规整，见 `txt_commands::default_chapter_normalize`
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct TxtChapterNormalizeOptions {
+    /// 是否启用章节规整
+    pub enabled: bool,
+    /// 相邻两章字符数都低于该值时合并
+    pub min_chars: u64,
+    /// 章节字符数超过该值时按段落拆分为若干“（续）”章节，为 0 时不拆分
+    pub max_chars: u64,
+    /// 识别章内小节并挂到所在章下（不单独成章）；与 `enabled` 独立，同样会改变章节序号，默认关闭
+    pub nest_subsections: bool,
+}
+
+impl Default for TxtChapterNormalizeOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_chars: 200,
+            max_chars: 50_000,
+            nest_subsections: false,
+        }
+    }
+}
+
+/// 行首缩进空白：全角空格、半角空格、制表符、不换行空格
+fn is_indent_char(c: char) -> bool {
+    matches!(c, '\u{3000}' | ' ' | '\t' | '\u{00A0}')
+}
+
+/// 计算缩进宽度（em）
+fn indent_width(indent: &str) -> f32 {
+    indent
+        .chars()
+        .map(|c| match c {
+            '\u{3000}' => 1.0,
+            '\t' => 2.0,
+            _ => 0.5,
+        })
+        .sum()
+}
+
+impl TxtChapterContent {
+    /// 按排版选项处理章节文本；字符位置仍指向原始全文
+    pub fn apply_format(&mut self, options: &TxtFormatOptions) {
+        if options.preserve_indent
+            && !options.trim_trailing_whitespace
+            && !options.with_paragraphs
+            && !options.vertical
+        {
+            return;
+        }
+
+        let mut content = String::with_capacity(self.content.len());
+        let mut paragraphs = Vec::new();
+
+        for line in self.content.lines() {
+            let body = line.trim_start_matches(is_indent_char);
+            let indent = &line[..line.len() - body.len()];
+            let body = if options.trim_trailing_whitespace {
+                body.trim_end()
+            } else {
+                body
+            };
+            let converted;
+            let body = if options.vertical {
+                converted = to_vertical_text(body);
+                converted.as_str()
+            } else {
+                body
+            };
+
+            if options.with_paragraphs && !body.trim().is_empty() {
+                paragraphs.push(TxtParagraph {
+                    text: body.to_string(),
+                    indent: if options.preserve_indent { indent_width(indent) } else { 0.0 },
+                });
+            }
+
+            if options.preserve_indent && !body.is_empty() {
+                content.push_str(indent);
+            }
+            content.push_str(body);
+            content.push('\n');
+        }
+
+        self.content = content;
+        if options.with_paragraphs {
+            self.paragraphs = Some(paragraphs);
+        }
+    }
 }
 
 /// TXT 书籍元数据（首次加载返回）
@@ -69,10 +215,142 @@ pub struct TxtBookMeta {
     pub total_bytes: u64,
     /// 总字符数
     pub total_chars: u64,
+    /// 全书字数（统计口径同 `TxtChapterMeta::word_count`）
+    #[serde(default)]
+    pub total_words: u64,
     /// 章节列表
     pub chapters: Vec<TxtChapterMeta>,
     /// 目录项（与原有 TocItem 兼容）
     pub toc: Vec<TocItem>,
+    /// 生成章节列表时使用的规整选项，重新解析时沿用
+    #[serde(default)]
+    pub chapter_normalize: TxtChapterNormalizeOptions,
+}
+
+/// 候选编码（用于前端手动切换编码）
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TxtEncodingCandidate {
+    /// 编码名称（encoding_rs 标准名称）
+    pub encoding: String,
+    /// 置信度（0-1）
+    pub confidence: f32,
+}
+
+/// 单章阅读时间预估
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TxtChapterEstimate {
+    pub index: u32,
+    pub title: String,
+    pub word_count: u64,
+    /// 预估阅读分钟数
+    pub minutes: f64,
+}
+
+/// 全书阅读时间预估
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TxtReadingEstimate {
+    /// 每分钟阅读字数
+    pub wpm: u32,
+    pub total_words: u64,
+    /// 全书预估阅读分钟数
+    pub total_minutes: f64,
+    pub chapters: Vec<TxtChapterEstimate>,
+}
+
+/// 快速定位方式：全书百分比（0-100，按总字符数计）、原文行号（从 1 开始）或全文字符偏移
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocateBy {
+    Percent(f32),
+    Line(u64),
+    Char(u64),
+}
+
+/// 快速定位结果
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TxtLocation {
+    /// 全文字符偏移
+    pub char_offset: u64,
+    /// 所在章节索引
+    pub chapter_index: u32,
+    /// 章内字符偏移
+    pub chapter_char_offset: u64,
+    /// 定位点在全书中的百分比（0-100）
+    pub percent: f32,
+}
+
+impl TxtBookMeta {
+    /// 按每分钟阅读字数估算全书和各章阅读时间（分钟，保留一位小数）
+    pub fn reading_estimate(&self, wpm: u32) -> Result<TxtReadingEstimate, BookError> {
+        if wpm == 0 {
+            return Err(BookError::new(
+                BookErrorCode::InvalidParameter,
+                "每分钟阅读字数必须大于 0",
+            ));
+        }
+        let minutes = |words: u64| (words as f64 / wpm as f64 * 10.0).round() / 10.0;
+
+        Ok(TxtReadingEstimate {
+            wpm,
+            total_words: self.total_words,
+            total_minutes: minutes(self.total_words),
+            chapters: self
+                .chapters
+                .iter()
+                .map(|c| TxtChapterEstimate {
+                    index: c.index,
+                    title: c.title.clone(),
+                    word_count: c.word_count,
+                    minutes: minutes(c.word_count),
+                })
+                .collect(),
+        })
+    }
+
+    /// 把全文字符偏移换算为 (章节索引, 章内字符偏移)；超出全文时定位到末章末尾，没有章节时返回 None
+    pub fn locate_char_offset(&self, char_offset: u64) -> Option<(u32, u64)> {
+        let idx = self
+            .chapters
+            .partition_point(|c| c.char_start <= char_offset)
+            .saturating_sub(1);
+        let chapter = self.chapters.get(idx)?;
+        let local = char_offset
+            .saturating_sub(chapter.char_start)
+            .min(chapter.char_end.saturating_sub(chapter.char_start));
+        Some((chapter.index, local))
+    }
+}
+
+/// 是否为中日韩文字（按字计数）
+fn is_cjk_char(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // 日文假名
+        | 0x3400..=0x4DBF    // CJK 扩展 A
+        | 0x4E00..=0x9FFF    // CJK 基本区
+        | 0xAC00..=0xD7AF    // 韩文音节
+        | 0xF900..=0xFAFF    // CJK 兼容汉字
+        | 0x20000..=0x2FA1F  // CJK 扩展 B 及以后
+    )
+}
+
+/// 统计字数：中日韩文字每字计 1，其他文字按连续字母数字组成的词计 1，标点和空白不计
+pub fn count_words(text: &str) -> u64 {
+    let mut count = 0u64;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk_char(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+                in_word = true;
+            }
+        } else {
+            in_word = false;
+        }
+    }
+    count
 }
 
 /// TXT 引擎
@@ -118,8 +396,18 @@ impl TxtEngine {
     }
 
     /// 快速解析元数据（不加载全文内容到内存中保持）
-    /// 返回章节元信息和目录，用于章节懒加载
-    pub fn load_metadata(path: &str) -> Result<TxtBookMeta, BookError> {
+    /// 返回章节元信息和目录，用于章节懒加载。
+    /// `force_encoding` 不为空时跳过自动检测，直接按指定编码解码
+    pub fn load_metadata(path: &str, force_encoding: Option<&str>) -> Result<TxtBookMeta, BookError> {
+        Self::load_metadata_with(path, force_encoding, &TxtChapterNormalizeOptions::default())
+    }
+
+    /// 按指定章节规整选项解析元数据
+    pub fn load_metadata_with(
+        path: &str,
+        force_encoding: Option<&str>,
+        normalize: &TxtChapterNormalizeOptions,
+    ) -> Result<TxtBookMeta, BookError> {
         // 检查文件是否存在
         if !Path::new(path).exists() {
             return Err(BookError::file_not_found(path));
@@ -158,7 +446,7 @@ impl TxtEngine {
             let bytes: &[u8] = &mmap;
 
             // 编码检测与解码
-            let (content, encoding) = Self::decode_content(bytes)?;
+            let (content, encoding) = Self::decode_content_with(bytes, force_encoding)?;
 
             // 文本预处理
             let normalized = Self::normalize_text(&content);
@@ -171,21 +459,27 @@ impl TxtEngine {
             let title = Self::extract_title_from_path(path);
 
             // 解析目录并获取章节元信息
-            let parser = TocParser::new();
+            let parser = Self::toc_parser(normalize.nest_subsections);
             let toc = parser.parse(&normalized, &lines);
 
-            // 将 TocItem 转换为 TxtChapterMeta，计算字节偏移量
-            let chapters =
-                Self::convert_toc_to_chapters(&toc, &normalized, bytes, &encoding);
-            let toc_indexed = Self::rewrite_toc_locations_as_chapter_index(&toc);
+            // 将 TocItem 转换为 TxtChapterMeta，按原文逐行建立的索引计算精确字节偏移量
+            let char_index = CharByteIndex::build(bytes, &encoding);
+            let chapters = Self::convert_toc_to_chapters(&toc, &char_index, normalize.nest_subsections);
+            let (mut chapters, index_map) =
+                Self::normalize_chapters(chapters, &normalized, &char_index, normalize);
+            Self::fill_chapter_word_counts(&mut chapters, &normalized);
+            let toc_indexed =
+                Self::rewrite_toc_locations_as_chapter_index(&toc, &index_map, normalize.nest_subsections);
 
             Ok(TxtBookMeta {
                 title,
                 encoding,
                 total_bytes: file_size,
                 total_chars,
+                total_words: count_words(&normalized),
                 chapters,
                 toc: toc_indexed,
+                chapter_normalize: normalize.clone(),
             })
         } else {
             // 小文件沿用原有读取逻辑
@@ -195,7 +489,7 @@ impl TxtEngine {
             let total_bytes = bytes.len() as u64;
 
             // 编码检测与解码
-            let (content, encoding) = Self::decode_content(&bytes)?;
+            let (content, encoding) = Self::decode_content_with(&bytes, force_encoding)?;
 
             // 文本预处理
             let normalized = Self::normalize_text(&content);
@@ -208,28 +502,39 @@ impl TxtEngine {
             let title = Self::extract_title_from_path(path);
 
             // 解析目录并获取章节元信息
-            let parser = TocParser::new();
+            let parser = Self::toc_parser(normalize.nest_subsections);
             let toc = parser.parse(&normalized, &lines);
 
-            // 将 TocItem 转换为 TxtChapterMeta，计算字节偏移量
-            let chapters =
-                Self::convert_toc_to_chapters(&toc, &normalized, &bytes, &encoding);
-            let toc_indexed = Self::rewrite_toc_locations_as_chapter_index(&toc);
+            // 将 TocItem 转换为 TxtChapterMeta，按原文逐行建立的索引计算精确字节偏移量
+            let char_index = CharByteIndex::build(&bytes, &encoding);
+            let chapters = Self::convert_toc_to_chapters(&toc, &char_index, normalize.nest_subsections);
+            let (mut chapters, index_map) =
+                Self::normalize_chapters(chapters, &normalized, &char_index, normalize);
+            Self::fill_chapter_word_counts(&mut chapters, &normalized);
+            let toc_indexed =
+                Self::rewrite_toc_locations_as_chapter_index(&toc, &index_map, normalize.nest_subsections);
 
             Ok(TxtBookMeta {
                 title,
                 encoding,
                 total_bytes,
                 total_chars,
+                total_words: count_words(&normalized),
                 chapters,
                 toc: toc_indexed,
+                chapter_normalize: normalize.clone(),
             })
         }
     }
 
     /// 加载指定章节的内容
-    pub fn load_chapter(path: &str, chapter_index: u32, meta: &TxtBookMeta) -> Result<TxtChapterContent, BookError> {
-        let chapters = Self::load_chapters(path, &[chapter_index], meta)?;
+    pub fn load_chapter(
+        path: &str,
+        chapter_index: u32,
+        meta: &TxtBookMeta,
+        force_encoding: Option<&str>,
+    ) -> Result<TxtChapterContent, BookError> {
+        let chapters = Self::load_chapters(path, &[chapter_index], meta, force_encoding)?;
         chapters
             .into_iter()
             .next()
@@ -246,11 +551,30 @@ impl TxtEngine {
     }
 
     /// 批量加载多个章节
-    pub fn load_chapters(path: &str, indices: &[u32], meta: &TxtBookMeta) -> Result<Vec<TxtChapterContent>, BookError> {
+    /// `force_encoding` 与元数据编码不一致时，按新编码重新解析元数据以重算章节字节偏移
+    pub fn load_chapters(
+        path: &str,
+        indices: &[u32],
+        meta: &TxtBookMeta,
+        force_encoding: Option<&str>,
+    ) -> Result<Vec<TxtChapterContent>, BookError> {
         if indices.is_empty() {
             return Ok(Vec::new());
         }
 
+        let remapped;
+        let meta = match force_encoding {
+            Some(label) if Self::resolve_encoding_label(label)? != meta.encoding => {
+                println!(
+                    "[TxtEngine] 章节加载编码切换，重新计算偏移: path={}, old_encoding={}, new_encoding={}",
+                    path, meta.encoding, label
+                );
+                remapped = Self::load_metadata_with(path, Some(label), &meta.chapter_normalize)?;
+                &remapped
+            }
+            _ => meta,
+        };
+
         // 过滤非法索引，避免越界
         let mut valid_indices = Vec::with_capacity(indices.len());
         for &idx in indices {
@@ -401,6 +725,7 @@ impl TxtEngine {
             content: normalized,
             char_start: chapter.char_start,
             char_end: chapter.char_end,
+            paragraphs: None,
         })
     }
 
@@ -465,6 +790,7 @@ impl TxtEngine {
             content: normalized,
             char_start: chapter.char_start,
             char_end: chapter.char_end,
+            paragraphs: None,
         })
     }
 
@@ -567,6 +893,7 @@ impl TxtEngine {
             content: slice,
             char_start,
             char_end,
+            paragraphs: None,
         })
     }
 
@@ -575,7 +902,7 @@ impl TxtEngine {
         meta_encoding: &str,
     ) -> Result<(String, u64, String, u64), BookError> {
         if let Ok(cache) = FULL_TEXT_CACHE.lock() {
-            if let Some(entry) = cache.get(path) {
+            if let Some(entry) = cache.get(path).filter(|e| e.encoding == meta_encoding) {
                 return Ok((
                     entry.normalized.clone(),
                     entry.normalized.chars().count() as u64,
@@ -593,16 +920,8 @@ impl TxtEngine {
         })?;
 
         let total_bytes = bytes.len() as u64;
-        let (content, encoding) = Self::decode_content(&bytes)?;
-
-        if encoding != meta_encoding {
-            println!(
-                "[TxtEngine] 全文缓存编码与元数据不一致: path={}, meta_encoding={}, detected_encoding={}",
-                path,
-                meta_encoding,
-                encoding
-            );
-        }
+        // 按元数据编码解码，保证与手动指定的编码一致
+        let (content, encoding) = Self::decode_content_with(&bytes, Some(meta_encoding))?;
 
         let normalized = Self::normalize_text(&content);
         let replacement_count = normalized.chars().filter(|c| *c == '\u{FFFD}').count();
@@ -636,41 +955,185 @@ impl TxtEngine {
         Ok((normalized, total_chars, encoding, total_bytes))
     }
 
-    /// 将 TocItem 转换为 TxtChapterMeta
+    /// 按章节字符区间统计各章字数，只遍历一次全文
+    fn fill_chapter_word_counts(chapters: &mut [TxtChapterMeta], content: &str) {
+        if chapters.is_empty() {
+            return;
+        }
+
+        let byte_offsets = Self::content_byte_offsets(
+            content,
+            chapters.iter().flat_map(|c| [c.char_start, c.char_end]).collect(),
+        );
+
+        for chapter in chapters.iter_mut() {
+            let start = byte_offsets.get(&chapter.char_start).copied().unwrap_or(content.len());
+            let end = byte_offsets.get(&chapter.char_end).copied().unwrap_or(content.len());
+            if start < end {
+                chapter.word_count = count_words(&content[start..end]);
+            }
+        }
+    }
+
+    /// 收集的字符偏移一次遍历换算为全文字符串内的字节偏移
+    fn content_byte_offsets(content: &str, mut boundaries: Vec<u64>) -> HashMap<u64, usize> {
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut byte_offsets: HashMap<u64, usize> = HashMap::with_capacity(boundaries.len());
+        let mut next = boundaries.iter().peekable();
+        for (char_idx, (byte_idx, _)) in content.char_indices().enumerate() {
+            while let Some(&&target) = next.peek() {
+                if target > char_idx as u64 {
+                    break;
+                }
+                byte_offsets.insert(target, byte_idx);
+                next.next();
+            }
+        }
+        for &target in next {
+            byte_offsets.insert(target, content.len());
+        }
+        byte_offsets
+    }
+
+    /// 规整章节：先合并相邻的过短章节，再在段落边界拆分过长章节
+    /// 返回规整后的章节及原章节序号到新章节序号的映射（用于改写目录定位）
+    fn normalize_chapters(
+        chapters: Vec<TxtChapterMeta>,
+        content: &str,
+        char_index: &CharByteIndex,
+        options: &TxtChapterNormalizeOptions,
+    ) -> (Vec<TxtChapterMeta>, Vec<u32>) {
+        if !options.enabled || chapters.is_empty() {
+            let index_map = (0..chapters.len() as u32).collect();
+            return (chapters, index_map);
+        }
+        let original_len = chapters.len();
+
+        // 合并：上一章与当前章都过短时并入上一章，标题沿用上一章
+        let mut merged: Vec<TxtChapterMeta> = Vec::with_capacity(chapters.len());
+        let mut merged_index = Vec::with_capacity(chapters.len());
+        for chapter in chapters {
+            if let Some(last) = merged.last_mut() {
+                if last.char_count < options.min_chars && chapter.char_count < options.min_chars {
+                    last.char_end = chapter.char_end;
+                    last.byte_end = chapter.byte_end;
+                    last.char_count = last.char_end.saturating_sub(last.char_start);
+                    merged_index.push((merged.len() - 1) as u32);
+                    continue;
+                }
+            }
+            merged_index.push(merged.len() as u32);
+            merged.push(chapter);
+        }
+
+        // 拆分：只为过长章节换算字节偏移
+        let is_long = |c: &TxtChapterMeta| options.max_chars > 0 && c.char_count > options.max_chars;
+        let byte_offsets = Self::content_byte_offsets(
+            content,
+            merged
+                .iter()
+                .filter(|c| is_long(c))
+                .flat_map(|c| [c.char_start, c.char_end])
+                .collect(),
+        );
+        let mut result = Vec::with_capacity(merged.len());
+        let mut first_piece = Vec::with_capacity(merged.len());
+        for chapter in merged {
+            first_piece.push(result.len() as u32);
+            if !is_long(&chapter) {
+                result.push(chapter);
+                continue;
+            }
+
+            let start = byte_offsets.get(&chapter.char_start).copied().unwrap_or(content.len());
+            let end = byte_offsets.get(&chapter.char_end).copied().unwrap_or(content.len());
+            let parts = chapter.char_count.div_ceil(options.max_chars);
+            let target = chapter.char_count.div_ceil(parts);
+
+            // 按行累计，达到目标长度后在行尾切分，保证不截断段落
+            let mut cuts: Vec<(u64, u64)> = Vec::new();
+            let (mut piece_chars, mut local_chars) = (0u64, 0u64);
+            for line in content[start..end].split_inclusive('\n') {
+                let line_chars = line.chars().count() as u64;
+                piece_chars += line_chars;
+                local_chars += line_chars;
+                if piece_chars >= target && local_chars < chapter.char_count {
+                    let char_offset = chapter.char_start + local_chars;
+                    cuts.push((char_offset, char_index.byte_offset(char_offset)));
+                    piece_chars = 0;
+                }
+            }
+
+            let mut bounds = vec![(chapter.char_start, chapter.byte_start)];
+            bounds.extend(cuts);
+            bounds.push((chapter.char_end, chapter.byte_end));
+            for (i, pair) in bounds.windows(2).enumerate() {
+                let ((char_start, byte_start), (char_end, byte_end)) = (pair[0], pair[1]);
+                let title = match i {
+                    0 => chapter.title.clone(),
+                    1 => format!("{}（续）", chapter.title),
+                    n => format!("{}（续{}）", chapter.title, n),
+                };
+                result.push(TxtChapterMeta {
+                    index: 0,
+                    title,
+                    level: chapter.level,
+                    byte_start,
+                    byte_end,
+                    char_start,
+                    char_end,
+                    char_count: char_end.saturating_sub(char_start),
+                    word_count: 0,
+                });
+            }
+        }
+
+        for (i, chapter) in result.iter_mut().enumerate() {
+            chapter.index = i as u32;
+        }
+        let index_map = merged_index.iter().map(|&m| first_piece[m as usize]).collect();
+
+        if result.len() != original_len {
+            println!(
+                "[TxtEngine] 章节规整完成: before={}, after={}, min_chars={}, max_chars={}",
+                original_len,
+                result.len(),
+                options.min_chars,
+                options.max_chars
+            );
+        }
+        (result, index_map)
+    }
+
+    /// 目录解析器；`nest_subsections` 为 true 时识别章内小节
+    fn toc_parser(nest_subsections: bool) -> TocParser {
+        TocParser::with_config(TocParserConfig {
+            detect_subsections: nest_subsections,
+            ..TocParserConfig::default()
+        })
+    }
+
+    /// 将 TocItem 转换为 TxtChapterMeta；`nest_subsections` 为 true 时章内小节不单独成章
     fn convert_toc_to_chapters(
         toc: &[TocItem],
-        content: &str,
-        raw_bytes: &[u8],
-        encoding: &str,
+        char_index: &CharByteIndex,
+        nest_subsections: bool,
     ) -> Vec<TxtChapterMeta> {
         let mut chapters = Vec::new();
         let mut flat_toc = Vec::new();
 
-        // 扁平化目录（包含子节点）
-        Self::flatten_toc(toc, &mut flat_toc);
-
-        // 预计算 UTF-8 下的字符到字节偏移映射，避免重复遍历
-        let is_utf8 = encoding == "UTF-8" || encoding.starts_with("UTF-8");
-        let (char_to_byte, total_chars) = if is_utf8 {
-            let mut mapping: Vec<u64> = Vec::with_capacity(content.chars().count() + 1);
-            mapping.push(0);
-            let mut acc: u64 = 0;
-            for c in content.chars() {
-                acc += c.len_utf8() as u64;
-                mapping.push(acc);
-            }
-            let total_chars = (mapping.len().saturating_sub(1)) as u64;
-            (Some(mapping), total_chars)
-        } else {
-            let total_chars = content.chars().count() as u64;
-            (None, total_chars)
-        };
+        // 扁平化目录
+        Self::flatten_toc(toc, nest_subsections, &mut flat_toc);
+
+        let total_chars = char_index.total_chars();
+        let total_bytes = char_index.total_bytes();
 
         println!(
-            "[TxtEngine] 目录转换开始: toc_nodes={}, flat_len={}, encoding={}, total_chars={}",
+            "[TxtEngine] 目录转换开始: toc_nodes={}, flat_len={}, total_chars={}",
             toc.len(),
             flat_toc.len(),
-            encoding,
             total_chars
         );
 
@@ -683,14 +1146,7 @@ impl TxtEngine {
             if char_start > total_chars {
                 char_start = total_chars;
             }
-
-            // 计算字节偏移量
-            let byte_start = if let Some(ref mapping) = char_to_byte {
-                let idx = char_start as usize;
-                mapping[idx.min(mapping.len().saturating_sub(1))]
-            } else {
-                Self::char_offset_to_byte_offset(content, raw_bytes, encoding, char_start as usize)
-            };
+            let byte_start = char_index.byte_offset(char_start);
 
             // 下一章的起始位置就是当前章的结束位置
             let (char_end, byte_end) = if i + 1 < flat_toc.len() {
@@ -701,15 +1157,9 @@ impl TxtEngine {
                 if next_char_start > total_chars {
                     next_char_start = total_chars;
                 }
-                let next_byte_start = if let Some(ref mapping) = char_to_byte {
-                    let idx = next_char_start as usize;
-                    mapping[idx.min(mapping.len().saturating_sub(1))]
-                } else {
-                    Self::char_offset_to_byte_offset(content, raw_bytes, encoding, next_char_start as usize)
-                };
-                (next_char_start, next_byte_start)
+                (next_char_start, char_index.byte_offset(next_char_start))
             } else {
-                (total_chars, raw_bytes.len() as u64)
+                (total_chars, total_bytes)
             };
 
             chapters.push(TxtChapterMeta {
@@ -720,6 +1170,8 @@ impl TxtEngine {
                 byte_end,
                 char_start,
                 char_end,
+                char_count: char_end.saturating_sub(char_start),
+                word_count: 0,
             });
         }
 
@@ -729,68 +1181,76 @@ impl TxtEngine {
                 chapters.len(),
                 last.index,
                 last.byte_end,
-                raw_bytes.len()
+                total_bytes
             );
         } else {
             println!(
                 "[TxtEngine] 目录转换完成: chapters=0, total_bytes={}",
-                raw_bytes.len()
+                total_bytes
             );
         }
 
         chapters
     }
 
-    /// 扁平化目录树
-    fn flatten_toc(toc: &[TocItem], flat: &mut Vec<TocItem>) {
-        for item in toc {
-            flat.push(item.clone());
-            if !item.children.is_empty() {
-                Self::flatten_toc(&item.children, flat);
+    /// 章内小节：挂在章下的小节级条目，只作为目录子项，正文仍属于所在章
+    fn is_nested_subsection(item: &TocItem, nested: bool) -> bool {
+        nested && item.level >= SUBSECTION_LEVEL
+    }
+
+    /// 扁平化目录树；`nest_subsections` 为 true 时跳过章内小节，否则与旧版一致，所有条目都单独成章
+    fn flatten_toc(toc: &[TocItem], nest_subsections: bool, flat: &mut Vec<TocItem>) {
+        fn walk(items: &[TocItem], nested: bool, nest_subsections: bool, flat: &mut Vec<TocItem>) {
+            for item in items {
+                if nest_subsections && TxtEngine::is_nested_subsection(item, nested) {
+                    continue;
+                }
+                flat.push(item.clone());
+                walk(&item.children, true, nest_subsections, flat);
             }
         }
+        walk(toc, false, nest_subsections, flat);
     }
 
-    fn rewrite_toc_locations_as_chapter_index(toc: &[TocItem]) -> Vec<TocItem> {
-        fn walk(items: &[TocItem], next_index: &mut u32) -> Vec<TocItem> {
+    /// 目录定位改写为章节序号（从 1 开始）；`index_map` 为扁平目录序号到规整后章节序号的映射
+    /// `nest_subsections` 为 true 时章内小节定位到所在章
+    fn rewrite_toc_locations_as_chapter_index(
+        toc: &[TocItem],
+        index_map: &[u32],
+        nest_subsections: bool,
+    ) -> Vec<TocItem> {
+        fn walk(
+            items: &[TocItem],
+            parent: Option<u32>,
+            nest_subsections: bool,
+            next_index: &mut u32,
+            index_map: &[u32],
+        ) -> Vec<TocItem> {
             items
                 .iter()
                 .map(|item| {
-                    let index = *next_index;
-                    *next_index = next_index.saturating_add(1);
+                    let nested = nest_subsections && TxtEngine::is_nested_subsection(item, true);
+                    let index = match parent.filter(|_| nested) {
+                        Some(parent_index) => parent_index,
+                        None => {
+                            let flat_index = *next_index;
+                            *next_index = next_index.saturating_add(1);
+                            index_map.get(flat_index as usize).copied().unwrap_or(flat_index)
+                        }
+                    };
 
                     TocItem {
                         title: item.title.clone(),
                         location: TocLocation::Page(index.saturating_add(1)),
                         level: item.level,
-                        children: walk(&item.children, next_index),
+                        children: walk(&item.children, Some(index), nest_subsections, next_index, index_map),
                     }
                 })
                 .collect()
         }
 
         let mut next_index: u32 = 0;
-        walk(toc, &mut next_index)
-    }
-
-    /// 将字符偏移量转换为字节偏移量
-    fn char_offset_to_byte_offset(content: &str, raw_bytes: &[u8], encoding: &str, char_offset: usize) -> u64 {
-        if encoding == "UTF-8" || encoding.starts_with("UTF-8") {
-            let mut byte_offset = 0usize;
-            for (i, c) in content.chars().enumerate() {
-                if i >= char_offset {
-                    break;
-                }
-                byte_offset += c.len_utf8();
-            }
-            return byte_offset.min(raw_bytes.len()) as u64;
-        }
-
-        let total_chars = content.chars().count().max(1);
-        let clamped_offset = char_offset.min(total_chars);
-        let ratio = raw_bytes.len() as f64 / total_chars as f64;
-        let byte_offset = (clamped_offset as f64 * ratio) as u64;
-        byte_offset.min(raw_bytes.len() as u64)
+        walk(toc, None, nest_subsections, &mut next_index, index_map)
     }
 
     /// 从路径提取标题
@@ -823,7 +1283,11 @@ impl TxtEngine {
                 let (decoded, _, _) = encoding_rs::BIG5.decode(bytes);
                 decoded.into_owned()
             }
-            _ => {
+            other => {
+                if let Some(enc) = encoding_rs::Encoding::for_label(other.as_bytes()) {
+                    let (decoded, _) = enc.decode_without_bom_handling(bytes);
+                    return Ok(decoded.into_owned());
+                }
                 // 尝试使用 chardetng 重新检测
                 let mut detector = EncodingDetector::new();
                 detector.feed(bytes, true);
@@ -876,6 +1340,200 @@ impl TxtEngine {
         Ok((decoded.into_owned(), encoding.name().to_string()))
     }
 
+    /// 将用户传入的编码名称规范为 encoding_rs 的标准名称（如 "gb2312" -> "GBK"）
+    fn resolve_encoding_label(label: &str) -> Result<String, BookError> {
+        encoding_rs::Encoding::for_label(label.trim().as_bytes())
+            .map(|enc| enc.name().to_string())
+            .ok_or_else(|| BookError::encoding_error(label).with_details("不支持的编码名称"))
+    }
+
+    /// 编码解码：指定编码时跳过自动检测，否则走 `decode_content`
+    fn decode_content_with(
+        bytes: &[u8],
+        force_encoding: Option<&str>,
+    ) -> Result<(String, String), BookError> {
+        let Some(label) = force_encoding else {
+            return Self::decode_content(bytes);
+        };
+
+        let encoding = Self::resolve_encoding_label(label)?;
+        // 仅剥离与指定编码匹配的 BOM
+        let body = match encoding.as_str() {
+            "UTF-8" if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) => &bytes[3..],
+            "UTF-16LE" if bytes.starts_with(&[0xFF, 0xFE]) => &bytes[2..],
+            "UTF-16BE" if bytes.starts_with(&[0xFE, 0xFF]) => &bytes[2..],
+            _ => bytes,
+        };
+        println!("[TxtEngine] 使用指定编码解码: encoding={}", encoding);
+        let content = Self::decode_bytes(body, &encoding)?;
+        Ok((content, encoding))
+    }
+
+    /// 按百分比、行号或字符偏移定位，换算为所在章节与章内偏移
+    /// 行号基于原文（规范化前）计算，需要读取文件逐行建立索引；其余方式只用元数据
+    pub fn locate(path: &str, meta: &TxtBookMeta, by: LocateBy) -> Result<TxtLocation, BookError> {
+        let char_offset = match by {
+            LocateBy::Percent(percent) if !percent.is_finite() => {
+                return Err(BookError::new(
+                    BookErrorCode::InvalidParameter,
+                    format!("定位百分比无效: {}", percent),
+                ));
+            }
+            LocateBy::Percent(percent) => {
+                (meta.total_chars as f64 * percent.clamp(0.0, 100.0) as f64 / 100.0).floor() as u64
+            }
+            LocateBy::Line(line) => {
+                let bytes = fs::read(path).map_err(|e| {
+                    BookError::new(BookErrorCode::IoError, format!("读取文件失败: {}", e))
+                })?;
+                CharByteIndex::build(&bytes, &meta.encoding).line_char_offset(line.saturating_sub(1))
+            }
+            LocateBy::Char(offset) => offset,
+        }
+        .min(meta.total_chars);
+
+        let (chapter_index, chapter_char_offset) = meta.locate_char_offset(char_offset).ok_or_else(|| {
+            BookError::new(BookErrorCode::InvalidParameter, "没有可定位的章节")
+        })?;
+        let percent = if meta.total_chars == 0 {
+            0.0
+        } else {
+            (char_offset as f64 / meta.total_chars as f64 * 100.0) as f32
+        };
+        Ok(TxtLocation {
+            char_offset,
+            chapter_index,
+            chapter_char_offset,
+            percent,
+        })
+    }
+
+    /// 列出候选编码及置信度，供前端手动选择
+    /// 仅取文件开头一段样本解码，按替代符与控制字符比例估算可信度
+    pub fn detect_encodings(path: &str) -> Result<Vec<TxtEncodingCandidate>, BookError> {
+        const SAMPLE_SIZE: usize = 64 * 1024;
+        const CANDIDATES: &[&str] = &[
+            "UTF-8", "GBK", "GB18030", "Big5", "UTF-16LE", "UTF-16BE", "Shift_JIS", "EUC-KR",
+            "windows-1252",
+        ];
+
+        if !Path::new(path).exists() {
+            return Err(BookError::file_not_found(path));
+        }
+
+        let mut file = File::open(path).map_err(|e| {
+            BookError::new(BookErrorCode::IoError, format!("打开文件失败: {}", e))
+        })?;
+        let mut sample = Vec::with_capacity(SAMPLE_SIZE);
+        file.by_ref()
+            .take(SAMPLE_SIZE as u64)
+            .read_to_end(&mut sample)
+            .map_err(|e| {
+                BookError::new(BookErrorCode::IoError, format!("读取文件失败: {}", e))
+            })?;
+        let is_partial = fs::metadata(path).map(|m| m.len() > sample.len() as u64).unwrap_or(false);
+
+        // BOM 可直接确定编码
+        let bom_encoding = if sample.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Some("UTF-8")
+        } else if sample.starts_with(&[0xFF, 0xFE]) {
+            Some("UTF-16LE")
+        } else if sample.starts_with(&[0xFE, 0xFF]) {
+            Some("UTF-16BE")
+        } else {
+            None
+        };
+
+        // chardetng 默认猜测及按地区提示的猜测
+        let mut detector = EncodingDetector::new();
+        detector.feed(&sample, !is_partial);
+        let primary_guess = detector.guess(None, true).name();
+        let hinted_guesses: Vec<&str> = [&b"cn"[..], b"tw", b"jp", b"kr"]
+            .iter()
+            .map(|tld| detector.guess(Some(tld), true).name())
+            .collect();
+
+        let nul_ratio =
+            sample.iter().filter(|b| **b == 0).count() as f32 / sample.len().max(1) as f32;
+
+        let mut names: Vec<&str> = CANDIDATES.to_vec();
+        for name in std::iter::once(primary_guess).chain(hinted_guesses.iter().copied()) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        let mut candidates: Vec<TxtEncodingCandidate> = names
+            .into_iter()
+            .filter_map(|name| {
+                let enc = encoding_rs::Encoding::for_label(name.as_bytes())?;
+                let is_utf16 = name.starts_with("UTF-16");
+                // 无 BOM 且几乎没有 0 字节时不可能是 UTF-16
+                if is_utf16 && bom_encoding != Some(name) && nul_ratio < 0.1 {
+                    return None;
+                }
+
+                let validity = Self::decode_validity(enc, &sample, is_partial);
+                let weight = if bom_encoding == Some(name) {
+                    1.0
+                } else if name == primary_guess {
+                    0.95
+                } else if hinted_guesses.contains(&name) {
+                    0.85
+                } else if enc.is_single_byte() {
+                    0.5
+                } else {
+                    0.7
+                };
+
+                let confidence = (validity * weight * 100.0).round() / 100.0;
+                if confidence <= 0.0 {
+                    return None;
+                }
+                Some(TxtEncodingCandidate {
+                    encoding: enc.name().to_string(),
+                    confidence,
+                })
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        candidates.dedup_by(|a, b| a.encoding == b.encoding);
+
+        Ok(candidates)
+    }
+
+    /// 以指定编码解码样本，返回有效字符占比（0-1）
+    /// 样本被截断时不统计末尾不完整的多字节序列
+    fn decode_validity(encoding: &'static encoding_rs::Encoding, sample: &[u8], is_partial: bool) -> f32 {
+        let mut decoder = encoding.new_decoder_with_bom_removal();
+        let mut decoded = String::with_capacity(
+            decoder
+                .max_utf8_buffer_length(sample.len())
+                .unwrap_or(sample.len() * 3),
+        );
+        let _ = decoder.decode_to_string(sample, &mut decoded, !is_partial);
+
+        let mut total = 0usize;
+        let mut bad = 0usize;
+        for c in decoded.chars() {
+            total += 1;
+            let is_control = c.is_control() && !matches!(c, '\n' | '\r' | '\t');
+            if c == '\u{FFFD}' || is_control || ('\u{E000}'..='\u{F8FF}').contains(&c) {
+                bad += 1;
+            }
+        }
+
+        if total == 0 {
+            return 0.0;
+        }
+        1.0 - bad as f32 / total as f32
+    }
+
     /// 文本预处理：统一换行符、去除多余空行
     fn normalize_text(content: &str) -> String {
         // 统一换行符为 \n
@@ -940,9 +1598,16 @@ impl TxtEngine {
             cover_image: None,
             page_count: 1, // 前端会进行虚拟分页
             format: Some(BookFormat::Txt),
+            series: None,
+            series_index: None,
         }
     }
 
+    /// 目录识别诊断：各章节模式的命中情况与疑似误匹配条目；`nest_subsections` 与加载元数据时的选项一致
+    pub fn diagnose_toc(&self, nest_subsections: bool) -> TocDiagnostics {
+        Self::toc_parser(nest_subsections).diagnose(&self.content, &self.lines)
+    }
+
     /// 章节识别，生成目录
     pub fn get_toc(&self) -> Vec<TocItem> {
         // 使用新的 TOC 解析器
@@ -962,3 +1627,178 @@ impl TxtEngine {
         toc
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_chapters_merge_and_split() {
+        let content = format!("序\n前言\n第一章\n{}", "正文正文正文正文\n".repeat(6));
+        let toc: Vec<TocItem> = [("序", 0), ("前言", 2), ("第一章", 5)]
+            .iter()
+            .map(|&(title, offset)| TocItem {
+                title: title.to_string(),
+                location: TocLocation::Page(offset),
+                level: 0,
+                children: vec![],
+            })
+            .collect();
+        let chapters = TxtEngine::convert_toc_to_chapters(&toc, &CharByteIndex::build(content.as_bytes(), "UTF-8"), false);
+        let options = TxtChapterNormalizeOptions {
+            enabled: true,
+            min_chars: 5,
+            max_chars: 20,
+            nest_subsections: false,
+        };
+        let (chapters, index_map) =
+            TxtEngine::normalize_chapters(
+                chapters,
+                &content,
+                &CharByteIndex::build(content.as_bytes(), "UTF-8"),
+                &options,
+            );
+
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["序", "第一章", "第一章（续）", "第一章（续2）"]);
+        assert_eq!(index_map, vec![0, 0, 1]);
+        assert_eq!(chapters[0].char_end, 5);
+        assert_eq!(chapters.last().unwrap().byte_end, content.len() as u64);
+        for (i, chapter) in chapters.iter().enumerate() {
+            assert_eq!(chapter.index, i as u32);
+            let text = &content[chapter.byte_start as usize..chapter.byte_end as usize];
+            assert_eq!(text.chars().count() as u64, chapter.char_count);
+            if i > 1 {
+                assert!(text.starts_with("正文") && text.ends_with('\n'));
+            }
+        }
+
+        let toc = TxtEngine::rewrite_toc_locations_as_chapter_index(&toc, &index_map, false);
+        assert_eq!(toc[1].location, TocLocation::Page(1));
+        assert_eq!(toc[2].location, TocLocation::Page(2));
+
+        let meta = TxtBookMeta {
+            title: String::new(),
+            encoding: "UTF-8".to_string(),
+            total_bytes: content.len() as u64,
+            total_chars: content.chars().count() as u64,
+            total_words: 0,
+            chapters,
+            toc,
+            chapter_normalize: options,
+        };
+        assert_eq!(meta.locate_char_offset(0), Some((0, 0)));
+        assert_eq!(meta.locate_char_offset(7), Some((1, 2)));
+        assert_eq!(meta.locate_char_offset(u64::MAX), Some((3, meta.chapters[3].char_end - meta.chapters[3].char_start)));
+
+        // 百分比与字符偏移只依赖元数据，不读取文件
+        let located = TxtEngine::locate("", &meta, LocateBy::Char(7)).unwrap();
+        assert_eq!((located.chapter_index, located.chapter_char_offset), (1, 2));
+        let located = TxtEngine::locate("", &meta, LocateBy::Percent(100.0)).unwrap();
+        assert_eq!(located.char_offset, meta.total_chars);
+        assert_eq!(located.percent, 100.0);
+        assert!(TxtEngine::locate("", &meta, LocateBy::Percent(f32::NAN)).is_err());
+    }
+
+    #[test]
+    fn test_diagnose_toc() {
+        let body = "正文内容".repeat(30);
+        let content = format!(
+            "第一章 开端\n\n{body}\n他说：\n一、要早起；\n二、要读书。\n\n第二章 发展\n\n{body}\n\n第二章 发展\n短\n"
+        );
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        let diagnostics = TxtEngine::toc_parser(true).diagnose(&content, &lines);
+
+        let chapter_stats = diagnostics.patterns.iter().find(|p| p.name == "chinese_chapter").unwrap();
+        assert_eq!((chapter_stats.matched, chapter_stats.accepted), (3, 3));
+        let numeric_stats = diagnostics.patterns.iter().find(|p| p.name == "chinese_numeric").unwrap();
+        assert_eq!((numeric_stats.matched, numeric_stats.accepted), (2, 0));
+
+        // 正文里的逐条列举命中了模式但未进入目录，并给出原因
+        let listing = diagnostics.entries.iter().find(|e| e.title == "一、要早起；").unwrap();
+        assert_eq!(listing.line_number, 5);
+        assert!(!listing.accepted && listing.rejected_reason.is_some());
+
+        // 重复标题与正文过短的章节被标为疑似误匹配
+        let suspects: Vec<usize> = diagnostics.suspects.iter().map(|s| s.line_number).collect();
+        assert_eq!(suspects, vec![8, 12]);
+        assert!(diagnostics.suspects[1].reason.contains("正文过短"));
+        assert!(!diagnostics.used_fallback);
+    }
+
+    #[test]
+    fn test_subsections_nested_under_chapter() {
+        let body = "正文内容".repeat(30);
+        let content = format!(
+            "第一章 开端\n\n一、缘起\n\n{body}\n他说：\n一、要早起；\n二、要读书。\n\n二、经过\n\n{body}\n\n第二章 发展\n\n（一）转折\n\n{body}\n1.1 细节\n{body}\n"
+        );
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        let toc = TxtEngine::toc_parser(true).parse(&content, &lines);
+
+        let titles: Vec<(&str, Vec<&str>)> = toc
+            .iter()
+            .map(|item| (item.title.as_str(), item.children.iter().map(|c| c.title.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                ("第一章 开端", vec!["一、缘起", "二、经过"]),
+                ("第二章 发展", vec!["（一）转折", "1.1 细节"]),
+            ]
+        );
+
+        let index = CharByteIndex::build(content.as_bytes(), "UTF-8");
+        let chapters = TxtEngine::convert_toc_to_chapters(&toc, &index, true);
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].char_end, chapters[1].char_start);
+
+        let indexed = TxtEngine::rewrite_toc_locations_as_chapter_index(&toc, &[0, 1], true);
+        assert_eq!(indexed[0].children[1].location, TocLocation::Page(1));
+        assert_eq!(indexed[1].location, TocLocation::Page(2));
+        assert_eq!(indexed[1].children[0].location, TocLocation::Page(2));
+
+        // 未开启小节识别时保持旧版划分：章后的“一、二”仍单独成章，新增的小节模式不参与识别
+        let toc = TocParser::new().parse(&content, &lines);
+        let chapters = TxtEngine::convert_toc_to_chapters(&toc, &index, false);
+        let titles: Vec<&str> = chapters.iter().map(|c| c.title.as_str()).collect();
+        assert!(titles.contains(&"一、缘起") && titles.contains(&"一、要早起；"));
+        assert!(!titles.contains(&"（一）转折"));
+    }
+
+    #[test]
+    fn test_apply_format_indent_and_trailing() {
+        let mut chapter = TxtChapterContent {
+            index: 0,
+            content: "第一章\n\u{3000}\u{3000}正文一段。  \n\n  半角缩进\t\n".to_string(),
+            char_start: 0,
+            char_end: 0,
+            paragraphs: None,
+        };
+        chapter.apply_format(&TxtFormatOptions {
+            preserve_indent: true,
+            trim_trailing_whitespace: true,
+            with_paragraphs: true,
+            vertical: false,
+        });
+
+        assert_eq!(chapter.content, "第一章\n\u{3000}\u{3000}正文一段。\n\n  半角缩进\n");
+        let paragraphs = chapter.paragraphs.unwrap();
+        assert_eq!(paragraphs.len(), 3);
+        assert_eq!(paragraphs[1].text, "正文一段。");
+        assert_eq!(paragraphs[1].indent, 2.0);
+        assert_eq!(paragraphs[2].indent, 1.0);
+
+        let mut plain = TxtChapterContent {
+            index: 0,
+            content: "\u{3000}\u{3000}正文\n".to_string(),
+            char_start: 0,
+            char_end: 0,
+            paragraphs: None,
+        };
+        plain.apply_format(&TxtFormatOptions {
+            preserve_indent: false,
+            ..Default::default()
+        });
+        assert_eq!(plain.content, "正文\n");
+    }
+}
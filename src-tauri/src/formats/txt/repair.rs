@@ -0,0 +1,208 @@
+//! 局部乱码修复
+//! 文件本身混用编码时，整体检测出的编码只能正确解码大部分内容，夹杂的段落会出现 U+FFFD 替换字符。
+//! 以行为单位定位解码出错的连续段，对该段原始字节依次尝试 UTF-8/GBK/Big5 重新解码，取乱码最少者；
+//! 解码无误的行原样保留
+
+use chardetng::EncodingDetector;
+use encoding_rs::Encoding;
+
+use super::BookError;
+
+/// 乱码段的备选编码，乱码数相同时靠前者优先
+const FALLBACK_ENCODINGS: &[&Encoding] = &[encoding_rs::UTF_8, encoding_rs::GBK, encoding_rs::BIG5];
+
+/// 单个乱码段的修复记录
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TxtRepairSegment {
+    /// 起始行号（从 0 开始）
+    pub line: u32,
+    /// 连续乱码行数
+    pub line_count: u32,
+    /// 在修复后文本中的起始字符偏移
+    pub char_offset: u64,
+    /// 修复后该段的字符数
+    pub char_count: u64,
+    /// 用于重新解码的编码，未找到更好的编码时为 None（保持原解码结果）
+    pub encoding: Option<String>,
+    /// 原编码解码的乱码字符数
+    pub bad_chars_before: u32,
+    /// 修复后的乱码字符数
+    pub bad_chars_after: u32,
+}
+
+/// 乱码修复结果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TxtRepairResult {
+    /// 修复后的文本
+    pub content: String,
+    /// 整体使用的编码
+    pub encoding: String,
+    /// 修复前 U+FFFD 替换字符占全部字符的比例
+    pub replacement_density: f32,
+    /// 解码出错的段落，按出现顺序排列
+    pub segments: Vec<TxtRepairSegment>,
+}
+
+/// 乱码字符：替换字符、非常规控制字符、私用区字符
+fn is_bad_char(c: char) -> bool {
+    c == '\u{FFFD}'
+        || (c.is_control() && !matches!(c, '\n' | '\r' | '\t'))
+        || ('\u{E000}'..='\u{F8FF}').contains(&c)
+}
+
+fn count_bad_chars(text: &str) -> u32 {
+    text.chars().filter(|&c| is_bad_char(c)).count() as u32
+}
+
+/// 以指定编码解码一段字节，不处理 BOM（BOM 只可能出现在文件开头，已在外层剥离）
+fn decode_segment(encoding: &'static Encoding, bytes: &[u8]) -> (String, bool) {
+    let (decoded, had_errors) = encoding.decode_without_bom_handling(bytes);
+    (decoded.into_owned(), had_errors)
+}
+
+/// 对乱码段依次尝试备选编码，返回乱码严格少于原解码结果的最佳结果
+fn best_fallback(
+    primary: &'static Encoding,
+    bytes: &[u8],
+    bad_before: u32,
+) -> Option<(&'static Encoding, String, u32)> {
+    let mut best: Option<(&'static Encoding, String, u32)> = None;
+    for &encoding in FALLBACK_ENCODINGS.iter().filter(|&&e| e != primary) {
+        let (decoded, _) = decode_segment(encoding, bytes);
+        let bad = count_bad_chars(&decoded);
+        let limit = best.as_ref().map_or(bad_before, |(_, _, b)| *b);
+        if bad < limit {
+            best = Some((encoding, decoded, bad));
+        }
+    }
+    best
+}
+
+/// 修复原始字节中的局部乱码
+/// `force_encoding` 为整体编码，不指定时自动检测；UTF-16 无法按字节切分行，只解码不修复
+pub fn repair_garbled_text(raw: &[u8], force_encoding: Option<&str>) -> Result<TxtRepairResult, BookError> {
+    let (primary, body) = if raw.starts_with(&[0xEF, 0xBB, 0xBF]) && force_encoding.is_none() {
+        (encoding_rs::UTF_8, &raw[3..])
+    } else {
+        let encoding = match force_encoding {
+            Some(label) => Encoding::for_label(label.trim().as_bytes())
+                .ok_or_else(|| BookError::encoding_error(label).with_details("不支持的编码名称"))?,
+            None => {
+                let mut detector = EncodingDetector::new();
+                detector.feed(raw, true);
+                detector.guess(None, true)
+            }
+        };
+        let (_, bom_len) = Encoding::for_bom(raw).filter(|(e, _)| *e == encoding).unwrap_or((encoding, 0));
+        (encoding, &raw[bom_len..])
+    };
+
+    if primary == encoding_rs::UTF_16LE || primary == encoding_rs::UTF_16BE {
+        let (content, _) = decode_segment(primary, body);
+        let replaced = content.chars().filter(|&c| c == '\u{FFFD}').count();
+        let total = content.chars().count().max(1);
+        return Ok(TxtRepairResult {
+            content,
+            encoding: primary.name().to_string(),
+            replacement_density: replaced as f32 / total as f32,
+            segments: Vec::new(),
+        });
+    }
+
+    let mut content = String::with_capacity(body.len());
+    let mut segments = Vec::new();
+    let (mut total_chars, mut replaced_chars) = (0u64, 0u64);
+    let mut char_offset = 0u64;
+    // 当前连续乱码段：起始行号、行数、起止字节
+    let mut pending: Option<(u32, u32, usize, usize)> = None;
+
+    let mut flush = |pending: &mut Option<(u32, u32, usize, usize)>, content: &mut String, char_offset: &mut u64| {
+        let Some((line, line_count, start, end)) = pending.take() else { return };
+        let bytes = &body[start..end];
+        let (decoded, _) = decode_segment(primary, bytes);
+        let bad_before = count_bad_chars(&decoded);
+        let (encoding, text, bad_after) = match best_fallback(primary, bytes, bad_before) {
+            Some((encoding, text, bad)) => (Some(encoding.name().to_string()), text, bad),
+            None => (None, decoded, bad_before),
+        };
+        let char_count = text.chars().count() as u64;
+        segments.push(TxtRepairSegment {
+            line,
+            line_count,
+            char_offset: *char_offset,
+            char_count,
+            encoding,
+            bad_chars_before: bad_before,
+            bad_chars_after: bad_after,
+        });
+        content.push_str(&text);
+        *char_offset += char_count;
+    };
+
+    let mut start = 0usize;
+    for (line, bytes) in body.split_inclusive(|&b| b == b'\n').enumerate() {
+        let end = start + bytes.len();
+        let (decoded, had_errors) = decode_segment(primary, bytes);
+        let chars = decoded.chars().count() as u64;
+        total_chars += chars;
+        if had_errors {
+            replaced_chars += decoded.chars().filter(|&c| c == '\u{FFFD}').count() as u64;
+            pending = match pending {
+                Some((first, count, seg_start, _)) => Some((first, count + 1, seg_start, end)),
+                None => Some((line as u32, 1, start, end)),
+            };
+        } else {
+            flush(&mut pending, &mut content, &mut char_offset);
+            content.push_str(&decoded);
+            char_offset += chars;
+        }
+        start = end;
+    }
+    flush(&mut pending, &mut content, &mut char_offset);
+
+    let repaired = segments.iter().filter(|s| s.encoding.is_some()).count();
+    if !segments.is_empty() {
+        println!(
+            "[TxtRepair] 乱码修复: encoding={}, 乱码段={}, 已修复={}",
+            primary.name(),
+            segments.len(),
+            repaired
+        );
+    }
+
+    Ok(TxtRepairResult {
+        content,
+        encoding: primary.name().to_string(),
+        replacement_density: replaced_chars as f32 / total_chars.max(1) as f32,
+        segments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repair_mixed_encoding() {
+        let (gbk_line, _, _) = encoding_rs::GBK.encode("第二段是GBK编码的中文内容\n");
+        let mut raw = "第一段是正常的UTF-8文本\n".as_bytes().to_vec();
+        raw.extend_from_slice(&gbk_line);
+        raw.extend_from_slice("第三段恢复UTF-8\n".as_bytes());
+
+        let result = repair_garbled_text(&raw, Some("utf-8")).unwrap();
+        assert_eq!(result.content, "第一段是正常的UTF-8文本\n第二段是GBK编码的中文内容\n第三段恢复UTF-8\n");
+        assert!(result.replacement_density > 0.0);
+        assert_eq!(result.segments.len(), 1);
+        let segment = &result.segments[0];
+        assert_eq!((segment.line, segment.line_count), (1, 1));
+        assert_eq!(segment.encoding.as_deref(), Some("GBK"));
+        assert_eq!(segment.char_offset, "第一段是正常的UTF-8文本\n".chars().count() as u64);
+        assert_eq!(segment.bad_chars_after, 0);
+
+        // 无乱码内容原样返回
+        let clean = repair_garbled_text("没有乱码\n".as_bytes(), Some("utf-8")).unwrap();
+        assert_eq!(clean.content, "没有乱码\n");
+        assert!(clean.segments.is_empty());
+        assert_eq!(clean.replacement_density, 0.0);
+    }
+}
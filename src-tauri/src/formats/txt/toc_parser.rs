@@ -3,9 +3,37 @@
 
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::Serialize;
+use std::collections::HashMap;
 
 use crate::formats::{TocItem, TocLocation};
 
+/// 章内小节的级别：作为章的子目录，不单独切分章节
+pub const SUBSECTION_LEVEL: u32 = 2;
+
+/// 章内小节标题的最大长度，更长的多半是以序号开头的正文段落
+const MAX_SUBSECTION_TITLE_CHARS: usize = 30;
+
+/// 章内小节下至少要有的正文字数，过短的多是正文里逐条列举的“一、二、三”
+const MIN_SUBSECTION_BODY_CHARS: usize = 80;
+
+/// 只用于识别章内小节的模式，未开启小节识别时不参与匹配（这些行按原有模式识别或视为正文）
+const SUBSECTION_ONLY_PATTERN_NAMES: &[&str] = &["numeric_subsection", "chinese_subsection_paren"];
+
+/// 可能是章内小节的模式：出现在明确的章标题之后时降为小节，并需通过小节启发式检查
+const SUBSECTION_PATTERN_NAMES: &[&str] = &[
+    "chinese_numeric",
+    "numeric_chapter",
+    "numeric_subsection",
+    "chinese_subsection_paren",
+];
+
+/// 诊断时标题超过该长度视为疑似误匹配（多为被当成标题的正文）
+const SUSPECT_TITLE_CHARS: usize = 40;
+
+/// 诊断时章节正文少于该字数视为疑似误匹配
+const SUSPECT_MIN_BODY_CHARS: usize = 50;
+
 /// 章节模式定义
 struct ChapterPatternDef {
     /// 正则表达式字符串
@@ -94,6 +122,20 @@ const CHAPTER_PATTERN_DEFS: &[ChapterPatternDef] = &[
         priority: 70,
         name: "chinese_section",
     },
+    // 多级数字小节 "1.1 标题"、"2.3.1 标题"（需排在纯数字章节之前）
+    ChapterPatternDef {
+        pattern: r"^\d{1,2}[.．]\d{1,2}(?:[.．]\d{1,2})?\s*[^\d.．\s].*",
+        level: 2,
+        priority: 65,
+        name: "numeric_subsection",
+    },
+    // 括号中文序号小节 "（一）标题"
+    ChapterPatternDef {
+        pattern: r"^[（(][一二三四五六七八九十]+[）)]\s*.+",
+        level: 2,
+        priority: 60,
+        name: "chinese_subsection_paren",
+    },
     // 纯数字章节 "001 标题" 或 "001.标题"
     ChapterPatternDef {
         pattern: r"^\d{1,4}[.、\s]\s*.{2,}",
@@ -178,6 +220,80 @@ struct CandidateChapter {
     confidence: i32,
 }
 
+/// 候选章节的筛选结论
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CandidateVerdict {
+    /// 采纳为目录项，附带动态分配后的级别
+    Accepted(u32),
+    /// 置信度低于阈值
+    LowConfidence,
+    /// 降为小节后未通过小节启发式检查
+    RejectedSubsection,
+}
+
+impl CandidateVerdict {
+    fn reason(self) -> Option<&'static str> {
+        match self {
+            CandidateVerdict::Accepted(_) => None,
+            CandidateVerdict::LowConfidence => Some("置信度低于阈值"),
+            CandidateVerdict::RejectedSubsection => Some("疑似正文中的条目列举，未作为小节"),
+        }
+    }
+}
+
+/// 单条章节模式的命中统计
+#[derive(Debug, Clone, Serialize)]
+pub struct TocPatternStats {
+    /// 模式名称
+    pub name: String,
+    /// 模式默认级别
+    pub level: u32,
+    pub priority: i32,
+    /// 正则命中的行数（每行只计第一个命中的模式）
+    pub matched: usize,
+    /// 最终进入目录的条数
+    pub accepted: usize,
+}
+
+/// 正则命中的一行及其筛选结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TocDiagnosticEntry {
+    /// 行号（从 1 开始，基于规范化后的文本）
+    pub line_number: usize,
+    pub title: String,
+    /// 命中的模式名称
+    pub pattern: String,
+    pub confidence: i32,
+    /// 是否进入目录
+    pub accepted: bool,
+    /// 进入目录时的级别
+    pub level: Option<u32>,
+    /// 未进入目录的原因
+    pub rejected_reason: Option<String>,
+}
+
+/// 已进入目录但疑似误匹配的条目
+#[derive(Debug, Clone, Serialize)]
+pub struct TocSuspectEntry {
+    pub line_number: usize,
+    pub title: String,
+    pub pattern: String,
+    pub reason: String,
+}
+
+/// 目录解析诊断结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TocDiagnostics {
+    pub total_lines: usize,
+    /// 各模式命中统计，按预定义顺序排列
+    pub patterns: Vec<TocPatternStats>,
+    /// 所有正则命中的行
+    pub entries: Vec<TocDiagnosticEntry>,
+    pub suspects: Vec<TocSuspectEntry>,
+    /// 识别出的章节过少，实际目录使用了按长度自动分段
+    pub used_fallback: bool,
+}
+
 /// 文本上下文，用于启发式分析
 struct TextContext<'a> {
     lines: &'a [String],
@@ -241,6 +357,8 @@ impl<'a> TextContext<'a> {
 /// 上下文感知的层级分配器
 /// 根据前后文动态判断章节级别
 struct LevelAssigner {
+    /// 是否识别章内小节（纯数字序号在章后降为小节）
+    detect_subsections: bool,
     /// 是否遇到过明确的章级别标题（如"第X章"）
     has_explicit_chapter: bool,
     /// 模式统计：用于判断 chinese_numeric 的真实级别
@@ -249,8 +367,9 @@ struct LevelAssigner {
 }
 
 impl LevelAssigner {
-    fn new() -> Self {
+    fn new(detect_subsections: bool) -> Self {
         Self {
+            detect_subsections,
             has_explicit_chapter: false,
             chapter_count: 0,
             numeric_count: 0,
@@ -299,6 +418,8 @@ impl LevelAssigner {
                 // 否则保持原级别（章级别）
                 original_level
             }
+            // 纯数字序号出现在明确的章之后时同样视为小节
+            "numeric_chapter" if self.detect_subsections && self.has_explicit_chapter => SUBSECTION_LEVEL,
             // 其他模式保持原级别
             _ => original_level,
         }
@@ -332,6 +453,20 @@ fn ends_with_sentence_punctuation(title: &str) -> bool {
     title.chars().last().map_or(false, |c| punctuations.contains(&c))
 }
 
+/// 章内小节启发式：标题需独占一行且足够短、不含句内标点，后面跟着成段正文；
+/// 用于排除正文里“一、……；二、……”式的逐条列举
+fn is_plausible_subsection(title: &str, body_chars: usize) -> bool {
+    let title_len = title.chars().count();
+    if title_len > MAX_SUBSECTION_TITLE_CHARS || body_chars < MIN_SUBSECTION_BODY_CHARS {
+        return false;
+    }
+    if title.contains(['，', ',', '。', '；', ';', '！', '？']) {
+        return false;
+    }
+    // 以冒号结尾的是列举的引导句
+    !title.ends_with(['：', ':']) && !ends_with_sentence_punctuation(title)
+}
+
 /// 计算候选章节的置信度分数
 fn calculate_confidence(candidate: &mut CandidateChapter, context: &TextContext) {
     let mut score = candidate.pattern_priority;
@@ -395,6 +530,8 @@ pub struct TocParserConfig {
     pub enable_smart_fallback: bool,
     /// 兜底分段的最小章节数阈值
     pub fallback_threshold: usize,
+    /// 识别章内小节（小节模式、小节启发式过滤）。会改变章节划分，默认关闭以保持已导入书籍的章节序号
+    pub detect_subsections: bool,
 }
 
 impl Default for TocParserConfig {
@@ -404,6 +541,7 @@ impl Default for TocParserConfig {
             enable_heuristics: true,
             enable_smart_fallback: true,
             fallback_threshold: 3,
+            detect_subsections: false,
         }
     }
 }
@@ -422,7 +560,6 @@ impl TocParser {
     }
 
     /// 使用自定义配置创建解析器
-    #[allow(dead_code)]
     pub fn with_config(config: TocParserConfig) -> Self {
         Self { config }
     }
@@ -438,13 +575,11 @@ impl TocParser {
         }
 
         // Stage 3: 过滤并构建层级目录
-        let toc = self.build_toc_tree(candidates);
+        let judged = self.judge_candidates(candidates, content.chars().count());
+        let toc = self.build_toc_tree(&judged);
 
         // 兜底策略
-        if self.config.enable_smart_fallback
-            && toc.len() < self.config.fallback_threshold
-            && lines.len() > 100
-        {
+        if self.needs_fallback(&toc, lines) {
             return self.smart_segmentation(content, lines);
         }
 
@@ -476,7 +611,10 @@ impl TocParser {
             }
 
             // 尝试匹配所有模式
-            for pattern in COMPILED_PATTERNS.iter() {
+            let patterns = COMPILED_PATTERNS.iter().filter(|p| {
+                self.config.detect_subsections || !SUBSECTION_ONLY_PATTERN_NAMES.contains(&p.name)
+            });
+            for pattern in patterns {
                 if pattern.regex.is_match(trimmed) {
                     candidates.push(CandidateChapter {
                         title: trimmed.to_string(),
@@ -513,29 +651,166 @@ impl TocParser {
         }
     }
 
-    /// Stage 3: 构建层级目录树
-    fn build_toc_tree(&self, candidates: Vec<CandidateChapter>) -> Vec<TocItem> {
-        // 过滤低置信度章节
-        let valid_chapters: Vec<_> = candidates
-            .into_iter()
-            .filter(|c| c.confidence >= self.config.min_confidence)
+    /// 识别出的顶层目录过少时改用智能分段
+    fn needs_fallback(&self, toc: &[TocItem], lines: &[String]) -> bool {
+        self.config.enable_smart_fallback && toc.len() < self.config.fallback_threshold && lines.len() > 100
+    }
+
+    /// 诊断目录解析：统计各模式命中情况，列出每条命中的行及筛选结论，并标出疑似误匹配的目录项
+    pub fn diagnose(&self, content: &str, lines: &[String]) -> TocDiagnostics {
+        let mut candidates = self.match_patterns(lines);
+        if self.config.enable_heuristics {
+            self.analyze_candidates(&mut candidates, lines);
+        }
+        let total_chars = content.chars().count();
+        let judged = self.judge_candidates(candidates, total_chars);
+        let used_fallback = self.needs_fallback(&self.build_toc_tree(&judged), lines);
+
+        let mut patterns: Vec<TocPatternStats> = COMPILED_PATTERNS
+            .iter()
+            .map(|p| TocPatternStats {
+                name: p.name.to_string(),
+                level: p.level,
+                priority: p.priority,
+                matched: 0,
+                accepted: 0,
+            })
+            .collect();
+        let mut entries = Vec::with_capacity(judged.len());
+        for (candidate, verdict) in &judged {
+            let accepted = matches!(verdict, CandidateVerdict::Accepted(_));
+            if let Some(stats) = patterns.iter_mut().find(|p| p.name == candidate.pattern_name) {
+                stats.matched += 1;
+                stats.accepted += accepted as usize;
+            }
+            entries.push(TocDiagnosticEntry {
+                line_number: candidate.line_number + 1,
+                title: candidate.title.clone(),
+                pattern: candidate.pattern_name.clone(),
+                confidence: candidate.confidence,
+                accepted,
+                level: match verdict {
+                    CandidateVerdict::Accepted(level) => Some(*level),
+                    _ => None,
+                },
+                rejected_reason: verdict.reason().map(str::to_string),
+            });
+        }
+
+        TocDiagnostics {
+            total_lines: lines.len(),
+            patterns,
+            entries,
+            suspects: Self::find_suspects(&judged, total_chars),
+            used_fallback,
+        }
+    }
+
+    /// 已采纳的目录项中疑似误匹配的条目：标题过长、标题重复、以句末标点结尾或正文过短
+    fn find_suspects(judged: &[(CandidateChapter, CandidateVerdict)], total_chars: usize) -> Vec<TocSuspectEntry> {
+        let accepted: Vec<&CandidateChapter> = judged
+            .iter()
+            .filter(|(_, verdict)| matches!(verdict, CandidateVerdict::Accepted(_)))
+            .map(|(candidate, _)| candidate)
             .collect();
+        let mut title_counts: HashMap<&str, usize> = HashMap::new();
+        for candidate in &accepted {
+            *title_counts.entry(candidate.title.as_str()).or_insert(0) += 1;
+        }
+
+        let mut suspects = Vec::new();
+        for (i, candidate) in accepted.iter().enumerate() {
+            let title_chars = candidate.title.chars().count();
+            let next_offset = accepted.get(i + 1).map_or(total_chars, |next| next.char_offset);
+            let body_chars = next_offset.saturating_sub(candidate.char_offset + title_chars);
+
+            let mut reasons = Vec::new();
+            if title_chars > SUSPECT_TITLE_CHARS {
+                reasons.push(format!("标题过长（{} 字）", title_chars));
+            }
+            let repeats = title_counts.get(candidate.title.as_str()).copied().unwrap_or(0);
+            if repeats > 1 {
+                reasons.push(format!("标题重复出现 {} 次", repeats));
+            }
+            if ends_with_sentence_punctuation(&candidate.title) {
+                reasons.push("以句末标点结尾，可能是正文".to_string());
+            }
+            if body_chars < SUSPECT_MIN_BODY_CHARS {
+                reasons.push(format!("章节正文过短（{} 字）", body_chars));
+            }
+            if !reasons.is_empty() {
+                suspects.push(TocSuspectEntry {
+                    line_number: candidate.line_number + 1,
+                    title: candidate.title.clone(),
+                    pattern: candidate.pattern_name.clone(),
+                    reason: reasons.join("；"),
+                });
+            }
+        }
+        suspects
+    }
+
+    /// Stage 3a: 过滤低置信度候选、动态分配级别并检查小节，给出每个候选的结论
+    fn judge_candidates(
+        &self,
+        candidates: Vec<CandidateChapter>,
+        total_chars: usize,
+    ) -> Vec<(CandidateChapter, CandidateVerdict)> {
+        let (valid_chapters, low_confidence): (Vec<_>, Vec<_>) = candidates
+            .into_iter()
+            .partition(|c| c.confidence >= self.config.min_confidence);
 
         // 创建层级分配器并预扫描
-        let mut level_assigner = LevelAssigner::new();
+        let mut level_assigner = LevelAssigner::new(self.config.detect_subsections);
         level_assigner.pre_scan(&valid_chapters);
 
-        let mut root: Vec<TocItem> = Vec::new();
-        let mut current_volume_idx: Option<usize> = None;
-        let mut current_chapter_idx: Option<usize> = None;
+        // 每个候选的正文截止位置（下一候选的起点），用于小节启发式
+        let next_offsets: Vec<usize> = valid_chapters
+            .iter()
+            .skip(1)
+            .map(|c| c.char_offset)
+            .chain(std::iter::once(total_chars))
+            .collect();
 
-        for chapter in valid_chapters {
+        let mut judged: Vec<(CandidateChapter, CandidateVerdict)> = low_confidence
+            .into_iter()
+            .map(|c| (c, CandidateVerdict::LowConfidence))
+            .collect();
+        for (chapter, next_offset) in valid_chapters.into_iter().zip(next_offsets) {
             // 动态分配级别
             let adjusted_level = level_assigner.assign_level(&chapter.pattern_name, chapter.level);
             level_assigner.record_pattern(&chapter.pattern_name);
 
+            let mut verdict = CandidateVerdict::Accepted(adjusted_level);
+            if self.config.detect_subsections
+                && adjusted_level == SUBSECTION_LEVEL
+                && SUBSECTION_PATTERN_NAMES.contains(&chapter.pattern_name.as_str())
+            {
+                let body_chars = next_offset.saturating_sub(chapter.char_offset + chapter.title.chars().count());
+                if !is_plausible_subsection(&chapter.title, body_chars) {
+                    verdict = CandidateVerdict::RejectedSubsection;
+                }
+            }
+            judged.push((chapter, verdict));
+        }
+        // 恢复文本顺序
+        judged.sort_by_key(|(c, _)| c.line_number);
+        judged
+    }
+
+    /// Stage 3b: 由已采纳的候选构建层级目录树
+    fn build_toc_tree(&self, judged: &[(CandidateChapter, CandidateVerdict)]) -> Vec<TocItem> {
+        let mut root: Vec<TocItem> = Vec::new();
+        let mut current_volume_idx: Option<usize> = None;
+        let mut current_chapter_idx: Option<usize> = None;
+
+        for (chapter, verdict) in judged {
+            let CandidateVerdict::Accepted(adjusted_level) = *verdict else {
+                continue;
+            };
+
             let item = TocItem {
-                title: chapter.title,
+                title: chapter.title.clone(),
                 location: TocLocation::Page(chapter.char_offset as u32),
                 level: adjusted_level,
                 children: vec![],
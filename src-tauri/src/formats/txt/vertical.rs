@@ -0,0 +1,70 @@
+//! 竖排标点转换
+//! 竖排时破折号、省略号、括号、引号需要换成竖排专用字形（CJK 竖排标点与兼容形式区块），
+//! 否则在部分 WebView 字体下方向错乱。逗号、句号等横竖通用的标点保持不变。
+//! 转换逐字符一一替换，章节内字符偏移保持不变
+
+/// 横排字符对应的竖排字形，不需要转换时返回 None
+fn vertical_form(c: char) -> Option<char> {
+    let v = match c {
+        '—' | '―' => '︱',
+        '–' => '︲',
+        '…' => '︙',
+        '‥' => '︰',
+        '（' => '︵',
+        '）' => '︶',
+        '｛' => '︷',
+        '｝' => '︸',
+        '〔' => '︹',
+        '〕' => '︺',
+        '【' => '︻',
+        '】' => '︼',
+        '《' => '︽',
+        '》' => '︾',
+        '〈' => '︿',
+        '〉' => '﹀',
+        '「' | '“' => '﹁',
+        '」' | '”' => '﹂',
+        '『' | '‘' => '﹃',
+        '』' | '’' => '﹄',
+        '［' => '﹇',
+        '］' => '﹈',
+        '〖' => '︗',
+        '〗' => '︘',
+        _ => return None,
+    };
+    Some(v)
+}
+
+/// 把文本中的标点转换为竖排字形
+/// 夹在拉丁字母之间的 ‘ ’ 视为英文撇号（如 don’t），保持不变
+pub fn to_vertical_text(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| {
+            let is_apostrophe = matches!(c, '‘' | '’')
+                && i > 0
+                && chars[i - 1].is_ascii_alphabetic()
+                && chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic());
+            if is_apostrophe {
+                c
+            } else {
+                vertical_form(c).unwrap_or(c)
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_vertical_text() {
+        let text = "他说：“走吧——去《长安》……”（完）don’t，『好』。";
+        let vertical = to_vertical_text(text);
+        assert_eq!(vertical, "他说：﹁走吧︱︱去︽长安︾︙︙﹂︵完︶don’t，﹃好﹄。");
+        assert_eq!(vertical.chars().count(), text.chars().count());
+    }
+}
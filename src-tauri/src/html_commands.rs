@@ -1,6 +1,8 @@
 //! HTML 相关的 Tauri 命令
 
-use crate::formats::html::HtmlEngine;
+use crate::formats::html::{HtmlEngine, HtmlResource};
+use crate::formats::sanitize::{sanitize_enabled, sanitize_html_if_enabled, set_sanitize_enabled};
+use crate::prefetch_commands::clear_prefetch;
 use serde::{Deserialize, Serialize};
 
 /// 加载 HTML 文档的结果
@@ -21,8 +23,48 @@ pub async fn html_load_document(file_path: String) -> Result<HtmlLoadResult, Str
         .map_err(|e| e.to_string())?;
 
     Ok(HtmlLoadResult {
-        content: engine.get_content().to_string(),
+        content: sanitize_html_if_enabled(engine.get_content().to_string()),
         encoding: engine.get_encoding().to_string(),
         title: engine.get_title(),
     })
 }
+
+/// 加载 HTML 文档并处理同目录的图片/CSS 资源
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HtmlLoadWithResourcesResult {
+    /// 资源引用已改写的 HTML 内容
+    pub content: String,
+    pub encoding: String,
+    pub title: Option<String>,
+    /// 被内联或改写为协议地址的资源
+    pub resources: Vec<HtmlResource>,
+}
+
+/// 加载 HTML 文档，同目录小图内联为 data URL，大图和 CSS 通过 `goread-res` 协议按需加载
+#[tauri::command]
+pub async fn html_load_document_with_resources(file_path: String) -> Result<HtmlLoadWithResourcesResult, String> {
+    tokio::task::spawn_blocking(move || {
+        let engine = HtmlEngine::from_file(&file_path).map_err(|e| e.to_string())?;
+        let processed = engine.get_content_with_resources();
+        Ok(HtmlLoadWithResourcesResult {
+            content: sanitize_html_if_enabled(processed.content),
+            encoding: engine.get_encoding().to_string(),
+            title: engine.get_title(),
+            resources: processed.resources,
+        })
+    })
+    .await
+    .map_err(|e| format!("加载 HTML 任务失败: {}", e))?
+}
+
+/// 设置是否清洗 EPUB/MOBI/HTML 章节内容（移除脚本、事件属性与外部请求），默认开启
+/// 供高级用户关闭，关闭后返回书中原始 HTML
+/// 磁盘章节缓存保存原始 HTML、读取时按当前开关清洗；内存中的预取章节已按旧开关处理，切换时一并丢弃
+#[tauri::command]
+pub async fn set_html_sanitize(enabled: bool) -> Result<bool, String> {
+    if sanitize_enabled() != enabled {
+        set_sanitize_enabled(enabled);
+        clear_prefetch();
+    }
+    Ok(true)
+}
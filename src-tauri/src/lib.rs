@@ -1,12 +1,18 @@
+mod comic_commands;
 mod commands;
 pub(crate) mod cover;
 mod epub_commands;
+mod footnote_commands;
 mod formats;
 mod html_commands;
+mod location_commands;
 mod markdown_commands;
 mod models;
+mod opds;
 mod pdf;
 mod pdf_commands;
+mod prefetch_commands;
+mod resource_protocol;
 mod tts;
 mod tts_commands;
 mod txt_commands;
@@ -17,8 +23,13 @@ use commands::{
     add_book,
     // bookmark commands
     add_bookmark,
+    get_txt_bookmark_position,
     // cover commands
+    cleanup_orphan_covers,
     clear_book_cover,
+    generate_group_cover,
+    generate_text_cover,
+    make_cover_thumbnail,
     get_books_needing_cover_rebuild,
     get_epub_books_without_cover,
     get_mobi_books_without_cover,
@@ -34,6 +45,19 @@ use commands::{
     batch_import_books,
     // import commands
     batch_read_files,
+    resolve_book_title,
+    import_from_archive,
+    // theme commands
+    save_theme,
+    get_themes,
+    delete_theme,
+    set_active_theme,
+    create_virtual_book,
+    virtual_book_load_metadata,
+    virtual_book_load_chapter,
+    // opds commands
+    opds_browse,
+    opds_download,
     cancel_scan,
     check_storage_permission,
     clear_recent_read_record,
@@ -42,20 +66,34 @@ use commands::{
     delete_group,
     // backup commands
     export_app_data,
+    export_bookmarks,
     frontend_log,
+    get_logs,
+    export_logs,
     get_all_books,
     get_all_groups,
     get_bookmarks,
+    get_bookmark_thumbnail,
     get_books_by_date_range,
     get_books_by_group,
+    get_books_by_series,
+    get_books_by_language,
+    get_library_languages,
+    search_library,
     get_daily_stats,
     get_day_stats_by_hour,
+    get_default_scan_excludes,
+    get_finished_books,
     get_reading_stats_by_range,
     get_recent_books,
+    get_recently_opened_books,
     get_root_directories,
     get_stats_summary,
+    get_unfinished_books,
     has_reading_sessions,
     import_app_data,
+    import_bookmarks,
+    invalidate_scan_cache,
     // book commands
     init_database,
     list_directory,
@@ -65,6 +103,11 @@ use commands::{
     move_book_to_group,
     read_file_bytes,
     rename_book,
+    update_book_metadata,
+    batch_update_books,
+    get_book_tags,
+    get_all_tags,
+    refresh_book_availability,
     reorder_group_books,
     reorder_groups,
     reorder_recent_books,
@@ -78,12 +121,17 @@ use commands::{
     scan_pdf_files,
     unmark_book_finished,
     update_book_progress,
+    get_progress_history,
+    update_reading_position,
+    get_reading_position,
     update_book_reading_mode,
     update_book_theme,
     update_book_total_pages,
     update_book_font_size,
     update_book_hide_divider,
     update_book_toc_sort,
+    update_book_series,
+    update_bookmark,
     update_books_last_read_time,
     update_group,
     read_file_base64,
@@ -91,13 +139,18 @@ use commands::{
     get_file_stats,
     fs_quick_fingerprint
 };
+use comic_commands::*;
 use epub_commands::*;
+use footnote_commands::get_footnote;
+use location_commands::resolve_location;
 use html_commands::*;
 use markdown_commands::*;
 use pdf_commands::*;
-use txt_commands::{txt_load_document, txt_load_metadata, txt_load_chapter, txt_clear_metadata_cache, txt_get_cache_stats};
-use tts_commands::tts_get_segments;
+use prefetch_commands::prefetch_chapters;
+use txt_commands::{txt_load_document, txt_load_metadata, txt_load_chapter, txt_clear_metadata_cache, txt_get_cache_stats, txt_detect_encodings, txt_diagnose_toc, txt_convert_for_vertical, txt_get_reading_estimate, txt_repair_text, txt_locate};
+use tts_commands::{get_sentences, tts_get_segments};
 use mobi_commands::*;
+use resource_protocol::{get_book_resource, handle_resource_request, RESOURCE_SCHEME};
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
 use sqlx::SqlitePool;
 use std::str::FromStr;
@@ -303,12 +356,14 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_native_tts::init())
+        .register_asynchronous_uri_scheme_protocol(RESOURCE_SCHEME, handle_resource_request)
         .setup(|app| {
             app.manage(Arc::new(ManagedTtsSessionState::new()));
             // 设置数据库连接
             tauri::async_runtime::block_on(async {
                 let app_data_dir = app.path().app_data_dir().unwrap();
                 std::fs::create_dir_all(&app_data_dir).unwrap();
+                commands::log::init_log_dir(&app_data_dir);
                 let db_path = app_data_dir.join("goread.db");
                 // sqlx 对 SQLite 推荐使用 sqlite:// 前缀，并使用正斜杠路径格式
                 let db_path_str = db_path.to_string_lossy().replace('\\', "/");
@@ -319,11 +374,12 @@ pub fn run() {
                     .create_if_missing(true);
                 let pool = SqlitePool::connect_with(opts).await.unwrap();
 
+                let pdf_config = load_pdf_runtime_config(&pool).await;
                 app.manage(Arc::new(Mutex::new(pool)));
                 app.manage(Arc::new(AtomicBool::new(false)));
 
-                // 初始化PDF管理器
-                app.manage(init_pdf_manager());
+                // 初始化PDF管理器（缓存上限与渲染并发使用持久化的运行时配置）
+                app.manage(init_pdf_manager(pdf_config).await);
 
                 #[cfg(target_os = "android")]
                 {
@@ -376,14 +432,21 @@ pub fn run() {
             init_database,
             add_book,
             get_all_books,
+            get_finished_books,
+            get_unfinished_books,
             get_recent_books,
+            get_recently_opened_books,
             update_book_progress,
+            get_progress_history,
+            update_reading_position,
+            get_reading_position,
             update_book_reading_mode,
             update_book_theme,
             update_book_total_pages,
             update_book_font_size,
             update_book_hide_divider,
             update_book_toc_sort,
+            update_book_series,
             mark_book_opened,
             clear_recent_read_record,
             delete_book,
@@ -391,20 +454,35 @@ pub fn run() {
             reorder_recent_books,
             reset_all_book_themes,
             rename_book,
+            update_book_metadata,
+            batch_update_books,
+            get_book_tags,
+            get_all_tags,
+            refresh_book_availability,
             add_group,
             get_all_groups,
             update_group,
             delete_group,
             get_books_by_group,
+            get_books_by_series,
+            get_books_by_language,
+            get_library_languages,
+            search_library,
             move_book_to_group,
             reorder_group_books,
             reorder_groups,
-            update_group,
             add_bookmark,
+            get_txt_bookmark_position,
             get_bookmarks,
+            get_bookmark_thumbnail,
+            update_bookmark,
             delete_bookmark,
+            export_bookmarks,
+            import_bookmarks,
             scan_pdf_files,
             scan_book_files,
+            get_default_scan_excludes,
+            invalidate_scan_cache,
             cancel_scan,
             list_directory,
             list_directory_supported,
@@ -417,27 +495,66 @@ pub fn run() {
             batch_read_files,
             batch_import_books,
             batch_get_pdf_info,
+            resolve_book_title,
+            import_from_archive,
+            save_theme,
+            get_themes,
+            delete_theme,
+            set_active_theme,
+            create_virtual_book,
+            virtual_book_load_metadata,
+            virtual_book_load_chapter,
+            opds_browse,
+            opds_download,
             frontend_log,
+            get_logs,
+            export_logs,
             read_file_base64,
             read_file_chunked,
             get_file_stats,
             fs_quick_fingerprint,
             // PDF相关命令
             pdf_load_document,
+            pdf_load_remote,
             pdf_render_page,
+            pdf_retry_page,
             pdf_render_page_to_file,
+            pdf_render_page_progressive,
+            pdf_cancel_progressive_render,
+            pdf_render_page_adaptive,
             pdf_render_page_tile,
+            pdf_render_tiles,
             pdf_render_page_base64,
+            pdf_cleanup_temp_files,
+            get_temp_usage,
+            clear_temp_renders,
             pdf_get_page_text,
+            pdf_extract_reflow_text,
             pdf_search_text,
             pdf_get_document_info,
+            pdf_get_page_info,
             pdf_get_outline,
+            pdf_get_current_chapter,
+            pdf_get_form_fields,
+            pdf_get_char_boxes,
+            pdf_ocr_page,
+            pdf_get_annotations,
+            pdf_list_attachments,
+            pdf_extract_attachment,
+            pdf_record_navigation,
             pdf_preload_pages,
+            pdf_ensure_window_rendered,
             pdf_clear_cache,
+            pdf_clear_page_cache,
             pdf_close_document,
+            pdf_invalidate_document,
             pdf_get_cache_stats,
             pdf_set_cache_expiry,
             pdf_set_cache_max_size,
+            pdf_configure,
+            pdf_set_output_format,
+            pdf_set_render_flags,
+            pdf_set_max_render_pixels,
             pdf_warmup_cache,
             pdf_get_performance_metrics,
             pdf_get_performance_report,
@@ -445,6 +562,9 @@ pub fn run() {
             pdf_render_pages_parallel,
             pdf_render_page_range_parallel,
             pdf_render_pages_with_threads,
+            pdf_render_thumbnails,
+            pdf_render_contact_sheet,
+            pdf_export_text,
             exit_app,
             // Markdown commands
             tts_managed_session_start,
@@ -460,12 +580,24 @@ pub fn run() {
             markdown_search_text,
             // HTML commands
             html_load_document,
+            html_load_document_with_resources,
+            set_html_sanitize,
+            comic_load_document,
+            comic_render_page,
+            comic_get_cover,
             // TXT commands
             txt_load_document,
             txt_load_metadata,
             txt_load_chapter,
             txt_clear_metadata_cache,
             txt_get_cache_stats,
+            txt_detect_encodings,
+            txt_diagnose_toc,
+            txt_convert_for_vertical,
+            txt_get_reading_estimate,
+            txt_repair_text,
+            txt_locate,
+            prefetch_chapters,
             // Status bar control commands
             show_status_bar,
             hide_status_bar,
@@ -485,6 +617,7 @@ pub fn run() {
             native_tts_session_set_end_of_book,
             // TTS 统一取片接口
             tts_get_segments,
+            get_sentences,
             // Stats commands
             save_reading_session,
             get_stats_summary,
@@ -501,6 +634,8 @@ pub fn run() {
             // EPUB cache commands
             epub_save_section,
             epub_load_section,
+            epub_paginate_section,
+            epub_locate_text_offset,
             epub_save_resource,
             epub_load_resource,
             epub_set_cache_expiry,
@@ -521,9 +656,15 @@ pub fn run() {
             rebuild_epub_cover,
             rebuild_mobi_cover,
             clear_book_cover,
+            cleanup_orphan_covers,
+            generate_group_cover,
+            generate_text_cover,
+            make_cover_thumbnail,
             // MOBI cache commands
             mobi_save_section,
             mobi_load_section,
+            mobi_paginate_section,
+            mobi_locate_text_offset,
             mobi_save_resource,
             mobi_load_resource,
             mobi_set_cache_expiry,
@@ -534,8 +675,12 @@ pub fn run() {
             mobi_save_metadata,
             mobi_load_metadata,
             mobi_prepare_book,
+            get_mobi_resource,
             epub_inspect,
-            epub_prepare_book
+            epub_prepare_book,
+            get_book_resource,
+            get_footnote,
+            resolve_location
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
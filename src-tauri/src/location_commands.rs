@@ -0,0 +1,21 @@
+//! 书签/目录位置解析
+//! 书签保存的位置字符串与目录 location 格式一致，跳转时由后端统一换算为章节索引与章内锚点
+
+use crate::formats::common::get_extension;
+use crate::formats::epub::read_spine;
+use crate::formats::location::{self, ResolvedLocation};
+
+/// 把 EPUB/MOBI 的位置字符串解析为 `{ section_index, anchor_id?, char_offset? }`
+/// 无法精确解析时返回最接近的章节起始（`exact` 为 false）
+#[tauri::command]
+pub async fn resolve_location(file_path: String, location: String) -> Result<ResolvedLocation, String> {
+    let spine = match get_extension(&file_path).as_deref() {
+        Some("epub") => tokio::task::spawn_blocking(move || read_spine(&file_path))
+            .await
+            .map_err(|e| format!("读取 spine 失败: {}", e))??,
+        // MOBI 的位置本身就是 section 索引，无需 spine
+        Some("mobi") | Some("azw3") | Some("azw") => Vec::new(),
+        _ => return Err("不支持的格式".to_string()),
+    };
+    Ok(location::resolve_location(&location, &spine))
+}
@@ -1,6 +1,6 @@
 //! Markdown 相关的 Tauri 命令
 
-use crate::formats::markdown::{MarkdownEngine, MarkdownSearchResult};
+use crate::formats::markdown::{MarkdownContentAnalysis, MarkdownEngine, MarkdownSearchResult};
 use crate::formats::{TocItem, BookMetadata};
 use serde::{Deserialize, Serialize};
 
@@ -17,6 +17,9 @@ pub struct MarkdownLoadResult {
     pub toc: Vec<TocItem>,
     /// 文档元数据
     pub metadata: BookMetadata,
+    /// 内容结构分析，前端据此按需加载代码高亮、公式、Mermaid 等依赖
+    #[serde(default)]
+    pub analysis: MarkdownContentAnalysis,
 }
 
 /// 加载 Markdown 文档
@@ -31,6 +34,7 @@ pub async fn markdown_load_document(file_path: String) -> Result<MarkdownLoadRes
         title: engine.get_title(),
         toc: engine.get_toc(),
         metadata: engine.get_metadata(),
+        analysis: engine.analyze_content(),
     })
 }
 
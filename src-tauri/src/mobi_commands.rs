@@ -1,6 +1,9 @@
 //! MOBI 相关的 Tauri 命令
 use crate::formats::mobi::cache::{MobiCacheManager, BookInfo, TocItem, MetadataCacheEntry, SectionCacheData};
-use crate::formats::mobi::engine::{prepare_book, MobiPreparedBook};
+use crate::formats::mobi::engine::{prepare_book_with, MobiPreparedBook, PreparedResource};
+use crate::formats::pagination::{paginate_html, SectionPagination, TypographyOptions};
+use crate::formats::text_offset::{locate_text_offset, SectionTextAnchor};
+use crate::resource_protocol::rewrite_resource_placeholders;
 use serde::Serialize;
 use serde_json::Value;
 use std::sync::Arc;
@@ -38,20 +41,83 @@ pub async fn mobi_save_section(
 }
 
 /// 从磁盘加载章节缓存（返回完整的 HTML、样式和资源引用）
+/// `resource_urls` 为 true 时将资源占位符改写为 `goread-res` 协议地址，由 WebView 按需加载
 #[tauri::command]
 pub async fn mobi_load_section(
     book_id: String,
     section_index: u32,
+    resource_urls: Option<bool>,
     state: State<'_, MobiCacheState>,
 ) -> Result<Option<SectionCacheData>, String> {
     let manager = state.lock().await;
-    manager.load_section(&book_id, section_index).await.map_err(|e| {
+    let section = manager.load_section(&book_id, section_index).await.map_err(|e| {
         eprintln!(
             "[MOBI缓存] 加载章节失败: book_id={}, section_index={}, error={}",
             book_id, section_index, e
         );
         e
-    })
+    })?;
+
+    if !resource_urls.unwrap_or(false) {
+        return Ok(section);
+    }
+    Ok(section.map(|mut data| {
+        data.html = rewrite_resource_placeholders(&data.html, &book_id);
+        data.styles = data
+            .styles
+            .iter()
+            .map(|css| rewrite_resource_placeholders(css, &book_id))
+            .collect();
+        data
+    }))
+}
+
+/// 按排版参数对已缓存章节分页（结果按字号/行距/视口尺寸分别缓存）
+/// 章节尚未缓存时返回 None
+#[tauri::command]
+pub async fn mobi_paginate_section(
+    book_id: String,
+    section_index: u32,
+    typography: TypographyOptions,
+    state: State<'_, MobiCacheState>,
+) -> Result<Option<SectionPagination>, String> {
+    typography.validate().map_err(|e| e.to_string())?;
+    let typography_key = typography.cache_key();
+
+    let manager = state.lock().await;
+    if let Some(cached) = manager
+        .load_section_pages(&book_id, section_index, &typography_key)
+        .await
+    {
+        return Ok(Some(cached));
+    }
+
+    let Some(section) = manager.load_section(&book_id, section_index).await? else {
+        return Ok(None);
+    };
+
+    let pagination = paginate_html(section_index, &section.html, &typography);
+    if let Err(e) = manager.save_section_pages(&book_id, &pagination).await {
+        eprintln!(
+            "[MOBI缓存] 保存分页结果失败: book_id={}, section_index={}, error={}",
+            book_id, section_index, e
+        );
+    }
+    Ok(Some(pagination))
+}
+
+/// 把章节纯文本偏移换算为 HTML 中的大致位置（用于恢复 `section` 阅读进度）
+/// 章节尚未缓存时返回 None
+#[tauri::command]
+pub async fn mobi_locate_text_offset(
+    book_id: String,
+    section_index: u32,
+    char_offset: usize,
+    state: State<'_, MobiCacheState>,
+) -> Result<Option<SectionTextAnchor>, String> {
+    let manager = state.lock().await;
+    let section = manager.load_section(&book_id, section_index).await?;
+    Ok(section.map(|section| locate_text_offset(&section.html, char_offset)))
 }
 
 /// 保存资源缓存到磁盘
@@ -96,6 +162,21 @@ pub async fn mobi_load_resource(
     })
 }
 
+/// 按需获取单个 MOBI 资源的原始字节（不经 JSON 数组序列化），资源不存在时返回错误
+/// 路径取自 `mobi_prepare_book` 返回的资源清单或章节 HTML 中的资源占位符
+#[tauri::command]
+pub async fn get_mobi_resource(
+    book_id: String,
+    path: String,
+    state: State<'_, MobiCacheState>,
+) -> Result<tauri::ipc::Response, String> {
+    let manager = state.lock().await;
+    match manager.load_resource(&book_id, &path).await? {
+        Some((data, _)) => Ok(tauri::ipc::Response::new(data)),
+        None => Err(format!("资源不存在: {}", path)),
+    }
+}
+
 /// 设置 MOBI 缓存有效期（天），0 表示不限
 #[tauri::command]
 pub async fn mobi_set_cache_expiry(
@@ -202,24 +283,41 @@ pub struct MobiPrepareResult {
     pub book_info: BookInfo,
     pub toc: Vec<TocItem>,
     pub section_count: u32,
+    /// 资源清单（路径、MIME、大小），字节通过 `get_mobi_resource` 或资源协议按需获取
+    pub resources: Vec<PreparedResource>,
 }
 
 /// 一次性解析 MOBI 文件并将章节/资源/元数据写入磁盘缓存
+/// 图片资源在解析线程中提取后立即落盘，不在内存中累积
 #[tauri::command]
 pub async fn mobi_prepare_book(
     file_path: String,
     book_id: String,
     state: State<'_, MobiCacheState>,
 ) -> Result<MobiPrepareResult, String> {
-    let prepared: MobiPreparedBook = task::spawn_blocking(move || prepare_book(&file_path))
-        .await
-        .map_err(|e| format!("MOBI 解析任务失败: {}", e))??;
+    // 清理旧缓存，资源在解析过程中直接写入
+    state.lock().await.clear_book_cache(&book_id).await
+        .map_err(|e| format!("清理旧缓存失败: {}", e))?;
 
-    let manager = state.lock().await;
+    let resource_book_id = book_id.clone();
+    let prepared = task::spawn_blocking(move || {
+        prepare_book_with(&file_path, |res, data| {
+            MobiCacheManager::write_resource(&resource_book_id, &res.path, data, &res.mime_type)
+                .map_err(|e| format!("保存资源缓存失败: {}", e))
+        })
+    })
+    .await
+    .map_err(|e| format!("MOBI 解析任务失败: {}", e))?;
 
-    // 清理旧缓存
-    manager.clear_book_cache(&book_id).await
-        .map_err(|e| format!("清理旧缓存失败: {}", e))?;
+    let manager = state.lock().await;
+    let prepared: MobiPreparedBook = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            // 解析中途失败时清掉已写入的部分资源
+            let _ = manager.clear_book_cache(&book_id).await;
+            return Err(e);
+        }
+    };
 
     // 保存所有章节
     for section in &prepared.sections {
@@ -232,11 +330,9 @@ pub async fn mobi_prepare_book(
         ).await.map_err(|e| format!("保存章节缓存失败: {}", e))?;
     }
 
-    // 保存所有资源
-    for res in &prepared.resources {
-        manager.save_resource(&book_id, &res.path, &res.data, &res.mime_type)
-            .await.map_err(|e| format!("保存资源缓存失败: {}", e))?;
-    }
+    // 保存脚注映射
+    manager.save_footnotes(&book_id, &prepared.footnotes)
+        .await.map_err(|e| format!("保存脚注缓存失败: {}", e))?;
 
     // 保存元数据
     manager.save_metadata(
@@ -253,5 +349,6 @@ pub async fn mobi_prepare_book(
         book_info: prepared.book_info,
         toc: prepared.toc,
         section_count: prepared.section_count,
+        resources: prepared.resources,
     })
 }
@@ -22,6 +22,58 @@ pub struct Book {
     pub precise_progress: Option<f64>,
     pub hide_divider: Option<bool>,
     pub toc_sort: Option<i64>,
+    pub series: Option<String>,      // 所属系列名称
+    pub series_index: Option<f32>,   // 系列序号，用于同系列内排序
+    pub last_progress_time: Option<i64>, // 最近一次进度推进的时间戳，用于「在读」排序
+    pub language: Option<String>,    // 书籍语言（BCP 47 标签，如 zh-CN、en），未知时为空
+    pub author: Option<String>,      // 作者，由用户手动编辑，未知时为空
+    pub file_status: Option<String>, // 文件可用状态：available / missing / downloading，见 BookFileStatus
+    /// 统一阅读位置（ReadingPosition 的 JSON），旧版备份中没有该字段
+    #[sqlx(default)]
+    #[serde(default)]
+    pub reading_position: Option<String>,
+    /// 书籍标签，存于 book_tags 表，书籍列表、批量更新结果与备份中填充，其余查询为空
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 阅读进度百分比（0-100），由后端根据 status/current_page/total_pages 计算，不落库
+    #[sqlx(default)]
+    #[serde(default)]
+    pub progress_percent: f64,
+    /// TXT 是否做章节规整，升级前已导入的书籍为 false；旧版备份中没有该字段，恢复时按 false 处理
+    #[sqlx(default)]
+    #[serde(default)]
+    pub txt_chapter_normalize: Option<bool>,
+    /// 所属分组名称，仅在导入结果中填充，不落库
+    #[sqlx(default)]
+    #[serde(default)]
+    pub group_name: Option<String>,
+}
+
+impl Book {
+    /// 是否已读完：status=1（手动标记，或翻到末页时自动标记；用户取消后即为未读完）
+    pub fn is_finished(&self) -> bool {
+        self.status == Some(1)
+    }
+
+    /// 计算阅读进度百分比
+    pub fn compute_progress_percent(&self) -> f64 {
+        if self.is_finished() {
+            return 100.0;
+        }
+        if self.total_pages <= 1 {
+            return 0.0;
+        }
+        let page = self.precise_progress.unwrap_or(self.current_page as f64);
+        let percent = page / self.total_pages as f64 * 100.0;
+        (percent.clamp(0.0, 100.0) * 10.0).round() / 10.0
+    }
+
+    /// 填充进度百分比字段
+    pub fn with_progress_percent(mut self) -> Self {
+        self.progress_percent = self.compute_progress_percent();
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -31,6 +83,138 @@ pub struct Group {
     pub book_count: u32,
     pub created_at: Option<i64>,
     pub sort_order: Option<i64>,
+    pub color: Option<String>, // 分组卡片颜色（#RRGGBB），为空时使用默认配色
+    pub icon: Option<String>,  // 分组图标（图标名或 emoji）
+    pub cover_image: Option<String>, // 拼贴封面相对路径，组内变化后清空并惰性重建
+    #[serde(skip_serializing, default)]
+    pub cover_stale: bool,
+}
+
+/// 批量更新书籍的字段，未提供的字段保持不变
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchBookUpdate {
+    pub group_id: Option<i64>,         // 移动到的分组
+    pub add_tags: Option<Vec<String>>, // 添加的标签，已有的标签不重复添加
+    pub remove_tags: Option<Vec<String>>, // 移除的标签，同时出现在 add_tags 中时以移除为准
+    pub finished: Option<bool>,        // 标记或取消已读完
+}
+
+/// 手动编辑书籍信息，未提供的字段保持不变
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BookMetadataUpdate {
+    pub title: Option<String>,
+    pub author: Option<String>,      // 传空字符串清空作者
+    pub cover_data: Option<String>,  // Base64 / data URL 或封面相对路径
+}
+
+/// 批量更新结果：更新后的书籍列表，以及不存在而跳过的 id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchUpdateResult {
+    pub books: Vec<Book>,
+    pub skipped: Vec<i64>,
+}
+
+/// 书籍文件的可用状态：外置存储未挂载或云端文件尚未下载时文件暂时不可访问，
+/// 前端据此灰显书籍并提示，而不是在打开时报错
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BookFileStatus {
+    Available,
+    Missing,
+    /// 云端占位文件存在、正文尚未下载到本地（如 iCloud 的 `.文件名.icloud`）
+    Downloading,
+}
+
+impl BookFileStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BookFileStatus::Available => "available",
+            BookFileStatus::Missing => "missing",
+            BookFileStatus::Downloading => "downloading",
+        }
+    }
+}
+
+/// 书库中某种语言的书籍数量（按主语言标签汇总，language 为空表示未知语言）
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct LanguageCount {
+    pub language: Option<String>,
+    pub count: i64,
+}
+
+/// 书库搜索可匹配的字段；书籍没有独立的标签，分组名即书架上的标签
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchField {
+    Title,
+    Author,
+    FilePath,
+    Series,
+    #[serde(alias = "tags")]
+    Group,
+}
+
+/// 搜索命中片段：start/end 为 value 中的字符区间（按 Unicode 字符计，左闭右开）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHighlight {
+    pub field: SearchField,
+    pub value: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// 书库搜索结果：书籍字段平铺，附带所在分组名和命中高亮
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibrarySearchResult {
+    #[serde(flatten)]
+    pub book: Book,
+    pub group_name: Option<String>,
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// 统一的阅读位置，按 `kind` 区分：页码（PDF 等分页格式）、章节内字符偏移（TXT）、EPUB CFI、
+/// 流式章节内纯文本偏移（EPUB/MOBI）
+/// 存于 books.reading_position（JSON），旧的 current_page 作为派生值继续维护
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReadingPosition {
+    /// 页码从 1 开始，可带小数表示页内滚动位置
+    Page { page: f64 },
+    /// 章节从 0 开始，offset 为章节内字符偏移
+    Char { chapter: u32, offset: u64 },
+    Cfi { cfi: String },
+    /// 流式书籍（EPUB/MOBI）的章节位置：章节从 0 开始，char_offset_in_text 为章节纯文本中的字符偏移，
+    /// 与字号、视口宽度无关
+    Section { section: u32, char_offset_in_text: u64 },
+}
+
+impl ReadingPosition {
+    /// 由旧进度字段推导阅读位置：TXT 的 current_page 为从 1 开始的章节号，其余格式按页码
+    /// （EPUB 旧数据没有 CFI，只能按页码保存）
+    pub fn from_legacy(file_path: &str, current_page: i64, precise_progress: Option<f64>) -> Self {
+        if file_path.to_lowercase().ends_with(".txt") {
+            ReadingPosition::Char {
+                chapter: (current_page - 1).max(0) as u32,
+                offset: 0,
+            }
+        } else {
+            ReadingPosition::Page {
+                page: precise_progress.unwrap_or(current_page as f64),
+            }
+        }
+    }
+
+    /// 对应的兼容页码（写入 current_page/precise_progress），CFI 无法换算时返回 None
+    pub fn legacy_page(&self) -> Option<f64> {
+        match self {
+            ReadingPosition::Page { page } => Some(*page),
+            ReadingPosition::Char { chapter, .. } => Some(*chapter as f64 + 1.0),
+            ReadingPosition::Section { section, .. } => Some(*section as f64 + 1.0),
+            ReadingPosition::Cfi { .. } => None,
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -50,7 +234,20 @@ pub struct Bookmark {
     pub book_id: i64,
     pub page_number: u32,
     pub title: String,
+    /// 用户备注
+    pub note: Option<String>,
     pub created_at: Option<i64>,
+    /// TXT 书签的全文字符偏移，换字号或重新分页后仍能定位到同一段（其他格式为空）
+    #[sqlx(default)]
+    pub char_offset: Option<i64>,
+    /// 书签所在章节标题（由目录映射得到，不入库）
+    #[sqlx(default)]
+    #[serde(default)]
+    pub chapter_title: Option<String>,
+    /// PDF 书签所在页的缩略图（data URL，按需渲染，不入库）
+    #[sqlx(default)]
+    #[serde(default)]
+    pub thumbnail: Option<String>,
 }
 
 #[allow(dead_code)]
@@ -84,6 +281,13 @@ pub struct ReadingSession {
     pub created_at: Option<i64>,
 }
 
+/// 进度历史中的一个点
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct ProgressHistoryPoint {
+    pub timestamp: i64,      // 记录时间戳（秒）
+    pub position_value: f64, // 当时的页码进度
+}
+
 /// 每日统计数据
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct DailyStats {
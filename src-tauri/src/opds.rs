@@ -0,0 +1,483 @@
+//! OPDS 1.2 书源
+//! 拉取 OPDS Atom feed 并解析为条目列表（标题、作者、封面、下载链接），相对链接按 feed 地址解析为绝对地址；
+//! 导航条目指向下一级 feed，分页通过 `rel="next"` 链接继续拉取。下载的书籍写入本地目录后走常规导入流程。
+//! 只支持 Basic Auth 认证
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use crate::formats::epub::nav::extract_attr;
+use crate::formats::epub::percent_decode_path;
+use crate::formats::BookFormat;
+use crate::pdf::remote::validate_url;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 进度回调的最小间隔字节数
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// 单本书下载的最大字节数（漫画压缩包可能较大），超过视为异常响应
+const MAX_DOWNLOAD_BYTES: u64 = 1024 * 1024 * 1024;
+
+const FEED_ACCEPT: &str = "application/atom+xml;profile=opds-catalog, application/atom+xml, application/xml;q=0.9, */*;q=0.8";
+
+static ENTRY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<(?:atom:)?entry\b[^>]*>(.*?)</(?:atom:)?entry\s*>").unwrap());
+static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?is)<(?:atom:)?link\b([^>]*?)/?>").unwrap());
+static AUTHOR_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?is)<(?:atom:)?author\b[^>]*>.*?<(?:atom:)?name\b[^>]*>(.*?)</(?:atom:)?name\s*>").unwrap()
+});
+static CDATA_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<!\[CDATA\[(.*?)\]\]>").unwrap());
+static TAG_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+static NUMERIC_ENTITY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"&#([xX][0-9a-fA-F]+|[0-9]+);").unwrap());
+static FILENAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)filename\*?\s*=\s*(?:UTF-8'[^']*')?"?([^";]+)"?"#).unwrap());
+
+/// Basic Auth 凭据
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpdsAuth {
+    pub username: String,
+    pub password: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpdsLink {
+    pub href: String,
+    pub rel: Option<String>,
+    pub mime_type: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpdsEntry {
+    pub id: Option<String>,
+    pub title: String,
+    pub authors: Vec<String>,
+    pub summary: Option<String>,
+    pub language: Option<String>,
+    pub cover_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    /// 下载链接（`rel` 为 `http://opds-spec.org/acquisition*`），可能有多种格式
+    pub acquisitions: Vec<OpdsLink>,
+    /// 导航条目指向的下一级 feed
+    pub navigation_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OpdsFeed {
+    /// 最后一次拉取的 feed 地址（重定向后）
+    pub url: String,
+    pub title: Option<String>,
+    pub entries: Vec<OpdsEntry>,
+    pub next_url: Option<String>,
+    pub previous_url: Option<String>,
+    pub search_url: Option<String>,
+    pub start_url: Option<String>,
+}
+
+/// 解码 XML 字符实体
+fn decode_xml_text(text: &str) -> String {
+    let text = NUMERIC_ENTITY_RE.replace_all(text, |caps: &regex::Captures| {
+        let value = &caps[1];
+        let code = match value.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => value.parse().ok(),
+        };
+        code.and_then(char::from_u32).map(String::from).unwrap_or_default()
+    });
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// 元素的纯文本内容：展开 CDATA、去掉内嵌标签（`type="xhtml"` 的 content），
+/// 再解码实体（`type="html"` 的 content 解码后仍是 HTML，再去一次标签）
+fn element_text(block: &str, tag: &str) -> Option<String> {
+    let pattern = format!(r"(?is)<(?:atom:)?{tag}\b[^>]*>(.*?)</(?:atom:)?{tag}\s*>", tag = regex::escape(tag));
+    let raw = Regex::new(&pattern).ok()?.captures(block)?.get(1)?.as_str().to_string();
+    let raw = CDATA_RE.replace_all(&raw, "$1");
+    let text = decode_xml_text(&TAG_RE.replace_all(&raw, ""));
+    let text = TAG_RE.replace_all(&text, "");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+fn resolve_url(base: Option<&reqwest::Url>, href: &str) -> String {
+    let href = decode_xml_text(href.trim());
+    base.and_then(|base| base.join(&href).ok())
+        .map(|url| url.to_string())
+        .unwrap_or(href)
+}
+
+fn parse_links(block: &str, base: Option<&reqwest::Url>) -> Vec<OpdsLink> {
+    LINK_RE
+        .captures_iter(block)
+        .filter_map(|caps| {
+            let attrs = &caps[1];
+            let href = extract_attr(attrs, "href").filter(|h| !h.trim().is_empty())?;
+            Some(OpdsLink {
+                href: resolve_url(base, &href),
+                rel: extract_attr(attrs, "rel").map(|r| decode_xml_text(&r)),
+                mime_type: extract_attr(attrs, "type").map(|t| decode_xml_text(&t)),
+                title: extract_attr(attrs, "title").map(|t| decode_xml_text(&t)),
+            })
+        })
+        .collect()
+}
+
+fn is_feed_type(mime_type: Option<&str>) -> bool {
+    mime_type.is_some_and(|t| t.to_ascii_lowercase().starts_with("application/atom+xml"))
+}
+
+fn parse_entry(block: &str, base: Option<&reqwest::Url>) -> OpdsEntry {
+    let links = parse_links(block, base);
+    let find_rel = |rels: &[&str]| {
+        links
+            .iter()
+            .find(|l| l.rel.as_deref().is_some_and(|r| rels.contains(&r)))
+            .map(|l| l.href.clone())
+    };
+    let cover_url = find_rel(&["http://opds-spec.org/image", "http://opds-spec.org/cover"]);
+    let thumbnail_url = find_rel(&["http://opds-spec.org/image/thumbnail", "http://opds-spec.org/thumbnail"]);
+    let acquisitions: Vec<OpdsLink> = links
+        .iter()
+        .filter(|l| l.rel.as_deref().is_some_and(|r| r.starts_with("http://opds-spec.org/acquisition")))
+        .cloned()
+        .collect();
+    // 没有下载链接的条目是导航条目，取指向 Atom feed 的链接
+    let navigation_url = if acquisitions.is_empty() {
+        links
+            .iter()
+            .find(|l| is_feed_type(l.mime_type.as_deref()) && l.rel.as_deref() != Some("alternate"))
+            .map(|l| l.href.clone())
+    } else {
+        None
+    };
+
+    OpdsEntry {
+        id: element_text(block, "id"),
+        title: element_text(block, "title").unwrap_or_default(),
+        authors: AUTHOR_RE
+            .captures_iter(block)
+            .map(|caps| decode_xml_text(TAG_RE.replace_all(&caps[1], "").trim()))
+            .filter(|name| !name.is_empty())
+            .collect(),
+        summary: element_text(block, "summary").or_else(|| element_text(block, "content")),
+        language: element_text(block, "dc:language"),
+        cover_url: cover_url.clone().or_else(|| thumbnail_url.clone()),
+        thumbnail_url: thumbnail_url.or(cover_url),
+        acquisitions,
+        navigation_url,
+    }
+}
+
+/// 解析 OPDS Atom feed；`base_url` 为 feed 地址，用于解析相对链接
+pub fn parse_feed(xml: &str, base_url: &str) -> OpdsFeed {
+    let base = reqwest::Url::parse(base_url).ok();
+    let entries: Vec<OpdsEntry> = ENTRY_RE
+        .captures_iter(xml)
+        .map(|caps| parse_entry(&caps[1], base.as_ref()))
+        .collect();
+
+    // feed 自身的标题与链接只在条目之外查找
+    let header = ENTRY_RE.replace_all(xml, "");
+    let links = parse_links(&header, base.as_ref());
+    let find_rel = |rel: &str| links.iter().find(|l| l.rel.as_deref() == Some(rel)).map(|l| l.href.clone());
+    // 优先可直接拼接 {searchTerms} 的 Atom 搜索模板，其次 OpenSearch 描述文档
+    let search_url = links
+        .iter()
+        .filter(|l| l.rel.as_deref() == Some("search"))
+        .max_by_key(|l| is_feed_type(l.mime_type.as_deref()))
+        .map(|l| l.href.replace("%7BsearchTerms%7D", "{searchTerms}"));
+
+    OpdsFeed {
+        url: base_url.to_string(),
+        title: element_text(&header, "title"),
+        entries,
+        next_url: find_rel("next"),
+        previous_url: find_rel("previous").or_else(|| find_rel("prev")),
+        search_url,
+        start_url: find_rel("start"),
+    }
+}
+
+fn build_client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .map_err(|e| format!("初始化网络客户端失败: {}", e))
+}
+
+/// 凭据只发往 feed 所在的源（协议 + 主机 + 端口），书源链接到的其他站点拿不到用户名密码
+fn auth_for<'a>(url: &reqwest::Url, auth: Option<&'a OpdsAuth>, auth_origin: Option<&reqwest::Url>) -> Option<&'a OpdsAuth> {
+    auth.filter(|_| auth_origin.is_some_and(|origin| origin.origin() == url.origin()))
+}
+
+/// `auth_origin` 为 feed 地址，只有同源请求才携带 `auth`
+async fn send_get(
+    url: &str,
+    auth: Option<&OpdsAuth>,
+    auth_origin: Option<&reqwest::Url>,
+    accept: &str,
+) -> Result<reqwest::Response, String> {
+    let parsed = validate_url(url)?;
+    let auth = auth_for(&parsed, auth, auth_origin);
+    let mut request = build_client()?.get(parsed).header(reqwest::header::ACCEPT, accept);
+    if let Some(auth) = auth {
+        request = request.basic_auth(&auth.username, auth.password.as_deref());
+    }
+    let response = request.send().await.map_err(|e| format!("请求失败: {}", e))?;
+    match response.status() {
+        reqwest::StatusCode::UNAUTHORIZED => Err("书源需要认证或用户名密码错误 (HTTP 401)".to_string()),
+        status if !status.is_success() => Err(format!("请求失败: HTTP {}", status.as_u16())),
+        _ => Ok(response),
+    }
+}
+
+/// 拉取 feed；`max_pages` 大于 1 时沿 next 链接继续拉取并合并条目，返回的 `next_url` 为最后一页的下一页
+/// 凭据只用于与 `url` 同源的分页请求
+pub async fn fetch_feed(url: &str, auth: Option<&OpdsAuth>, max_pages: u32) -> Result<OpdsFeed, String> {
+    let auth_origin = validate_url(url)?;
+    let mut feed: Option<OpdsFeed> = None;
+    let mut next = Some(url.to_string());
+    for _ in 0..max_pages.max(1) {
+        let Some(page_url) = next.take() else { break };
+        let response = send_get(&page_url, auth, Some(&auth_origin), FEED_ACCEPT).await?;
+        let final_url = response.url().to_string();
+        let body = response.text().await.map_err(|e| format!("读取响应失败: {}", e))?;
+        let page = parse_feed(&body, &final_url);
+        // 防止 next 指向自身导致死循环
+        next = page.next_url.clone().filter(|n| *n != final_url && *n != page_url);
+        feed = Some(match feed {
+            None => page,
+            Some(mut merged) => {
+                merged.entries.extend(page.entries);
+                merged.next_url = page.next_url;
+                merged.url = page.url;
+                merged
+            }
+        });
+    }
+    let mut feed = feed.ok_or_else(|| "书源未返回内容".to_string())?;
+    feed.next_url = next;
+    println!("[Opds] 拉取 feed: url={}, entries={}", url, feed.entries.len());
+    Ok(feed)
+}
+
+/// 由 MIME 类型推断书籍扩展名
+fn extension_for_mime(mime_type: &str) -> Option<&'static str> {
+    let mime = mime_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    let ext = match mime.as_str() {
+        "application/epub+zip" => "epub",
+        "application/pdf" => "pdf",
+        "application/x-mobipocket-ebook" => "mobi",
+        "application/vnd.amazon.ebook" | "application/x-mobi8-ebook" => "azw3",
+        "application/x-fictionbook+xml" | "application/fb2" => "fb2",
+        "application/x-cbz" | "application/vnd.comicbook+zip" => "cbz",
+        "application/x-cbr" | "application/vnd.comicbook-rar" => "cbr",
+        "text/plain" => "txt",
+        "text/html" | "application/xhtml+xml" => "html",
+        "text/markdown" => "md",
+        _ => return None,
+    };
+    Some(ext)
+}
+
+/// 确定下载文件名：Content-Disposition > URL 路径最后一段；扩展名不是受支持的书籍格式时按 MIME 类型补全
+fn download_file_name(url: &reqwest::Url, content_disposition: Option<&str>, mime_type: Option<&str>) -> Option<String> {
+    let from_header = content_disposition
+        .and_then(|value| FILENAME_RE.captures(value))
+        .map(|caps| percent_decode_path(caps[1].trim()));
+    let from_url = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back().map(percent_decode_path))
+        .filter(|name| !name.is_empty());
+    let name = from_header.or(from_url).unwrap_or_else(|| "book".to_string());
+    // 只保留文件名部分，防止路径穿越
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default().trim().to_string();
+    let name = if name.is_empty() || name.starts_with('.') { format!("book{}", name) } else { name };
+
+    if BookFormat::from_path(&name).is_some() {
+        return Some(name);
+    }
+    mime_type.and_then(extension_for_mime).map(|ext| format!("{}.{}", name, ext))
+}
+
+/// 下载目标对应的 `.part` 临时文件
+fn part_path(target: &Path) -> PathBuf {
+    let mut name = target.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    target.with_file_name(name)
+}
+
+/// 目标目录中不冲突的文件路径：`name.epub`、`name (1).epub`……；正在下载（`.part` 存在）的名字同样跳过
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let is_free = |p: &Path| !p.exists() && !part_path(p).exists();
+    let candidate = dir.join(file_name);
+    if is_free(&candidate) {
+        return candidate;
+    }
+    let path = Path::new(file_name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let ext = path.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+    (1..)
+        .map(|i| dir.join(format!("{} ({}).{}", stem, i, ext)))
+        .find(|p| is_free(p))
+        .unwrap_or(candidate)
+}
+
+/// 下载书籍到 `dest_dir`，返回本地文件路径；先写入 `.part` 临时文件，完成后改名
+/// 凭据只在下载地址与 `feed_url` 同源时发送；超过 `MAX_DOWNLOAD_BYTES` 的响应中止下载
+/// `on_progress(已下载字节, 总字节)` 按间隔回调，总长未知时为 None
+pub async fn download_book<F>(
+    url: &str,
+    dest_dir: &Path,
+    auth: Option<&OpdsAuth>,
+    feed_url: Option<&str>,
+    mut on_progress: F,
+) -> Result<PathBuf, String>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let auth_origin = feed_url.and_then(|u| validate_url(u).ok());
+    let mut response = send_get(url, auth, auth_origin.as_ref(), "*/*").await?;
+    let header = |name: reqwest::header::HeaderName| {
+        response.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let content_type = header(reqwest::header::CONTENT_TYPE);
+    let disposition = header(reqwest::header::CONTENT_DISPOSITION);
+    let file_name = download_file_name(response.url(), disposition.as_deref(), content_type.as_deref())
+        .ok_or_else(|| format!("不支持的书籍格式: {}", content_type.as_deref().unwrap_or("未知")))?;
+
+    let total = response.content_length();
+    if total.is_some_and(|total| total > MAX_DOWNLOAD_BYTES) {
+        return Err(format!("文件过大: {} 字节", total.unwrap_or_default()));
+    }
+
+    tokio::fs::create_dir_all(dest_dir)
+        .await
+        .map_err(|e| format!("创建下载目录失败: {}", e))?;
+    let target = unique_path(dest_dir, &file_name);
+    let part = part_path(&target);
+    // create_new 占住临时文件名，同名的并发下载不会写进同一个 `.part`
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&part)
+        .await
+        .map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+    let mut downloaded = 0u64;
+    let mut last_reported = 0u64;
+    on_progress(0, total);
+    let result: Result<(), String> = async {
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("下载中断: {}", e))? {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("写入临时文件失败: {}", e))?;
+            downloaded += chunk.len() as u64;
+            if downloaded > MAX_DOWNLOAD_BYTES {
+                return Err(format!("文件过大: 超过 {} 字节", MAX_DOWNLOAD_BYTES));
+            }
+            if downloaded - last_reported >= PROGRESS_STEP_BYTES {
+                last_reported = downloaded;
+                on_progress(downloaded, total);
+            }
+        }
+        file.flush().await.map_err(|e| format!("写入临时文件失败: {}", e))
+    }
+    .await;
+    drop(file);
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(e);
+    }
+    if total.is_some_and(|total| total != downloaded) {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(format!("下载不完整: {}/{} 字节", downloaded, total.unwrap_or_default()));
+    }
+    tokio::fs::rename(&part, &target)
+        .await
+        .map_err(|e| format!("保存下载文件失败: {}", e))?;
+    on_progress(downloaded, Some(downloaded));
+
+    println!("[Opds] 下载完成: url={}, file={}, size={}", url, target.display(), downloaded);
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_feed() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xmlns:dc="http://purl.org/dc/terms/">
+  <title>新书 &amp; 推荐</title>
+  <link rel="next" href="?page=2" type="application/atom+xml;profile=opds-catalog;kind=acquisition"/>
+  <link rel="search" href="/opds/search/{searchTerms}" type="application/atom+xml"/>
+  <entry>
+    <title>三体</title>
+    <id>urn:uuid:1</id>
+    <author><name>刘慈欣</name></author>
+    <dc:language>zh</dc:language>
+    <content type="html">&lt;p&gt;地球往事&lt;/p&gt;</content>
+    <link rel="http://opds-spec.org/image" href="covers/1.jpg" type="image/jpeg"/>
+    <link rel="http://opds-spec.org/acquisition/open-access" href="/dl/1.epub" type="application/epub+zip"/>
+  </entry>
+  <entry>
+    <title>科幻分类</title>
+    <link rel="subsection" href="category/scifi" type="application/atom+xml;profile=opds-catalog;kind=navigation"/>
+  </entry>
+</feed>"#;
+        let feed = parse_feed(xml, "https://example.com/opds/new");
+        assert_eq!(feed.title.as_deref(), Some("新书 & 推荐"));
+        assert_eq!(feed.next_url.as_deref(), Some("https://example.com/opds/new?page=2"));
+        assert_eq!(feed.search_url.as_deref(), Some("https://example.com/opds/search/{searchTerms}"));
+        assert_eq!(feed.entries.len(), 2);
+
+        let book = &feed.entries[0];
+        assert_eq!((book.title.as_str(), book.authors.clone()), ("三体", vec!["刘慈欣".to_string()]));
+        assert_eq!(book.summary.as_deref(), Some("地球往事"));
+        assert_eq!(book.cover_url.as_deref(), Some("https://example.com/opds/covers/1.jpg"));
+        assert_eq!(book.acquisitions[0].href, "https://example.com/dl/1.epub");
+        assert_eq!(book.navigation_url, None);
+
+        let nav = &feed.entries[1];
+        assert!(nav.acquisitions.is_empty());
+        assert_eq!(nav.navigation_url.as_deref(), Some("https://example.com/opds/category/scifi"));
+    }
+
+    #[test]
+    fn test_download_file_name() {
+        let url = reqwest::Url::parse("https://example.com/get/42").unwrap();
+        assert_eq!(
+            download_file_name(&url, Some(r#"attachment; filename="../三体.epub""#), None).as_deref(),
+            Some("三体.epub")
+        );
+        assert_eq!(download_file_name(&url, None, Some("application/pdf")).as_deref(), Some("42.pdf"));
+        assert_eq!(download_file_name(&url, None, Some("image/png")), None);
+    }
+
+    #[test]
+    fn test_auth_for_same_origin_only() {
+        let auth = OpdsAuth { username: "reader".to_string(), password: None };
+        let feed = reqwest::Url::parse("https://books.example/opds/root.xml").unwrap();
+        let same = reqwest::Url::parse("https://books.example/download/1.epub").unwrap();
+        let other_host = reqwest::Url::parse("https://cdn.example/1.epub").unwrap();
+        let other_scheme = reqwest::Url::parse("http://books.example/download/1.epub").unwrap();
+        assert!(auth_for(&same, Some(&auth), Some(&feed)).is_some());
+        assert!(auth_for(&other_host, Some(&auth), Some(&feed)).is_none());
+        assert!(auth_for(&other_scheme, Some(&auth), Some(&feed)).is_none());
+        assert!(auth_for(&same, Some(&auth), None).is_none());
+    }
+}
@@ -0,0 +1,108 @@
+//! PDF 注释（annotation）读取
+//! 只读提取：枚举页面上的高亮、下划线、便签、方框等用户注释，取出类型、颜色、位置和文字内容
+
+use pdfium_render::prelude::{
+    PdfDocument, PdfPageAnnotationCommon, PdfPageAnnotationType, PdfRect,
+};
+
+use crate::pdf::page_box::PageBoxes;
+use crate::pdf::types::{FormFieldRect, PageAnnotations, PdfAnnotation, PdfError};
+
+/// 读取指定页（从 1 开始）的注释；页面没有注释时返回空列表
+/// 链接、表单控件和弹出窗口不属于用户注释，不在结果中
+pub fn extract_page_annotations(
+    document: &PdfDocument<'_>,
+    page_number: u32,
+) -> Result<PageAnnotations, PdfError> {
+    let page = document
+        .pages()
+        .get((page_number - 1) as u16)
+        .map_err(|e| PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string()))?;
+
+    let page_width = page.width().value;
+    let page_height = page.height().value;
+    let boxes = PageBoxes::read(&page);
+    let mut annotations = Vec::new();
+
+    for (index, annotation) in page.annotations().iter().enumerate() {
+        let Some(annotation_type) = annotation_type_name(annotation.annotation_type()) else {
+            continue;
+        };
+        if annotation.is_hidden() {
+            continue;
+        }
+        let Ok(bounds) = annotation.bounds() else {
+            continue;
+        };
+
+        // 文本标注的颜色存放在 /C（pdfium 的描边色），方框/圆形的填充色为 /IC
+        let color = annotation
+            .stroke_color()
+            .or_else(|_| annotation.fill_color())
+            .ok()
+            .map(|color| format!("#{}", color.to_hex()));
+
+        // 高亮等文本标注可能覆盖多行，逐行矩形来自 QuadPoints
+        let quads = if annotation.has_attachment_points() {
+            annotation
+                .attachment_points()
+                .iter()
+                .map(|quad| to_page_rect(&quad.to_rect(), &boxes))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        annotations.push(PdfAnnotation {
+            index: index as u32,
+            annotation_type: annotation_type.to_string(),
+            color,
+            rect: to_page_rect(&bounds, &boxes),
+            quads,
+            contents: annotation.contents().filter(|text| !text.trim().is_empty()),
+            // pdfium 以 creator 暴露 /T（作者）
+            author: annotation.creator().filter(|author| !author.trim().is_empty()),
+            modified: annotation.modification_date(),
+        });
+    }
+
+    Ok(PageAnnotations {
+        page_number,
+        page_width,
+        page_height,
+        annotations,
+    })
+}
+
+/// PDF 坐标原点在左下角，转换为与渲染图一致、以 CropBox 左上角为原点的坐标（与表单字段一致）
+fn to_page_rect(rect: &PdfRect, boxes: &PageBoxes) -> FormFieldRect {
+    FormFieldRect {
+        x: boxes.x(rect.left().value),
+        y: boxes.y_from_top(rect.top().value),
+        width: rect.width().value,
+        height: rect.height().value,
+    }
+}
+
+/// 返回给前端的注释类型名；不属于用户注释的类型返回 None
+fn annotation_type_name(annotation_type: PdfPageAnnotationType) -> Option<&'static str> {
+    let name = match annotation_type {
+        PdfPageAnnotationType::Highlight => "highlight",
+        PdfPageAnnotationType::Underline => "underline",
+        PdfPageAnnotationType::Squiggly => "squiggly",
+        PdfPageAnnotationType::Strikeout => "strikeout",
+        PdfPageAnnotationType::Text => "note",
+        PdfPageAnnotationType::FreeText => "free_text",
+        PdfPageAnnotationType::Square => "square",
+        PdfPageAnnotationType::Circle => "circle",
+        PdfPageAnnotationType::Line => "line",
+        PdfPageAnnotationType::Polygon => "polygon",
+        PdfPageAnnotationType::Polyline => "polyline",
+        PdfPageAnnotationType::Ink => "ink",
+        PdfPageAnnotationType::Stamp => "stamp",
+        PdfPageAnnotationType::Caret => "caret",
+        PdfPageAnnotationType::FileAttachment => "file_attachment",
+        _ => return None,
+    };
+    Some(name)
+}
@@ -0,0 +1,45 @@
+//! PDF 嵌入文件（附件）读取
+//! 列出文档级附件的名称和大小，并按名称导出到磁盘
+
+use std::path::Path;
+
+use pdfium_render::prelude::PdfDocument;
+
+use crate::pdf::types::{PdfAttachmentInfo, PdfError};
+
+/// 列出文档中的嵌入文件；没有附件时返回空列表
+pub fn list_attachments(document: &PdfDocument<'_>) -> Vec<PdfAttachmentInfo> {
+    document
+        .attachments()
+        .iter()
+        .enumerate()
+        .map(|(index, attachment)| PdfAttachmentInfo {
+            index: index as u32,
+            name: attachment.name(),
+            size: attachment.len() as u64,
+        })
+        .collect()
+}
+
+/// 将名称匹配的第一个附件写入 `out_path`，返回写入的字节数
+pub fn extract_attachment(document: &PdfDocument<'_>, name: &str, out_path: &str) -> Result<u64, PdfError> {
+    let attachment = document
+        .attachments()
+        .iter()
+        .find(|attachment| attachment.name() == name)
+        .ok_or_else(|| PdfError::InvalidParameter {
+            param: "name".to_string(),
+            value: name.to_string(),
+            expected: "文档中存在的附件名称".to_string(),
+        })?;
+
+    let bytes = attachment
+        .save_to_bytes()
+        .map_err(|e| PdfError::parse_error(None, format!("读取附件失败: {}", name), e.to_string()))?;
+
+    if let Some(parent) = Path::new(out_path).parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| PdfError::io_error(Some(parent.display().to_string()), e))?;
+    }
+    std::fs::write(out_path, &bytes).map_err(|e| PdfError::io_error(Some(out_path.to_string()), e))?;
+    Ok(bytes.len() as u64)
+}
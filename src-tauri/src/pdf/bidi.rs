@@ -0,0 +1,181 @@
+//! 双向文本（阿拉伯文、希伯来文等 RTL 文字）处理
+//! pdfium 按内容流顺序返回字符，很多 RTL 文档按视觉顺序（从左到右）写入字形，直接拼接得到的是倒序文本，
+//! 搜索时按逻辑顺序输入的词找不到。这里按行检测：行内 RTL 字符沿存储顺序从左向右排布时视为视觉顺序，
+//! 按行主方向用 unicode-bidi 重排回逻辑顺序，字符边界随字符一起移动，保证高亮矩形仍对应视觉位置
+
+use std::ops::Range;
+use unicode_bidi::{bidi_class, BidiClass, Level, ParagraphBidiInfo};
+
+/// 字符边界（PDF 坐标，原点在左下角）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharBounds {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// 页面上的单个字符及其边界，pdfium 生成的换行等字符没有边界
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageChar {
+    pub ch: char,
+    pub bounds: Option<CharBounds>,
+}
+
+fn is_rtl_char(c: char) -> bool {
+    matches!(bidi_class(c), BidiClass::R | BidiClass::AL)
+}
+
+/// 文本是否包含 RTL 字符；纯 LTR 页面无需重排
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl_char)
+}
+
+/// 将页面字符序列按行转换为逻辑顺序，不含 RTL 字符或已是逻辑顺序的行保持不变
+pub fn to_logical_order(chars: &[PageChar]) -> Vec<PageChar> {
+    let mut result = Vec::with_capacity(chars.len());
+    let mut line_start = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if matches!(c.ch, '\n' | '\r') {
+            result.extend(logical_line(&chars[line_start..i]));
+            result.push(*c);
+            line_start = i + 1;
+        }
+    }
+    result.extend(logical_line(&chars[line_start..]));
+    result
+}
+
+/// 字符序列拼接为字符串
+pub fn chars_text(chars: &[PageChar]) -> String {
+    chars.iter().map(|c| c.ch).collect()
+}
+
+fn logical_line(line: &[PageChar]) -> Vec<PageChar> {
+    if !is_visual_order(line) {
+        return line.to_vec();
+    }
+
+    // 行主方向：强 RTL 字符多于强 LTR 字符时按 RTL 段落处理
+    let (rtl, ltr) = line.iter().fold((0usize, 0usize), |(rtl, ltr), c| match bidi_class(c.ch) {
+        BidiClass::R | BidiClass::AL => (rtl + 1, ltr),
+        BidiClass::L => (rtl, ltr + 1),
+        _ => (rtl, ltr),
+    });
+    let level = if rtl >= ltr { Level::rtl() } else { Level::ltr() };
+
+    // 视觉顺序与逻辑顺序之间的重排互逆：把视觉串当作逻辑串跑一遍 bidi 重排即得到逻辑顺序
+    let text = chars_text(line);
+    let info = ParagraphBidiInfo::new(&text, Some(level));
+    let levels = info.reordered_levels_per_char(0..text.len());
+    ParagraphBidiInfo::reorder_visual(&levels)
+        .into_iter()
+        .map(|index| line[index])
+        .collect()
+}
+
+/// 行内 RTL 字符沿存储顺序从左向右排布时，说明该行按视觉顺序存储
+fn is_visual_order(line: &[PageChar]) -> bool {
+    let mut rtl_centers = line
+        .iter()
+        .filter(|c| is_rtl_char(c.ch))
+        .filter_map(|c| c.bounds.map(|b| (b.left + b.right) / 2.0));
+    match (rtl_centers.next(), rtl_centers.next_back()) {
+        (Some(first), Some(last)) => first < last,
+        _ => false,
+    }
+}
+
+/// 在字符序列中查找关键词，返回命中的字符下标区间（不重叠）
+pub fn find_matches(chars: &[PageChar], query: &str, case_sensitive: bool) -> Vec<Range<usize>> {
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    if needle.is_empty() || needle.len() > chars.len() {
+        return Vec::new();
+    }
+
+    let haystack: Vec<char> = chars.iter().map(|c| fold(c.ch)).collect();
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + needle.len() <= haystack.len() {
+        if haystack[start..start + needle.len()] == needle[..] {
+            matches.push(start..start + needle.len());
+            start += needle.len();
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+/// 多个字符边界的外接矩形，全部没有边界时为 None
+pub fn union_bounds(chars: &[PageChar]) -> Option<CharBounds> {
+    chars.iter().filter_map(|c| c.bounds).reduce(|a, b| CharBounds {
+        left: a.left.min(b.left),
+        top: a.top.max(b.top),
+        right: a.right.max(b.right),
+        bottom: a.bottom.min(b.bottom),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按给定顺序从左到右排布字符，模拟按视觉顺序写入的页面
+    fn visual_line(text: &str) -> Vec<PageChar> {
+        text.chars()
+            .enumerate()
+            .map(|(i, ch)| PageChar {
+                ch,
+                bounds: Some(CharBounds {
+                    left: i as f32 * 10.0,
+                    top: 20.0,
+                    right: i as f32 * 10.0 + 8.0,
+                    bottom: 10.0,
+                }),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_to_logical_order() {
+        // 视觉顺序存储的希伯来文 "שלום עולם"（从左到右为 "םלוע םולש"）
+        let chars = visual_line("םלוע םולש");
+        let logical = to_logical_order(&chars);
+        assert_eq!(chars_text(&logical), "שלום עולם");
+
+        // 重排后字符仍带着原来的边界：逻辑上的首字符位于最右侧
+        assert_eq!(logical[0].bounds, chars[chars.len() - 1].bounds);
+        let hit = find_matches(&logical, "עולם", true);
+        assert_eq!(hit, vec![5..9]);
+        let rect = union_bounds(&logical[hit[0].clone()]).unwrap();
+        assert_eq!((rect.left, rect.right), (0.0, 38.0));
+
+        // RTL 段落中的拉丁词与数字保持从左到右
+        let logical = to_logical_order(&visual_line("PDF 2024 םולש"));
+        assert_eq!(chars_text(&logical), "שלום PDF 2024");
+
+        // 已是逻辑顺序（RTL 字符从右向左排布）与纯 LTR 的行保持不变
+        let mut already_logical = visual_line("םולש");
+        already_logical.reverse();
+        assert_eq!(chars_text(&already_logical), "שלום");
+        assert_eq!(to_logical_order(&already_logical), already_logical);
+        let ltr = visual_line("Hello\nWorld");
+        assert_eq!(to_logical_order(&ltr), ltr);
+    }
+
+    #[test]
+    fn test_find_matches_case_insensitive() {
+        let chars = visual_line("Abc abc ABC");
+        assert_eq!(find_matches(&chars, "abc", false), vec![0..3, 4..7, 8..11]);
+        assert_eq!(find_matches(&chars, "abc", true), vec![4..7]);
+        assert!(find_matches(&chars, "", false).is_empty());
+    }
+}
@@ -1,25 +1,89 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, RwLock as StdRwLock};
 use std::time::{Duration, Instant};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use tokio::sync::RwLock;
 use moka::future::Cache as MokaCache;
 use crate::formats::{BookRenderCache, BoxFuture};
-use crate::pdf::types::{CacheKey, RenderResult, PdfError};
+use crate::pdf::types::{CacheKey, ImageFormat, RenderQuality, RenderResult, PdfError};
+use image::RgbaImage;
 
 const DEFAULT_MAX_CACHE_SIZE: usize = 256 * 1024 * 1024; // 256MB（按权重表示字节数）
-const DEFAULT_MAX_CACHE_ITEMS: usize = 50; // 仅用于统计展示
+const DEFAULT_MAX_CACHE_ITEMS: usize = 50; // 条目数上限，超出时淘汰最久未访问的条目
 const DEFAULT_CACHE_TIME_TO_IDLE_SECS: u64 = 24 * 60 * 60; // 一天内未访问的页面按默认策略过期
+const DEFAULT_MAX_ENCODED_SIZE: u64 = 32 * 1024 * 1024; // 内容哈希二级映射的字节上限
+
+type RenderStore = MokaCache<CacheKey, RenderResult>;
+type EncodedStore = MokaCache<ContentKey, Vec<u8>>;
+
+/// 位图内容摘要：纯色页（全白、全黑等）直接以像素值表示，其余页面为像素数据的哈希
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContentHash {
+    Uniform([u8; 4]),
+    Digest(u64),
+}
+
+/// 内容哈希键：除位图内容外还包含像素尺寸、质量、输出格式与渲染变体，不同参数的渲染结果不会被视为相同
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentKey {
+    pub content: ContentHash,
+    pub width: u32,
+    pub height: u32,
+    pub quality: RenderQuality,
+    pub format: ImageFormat,
+    pub variant: String,
+}
+
+impl ContentKey {
+    pub fn from_image(image: &RgbaImage, quality: RenderQuality, format: ImageFormat, variant: String) -> Self {
+        let (width, height) = image.dimensions();
+        Self {
+            content: content_hash(image.as_raw()),
+            width,
+            height,
+            quality,
+            format,
+            variant,
+        }
+    }
+}
+
+/// 计算位图内容摘要，先检测纯色页：遇到第一个不同像素即停止，非纯色页通常很快退出
+fn content_hash(pixels: &[u8]) -> ContentHash {
+    if let Some(first) = pixels.get(..4) {
+        if pixels.chunks_exact(4).all(|p| p == first) {
+            return ContentHash::Uniform([first[0], first[1], first[2], first[3]]);
+        }
+    }
+    let mut hasher = DefaultHasher::new();
+    pixels.hash(&mut hasher);
+    ContentHash::Digest(hasher.finish())
+}
+
+fn build_store(max_size: usize) -> RenderStore {
+    MokaCache::builder()
+        .weigher(|_k: &CacheKey, v: &RenderResult| v.image_data.len() as u32)
+        .max_capacity(max_size as u64)
+        .build()
+}
 
 pub struct CacheManager {
-    cache: MokaCache<CacheKey, RenderResult>,
+    // moka 不支持修改容量，调整上限时整体替换；克隆出的实例共享同一句柄，已打开文档的引擎也随之生效
+    cache: Arc<StdRwLock<RenderStore>>,
     sizes: Arc<RwLock<HashMap<CacheKey, usize>>>,
     // 记录每个缓存项的最后访问时间，实现自定义空闲过期策略
     access_times: Arc<RwLock<HashMap<CacheKey, Instant>>>,
-    max_size: usize,
-    max_items: usize,
+    max_size: Arc<AtomicUsize>,
+    max_items: Arc<AtomicUsize>,
     // 逻辑空闲过期时间（秒），0 表示不限时间，仅按容量淘汰
     time_to_idle_secs: Arc<AtomicU64>,
+    // 累计命中/未命中次数，克隆出的实例共享计数
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    // 内容哈希 → 已编码图像，内容相同的页面（空白页、重复水印页）跳过重复编码
+    encoded: EncodedStore,
 }
 
 impl CacheManager {
@@ -28,20 +92,26 @@ impl CacheManager {
     }
 
     pub fn with_limits(max_size: usize, max_items: usize) -> Self {
-        let cache = MokaCache::builder()
-            .weigher(|_k: &CacheKey, v: &RenderResult| v.image_data.len() as u32)
-            .max_capacity(max_size as u64)
-            .build();
         Self {
-            cache,
+            cache: Arc::new(StdRwLock::new(build_store(max_size))),
             sizes: Arc::new(RwLock::new(HashMap::new())),
-            max_size,
-            max_items,
+            max_size: Arc::new(AtomicUsize::new(max_size)),
+            max_items: Arc::new(AtomicUsize::new(max_items)),
             access_times: Arc::new(RwLock::new(HashMap::new())),
             time_to_idle_secs: Arc::new(AtomicU64::new(DEFAULT_CACHE_TIME_TO_IDLE_SECS)),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            encoded: MokaCache::builder()
+                .weigher(|_k: &ContentKey, v: &Vec<u8>| v.len() as u32)
+                .max_capacity(DEFAULT_MAX_ENCODED_SIZE)
+                .build(),
         }
     }
 
+    fn store(&self) -> RenderStore {
+        self.cache.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
     pub async fn get(&self, key: &CacheKey) -> Option<RenderResult> {
         // 先检查逻辑空闲过期时间
         let ttl_secs = self.time_to_idle_secs.load(Ordering::Relaxed);
@@ -57,34 +127,42 @@ impl CacheManager {
             if expired {
                 // 过期时同步移除缓存记录
                 let _ = self.remove(key).await;
+                self.misses.fetch_add(1, Ordering::Relaxed);
                 return None;
             }
         }
 
-        let result = self.cache.get(key).await;
-        if result.is_some() && ttl_secs > 0 {
+        // 访问时间始终记录：既用于空闲过期，也用于统计最久未访问条目的年龄
+        let result = self.store().get(key).await;
+        if result.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
             let mut times = self.access_times.write().await;
             times.insert(key.clone(), Instant::now());
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
         }
         result
     }
 
     pub async fn put(&self, key: CacheKey, data: RenderResult) -> Result<(), PdfError> {
         let size = data.image_data.len();
-        self.cache.insert(key.clone(), data).await;
-        let mut sizes = self.sizes.write().await;
-        sizes.insert(key.clone(), size);
-        let ttl_secs = self.time_to_idle_secs.load(Ordering::Relaxed);
-        if ttl_secs > 0 {
-            let mut times = self.access_times.write().await;
-            times.insert(key, Instant::now());
+        self.store().insert(key.clone(), data).await;
+        let count = {
+            let mut sizes = self.sizes.write().await;
+            sizes.insert(key.clone(), size);
+            sizes.len()
+        };
+        self.access_times.write().await.insert(key, Instant::now());
+        if count > self.max_items.load(Ordering::Relaxed) {
+            self.evict_to_limits().await;
         }
         Ok(())
     }
 
     pub async fn remove(&self, key: &CacheKey) -> Option<RenderResult> {
-        let val = self.cache.get(key).await;
-        self.cache.invalidate(key).await;
+        let store = self.store();
+        let val = store.get(key).await;
+        store.invalidate(key).await;
         let mut sizes = self.sizes.write().await;
         sizes.remove(key);
         let mut times = self.access_times.write().await;
@@ -93,20 +171,23 @@ impl CacheManager {
     }
 
     pub async fn clear(&self) {
-        self.cache.invalidate_all();
+        self.store().invalidate_all();
+        self.encoded.invalidate_all();
         let mut sizes = self.sizes.write().await;
         sizes.clear();
         let mut times = self.access_times.write().await;
         times.clear();
     }
 
-    pub async fn clear_page(&self, file_path: &str, page_number: u32) {
+    /// 清除指定文件的所有缓存项
+    pub async fn clear_file(&self, file_path: &str) {
         let keys: Vec<CacheKey> = {
             let sizes = self.sizes.read().await;
-            sizes.keys().filter(|k| k.file_path == file_path && k.page_number == page_number).cloned().collect()
+            sizes.keys().filter(|k| k.file_path == file_path).cloned().collect()
         };
+        let store = self.store();
         for k in keys.iter() {
-            self.cache.invalidate(k).await;
+            store.invalidate(k).await;
         }
         let mut sizes = self.sizes.write().await;
         for k in keys.iter() {
@@ -118,21 +199,90 @@ impl CacheManager {
         }
     }
 
+    /// 清除指定页面所有质量、尺寸、主题的缓存项，返回清除的条目数
+    pub async fn clear_page(&self, file_path: &str, page_number: u32) -> usize {
+        let keys: Vec<CacheKey> = {
+            let sizes = self.sizes.read().await;
+            sizes.keys().filter(|k| k.file_path == file_path && k.page_number == page_number).cloned().collect()
+        };
+        let store = self.store();
+        for k in keys.iter() {
+            store.invalidate(k).await;
+        }
+        let mut sizes = self.sizes.write().await;
+        for k in keys.iter() {
+            sizes.remove(k);
+        }
+        let mut times = self.access_times.write().await;
+        for k in keys.iter() {
+            times.remove(k);
+        }
+        keys.len()
+    }
+
+    /// 统计缓存占用：条目数与字节数以实际仍在缓存中的条目为准（容量淘汰的条目不计入），并按质量等级分类
     pub async fn get_stats(&self) -> CacheStats {
-        let sizes = self.sizes.read().await;
-        let total_size: usize = sizes.values().copied().sum();
+        let store = self.store();
+        store.run_pending_tasks().await;
+
+        let mut by_quality: Vec<QualityCacheStats> = Vec::new();
+        let (mut item_count, mut total_size) = (0usize, 0usize);
+        for (key, value) in store.iter() {
+            let size = value.image_data.len();
+            item_count += 1;
+            total_size += size;
+            match by_quality.iter_mut().find(|q| q.quality == key.quality) {
+                Some(entry) => {
+                    entry.item_count += 1;
+                    entry.total_size += size;
+                }
+                None => by_quality.push(QualityCacheStats {
+                    quality: key.quality.clone(),
+                    item_count: 1,
+                    total_size: size,
+                }),
+            }
+        }
+        by_quality.sort_by_key(|q| std::cmp::Reverse(q.total_size));
+
+        let oldest_entry_age_secs = {
+            let times = self.access_times.read().await;
+            times
+                .iter()
+                .filter(|(key, _)| store.contains_key(*key))
+                .map(|(_, last)| last.elapsed().as_secs())
+                .max()
+        };
+
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let lookups = hits + misses;
         CacheStats {
-            item_count: sizes.len(),
+            item_count,
             total_size,
-            max_size: self.max_size,
-            max_items: self.max_items,
-            hit_rate: 0.0,
+            max_size: self.max_size.load(Ordering::Relaxed),
+            max_items: self.max_items.load(Ordering::Relaxed),
+            hit_rate: if lookups > 0 { hits as f64 / lookups as f64 } else { 0.0 },
+            hits,
+            misses,
+            by_quality,
+            oldest_entry_age_secs,
         }
     }
 
+    /// 按内容查找已编码的图像数据
+    pub async fn get_encoded(&self, key: &ContentKey) -> Option<Vec<u8>> {
+        self.encoded.get(key).await
+    }
+
+    /// 记录内容对应的编码结果
+    pub async fn put_encoded(&self, key: ContentKey, image_data: Vec<u8>) {
+        self.encoded.insert(key, image_data).await;
+    }
+
     pub async fn contains(&self, key: &CacheKey) -> bool {
         // get 不改变缓存内容，但会克隆；为避免克隆，这里使用 contains_key
-        self.cache.contains_key(key)
+        self.store().contains_key(key)
     }
 
     pub async fn get_cached_pages(&self) -> Vec<u32> {
@@ -148,38 +298,78 @@ impl CacheManager {
         self.time_to_idle_secs.store(secs, Ordering::Relaxed);
     }
 
-    pub fn set_max_size(&mut self, max_size: usize) {
-        self.max_size = max_size;
-        // 重新构建缓存容量（注意：这会丢失内部策略状态，但不影响功能）
-        let new = MokaCache::builder()
-            .weigher(|_k: &CacheKey, v: &RenderResult| v.image_data.len() as u32)
-            .max_capacity(max_size as u64)
-            .build();
-        // 将旧缓存中可见的键值迁移（通过 sizes 表）
-        let sizes = futures::executor::block_on(self.sizes.read());
-        for (k, _) in sizes.iter() {
-            if let Some(v) = futures::executor::block_on(self.cache.get(k)) {
-                futures::executor::block_on(new.insert(k.clone(), v));
+    /// 当前的字节上限与条目上限
+    pub fn limits(&self) -> (usize, usize) {
+        (self.max_size.load(Ordering::Relaxed), self.max_items.load(Ordering::Relaxed))
+    }
+
+    /// 运行时调整缓存上限，并立即淘汰超出新限额的条目，返回淘汰的条目数
+    /// 字节上限变化时重建缓存，按最近访问顺序迁移旧条目，放不下的最久未访问条目直接丢弃
+    pub async fn set_limits(&self, max_size: usize, max_items: usize) -> usize {
+        self.max_items.store(max_items, Ordering::Relaxed);
+        let old_size = self.max_size.swap(max_size, Ordering::Relaxed);
+        if old_size != max_size {
+            let old = self.store();
+            let new = build_store(max_size);
+            let mut recent: Vec<(CacheKey, Instant)> = {
+                let times = self.access_times.read().await;
+                times.iter().map(|(k, t)| (k.clone(), *t)).collect()
+            };
+            recent.sort_by_key(|(_, t)| std::cmp::Reverse(*t));
+            let mut used = 0usize;
+            for (key, _) in recent {
+                let Some(value) = old.get(&key).await else { continue };
+                used += value.image_data.len();
+                if used > max_size {
+                    break;
+                }
+                new.insert(key, value).await;
             }
+            *self.cache.write().unwrap_or_else(|e| e.into_inner()) = new;
         }
-        drop(sizes);
-        self.cache = new;
+        self.evict_to_limits().await
     }
 
-    pub fn set_max_items(&mut self, max_items: usize) {
-        self.max_items = max_items;
+    /// 清理已被容量淘汰的索引，并淘汰超出条目上限的最久未访问条目，返回按条目上限淘汰的数量
+    async fn evict_to_limits(&self) -> usize {
+        let store = self.store();
+        store.run_pending_tasks().await;
+        let max_items = self.max_items.load(Ordering::Relaxed);
+
+        let mut sizes = self.sizes.write().await;
+        let mut times = self.access_times.write().await;
+        sizes.retain(|k, _| store.contains_key(k));
+        times.retain(|k, _| sizes.contains_key(k));
+        if sizes.len() <= max_items {
+            return 0;
+        }
+
+        let mut by_age: Vec<(CacheKey, Instant)> = times.iter().map(|(k, t)| (k.clone(), *t)).collect();
+        by_age.sort_by_key(|(_, t)| *t);
+        let excess = sizes.len() - max_items;
+        let mut evicted = 0;
+        for (key, _) in by_age.into_iter().take(excess) {
+            store.invalidate(&key).await;
+            sizes.remove(&key);
+            times.remove(&key);
+            evicted += 1;
+        }
+        evicted
     }
 }
 
 impl Clone for CacheManager {
     fn clone(&self) -> Self {
         Self {
-            cache: self.cache.clone(),
+            cache: Arc::clone(&self.cache),
             sizes: Arc::clone(&self.sizes),
             access_times: Arc::clone(&self.access_times),
-            max_size: self.max_size,
-            max_items: self.max_items,
+            max_size: Arc::clone(&self.max_size),
+            max_items: Arc::clone(&self.max_items),
             time_to_idle_secs: Arc::clone(&self.time_to_idle_secs),
+            hits: Arc::clone(&self.hits),
+            misses: Arc::clone(&self.misses),
+            encoded: self.encoded.clone(),
         }
     }
 }
@@ -191,6 +381,21 @@ pub struct CacheStats {
     pub max_size: usize,
     pub max_items: usize,
     pub hit_rate: f64,
+    /// 累计命中/未命中次数（进程启动以来）
+    pub hits: u64,
+    pub misses: u64,
+    /// 按质量等级分类的占用，按字节数降序
+    pub by_quality: Vec<QualityCacheStats>,
+    /// 最久未访问条目距上次访问的秒数，缓存为空时为 None
+    pub oldest_entry_age_secs: Option<u64>,
+}
+
+/// 单个质量等级的缓存占用
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QualityCacheStats {
+    pub quality: RenderQuality,
+    pub item_count: usize,
+    pub total_size: usize,
 }
 
 /// PDF 渲染缓存管理器的统一接口实现，直接复用现有缓存逻辑
@@ -220,7 +425,7 @@ impl BookRenderCache for CacheManager {
         Box::pin(CacheManager::clear(self))
     }
 
-    fn cache_clear_page<'a>(&'a self, file_path: &'a str, page_number: u32) -> BoxFuture<'a, ()> {
+    fn cache_clear_page<'a>(&'a self, file_path: &'a str, page_number: u32) -> BoxFuture<'a, usize> {
         Box::pin(CacheManager::clear_page(self, file_path, page_number))
     }
 
@@ -251,6 +456,9 @@ mod tests {
             width: 800,
             height: 600,
             format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: String::new(),
         };
 
         // 测试插入
@@ -288,6 +496,9 @@ mod tests {
                 width: 800,
                 height: 600,
                 format: ImageFormat::Png,
+                error: None,
+                rotation: 0,
+                etag: String::new(),
             };
             cache.put(key, data).await.unwrap();
         }
@@ -317,10 +528,138 @@ mod tests {
             width: 800,
             height: 600,
             format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: String::new(),
         };
         cache.put(key4, data4).await.unwrap();
 
         // 第1个条目应该还在（因为被访问过）
         assert!(cache.get(&key1).await.is_some());
     }
+
+    #[tokio::test]
+    async fn test_cache_stats_by_quality() {
+        let cache = CacheManager::with_limits(1024 * 1024, 10);
+        let key = |page: u32, quality: RenderQuality| {
+            CacheKey::new("a.pdf".to_string(), page, quality, 800, 600, "light".to_string())
+        };
+        let data = |size: usize| RenderResult {
+            image_data: vec![0u8; size],
+            width: 800,
+            height: 600,
+            format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: String::new(),
+        };
+
+        cache.put(key(1, RenderQuality::Standard), data(100)).await.unwrap();
+        cache.put(key(2, RenderQuality::Standard), data(100)).await.unwrap();
+        cache.put(key(1, RenderQuality::Thumbnail), data(30)).await.unwrap();
+        assert!(cache.get(&key(1, RenderQuality::Standard)).await.is_some());
+        assert!(cache.get(&key(3, RenderQuality::High)).await.is_none());
+
+        let stats = cache.get_stats().await;
+        assert_eq!((stats.item_count, stats.total_size), (3, 230));
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.hit_rate, 0.5);
+        assert_eq!(stats.by_quality.len(), 2);
+        assert_eq!(stats.by_quality[0].quality, RenderQuality::Standard);
+        assert_eq!((stats.by_quality[0].item_count, stats.by_quality[0].total_size), (2, 200));
+        assert!(stats.oldest_entry_age_secs.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_set_limits_evicts() {
+        let cache = CacheManager::with_limits(1024 * 1024, 10);
+        let key = |page: u32| CacheKey::new("a.pdf".to_string(), page, RenderQuality::Standard, 800, 600, "light".to_string());
+        let data = RenderResult {
+            image_data: vec![0u8; 100],
+            width: 800,
+            height: 600,
+            format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: String::new(),
+        };
+        for page in 1..=5 {
+            cache.put(key(page), data.clone()).await.unwrap();
+            tokio::time::sleep(Duration::from_millis(2)).await;
+        }
+        assert!(cache.get(&key(1)).await.is_some());
+
+        // 条目上限降到 3：淘汰最久未访问的第 2、3 页
+        assert_eq!(cache.set_limits(1024 * 1024, 3).await, 2);
+        assert!(cache.contains(&key(1)).await);
+        assert!(!cache.contains(&key(2)).await && !cache.contains(&key(3)).await);
+
+        // 克隆实例共享新容量：字节上限降到 250 只能保留最近访问的两页
+        let shared = cache.clone();
+        cache.set_limits(250, 3).await;
+        assert_eq!(shared.limits(), (250, 3));
+        assert!(shared.contains(&key(1)).await && shared.contains(&key(5)).await);
+        assert!(!shared.contains(&key(4)).await);
+    }
+
+    #[tokio::test]
+    async fn test_clear_file() {
+        let cache = CacheManager::with_limits(1024 * 1024, 10);
+        let key = |file: &str, page: u32| {
+            CacheKey::new(file.to_string(), page, RenderQuality::Standard, 800, 600, "light".to_string())
+        };
+        let data = RenderResult {
+            image_data: vec![0u8; 100],
+            width: 800,
+            height: 600,
+            format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: String::new(),
+        };
+
+        cache.put(key("a.pdf", 1), data.clone()).await.unwrap();
+        cache.put(key("a.pdf", 2), data.clone()).await.unwrap();
+        cache.put(key("b.pdf", 1), data).await.unwrap();
+
+        assert_eq!(cache.clear_page("a.pdf", 2).await, 1);
+        assert!(cache.get(&key("a.pdf", 1)).await.is_some());
+
+        cache.clear_file("a.pdf").await;
+
+        assert!(cache.get(&key("a.pdf", 1)).await.is_none());
+        assert!(cache.get(&key("a.pdf", 2)).await.is_none());
+        assert!(cache.get(&key("b.pdf", 1)).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_content_key_reuse() {
+        let white = RgbaImage::from_pixel(80, 60, image::Rgba([255, 255, 255, 255]));
+        let key = |image: &RgbaImage, variant: &str| {
+            ContentKey::from_image(image, RenderQuality::Standard, ImageFormat::WebP, variant.to_string())
+        };
+
+        assert_eq!(key(&white, "light").content, ContentHash::Uniform([255, 255, 255, 255]));
+        // 尺寸、主题、质量不同的纯色页不视为相同
+        let smaller = RgbaImage::from_pixel(40, 30, image::Rgba([255, 255, 255, 255]));
+        assert_ne!(key(&white, "light"), key(&smaller, "light"));
+        assert_ne!(key(&white, "light"), key(&white, "dark"));
+        assert_ne!(
+            key(&white, "light"),
+            ContentKey::from_image(&white, RenderQuality::High, ImageFormat::WebP, "light".to_string())
+        );
+
+        let mut marked = white.clone();
+        marked.put_pixel(10, 10, image::Rgba([0, 0, 0, 255]));
+        assert!(matches!(key(&marked, "light").content, ContentHash::Digest(_)));
+        assert_eq!(key(&marked, "light"), key(&marked.clone(), "light"));
+        assert_ne!(key(&marked, "light"), key(&white, "light"));
+
+        let cache = CacheManager::with_limits(1024 * 1024, 10);
+        cache.put_encoded(key(&marked, "light"), vec![1, 2, 3]).await;
+        assert_eq!(cache.get_encoded(&key(&marked, "light")).await, Some(vec![1, 2, 3]));
+        assert!(cache.get_encoded(&key(&white, "light")).await.is_none());
+        cache.clear().await;
+        assert!(cache.get_encoded(&key(&marked, "light")).await.is_none());
+    }
 }
@@ -0,0 +1,137 @@
+//! PDF 字符级坐标
+//! 逐字符读取 pdfium 文本页的字符与边界，供前端划词选择、复制时把触摸点映射到字符索引。
+//! 坐标与批注、表单一致：以 CropBox 左上角为原点、单位为点，按渲染缩放比例换算即可叠加到渲染图上
+
+use pdfium_render::prelude::PdfDocument;
+
+use crate::pdf::page_box::PageBoxes;
+use crate::pdf::types::{FormFieldRect, PageCharBoxes, PdfCharBox, PdfError};
+
+/// pdfium 返回的原始字符
+struct RawChar {
+    index: u32,
+    value: u32,
+    rect: Option<FormFieldRect>,
+}
+
+/// 读取指定页（从 1 开始）每个字符的矩形；传入 `region` 时只返回与该区域相交的字符
+pub fn extract_page_char_boxes(
+    document: &PdfDocument<'_>,
+    page_number: u32,
+    region: Option<&FormFieldRect>,
+) -> Result<PageCharBoxes, PdfError> {
+    let page = document
+        .pages()
+        .get((page_number - 1) as u16)
+        .map_err(|e| PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string()))?;
+    let text = page
+        .text()
+        .map_err(|e| PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string()))?;
+    let boxes = PageBoxes::read(&page);
+
+    let raw: Vec<RawChar> = text
+        .chars()
+        .iter()
+        .map(|c| RawChar {
+            index: c.index() as u32,
+            value: c.unicode_value(),
+            // pdfium 生成的空格、换行等字符边界为空矩形
+            rect: c
+                .loose_bounds()
+                .ok()
+                .filter(|r| r.width().value > 0.0 || r.height().value > 0.0)
+                .map(|r| FormFieldRect {
+                    x: boxes.x(r.left().value),
+                    y: boxes.y_from_top(r.top().value),
+                    width: r.width().value,
+                    height: r.height().value,
+                }),
+        })
+        .collect();
+
+    let mut chars = build_char_boxes(raw);
+    if let Some(region) = region {
+        chars.retain(|c| intersects(&c.rect, region));
+    }
+
+    Ok(PageCharBoxes {
+        page_number,
+        page_width: page.width().value,
+        page_height: page.height().value,
+        chars,
+    })
+}
+
+/// 整理原始字符：UTF-16 代理对合并为一个字符（CJK 扩展区等），无效码点替换为 U+FFFD；
+/// 没有边界的字符中，空白与控制字符取前一字符右侧的零宽矩形，连字拆出的后续字符沿用前一字符的字形矩形
+fn build_char_boxes(raw: Vec<RawChar>) -> Vec<PdfCharBox> {
+    let mut result: Vec<PdfCharBox> = Vec::with_capacity(raw.len());
+    let mut iter = raw.into_iter().peekable();
+    while let Some(current) = iter.next() {
+        let mut value = current.value;
+        let mut rect = current.rect;
+        if (0xD800..0xDC00).contains(&value) {
+            if let Some(low) = iter.next_if(|next| (0xDC00..0xE000).contains(&next.value)) {
+                value = 0x10000 + ((value - 0xD800) << 10) + (low.value - 0xDC00);
+                rect = rect.or(low.rect);
+            }
+        }
+        let ch = char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER);
+
+        let rect = rect.unwrap_or_else(|| match result.last() {
+            Some(prev) if ch.is_whitespace() || ch.is_control() => FormFieldRect {
+                x: prev.rect.x + prev.rect.width,
+                y: prev.rect.y,
+                width: 0.0,
+                height: prev.rect.height,
+            },
+            Some(prev) => prev.rect.clone(),
+            None => FormFieldRect { x: 0.0, y: 0.0, width: 0.0, height: 0.0 },
+        });
+        result.push(PdfCharBox {
+            ch: ch.to_string(),
+            index: current.index,
+            rect,
+        });
+    }
+    result
+}
+
+/// 两个矩形是否相交（含边界接触），零宽矩形落在区域边界上也算
+fn intersects(rect: &FormFieldRect, region: &FormFieldRect) -> bool {
+    rect.x <= region.x + region.width
+        && rect.x + rect.width >= region.x
+        && rect.y <= region.y + region.height
+        && rect.y + rect.height >= region.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, width: f32) -> Option<FormFieldRect> {
+        Some(FormFieldRect { x, y: 100.0, width, height: 12.0 })
+    }
+
+    #[test]
+    fn test_build_char_boxes() {
+        // “𠀀”（U+20000）以代理对给出，“ﬁ”连字拆为 f、i 两个字符，i 没有边界，末尾是生成的换行
+        let raw = vec![
+            RawChar { index: 0, value: 0x4E2D, rect: rect(0.0, 12.0) },
+            RawChar { index: 1, value: 0xD840, rect: rect(12.0, 12.0) },
+            RawChar { index: 2, value: 0xDC00, rect: None },
+            RawChar { index: 3, value: 'f' as u32, rect: rect(24.0, 10.0) },
+            RawChar { index: 4, value: 'i' as u32, rect: None },
+            RawChar { index: 5, value: '\n' as u32, rect: None },
+        ];
+        let boxes = build_char_boxes(raw);
+        let chars: Vec<(&str, u32)> = boxes.iter().map(|b| (b.ch.as_str(), b.index)).collect();
+        assert_eq!(chars, vec![("中", 0), ("𠀀", 1), ("f", 3), ("i", 4), ("\n", 5)]);
+        assert_eq!((boxes[3].rect.x, boxes[3].rect.width), (24.0, 10.0));
+        assert_eq!((boxes[4].rect.x, boxes[4].rect.width), (34.0, 0.0));
+
+        let region = FormFieldRect { x: 20.0, y: 105.0, width: 5.0, height: 1.0 };
+        let hits: Vec<u32> = boxes.iter().filter(|b| intersects(&b.rect, &region)).map(|b| b.index).collect();
+        assert_eq!(hits, vec![1, 3, 4]);
+    }
+}
@@ -0,0 +1,184 @@
+//! PDF 页面联系表（多页缩略图拼图）
+//! 九宫格预览等场景一次需要几十张缩略图，逐张传输和解码开销大；这里把缩略图按网格拼成一张 JPEG，
+//! 同时返回每页在图中的像素区域，前端解码一张图即可显示多页预览。总尺寸超过上限时等比缩小所有单元格
+
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::pdf::types::{ContactSheetCell, ImageFormat, PageThumbnail, PdfContactSheet, PdfError, RenderResult};
+
+/// 联系表宽、高的像素上限
+pub const MAX_CONTACT_SHEET_SIDE: u32 = 4096;
+
+/// 单元格之间及四周的留白（像素）
+const CELL_GAP: u32 = 8;
+
+const JPEG_QUALITY: u8 = 82;
+
+/// 网格布局：单元格尺寸为所有缩略图的最大宽高，整体超限时按同一比例缩小
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct GridLayout {
+    cols: u32,
+    rows: u32,
+    cell_width: u32,
+    cell_height: u32,
+    scale: f32,
+}
+
+impl GridLayout {
+    fn sheet_size(&self) -> (u32, u32) {
+        (
+            self.cols * self.cell_width + (self.cols + 1) * CELL_GAP,
+            self.rows * self.cell_height + (self.rows + 1) * CELL_GAP,
+        )
+    }
+
+    /// 第 index 个单元格左上角坐标
+    fn cell_origin(&self, index: u32) -> (u32, u32) {
+        let (col, row) = (index % self.cols, index / self.cols);
+        (
+            CELL_GAP + col * (self.cell_width + CELL_GAP),
+            CELL_GAP + row * (self.cell_height + CELL_GAP),
+        )
+    }
+}
+
+fn plan_grid(sizes: &[(u32, u32)], cols: u32, max_side: u32) -> GridLayout {
+    let count = sizes.len().max(1) as u32;
+    let cols = cols.clamp(1, count);
+    let rows = count.div_ceil(cols);
+    let max_width = sizes.iter().map(|s| s.0).max().unwrap_or(1).max(1);
+    let max_height = sizes.iter().map(|s| s.1).max().unwrap_or(1).max(1);
+
+    let available = |n: u32| max_side.saturating_sub((n + 1) * CELL_GAP).max(n) as f32;
+    let scale = (available(cols) / (cols * max_width) as f32)
+        .min(available(rows) / (rows * max_height) as f32)
+        .min(1.0);
+    GridLayout {
+        cols,
+        rows,
+        cell_width: ((max_width as f32 * scale) as u32).max(1),
+        cell_height: ((max_height as f32 * scale) as u32).max(1),
+        scale,
+    }
+}
+
+/// 把缩略图按 `cols` 列拼成联系表；底色取首张缩略图左上角像素（即当前主题下的纸张颜色）
+pub fn compose_contact_sheet(thumbnails: &[PageThumbnail], cols: u32) -> Result<PdfContactSheet, PdfError> {
+    let images = thumbnails
+        .iter()
+        .map(|t| {
+            image::load_from_memory(&t.result.image_data)
+                .map(|img| img.to_rgba8())
+                .map_err(|e| PdfError::render_error(t.page, "联系表解码缩略图", e.to_string()))
+        })
+        .collect::<Result<Vec<RgbaImage>, PdfError>>()?;
+
+    let sizes: Vec<(u32, u32)> = images.iter().map(|img| img.dimensions()).collect();
+    let layout = plan_grid(&sizes, cols, MAX_CONTACT_SHEET_SIDE);
+    let (sheet_width, sheet_height) = layout.sheet_size();
+    let background = images
+        .first()
+        .map(|img| *img.get_pixel(0, 0))
+        .map(|Rgba([r, g, b, _])| Rgba([r, g, b, 255]))
+        .unwrap_or(Rgba([255, 255, 255, 255]));
+    let mut sheet = RgbaImage::from_pixel(sheet_width, sheet_height, background);
+
+    let mut cells = Vec::with_capacity(images.len());
+    for (index, (thumbnail, image)) in thumbnails.iter().zip(images).enumerate() {
+        let image = if layout.scale < 1.0 {
+            let (w, h) = image.dimensions();
+            let width = ((w as f32 * layout.scale) as u32).clamp(1, layout.cell_width);
+            let height = ((h as f32 * layout.scale) as u32).clamp(1, layout.cell_height);
+            imageops::resize(&image, width, height, imageops::FilterType::Triangle)
+        } else {
+            image
+        };
+        let (width, height) = image.dimensions();
+        let (cell_x, cell_y) = layout.cell_origin(index as u32);
+        let x = cell_x + (layout.cell_width - width) / 2;
+        let y = cell_y + (layout.cell_height - height) / 2;
+        imageops::overlay(&mut sheet, &image, x as i64, y as i64);
+        cells.push(ContactSheetCell {
+            page: thumbnail.page,
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    let rgb = DynamicImage::ImageRgba8(sheet).to_rgb8();
+    let mut buffer = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY)
+        .encode(rgb.as_raw(), sheet_width, sheet_height, image::ColorType::Rgb8)
+        .map_err(|e| PdfError::render_error(0, "联系表JPEG编码", e.to_string()))?;
+
+    // 联系表的 ETag 由各缩略图的 ETag 与列数组合而成，任一页变化都会改变
+    let mut hasher = DefaultHasher::new();
+    layout.cols.hash(&mut hasher);
+    for thumbnail in thumbnails {
+        (thumbnail.page, &thumbnail.result.etag).hash(&mut hasher);
+    }
+
+    Ok(PdfContactSheet {
+        result: RenderResult {
+            image_data: buffer,
+            width: sheet_width,
+            height: sheet_height,
+            format: ImageFormat::Jpeg,
+            error: None,
+            rotation: 0,
+            etag: format!("{:016x}", hasher.finish()),
+        },
+        cols: layout.cols,
+        rows: layout.rows,
+        cells,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thumbnail(page: u32, width: u32, height: u32) -> PageThumbnail {
+        let image = RgbaImage::from_pixel(width, height, Rgba([250, 250, 250, 255]));
+        let mut png = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        PageThumbnail {
+            page,
+            result: RenderResult {
+                image_data: png,
+                width,
+                height,
+                format: ImageFormat::Png,
+                error: None,
+                rotation: 0,
+                etag: String::new(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_compose_contact_sheet() {
+        let thumbs = vec![thumbnail(1, 100, 140), thumbnail(2, 100, 140), thumbnail(5, 140, 100)];
+        let sheet = compose_contact_sheet(&thumbs, 2).unwrap();
+        assert_eq!((sheet.cols, sheet.rows), (2, 2));
+        assert_eq!((sheet.result.width, sheet.result.height), (2 * 140 + 3 * 8, 2 * 140 + 3 * 8));
+        let pages: Vec<u32> = sheet.cells.iter().map(|c| c.page).collect();
+        assert_eq!(pages, vec![1, 2, 5]);
+        // 横向页在单元格内垂直居中
+        let last = &sheet.cells[2];
+        assert_eq!((last.x, last.y, last.width, last.height), (8, 8 + 140 + 8 + 20, 140, 100));
+
+        // 超过尺寸上限时等比缩小
+        let layout = plan_grid(&[(600, 800); 50], 5, MAX_CONTACT_SHEET_SIDE);
+        let (w, h) = layout.sheet_size();
+        assert!(w <= MAX_CONTACT_SHEET_SIDE && h <= MAX_CONTACT_SHEET_SIDE);
+        assert_eq!((layout.cols, layout.rows), (5, 10));
+        assert!(layout.scale < 1.0);
+    }
+}
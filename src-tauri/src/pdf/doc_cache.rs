@@ -0,0 +1,150 @@
+//! 线程级 PDF 文档句柄缓存
+//! `PdfDocument` 不是 Send，无法在引擎间共享；这里在每个渲染线程内保留 pdfium 实例和最近打开的文档，
+//! 同一文件的后续渲染直接复用已打开的句柄，避免每次翻页都重新绑定库并解析文档
+
+use pdfium_render::prelude::*;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::pdf::engine::PdfEngine;
+use crate::pdf::types::PdfError;
+
+/// 失效代数，`invalidate_all` 时递增
+/// 不按文件路径分别记录，关闭过的文件不会留下条目；每个线程只缓存一个文档，其他文件的句柄随之失效的代价只是重新打开一次
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 文件指纹：大小 + 修改时间，用于发现文件被替换
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileStamp {
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl FileStamp {
+    fn read(path: &str) -> Result<Self, PdfError> {
+        let metadata =
+            std::fs::metadata(path).map_err(|e| PdfError::file_not_found(path.to_string(), e))?;
+        Ok(Self {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+/// 自持 pdfium 实例的文档句柄
+/// `PdfDocument` 借用创建它的 `Pdfium`，这里把两者放进同一个结构体：
+/// - `pdfium` 是 `Arc`，实例位于堆上，结构体移动时地址不变；只要本结构体存活，实例就不会被释放
+/// - 字段声明顺序即析构顺序，`document` 先于 `pdfium` 释放
+/// - 两个字段均为私有，文档只通过 `document()` 以不长于 `&self` 的生命周期借出
+///
+/// 以上三点保证擦除成 `'static` 的借用永远不会越过 pdfium 实例的生命周期
+struct OwnedDocument {
+    document: PdfDocument<'static>,
+    pdfium: Arc<Pdfium>,
+}
+
+impl OwnedDocument {
+    fn load(pdfium: Arc<Pdfium>, file_path: &str) -> Result<Self, PdfError> {
+        let document = pdfium
+            .load_pdf_from_file(file_path, None)
+            .map_err(|e| PdfError::FileNotFound {
+                path: file_path.to_string(),
+                source: e.to_string(),
+            })?;
+        // SAFETY: 见结构体文档，借用的 pdfium 由同一结构体中的 Arc 持有且晚于文档析构
+        let document: PdfDocument<'static> = unsafe { std::mem::transmute(document) };
+        Ok(Self { document, pdfium })
+    }
+
+    fn pdfium(&self) -> &Arc<Pdfium> {
+        &self.pdfium
+    }
+
+    fn document(&self) -> &PdfDocument<'_> {
+        &self.document
+    }
+}
+
+struct CachedDocument {
+    owned: OwnedDocument,
+    path: String,
+    stamp: FileStamp,
+    generation: u64,
+}
+
+#[derive(Default)]
+struct ThreadDocumentCache {
+    document: Option<CachedDocument>,
+    pdfium: Option<Arc<Pdfium>>,
+}
+
+thread_local! {
+    static THREAD_CACHE: RefCell<ThreadDocumentCache> = RefCell::new(ThreadDocumentCache::default());
+}
+
+/// 取本线程的 pdfium 实例，首次使用时创建
+fn thread_pdfium(cell: &RefCell<ThreadDocumentCache>) -> Result<Arc<Pdfium>, PdfError> {
+    if let Some(pdfium) = &cell.borrow().pdfium {
+        return Ok(Arc::clone(pdfium));
+    }
+    let pdfium = Arc::new(PdfEngine::create_pdfium()?);
+    cell.borrow_mut().pdfium = Some(Arc::clone(&pdfium));
+    Ok(pdfium)
+}
+
+/// 使用当前线程缓存的文档执行操作
+/// 缓存的文档路径、文件指纹或失效代数任一不一致时重新加载；文件已被删除时返回 `FileNotFound`。
+/// 执行 `f` 前把文档从缓存中取出、结束后放回，`f` 内可以嵌套调用本函数（嵌套调用按未命中处理）
+pub fn with_cached_document<F, R>(file_path: &str, f: F) -> Result<R, PdfError>
+where
+    F: FnOnce(&Arc<Pdfium>, &PdfDocument<'_>) -> Result<R, PdfError>,
+{
+    THREAD_CACHE.with(|cell| {
+        let stamp = match FileStamp::read(file_path) {
+            Ok(stamp) => stamp,
+            Err(e) => {
+                // 文件已不存在，释放可能仍持有的旧句柄
+                let mut cache = cell.borrow_mut();
+                if cache.document.as_ref().is_some_and(|d| d.path == file_path) {
+                    cache.document = None;
+                }
+                return Err(e);
+            }
+        };
+        let generation = GENERATION.load(Ordering::Acquire);
+
+        // 取出后不再持有 RefCell 借用，`f` 运行期间缓存槽为空
+        let taken = cell.borrow_mut().document.take();
+
+        let cached = match taken {
+            Some(cached)
+                if cached.path == file_path && cached.stamp == stamp && cached.generation == generation =>
+            {
+                cached
+            }
+            stale => {
+                // 先关闭旧文档再打开新文档，单线程同时只持有一个文档
+                drop(stale);
+                CachedDocument {
+                    owned: OwnedDocument::load(thread_pdfium(cell)?, file_path)?,
+                    path: file_path.to_string(),
+                    stamp,
+                    generation,
+                }
+            }
+        };
+
+        let result = f(cached.owned.pdfium(), cached.owned.document());
+        // 嵌套调用期间放入的文档被替换释放，保留外层（最近仍在使用的）文档
+        cell.borrow_mut().document = Some(cached);
+        result
+    })
+}
+
+/// 使所有线程缓存的文档句柄失效（文档关闭、被淘汰或全部清除时调用）
+/// 各线程在下次访问时发现代数变化并释放旧句柄；空闲的阻塞线程退出时也会随线程本地存储一并释放
+pub fn invalidate_all() {
+    GENERATION.fetch_add(1, Ordering::AcqRel);
+}
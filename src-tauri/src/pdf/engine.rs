@@ -1,18 +1,34 @@
 use pdfium_render::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
-use std::time::UNIX_EPOCH;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
+use crate::commands::log::write_log;
 use crate::formats::BookRenderCache;
+use crate::pdf::annotations;
+use crate::pdf::attachments;
+use crate::pdf::bidi::{self, PageChar};
+use crate::pdf::char_boxes;
+use crate::pdf::contact_sheet;
 use crate::pdf::cache::CacheManager;
-use crate::pdf::renderer::PdfRenderer;
+use crate::pdf::doc_cache::{self, with_cached_document};
+use crate::pdf::performance::PerformanceMonitor;
+use crate::pdf::forms;
+use crate::pdf::ocr;
+use crate::pdf::page_box::PageBoxes;
+use crate::pdf::preload_predictor::{PredictorStatistics, PreloadPredictor};
+use crate::pdf::reflow::{self, ReflowText, TextFragment};
+use crate::pdf::renderer::{render_error_placeholder, rotated_page_size, PdfRenderer};
+use crate::pdf::tiles::TileGrid;
 use crate::pdf::types::*;
 
-fn compute_file_hash(path: &str) -> Result<String, PdfError> {
+pub(crate) fn compute_file_hash(path: &str) -> Result<String, PdfError> {
     let metadata = std::fs::metadata(path)
         .map_err(|e| PdfError::file_not_found(path.to_string(), e))?;
 
@@ -37,10 +53,13 @@ fn pdf_cache_root() -> PathBuf {
     dir
 }
 
+/// 文档信息缓存格式版本，页信息字段变化时递增，使旧缓存自动失效
+const PDF_META_CACHE_VERSION: u32 = 2;
+
 fn pdf_meta_cache_path(file_hash: &str) -> PathBuf {
     let mut dir = pdf_cache_root();
     dir.push("pdf_meta");
-    dir.push(format!("{}.json", file_hash));
+    dir.push(format!("{}.v{}.json", file_hash, PDF_META_CACHE_VERSION));
     dir
 }
 
@@ -51,11 +70,449 @@ fn pdf_pages_cache_dir(file_hash: &str) -> PathBuf {
     dir
 }
 
+fn pdf_ocr_cache_dir(file_hash: &str) -> PathBuf {
+    let mut dir = pdf_cache_root();
+    dir.push("pdf_ocr");
+    dir.push(file_hash);
+    dir
+}
+
+/// 删除磁盘缓存中指定页面的所有渲染文件（文件名形如 `p_{页码}_{质量}_{宽}x{高}_{主题}.{扩展名}`），返回删除的文件数
+fn remove_page_disk_cache(file_hash: &str, page_number: u32) -> usize {
+    let prefix = format!("p_{}_", page_number);
+    let Ok(entries) = std::fs::read_dir(pdf_pages_cache_dir(file_hash)) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.starts_with(&prefix)))
+        .filter(|entry| std::fs::remove_file(entry.path()).is_ok())
+        .count()
+}
+
+/// 旧版本直接写在临时目录下的渲染文件（`goread_*.png` 等）
+fn is_legacy_render_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    name.starts_with("goread_") && matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "webp")
+}
+
+fn remove_file_counted(path: &Path, stats: &mut TempCleanupStats) {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if std::fs::remove_file(path).is_ok() {
+        stats.removed_files += 1;
+        stats.freed_bytes += size;
+    }
+}
+
+fn remove_dir_counted(dir: &Path, stats: &mut TempCleanupStats) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => remove_dir_counted(&path, stats),
+            Ok(_) => remove_file_counted(&path, stats),
+            Err(_) => {}
+        }
+    }
+    let _ = std::fs::remove_dir(dir);
+}
+
+/// 清理 `render_page_to_file` 落盘的页面图片及旧版本遗留的 `goread_*` 渲染文件；
+/// 不触碰 PDF 元数据缓存和 EPUB/MOBI 章节缓存。清理后之前返回的文件路径会失效
+pub fn cleanup_render_temp_files() -> TempCleanupStats {
+    let mut stats = TempCleanupStats::default();
+
+    if let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_file()) && is_legacy_render_file(&path) {
+                remove_file_counted(&path, &mut stats);
+            }
+        }
+    }
+
+    let mut pages_root = pdf_cache_root();
+    pages_root.push("pdf_pages");
+    remove_dir_counted(&pages_root, &mut stats);
+
+    println!(
+        "[PDF] 临时渲染文件清理完成: files={}, bytes={}",
+        stats.removed_files, stats.freed_bytes
+    );
+    stats
+}
+
+/// 临时渲染文件默认保留天数，超过且未再被读取的文件在启动和关闭文档时自动清理
+pub const DEFAULT_TEMP_RENDER_MAX_AGE_DAYS: u32 = 7;
+
+/// 清理时的最短保留时间，避免删掉刚写入、前端还未加载的文件
+const MIN_TEMP_RENDER_AGE: Duration = Duration::from_secs(60);
+
+/// 文件最后修改时间距今是否已超过 `max_age`；取不到时间时视为未过期，宁可少删
+fn is_older_than(path: &Path, max_age: Duration) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age >= max_age)
+}
+
+/// 刷新文件修改时间，磁盘缓存命中时调用，使清理按最近阅读时间而不是生成时间计算
+fn touch_file(path: &Path) {
+    let _ = std::fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// 当前临时渲染文件（页面图片磁盘缓存和旧版本 `goread_*` 图片）的总字节数
+pub fn temp_render_usage() -> u64 {
+    let legacy: u64 = std::fs::read_dir(std::env::temp_dir())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()) && is_legacy_render_file(&entry.path()))
+                .map(|entry| entry.metadata().map(|m| m.len()).unwrap_or(0))
+                .sum()
+        })
+        .unwrap_or(0);
+    legacy + dir_size(&pdf_cache_root().join("pdf_pages"))
+}
+
+/// 按最近阅读时间清理临时渲染文件：删除超过 `max_age` 未被读取的页面图片和旧版本 `goread_*` 图片，
+/// 清空后的文档目录一并删除。`max_age` 不低于 `MIN_TEMP_RENDER_AGE`，传 0 时也保留刚写入的文件。
+/// `open_hashes` 为已打开文档的文件哈希，其目录正在使用，整体跳过
+pub fn cleanup_stale_render_files(max_age: Duration, open_hashes: &HashSet<String>) -> TempCleanupStats {
+    let max_age = max_age.max(MIN_TEMP_RENDER_AGE);
+    let mut stats = TempCleanupStats::default();
+
+    if let Ok(entries) = std::fs::read_dir(std::env::temp_dir()) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_file())
+                && is_legacy_render_file(&path)
+                && is_older_than(&path, max_age)
+            {
+                remove_file_counted(&path, &mut stats);
+            }
+        }
+    }
+
+    if let Ok(dirs) = std::fs::read_dir(pdf_cache_root().join("pdf_pages")) {
+        for dir in dirs.flatten() {
+            let in_use = dir.file_name().to_str().is_some_and(|name| open_hashes.contains(name));
+            if in_use || !dir.file_type().is_ok_and(|t| t.is_dir()) {
+                continue;
+            }
+            let Ok(files) = std::fs::read_dir(dir.path()) else {
+                continue;
+            };
+            for file in files.flatten() {
+                let path = file.path();
+                if file.file_type().is_ok_and(|t| t.is_file()) && is_older_than(&path, max_age) {
+                    remove_file_counted(&path, &mut stats);
+                }
+            }
+            // 目录非空时删除失败，保留未过期的文件
+            let _ = std::fs::remove_dir(dir.path());
+        }
+    }
+
+    if stats.removed_files > 0 {
+        println!(
+            "[PDF] 过期渲染文件清理完成: max_age={}s, files={}, bytes={}",
+            max_age.as_secs(),
+            stats.removed_files,
+            stats.freed_bytes
+        );
+    }
+    stats
+}
+
+/// 并行渲染默认 worker 数上限（每个 worker 持有一个 pdfium 实例和已加载文档）
+const DEFAULT_RENDER_WORKERS: usize = 4;
+
+/// 调用方指定线程数时允许的最大 worker 数
+const MAX_RENDER_WORKERS: usize = 8;
+
+/// 运行时可调的并行渲染 worker 数上限，由 `pdf_configure` 按设备性能设定
+static RENDER_CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_RENDER_WORKERS);
+
+/// 当前的并行渲染 worker 数上限
+pub fn render_concurrency() -> usize {
+    RENDER_CONCURRENCY.load(Ordering::Relaxed)
+}
+
+/// 运行时缓存上限最小值，避免误配置导致每次翻页都重新渲染
+const MIN_CACHE_BYTES: usize = 16 * 1024 * 1024;
+
+/// 运行时缓存上限最大值
+const MAX_CACHE_BYTES: usize = 2 * 1024 * 1024 * 1024;
+
+/// 运行时缓存条目上限最大值
+const MAX_CACHE_ITEMS: usize = 2000;
+
+/// 单次批量缩略图请求允许的最大页数
+pub const MAX_THUMBNAIL_BATCH_PAGES: u32 = 50;
+
+/// 根据翻页行为预测后自动预加载的最大页数
+const MAX_PREDICTED_PRELOAD_PAGES: usize = 4;
+
+/// 判断页面尺寸一致时允许的误差（点），吸收扫描件和导出工具带来的细微差异
+const PAGE_SIZE_TOLERANCE: f32 = 1.0;
+
 /// PDF 引擎，负责文档加载和管理
 pub struct PdfEngine {
     file_path: String,
     document_info: Option<PdfDocumentInfo>,
     cache: CacheManager,
+    thumb_cache: CacheManager,
+    /// 翻页行为预测器，用于渲染后自动预加载
+    predictor: Arc<Mutex<PreloadPredictor>>,
+    /// 渲染性能监控，跨多次渲染累积数据
+    performance_monitor: PerformanceMonitor,
+    /// 加载时的文件指纹（路径 + 大小 + 修改时间），用于发现文件被外部替换
+    file_hash: Option<String>,
+    /// 展开后的目录章节（按起始页排序），首次查询当前章节时从 outline 构建
+    outline_chapters: Mutex<Option<Arc<Vec<OutlineChapter>>>>,
+    /// 逐页尺寸信息，加载文档时只读首页，其余由后台任务填充或按需即时查询
+    page_infos: PageInfoStore,
+}
+
+/// 懒加载的逐页信息，下标为页码 - 1
+/// 每次加载文档都换新的存储，后台填充任务发现只剩自己持有引用时即停止
+type PageInfoStore = Arc<Mutex<Vec<Option<PdfPageInfo>>>>;
+
+/// 后台填充页信息时每次持锁读取的页数
+const PAGE_INFO_FILL_BATCH: u32 = 64;
+
+/// 读取单页尺寸与旋转角度
+fn read_page_info(pages: &PdfPages<'_>, page_number: u32) -> Result<PdfPageInfo, PdfError> {
+    let page = pages.get((page_number - 1) as u16).map_err(|e| {
+        PdfError::parse_error(Some(page_number), "读取页面失败", e.to_string())
+    })?;
+    let rotation = match page.rotation() {
+        Ok(PdfPageRenderRotation::None) => 0,
+        Ok(PdfPageRenderRotation::Degrees90) => 90,
+        Ok(PdfPageRenderRotation::Degrees180) => 180,
+        Ok(PdfPageRenderRotation::Degrees270) => 270,
+        Err(_) => 0, // 默认无旋转
+    };
+    let boxes = PageBoxes::read(&page);
+    let (media_width, media_height) = boxes.media_size(rotation);
+    let (crop_offset_x, crop_offset_y) = boxes.crop_offset(rotation);
+    Ok(PdfPageInfo {
+        width: page.width().value,
+        height: page.height().value,
+        number: page_number,
+        rotation,
+        media_width,
+        media_height,
+        crop_offset_x,
+        crop_offset_y,
+    })
+}
+
+/// 用已读取的页信息补全文档信息；全部页就绪时 `pages_complete` 为 true，此前 `uniform_size` 一律为 false
+fn merge_page_infos(mut info: PdfDocumentInfo, page_infos: &[Option<PdfPageInfo>]) -> PdfDocumentInfo {
+    info.pages = page_infos.iter().flatten().cloned().collect();
+    info.pages_complete = info.pages.len() as u32 == info.page_count;
+    let (max_page_width, max_page_height, uniform_size) = summarize_page_sizes(&info.pages);
+    info.max_page_width = max_page_width;
+    info.max_page_height = max_page_height;
+    info.uniform_size = uniform_size && info.pages_complete;
+    info
+}
+
+/// 展开后的目录条目
+#[derive(Debug, Clone)]
+struct OutlineChapter {
+    page: u32,
+    level: u32,
+    title: String,
+}
+
+/// 读取字符及其边界（以 CropBox 为基准）；pdfium 生成的换行等字符边界为空矩形，按无边界处理
+fn collect_page_chars(chars: &PdfPageTextChars<'_>, boxes: &PageBoxes) -> Vec<PageChar> {
+    chars
+        .iter()
+        .filter_map(|c| {
+            let ch = c.unicode_char()?;
+            let bounds = c
+                .loose_bounds()
+                .ok()
+                .filter(|r| r.width().value > 0.0 || r.height().value > 0.0)
+                .map(|r| bidi::CharBounds {
+                    left: boxes.x(r.left().value),
+                    top: boxes.y(r.top().value),
+                    right: boxes.x(r.right().value),
+                    bottom: boxes.y(r.bottom().value),
+                });
+            Some(PageChar { ch, bounds })
+        })
+        .collect()
+}
+
+/// 按逻辑顺序拼接字符文本（RTL 行视觉顺序存储时重排）
+fn logical_text(chars: &PdfPageTextChars<'_>, boxes: &PageBoxes) -> String {
+    bidi::chars_text(&bidi::to_logical_order(&collect_page_chars(chars, boxes)))
+}
+
+/// 在逻辑顺序的字符序列中搜索，命中位置为匹配字符边界的外接矩形
+fn search_logical_chars(page_number: u32, chars: &[PageChar], query: &str, case_sensitive: bool) -> Vec<SearchResult> {
+    bidi::find_matches(chars, query, case_sensitive)
+        .into_iter()
+        .map(|range| {
+            let context_start = range.start.saturating_sub(30);
+            let context_end = (range.end + 30).min(chars.len());
+            let position = match bidi::union_bounds(&chars[range.clone()]) {
+                Some(rect) => TextPosition {
+                    x: rect.left,
+                    y: rect.top,
+                    width: rect.right - rect.left,
+                    height: rect.top - rect.bottom,
+                },
+                None => TextPosition { x: 0.0, y: 0.0, width: 0.0, height: 0.0 },
+            };
+            SearchResult {
+                page_number,
+                text: bidi::chars_text(&chars[range]),
+                position,
+                context: bidi::chars_text(&chars[context_start..context_end]),
+            }
+        })
+        .collect()
+}
+
+/// 书签树的最大深度，超过后不再展开子节点
+const MAX_OUTLINE_DEPTH: u32 = 64;
+
+/// 单个文档最多读取的书签数，防止损坏文档中 First/Next 成环导致无限展开
+const MAX_OUTLINE_ITEMS: usize = 20_000;
+
+/// 大纲节点的 First/Next 遍历接口，层级构建不直接依赖 pdfium，便于测试
+trait OutlineNode: Sized {
+    fn first_child(&self) -> Option<Self>;
+    fn next_sibling(&self) -> Option<Self>;
+    /// 由节点自身与已构建的子节点生成书签
+    fn to_bookmark(&self, level: u32, children: Vec<Bookmark>) -> Bookmark;
+}
+
+impl<'a> OutlineNode for PdfBookmark<'a> {
+    fn first_child(&self) -> Option<Self> {
+        PdfBookmark::first_child(self)
+    }
+
+    fn next_sibling(&self) -> Option<Self> {
+        PdfBookmark::next_sibling(self)
+    }
+
+    fn to_bookmark(&self, level: u32, children: Vec<Bookmark>) -> Bookmark {
+        let page_number = PdfEngine::resolve_bookmark_page(self);
+        Bookmark {
+            title: self.title().unwrap_or_default(),
+            page_number: page_number.unwrap_or(0),
+            level,
+            fallback_page: page_number.is_none().then(|| first_located_page(&children)).flatten(),
+            children,
+            resolved: page_number.is_some(),
+        }
+    }
+}
+
+/// 自身没有可解析的目标时（常见于只用作分组的卷/部标题），取第一个可定位的子节点所在页
+fn first_located_page(children: &[Bookmark]) -> Option<u32> {
+    children.iter().find_map(|child| {
+        if child.resolved {
+            Some(child.page_number)
+        } else {
+            child.fallback_page
+        }
+    })
+}
+
+/// 从某一级的第一个节点开始构建该级书签：同级只沿 Next 前进，子级只从 First 进入，
+/// 每个节点恰好访问一次并挂在真正的父节点下。相邻且标题、页码与子树完全相同的项视为重复，只保留一份；
+/// 不相邻的同名同页书签照常保留。pdfium 不暴露书签句柄，无法按节点判断成环，成环的文档由总数上限截断
+fn build_outline_level<N: OutlineNode>(first: Option<N>, level: u32, budget: &mut usize) -> Vec<Bookmark> {
+    let mut items: Vec<Bookmark> = Vec::new();
+    let mut current = first;
+    while let Some(node) = current {
+        if *budget == 0 {
+            break;
+        }
+        *budget -= 1;
+        let children = if level + 1 < MAX_OUTLINE_DEPTH {
+            build_outline_level(node.first_child(), level + 1, budget)
+        } else {
+            Vec::new()
+        };
+        let bookmark = node.to_bookmark(level, children);
+        if items.last() != Some(&bookmark) {
+            items.push(bookmark);
+        }
+        current = node.next_sibling();
+    }
+    items
+}
+
+/// 展开目录树为按起始页排序的章节列表；同页时稳定排序保持父节点在前，查找时取到最深一级
+fn flatten_outline(bookmarks: &[Bookmark]) -> Vec<OutlineChapter> {
+    fn walk(items: &[Bookmark], out: &mut Vec<OutlineChapter>) {
+        for item in items {
+            if item.page_number > 0 {
+                out.push(OutlineChapter {
+                    page: item.page_number,
+                    level: item.level,
+                    title: item.title.clone(),
+                });
+            }
+            walk(&item.children, out);
+        }
+    }
+    let mut chapters = Vec::new();
+    walk(bookmarks, &mut chapters);
+    chapters.sort_by_key(|c| c.page);
+    chapters
+}
+
+/// 取起始页不大于 `page` 的最后一个章节，结束页为下一章起始页的前一页（最后一章到文末）
+fn locate_chapter(chapters: &[OutlineChapter], page: u32, page_count: u32) -> Option<PdfChapterProgress> {
+    let idx = chapters.partition_point(|c| c.page <= page).checked_sub(1)?;
+    let chapter = &chapters[idx];
+    let end_page = chapters
+        .get(idx + 1)
+        .map_or(page_count, |next| next.page.saturating_sub(1))
+        .max(page);
+    let total = end_page - chapter.page + 1;
+    Some(PdfChapterProgress {
+        title: chapter.title.clone(),
+        level: chapter.level,
+        start_page: chapter.page,
+        end_page,
+        progress: (page - chapter.page + 1) as f32 / total as f32 * 100.0,
+    })
 }
 
 impl PdfEngine {
@@ -65,6 +522,12 @@ impl PdfEngine {
             file_path: String::new(),
             document_info: None,
             cache: CacheManager::with_limits(50 * 1024 * 1024, 20),
+            thumb_cache: CacheManager::with_limits(16 * 1024 * 1024, 64),
+            predictor: Arc::new(Mutex::new(PreloadPredictor::new())),
+            performance_monitor: PerformanceMonitor::new(),
+            file_hash: None,
+            outline_chapters: Mutex::new(None),
+            page_infos: PageInfoStore::default(),
         })
     }
 
@@ -74,11 +537,17 @@ impl PdfEngine {
             file_path: String::new(),
             document_info: None,
             cache,
+            thumb_cache: CacheManager::with_limits(16 * 1024 * 1024, 64),
+            predictor: Arc::new(Mutex::new(PreloadPredictor::new())),
+            performance_monitor: PerformanceMonitor::new(),
+            file_hash: None,
+            outline_chapters: Mutex::new(None),
+            page_infos: PageInfoStore::default(),
         })
     }
 
     /// 创建 Pdfium 实例（内部使用）
-    fn create_pdfium() -> Result<Pdfium, PdfError> {
+    pub(crate) fn create_pdfium() -> Result<Pdfium, PdfError> {
         // Android: jniLibs 中的 .so 文件会自动复制到应用的 native library 目录
         // 直接通过库名加载即可
         #[cfg(target_os = "android")]
@@ -156,20 +625,41 @@ impl PdfEngine {
 
     /// 加载 PDF 文档
     pub async fn load_document(&mut self, path: &str) -> Result<PdfDocumentInfo, PdfError> {
+        if let Ok(mut chapters) = self.outline_chapters.lock() {
+            *chapters = None;
+        }
         if !self.file_path.is_empty() && self.file_path != path {
             self.cache.clear().await;
+            self.thumb_cache.clear().await;
+            if let Ok(mut predictor) = self.predictor.lock() {
+                predictor.clear_history();
+            }
         }
 
         let file_hash = compute_file_hash(path)?;
         let meta_path = pdf_meta_cache_path(&file_hash);
 
         if meta_path.exists() {
-            if let Ok(file) = std::fs::File::open(&meta_path) {
-                if let Ok(info) = serde_json::from_reader::<_, PdfDocumentInfo>(file) {
+            let cached = std::fs::File::open(&meta_path)
+                .ok()
+                .and_then(|file| serde_json::from_reader::<_, PdfDocumentInfo>(file).ok());
+            // 缺少 MediaBox 尺寸的是旧格式缓存，按未命中处理
+            let valid = cached.filter(|info| {
+                let has_media_box = info.pages.iter().all(|p| p.media_width > 0.0 && p.media_height > 0.0);
+                info.pages_complete && info.pages.len() as u32 == info.page_count && has_media_box
+            });
+            match valid {
+                Some(info) => {
                     self.file_path = path.to_string();
+                    self.page_infos = Arc::new(Mutex::new(info.pages.iter().cloned().map(Some).collect()));
                     self.document_info = Some(info.clone());
+                    self.file_hash = Some(file_hash);
                     return Ok(info);
                 }
+                // 无法解析或已过时的缓存直接删除，页信息补全后会重新写入
+                None => {
+                    let _ = std::fs::remove_file(&meta_path);
+                }
             }
         }
 
@@ -183,47 +673,110 @@ impl PdfEngine {
 
         let document_info = self.extract_document_info(&document)?;
 
+        let mut page_infos = vec![None; document_info.page_count as usize];
+        for page in &document_info.pages {
+            page_infos[(page.number - 1) as usize] = Some(page.clone());
+        }
+        self.page_infos = Arc::new(Mutex::new(page_infos));
         self.file_path = path.to_string();
         self.document_info = Some(document_info.clone());
+        self.file_hash = Some(file_hash);
 
-        if let Some(parent) = meta_path.parent() {
-            let _ = std::fs::create_dir_all(parent);
-        }
-        if let Ok(json) = serde_json::to_vec(&document_info) {
-            let _ = std::fs::write(&meta_path, json);
-        }
+        self.spawn_page_info_fill(document_info.clone(), meta_path);
 
         Ok(document_info)
     }
 
-    /// 提取文档信息
-    fn extract_document_info(&self, document: &PdfDocument<'_>) -> Result<PdfDocumentInfo, PdfError> {
-        let pages = document.pages();
-        let page_count = pages.len() as u32;
-        
-        let mut page_infos = Vec::new();
-        for i in 0..page_count {
-            let page = pages.get(i as u16).map_err(|e| {
-                PdfError::parse_error(Some(i + 1), "读取页面失败", e.to_string())
-            })?;
+    /// 后台逐批读取其余页信息，全部就绪后写入磁盘元数据缓存，下次打开直接使用
+    fn spawn_page_info_fill(&self, base_info: PdfDocumentInfo, meta_path: PathBuf) {
+        let store = Arc::clone(&self.page_infos);
+        let file_path = self.file_path.clone();
+        let page_count = base_info.page_count;
+        tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            let mut next = 1;
+            while next <= page_count {
+                // 文档已关闭或重新加载，停止填充
+                if Arc::strong_count(&store) == 1 {
+                    return;
+                }
+                let end = (next + PAGE_INFO_FILL_BATCH - 1).min(page_count);
+                let batch = with_cached_document(&file_path, |_, document| {
+                    let pages = document.pages();
+                    (next..=end).map(|number| read_page_info(pages, number)).collect::<Result<Vec<_>, _>>()
+                });
+                let batch = match batch {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        eprintln!("[PdfEngine] 后台读取页信息失败: file={}, {}", file_path, e);
+                        return;
+                    }
+                };
+                let Ok(mut infos) = store.lock() else {
+                    return;
+                };
+                for info in batch {
+                    let index = (info.number - 1) as usize;
+                    if let Some(slot) = infos.get_mut(index) {
+                        slot.get_or_insert(info);
+                    }
+                }
+                next = end + 1;
+            }
 
-            let width = page.width().value;
-            let height = page.height().value;
-            let rotation = match page.rotation() {
-                Ok(PdfPageRenderRotation::None) => 0,
-                Ok(PdfPageRenderRotation::Degrees90) => 90,
-                Ok(PdfPageRenderRotation::Degrees180) => 180,
-                Ok(PdfPageRenderRotation::Degrees270) => 270,
-                Err(_) => 0, // 默认无旋转
+            let Ok(infos) = store.lock() else {
+                return;
             };
+            let full_info = merge_page_infos(base_info, &infos);
+            drop(infos);
+            if let Some(parent) = meta_path.parent() {
+                let _ = std::fs::create_dir_all(parent);
+            }
+            if let Ok(json) = serde_json::to_vec(&full_info) {
+                let _ = std::fs::write(&meta_path, json);
+            }
+            println!(
+                "[PdfEngine] 页信息填充完成: file={}, pages={}, elapsed_ms={}",
+                file_path,
+                page_count,
+                start.elapsed().as_millis()
+            );
+        });
+    }
 
-            page_infos.push(PdfPageInfo {
-                width,
-                height,
-                number: i + 1,
-                rotation,
-            });
+    /// 文件自加载后是否被修改、替换或删除
+    pub fn is_file_changed(&self) -> bool {
+        match &self.file_hash {
+            Some(loaded) => compute_file_hash(&self.file_path)
+                .map(|current| current != *loaded)
+                .unwrap_or(true),
+            None => false,
         }
+    }
+
+    /// 删除加载时文件版本对应的磁盘缓存（文档信息、页面图片与 OCR 结果）
+    pub fn remove_disk_cache(&self) {
+        if let Some(file_hash) = &self.file_hash {
+            let _ = std::fs::remove_file(pdf_meta_cache_path(file_hash));
+            let _ = std::fs::remove_dir_all(pdf_pages_cache_dir(file_hash));
+            let _ = std::fs::remove_dir_all(pdf_ocr_cache_dir(file_hash));
+        }
+    }
+
+    /// 提取文档信息：只读取页数和首页尺寸，其余页由后台任务填充，超大文档也能立即返回；
+    /// 只读到首页时无法判断尺寸是否一致，`uniform_size` 为 false
+    fn extract_document_info(&self, document: &PdfDocument<'_>) -> Result<PdfDocumentInfo, PdfError> {
+        let pages = document.pages();
+        let page_count = pages.len() as u32;
+
+        let page_infos = if page_count > 0 {
+            vec![read_page_info(pages, 1)?]
+        } else {
+            Vec::new()
+        };
+        let (max_page_width, max_page_height, uniform_size) = summarize_page_sizes(&page_infos);
+        let pages_complete = page_infos.len() as u32 == page_count;
+        let uniform_size = uniform_size && pages_complete;
 
         // 提取元数据
         // 暂时设置为 None，避免 pdfium-render 不同版本的 trait bound 问题
@@ -247,9 +800,44 @@ impl PdfEngine {
             producer,
             creation_date,
             modification_date,
+            max_page_width,
+            max_page_height,
+            uniform_size,
+            pages_complete,
         })
     }
 
+    /// 获取渲染性能监控器
+    pub fn performance_monitor(&self) -> &PerformanceMonitor {
+        &self.performance_monitor
+    }
+
+    /// 根据页面信息预先算出渲染缓存键（与 renderer 中的目标尺寸逻辑一致），页面信息无法读取时返回 None
+    /// 后台尚未填充到的页先即时读取页面信息，保证这些页也能命中内存缓存
+    async fn render_cache_key(&self, page_number: u32, options: &RenderOptions) -> Option<CacheKey> {
+        let page_info = self.get_page_info(page_number).await.ok()?;
+        let rotation = options.rotation_for(page_info.width, page_info.height);
+        let (base_width, base_height) = rotated_page_size(page_info.width, page_info.height, rotation);
+
+        let (target_width, target_height) = if let Some(w) = options.width {
+            let aspect_ratio = base_height / base_width;
+            (w, (w as f32 * aspect_ratio) as u32)
+        } else if let Some(h) = options.height {
+            let aspect_ratio = base_width / base_height;
+            ((h as f32 * aspect_ratio) as u32, h)
+        } else {
+            (base_width as u32, base_height as u32)
+        };
+        Some(CacheKey::new(
+            self.file_path.clone(),
+            page_number,
+            options.quality.clone(),
+            target_width,
+            target_height,
+            options.cache_variant_for_rotation(rotation),
+        ))
+    }
+
     /// 渲染单个页面
     pub async fn render_page(
         &self,
@@ -264,34 +852,9 @@ impl PdfEngine {
         }
 
         // 提前检查缓存（在加载文档之前）
-        if let Some(page_info) = self.document_info.as_ref().and_then(|info| info.pages.get((page_number - 1) as usize)) {
-            let base_width = page_info.width;
-            let base_height = page_info.height;
-            
-            // 计算目标尺寸（与 renderer 中的逻辑一致）
-            let (target_width, target_height) = if let Some(w) = options.width {
-                let aspect_ratio = base_height / base_width;
-                (w, (w as f32 * aspect_ratio) as u32)
-            } else if let Some(h) = options.height {
-                let aspect_ratio = base_width / base_height;
-                ((h as f32 * aspect_ratio) as u32, h)
-            } else {
-                (base_width as u32, base_height as u32)
-            };
-            let theme_key = options
-                .theme
-                .clone()
-                .unwrap_or_else(|| "light".to_string());
-            let cache_key = CacheKey::new(
-                self.file_path.clone(),
-                page_number,
-                options.quality.clone(),
-                target_width,
-                target_height,
-                theme_key,
-            );
-            
+        if let Some(cache_key) = self.render_cache_key(page_number, &options).await {
             if let Some(cached) = BookRenderCache::cache_get(&self.cache, &cache_key).await {
+                self.performance_monitor.record_cache_hit().await;
                 println!("[backend] 页面 {} 从缓存加载（跳过文档加载）", page_number);
                 return Ok(cached);
             }
@@ -299,34 +862,44 @@ impl PdfEngine {
 
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
+        let fallback_options = options.fallback_on_error.then(|| options.clone());
         
-        tokio::task::spawn_blocking(move || {
+        let result = tokio::task::spawn_blocking(move || {
             let start = std::time::Instant::now();
             
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let load_time = start.elapsed();
-            println!("[backend] 页面 {} 文档加载耗时: {}ms", page_number, load_time.as_millis());
-            
-            let render_start = std::time::Instant::now();
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            let result = renderer.render_page_sync(&document, page_number, options)?;
-            
-            let render_time = render_start.elapsed();
-            let total_time = start.elapsed();
-            println!("[backend] 页面 {} 渲染耗时: {}ms, 总耗时: {}ms", 
-                page_number, render_time.as_millis(), total_time.as_millis());
-            
-            Ok(result)
+            with_cached_document(&file_path, |pdfium, document| {
+                let load_time = start.elapsed();
+                println!("[backend] 页面 {} 文档加载耗时: {}ms", page_number, load_time.as_millis());
+
+                let render_start = std::time::Instant::now();
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                let result = renderer.render_page_sync(document, page_number, options)?;
+
+                let render_time = render_start.elapsed();
+                let total_time = start.elapsed();
+                println!("[backend] 页面 {} 渲染耗时: {}ms, 总耗时: {}ms", 
+                    page_number, render_time.as_millis(), total_time.as_millis());
+
+                Ok(result)
+            })
         })
         .await
-        .map_err(|e| PdfError::render_error(page_number, "render_page", format!("渲染任务失败: {}", e)))?
+        .map_err(|e| PdfError::render_error(page_number, "render_page", format!("渲染任务失败: {}", e)))
+        .and_then(|result| result)
+        .inspect_err(|e| {
+            write_log("error", "PDF", &format!("页面渲染失败: file={}, page={}, {}", self.file_path, page_number, e))
+        });
+
+        // 损坏页返回占位图，真实错误随结果返回，前端可提示并调用 pdf_retry_page 重试
+        match (result, fallback_options) {
+            (Err(e), Some(options)) => {
+                let page_size = self.cached_page_info(page_number).map(|info| (info.width, info.height));
+                render_error_placeholder(page_number, page_size, &options, e.to_string())
+            }
+            (result, _) => result,
+        }
     }
 
     pub async fn render_page_to_file(
@@ -338,9 +911,10 @@ impl PdfEngine {
             return Err(PdfError::PageNotFound { page: page_number, total_pages: self.get_page_count() });
         }
 
-        let (target_width, target_height) = if let Some(info) = self.document_info.as_ref().and_then(|i| i.pages.get((page_number - 1) as usize)) {
-            let base_width = info.width;
-            let base_height = info.height;
+        let mut rotation = 0;
+        let (target_width, target_height) = if let Ok(info) = self.get_page_info(page_number).await {
+            rotation = options.rotation_for(info.width, info.height);
+            let (base_width, base_height) = rotated_page_size(info.width, info.height, rotation);
             if let Some(w) = options.width {
                 let aspect = base_height / base_width;
                 (w, (w as f32 * aspect) as u32)
@@ -360,11 +934,9 @@ impl PdfEngine {
             RenderQuality::Standard => "std",
             RenderQuality::High => "high",
             RenderQuality::Best => "best",
+            RenderQuality::Adaptive => "adaptive",
         };
-        let theme_key = options
-            .theme
-            .clone()
-            .unwrap_or_else(|| "light".to_string());
+        let theme_key = options.cache_variant_for_rotation(rotation);
         let cache_key = CacheKey::new(
             self.file_path.clone(),
             page_number,
@@ -376,11 +948,6 @@ impl PdfEngine {
 
         let file_hash = compute_file_hash(&self.file_path)?;
         let pages_dir = pdf_pages_cache_dir(&file_hash);
-        let ext_from_quality = |quality: &RenderQuality| match quality {
-            RenderQuality::Thumbnail => "png",
-            RenderQuality::Best => "png",
-            _ => "webp",
-        };
         let disk_path = pages_dir.join(format!(
             "p_{}_{}_{}x{}_{}.{}",
             page_number,
@@ -388,10 +955,11 @@ impl PdfEngine {
             target_width,
             target_height,
             theme_key,
-            ext_from_quality(&options.quality)
+            options.output_format().extension()
         ));
 
         if std::path::Path::new(&disk_path).exists() {
+            touch_file(&disk_path);
             return Ok(disk_path.to_string_lossy().to_string());
         }
 
@@ -432,18 +1000,39 @@ impl PdfEngine {
         let cache = self.cache.clone();
 
         tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound { path: file_path.clone(), source: e.to_string() })?;
-
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            renderer.render_page_tile_sync(&document, page_number, region, options)
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
+                renderer.render_page_tile_sync(document, page_number, region, options)
+            })
         })
         .await
         .map_err(|e| PdfError::render_error(page_number, "render_page_tile", format!("渲染任务失败: {}", e)))?
     }
 
+    /// 按网格渲染与视口相交的分块
+    pub async fn render_page_tiles(
+        &self,
+        page_number: u32,
+        grid: TileGrid,
+        options: RenderOptions,
+    ) -> Result<Vec<PageTile>, PdfError> {
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound { page: page_number, total_pages: self.get_page_count() });
+        }
+
+        let file_path = self.file_path.clone();
+        let cache = self.cache.clone();
+
+        tokio::task::spawn_blocking(move || {
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
+                renderer.render_page_tiles_sync(document, page_number, &grid, options)
+            })
+        })
+        .await
+        .map_err(|e| PdfError::render_error(page_number, "render_page_tiles", format!("渲染任务失败: {}", e)))?
+    }
+
     /// 渲染页面范围
     pub async fn render_page_range(
         &self,
@@ -457,73 +1046,461 @@ impl PdfEngine {
 
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
         
         tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            let mut results = Vec::new();
-            for page_num in start..=end {
-                let result = renderer.render_page_sync(&document, page_num, options.clone())?;
-                results.push(result);
-            }
-            Ok(results)
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                let mut results = Vec::new();
+                for page_num in start..=end {
+                    let result = renderer.render_page_sync(document, page_num, options.clone())?;
+                    results.push(result);
+                }
+                Ok(results)
+            })
         })
         .await
         .map_err(|e| PdfError::render_error(0, "render_page_range", format!("渲染任务失败: {}", e)))?
     }
 
     /// 并行渲染多个页面
+    /// 并发数上限为运行时配置的 `render_concurrency()`，每个 worker 只加载一次文档并顺序渲染分配到的页面
     pub async fn render_pages_parallel(
         &self,
         page_numbers: Vec<u32>,
         options: RenderOptions,
     ) -> Vec<Result<RenderResult, PdfError>> {
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(render_concurrency());
+        self.render_pages_with_workers(page_numbers, options, workers).await
+    }
+
+    /// 按固定 worker 数分块渲染页面，结果顺序与 `page_numbers` 保持一致
+    async fn render_pages_with_workers(
+        &self,
+        page_numbers: Vec<u32>,
+        options: RenderOptions,
+        workers: usize,
+    ) -> Vec<Result<RenderResult, PdfError>> {
+        if page_numbers.is_empty() {
+            return Vec::new();
+        }
+
+        let workers = workers.clamp(1, MAX_RENDER_WORKERS).min(page_numbers.len());
+        let chunk_size = page_numbers.len().div_ceil(workers);
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
-        
+        let monitor = self.performance_monitor.clone();
+
         let handles: Vec<_> = page_numbers
-            .into_iter()
-            .map(|page_num| {
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let pages = chunk.to_vec();
                 let file_path = file_path.clone();
                 let cache = cache.clone();
+                let monitor = monitor.clone();
                 let options = options.clone();
-                
-                tokio::task::spawn_blocking(move || {
-                    let pdfium = Arc::new(Self::create_pdfium()?);
-                    let document = pdfium
-                        .load_pdf_from_file(&file_path, None)
-                        .map_err(|e| PdfError::FileNotFound {
-                            path: file_path.clone(),
-                            source: e.to_string(),
-                        })?;
-                    
-                    let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-                    renderer.render_page_sync(&document, page_num, options)
-                })
+
+                let handle = tokio::task::spawn_blocking(move || {
+                    with_cached_document(&file_path, |pdfium, document| {
+                        let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                            .with_performance_monitor(monitor.clone());
+                        Ok::<_, PdfError>(
+                            pages
+                                .iter()
+                                .map(|&page_num| renderer.render_page_sync(document, page_num, options.clone()))
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                });
+                (chunk.len(), handle)
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(page_numbers.len());
+        for (len, handle) in handles {
+            match handle.await {
+                Ok(Ok(chunk_results)) => results.extend(chunk_results),
+                // 文档加载失败时，该 worker 负责的所有页面都返回同一错误
+                Ok(Err(err)) => results.extend((0..len).map(|_| Err(err.clone()))),
+                Err(e) => {
+                    let err = PdfError::render_error(0, "render_pages_parallel", format!("渲染任务失败: {}", e));
+                    results.extend((0..len).map(|_| Err(err.clone())));
+                }
+            }
+        }
+
+        results
+    }
+
+    /// 确保一组页面都已在渲染缓存中：已缓存的跳过，缺失的分给多个 worker 并发渲染
+    /// 每页开始渲染前调用 `should_render`，返回 false 时不再渲染该页（如窗口已移走），状态记为 Cancelled
+    pub async fn ensure_pages_cached<F>(
+        &self,
+        page_numbers: Vec<u32>,
+        options: RenderOptions,
+        should_render: F,
+    ) -> Vec<PageCacheState>
+    where
+        F: Fn(u32) -> bool + Send + Sync + 'static,
+    {
+        let page_count = self.get_page_count();
+        let mut states = Vec::with_capacity(page_numbers.len());
+        let mut missing = Vec::new();
+        for page in page_numbers {
+            if page < 1 || page > page_count {
+                states.push(PageCacheState {
+                    page,
+                    status: PageCacheStatus::Failed,
+                    error: Some(PdfError::PageNotFound { page, total_pages: page_count }.to_string()),
+                });
+                continue;
+            }
+            match self.render_cache_key(page, &options) {
+                Some(key) if self.cache.contains(&key).await => states.push(PageCacheState {
+                    page,
+                    status: PageCacheStatus::Cached,
+                    error: None,
+                }),
+                _ => missing.push(page),
+            }
+        }
+        if missing.is_empty() {
+            return states;
+        }
+
+        let workers = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(render_concurrency())
+            .min(missing.len());
+        // 轮流分配给各 worker，保证调用方排在前面的页面（如离当前页最近的）最先渲染
+        let mut buckets = vec![Vec::new(); workers];
+        for (i, page) in missing.into_iter().enumerate() {
+            buckets[i % workers].push(page);
+        }
+        let should_render = Arc::new(should_render);
+
+        let handles: Vec<_> = buckets
+            .into_iter()
+            .map(|pages| {
+                let chunk = pages.clone();
+                let file_path = self.file_path.clone();
+                let renderer_cache = self.cache.clone();
+                let monitor = self.performance_monitor.clone();
+                let options = options.clone();
+                let should_render = Arc::clone(&should_render);
+
+                let handle = tokio::task::spawn_blocking(move || {
+                    with_cached_document(&file_path, |pdfium, document| {
+                        let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), renderer_cache)
+                            .with_performance_monitor(monitor.clone());
+                        Ok::<_, PdfError>(
+                            pages
+                                .iter()
+                                .map(|&page| {
+                                    if !should_render(page) {
+                                        return (PageCacheStatus::Cancelled, None);
+                                    }
+                                    match renderer.render_page_sync(document, page, options.clone()) {
+                                        Ok(_) => (PageCacheStatus::Rendered, None),
+                                        Err(e) => (PageCacheStatus::Failed, Some(e.to_string())),
+                                    }
+                                })
+                                .collect::<Vec<_>>(),
+                        )
+                    })
+                });
+                (chunk, handle)
             })
             .collect();
 
-        let mut results = Vec::new();
-        for handle in handles {
-            let result = handle
-                .await
-                .map_err(|e| PdfError::render_error(0, "render_pages_parallel", format!("渲染任务失败: {}", e)))
-                .and_then(|r| r);
-            results.push(result);
+        for (pages, handle) in handles {
+            let outcomes = match handle.await {
+                Ok(Ok(outcomes)) => outcomes,
+                Ok(Err(err)) => pages.iter().map(|_| (PageCacheStatus::Failed, Some(err.to_string()))).collect(),
+                Err(e) => {
+                    let message = format!("渲染任务失败: {}", e);
+                    pages.iter().map(|_| (PageCacheStatus::Failed, Some(message.clone()))).collect()
+                }
+            };
+            states.extend(
+                pages
+                    .into_iter()
+                    .zip(outcomes)
+                    .map(|(page, (status, error))| PageCacheState { page, status, error }),
+            );
+        }
+
+        states.sort_by_key(|state| state.page);
+        states
+    }
+
+    /// 批量渲染一段页码的缩略图
+    /// 只加载一次文档，并复用引擎级缩略图缓存；页数超过 `MAX_THUMBNAIL_BATCH_PAGES` 时需要调用方分批请求
+    pub async fn render_thumbnails(
+        &self,
+        start_page: u32,
+        end_page: u32,
+        options: RenderOptions,
+    ) -> Result<Vec<PageThumbnail>, PdfError> {
+        let page_count = self.get_page_count();
+        if start_page < 1 || start_page > page_count {
+            return Err(PdfError::page_not_found(start_page, page_count));
+        }
+        if end_page < start_page || end_page > page_count {
+            return Err(PdfError::invalid_param(
+                "end_page",
+                end_page.to_string(),
+                format!("{}..={}", start_page, page_count),
+            ));
+        }
+        let requested = end_page - start_page + 1;
+        if requested > MAX_THUMBNAIL_BATCH_PAGES {
+            return Err(PdfError::invalid_param(
+                "page_range",
+                format!("{}-{} (共 {} 页)", start_page, end_page, requested),
+                format!("单次最多 {} 页，请分批请求", MAX_THUMBNAIL_BATCH_PAGES),
+            ));
+        }
+
+        self.render_thumbnail_pages((start_page..=end_page).collect(), options).await
+    }
+
+    /// 渲染任意一组页码的缩略图（如书签所在页），同样复用引擎级缩略图缓存、只加载一次文档
+    /// 单页渲染失败不影响整批：该页返回占位图，真实错误附在 `RenderResult.error`
+    pub async fn render_thumbnail_pages(
+        &self,
+        page_numbers: Vec<u32>,
+        options: RenderOptions,
+    ) -> Result<Vec<PageThumbnail>, PdfError> {
+        let page_count = self.get_page_count();
+        if let Some(&page) = page_numbers.iter().find(|&&p| p < 1 || p > page_count) {
+            return Err(PdfError::page_not_found(page, page_count));
+        }
+        if page_numbers.len() > MAX_THUMBNAIL_BATCH_PAGES as usize {
+            return Err(PdfError::invalid_param(
+                "page_numbers",
+                format!("共 {} 页", page_numbers.len()),
+                format!("单次最多 {} 页，请分批请求", MAX_THUMBNAIL_BATCH_PAGES),
+            ));
+        }
+        let Some(&first_page) = page_numbers.first() else {
+            return Ok(Vec::new());
+        };
+
+        let options = RenderOptions {
+            quality: RenderQuality::Thumbnail,
+            fit_to_width: options.width.is_some(),
+            ..options
+        };
+        let file_path = self.file_path.clone();
+        let cache = self.cache.clone();
+        let thumb_cache = self.thumb_cache.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_caches(file_path.clone(), pdfium.clone(), cache, thumb_cache);
+                let thumbnails = renderer
+                    .render_thumbnails_sync(document, &page_numbers, options.clone())
+                    .into_iter()
+                    .map(|(page, result)| -> Result<PageThumbnail, PdfError> {
+                        let result = match result {
+                            Ok(result) => result,
+                            Err(e) => {
+                                let message = format!("缩略图渲染失败: file={}, page={}, {}", file_path, page, e);
+                                write_log("error", "PDF", &message);
+                                let page_size = document
+                                    .pages()
+                                    .get((page - 1) as u16)
+                                    .ok()
+                                    .map(|p| (p.width().value, p.height().value));
+                                render_error_placeholder(page, page_size, &options, e.to_string())?
+                            }
+                        };
+                        Ok(PageThumbnail { page, result })
+                    })
+                    .collect::<Result<Vec<_>, PdfError>>()?;
+
+                println!(
+                    "[backend] 缩略图 {} 页渲染完成（首页 {}）, 耗时: {}ms",
+                    page_numbers.len(),
+                    first_page,
+                    start.elapsed().as_millis()
+                );
+                Ok(thumbnails)
+            })
+        })
+        .await
+        .map_err(|e| PdfError::render_error(first_page, "render_thumbnails", format!("缩略图任务失败: {}", e)))?
+    }
+
+    /// 把一组页面的缩略图拼成联系表（页数上限同批量缩略图），缩略图复用缓存，拼接与编码在工作线程完成
+    pub async fn render_contact_sheet(
+        &self,
+        page_numbers: Vec<u32>,
+        cols: u32,
+        options: RenderOptions,
+    ) -> Result<PdfContactSheet, PdfError> {
+        if page_numbers.is_empty() {
+            return Err(PdfError::invalid_param("page_numbers", "[]", "至少 1 页"));
+        }
+        if cols == 0 {
+            return Err(PdfError::invalid_param("cols", "0", ">= 1"));
+        }
+        let thumbnails = self.render_thumbnail_pages(page_numbers, options).await?;
+
+        tokio::task::spawn_blocking(move || {
+            let start = std::time::Instant::now();
+            let sheet = contact_sheet::compose_contact_sheet(&thumbnails, cols)?;
+            println!(
+                "[backend] 联系表拼接完成: {} 页, {}x{}, {} bytes, 耗时: {}ms",
+                sheet.cells.len(),
+                sheet.result.width,
+                sheet.result.height,
+                sheet.result.image_data.len(),
+                start.elapsed().as_millis()
+            );
+            Ok(sheet)
+        })
+        .await
+        .map_err(|e| PdfError::render_error(0, "render_contact_sheet", format!("联系表任务失败: {}", e)))?
+    }
+
+    /// 提取页面文本
+    pub fn extract_page_text(&self, page_number: u32) -> Result<PageText, PdfError> {
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound {
+                page: page_number,
+                total_pages: self.get_page_count(),
+            });
+        }
+
+        self.with_document(|_pdfium, document| {
+            let page = document.pages().get((page_number - 1) as u16).map_err(|e| {
+                PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
+            })?;
+
+            let text = page.text().map_err(|e| {
+                PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string())
+            })?;
+
+            // 含 RTL 文字的页面按逻辑顺序输出，纯 LTR 页面保持 pdfium 原有顺序
+            let boxes = PageBoxes::read(&page);
+            let full_text = text.all();
+            let has_rtl = bidi::has_rtl(&full_text);
+            let full_text = if has_rtl { logical_text(&text.chars(), &boxes) } else { full_text };
+            
+            let mut blocks = Vec::new();
+            for segment in text.segments().iter() {
+                let segment_text = match segment.chars() {
+                    Ok(chars) if has_rtl => logical_text(&chars, &boxes),
+                    _ => segment.text(),
+                };
+                // 只添加非空文本
+                if !segment_text.trim().is_empty() {
+                    let bounds = segment.bounds();
+                    blocks.push(TextBlock {
+                        text: segment_text,
+                        position: TextPosition {
+                            x: boxes.x(bounds.left().value),
+                            y: boxes.y(bounds.top().value),
+                            width: bounds.width().value,
+                            height: bounds.height().value,
+                        },
+                        font_size: 12.0, // 暂定默认值，Pdfium 复杂 API 可获取准确值
+                        font_name: None,
+                    });
+                }
+            }
+
+            Ok(PageText {
+                page_number,
+                blocks,
+                full_text,
+            })
+        })
+    }
+
+    /// 按阅读顺序提取页面文本：双栏/多栏页面逐栏输出，并过滤页眉页脚
+    pub fn extract_reflow_text(&self, page_number: u32) -> Result<ReflowText, PdfError> {
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound {
+                page: page_number,
+                total_pages: self.get_page_count(),
+            });
+        }
+
+        self.with_document(|_pdfium, document| {
+            let page = document.pages().get((page_number - 1) as u16).map_err(|e| {
+                PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
+            })?;
+
+            let text = page.text().map_err(|e| {
+                PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string())
+            })?;
+
+            // 片段坐标换算到 CropBox 基准，与下面的页面宽高一致，页眉页脚与分栏判断才不会偏移
+            let boxes = PageBoxes::read(&page);
+            let fragments = text
+                .segments()
+                .iter()
+                .filter_map(|segment| {
+                    let segment_text = segment.text();
+                    if segment_text.trim().is_empty() {
+                        return None;
+                    }
+                    let bounds = segment.bounds();
+                    Some(TextFragment {
+                        text: segment_text,
+                        left: boxes.x(bounds.left().value),
+                        top: boxes.y(bounds.top().value),
+                        right: boxes.x(bounds.right().value),
+                        bottom: boxes.y(bounds.bottom().value),
+                    })
+                })
+                .collect();
+
+            Ok(reflow::reflow_page_text(
+                page_number,
+                fragments,
+                page.width().value,
+                page.height().value,
+                text.all(),
+            ))
+        })
+    }
+
+    /// 读取页面表单字段（只读）
+    pub fn get_form_fields(&self, page_number: u32) -> Result<PageFormFields, PdfError> {
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound {
+                page: page_number,
+                total_pages: self.get_page_count(),
+            });
+        }
+
+        self.with_document(|_pdfium, document| forms::extract_page_form_fields(document, page_number))
+    }
+
+    /// 读取页面每个字符的矩形，`region` 不为空时只返回与该区域相交的字符
+    pub fn get_char_boxes(&self, page_number: u32, region: Option<&FormFieldRect>) -> Result<PageCharBoxes, PdfError> {
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound {
+                page: page_number,
+                total_pages: self.get_page_count(),
+            });
         }
 
-        results
+        self.with_document(|_pdfium, document| char_boxes::extract_page_char_boxes(document, page_number, region))
     }
 
-    /// 提取页面文本
-    pub fn extract_page_text(&self, page_number: u32) -> Result<PageText, PdfError> {
+    /// 读取页面注释（高亮、下划线、便签等，只读）
+    pub fn get_annotations(&self, page_number: u32) -> Result<PageAnnotations, PdfError> {
         if page_number < 1 || page_number > self.get_page_count() {
             return Err(PdfError::PageNotFound {
                 page: page_number,
@@ -531,51 +1508,71 @@ impl PdfEngine {
             });
         }
 
-        self.with_document(|_pdfium, document| {
-            let page = document.pages().get((page_number - 1) as u16).map_err(|e| {
-                PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
-            })?;
+        self.with_document(|_pdfium, document| annotations::extract_page_annotations(document, page_number))
+    }
 
-            let text = page.text().map_err(|e| {
-                PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string())
-            })?;
+    /// 列出文档嵌入文件（附件），没有附件时返回空列表
+    pub fn list_attachments(&self) -> Result<Vec<PdfAttachmentInfo>, PdfError> {
+        self.with_document(|_pdfium, document| Ok(attachments::list_attachments(document)))
+    }
 
-            let full_text = text.all();
-            
-            let mut blocks = Vec::new();
-            for segment in text.segments().iter() {
-                let segment_text = segment.text();
-                // 只添加非空文本
-                if !segment_text.trim().is_empty() {
-                    let bounds = segment.bounds();
-                    blocks.push(TextBlock {
-                        text: segment_text,
-                        position: TextPosition {
-                            x: bounds.left.value,
-                            y: bounds.top.value,
-                            width: bounds.width().value,
-                            height: bounds.height().value,
-                        },
-                        font_size: 12.0, // 暂定默认值，Pdfium 复杂 API 可获取准确值
-                        font_name: None,
-                    });
-                }
-            }
+    /// 按名称导出附件到 `out_path`，返回写入的字节数
+    pub fn extract_attachment(&self, name: &str, out_path: &str) -> Result<u64, PdfError> {
+        self.with_document(|_pdfium, document| attachments::extract_attachment(document, name, out_path))
+    }
 
-            Ok(PageText {
-                page_number,
-                blocks,
-                full_text,
-            })
+    /// 对单页做 OCR（需启用 `ocr` feature），`lang` 为 tesseract 语言包（如 `chi_sim+eng`）
+    /// 结果按语言写入磁盘 sidecar 文件，再次调用直接读取
+    pub async fn ocr_page(&self, page_number: u32, lang: Option<&str>) -> Result<PageOcrResult, PdfError> {
+        let info = self.get_page_info(page_number).await?;
+        let lang = ocr::normalize_lang(lang)?;
+        let ocr_dir = self.file_hash.as_deref().map(pdf_ocr_cache_dir);
+        if let Some(cached) = ocr_dir.as_deref().and_then(|dir| ocr::load_sidecar(dir, page_number, Some(&lang))) {
+            return Ok(cached);
+        }
+
+        let file_path = self.file_path.clone();
+        let cache = self.cache.clone();
+        let options = RenderOptions {
+            quality: RenderQuality::Best,
+            width: Some(ocr::render_width(info.width)),
+            fit_to_width: true,
+            format: Some(ImageFormat::Png),
+            max_pixels: Some(ocr::OCR_MAX_PIXELS),
+            ..RenderOptions::default()
+        };
+        let result = tokio::task::spawn_blocking(move || {
+            let image = with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
+                renderer.render_page_image_sync(document, page_number, &options)
+            })?;
+            let tsv = ocr::recognize_tsv(&image, &lang, page_number)?;
+            let scale = (info.width / image.width() as f32, info.height / image.height() as f32);
+            Ok::<_, PdfError>(ocr::build_result(page_number, &lang, (info.width, info.height), scale, &tsv))
         })
+        .await
+        .map_err(|e| PdfError::render_error(page_number, "ocr_page", format!("OCR 任务失败: {}", e)))??;
+
+        println!(
+            "[PdfEngine] OCR 完成: file={}, page={}, lang={}, words={}",
+            self.file_path,
+            page_number,
+            result.lang,
+            result.words.len()
+        );
+        if let Some(dir) = &ocr_dir {
+            ocr::save_sidecar(dir, &result);
+        }
+        Ok(result)
     }
 
-    /// 搜索文本
+    /// 搜索文本；没有原生文字层的页面回退使用已缓存的 OCR 结果
     pub fn search_text(
         &self,
         query: &str,
         case_sensitive: bool,
     ) -> Result<Vec<SearchResult>, PdfError> {
+        let ocr_dir = self.file_hash.as_deref().map(pdf_ocr_cache_dir);
         self.with_document(|_pdfium, document| {
             let mut results = Vec::new();
             let pages = document.pages();
@@ -590,6 +1587,21 @@ impl PdfEngine {
                 })?;
 
                 let page_text = text.all();
+                if page_text.trim().is_empty() {
+                    let ocr_result = ocr_dir
+                        .as_deref()
+                        .and_then(|dir| ocr::load_sidecar(dir, page_index as u32 + 1, None));
+                    if let Some(ocr_result) = ocr_result {
+                        results.extend(ocr::search_ocr(&ocr_result, query, case_sensitive));
+                    }
+                    continue;
+                }
+                // RTL 页面按逻辑顺序逐字符匹配，命中位置取字符边界
+                if bidi::has_rtl(&page_text) {
+                    let chars = bidi::to_logical_order(&collect_page_chars(&text.chars(), &PageBoxes::read(&page)));
+                    results.extend(search_logical_chars(page_index as u32 + 1, &chars, query, case_sensitive));
+                    continue;
+                }
                 let search_text = if case_sensitive {
                     page_text.clone()
                 } else {
@@ -651,6 +1663,70 @@ impl PdfEngine {
         })
     }
 
+    /// 导出文本到文件
+    /// 按页提取并根据文本片段位置还原换行与段落，可选在页间插入 `--- Page N ---` 标记
+    pub fn export_text(
+        &self,
+        out_path: &str,
+        page_range: Option<(u32, u32)>,
+        with_page_markers: bool,
+    ) -> Result<String, PdfError> {
+        let page_count = self.get_page_count();
+        let (start, end) = page_range.unwrap_or((1, page_count));
+        if start < 1 || start > end || end > page_count {
+            return Err(PdfError::invalid_param(
+                "page_range",
+                format!("{}-{}", start, end),
+                format!("1-{}", page_count),
+            ));
+        }
+
+        let content = self.with_document(|_pdfium, document| {
+            let pages = document.pages();
+            let mut content = String::new();
+            let mut has_text = false;
+
+            for page_number in start..=end {
+                let page = pages.get((page_number - 1) as u16).map_err(|e| {
+                    PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
+                })?;
+                let text = page.text().map_err(|e| {
+                    PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string())
+                })?;
+
+                let page_text = layout_page_text(&text);
+                has_text |= !page_text.trim().is_empty();
+
+                if with_page_markers {
+                    content.push_str(&format!("--- Page {} ---\n", page_number));
+                } else if page_number > start {
+                    content.push('\n');
+                }
+                content.push_str(page_text.trim_end());
+                content.push('\n');
+            }
+
+            if !has_text {
+                return Err(PdfError::unsupported_feature(
+                    "无可提取文本（可能是没有文字层的扫描版 PDF）",
+                    None,
+                ));
+            }
+            Ok(content)
+        })?;
+
+        if let Some(parent) = std::path::Path::new(out_path).parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| PdfError::io_error(Some(parent.to_string_lossy().to_string()), e))?;
+            }
+        }
+        std::fs::write(out_path, content)
+            .map_err(|e| PdfError::io_error(Some(out_path.to_string()), e))?;
+
+        Ok(out_path.to_string())
+    }
+
     /// 获取文档大纲（书签）
     pub fn get_outline(&self) -> Result<PdfOutline, PdfError> {
         self.with_document(|_pdfium, document| {
@@ -659,76 +1735,107 @@ impl PdfEngine {
         })
     }
 
-    /// 提取书签
-    /// 直接通过 `PdfBookmark::children()` 递归构建树，避免依赖 `children_len()` 在部分文档上返回不准确导致层级被扁平化
-    fn extract_bookmarks(&self, document: &PdfDocument<'_>) -> Result<Vec<Bookmark>, PdfError> {
-        let mut roots = Vec::new();
-        let bookmarks = document.bookmarks();
-        if let Some(root) = bookmarks.root() {
-            fn add_node<'a>(engine: &PdfEngine, roots: &mut Vec<Bookmark>, bm: &PdfBookmark<'a>) -> Result<(), PdfError> {
-                let node = engine.build_bookmark_tree(bm, 0)?;
-                let duplicated = roots
-                    .iter()
-                    .any(|b: &Bookmark| b.title == node.title && b.page_number == node.page_number);
-                if !duplicated {
-                    roots.push(node);
-                }
-                Ok(())
-            }
-
-            add_node(self, &mut roots, &root)?;
-
-            for top in root.iter_siblings() {
-                add_node(self, &mut roots, &top)?;
-            }
+    /// 当前页所属的目录章节及章内进度；文档没有可定位的目录时返回 None
+    /// 展开后的目录在引擎内缓存，连续翻页时不会重复读取 outline
+    pub fn current_chapter(&self, page: u32) -> Result<Option<PdfChapterProgress>, PdfError> {
+        let page_count = self.get_page_count();
+        if page < 1 || page > page_count {
+            return Err(PdfError::page_not_found(page, page_count));
+        }
 
-            if roots.is_empty() {
-                for child in root.iter_direct_children() {
-                    add_node(self, &mut roots, &child)?;
+        let cached = self.outline_chapters.lock().ok().and_then(|c| c.clone());
+        let chapters = match cached {
+            Some(chapters) => chapters,
+            None => {
+                let chapters = Arc::new(flatten_outline(&self.get_outline()?.bookmarks));
+                if let Ok(mut cache) = self.outline_chapters.lock() {
+                    *cache = Some(Arc::clone(&chapters));
                 }
+                chapters
             }
+        };
+        Ok(locate_chapter(&chapters, page, page_count))
+    }
+
+    /// 提取书签
+    /// pdfium 的 `root()` 是大纲的第一个顶层书签而非容器节点：顶层书签为它及其后续同级节点，
+    /// 各节点的子书签只通过 `first_child()` 进入，不依赖 `children_len()`（部分文档上返回不准确）
+    fn extract_bookmarks(&self, document: &PdfDocument<'_>) -> Result<Vec<Bookmark>, PdfError> {
+        let mut budget = MAX_OUTLINE_ITEMS;
+        let roots = build_outline_level(document.bookmarks().root(), 0, &mut budget);
+        if budget == 0 {
+            eprintln!("[PdfEngine] 书签数量超过上限 {}，目录可能不完整", MAX_OUTLINE_ITEMS);
         }
         Ok(roots)
     }
 
-    /// 递归构建书签树
-    fn build_bookmark_tree<'a>(&self, pdf_bookmark: &PdfBookmark<'a>, level: u32) -> Result<Bookmark, PdfError> {
-        let title = pdf_bookmark.title().unwrap_or_default();
-        let page_number = if let Some(dest) = pdf_bookmark.destination() {
-            dest.page_index().unwrap_or(0) as u32 + 1
-        } else {
-            0
-        };
-
-        let mut children = Vec::new();
-        for child in pdf_bookmark.iter_direct_children() {
-            children.push(self.build_bookmark_tree(&child, level + 1)?);
+    /// 解析书签目标页码（从 1 开始）
+    /// 依次尝试书签自身的目标和 GoTo 动作的目标；两者在 pdfium 内部都会按名称树解析具名目标
+    fn resolve_bookmark_page(pdf_bookmark: &PdfBookmark<'_>) -> Option<u32> {
+        if let Some(index) = pdf_bookmark
+            .destination()
+            .and_then(|dest| dest.page_index().ok())
+        {
+            return Some(index as u32 + 1);
         }
 
-        Ok(Bookmark { title, page_number, level, children })
+        match pdf_bookmark.action()? {
+            PdfAction::LocalDestination(action) => action
+                .destination()
+                .ok()
+                .and_then(|dest| dest.page_index().ok())
+                .map(|index| index as u32 + 1),
+            _ => None,
+        }
     }
 
-    /// 获取页面信息
-    pub fn get_page_info(&self, page_number: u32) -> Result<PdfPageInfo, PdfError> {
-        let info = self.document_info.as_ref().ok_or(PdfError::ParseError {
-            page: None,
-            message: "文档信息未加载".to_string(),
-            source: String::new(),
-        })?;
-
-        info.pages
-            .iter()
-            .find(|p| p.number == page_number)
-            .cloned()
-            .ok_or(PdfError::PageNotFound {
+    /// 获取页面信息；后台尚未填充的页在阻塞线程中即时读取单页并写回
+    pub async fn get_page_info(&self, page_number: u32) -> Result<PdfPageInfo, PdfError> {
+        if self.document_info.is_none() {
+            return Err(PdfError::ParseError {
+                page: None,
+                message: "文档信息未加载".to_string(),
+                source: String::new(),
+            });
+        }
+        if page_number < 1 || page_number > self.get_page_count() {
+            return Err(PdfError::PageNotFound {
                 page: page_number,
                 total_pages: self.get_page_count(),
-            })
+            });
+        }
+
+        if let Some(info) = self.cached_page_info(page_number) {
+            return Ok(info);
+        }
+
+        let file_path = self.file_path.clone();
+        let info = tokio::task::spawn_blocking(move || {
+            with_cached_document(&file_path, |_, document| read_page_info(document.pages(), page_number))
+        })
+        .await
+        .map_err(|e| PdfError::parse_error(Some(page_number), "读取页面信息任务失败", e.to_string()))??;
+        if let Ok(mut infos) = self.page_infos.lock() {
+            if let Some(slot) = infos.get_mut((page_number - 1) as usize) {
+                *slot = Some(info.clone());
+            }
+        }
+        Ok(info)
     }
 
-    /// 获取文档信息
-    pub fn get_document_info(&self) -> Option<&PdfDocumentInfo> {
-        self.document_info.as_ref()
+    /// 已读取的页面信息，不触发即时查询
+    fn cached_page_info(&self, page_number: u32) -> Option<PdfPageInfo> {
+        let infos = self.page_infos.lock().ok()?;
+        infos.get(page_number.checked_sub(1)? as usize)?.clone()
+    }
+
+    /// 获取文档信息，`pages` 只包含已读取的页，全部就绪前 `pages_complete` 为 false
+    pub fn get_document_info(&self) -> Option<PdfDocumentInfo> {
+        let info = self.document_info.clone()?;
+        match self.page_infos.lock() {
+            Ok(infos) => Some(merge_page_infos(info, &infos)),
+            Err(_) => Some(info),
+        }
     }
 
     /// 获取页面总数
@@ -752,87 +1859,145 @@ impl PdfEngine {
     /// 清除缓存
     pub async fn clear_cache(&self) {
         BookRenderCache::cache_clear_all(&self.cache).await;
+        BookRenderCache::cache_clear_all(&self.thumb_cache).await;
     }
 
-    /// 清除指定页面的缓存
-    pub async fn clear_page_cache(&self, page_number: u32) {
-        BookRenderCache::cache_clear_page(&self.cache, &self.file_path, page_number).await;
+    /// 清除指定页面的内存与磁盘缓存，返回清除的条目数
+    pub async fn clear_page_cache(&self, page_number: u32) -> usize {
+        let memory = BookRenderCache::cache_clear_page(&self.cache, &self.file_path, page_number).await
+            + BookRenderCache::cache_clear_page(&self.thumb_cache, &self.file_path, page_number).await;
+        // 磁盘缓存目录按当前文件版本命名，与 render_page_to_file 一致
+        let disk = compute_file_hash(&self.file_path)
+            .ok()
+            .or_else(|| self.file_hash.clone())
+            .map_or(0, |hash| remove_page_disk_cache(&hash, page_number));
+        memory + disk
     }
 
     /// 关闭文档
     pub fn close(&mut self) {
         self.document_info = None;
+        self.page_infos = PageInfoStore::default();
         self.file_path.clear();
+        if let Ok(mut chapters) = self.outline_chapters.lock() {
+            *chapters = None;
+        }
     }
 
     /// 预热缓存
     pub async fn warmup_cache(&self, strategy: WarmupStrategy) -> Result<(), PdfError> {
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
         let page_count = self.get_page_count();
         let pages_to_render = strategy.get_pages_to_render(page_count);
         let quality = strategy.quality();
 
         tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            for page in pages_to_render {
-                let options = RenderOptions {
-                    quality: quality.clone(),
-                    ..Default::default()
-                };
-                let _ = renderer.render_page_sync(&document, page, options);
-            }
-            Ok(())
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                for page in pages_to_render {
+                    let options = RenderOptions {
+                        quality: quality.clone(),
+                        ..Default::default()
+                    };
+                    let _ = renderer.render_page_sync(document, page, options);
+                }
+                Ok(())
+            })
         })
         .await
         .map_err(|e| PdfError::render_error(0, "warmup_cache", format!("预热任务失败: {}", e)))?
     }
 
+    /// 记录一次翻页，供预测器识别顺序阅读/跳读模式和阅读速度
+    pub fn record_navigation(&self, from_page: u32, to_page: u32, timestamp_ms: u64) -> Option<PredictorStatistics> {
+        let mut predictor = self.predictor.lock().ok()?;
+        predictor.record_navigation(from_page, to_page, timestamp_ms);
+        Some(predictor.get_statistics())
+    }
+
+    /// 根据翻页行为预测接下来可能访问的页面（按优先级降序，不含当前页）
+    /// 尚无翻页记录时返回空列表，避免在未接入导航上报时额外渲染
+    pub fn predicted_pages(&self, current_page: u32) -> Vec<u32> {
+        let Ok(predictor) = self.predictor.lock() else {
+            return Vec::new();
+        };
+        if predictor.history_size() == 0 {
+            return Vec::new();
+        }
+        predictor
+            .predict_next_pages(current_page, self.get_page_count())
+            .into_iter()
+            .map(|(page, _)| page)
+            .filter(|&page| page >= 1 && page != current_page && page <= self.get_page_count())
+            .take(MAX_PREDICTED_PRELOAD_PAGES)
+            .collect()
+    }
+
+    /// 按预测结果预加载页面，使用与当前页相同的渲染参数以便命中缓存
+    /// 渲染在阻塞线程中进行，返回的句柄不借用引擎，调用方可先释放引擎锁再等待
+    /// 无预测页面时返回 None；任务完成后返回实际预加载的页码
+    pub fn spawn_predicted_preload(
+        &self,
+        current_page: u32,
+        options: RenderOptions,
+    ) -> Option<tokio::task::JoinHandle<Result<Vec<u32>, PdfError>>> {
+        let pages = self.predicted_pages(current_page);
+        if pages.is_empty() {
+            return None;
+        }
+
+        let file_path = self.file_path.clone();
+        let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
+
+        Some(tokio::task::spawn_blocking(move || {
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                for &page in &pages {
+                    let _ = renderer.render_page_sync(document, page, options.clone());
+                }
+                Ok(pages)
+            })
+        }))
+    }
+
     /// 预加载页面
-    pub async fn preload_pages(
+    /// 与 `spawn_predicted_preload` 相同，返回的句柄不借用引擎，等待前应先释放引擎锁
+    pub fn spawn_preload_pages(
         &self,
         start_page: u32,
         end_page: u32,
         quality: RenderQuality,
-    ) -> Result<(), PdfError> {
+    ) -> tokio::task::JoinHandle<Result<(), PdfError>> {
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
         let page_count = self.get_page_count();
         let start = start_page.max(1);
         let end = end_page.min(page_count);
 
         tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            for page in start..=end {
-                let options = RenderOptions {
-                    quality: quality.clone(),
-                    ..Default::default()
-                };
-                let _ = renderer.render_page_sync(&document, page, options);
-            }
-            Ok(())
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                for page in start..=end {
+                    let options = RenderOptions {
+                        quality: quality.clone(),
+                        ..Default::default()
+                    };
+                    let _ = renderer.render_page_sync(document, page, options);
+                }
+                Ok(())
+            })
         })
-        .await
-        .map_err(|e| PdfError::render_error(0, "preload_pages", format!("预加载任务失败: {}", e)))?
     }
 
-    /// 渐进式渲染页面
+    /// 渐进式渲染页面：从缩略图逐级提升到 `options.quality`，每完成一级回调一次
+    /// 回调返回 false 时跳过后续阶段（如页面已被新请求取代）
     pub async fn render_page_progressive<F>(
         &self,
         page_number: u32,
@@ -840,7 +2005,7 @@ impl PdfEngine {
         mut callback: F,
     ) -> Result<(), PdfError>
     where
-        F: FnMut(RenderQuality, RenderResult) + Send + 'static,
+        F: FnMut(RenderQuality, RenderResult) -> bool + Send + 'static,
     {
         let page_count = self.get_page_count();
         if page_number < 1 || page_number > page_count {
@@ -849,28 +2014,25 @@ impl PdfEngine {
 
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
         
         tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            
-            // 渐进式渲染：先低质量，再高质量
-            let qualities = vec![RenderQuality::Thumbnail, RenderQuality::Standard, RenderQuality::High];
-            for quality in qualities {
-                let mut opts = options.clone();
-                opts.quality = quality.clone();
-                let result = renderer.render_page_sync(&document, page_number, opts)?;
-                callback(quality, result);
-            }
-            
-            Ok(())
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+
+                // 渐进式渲染：先低质量，再高质量
+                for quality in progressive_stages(&options.quality) {
+                    let mut opts = options.clone();
+                    opts.quality = quality.clone();
+                    let result = renderer.render_page_sync(document, page_number, opts)?;
+                    if !callback(quality, result) {
+                        break;
+                    }
+                }
+
+                Ok(())
+            })
         })
         .await
         .map_err(|e| PdfError::render_error(page_number, "render_page_progressive", format!("渐进式渲染任务失败: {}", e)))?
@@ -884,23 +2046,19 @@ impl PdfEngine {
     ) -> Vec<Result<RenderResult, PdfError>> {
         let file_path = self.file_path.clone();
         let cache = self.cache.clone();
+        let monitor = self.performance_monitor.clone();
         
         match tokio::task::spawn_blocking(move || {
-            let pdfium = Arc::new(Self::create_pdfium()?);
-            let document = pdfium
-                .load_pdf_from_file(&file_path, None)
-                .map_err(|e| PdfError::FileNotFound {
-                    path: file_path.clone(),
-                    source: e.to_string(),
-                })?;
-            
-            let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache);
-            let mut results = Vec::new();
-            for page_num in page_numbers {
-                let result = renderer.render_page_sync(&document, page_num, options.clone());
-                results.push(result);
-            }
-            Ok::<Vec<Result<RenderResult, PdfError>>, PdfError>(results)
+            with_cached_document(&file_path, |pdfium, document| {
+                let renderer = PdfRenderer::with_cache(file_path.clone(), pdfium.clone(), cache)
+                    .with_performance_monitor(monitor.clone());
+                let mut results = Vec::new();
+                for page_num in page_numbers {
+                    let result = renderer.render_page_sync(document, page_num, options.clone());
+                    results.push(result);
+                }
+                Ok::<Vec<Result<RenderResult, PdfError>>, PdfError>(results)
+            })
         })
         .await
         {
@@ -910,17 +2068,76 @@ impl PdfEngine {
         }
     }
 
-    /// 使用自定义线程池渲染页面
+    /// 使用指定线程数渲染页面
+    /// PdfDocument 不是 Send，因此每个线程各自加载一次文档，`num_threads` 即同时存在的文档实例上限
     pub async fn render_pages_with_thread_pool(
         &self,
         page_numbers: Vec<u32>,
         options: RenderOptions,
-        _num_threads: usize,
+        num_threads: usize,
     ) -> Vec<Result<RenderResult, PdfError>> {
-        // 由于 PdfDocument 不是 Send，我们使用并行渲染而不是线程池
-        // 这里忽略 num_threads 参数，使用默认的并行策略
-        self.render_pages_parallel(page_numbers, options).await
+        self.render_pages_with_workers(page_numbers, options, num_threads).await
+    }
+}
+
+/// 渐进式渲染的各阶段：缩略图、标准，再到目标质量（目标不高于标准时到目标为止）
+fn progressive_stages(target: &RenderQuality) -> Vec<RenderQuality> {
+    match target {
+        RenderQuality::Thumbnail => vec![RenderQuality::Thumbnail],
+        RenderQuality::Standard => vec![RenderQuality::Thumbnail, RenderQuality::Standard],
+        high => vec![RenderQuality::Thumbnail, RenderQuality::Standard, high.clone()],
+    }
+}
+
+/// 页面尺寸聚合：最大宽度、最大高度，以及是否所有页尺寸一致（允许 PAGE_SIZE_TOLERANCE 点的误差）
+fn summarize_page_sizes(pages: &[PdfPageInfo]) -> (f32, f32, bool) {
+    let max_width = pages.iter().map(|p| p.width).fold(0.0, f32::max);
+    let max_height = pages.iter().map(|p| p.height).fold(0.0, f32::max);
+    let uniform = pages.windows(2).all(|w| {
+        (w[0].width - w[1].width).abs() <= PAGE_SIZE_TOLERANCE
+            && (w[0].height - w[1].height).abs() <= PAGE_SIZE_TOLERANCE
+    });
+    (max_width, max_height, uniform)
+}
+
+/// 按文本片段的位置还原页面排版：同一行内的片段拼接，换行处插入换行，行距明显增大时视为分段
+fn layout_page_text(text: &PdfPageText<'_>) -> String {
+    let mut out = String::new();
+    // 上一片段的 (top, bottom, right, 行高)
+    let mut prev: Option<(f32, f32, f32, f32)> = None;
+
+    for segment in text.segments().iter() {
+        let segment_text = segment.text();
+        let segment_text = segment_text.trim_end_matches(['\r', '\n']);
+        if segment_text.trim().is_empty() {
+            continue;
+        }
+
+        let bounds = segment.bounds();
+        let top = bounds.top().value;
+        let bottom = bounds.bottom().value;
+        let left = bounds.left().value;
+        let right = bounds.right().value;
+        let height = (top - bottom).abs().max(1.0);
+
+        if let Some((prev_top, prev_bottom, prev_right, prev_height)) = prev {
+            let same_line = (top - prev_top).abs() < height.min(prev_height) * 0.5;
+            if same_line {
+                if left - prev_right > height * 0.25 && !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            } else if prev_bottom - top > prev_height * 0.8 {
+                out.push_str("\n\n");
+            } else {
+                out.push('\n');
+            }
+        }
+
+        out.push_str(segment_text);
+        prev = Some((top, bottom, right, height));
     }
+
+    out
 }
 
 /// 预热策略
@@ -977,10 +2194,43 @@ impl WarmupStrategy {
     }
 }
 
+/// PDF 渲染运行时配置（缓存上限与并行渲染并发数），持久化在设置表中，启动时读取
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PdfRuntimeConfig {
+    pub max_cache_bytes: usize,
+    pub max_cache_items: usize,
+    pub max_render_concurrency: usize,
+}
+
+impl Default for PdfRuntimeConfig {
+    fn default() -> Self {
+        Self {
+            max_cache_bytes: 100 * 1024 * 1024,
+            max_cache_items: 50,
+            max_render_concurrency: DEFAULT_RENDER_WORKERS,
+        }
+    }
+}
+
+impl PdfRuntimeConfig {
+    /// 将各项限制在允许范围内
+    pub fn normalized(self) -> Self {
+        Self {
+            max_cache_bytes: self.max_cache_bytes.clamp(MIN_CACHE_BYTES, MAX_CACHE_BYTES),
+            max_cache_items: self.max_cache_items.clamp(1, MAX_CACHE_ITEMS),
+            max_render_concurrency: self.max_render_concurrency.clamp(1, MAX_RENDER_WORKERS),
+        }
+    }
+}
+
 /// PDF 引擎管理器
 pub struct PdfEngineManager {
     engines: Arc<RwLock<HashMap<String, Arc<RwLock<PdfEngine>>>>>,
     cache_manager: CacheManager,
+    /// 用户显式选择的页面输出格式，为 None 时按渲染质量自动选择
+    output_format: Option<ImageFormat>,
+    /// 用户设置的渲染标志（抗锯齿、注释、灰度等）
+    render_flags: RenderFlags,
 }
 
 impl PdfEngineManager {
@@ -989,6 +2239,8 @@ impl PdfEngineManager {
         Ok(Self {
             engines: Arc::new(RwLock::new(HashMap::new())),
             cache_manager: CacheManager::new(),
+            output_format: None,
+            render_flags: RenderFlags::default(),
         })
     }
 
@@ -997,6 +2249,8 @@ impl PdfEngineManager {
         Ok(Self {
             engines: Arc::new(RwLock::new(HashMap::new())),
             cache_manager: CacheManager::with_limits(max_size, max_items),
+            output_format: None,
+            render_flags: RenderFlags::default(),
         })
     }
 
@@ -1005,14 +2259,15 @@ impl PdfEngineManager {
         &self,
         file_path: &str,
     ) -> Result<Arc<RwLock<PdfEngine>>, PdfError> {
-        let engines = self.engines.read().await;
-
-        if let Some(engine) = engines.get(file_path) {
-            return Ok(Arc::clone(engine));
+        let existing = self.get_engine(file_path).await;
+        if let Some(engine) = existing {
+            if !engine.read().await.is_file_changed() {
+                return Ok(engine);
+            }
+            println!("[PdfEngine] 文件已变更，重新加载: {}", file_path);
+            self.invalidate_document(file_path).await;
         }
 
-        drop(engines);
-
         let mut engine = PdfEngine::with_cache(self.cache_manager.clone())?;
         engine.load_document(file_path).await?;
 
@@ -1032,12 +2287,45 @@ impl PdfEngineManager {
 
     /// 移除引擎
     pub async fn remove_engine(&self, file_path: &str) -> Option<Arc<RwLock<PdfEngine>>> {
+        doc_cache::invalidate_all();
         let mut engines = self.engines.write().await;
         engines.remove(file_path)
     }
 
+    /// 已打开文档的文件路径
+    pub async fn open_file_paths(&self) -> HashSet<String> {
+        self.engines.read().await.keys().cloned().collect()
+    }
+
+    /// 已打开文档的文件哈希（即磁盘渲染缓存目录名），清理临时渲染文件时跳过这些目录
+    pub async fn open_file_hashes(&self) -> HashSet<String> {
+        let engines = self.engines.read().await;
+        engines.keys().filter_map(|path| compute_file_hash(path).ok()).collect()
+    }
+
+    /// 使指定文件的引擎和所有缓存失效，下次访问时重新加载；返回是否存在已加载的引擎
+    pub async fn invalidate_document(&self, file_path: &str) -> bool {
+        let removed = self.remove_engine(file_path).await;
+        if let Some(engine) = &removed {
+            engine.read().await.remove_disk_cache();
+        }
+        self.cache_manager.clear_file(file_path).await;
+        removed.is_some()
+    }
+
+    /// 清除指定页面的缓存（旋转、裁剪等单页操作后使用），文档未加载时只清共享缓存与磁盘缓存；返回清除的条目数
+    pub async fn clear_page_cache(&self, file_path: &str, page_number: u32) -> usize {
+        if let Some(engine) = self.get_engine(file_path).await {
+            return engine.read().await.clear_page_cache(page_number).await;
+        }
+        let memory = self.cache_manager.clear_page(file_path, page_number).await;
+        let disk = compute_file_hash(file_path).map_or(0, |hash| remove_page_disk_cache(&hash, page_number));
+        memory + disk
+    }
+
     /// 清除所有引擎
     pub async fn clear_all(&self) {
+        doc_cache::invalidate_all();
         let mut engines = self.engines.write().await;
         engines.clear();
         self.cache_manager.clear().await;
@@ -1057,6 +2345,45 @@ impl PdfEngineManager {
     pub fn get_cache_manager_mut(&mut self) -> &mut CacheManager {
         &mut self.cache_manager
     }
+
+    /// 获取用户选择的页面输出格式
+    pub fn output_format(&self) -> Option<ImageFormat> {
+        self.output_format.clone()
+    }
+
+    /// 设置页面输出格式，None 表示恢复按质量自动选择
+    pub fn set_output_format(&mut self, format: Option<ImageFormat>) {
+        self.output_format = format;
+    }
+
+    /// 获取用户设置的渲染标志
+    pub fn render_flags(&self) -> RenderFlags {
+        self.render_flags.clone()
+    }
+
+    /// 设置渲染标志；标志计入缓存键，切换后不会读到旧设置下的缓存
+    pub fn set_render_flags(&mut self, flags: RenderFlags) {
+        self.render_flags = flags;
+    }
+
+    /// 当前生效的运行时配置
+    pub fn runtime_config(&self) -> PdfRuntimeConfig {
+        let (max_cache_bytes, max_cache_items) = self.cache_manager.limits();
+        PdfRuntimeConfig {
+            max_cache_bytes,
+            max_cache_items,
+            max_render_concurrency: render_concurrency(),
+        }
+    }
+
+    /// 应用运行时配置：调整缓存上限并立即淘汰超额条目，更新并行渲染并发上限；返回淘汰的条目数
+    pub async fn apply_runtime_config(&self, config: &PdfRuntimeConfig) -> usize {
+        let config = config.clone().normalized();
+        RENDER_CONCURRENCY.store(config.max_render_concurrency, Ordering::Relaxed);
+        self.cache_manager
+            .set_limits(config.max_cache_bytes, config.max_cache_items)
+            .await
+    }
 }
 
 impl Clone for PdfEngineManager {
@@ -1064,6 +2391,8 @@ impl Clone for PdfEngineManager {
         Self {
             engines: Arc::clone(&self.engines),
             cache_manager: self.cache_manager.clone(),
+            output_format: self.output_format.clone(),
+            render_flags: self.render_flags.clone(),
         }
     }
 }
@@ -1076,4 +2405,192 @@ mod tests {
     async fn test_engine_manager() {
         let _manager = PdfEngineManager::new();
     }
+
+    /// 以数组模拟的大纲节点：(标题, 页码, First, Next)
+    #[derive(Clone, Copy)]
+    struct TestNode {
+        tree: &'static [(&'static str, u32, Option<usize>, Option<usize>)],
+        index: usize,
+    }
+
+    impl OutlineNode for TestNode {
+        fn first_child(&self) -> Option<Self> {
+            self.tree[self.index].2.map(|index| TestNode { index, ..*self })
+        }
+
+        fn next_sibling(&self) -> Option<Self> {
+            self.tree[self.index].3.map(|index| TestNode { index, ..*self })
+        }
+
+        fn to_bookmark(&self, level: u32, children: Vec<Bookmark>) -> Bookmark {
+            let (title, page, _, _) = self.tree[self.index];
+            let fallback_page = if page > 0 { None } else { first_located_page(&children) };
+            Bookmark { title: title.to_string(), page_number: page, level, children, resolved: page > 0, fallback_page }
+        }
+    }
+
+    #[test]
+    fn test_build_outline_levels() {
+        // 第一部 > 第一章 > (1.1, 1.2)，第一部 > 第二章；两个无标题分组不相邻，子节点不同，都应保留
+        static TREE: &[(&str, u32, Option<usize>, Option<usize>)] = &[
+            ("第一部", 0, Some(1), Some(5)),
+            ("第一章", 3, Some(2), Some(4)),
+            ("1.1", 3, None, Some(3)),
+            ("1.2", 5, None, None),
+            ("第二章", 8, None, None),
+            ("", 0, Some(6), Some(7)),
+            ("第三章", 12, None, None),
+            ("附录", 20, None, Some(8)),
+            ("", 0, Some(9), None),
+            ("第四章", 25, None, None),
+        ];
+        let mut budget = 100;
+        let roots = build_outline_level(Some(TestNode { tree: TREE, index: 0 }), 0, &mut budget);
+
+        let titles: Vec<&str> = roots.iter().map(|b| b.title.as_str()).collect();
+        assert_eq!(titles, vec!["第一部", "", "附录", ""]);
+        // 分组标题自身无目标：不算已解析，页码退回到第一个子节点
+        assert_eq!((roots[0].page_number, roots[0].resolved, roots[0].fallback_page), (0, false, Some(3)));
+        assert_eq!((roots[2].resolved, roots[2].fallback_page), (true, None));
+        assert_eq!(roots[0].children.len(), 2);
+        let chapter = &roots[0].children[0];
+        assert_eq!((chapter.title.as_str(), chapter.level), ("第一章", 1));
+        let sections: Vec<(&str, u32)> = chapter.children.iter().map(|c| (c.title.as_str(), c.level)).collect();
+        assert_eq!(sections, vec![("1.1", 2), ("1.2", 2)]);
+        assert_eq!(roots[1].children[0].title, "第三章");
+        assert_eq!(roots[3].children[0].title, "第四章");
+        assert_eq!((roots[1].fallback_page, roots[3].fallback_page), (Some(12), Some(25)));
+        assert_eq!(budget, 100 - TREE.len());
+    }
+
+    #[test]
+    fn test_build_outline_cycle_bounded() {
+        // 第一章 ⇄ 第二章（Next 成环）：由总数上限截断，不会无限展开
+        static CYCLE: &[(&str, u32, Option<usize>, Option<usize>)] = &[
+            ("第一章", 1, None, Some(1)),
+            ("第二章", 2, None, Some(0)),
+        ];
+        let mut budget = 10;
+        let roots = build_outline_level(Some(TestNode { tree: CYCLE, index: 0 }), 0, &mut budget);
+        assert_eq!((roots.len(), budget), (10, 0));
+
+        // Next 指回自身：相邻重复项合并为一项
+        static SELF_LOOP: &[(&str, u32, Option<usize>, Option<usize>)] = &[("附录", 3, None, Some(0))];
+        let mut budget = 10;
+        let roots = build_outline_level(Some(TestNode { tree: SELF_LOOP, index: 0 }), 0, &mut budget);
+        assert_eq!((roots.len(), budget), (1, 0));
+    }
+
+    #[test]
+    fn test_locate_chapter() {
+        let bookmark = |title: &str, page: u32, level: u32, children: Vec<Bookmark>| Bookmark {
+            title: title.to_string(),
+            page_number: page,
+            level,
+            children,
+            resolved: page > 0,
+            fallback_page: None,
+        };
+        let outline = vec![
+            bookmark("封面", 0, 0, vec![]),
+            bookmark("第一章", 3, 0, vec![bookmark("1.1", 3, 1, vec![]), bookmark("1.2", 6, 1, vec![])]),
+            bookmark("第二章", 11, 0, vec![]),
+        ];
+        let chapters = flatten_outline(&outline);
+
+        assert!(locate_chapter(&chapters, 2, 20).is_none());
+        let c = locate_chapter(&chapters, 4, 20).unwrap();
+        assert_eq!((c.title.as_str(), c.start_page, c.end_page), ("1.1", 3, 5));
+        assert!((c.progress - 200.0 / 3.0).abs() < 0.01);
+        let c = locate_chapter(&chapters, 20, 20).unwrap();
+        assert_eq!((c.title.as_str(), c.start_page, c.end_page, c.progress), ("第二章", 11, 20, 100.0));
+    }
+
+    #[test]
+    fn test_progressive_stages() {
+        assert_eq!(progressive_stages(&RenderQuality::Thumbnail), vec![RenderQuality::Thumbnail]);
+        assert_eq!(
+            progressive_stages(&RenderQuality::Best),
+            vec![RenderQuality::Thumbnail, RenderQuality::Standard, RenderQuality::Best]
+        );
+    }
+
+    #[test]
+    fn test_summarize_page_sizes() {
+        let page = |number, width, height| PdfPageInfo {
+            width,
+            height,
+            number,
+            rotation: 0,
+            media_width: width,
+            media_height: height,
+            crop_offset_x: 0.0,
+            crop_offset_y: 0.0,
+        };
+        assert_eq!(
+            summarize_page_sizes(&[page(1, 595.0, 842.0), page(2, 595.3, 841.9)]),
+            (595.3, 842.0, true)
+        );
+        assert_eq!(
+            summarize_page_sizes(&[page(1, 595.0, 842.0), page(2, 842.0, 595.0)]),
+            (842.0, 842.0, false)
+        );
+        assert_eq!(summarize_page_sizes(&[]), (0.0, 0.0, true));
+    }
+
+    #[test]
+    fn test_merge_page_infos() {
+        let page = |number, width, height| PdfPageInfo {
+            width,
+            height,
+            number,
+            rotation: 0,
+            media_width: width,
+            media_height: height,
+            crop_offset_x: 0.0,
+            crop_offset_y: 0.0,
+        };
+        let base = PdfDocumentInfo {
+            page_count: 3,
+            pages: vec![page(1, 595.0, 842.0)],
+            title: None,
+            author: None,
+            subject: None,
+            keywords: None,
+            creator: None,
+            producer: None,
+            creation_date: None,
+            modification_date: None,
+            max_page_width: 595.0,
+            max_page_height: 842.0,
+            uniform_size: false,
+            pages_complete: false,
+        };
+
+        // 已读取的页尺寸一致，但还有页未就绪时不能断定全书一致
+        let pending = merge_page_infos(base.clone(), &[Some(page(1, 595.0, 842.0)), None, Some(page(3, 595.0, 842.0))]);
+        assert!(!pending.pages_complete);
+        assert!(!pending.uniform_size);
+
+        let partial = merge_page_infos(base.clone(), &[Some(page(1, 595.0, 842.0)), None, Some(page(3, 842.0, 595.0))]);
+        assert!(!partial.pages_complete);
+        assert_eq!(partial.pages.iter().map(|p| p.number).collect::<Vec<_>>(), vec![1, 3]);
+        assert!(!partial.uniform_size);
+
+        let full = merge_page_infos(base, &[Some(page(1, 595.0, 842.0)), Some(page(2, 595.0, 842.0)), Some(page(3, 595.0, 842.0))]);
+        assert!(full.pages_complete);
+        assert_eq!(full.pages.len(), 3);
+        assert!(full.uniform_size);
+    }
+
+    #[test]
+    fn test_adaptive_render_width() {
+        // 2x 高分屏上 400px 宽的视口需要 800 像素
+        let options = RenderOptions::adaptive(595.0, 192.0, 400);
+        assert_eq!(options.quality, RenderQuality::Adaptive);
+        assert_eq!((options.width, options.fit_to_width), (Some(800), true));
+        // 小页面不超过原始宽度的 4 倍，无效 DPI 按 1x 处理
+        assert_eq!(RenderOptions::adaptive(100.0, 480.0, 1000).width, Some(400));
+        assert_eq!(RenderOptions::adaptive(595.0, 0.0, 360).width, Some(360));
+    }
 }
@@ -0,0 +1,73 @@
+//! PDF 表单字段（AcroForm）读取
+//! 只读展示：枚举页面上的 Widget 注释，取出字段名、类型、当前值和位置
+
+use pdfium_render::prelude::{
+    PdfDocument, PdfFormField as PdfiumFormField, PdfFormFieldCommon, PdfPageAnnotationCommon,
+};
+
+use crate::pdf::page_box::PageBoxes;
+use crate::pdf::types::{FormFieldRect, PageFormFields, PdfError, PdfFormField};
+
+/// 读取指定页（从 1 开始）的表单字段；文档没有 AcroForm 时返回空列表
+pub fn extract_page_form_fields(
+    document: &PdfDocument<'_>,
+    page_number: u32,
+) -> Result<PageFormFields, PdfError> {
+    let page = document
+        .pages()
+        .get((page_number - 1) as u16)
+        .map_err(|e| PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string()))?;
+
+    let page_width = page.width().value;
+    let page_height = page.height().value;
+    let boxes = PageBoxes::read(&page);
+    let mut fields = Vec::new();
+
+    if document.form().is_some() {
+        for annotation in page.annotations().iter() {
+            let Some(field) = annotation.as_form_field() else {
+                continue;
+            };
+            let Ok(bounds) = annotation.bounds() else {
+                continue;
+            };
+
+            let (field_type, value) = field_type_and_value(field);
+            fields.push(PdfFormField {
+                name: field.name().unwrap_or_default(),
+                field_type: field_type.to_string(),
+                value,
+                // PDF 坐标原点在左下角，转换为与渲染图一致、以 CropBox 左上角为原点的坐标
+                rect: FormFieldRect {
+                    x: boxes.x(bounds.left().value),
+                    y: boxes.y_from_top(bounds.top().value),
+                    width: bounds.width().value,
+                    height: bounds.height().value,
+                },
+                read_only: field.is_read_only(),
+            });
+        }
+    }
+
+    Ok(PageFormFields {
+        page_number,
+        page_width,
+        page_height,
+        fields,
+    })
+}
+
+/// 字段类型名及当前值；复选框/单选框的值为 `"true"`/`"false"`
+fn field_type_and_value(field: &PdfiumFormField) -> (&'static str, Option<String>) {
+    let checked = |result: Result<bool, _>| result.ok().map(|checked| checked.to_string());
+    match field {
+        PdfiumFormField::Text(text) => ("text", text.value()),
+        PdfiumFormField::Checkbox(checkbox) => ("checkbox", checked(checkbox.is_checked())),
+        PdfiumFormField::RadioButton(radio) => ("radio", checked(radio.is_checked())),
+        PdfiumFormField::ComboBox(combo) => ("combo_box", combo.value()),
+        PdfiumFormField::ListBox(list) => ("list_box", list.value()),
+        PdfiumFormField::PushButton(_) => ("push_button", None),
+        PdfiumFormField::Signature(_) => ("signature", None),
+        PdfiumFormField::Unknown(_) => ("unknown", None),
+    }
+}
@@ -1,15 +1,32 @@
+pub mod annotations;
+pub mod attachments;
+pub mod bidi;
 pub mod cache;
+pub mod char_boxes;
+pub mod contact_sheet;
+pub mod doc_cache;
 pub mod engine;
+pub mod forms;
+pub mod ocr;
+pub mod page_box;
 pub mod performance;
 pub mod preload_predictor;
+pub mod reflow;
+pub mod remote;
 pub mod renderer;
+pub mod tiles;
 pub mod types;
 
 pub use cache::CacheManager;
-pub use engine::{PdfEngine, PdfEngineManager, WarmupStrategy};
+pub use engine::{
+    cleanup_render_temp_files, cleanup_stale_render_files, temp_render_usage, PdfEngine, PdfEngineManager,
+    PdfRuntimeConfig, WarmupStrategy, DEFAULT_TEMP_RENDER_MAX_AGE_DAYS, MAX_THUMBNAIL_BATCH_PAGES,
+};
 pub use performance::{
-    PerformanceMetrics, PerformanceMonitor, PerformanceReport, PerformanceTimer,
+    PageLatency, PerformanceMetrics, PerformanceMonitor, PerformanceReport, PerformanceTimer,
+    RenderStageTimings,
 };
 pub use preload_predictor::{NavigationPattern, PreloadPredictor, ReadingSpeed};
 pub use renderer::PdfRenderer;
+pub use tiles::{TileGrid, TileRect, DEFAULT_TILE_SIZE};
 pub use types::*;
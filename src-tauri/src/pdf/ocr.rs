@@ -0,0 +1,298 @@
+//! 扫描版 PDF 的 OCR 文本层
+//! 没有文字层的页面按需渲染为灰度位图后交给 tesseract 识别（需启用 `ocr` feature），
+//! 识别结果按语言写入磁盘 sidecar 文件，搜索时对没有原生文字的页面回退使用。
+//! 坐标与字符坐标一致：以 CropBox 左上角为原点、单位为点
+
+use std::path::{Path, PathBuf};
+
+use image::RgbaImage;
+
+use crate::pdf::types::{FormFieldRect, PageOcrResult, PdfError, PdfOcrWord, SearchResult, TextPosition};
+
+/// 未指定语言时使用的语言包
+pub const DEFAULT_OCR_LANG: &str = "chi_sim+eng";
+/// OCR 渲染分辨率，tesseract 在 300 DPI 左右识别效果最好
+pub const OCR_DPI: f32 = 300.0;
+/// OCR 渲染的像素上限，超大页面按比例降低分辨率
+pub const OCR_MAX_PIXELS: usize = 40_000_000;
+
+/// 规整语言参数：去除空白，空值使用默认语言包。
+/// 语言名会拼进 sidecar 文件名与 `{name}.traineddata` 路径，`+` 分隔的每一段只允许字母、数字与下划线
+pub fn normalize_lang(lang: Option<&str>) -> Result<String, PdfError> {
+    let lang: String = lang.unwrap_or_default().chars().filter(|c| !c.is_whitespace()).collect();
+    if lang.is_empty() {
+        return Ok(DEFAULT_OCR_LANG.to_string());
+    }
+    let valid = lang
+        .split('+')
+        .all(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    if !valid {
+        return Err(PdfError::invalid_param("lang", lang, "以 + 分隔的 tesseract 语言包名（字母、数字、下划线）"));
+    }
+    Ok(lang)
+}
+
+/// 按 OCR 分辨率计算渲染宽度（像素）
+pub fn render_width(page_width: f32) -> u32 {
+    (page_width.max(1.0) * OCR_DPI / 72.0).round() as u32
+}
+
+/// sidecar 文件名：`p_{页码}_{语言}.json`
+fn sidecar_path(dir: &Path, page_number: u32, lang: &str) -> PathBuf {
+    dir.join(format!("p_{}_{}.json", page_number, lang.replace('+', "-")))
+}
+
+/// 读取 sidecar 中的识别结果；`lang` 为空时取该页任意语言的结果
+pub fn load_sidecar(dir: &Path, page_number: u32, lang: Option<&str>) -> Option<PageOcrResult> {
+    let path = match lang {
+        Some(lang) => sidecar_path(dir, page_number, lang),
+        None => {
+            let prefix = format!("p_{}_", page_number);
+            std::fs::read_dir(dir)
+                .ok()?
+                .flatten()
+                .map(|entry| entry.path())
+                .find(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".json"))
+                })?
+        }
+    };
+    let json = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// 写入 sidecar，失败只记录日志（下次重新识别）
+pub fn save_sidecar(dir: &Path, result: &PageOcrResult) {
+    let path = sidecar_path(dir, result.page_number, &result.lang);
+    let written = std::fs::create_dir_all(dir)
+        .and_then(|_| serde_json::to_vec(result).map_err(std::io::Error::other))
+        .and_then(|json| std::fs::write(&path, json));
+    if let Err(e) = written {
+        eprintln!("[PdfOcr] 写入 OCR 缓存失败: {} ({})", path.display(), e);
+    }
+}
+
+/// 检查 `TESSDATA_PREFIX` 下缺少的语言包；未设置该变量时交给 tesseract 按默认路径查找
+#[cfg(feature = "ocr")]
+fn missing_lang_packs(lang: &str) -> Vec<String> {
+    let Some(prefix) = std::env::var_os("TESSDATA_PREFIX") else {
+        return Vec::new();
+    };
+    let dir = PathBuf::from(prefix);
+    lang.split('+')
+        .filter(|name| !name.is_empty() && !dir.join(format!("{}.traineddata", name)).exists())
+        .map(str::to_string)
+        .collect()
+}
+
+/// 调用 tesseract 识别位图，返回 TSV 格式的逐词结果
+#[cfg(feature = "ocr")]
+pub fn recognize_tsv(image: &RgbaImage, lang: &str, page_number: u32) -> Result<String, PdfError> {
+    use image::ImageEncoder;
+
+    let missing = missing_lang_packs(lang);
+    if !missing.is_empty() {
+        return Err(PdfError::UnsupportedFeature {
+            feature: format!("OCR 缺少语言包: {}", missing.join(", ")),
+            page: Some(page_number),
+        });
+    }
+
+    // 转为灰度 PNG，减小内存拷贝且不影响识别
+    let gray = image::DynamicImage::ImageRgba8(image.clone()).to_luma8();
+    let mut png = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut png)
+        .write_image(gray.as_raw(), gray.width(), gray.height(), image::ColorType::L8)
+        .map_err(|e| PdfError::render_error(page_number, "OCR图像编码", e.to_string()))?;
+
+    let mut engine = tesseract::Tesseract::new(None, Some(lang))
+        .map_err(|e| PdfError::UnsupportedFeature {
+            feature: format!("OCR 初始化失败，请确认已安装语言包 {}: {}", lang, e),
+            page: Some(page_number),
+        })?
+        .set_image_from_mem(&png)
+        .map_err(|e| PdfError::render_error(page_number, "OCR加载图像", e.to_string()))?
+        .set_source_resolution(OCR_DPI as i32)
+        .recognize()
+        .map_err(|e| PdfError::render_error(page_number, "OCR识别", e.to_string()))?;
+    engine
+        .get_tsv_text(0)
+        .map_err(|e| PdfError::render_error(page_number, "OCR读取结果", e.to_string()))
+}
+
+/// 未启用 `ocr` feature 时不支持识别
+#[cfg(not(feature = "ocr"))]
+pub fn recognize_tsv(_image: &RgbaImage, _lang: &str, page_number: u32) -> Result<String, PdfError> {
+    Err(PdfError::UnsupportedFeature {
+        feature: "OCR（构建时未启用 ocr feature）".to_string(),
+        page: Some(page_number),
+    })
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32, 0x3000..=0x303F | 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xFF00..=0xFFEF)
+}
+
+/// 按行拼接单词：同一行内以空格分隔（相邻中日韩字符之间不加空格），行间换行；
+/// 同时返回每个字符所属的单词序号，分隔符为 None
+fn layout_text(words: &[PdfOcrWord]) -> (String, Vec<Option<usize>>) {
+    let mut text = String::new();
+    let mut owners = Vec::new();
+    let mut prev: Option<(u32, char)> = None;
+    for (index, word) in words.iter().enumerate() {
+        let Some(first) = word.text.chars().next() else { continue };
+        if let Some((line, last)) = prev {
+            let separator = if line != word.line {
+                Some('\n')
+            } else if is_cjk(last) && is_cjk(first) {
+                None
+            } else {
+                Some(' ')
+            };
+            if let Some(separator) = separator {
+                text.push(separator);
+                owners.push(None);
+            }
+        }
+        for c in word.text.chars() {
+            text.push(c);
+            owners.push(Some(index));
+        }
+        prev = word.text.chars().last().map(|last| (word.line, last));
+    }
+    (text, owners)
+}
+
+/// 解析 tesseract TSV 输出，像素坐标按 `scale`（点/像素）换算为页面坐标
+/// TSV 列：level page_num block_num par_num line_num word_num left top width height conf text，level 5 为单词
+pub fn build_result(
+    page_number: u32,
+    lang: &str,
+    page_size: (f32, f32),
+    scale: (f32, f32),
+    tsv: &str,
+) -> PageOcrResult {
+    let mut words = Vec::new();
+    let mut line_keys: Vec<(u32, u32, u32, u32)> = Vec::new();
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != "5" {
+            continue;
+        }
+        let text = cols[11].trim();
+        let confidence: f32 = cols[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let key = (num(1), num(2), num(3), num(4));
+        if line_keys.last() != Some(&key) {
+            line_keys.push(key);
+        }
+        words.push(PdfOcrWord {
+            text: text.to_string(),
+            confidence,
+            line: line_keys.len() as u32 - 1,
+            rect: FormFieldRect {
+                x: num(6) as f32 * scale.0,
+                y: num(7) as f32 * scale.1,
+                width: num(8) as f32 * scale.0,
+                height: num(9) as f32 * scale.1,
+            },
+        });
+    }
+    let (text, _) = layout_text(&words);
+    PageOcrResult {
+        page_number,
+        lang: lang.to_string(),
+        page_width: page_size.0,
+        page_height: page_size.1,
+        text,
+        words,
+    }
+}
+
+/// 在 OCR 结果中搜索，命中位置为匹配所覆盖单词的外接矩形
+pub fn search_ocr(result: &PageOcrResult, query: &str, case_sensitive: bool) -> Vec<SearchResult> {
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let needle: Vec<char> = query.chars().map(fold).collect();
+    let (text, owners) = layout_text(&result.words);
+    let chars: Vec<char> = text.chars().collect();
+    if needle.is_empty() || needle.len() > chars.len() {
+        return Vec::new();
+    }
+
+    let mut results = Vec::new();
+    for start in 0..=chars.len() - needle.len() {
+        let end = start + needle.len();
+        if !chars[start..end].iter().zip(&needle).all(|(&c, &n)| fold(c) == n) {
+            continue;
+        }
+        let mut covered: Vec<usize> = owners[start..end].iter().flatten().copied().collect();
+        covered.dedup();
+        let position = covered
+            .iter()
+            .map(|&i| &result.words[i].rect)
+            .fold(None::<(f32, f32, f32, f32)>, |acc, r| {
+                let (x1, y1, x2, y2) = (r.x, r.y, r.x + r.width, r.y + r.height);
+                Some(match acc {
+                    Some((a, b, c, d)) => (a.min(x1), b.min(y1), c.max(x2), d.max(y2)),
+                    None => (x1, y1, x2, y2),
+                })
+            })
+            .map_or(TextPosition { x: 0.0, y: 0.0, width: 0.0, height: 0.0 }, |(x1, y1, x2, y2)| {
+                TextPosition { x: x1, y: y1, width: x2 - x1, height: y2 - y1 }
+            });
+        let context_start = start.saturating_sub(30);
+        let context_end = (end + 30).min(chars.len());
+        results.push(SearchResult {
+            page_number: result.page_number,
+            text: chars[start..end].iter().collect(),
+            position,
+            context: chars[context_start..context_end].iter().collect(),
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t2480\t3508\t-1\t
+5\t1\t1\t1\t1\t1\t100\t200\t80\t40\t95.5\tHello
+5\t1\t1\t1\t1\t2\t200\t200\t100\t40\t91.2\tWorld
+5\t1\t1\t1\t2\t1\t100\t260\t40\t40\t88.0\t扫描
+5\t1\t1\t1\t2\t2\t140\t260\t40\t40\t87.0\t文档
+";
+
+    #[test]
+    fn test_build_and_search_ocr() {
+        let result = build_result(3, "chi_sim+eng", (595.0, 842.0), (0.5, 0.5), TSV);
+        assert_eq!(result.text, "Hello World\n扫描文档");
+        assert_eq!(result.words.len(), 4);
+        assert_eq!(result.words[2].line, 1);
+        assert_eq!(result.words[0].rect.x, 50.0);
+
+        let hits = search_ocr(&result, "hello w", false);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].page_number, 3);
+        assert_eq!(hits[0].text, "Hello W");
+        assert_eq!((hits[0].position.x, hits[0].position.width), (50.0, 100.0));
+
+        let hits = search_ocr(&result, "描文", true);
+        assert_eq!(hits.len(), 1);
+        assert_eq!((hits[0].position.x, hits[0].position.width), (50.0, 40.0));
+        assert!(search_ocr(&result, "HELLO", true).is_empty());
+
+        assert_eq!(normalize_lang(Some(" eng ")).unwrap(), "eng");
+        assert_eq!(normalize_lang(Some("chi_sim+eng")).unwrap(), "chi_sim+eng");
+        assert_eq!(normalize_lang(None).unwrap(), DEFAULT_OCR_LANG);
+        assert!(normalize_lang(Some("../../etc")).is_err());
+        assert!(normalize_lang(Some("eng+..")).is_err());
+        assert!(normalize_lang(Some("eng+")).is_err());
+    }
+}
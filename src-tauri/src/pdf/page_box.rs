@@ -0,0 +1,151 @@
+//! 页面基准框（CropBox / MediaBox）
+//! pdfium 的页面尺寸与渲染都以 CropBox（与 MediaBox 取交集）为准，渲染图左下角对应 CropBox 左下角；
+//! 而文本、批注、表单等对象坐标位于 PDF 用户空间，CropBox 原点不在 (0, 0) 时需先平移才能与渲染图对齐。
+//! 这里统一读取两个框并提供坐标换算，所有返回给前端的坐标都以 CropBox 为基准
+
+use pdfium_render::prelude::{PdfPage, PdfRect};
+
+/// 用户空间中的矩形框（点）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxRect {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+impl BoxRect {
+    fn from_pdf_rect(rect: &PdfRect) -> Self {
+        // 部分文档的框坐标是反向书写的，统一规整为 left < right、bottom < top
+        let (x1, x2) = (rect.left().value, rect.right().value);
+        let (y1, y2) = (rect.bottom().value, rect.top().value);
+        Self {
+            left: x1.min(x2),
+            bottom: y1.min(y2),
+            right: x1.max(x2),
+            top: y1.max(y2),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.top - self.bottom
+    }
+
+    fn intersect(&self, other: &BoxRect) -> Option<BoxRect> {
+        let rect = BoxRect {
+            left: self.left.max(other.left),
+            bottom: self.bottom.max(other.bottom),
+            right: self.right.min(other.right),
+            top: self.top.min(other.top),
+        };
+        (rect.width() > 0.0 && rect.height() > 0.0).then_some(rect)
+    }
+}
+
+/// 页面的 MediaBox 与实际生效的 CropBox
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageBoxes {
+    pub media: BoxRect,
+    /// 渲染与坐标基准：CropBox 与 MediaBox 的交集，未设置 CropBox 时等于 MediaBox
+    pub crop: BoxRect,
+}
+
+impl PageBoxes {
+    /// 读取页面的基准框；MediaBox 缺失时退回以页面尺寸为大小、原点为 (0, 0) 的框
+    pub fn read(page: &PdfPage) -> Self {
+        let boundaries = page.boundaries();
+        let media = boundaries
+            .media()
+            .map(|b| BoxRect::from_pdf_rect(&b.bounds))
+            .ok()
+            .filter(|b| b.width() > 0.0 && b.height() > 0.0)
+            .unwrap_or(BoxRect {
+                left: 0.0,
+                bottom: 0.0,
+                right: page.width().value,
+                top: page.height().value,
+            });
+        let crop = boundaries.crop().ok().map(|b| BoxRect::from_pdf_rect(&b.bounds));
+        Self::new(media, crop)
+    }
+
+    fn new(media: BoxRect, crop: Option<BoxRect>) -> Self {
+        let crop = crop.and_then(|crop| crop.intersect(&media)).unwrap_or(media);
+        Self { media, crop }
+    }
+
+    /// 用户空间 x 坐标转为以 CropBox 左边为原点
+    pub fn x(&self, x: f32) -> f32 {
+        x - self.crop.left
+    }
+
+    /// 用户空间 y 坐标转为以 CropBox 底边为原点（仍为左下角原点、y 向上）
+    pub fn y(&self, y: f32) -> f32 {
+        y - self.crop.bottom
+    }
+
+    /// 用户空间 y 坐标转为以 CropBox 顶边为原点、y 向下（与渲染图一致）
+    pub fn y_from_top(&self, y: f32) -> f32 {
+        self.crop.top - y
+    }
+
+    /// 按页面旋转换算到显示方向的 MediaBox 尺寸
+    pub fn media_size(&self, rotation: i32) -> (f32, f32) {
+        oriented(self.media.width(), self.media.height(), rotation)
+    }
+
+    /// 按页面旋转换算到显示方向的 CropBox 左上角相对 MediaBox 左上角的偏移
+    pub fn crop_offset(&self, rotation: i32) -> (f32, f32) {
+        let left = self.crop.left - self.media.left;
+        let right = self.media.right - self.crop.right;
+        let top = self.media.top - self.crop.top;
+        let bottom = self.crop.bottom - self.media.bottom;
+        // 顺时针旋转 90° 后原底边成为左边、原左边成为顶边，其余角度同理
+        match rotation {
+            90 => (bottom, left),
+            180 => (right, bottom),
+            270 => (top, right),
+            _ => (left, top),
+        }
+    }
+}
+
+fn oriented(width: f32, height: f32, rotation: i32) -> (f32, f32) {
+    if rotation == 90 || rotation == 270 {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: f32, bottom: f32, right: f32, top: f32) -> BoxRect {
+        BoxRect { left, bottom, right, top }
+    }
+
+    #[test]
+    fn test_page_boxes() {
+        // 四周各裁掉 9pt 出血，左下再额外多裁 1pt
+        let boxes = PageBoxes::new(rect(0.0, 0.0, 630.0, 810.0), Some(rect(10.0, 10.0, 621.0, 801.0)));
+        assert_eq!((boxes.crop.width(), boxes.crop.height()), (611.0, 791.0));
+        assert_eq!((boxes.x(10.0), boxes.y(10.0), boxes.y_from_top(801.0)), (0.0, 0.0, 0.0));
+        assert_eq!(boxes.crop_offset(0), (10.0, 9.0));
+        assert_eq!(boxes.crop_offset(90), (10.0, 10.0));
+        assert_eq!(boxes.crop_offset(180), (9.0, 10.0));
+        assert_eq!(boxes.crop_offset(270), (9.0, 9.0));
+        assert_eq!(boxes.media_size(90), (810.0, 630.0));
+
+        // CropBox 超出 MediaBox 时取交集，未设置或不相交时退回 MediaBox
+        let media = rect(0.0, 0.0, 595.0, 842.0);
+        assert_eq!(PageBoxes::new(media, Some(rect(-20.0, 100.0, 700.0, 900.0))).crop, rect(0.0, 100.0, 595.0, 842.0));
+        assert_eq!(PageBoxes::new(media, None).crop, media);
+        assert_eq!(PageBoxes::new(media, Some(rect(600.0, 0.0, 700.0, 10.0))).crop, media);
+    }
+}
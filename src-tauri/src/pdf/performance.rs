@@ -2,7 +2,7 @@
 // 用于收集和分析PDF渲染性能指标
 
 use std::time::{Duration, Instant};
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
@@ -28,6 +28,70 @@ pub struct PerformanceMetrics {
     pub memory_usage_bytes: usize,
     /// 最近渲染时间列表
     pub recent_render_times: Vec<f64>,
+    /// 渲染延迟中位数（毫秒）
+    #[serde(default)]
+    pub p50_render_time_ms: f64,
+    /// 渲染延迟 95 分位（毫秒）
+    #[serde(default)]
+    pub p95_render_time_ms: f64,
+    /// 平均页面加载耗时（毫秒）
+    #[serde(default)]
+    pub avg_load_time_ms: f64,
+    /// 平均光栅化耗时（毫秒）
+    #[serde(default)]
+    pub avg_rasterize_time_ms: f64,
+    /// 平均编码耗时（毫秒）
+    #[serde(default)]
+    pub avg_encode_time_ms: f64,
+}
+
+/// 单次渲染各阶段耗时
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStageTimings {
+    /// 获取页面对象
+    pub load: Duration,
+    /// 光栅化为位图
+    pub rasterize: Duration,
+    /// 图像编码
+    pub encode: Duration,
+}
+
+impl RenderStageTimings {
+    pub fn total(&self) -> Duration {
+        self.load + self.rasterize + self.encode
+    }
+}
+
+/// 单页渲染延迟统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageLatency {
+    pub page_number: u32,
+    /// 统计窗口内的样本数
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// 每页保留的最近样本数
+const MAX_PAGE_SAMPLES: usize = 20;
+
+/// 最近邻秩法计算分位数，`values` 需已升序排列
+fn percentile(values: &[f64], p: f64) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * values.len() as f64).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+fn sorted(values: impl Iterator<Item = f64>) -> Vec<f64> {
+    let mut values: Vec<f64> = values.collect();
+    values.sort_by(|a, b| a.total_cmp(b));
+    values
+}
+
+fn duration_ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
 }
 
 impl Default for PerformanceMetrics {
@@ -42,6 +106,11 @@ impl Default for PerformanceMetrics {
             cache_misses: 0,
             memory_usage_bytes: 0,
             recent_render_times: Vec::new(),
+            p50_render_time_ms: 0.0,
+            p95_render_time_ms: 0.0,
+            avg_load_time_ms: 0.0,
+            avg_rasterize_time_ms: 0.0,
+            avg_encode_time_ms: 0.0,
         }
     }
 }
@@ -50,6 +119,10 @@ impl Default for PerformanceMetrics {
 pub struct PerformanceMonitor {
     metrics: Arc<RwLock<PerformanceMetrics>>,
     render_times: Arc<RwLock<VecDeque<Duration>>>,
+    /// 最近的分阶段耗时
+    stage_times: Arc<RwLock<VecDeque<RenderStageTimings>>>,
+    /// 按页记录的最近渲染延迟（毫秒）
+    page_times: Arc<RwLock<HashMap<u32, VecDeque<f64>>>>,
     max_history: usize,
 }
 
@@ -58,6 +131,8 @@ impl PerformanceMonitor {
         Self {
             metrics: Arc::new(RwLock::new(PerformanceMetrics::default())),
             render_times: Arc::new(RwLock::new(VecDeque::new())),
+            stage_times: Arc::new(RwLock::new(VecDeque::new())),
+            page_times: Arc::new(RwLock::new(HashMap::new())),
             max_history: 100, // 保留最近100次渲染记录
         }
     }
@@ -66,6 +141,8 @@ impl PerformanceMonitor {
         Self {
             metrics: Arc::new(RwLock::new(PerformanceMetrics::default())),
             render_times: Arc::new(RwLock::new(VecDeque::new())),
+            stage_times: Arc::new(RwLock::new(VecDeque::new())),
+            page_times: Arc::new(RwLock::new(HashMap::new())),
             max_history,
         }
     }
@@ -96,6 +173,57 @@ impl PerformanceMonitor {
         metrics.recent_render_times = times.iter()
             .map(|d| d.as_secs_f64() * 1000.0)
             .collect();
+
+        let recent = sorted(metrics.recent_render_times.iter().copied());
+        metrics.p50_render_time_ms = percentile(&recent, 0.5);
+        metrics.p95_render_time_ms = percentile(&recent, 0.95);
+    }
+
+    /// 记录一次实际渲染（缓存未命中）：总耗时、分阶段耗时及按页延迟
+    pub async fn record_page_render(&self, page_number: u32, timings: RenderStageTimings) {
+        let total = timings.total();
+        self.record_render_time(total).await;
+
+        {
+            let mut stages = self.stage_times.write().await;
+            stages.push_back(timings);
+            if stages.len() > self.max_history {
+                stages.pop_front();
+            }
+
+            let count = stages.len() as f64;
+            let mut metrics = self.metrics.write().await;
+            metrics.avg_load_time_ms = stages.iter().map(|t| duration_ms(t.load)).sum::<f64>() / count;
+            metrics.avg_rasterize_time_ms =
+                stages.iter().map(|t| duration_ms(t.rasterize)).sum::<f64>() / count;
+            metrics.avg_encode_time_ms = stages.iter().map(|t| duration_ms(t.encode)).sum::<f64>() / count;
+        }
+
+        let mut pages = self.page_times.write().await;
+        let samples = pages.entry(page_number).or_default();
+        samples.push_back(duration_ms(total));
+        if samples.len() > MAX_PAGE_SAMPLES {
+            samples.pop_front();
+        }
+    }
+
+    /// 按页统计渲染延迟分位数，按页码升序排列
+    pub async fn get_page_latencies(&self) -> Vec<PageLatency> {
+        let pages = self.page_times.read().await;
+        let mut latencies: Vec<PageLatency> = pages
+            .iter()
+            .map(|(&page_number, samples)| {
+                let values = sorted(samples.iter().copied());
+                PageLatency {
+                    page_number,
+                    samples: values.len(),
+                    p50_ms: percentile(&values, 0.5),
+                    p95_ms: percentile(&values, 0.95),
+                }
+            })
+            .collect();
+        latencies.sort_by_key(|latency| latency.page_number);
+        latencies
     }
 
     /// 记录缓存命中
@@ -137,6 +265,8 @@ impl PerformanceMonitor {
         
         *metrics = PerformanceMetrics::default();
         times.clear();
+        self.stage_times.write().await.clear();
+        self.page_times.write().await.clear();
     }
 
     /// 获取性能报告
@@ -146,11 +276,13 @@ impl PerformanceMonitor {
         // 修复：metrics moved问题。先生成 recommendations，再构建 struct。
         // 或者克隆一份传给 recommendations。
         let recommendations = self.generate_recommendations(&metrics);
+        let page_latencies = self.get_page_latencies().await;
 
         PerformanceReport {
             metrics, // 这里 move metrics
             timestamp: chrono::Utc::now().to_rfc3339(),
             recommendations,
+            page_latencies,
         }
     }
 
@@ -190,6 +322,8 @@ impl Clone for PerformanceMonitor {
         Self {
             metrics: Arc::clone(&self.metrics),
             render_times: Arc::clone(&self.render_times),
+            stage_times: Arc::clone(&self.stage_times),
+            page_times: Arc::clone(&self.page_times),
             max_history: self.max_history,
         }
     }
@@ -201,6 +335,9 @@ pub struct PerformanceReport {
     pub metrics: PerformanceMetrics,
     pub timestamp: String,
     pub recommendations: Vec<String>,
+    /// 按页统计的渲染延迟
+    #[serde(default)]
+    pub page_latencies: Vec<PageLatency>,
 }
 
 /// 性能计时器
@@ -304,4 +441,33 @@ mod tests {
         assert!(!report.recommendations.is_empty());
         assert!(report.metrics.cache_hit_rate > 0.0);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_page_render_percentiles() {
+        let monitor = PerformanceMonitor::new();
+
+        for ms in [10, 20, 30, 40, 100] {
+            let timings = RenderStageTimings {
+                load: Duration::from_millis(0),
+                rasterize: Duration::from_millis(ms),
+                encode: Duration::from_millis(0),
+            };
+            monitor.record_page_render(3, timings).await;
+        }
+        monitor
+            .record_page_render(1, RenderStageTimings { encode: Duration::from_millis(5), ..Default::default() })
+            .await;
+
+        let metrics = monitor.get_metrics().await;
+        assert_eq!(metrics.total_renders, 6);
+        assert!(metrics.avg_encode_time_ms > 0.0);
+
+        let latencies = monitor.get_page_latencies().await;
+        assert_eq!(latencies.len(), 2);
+        assert_eq!(latencies[0].page_number, 1);
+        let page3 = &latencies[1];
+        assert_eq!(page3.samples, 5);
+        assert!((page3.p50_ms - 30.0).abs() < 1.0);
+        assert!((page3.p95_ms - 100.0).abs() < 1.0);
+    }
+}
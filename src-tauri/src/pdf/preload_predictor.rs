@@ -29,6 +29,8 @@ pub enum ReadingSpeed {
 struct PageVisit {
     page_number: u32,
     timestamp: Instant,
+    /// Client-side timestamp in milliseconds, if reported by the frontend
+    timestamp_ms: Option<u64>,
     duration: Option<Duration>,
 }
 
@@ -71,10 +73,29 @@ impl PreloadPredictor {
 
     /// Record a page visit
     pub fn record_visit(&mut self, page_number: u32) {
+        self.push_visit(page_number, None);
+    }
+
+    /// Record a navigation event reported by the frontend
+    /// `timestamp_ms` is the client time of the page turn, used to measure time spent on `from_page`
+    pub fn record_navigation(&mut self, from_page: u32, to_page: u32, timestamp_ms: u64) {
+        // Make sure the page we are leaving is part of the history (e.g. first event after opening)
+        if self.visit_history.back().map(|v| v.page_number) != Some(from_page) {
+            self.push_visit(from_page, None);
+        }
+        self.push_visit(to_page, Some(timestamp_ms));
+    }
+
+    fn push_visit(&mut self, page_number: u32, timestamp_ms: Option<u64>) {
         // Update duration of previous visit
         if let Some(last_visit) = self.visit_history.back_mut() {
             if last_visit.duration.is_none() {
-                last_visit.duration = Some(last_visit.timestamp.elapsed());
+                last_visit.duration = match (last_visit.timestamp_ms, timestamp_ms) {
+                    (Some(start), Some(end)) => Some(Duration::from_millis(end.saturating_sub(start))),
+                    // Arrival time on the previous page is unknown, don't guess its duration
+                    (None, Some(_)) => None,
+                    _ => Some(last_visit.timestamp.elapsed()),
+                };
             }
         }
 
@@ -82,6 +103,7 @@ impl PreloadPredictor {
         let visit = PageVisit {
             page_number,
             timestamp: Instant::now(),
+            timestamp_ms,
             duration: None,
         };
 
@@ -446,6 +468,24 @@ mod tests {
         assert!(!predictions.is_empty());
     }
 
+    #[test]
+    fn test_record_navigation_detects_jumping() {
+        let mut predictor = PreloadPredictor::new();
+
+        // Page turns 8s apart: sequential, fast reading
+        for (i, page) in (1..=6).enumerate() {
+            predictor.record_navigation(page, page + 1, 8_000 * (i as u64 + 1));
+        }
+        assert!(matches!(predictor.get_pattern(), NavigationPattern::Sequential));
+        assert!(matches!(predictor.get_speed(), ReadingSpeed::Fast));
+
+        // Jumping around the document
+        for (from, to) in [(7, 40), (40, 12), (12, 80), (80, 3), (3, 60), (60, 25), (25, 90), (90, 5)] {
+            predictor.record_navigation(from, to, 100_000);
+        }
+        assert!(matches!(predictor.get_pattern(), NavigationPattern::Random));
+    }
+
     #[test]
     fn test_clear_history() {
         let mut predictor = PreloadPredictor::new();
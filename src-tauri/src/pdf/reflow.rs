@@ -0,0 +1,305 @@
+//! PDF 文本按阅读顺序重排（reflow）
+//! 根据文本片段的位置做分栏检测与行排序：先去掉页眉页脚等贴边短文本，
+//! 再在 x 方向寻找贯穿全页的空白竖带作为栏间距；跨栏的标题把页面分成上下若干段，
+//! 每段内按从左到右的栏、栏内从上到下的行输出。坐标沿用 PDF 约定（原点在左下角）
+
+use serde::{Deserialize, Serialize};
+
+/// 页眉页脚所在的上下边缘比例
+const EDGE_RATIO: f32 = 0.07;
+/// 边缘区域内不超过该字符数的行视为页眉页脚
+const EDGE_MAX_CHARS: usize = 60;
+/// 宽度超过页面该比例的片段不参与分栏检测（通栏标题、整行文本）
+const WIDE_FRAGMENT_RATIO: f32 = 0.6;
+/// 栏间距的最小宽度（点）
+const MIN_GUTTER_WIDTH: f32 = 6.0;
+/// 每栏至少占全部字符的比例
+const MIN_COLUMN_SHARE: f32 = 0.15;
+
+/// 带位置的文本片段
+#[derive(Debug, Clone)]
+pub struct TextFragment {
+    pub text: String,
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl TextFragment {
+    fn height(&self) -> f32 {
+        (self.top - self.bottom).abs().max(1.0)
+    }
+
+    fn center_y(&self) -> f32 {
+        (self.top + self.bottom) / 2.0
+    }
+
+    fn char_count(&self) -> usize {
+        self.text.chars().filter(|c| !c.is_whitespace()).count()
+    }
+}
+
+/// 重排结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReflowText {
+    pub page_number: u32,
+    pub text: String,
+    /// 检测到的栏数
+    pub columns: usize,
+    /// 作为页眉页脚过滤掉的行数
+    pub removed_lines: usize,
+}
+
+/// 按阅读顺序重排页面文本；单栏且没有过滤任何内容时返回 `original`，与原有提取结果保持一致
+pub fn reflow_page_text(
+    page_number: u32,
+    fragments: Vec<TextFragment>,
+    page_width: f32,
+    page_height: f32,
+    original: String,
+) -> ReflowText {
+    let (fragments, removed_lines) = strip_edge_noise(fragments, page_height);
+    let boundaries = detect_column_boundaries(&fragments, page_width);
+    let columns = boundaries.len() + 1;
+
+    let text = if columns == 1 && removed_lines == 0 {
+        original
+    } else {
+        layout_columns(fragments, &boundaries)
+    };
+
+    ReflowText {
+        page_number,
+        text,
+        columns,
+        removed_lines,
+    }
+}
+
+/// 将片段按行聚合：纵向位置接近的片段归为一行，行内按 x 排序
+fn group_lines(mut fragments: Vec<TextFragment>) -> Vec<Vec<TextFragment>> {
+    fragments.sort_by(|a, b| b.top.total_cmp(&a.top).then(a.left.total_cmp(&b.left)));
+
+    let mut lines: Vec<Vec<TextFragment>> = Vec::new();
+    for fragment in fragments {
+        let joined = lines.last_mut().filter(|line| {
+            let first = &line[0];
+            (fragment.center_y() - first.center_y()).abs() < fragment.height().min(first.height()) * 0.5
+        });
+        match joined {
+            Some(line) => line.push(fragment),
+            None => lines.push(vec![fragment]),
+        }
+    }
+    for line in &mut lines {
+        line.sort_by(|a, b| a.left.total_cmp(&b.left));
+    }
+    lines
+}
+
+/// 去掉位于页面上下边缘的短行（页码、页眉页脚）
+fn strip_edge_noise(fragments: Vec<TextFragment>, page_height: f32) -> (Vec<TextFragment>, usize) {
+    if page_height <= 0.0 {
+        return (fragments, 0);
+    }
+    let top_edge = page_height * (1.0 - EDGE_RATIO);
+    let bottom_edge = page_height * EDGE_RATIO;
+
+    let mut kept = Vec::new();
+    let mut removed = 0;
+    for line in group_lines(fragments) {
+        let center = line[0].center_y();
+        let chars: usize = line.iter().map(TextFragment::char_count).sum();
+        if (center > top_edge || center < bottom_edge) && chars <= EDGE_MAX_CHARS {
+            removed += 1;
+        } else {
+            kept.extend(line);
+        }
+    }
+    (kept, removed)
+}
+
+/// 在 x 方向寻找几乎没有片段覆盖的竖带，返回各栏之间的分界 x 坐标
+fn detect_column_boundaries(fragments: &[TextFragment], page_width: f32) -> Vec<f32> {
+    if page_width <= 0.0 || fragments.len() < 4 {
+        return Vec::new();
+    }
+
+    let bins = page_width.ceil() as usize;
+    let mut coverage = vec![0usize; bins + 1];
+    let narrow: Vec<&TextFragment> = fragments
+        .iter()
+        .filter(|f| f.right - f.left <= page_width * WIDE_FRAGMENT_RATIO)
+        .collect();
+    for fragment in &narrow {
+        let start = fragment.left.max(0.0).floor() as usize;
+        let end = (fragment.right.max(0.0).ceil() as usize).min(bins);
+        for count in coverage.iter_mut().take(end).skip(start.min(end)) {
+            *count += 1;
+        }
+    }
+
+    // 允许少量片段（通栏标题、图注等）压在栏间距上
+    let tolerance = (narrow.len() / 20).max(1);
+    let total_chars: usize = fragments.iter().map(TextFragment::char_count).sum();
+    let text_left = fragments.iter().map(|f| f.left).fold(f32::MAX, f32::min).max(0.0) as usize;
+    let text_right = (fragments.iter().map(|f| f.right).fold(0.0, f32::max).ceil() as usize).min(bins);
+
+    let mut boundaries: Vec<f32> = Vec::new();
+    let mut gap_start: Option<usize> = None;
+    for (x, &count) in coverage.iter().enumerate().take(text_right + 1).skip(text_left) {
+        let empty = x < text_right && count <= tolerance;
+        match (empty, gap_start) {
+            (true, None) => gap_start = Some(x),
+            (false, Some(start)) => {
+                gap_start = None;
+                if (x - start) as f32 >= MIN_GUTTER_WIDTH {
+                    boundaries.push((start + x) as f32 / 2.0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // 每栏都要有足够的文字，否则视为表格或零散文本的空隙
+    let mut accepted: Vec<f32> = Vec::new();
+    for boundary in boundaries {
+        let previous = accepted.last().copied().unwrap_or(f32::MIN);
+        let left_chars: usize = fragments
+            .iter()
+            .filter(|f| f.left >= previous && f.right <= boundary)
+            .map(TextFragment::char_count)
+            .sum();
+        let right_chars: usize = fragments
+            .iter()
+            .filter(|f| f.left >= boundary)
+            .map(TextFragment::char_count)
+            .sum();
+        let min_chars = (total_chars as f32 * MIN_COLUMN_SHARE) as usize;
+        if left_chars > min_chars && right_chars > min_chars {
+            accepted.push(boundary);
+        }
+    }
+    accepted
+}
+
+/// 片段所在栏；横跨栏间距时返回 None
+fn column_of(fragment: &TextFragment, boundaries: &[f32]) -> Option<usize> {
+    let column = boundaries.iter().filter(|&&b| fragment.left >= b).count();
+    let right_limit = boundaries.get(column).copied().unwrap_or(f32::MAX);
+    (fragment.right <= right_limit).then_some(column)
+}
+
+/// 按段、栏、行的顺序拼接文本
+fn layout_columns(fragments: Vec<TextFragment>, boundaries: &[f32]) -> String {
+    let columns = boundaries.len() + 1;
+    let (spanning, in_columns): (Vec<_>, Vec<_>) = fragments
+        .into_iter()
+        .partition(|f| column_of(f, boundaries).is_none());
+
+    // 通栏行把页面分成上下若干段
+    let spanning_lines = group_lines(spanning);
+    let band_of = |fragment: &TextFragment| {
+        spanning_lines
+            .iter()
+            .filter(|line| line[0].center_y() > fragment.center_y())
+            .count()
+    };
+
+    let mut cells: Vec<Vec<Vec<TextFragment>>> = vec![vec![Vec::new(); columns]; spanning_lines.len() + 1];
+    for fragment in in_columns {
+        let column = column_of(&fragment, boundaries).unwrap_or(0);
+        cells[band_of(&fragment)][column].push(fragment);
+    }
+
+    let mut blocks: Vec<String> = Vec::new();
+    let mut spanning_lines = spanning_lines.into_iter();
+    for band in cells {
+        for cell in band {
+            if !cell.is_empty() {
+                blocks.push(join_lines(&group_lines(cell)));
+            }
+        }
+        if let Some(line) = spanning_lines.next() {
+            blocks.push(join_lines(&[line]));
+        }
+    }
+    blocks.retain(|block| !block.is_empty());
+    blocks.join("\n\n")
+}
+
+/// 行内片段以空格分隔，行间换行，行距明显增大时视为分段
+fn join_lines(lines: &[Vec<TextFragment>]) -> String {
+    let mut out = String::new();
+    let mut prev: Option<(f32, f32)> = None;
+    for line in lines {
+        let top = line.iter().map(|f| f.top).fold(f32::MIN, f32::max);
+        let bottom = line.iter().map(|f| f.bottom).fold(f32::MAX, f32::min);
+        if let Some((prev_bottom, prev_height)) = prev {
+            if prev_bottom - top > prev_height * 0.8 {
+                out.push_str("\n\n");
+            } else {
+                out.push('\n');
+            }
+        }
+
+        let mut prev_right: Option<f32> = None;
+        for fragment in line {
+            let text = fragment.text.trim_end_matches(['\r', '\n']);
+            if let Some(right) = prev_right {
+                if fragment.left - right > fragment.height() * 0.25 && !out.ends_with(' ') {
+                    out.push(' ');
+                }
+            }
+            out.push_str(text);
+            prev_right = Some(fragment.right);
+        }
+        prev = Some((bottom, (top - bottom).abs().max(1.0)));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(text: &str, left: f32, top: f32, width: f32) -> TextFragment {
+        TextFragment {
+            text: text.to_string(),
+            left,
+            top,
+            right: left + width,
+            bottom: top - 10.0,
+        }
+    }
+
+    #[test]
+    fn test_two_column_reading_order() {
+        // 612x792 的页面：通栏标题、左右两栏各三行、底部页码
+        let mut fragments = vec![fragment("A Two Column Paper Title", 150.0, 740.0, 312.0)];
+        for (i, top) in [700.0, 686.0, 672.0].iter().enumerate() {
+            fragments.push(fragment(&format!("left line {}", i + 1), 50.0, *top, 240.0));
+            fragments.push(fragment(&format!("right line {}", i + 1), 320.0, *top, 240.0));
+        }
+        fragments.push(fragment("7", 300.0, 30.0, 8.0));
+
+        let result = reflow_page_text(1, fragments, 612.0, 792.0, "original".to_string());
+        assert_eq!(result.columns, 2);
+        assert_eq!(result.removed_lines, 1);
+        assert_eq!(
+            result.text,
+            "A Two Column Paper Title\n\nleft line 1\nleft line 2\nleft line 3\n\nright line 1\nright line 2\nright line 3"
+        );
+    }
+
+    #[test]
+    fn test_single_column_keeps_original() {
+        let fragments = (0..6)
+            .map(|i| fragment(&format!("body line {}", i), 72.0, 700.0 - i as f32 * 14.0, 300.0 + i as f32 * 20.0))
+            .collect();
+        let result = reflow_page_text(3, fragments, 612.0, 792.0, "original".to_string());
+        assert_eq!(result.columns, 1);
+        assert_eq!(result.text, "original");
+    }
+}
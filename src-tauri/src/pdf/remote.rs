@@ -0,0 +1,390 @@
+//! 网络 PDF 下载
+//! 远程文件流式下载到应用缓存目录的 `.part` 临时文件，完成并校验 `%PDF-` 文件头后改名为正式文件，
+//! 之后按本地文件走现有渲染链路。缓存文件名由 URL 哈希得到，同一 URL 不重复下载；
+//! 下载中断时保留 `.part` 及响应的 ETag/Last-Modified，下次请求通过 Range + If-Range 续传；
+//! 远程文件已变化或服务器不支持 Range 时返回完整内容，从头下载。
+//! 单个文件超过 `MAX_DOWNLOAD_BYTES` 时中止；缓存目录超过 `MAX_CACHE_BYTES` 时按最近使用时间淘汰旧文件，已打开的文件不淘汰
+
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// 建立连接超时
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// 两次读取之间的最长等待，超过视为连接停滞
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 进度回调的最小间隔字节数
+const PROGRESS_STEP_BYTES: u64 = 256 * 1024;
+
+/// 单个远程 PDF 的最大字节数（1 GiB），声明长度或实际写入超过即中止下载
+const MAX_DOWNLOAD_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// 远程 PDF 缓存目录的容量上限（2 GiB），超过后从最久未使用的文件开始删除
+const MAX_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// 未完成的 `.part` 超过该时长未更新即视为放弃的下载，清理时删除
+const STALE_PART_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+/// 每个 URL 一把锁，同一 URL 的并发请求排队，后到者直接命中缓存
+static DOWNLOAD_LOCKS: Lazy<StdMutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 远程 PDF 在缓存目录中的文件名：URL 的 SHA-256 前 16 位
+pub fn cache_file_name(url: &str) -> String {
+    let digest = Sha256::digest(url.as_bytes());
+    let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}.pdf", &hex[..16])
+}
+
+/// 内容类型是否可能是 PDF；未声明或通用二进制类型放行，最终以文件头校验为准
+pub fn is_acceptable_content_type(content_type: Option<&str>) -> bool {
+    let Some(content_type) = content_type else {
+        return true;
+    };
+    let mime = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+    matches!(
+        mime.as_str(),
+        "" | "application/pdf" | "application/x-pdf" | "application/octet-stream" | "binary/octet-stream"
+    )
+}
+
+/// 解析 `Content-Range: bytes start-end/total`，返回 (start, total)，总长未知时为 None
+pub fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (span, total) = range.split_once('/')?;
+    let (start, _) = span.split_once('-')?;
+    let start = start.trim().parse().ok()?;
+    let total = total.trim().parse().ok();
+    Some((start, total))
+}
+
+/// 校验 URL，只允许 http/https
+pub fn validate_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("无效的 URL: {}", e))?;
+    match parsed.scheme() {
+        "http" | "https" => Ok(parsed),
+        scheme => Err(format!("不支持的协议: {}", scheme)),
+    }
+}
+
+async fn has_pdf_header(path: &Path) -> bool {
+    let Ok(mut file) = tokio::fs::File::open(path).await else {
+        return false;
+    };
+    let mut header = [0u8; 5];
+    file.read_exact(&mut header).await.is_ok() && header == PDF_MAGIC
+}
+
+/// 响应中可用于 If-Range 的校验值：优先强 ETag，其次 Last-Modified（弱 ETag 不能用于 If-Range）
+fn resume_validator(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    let etag = headers
+        .get(reqwest::header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"));
+    etag.or_else(|| headers.get(reqwest::header::LAST_MODIFIED).and_then(|v| v.to_str().ok()))
+        .map(str::to_string)
+}
+
+/// `.part` 旁记录校验值的文件
+fn validator_path(part: &Path) -> PathBuf {
+    let mut name = part.as_os_str().to_os_string();
+    name.push(".validator");
+    PathBuf::from(name)
+}
+
+/// 删除未完成的下载及其校验值
+async fn remove_part(part: &Path) {
+    let _ = tokio::fs::remove_file(part).await;
+    let _ = tokio::fs::remove_file(validator_path(part)).await;
+}
+
+/// 清理缓存目录：删除长期未更新的 `.part` 及其校验值，已完成的 PDF 总量超过 `max_bytes` 时按修改时间从旧到新删除；
+/// `keep` 为刚下载或命中的文件以及已打开的文件，不参与淘汰。命中缓存时会刷新修改时间，修改时间即最近使用时间
+fn prune_cache(cache_dir: &Path, keep: &HashSet<PathBuf>, max_bytes: u64) {
+    let Ok(entries) = std::fs::read_dir(cache_dir) else {
+        return;
+    };
+    let now = SystemTime::now();
+    let mut files: Vec<(PathBuf, u64, SystemTime)> = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        if path.extension().is_some_and(|ext| ext == "part" || ext == "validator") {
+            if now.duration_since(modified).unwrap_or_default() > STALE_PART_AGE && std::fs::remove_file(&path).is_ok() {
+                println!("[RemotePdf] 删除过期的临时文件: {}", path.display());
+            }
+            continue;
+        }
+        files.push((path, metadata.len(), modified));
+    }
+
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, len, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if keep.contains(&path) {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+            println!("[RemotePdf] 缓存超出上限，删除: {}", path.display());
+        }
+    }
+}
+
+/// 刷新缓存文件的修改时间，作为最近使用时间供淘汰排序
+fn touch(path: &Path) {
+    if let Ok(file) = std::fs::OpenOptions::new().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+async fn prune_cache_async(cache_dir: &Path, target: &Path, mut keep: HashSet<PathBuf>) {
+    let cache_dir = cache_dir.to_path_buf();
+    let target = target.to_path_buf();
+    let _ = tokio::task::spawn_blocking(move || {
+        touch(&target);
+        keep.insert(target);
+        prune_cache(&cache_dir, &keep, MAX_CACHE_BYTES);
+    })
+    .await;
+}
+
+fn download_lock(key: &str) -> Arc<tokio::sync::Mutex<()>> {
+    let mut locks = DOWNLOAD_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+    locks.entry(key.to_string()).or_default().clone()
+}
+
+/// 下载远程 PDF 到 `cache_dir`，返回本地文件路径；已缓存时直接返回
+/// `open_paths` 为已打开的文件，缓存淘汰时跳过；
+/// `on_progress(已下载字节, 总字节)` 在下载过程中按间隔回调，总长未知时为 None
+pub async fn download_pdf<F>(
+    url: &str,
+    cache_dir: &Path,
+    open_paths: HashSet<PathBuf>,
+    mut on_progress: F,
+) -> Result<PathBuf, String>
+where
+    F: FnMut(u64, Option<u64>),
+{
+    let parsed = validate_url(url)?;
+    let file_name = cache_file_name(parsed.as_str());
+    let target = cache_dir.join(&file_name);
+    let part = cache_dir.join(format!("{}.part", file_name));
+
+    let lock = download_lock(parsed.as_str());
+    let _guard = lock.lock().await;
+
+    if has_pdf_header(&target).await {
+        println!("[RemotePdf] 命中缓存: {}", target.display());
+        prune_cache_async(cache_dir, &target, open_paths).await;
+        return Ok(target);
+    }
+    tokio::fs::create_dir_all(cache_dir)
+        .await
+        .map_err(|e| format!("创建缓存目录失败: {}", e))?;
+
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .map_err(|e| format!("初始化下载客户端失败: {}", e))?;
+
+    // 没有记录校验值的 .part 无法确认远程文件未变，不续传
+    let validator = tokio::fs::read_to_string(validator_path(&part)).await.ok();
+    let mut resume_from = match &validator {
+        Some(_) => tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+    let mut response = loop {
+        let mut request = client.get(parsed.clone());
+        if let (true, Some(validator)) = (resume_from > 0, &validator) {
+            // 远程文件已变化时服务器返回 200 完整内容，下面按从头下载处理
+            request = request
+                .header(reqwest::header::RANGE, format!("bytes={}-", resume_from))
+                .header(reqwest::header::IF_RANGE, validator.trim());
+        }
+        let response = request.send().await.map_err(|e| format!("请求失败: {}", e))?;
+        // 续传起点超出文件长度（文件已更新或 .part 损坏），丢弃后从头下载
+        if response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+            println!("[RemotePdf] 续传区间无效，重新下载: {}", url);
+            remove_part(&part).await;
+            resume_from = 0;
+            continue;
+        }
+        break response;
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("下载失败: HTTP {}", status.as_u16()));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok());
+    if !is_acceptable_content_type(content_type) {
+        return Err(format!("链接不是 PDF 文件: {}", content_type.unwrap_or_default()));
+    }
+
+    // 206 按 Content-Range 续写，其余情况视为服务器不支持 Range，从头写入
+    let range = response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(parse_content_range);
+    let (mut downloaded, total) = match (status, range) {
+        (reqwest::StatusCode::PARTIAL_CONTENT, Some((start, total))) if start == resume_from => (start, total),
+        _ => (0, response.content_length()),
+    };
+    if resume_from > 0 {
+        println!("[RemotePdf] 续传: from={}, resumed={}", resume_from, downloaded > 0);
+    }
+    if let Some(total) = total.filter(|&total| total > MAX_DOWNLOAD_BYTES) {
+        remove_part(&part).await;
+        return Err(format!("文件过大: {} 字节，上限 {} 字节", total, MAX_DOWNLOAD_BYTES));
+    }
+    // 从头下载时记录本次响应的校验值，供中断后续传；响应没有校验值时不记录，下次从头下载
+    if downloaded == 0 {
+        match resume_validator(response.headers()) {
+            Some(validator) => {
+                let _ = tokio::fs::write(validator_path(&part), validator).await;
+            }
+            None => {
+                let _ = tokio::fs::remove_file(validator_path(&part)).await;
+            }
+        }
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(downloaded > 0)
+        .truncate(downloaded == 0)
+        .open(&part)
+        .await
+        .map_err(|e| format!("创建临时文件失败: {}", e))?;
+
+    on_progress(downloaded, total);
+    let mut last_reported = downloaded;
+    while let Some(chunk) = response.chunk().await.map_err(|e| format!("下载中断: {}", e))? {
+        downloaded += chunk.len() as u64;
+        if downloaded > MAX_DOWNLOAD_BYTES {
+            drop(file);
+            remove_part(&part).await;
+            return Err(format!("文件过大: 超过上限 {} 字节", MAX_DOWNLOAD_BYTES));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("写入临时文件失败: {}", e))?;
+        if downloaded - last_reported >= PROGRESS_STEP_BYTES {
+            last_reported = downloaded;
+            on_progress(downloaded, total);
+        }
+    }
+    file.flush().await.map_err(|e| format!("写入临时文件失败: {}", e))?;
+    drop(file);
+
+    if let Some(total) = total {
+        if downloaded != total {
+            return Err(format!("下载不完整: {}/{} 字节", downloaded, total));
+        }
+    }
+    if !has_pdf_header(&part).await {
+        remove_part(&part).await;
+        return Err("下载内容不是有效的 PDF 文件".to_string());
+    }
+    tokio::fs::rename(&part, &target)
+        .await
+        .map_err(|e| format!("保存下载文件失败: {}", e))?;
+    let _ = tokio::fs::remove_file(validator_path(&part)).await;
+    on_progress(downloaded, Some(downloaded));
+    prune_cache_async(cache_dir, &target, open_paths).await;
+
+    println!("[RemotePdf] 下载完成: url={}, size={}", url, downloaded);
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_pdf_helpers() {
+        assert!(is_acceptable_content_type(None));
+        assert!(is_acceptable_content_type(Some("application/pdf")));
+        assert!(is_acceptable_content_type(Some("Application/PDF; charset=binary")));
+        assert!(is_acceptable_content_type(Some("application/octet-stream")));
+        assert!(!is_acceptable_content_type(Some("text/html; charset=utf-8")));
+        assert!(!is_acceptable_content_type(Some("image/png")));
+
+        assert_eq!(parse_content_range("bytes 100-199/1000"), Some((100, Some(1000))));
+        assert_eq!(parse_content_range("bytes 100-199/*"), Some((100, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+
+        let name = cache_file_name("https://example.com/a.pdf");
+        assert_eq!(name.len(), 20);
+        assert_eq!(name, cache_file_name("https://example.com/a.pdf"));
+        assert_ne!(name, cache_file_name("https://example.com/b.pdf"));
+
+        assert!(validate_url("https://example.com/a.pdf").is_ok());
+        assert!(validate_url("file:///etc/passwd").is_err());
+        assert!(validate_url("not a url").is_err());
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(reqwest::header::LAST_MODIFIED, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        headers.insert(reqwest::header::ETAG, "W/\"weak\"".parse().unwrap());
+        assert_eq!(resume_validator(&headers).as_deref(), Some("Wed, 21 Oct 2015 07:28:00 GMT"));
+        headers.insert(reqwest::header::ETAG, "\"strong\"".parse().unwrap());
+        assert_eq!(resume_validator(&headers).as_deref(), Some("\"strong\""));
+        assert_eq!(validator_path(Path::new("/tmp/a.pdf.part")), Path::new("/tmp/a.pdf.part.validator"));
+    }
+
+    #[test]
+    fn test_prune_cache() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let base = SystemTime::now() - Duration::from_secs(3600);
+        let write = |name: &str, len: usize, modified: SystemTime| {
+            let path = dir.join(name);
+            std::fs::write(&path, vec![0u8; len]).unwrap();
+            std::fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+            path
+        };
+        let oldest = write("a.pdf", 40, base);
+        let older = write("b.pdf", 40, base + Duration::from_secs(60));
+        let newest = write("c.pdf", 40, base + Duration::from_secs(120));
+        let stale_part = write("d.pdf.part", 10, base - STALE_PART_AGE);
+        let fresh_part = write("e.pdf.part", 10, base);
+
+        let stale_validator = write("d.pdf.part.validator", 10, base - STALE_PART_AGE);
+        let opened = write("f.pdf", 40, base - Duration::from_secs(60));
+
+        // 超出上限时从最旧的开始删除，刚使用的文件和已打开的文件即使最旧也保留
+        let keep = HashSet::from([oldest.clone(), opened.clone()]);
+        prune_cache(dir, &keep, 80);
+        assert!(oldest.exists());
+        assert!(opened.exists());
+        assert!(!older.exists());
+        assert!(!newest.exists());
+        assert!(!stale_part.exists());
+        assert!(!stale_validator.exists());
+        assert!(fresh_part.exists());
+    }
+}
@@ -1,18 +1,153 @@
 use pdfium_render::prelude::*;
 use image::{RgbaImage, Rgba};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use webp::Encoder;
 
+use crate::commands::log::write_log;
 use crate::formats::BookRenderCache;
+use crate::pdf::tiles::{TileGrid, TileRect};
 use crate::pdf::types::{
-    CacheKey, ImageFormat, PdfError, RenderOptions, RenderQuality, RenderResult,
+    CacheKey, ImageFormat, PageTile, PdfError, RenderFlags, RenderOptions, RenderQuality,
+    RenderResult, TileKey,
 };
-use crate::pdf::cache::CacheManager;
-use crate::pdf::performance::{PerformanceMonitor, PerformanceTimer};
+use crate::pdf::cache::{CacheManager, ContentKey};
+use crate::pdf::engine::compute_file_hash;
+use crate::pdf::performance::{PerformanceMonitor, PerformanceTimer, RenderStageTimings};
+use std::time::Instant;
+
+/// 默认单页位图像素上限（32M 像素，RGBA 约 128MB），防止超大页面分配位图时 OOM
+pub const DEFAULT_MAX_RENDER_PIXELS: usize = 32 * 1024 * 1024;
+/// 像素上限的下限，避免误设过小导致页面无法辨认
+const MIN_MAX_RENDER_PIXELS: usize = 1024 * 1024;
+
+/// 全局像素上限，0 表示使用默认值
+static MAX_RENDER_PIXELS: AtomicUsize = AtomicUsize::new(0);
+
+/// 设置全局像素上限（前端按设备内存下发），None 恢复默认值
+pub fn set_max_render_pixels(max_pixels: Option<usize>) {
+    let value = max_pixels.map_or(0, |v| v.max(MIN_MAX_RENDER_PIXELS));
+    MAX_RENDER_PIXELS.store(value, Ordering::Relaxed);
+}
+
+/// 当前生效的全局像素上限
+pub fn max_render_pixels() -> usize {
+    match MAX_RENDER_PIXELS.load(Ordering::Relaxed) {
+        0 => DEFAULT_MAX_RENDER_PIXELS,
+        value => value,
+    }
+}
+
+/// 把注释、抗锯齿、灰度等渲染标志应用到 pdfium 渲染配置
+fn apply_render_flags(config: PdfRenderConfig, flags: &RenderFlags) -> PdfRenderConfig {
+    config
+        .render_annotations(flags.render_annotations)
+        .render_form_data(flags.render_annotations)
+        .set_text_smoothing(flags.smooth_text)
+        .set_image_smoothing(flags.smooth_graphics)
+        .set_path_smoothing(flags.smooth_graphics)
+        .use_lcd_text_rendering(flags.lcd_text)
+        .use_grayscale_rendering(flags.grayscale)
+}
+
+/// 在渲染标志之外把位图清空为全透明：pdfium 默认先填充不透明白色，透明区域的背景色
+/// 改由转换位图时的 `blend_background` 按 `background_color` 合成
+fn apply_render_options(config: PdfRenderConfig, options: &RenderOptions) -> PdfRenderConfig {
+    apply_render_flags(config, &options.flags).set_clear_color(PdfColor::new(0, 0, 0, 0))
+}
+
+/// 按像素上限等比缩小目标尺寸，未超限时原样返回
+fn fit_pixel_budget(width: u32, height: u32, max_pixels: usize) -> (u32, u32) {
+    let pixels = width as u64 * height as u64;
+    if pixels <= max_pixels as u64 {
+        return (width, height);
+    }
+    let ratio = (max_pixels as f64 / pixels as f64).sqrt();
+    (
+        ((width as f64 * ratio).floor() as u32).max(1),
+        ((height as f64 * ratio).floor() as u32).max(1),
+    )
+}
+
+/// 按顺时针旋转角度换算页面尺寸，旋转 90/270 度时宽高互换
+pub fn rotated_page_size(width: f32, height: f32, rotation: u32) -> (f32, f32) {
+    if rotation % 180 == 90 {
+        (height, width)
+    } else {
+        (width, height)
+    }
+}
+
+/// 渲染失败占位图的提示文字
+const RENDER_FAILED_TEXT: &str = "本页渲染失败，点击重试";
+/// 占位图像素上限，只需看清提示文字，不必与正常渲染同样清晰
+const PLACEHOLDER_MAX_PIXELS: usize = 1024 * 1024;
+/// 页面尺寸未知时按 A4 比例生成占位图
+const PLACEHOLDER_FALLBACK_PAGE_SIZE: (f32, f32) = (595.0, 842.0);
+
+/// 生成渲染失败占位图：浅灰底、细边框、居中提示文字，宽高比与页面一致以免翻页时版面跳动
+/// 占位图不写入缓存，`error` 为真实的渲染错误
+pub fn render_error_placeholder(
+    page_number: u32,
+    page_size: Option<(f32, f32)>,
+    options: &RenderOptions,
+    error: String,
+) -> Result<RenderResult, PdfError> {
+    use image::{ImageEncoder, Rgb, RgbImage};
+
+    let (base_width, base_height) = page_size.unwrap_or(PLACEHOLDER_FALLBACK_PAGE_SIZE);
+    let (width, height) = PdfRenderer::requested_dimensions(base_width, base_height, options);
+    let (width, height) = fit_pixel_budget(width, height, PLACEHOLDER_MAX_PIXELS);
+
+    let background = [240, 240, 240];
+    let foreground = [110, 110, 110];
+    let mut canvas = RgbImage::from_pixel(width, height, Rgb(background));
+    let frame = Rgb([210, 210, 210]);
+    for x in 0..width {
+        canvas.put_pixel(x, 0, frame);
+        canvas.put_pixel(x, height - 1, frame);
+    }
+    for y in 0..height {
+        canvas.put_pixel(0, y, frame);
+        canvas.put_pixel(width - 1, y, frame);
+    }
+
+    // 字号按宽度收缩，保证整行提示放得下
+    if let Some(font) = crate::cover::cjk_font() {
+        let size = (width as f32 * 0.8 / RENDER_FAILED_TEXT.chars().count() as f32).clamp(8.0, 40.0);
+        let baseline = (height as f32 + size) / 2.0;
+        crate::cover::draw_text_line(&mut canvas, font, RENDER_FAILED_TEXT, size, baseline, foreground);
+    }
+
+    let mut image_data = Vec::new();
+    image::codecs::png::PngEncoder::new(&mut image_data)
+        .write_image(canvas.as_raw(), width, height, image::ColorType::Rgb8)
+        .map_err(|e| PdfError::render_error(page_number, "占位图编码", e.to_string()))?;
+    // 占位图的 ETag 与正常渲染结果区分开，重试成功后前端不会沿用占位图
+    let placeholder_key = CacheKey::new(
+        String::new(),
+        page_number,
+        options.quality.clone(),
+        width,
+        height,
+        format!("{}#error", options.cache_variant()),
+    );
+    Ok(RenderResult {
+        image_data,
+        width,
+        height,
+        format: ImageFormat::Png,
+        etag: placeholder_key.etag(&error),
+        error: Some(error),
+        rotation: 0,
+    })
+}
 
 /// PDF 渲染器，负责将 PDF 页面渲染为图像
 pub struct PdfRenderer {
     file_path: String,
+    /// 文档版本（路径、大小、修改时间的哈希），参与生成渲染结果的 ETag
+    doc_version: String,
     cache: CacheManager,
     thumb_cache: CacheManager,
     performance_monitor: Option<PerformanceMonitor>,
@@ -23,6 +158,7 @@ impl PdfRenderer {
     /// 创建新的渲染器
     pub fn new(file_path: String, pdfium: Arc<Pdfium>) -> Self {
         Self {
+            doc_version: compute_file_hash(&file_path).unwrap_or_default(),
             file_path,
             cache: CacheManager::new(),
             thumb_cache: CacheManager::with_limits(16 * 1024 * 1024, 64),
@@ -34,6 +170,7 @@ impl PdfRenderer {
     /// 使用指定的缓存管理器创建渲染器
     pub fn with_cache(file_path: String, pdfium: Arc<Pdfium>, cache: CacheManager) -> Self {
         Self {
+            doc_version: compute_file_hash(&file_path).unwrap_or_default(),
             file_path,
             cache,
             thumb_cache: CacheManager::with_limits(16 * 1024 * 1024, 64),
@@ -42,6 +179,23 @@ impl PdfRenderer {
         }
     }
 
+    /// 使用指定的页面缓存与缩略图缓存创建渲染器（缩略图缓存可跨多次请求复用）
+    pub fn with_caches(
+        file_path: String,
+        pdfium: Arc<Pdfium>,
+        cache: CacheManager,
+        thumb_cache: CacheManager,
+    ) -> Self {
+        Self {
+            doc_version: compute_file_hash(&file_path).unwrap_or_default(),
+            file_path,
+            cache,
+            thumb_cache,
+            performance_monitor: Some(PerformanceMonitor::new()),
+            pdfium,
+        }
+    }
+
     /// 设置性能监控器
     pub fn with_performance_monitor(mut self, monitor: PerformanceMonitor) -> Self {
         self.performance_monitor = Some(monitor);
@@ -67,6 +221,7 @@ impl PdfRenderer {
         options: RenderOptions,
     ) -> Result<RenderResult, PdfError> {
         // 获取页面
+        let load_start = Instant::now();
         let page = document
             .pages()
             .get((page_number - 1) as u16)
@@ -74,32 +229,39 @@ impl PdfRenderer {
                 PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
             })?;
 
-        let base_width = page.width().value;
-        let base_height = page.height().value;
+        let rotation = options.rotation_for(page.width().value, page.height().value);
+        let (base_width, base_height) = rotated_page_size(page.width().value, page.height().value, rotation);
+        let load_time = load_start.elapsed();
 
         let (target_width, target_height) =
             self.calculate_dimensions(base_width, base_height, &options);
 
-        let theme_key = options
-            .theme
-            .clone()
-            .unwrap_or_else(|| "light".to_string());
+        let theme_key = options.cache_variant_for_rotation(rotation);
         let cache_key = CacheKey::new(
             self.file_path.clone(),
             page_number,
             options.quality.clone(),
             target_width,
             target_height,
-            theme_key,
+            theme_key.clone(),
         );
+        let cache = if matches!(options.quality, RenderQuality::Thumbnail) {
+            self.thumb_cache.clone()
+        } else {
+            self.cache.clone()
+        };
 
         let cached = tokio::task::block_in_place(|| {
             tokio::runtime::Handle::current().block_on(async {
-                if matches!(options.quality, RenderQuality::Thumbnail) {
-                    BookRenderCache::cache_get(&self.thumb_cache, &cache_key).await
-                } else {
-                    BookRenderCache::cache_get(&self.cache, &cache_key).await
+                let cached = BookRenderCache::cache_get(&cache, &cache_key).await;
+                if let Some(monitor) = &self.performance_monitor {
+                    if cached.is_some() {
+                        monitor.record_cache_hit().await;
+                    } else {
+                        monitor.record_cache_miss().await;
+                    }
                 }
+                cached
             })
         });
 
@@ -108,40 +270,182 @@ impl PdfRenderer {
         }
 
         // 渲染页面
-        let image = self.render_page_to_image(&page, page_number, target_width, target_height, &options)?;
-
-        // 编码图像（按质量选择格式）
-        let out_format = match options.quality {
-            RenderQuality::Thumbnail => ImageFormat::Png,
-            RenderQuality::Standard => ImageFormat::WebP,
-            RenderQuality::High => ImageFormat::WebP,
-            RenderQuality::Best => ImageFormat::Png,
+        let rasterize_start = Instant::now();
+        let image = self.render_page_to_image(&page, page_number, target_width, target_height, rotation, &options)?;
+        let rasterize_time = rasterize_start.elapsed();
+
+        // 编码图像（按质量选择格式），内容相同的页面直接复用已有编码结果
+        let encode_start = Instant::now();
+        let out_format = options.output_format();
+        let content_key = ContentKey::from_image(&image, options.quality.clone(), out_format.clone(), theme_key);
+        let reused = tokio::task::block_in_place(|| {
+            tokio::runtime::Handle::current().block_on(cache.get_encoded(&content_key))
+        });
+        let encoded_now = reused.is_none();
+        let image_data = match reused {
+            Some(image_data) => image_data,
+            None => self.encode_image(&image, out_format.clone())?,
         };
-        let image_data = self.encode_image(&image, out_format.clone())?;
+        let encode_time = encode_start.elapsed();
+
+        if let Some(monitor) = &self.performance_monitor {
+            let timings = RenderStageTimings {
+                load: load_time,
+                rasterize: rasterize_time,
+                encode: encode_time,
+            };
+            tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current().block_on(monitor.record_page_render(page_number, timings))
+            });
+        }
 
         let result = RenderResult {
             image_data,
             width: target_width,
             height: target_height,
             format: out_format,
+            error: None,
+            rotation,
+            etag: cache_key.etag(&self.doc_version),
         };
 
         // 异步缓存结果（不阻塞返回）
         let cache_key_clone = cache_key.clone();
         let result_clone = result.clone();
-        let cache = if matches!(options.quality, RenderQuality::Thumbnail) {
-            self.thumb_cache.clone()
-        } else {
-            self.cache.clone()
-        };
-        
+
         tokio::task::spawn(async move {
+            if encoded_now {
+                cache.put_encoded(content_key, result_clone.image_data.clone()).await;
+            }
             let _ = BookRenderCache::cache_put(&cache, cache_key_clone, result_clone).await;
         });
 
         Ok(result)
     }
 
+    /// 批量渲染缩略图（同步版本）
+    /// Pdfium 不支持多线程访问同一文档，因此光栅化按页顺序执行，耗时的 PNG 编码再分摊到多个线程并行完成
+    pub fn render_thumbnails_sync(
+        &self,
+        document: &PdfDocument<'_>,
+        page_numbers: &[u32],
+        options: RenderOptions,
+    ) -> Vec<(u32, Result<RenderResult, PdfError>)> {
+        let options = RenderOptions {
+            quality: RenderQuality::Thumbnail,
+            ..options
+        };
+        let theme_key = options.cache_variant();
+
+        let mut results: Vec<(u32, Option<Result<RenderResult, PdfError>>)> =
+            Vec::with_capacity(page_numbers.len());
+        // 待编码的页面：(结果下标, 缓存键, 图像)
+        let mut pending: Vec<(usize, CacheKey, RgbaImage)> = Vec::new();
+
+        for &page_number in page_numbers {
+            let page = match document.pages().get((page_number - 1) as u16) {
+                Ok(page) => page,
+                Err(e) => {
+                    results.push((
+                        page_number,
+                        Some(Err(PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string()))),
+                    ));
+                    continue;
+                }
+            };
+
+            let (target_width, target_height) =
+                self.calculate_dimensions(page.width().value, page.height().value, &options);
+            let cache_key = CacheKey::new(
+                self.file_path.clone(),
+                page_number,
+                RenderQuality::Thumbnail,
+                target_width,
+                target_height,
+                theme_key.clone(),
+            );
+
+            let cached = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current()
+                    .block_on(BookRenderCache::cache_get(&self.thumb_cache, &cache_key))
+            });
+            if let Some(result) = cached {
+                results.push((page_number, Some(Ok(result))));
+                continue;
+            }
+
+            match self.render_page_to_image(&page, page_number, target_width, target_height, 0, &options) {
+                Ok(image) => {
+                    pending.push((results.len(), cache_key, image));
+                    results.push((page_number, None));
+                }
+                Err(e) => results.push((page_number, Some(Err(e)))),
+            }
+        }
+
+        if !pending.is_empty() {
+            let workers = std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+                .clamp(1, 4);
+            let chunk_size = pending.len().div_ceil(workers);
+            let doc_version = &self.doc_version;
+
+            let encoded: Vec<(usize, CacheKey, Result<RenderResult, PdfError>)> = std::thread::scope(|scope| {
+                let handles: Vec<_> = pending
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .map(|(idx, key, image)| {
+                                    let result = encode_png(image).map(|image_data| RenderResult {
+                                        image_data,
+                                        width: key.width,
+                                        height: key.height,
+                                        format: ImageFormat::Png,
+                                        error: None,
+                                        rotation: 0,
+                                        etag: key.etag(doc_version),
+                                    });
+                                    (*idx, key.clone(), result)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+
+                handles
+                    .into_iter()
+                    .flat_map(|h| h.join().unwrap_or_default())
+                    .collect()
+            });
+
+            for (idx, key, result) in encoded {
+                if let Ok(ref rendered) = result {
+                    let thumb_cache = self.thumb_cache.clone();
+                    let rendered = rendered.clone();
+                    tokio::task::block_in_place(|| {
+                        tokio::runtime::Handle::current().block_on(async {
+                            let _ = BookRenderCache::cache_put(&thumb_cache, key, rendered).await;
+                        })
+                    });
+                }
+                results[idx].1 = Some(result);
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|(page_number, result)| {
+                let result = result.unwrap_or_else(|| {
+                    Err(PdfError::render_error(page_number, "PNG编码", "编码线程异常退出".to_string()))
+                });
+                (page_number, result)
+            })
+            .collect()
+    }
+
     /// 渲染单个页面
     pub async fn render_page(
         &self,
@@ -163,34 +467,28 @@ impl PdfRenderer {
                 PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
             })?;
 
-        let base_width = page.width().value;
-        let base_height = page.height().value;
+        let rotation = options.rotation_for(page.width().value, page.height().value);
+        let (base_width, base_height) = rotated_page_size(page.width().value, page.height().value, rotation);
 
         let (target_width, target_height) =
             self.calculate_dimensions(base_width, base_height, &options);
 
-        let theme_key = options
-            .theme
-            .clone()
-            .unwrap_or_else(|| "light".to_string());
+        let theme_key = options.cache_variant_for_rotation(rotation);
         let cache_key = CacheKey::new(
             self.file_path.clone(),
             page_number,
             options.quality.clone(),
             target_width,
             target_height,
-            theme_key,
+            theme_key.clone(),
         );
 
-        let use_thumb_cache = matches!(options.quality, RenderQuality::Thumbnail);
-        if use_thumb_cache {
-            if let Some(cached) = BookRenderCache::cache_get(&self.thumb_cache, &cache_key).await {
-                if let Some(monitor) = &self.performance_monitor {
-                    monitor.record_cache_hit().await;
-                }
-                return Ok(cached);
-            }
-        } else if let Some(cached) = BookRenderCache::cache_get(&self.cache, &cache_key).await {
+        let cache = if matches!(options.quality, RenderQuality::Thumbnail) {
+            &self.thumb_cache
+        } else {
+            &self.cache
+        };
+        if let Some(cached) = BookRenderCache::cache_get(cache, &cache_key).await {
             if let Some(monitor) = &self.performance_monitor {
                 monitor.record_cache_hit().await;
             }
@@ -202,49 +500,77 @@ impl PdfRenderer {
         }
 
         // 渲染页面
-        let image = self.render_page_to_image(&page, page_number, target_width, target_height, &options)?;
-
-        // 编码图像（按质量选择格式）
-        let out_format = match options.quality {
-            RenderQuality::Thumbnail => ImageFormat::Png,
-            RenderQuality::Standard => ImageFormat::WebP,
-            RenderQuality::High => ImageFormat::WebP,
-            RenderQuality::Best => ImageFormat::Png,
+        let image = self.render_page_to_image(&page, page_number, target_width, target_height, rotation, &options)?;
+
+        // 编码图像（按质量选择格式），内容相同的页面直接复用已有编码结果
+        let out_format = options.output_format();
+        let content_key = ContentKey::from_image(&image, options.quality.clone(), out_format.clone(), theme_key);
+        let image_data = match cache.get_encoded(&content_key).await {
+            Some(image_data) => image_data,
+            None => {
+                let image_data = self.encode_image(&image, out_format.clone())?;
+                cache.put_encoded(content_key, image_data.clone()).await;
+                image_data
+            }
         };
-        let image_data = self.encode_image(&image, out_format.clone())?;
 
         let result = RenderResult {
             image_data,
             width: target_width,
             height: target_height,
             format: out_format,
+            error: None,
+            rotation,
+            etag: cache_key.etag(&self.doc_version),
         };
 
-        if use_thumb_cache {
-            BookRenderCache::cache_put(&self.thumb_cache, cache_key, result.clone()).await?;
-        } else {
-            BookRenderCache::cache_put(&self.cache, cache_key, result.clone()).await?;
-        }
+        BookRenderCache::cache_put(cache, cache_key, result.clone()).await?;
 
         timer.finish().await;
 
         Ok(result)
     }
 
-    /// 将 PDF 页面渲染为图像
+    /// 渲染页面为未编码的 RGBA 位图，不经过缓存（供 OCR 等需要原始像素的场景）
+    pub fn render_page_image_sync(
+        &self,
+        document: &PdfDocument<'_>,
+        page_number: u32,
+        options: &RenderOptions,
+    ) -> Result<RgbaImage, PdfError> {
+        let page = document
+            .pages()
+            .get((page_number - 1) as u16)
+            .map_err(|e| {
+                PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
+            })?;
+        let (width, height) = self.calculate_dimensions(page.width().value, page.height().value, options);
+        self.render_page_to_image(&page, page_number, width, height, 0, options)
+    }
+
+    /// 将 PDF 页面渲染为图像，`width`/`height` 为旋转后的输出尺寸
     fn render_page_to_image(
         &self,
         page: &PdfPage,
         page_number: u32,
         width: u32,
         height: u32,
+        rotation: u32,
         options: &RenderOptions,
     ) -> Result<RgbaImage, PdfError> {
-        // 配置渲染选项
-        let config = PdfRenderConfig::new()
-            .set_target_width(width as i32)
-            .set_target_height(height as i32)
-            .rotate_if_landscape(PdfPageRenderRotation::None, false);
+        // 配置渲染选项；横页旋转时目标尺寸按未旋转页面给出，由 pdfium 旋转约束后输出 width x height 的位图
+        let config = if rotation == 90 {
+            PdfRenderConfig::new()
+                .set_target_width(height as i32)
+                .set_target_height(width as i32)
+                .rotate_if_landscape(PdfPageRenderRotation::Degrees90, true)
+        } else {
+            PdfRenderConfig::new()
+                .set_target_width(width as i32)
+                .set_target_height(height as i32)
+                .rotate_if_landscape(PdfPageRenderRotation::None, false)
+        };
+        let config = apply_render_options(config, options);
 
         // 渲染为位图
         let bitmap = page.render_with_config(&config).map_err(|e| {
@@ -288,6 +614,7 @@ impl PdfRenderer {
             .set_target_width(target_width as i32)
             .set_target_height(target_height as i32)
             .rotate_if_landscape(PdfPageRenderRotation::None, false);
+        let config = apply_render_options(config, &options);
 
         let bitmap = page.render_with_config(&config).map_err(|e| {
             PdfError::render_error(page_number, "render_with_config", e.to_string())
@@ -307,7 +634,150 @@ impl PdfRenderer {
 
         let image_data = self.encode_image(&sub_image, ImageFormat::Png)?;
 
-        Ok(RenderResult { image_data, width: region_px_w, height: region_px_h, format: ImageFormat::Png })
+        let region_key = CacheKey::new(
+            self.file_path.clone(),
+            page_number,
+            options.quality.clone(),
+            target_width,
+            target_height,
+            format!(
+                "{}#region:{},{},{}x{}",
+                options.cache_variant(),
+                region_px_x,
+                region_px_y,
+                region_px_w,
+                region_px_h
+            ),
+        );
+
+        Ok(RenderResult {
+            image_data,
+            width: region_px_w,
+            height: region_px_h,
+            format: ImageFormat::Png,
+            error: None,
+            rotation: 0,
+            etag: region_key.etag(&self.doc_version),
+        })
+    }
+
+    /// 渲染与视口相交的分块（同步版本）
+    /// 每个分块通过平移+缩放矩阵直接渲染到分块大小的位图，不生成整页高分辨率位图
+    pub fn render_page_tiles_sync(
+        &self,
+        document: &PdfDocument<'_>,
+        page_number: u32,
+        grid: &TileGrid,
+        options: RenderOptions,
+    ) -> Result<Vec<PageTile>, PdfError> {
+        let page = document
+            .pages()
+            .get((page_number - 1) as u16)
+            .map_err(|e| {
+                PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string())
+            })?;
+
+        let base_width = page.width().value;
+        let base_height = page.height().value;
+        let (page_width, page_height) = grid.page_pixel_size(base_width, base_height);
+        let variant = options.cache_variant();
+        let out_format = options.output_format();
+
+        let mut tiles = Vec::new();
+        for rect in grid.visible_tiles(base_width, base_height) {
+            let cache_key = TileKey::new(
+                page_number,
+                options.quality.clone(),
+                rect.x,
+                rect.y,
+                rect.width,
+                rect.height,
+            )
+            .for_page(page_width, page_height, variant.clone())
+            .cache_key(&self.file_path);
+
+            let cached = tokio::task::block_in_place(|| {
+                tokio::runtime::Handle::current()
+                    .block_on(BookRenderCache::cache_get(&self.cache, &cache_key))
+            });
+
+            let result = match cached {
+                Some(result) => result,
+                None => {
+                    let image = self.render_tile_to_image(
+                        &page,
+                        page_number,
+                        (page_width, page_height),
+                        &rect,
+                        &options,
+                    )?;
+                    let image_data = self.encode_image(&image, out_format.clone())?;
+                    let result = RenderResult {
+                        image_data,
+                        width: rect.width,
+                        height: rect.height,
+                        format: out_format.clone(),
+                        error: None,
+                        rotation: 0,
+                        etag: cache_key.etag(&self.doc_version),
+                    };
+
+                    let cache = self.cache.clone();
+                    let result_clone = result.clone();
+                    tokio::task::spawn(async move {
+                        let _ = BookRenderCache::cache_put(&cache, cache_key, result_clone).await;
+                    });
+                    result
+                }
+            };
+
+            tiles.push(PageTile {
+                row: rect.row,
+                column: rect.column,
+                x: rect.x,
+                y: rect.y,
+                result,
+            });
+        }
+
+        Ok(tiles)
+    }
+
+    /// 渲染单个分块：先把页面平移到分块左上角，再缩放到整页像素尺寸
+    fn render_tile_to_image(
+        &self,
+        page: &PdfPage,
+        page_number: u32,
+        page_size: (u32, u32),
+        rect: &TileRect,
+        options: &RenderOptions,
+    ) -> Result<RgbaImage, PdfError> {
+        let (page_width, page_height) = page_size;
+        let scale_x = page_width as f32 / page.width().value;
+        let scale_y = page_height as f32 / page.height().value;
+
+        let config = PdfRenderConfig::new()
+            .set_target_size(page_width as i32, page_height as i32)
+            .rotate_if_landscape(PdfPageRenderRotation::None, false)
+            .translate(
+                PdfPoints::new(-(rect.x as f32) / scale_x),
+                PdfPoints::new(-(rect.y as f32) / scale_y),
+            )
+            .map_err(|e| PdfError::render_error(page_number, "tile_transform", e.to_string()))?;
+        let config = apply_render_options(config, options);
+
+        let mut bitmap = PdfBitmap::empty(
+            rect.width as i32,
+            rect.height as i32,
+            PdfBitmapFormat::BGRA,
+            self.pdfium.bindings(),
+        )
+        .map_err(|e| PdfError::render_error(page_number, "create_bitmap", e.to_string()))?;
+
+        page.render_into_bitmap_with_config(&mut bitmap, &config)
+            .map_err(|e| PdfError::render_error(page_number, "render_into_bitmap", e.to_string()))?;
+
+        self.bitmap_to_rgba_image(&bitmap, page_number, rect.width, rect.height, options)
     }
 
     /// 将 Pdfium 位图转换为 RGBA 图像
@@ -344,6 +814,9 @@ impl PdfRenderer {
         // 3. 准备目标缓冲区
         let target_size = (width * height * 4) as usize;
         let mut rgba_data = Vec::with_capacity(target_size);
+        // 背景合成与夜间反色在格式转换的同一次遍历中完成
+        let invert = ThemeInvert::from_theme(options.theme.as_deref());
+        let background = options.background_rgba().0;
 
         // 4. 按行遍历并转换
         match format {
@@ -360,10 +833,12 @@ impl PdfRenderer {
                     
                     // 使用 chunks_exact 优化循环
                     for chunk in row_data.chunks_exact(4) {
-                        rgba_data.push(chunk[0]); // R
-                        rgba_data.push(chunk[1]); // G
-                        rgba_data.push(chunk[2]); // B
-                        rgba_data.push(chunk[3]); // A
+                        let [r, g, b, a] = blend_background(chunk[0], chunk[1], chunk[2], chunk[3], background);
+                        let [r, g, b] = invert.apply(r, g, b);
+                        rgba_data.push(r); // R
+                        rgba_data.push(g); // G
+                        rgba_data.push(b); // B
+                        rgba_data.push(a); // A
                     }
                 }
             }
@@ -378,10 +853,11 @@ impl PdfRenderer {
                     let row_data = &buffer[start..end];
                     
                     for chunk in row_data.chunks_exact(3) {
-                        rgba_data.push(chunk[0]); // R
-                        rgba_data.push(chunk[1]); // G
-                        rgba_data.push(chunk[2]); // B
-                        rgba_data.push(255);      // A
+                        let [r, g, b] = invert.apply(chunk[0], chunk[1], chunk[2]);
+                        rgba_data.push(r);   // R
+                        rgba_data.push(g);   // G
+                        rgba_data.push(b);   // B
+                        rgba_data.push(255); // A
                     }
                 }
             }
@@ -396,6 +872,7 @@ impl PdfRenderer {
                     let row_data = &buffer[start..end];
                     
                     for &val in row_data {
+                        let [val, _, _] = invert.apply(val, val, val);
                         rgba_data.push(val); // R
                         rgba_data.push(val); // G
                         rgba_data.push(val); // B
@@ -417,23 +894,6 @@ impl PdfRenderer {
             rgba_data.resize(target_size, 0);
         }
 
-        if let Some(theme) = options.theme.as_deref() {
-            if theme == "dark" {
-                let mut i = 0usize;
-                while i + 3 < rgba_data.len() {
-                    let r = rgba_data[i];
-                    let g = rgba_data[i + 1];
-                    let b = rgba_data[i + 2];
-                    let a = rgba_data[i + 3];
-                    rgba_data[i] = 255u8.saturating_sub(r);
-                    rgba_data[i + 1] = 255u8.saturating_sub(g);
-                    rgba_data[i + 2] = 255u8.saturating_sub(b);
-                    rgba_data[i + 3] = a;
-                    i += 4;
-                }
-            }
-        }
-
         RgbaImage::from_vec(width, height, rgba_data).ok_or_else(|| {
             PdfError::render_error(
                 page_number,
@@ -471,6 +931,8 @@ impl PdfRenderer {
         let stride = ((full_width * bytes_per_pixel + 3) & !3) as usize;
 
         let mut rgba_data = Vec::with_capacity((w * h * 4) as usize);
+        let invert = ThemeInvert::from_theme(options.theme.as_deref());
+        let background = options.background_rgba().0;
 
         match format {
             PdfBitmapFormat::BGRA => {
@@ -480,10 +942,13 @@ impl PdfRenderer {
                         let sx = x + col;
                         let idx = (sy as usize) * stride + (sx as usize) * 4;
                         if idx + 3 >= buffer.len() { rgba_data.extend_from_slice(&[0,0,0,0]); continue; }
-                        rgba_data.push(buffer[idx + 0]);
-                        rgba_data.push(buffer[idx + 1]);
-                        rgba_data.push(buffer[idx + 2]);
-                        rgba_data.push(buffer[idx + 3]);
+                        let [r, g, b, a] =
+                            blend_background(buffer[idx], buffer[idx + 1], buffer[idx + 2], buffer[idx + 3], background);
+                        let [r, g, b] = invert.apply(r, g, b);
+                        rgba_data.push(r);
+                        rgba_data.push(g);
+                        rgba_data.push(b);
+                        rgba_data.push(a);
                     }
                 }
             }
@@ -494,9 +959,10 @@ impl PdfRenderer {
                         let sx = x + col;
                         let idx = (sy as usize) * stride + (sx as usize) * 3;
                         if idx + 2 >= buffer.len() { rgba_data.extend_from_slice(&[0,0,0,255]); continue; }
-                        rgba_data.push(buffer[idx + 0]);
-                        rgba_data.push(buffer[idx + 1]);
-                        rgba_data.push(buffer[idx + 2]);
+                        let [r, g, b] = invert.apply(buffer[idx], buffer[idx + 1], buffer[idx + 2]);
+                        rgba_data.push(r);
+                        rgba_data.push(g);
+                        rgba_data.push(b);
                         rgba_data.push(255);
                     }
                 }
@@ -508,7 +974,7 @@ impl PdfRenderer {
                         let sx = x + col;
                         let idx = (sy as usize) * stride + (sx as usize);
                         if idx >= buffer.len() { rgba_data.extend_from_slice(&[0,0,0,255]); continue; }
-                        let v = buffer[idx];
+                        let [v, _, _] = invert.apply(buffer[idx], buffer[idx], buffer[idx]);
                         rgba_data.push(v);
                         rgba_data.push(v);
                         rgba_data.push(v);
@@ -521,35 +987,36 @@ impl PdfRenderer {
             }
         }
 
-        if let Some(theme) = options.theme.as_deref() {
-            if theme == "dark" {
-                let mut i = 0usize;
-                while i + 3 < rgba_data.len() {
-                    let r = rgba_data[i];
-                    let g = rgba_data[i + 1];
-                    let b = rgba_data[i + 2];
-                    let a = rgba_data[i + 3];
-                    rgba_data[i] = 255u8.saturating_sub(r);
-                    rgba_data[i + 1] = 255u8.saturating_sub(g);
-                    rgba_data[i + 2] = 255u8.saturating_sub(b);
-                    rgba_data[i + 3] = a;
-                    i += 4;
-                }
-            }
-        }
-
         RgbaImage::from_vec(w, h, rgba_data).ok_or_else(|| {
             PdfError::render_error(page_number, "image_creation", "无法从数据创建图像缓冲区".to_string())
         })
     }
 
-    /// 计算目标尺寸
+    /// 计算目标尺寸，超过像素上限时自动下调缩放
     fn calculate_dimensions(
         &self,
         base_width: f32,
         base_height: f32,
         options: &RenderOptions,
     ) -> (u32, u32) {
+        let (width, height) = Self::requested_dimensions(base_width, base_height, options);
+        let max_pixels = options
+            .max_pixels
+            .map_or_else(max_render_pixels, |v| v.max(MIN_MAX_RENDER_PIXELS));
+        let (fitted_width, fitted_height) = fit_pixel_budget(width, height, max_pixels);
+        if (fitted_width, fitted_height) != (width, height) {
+            let message = format!(
+                "页面尺寸超过像素上限，下调缩放: file={}, requested={}x{}, fitted={}x{}, max_pixels={}",
+                self.file_path, width, height, fitted_width, fitted_height, max_pixels
+            );
+            println!("[PdfRenderer] {}", message);
+            write_log("warn", "PDF", &message);
+        }
+        (fitted_width, fitted_height)
+    }
+
+    /// 按渲染质量或指定宽高计算的原始目标尺寸
+    fn requested_dimensions(base_width: f32, base_height: f32, options: &RenderOptions) -> (u32, u32) {
         let scale = options.quality.scale_factor();
 
         // 防止尺寸为 0
@@ -583,15 +1050,7 @@ impl PdfRenderer {
 
         match format {
             ImageFormat::Png => {
-                let encoder = image::codecs::png::PngEncoder::new_with_quality(
-                    &mut buffer,
-                    image::codecs::png::CompressionType::Best,
-                    image::codecs::png::FilterType::Adaptive,
-                );
-                use image::ImageEncoder;
-                encoder
-                    .write_image(image.as_raw(), width, height, image::ColorType::Rgba8)
-                    .map_err(|e| PdfError::render_error(0, "PNG编码", e.to_string()))?;
+                buffer = encode_png(image)?;
             }
             ImageFormat::Jpeg => {
                 let rgb_image = self.convert_rgba_to_rgb(image);
@@ -612,6 +1071,10 @@ impl PdfRenderer {
                 };
                 buffer = webp_data.to_vec();
             }
+            ImageFormat::Avif => {
+                let quality = self.calculate_avif_quality(width, height);
+                buffer = encode_avif(image, quality)?;
+            }
         }
 
         Ok(buffer)
@@ -660,6 +1123,21 @@ impl PdfRenderer {
         }
     }
 
+    /// 计算 AVIF 质量
+    /// AVIF 在同等质量值下的主观画质高于 WebP，整体比 `calculate_webp_quality` 低一档
+    fn calculate_avif_quality(&self, width: u32, height: u32) -> f32 {
+        let pixels = width * height;
+        if pixels > 2_000_000 {
+            70.0
+        } else if pixels > 1_000_000 {
+            75.0
+        } else if pixels > 500_000 {
+            80.0
+        } else {
+            85.0
+        }
+    }
+
     /// 并行渲染多个页面
     pub async fn render_pages_parallel(
         &self,
@@ -726,8 +1204,95 @@ impl PdfRenderer {
     }
 
     /// 清除指定页面的缓存
-    pub async fn clear_page_cache(&self, page_number: u32) {
-        BookRenderCache::cache_clear_page(&self.cache, &self.file_path, page_number).await;
+    pub async fn clear_page_cache(&self, page_number: u32) -> usize {
+        BookRenderCache::cache_clear_page(&self.cache, &self.file_path, page_number).await
+    }
+}
+
+/// PNG 编码（不依赖渲染器状态，可在工作线程中调用）
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, PdfError> {
+    let mut buffer = Vec::new();
+    let (width, height) = image.dimensions();
+    let encoder = image::codecs::png::PngEncoder::new_with_quality(
+        &mut buffer,
+        image::codecs::png::CompressionType::Best,
+        image::codecs::png::FilterType::Adaptive,
+    );
+    use image::ImageEncoder;
+    encoder
+        .write_image(image.as_raw(), width, height, image::ColorType::Rgba8)
+        .map_err(|e| PdfError::render_error(0, "PNG编码", e.to_string()))?;
+    Ok(buffer)
+}
+
+/// AVIF 编码速度（1 最慢最小，10 最快），取偏快的档位以控制翻页时的编码耗时
+const AVIF_ENCODE_SPEED: u8 = 8;
+
+fn encode_avif(image: &RgbaImage, quality: f32) -> Result<Vec<u8>, PdfError> {
+    let (width, height) = image.dimensions();
+    let pixels: Vec<ravif::RGBA8> = image
+        .as_raw()
+        .chunks_exact(4)
+        .map(|p| ravif::RGBA8::new(p[0], p[1], p[2], p[3]))
+        .collect();
+    let encoded = ravif::Encoder::new()
+        .with_quality(quality)
+        .with_speed(AVIF_ENCODE_SPEED)
+        .encode_rgba(ravif::Img::new(pixels.as_slice(), width as usize, height as usize))
+        .map_err(|e| PdfError::render_error(0, "AVIF编码", e.to_string()))?;
+    Ok(encoded.avif_file)
+}
+
+/// 透明/半透明像素与背景色做 alpha 合成，背景不透明时结果也不透明；
+/// 合成发生在主题反色之前，背景与页面纸张一样随夜间主题反色
+#[inline]
+fn blend_background(r: u8, g: u8, b: u8, a: u8, background: [u8; 4]) -> [u8; 4] {
+    if a == 255 {
+        return [r, g, b, a];
+    }
+    let alpha = a as u32;
+    let mix = |fg: u8, bg: u8| ((fg as u32 * alpha + bg as u32 * (255 - alpha) + 127) / 255) as u8;
+    let out_alpha = alpha + (background[3] as u32 * (255 - alpha) + 127) / 255;
+    [mix(r, background[0]), mix(g, background[1]), mix(b, background[2]), out_alpha.min(255) as u8]
+}
+
+/// 智能反色的饱和度阈值（RGB 最大分量与最小分量之差），低于该值视为接近灰度
+const SMART_INVERT_SATURATION_THRESHOLD: u8 = 48;
+
+/// 夜间主题的反色方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ThemeInvert {
+    /// 不反色
+    None,
+    /// `dark`：整页反色
+    Full,
+    /// `dark_smart`：只反转接近黑白的像素（文字、背景），保留彩色插图
+    Smart,
+}
+
+impl ThemeInvert {
+    fn from_theme(theme: Option<&str>) -> Self {
+        match theme {
+            Some("dark") => ThemeInvert::Full,
+            Some("dark_smart") => ThemeInvert::Smart,
+            _ => ThemeInvert::None,
+        }
+    }
+
+    #[inline]
+    fn apply(self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        match self {
+            ThemeInvert::None => [r, g, b],
+            ThemeInvert::Full => [255 - r, 255 - g, 255 - b],
+            ThemeInvert::Smart => {
+                let saturation = r.max(g).max(b) - r.min(g).min(b);
+                if saturation < SMART_INVERT_SATURATION_THRESHOLD {
+                    [255 - r, 255 - g, 255 - b]
+                } else {
+                    [r, g, b]
+                }
+            }
+        }
     }
 }
 
@@ -735,6 +1300,7 @@ impl Clone for PdfRenderer {
     fn clone(&self) -> Self {
         Self {
             file_path: self.file_path.clone(),
+            doc_version: self.doc_version.clone(),
             cache: self.cache.clone(),
             thumb_cache: self.thumb_cache.clone(),
             performance_monitor: self.performance_monitor.clone(),
@@ -742,3 +1308,98 @@ impl Clone for PdfRenderer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_error_placeholder() {
+        let options = RenderOptions {
+            width: Some(600),
+            fit_to_width: true,
+            ..RenderOptions::default()
+        };
+        let result = render_error_placeholder(3, Some((300.0, 400.0)), &options, "页面损坏".to_string()).unwrap();
+        assert_eq!((result.width, result.height), (600, 800));
+        assert_eq!(result.error.as_deref(), Some("页面损坏"));
+        let decoded = image::load_from_memory(&result.image_data).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (600, 800));
+
+        // 超大尺寸按占位图像素上限缩小
+        let options = RenderOptions {
+            width: Some(4000),
+            fit_to_width: true,
+            ..RenderOptions::default()
+        };
+        let result = render_error_placeholder(1, None, &options, String::new()).unwrap();
+        assert!((result.width * result.height) as usize <= PLACEHOLDER_MAX_PIXELS);
+    }
+
+    #[test]
+    fn test_blend_background() {
+        assert_eq!(blend_background(10, 20, 30, 255, [255, 255, 255, 255]), [10, 20, 30, 255]);
+        assert_eq!(blend_background(0, 0, 0, 0, [255, 255, 255, 255]), [255, 255, 255, 255]);
+        assert_eq!(blend_background(0, 0, 0, 128, [255, 255, 255, 255]), [127, 127, 127, 255]);
+        assert_eq!(blend_background(0, 0, 0, 0, [40, 50, 60, 255]), [40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn test_render_background_color() {
+        // 需要可加载的 pdfium 动态库，当前平台没有时跳过
+        let Ok(pdfium) = crate::pdf::engine::PdfEngine::create_pdfium() else {
+            eprintln!("pdfium 不可用，跳过背景色渲染测试");
+            return;
+        };
+        let pdfium = Arc::new(pdfium);
+        let mut document = pdfium.create_new_pdf().unwrap();
+        document.pages_mut().create_page_at_end(PdfPagePaperSize::a4()).unwrap();
+
+        let renderer = PdfRenderer::new(String::new(), Arc::clone(&pdfium));
+        let options = RenderOptions {
+            width: Some(60),
+            fit_to_width: true,
+            background_color: Some([40, 50, 60, 255]),
+            ..RenderOptions::default()
+        };
+        let image = renderer.render_page_image_sync(&document, 1, &options).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [40, 50, 60, 255]);
+        assert_eq!(image.get_pixel(30, 40).0, [40, 50, 60, 255]);
+
+        let image = renderer.render_page_image_sync(&document, 1, &RenderOptions::default()).unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_landscape_rotation() {
+        let options = RenderOptions {
+            auto_rotate_landscape: true,
+            ..RenderOptions::default()
+        };
+        assert_eq!(options.rotation_for(842.0, 595.0), 90);
+        assert_eq!(options.rotation_for(595.0, 842.0), 0);
+        assert_eq!(RenderOptions::default().rotation_for(842.0, 595.0), 0);
+        assert_eq!(rotated_page_size(842.0, 595.0, 90), (595.0, 842.0));
+        assert_eq!(options.cache_variant_for_rotation(90), "light_rot90");
+        assert_eq!(options.cache_variant_for_rotation(0), "light");
+    }
+
+    #[test]
+    fn test_cache_key_etag() {
+        let key = |theme: &str| CacheKey::new("/a.pdf".to_string(), 1, RenderQuality::Standard, 800, 1000, theme.to_string());
+        assert_eq!(key("light").etag("v1"), key("light").etag("v1"));
+        assert_ne!(key("light").etag("v1"), key("dark").etag("v1"));
+        assert_ne!(key("light").etag("v1"), key("light").etag("v2"));
+        assert_eq!(key("light").etag("v1").len(), 16);
+    }
+
+    #[test]
+    fn test_fit_pixel_budget() {
+        assert_eq!(fit_pixel_budget(1200, 1600, DEFAULT_MAX_RENDER_PIXELS), (1200, 1600));
+
+        // 海报类页面：40000x30000 缩到 32M 像素以内并保持宽高比
+        let (width, height) = fit_pixel_budget(40_000, 30_000, DEFAULT_MAX_RENDER_PIXELS);
+        assert!(width as u64 * height as u64 <= DEFAULT_MAX_RENDER_PIXELS as u64);
+        assert!((width as f64 / height as f64 - 4.0 / 3.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,136 @@
+//! PDF 分块（tiled）渲染的网格计算
+//! 按缩放比例把整页划分为固定大小的像素网格，只挑出与视口相交的分块；
+//! 网格与视口位置无关，滚动时相同分块的缓存键保持不变
+
+use serde::{Deserialize, Serialize};
+
+use crate::pdf::types::RenderRegion;
+
+/// 默认分块边长（像素）
+pub const DEFAULT_TILE_SIZE: u32 = 512;
+const MIN_TILE_SIZE: u32 = 64;
+const MAX_TILE_SIZE: u32 = 2048;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 16.0;
+
+/// 单次最多返回的分块数，防止高缩放、小分块配合大视口时一次渲染成千上万块
+const MAX_VISIBLE_TILES: usize = 64;
+
+/// 分块渲染请求
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileGrid {
+    /// 缩放比例：每个 PDF 点对应的像素数（前端需自行乘上设备像素比）
+    pub zoom: f32,
+    /// 视口区域，单位为 PDF 点，左上角为原点
+    pub viewport: RenderRegion,
+    /// 分块边长（像素）
+    pub tile_size: u32,
+}
+
+/// 单个分块在缩放后整页位图中的位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TileRect {
+    pub row: u32,
+    pub column: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl TileGrid {
+    pub fn new(zoom: f32, viewport: RenderRegion, tile_size: Option<u32>) -> Self {
+        let zoom = if zoom.is_finite() { zoom.clamp(MIN_ZOOM, MAX_ZOOM) } else { 1.0 };
+        let tile_size = tile_size
+            .unwrap_or(DEFAULT_TILE_SIZE)
+            .clamp(MIN_TILE_SIZE, MAX_TILE_SIZE);
+        Self { zoom, viewport, tile_size }
+    }
+
+    /// 缩放后的整页像素尺寸
+    pub fn page_pixel_size(&self, base_width: f32, base_height: f32) -> (u32, u32) {
+        (
+            (base_width * self.zoom).round().max(1.0) as u32,
+            (base_height * self.zoom).round().max(1.0) as u32,
+        )
+    }
+
+    /// 与视口相交的分块，按行优先排列，最多 `MAX_VISIBLE_TILES` 个（超出的部分需缩小视口后分批请求）；
+    /// 视口完全落在页面外时返回空列表
+    pub fn visible_tiles(&self, base_width: f32, base_height: f32) -> Vec<TileRect> {
+        let (page_width, page_height) = self.page_pixel_size(base_width, base_height);
+        let to_px = |value: f32, limit: u32| (value * self.zoom).clamp(0.0, limit as f32);
+
+        let left = to_px(self.viewport.x, page_width);
+        let top = to_px(self.viewport.y, page_height);
+        let right = to_px(self.viewport.x + self.viewport.width, page_width);
+        let bottom = to_px(self.viewport.y + self.viewport.height, page_height);
+        if right <= left || bottom <= top {
+            return Vec::new();
+        }
+
+        let size = self.tile_size;
+        let first_column = left as u32 / size;
+        let last_column = (right.ceil() as u32).div_ceil(size);
+        let first_row = top as u32 / size;
+        let last_row = (bottom.ceil() as u32).div_ceil(size);
+
+        let mut tiles = Vec::new();
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                if tiles.len() >= MAX_VISIBLE_TILES {
+                    return tiles;
+                }
+                let x = column * size;
+                let y = row * size;
+                tiles.push(TileRect {
+                    row,
+                    column,
+                    x,
+                    y,
+                    width: size.min(page_width - x),
+                    height: size.min(page_height - y),
+                });
+            }
+        }
+        tiles
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_tiles_cover_viewport() {
+        // 600x800 的页面放大 2 倍后为 1200x1600
+        let viewport = RenderRegion { x: 250.0, y: 100.0, width: 300.0, height: 200.0 };
+        let grid = TileGrid::new(2.0, viewport, Some(512));
+        let tiles = grid.visible_tiles(600.0, 800.0);
+
+        // 视口像素范围 x: 500..1100, y: 200..600
+        let columns: Vec<u32> = tiles.iter().filter(|t| t.row == 0).map(|t| t.column).collect();
+        assert_eq!(columns, vec![0, 1, 2]);
+        assert_eq!(tiles.len(), 6);
+
+        let edge = tiles.iter().find(|t| t.row == 1 && t.column == 2).unwrap();
+        assert_eq!((edge.x, edge.y, edge.width, edge.height), (1024, 512, 176, 512));
+    }
+
+    #[test]
+    fn test_viewport_outside_page() {
+        let viewport = RenderRegion { x: 700.0, y: 0.0, width: 100.0, height: 100.0 };
+        let grid = TileGrid::new(1.0, viewport, None);
+        assert!(grid.visible_tiles(600.0, 800.0).is_empty());
+    }
+
+    #[test]
+    fn test_visible_tiles_capped() {
+        // 放大 16 倍、64 像素分块时整页约 150x200 块，只返回前 MAX_VISIBLE_TILES 个
+        let viewport = RenderRegion { x: 0.0, y: 0.0, width: 600.0, height: 800.0 };
+        let grid = TileGrid::new(16.0, viewport, Some(64));
+        let tiles = grid.visible_tiles(600.0, 800.0);
+        assert_eq!(tiles.len(), MAX_VISIBLE_TILES);
+        assert_eq!((tiles[0].row, tiles[0].column), (0, 0));
+    }
+}
@@ -1,12 +1,25 @@
 use image::Rgba;
 use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PdfPageInfo {
+    /// 页面宽高（点）：以 CropBox 为准，与渲染图及文本、批注坐标的基准一致
     pub width: f32,
     pub height: f32,
     pub number: u32,
     pub rotation: i32,
+    /// MediaBox 宽高（点，已按旋转换算到显示方向）；与 width/height 不同时说明页面有裁切（如印刷出血）
+    #[serde(default)]
+    pub media_width: f32,
+    #[serde(default)]
+    pub media_height: f32,
+    /// CropBox 左上角相对 MediaBox 左上角的偏移（点，显示方向），前端显示完整版面时据此定位裁切版面
+    #[serde(default)]
+    pub crop_offset_x: f32,
+    #[serde(default)]
+    pub crop_offset_y: f32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +34,15 @@ pub struct PdfDocumentInfo {
     pub producer: Option<String>,
     pub creation_date: Option<String>,
     pub modification_date: Option<String>,
+    /// 所有页面中的最大宽度（点）
+    pub max_page_width: f32,
+    /// 所有页面中的最大高度（点）
+    pub max_page_height: f32,
+    /// 所有页面尺寸是否一致，前端据此选择统一页框或逐页定高布局；`pages_complete` 为 false 时尚未确定，固定为 false
+    pub uniform_size: bool,
+    /// `pages` 是否已包含全部页面；为 false 时其余页尺寸仍在后台读取，可通过 `pdf_get_page_info` 按页查询
+    #[serde(default = "default_true")]
+    pub pages_complete: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -29,8 +51,15 @@ pub enum RenderQuality {
     Standard,  // 标准质量，正常阅读 (1.0x)
     High,      // 高质量，缩放查看 (1.5x)
     Best,      // 最佳质量，打印预览 (2.0x)
+    Adaptive,  // 自适应，按设备 DPI 与视口宽度直接算出目标像素宽度
 }
 
+/// CSS 参考像素密度，设备 DPI 除以该值即设备像素比
+pub const CSS_REFERENCE_DPI: f32 = 96.0;
+
+/// 自适应渲染相对页面原始尺寸的最大缩放，避免小页面在超大视口上过度渲染
+pub const MAX_ADAPTIVE_SCALE: f32 = 4.0;
+
 impl RenderQuality {
     pub fn scale_factor(&self) -> f32 {
         match self {
@@ -38,6 +67,19 @@ impl RenderQuality {
             RenderQuality::Standard => 1.0,
             RenderQuality::High => 1.5,
             RenderQuality::Best => 2.0,
+            // 自适应渲染总是显式给出目标宽度，缩放系数只作兜底
+            RenderQuality::Adaptive => 1.0,
+        }
+    }
+
+    /// 未显式指定输出格式时该质量使用的编码格式
+    pub fn default_format(&self) -> ImageFormat {
+        match self {
+            RenderQuality::Thumbnail => ImageFormat::Png,
+            RenderQuality::Standard => ImageFormat::WebP,
+            RenderQuality::High => ImageFormat::WebP,
+            RenderQuality::Best => ImageFormat::Png,
+            RenderQuality::Adaptive => ImageFormat::WebP,
         }
     }
 
@@ -54,6 +96,60 @@ impl RenderQuality {
     }
 }
 
+fn default_true() -> bool {
+    true
+}
+
+/// pdfium 渲染标志，默认值与 pdfium-render 的默认配置一致
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RenderFlags {
+    /// 渲染注释与表单控件
+    #[serde(default = "default_true")]
+    pub render_annotations: bool,
+    /// 文本抗锯齿
+    #[serde(default = "default_true")]
+    pub smooth_text: bool,
+    /// 图片与矢量路径抗锯齿
+    #[serde(default = "default_true")]
+    pub smooth_graphics: bool,
+    /// LCD 子像素文本渲染（仅适合不旋转、不反色的横屏显示）
+    #[serde(default)]
+    pub lcd_text: bool,
+    /// 灰度渲染
+    #[serde(default)]
+    pub grayscale: bool,
+}
+
+impl Default for RenderFlags {
+    fn default() -> Self {
+        Self {
+            render_annotations: true,
+            smooth_text: true,
+            smooth_graphics: true,
+            lcd_text: false,
+            grayscale: false,
+        }
+    }
+}
+
+impl RenderFlags {
+    /// 缓存键后缀：默认标志返回 None，保持原有缓存键不变
+    pub fn cache_suffix(&self) -> Option<String> {
+        if *self == Self::default() {
+            return None;
+        }
+        let bit = |on: bool| if on { '1' } else { '0' };
+        Some(format!(
+            "f{}{}{}{}{}",
+            bit(self.render_annotations),
+            bit(self.smooth_text),
+            bit(self.smooth_graphics),
+            bit(self.lcd_text),
+            bit(self.grayscale)
+        ))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RenderOptions {
     pub quality: RenderQuality,
@@ -62,7 +158,25 @@ pub struct RenderOptions {
     pub background_color: Option<[u8; 4]>,
     pub fit_to_width: bool,
     pub fit_to_height: bool,
+    /// 主题：`light`（默认）、`dark`（整页反色）、`dark_smart`（只反转接近黑白的像素，保留彩色图片）
     pub theme: Option<String>,
+    /// 显式指定的输出格式，为 None 时按渲染质量自动选择
+    /// AVIF 编码耗时较高，只在用户显式选择时使用，且缩略图始终使用 PNG
+    #[serde(default)]
+    pub format: Option<ImageFormat>,
+    /// 单页位图最大像素数，超过时自动下调缩放；为 None 时使用全局上限
+    #[serde(default)]
+    pub max_pixels: Option<usize>,
+    /// 注释、抗锯齿、灰度等渲染标志（序列化时展开为同级字段）
+    #[serde(default, flatten)]
+    pub flags: RenderFlags,
+    /// 渲染失败时返回“本页渲染失败，点击重试”占位图而不是错误，真实错误附在 `RenderResult.error`
+    #[serde(default)]
+    pub fallback_on_error: bool,
+    /// 横向页面（宽大于高）顺时针旋转 90 度渲染，使宽幅图表铺满竖屏宽度；前端仅在设备竖屏时开启
+    /// 分块渲染不受此项影响
+    #[serde(default)]
+    pub auto_rotate_landscape: bool,
 }
 
 impl Default for RenderOptions {
@@ -75,15 +189,83 @@ impl Default for RenderOptions {
             fit_to_width: false,
             fit_to_height: false,
             theme: None,
+            format: None,
+            max_pixels: None,
+            flags: RenderFlags::default(),
+            fallback_on_error: false,
+            auto_rotate_landscape: false,
         }
     }
 }
 
 impl RenderOptions {
+    /// 自适应渲染参数：目标像素宽度 = 视口宽度（CSS 像素）× 设备像素比，高度按页面比例推算，
+    /// 不超过页面原始宽度的 MAX_ADAPTIVE_SCALE 倍；缓存键随实际目标宽高变化
+    pub fn adaptive(page_width: f32, device_dpi: f32, viewport_width: u32) -> Self {
+        let device_pixel_ratio = if device_dpi.is_finite() && device_dpi > 0.0 {
+            device_dpi / CSS_REFERENCE_DPI
+        } else {
+            1.0
+        };
+        let target = (viewport_width.max(1) as f32 * device_pixel_ratio).ceil();
+        let limit = (page_width.max(1.0) * MAX_ADAPTIVE_SCALE).ceil();
+        Self {
+            quality: RenderQuality::Adaptive,
+            width: Some(target.min(limit).max(1.0) as u32),
+            fit_to_width: true,
+            ..Self::default()
+        }
+    }
+
     pub fn background_rgba(&self) -> Rgba<u8> {
         let color = self.background_color.unwrap_or([255, 255, 255, 255]);
         Rgba(color)
     }
+
+    /// 实际使用的输出格式
+    pub fn output_format(&self) -> ImageFormat {
+        match (&self.format, &self.quality) {
+            (Some(format), quality) if *quality != RenderQuality::Thumbnail => format.clone(),
+            (_, quality) => quality.default_format(),
+        }
+    }
+
+    /// 按页面尺寸（点）决定实际的顺时针旋转角度：开启横页自动旋转且页面宽大于高时为 90，否则为 0
+    pub fn rotation_for(&self, page_width: f32, page_height: f32) -> u32 {
+        if self.auto_rotate_landscape && page_width > page_height {
+            90
+        } else {
+            0
+        }
+    }
+
+    /// 带旋转角度的缓存变体，旋转后的页面追加 `_rot{角度}` 后缀，与未旋转的结果分开缓存
+    pub fn cache_variant_for_rotation(&self, rotation: u32) -> String {
+        let variant = self.cache_variant();
+        if rotation == 0 {
+            variant
+        } else {
+            format!("{}_rot{}", variant, rotation)
+        }
+    }
+
+    /// 缓存键中的变体部分：主题，显式指定了非默认格式时追加格式后缀，非默认渲染标志时追加标志后缀，
+    /// 背景色不是白色时追加背景色后缀
+    pub fn cache_variant(&self) -> String {
+        let mut variant = self.theme.clone().unwrap_or_else(|| "light".to_string());
+        let format = self.output_format();
+        if format != self.quality.default_format() {
+            variant = format!("{}_{}", variant, format.extension());
+        }
+        let [r, g, b, a] = self.background_rgba().0;
+        if [r, g, b, a] != [255, 255, 255, 255] {
+            variant = format!("{}_bg{:02x}{:02x}{:02x}{:02x}", variant, r, g, b, a);
+        }
+        if let Some(suffix) = self.flags.cache_suffix() {
+            variant = format!("{}_{}", variant, suffix);
+        }
+        variant
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -94,6 +276,7 @@ pub struct RenderRegion {
     pub height: f32,
 }
 
+/// 分块缓存键：分块在缩放后整页位图中的像素区域，加上整页像素尺寸（由缩放比例决定）和渲染变体
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TileKey {
     pub page_number: u32,
@@ -102,11 +285,50 @@ pub struct TileKey {
     pub tile_y: u32,
     pub tile_width: u32,
     pub tile_height: u32,
+    #[serde(default)]
+    pub page_width: u32,
+    #[serde(default)]
+    pub page_height: u32,
+    #[serde(default)]
+    pub variant: String,
 }
 
 impl TileKey {
     pub fn new(page_number: u32, quality: RenderQuality, tile_x: u32, tile_y: u32, tile_width: u32, tile_height: u32) -> Self {
-        Self { page_number, quality, tile_x, tile_y, tile_width, tile_height }
+        Self {
+            page_number,
+            quality,
+            tile_x,
+            tile_y,
+            tile_width,
+            tile_height,
+            page_width: 0,
+            page_height: 0,
+            variant: String::new(),
+        }
+    }
+
+    /// 设置整页像素尺寸与渲染变体（主题/格式）
+    pub fn for_page(mut self, page_width: u32, page_height: u32, variant: String) -> Self {
+        self.page_width = page_width;
+        self.page_height = page_height;
+        self.variant = variant;
+        self
+    }
+
+    /// 转换为页面缓存键，与整页渲染结果共用缓存容量，并随页面缓存一起清理
+    pub fn cache_key(&self, file_path: &str) -> CacheKey {
+        CacheKey::new(
+            file_path.to_string(),
+            self.page_number,
+            self.quality.clone(),
+            self.page_width,
+            self.page_height,
+            format!(
+                "{}#tile:{},{},{}x{}",
+                self.variant, self.tile_x, self.tile_y, self.tile_width, self.tile_height
+            ),
+        )
     }
 }
 
@@ -141,12 +363,19 @@ pub struct SearchResult {
     pub context: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Bookmark {
     pub title: String,
+    /// 自身目标页码（从 1 开始），无法定位时为 0
     pub page_number: u32,
     pub level: u32,
     pub children: Vec<Bookmark>,
+    /// 自身目标是否解析成功，不考虑子节点
+    #[serde(default)]
+    pub resolved: bool,
+    /// 自身无法定位时第一个可定位子孙节点的页码，供分组标题跳转使用
+    #[serde(default)]
+    pub fallback_page: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +383,127 @@ pub struct PdfOutline {
     pub bookmarks: Vec<Bookmark>,
 }
 
+/// 当前页所属的目录章节
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfChapterProgress {
+    pub title: String,
+    pub level: u32,
+    pub start_page: u32,
+    /// 章节结束页（含），为下一章起始页的前一页
+    pub end_page: u32,
+    /// 章内进度百分比（0-100），读到当前页末尾计
+    pub progress: f32,
+}
+
+/// 表单字段/注释矩形，单位为 PDF 点，原点在页面左上角（与渲染图方向一致，按渲染缩放比例换算即可叠加）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormFieldRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// 表单字段（只读）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfFormField {
+    pub name: String,
+    /// text / checkbox / radio / combo_box / list_box / push_button / signature / unknown
+    pub field_type: String,
+    pub value: Option<String>,
+    pub rect: FormFieldRect,
+    pub read_only: bool,
+}
+
+/// 单页表单字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageFormFields {
+    pub page_number: u32,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub fields: Vec<PdfFormField>,
+}
+
+/// 页面注释（只读）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfAnnotation {
+    /// 注释在页面注释列表中的序号
+    pub index: u32,
+    /// highlight / underline / squiggly / strikeout / note / free_text / square / circle / line / polygon / polyline / ink / stamp / caret / file_attachment
+    pub annotation_type: String,
+    /// `#RRGGBB`
+    pub color: Option<String>,
+    pub rect: FormFieldRect,
+    /// 高亮、下划线等文本标注的逐行区域，其他类型为空
+    pub quads: Vec<FormFieldRect>,
+    pub contents: Option<String>,
+    pub author: Option<String>,
+    /// PDF 日期字符串，如 `D:20240101120000+08'00'`
+    pub modified: Option<String>,
+}
+
+/// 单页注释
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageAnnotations {
+    pub page_number: u32,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub annotations: Vec<PdfAnnotation>,
+}
+
+/// 页面上的单个字符及其矩形（用于划词选择）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfCharBox {
+    #[serde(rename = "char")]
+    pub ch: String,
+    /// 字符在页面文本中的序号，与 pdfium 文本索引一致
+    pub index: u32,
+    pub rect: FormFieldRect,
+}
+
+/// 单页字符坐标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageCharBoxes {
+    pub page_number: u32,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub chars: Vec<PdfCharBox>,
+}
+
+/// OCR 识别出的单词
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfOcrWord {
+    pub text: String,
+    /// 识别置信度（0-100）
+    pub confidence: f32,
+    /// 所在行序号（从 0 开始）
+    pub line: u32,
+    pub rect: FormFieldRect,
+}
+
+/// 单页 OCR 结果，坐标与字符坐标一致：以 CropBox 左上角为原点、单位为点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageOcrResult {
+    pub page_number: u32,
+    /// 识别使用的语言包，如 `chi_sim+eng`
+    pub lang: String,
+    pub page_width: f32,
+    pub page_height: f32,
+    /// 按行拼接的识别文本
+    pub text: String,
+    pub words: Vec<PdfOcrWord>,
+}
+
+/// 文档嵌入文件（附件）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfAttachmentInfo {
+    /// 附件在文档附件列表中的序号
+    pub index: u32,
+    pub name: String,
+    /// 附件大小（字节）
+    pub size: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CacheKey {
     pub file_path: String,
@@ -175,6 +525,15 @@ impl CacheKey {
             theme,
         }
     }
+
+    /// 渲染结果的 ETag：对缓存键全部字段（页码、质量、尺寸、主题等变体）与文档版本取哈希，
+    /// 任一项变化都会得到不同的值，前端可直接作为图片缓存键
+    pub fn etag(&self, doc_version: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.hash(&mut hasher);
+        doc_version.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -183,13 +542,87 @@ pub struct RenderResult {
     pub width: u32,
     pub height: u32,
     pub format: ImageFormat,
+    /// 为占位图时记录真实的渲染错误
+    #[serde(default)]
+    pub error: Option<String>,
+    /// 实际的顺时针旋转角度（0 或 90），前端据此把触摸坐标换算回页面坐标
+    #[serde(default)]
+    pub rotation: u32,
+    /// 内容版本标识，由缓存键与文档版本生成（见 `CacheKey::etag`），主题、尺寸或文档变化时随之改变
+    #[serde(default)]
+    pub etag: String,
+}
+
+/// 分块渲染中的单个分块，`x`/`y` 为分块在缩放后整页位图中的像素偏移
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageTile {
+    pub row: u32,
+    pub column: u32,
+    pub x: u32,
+    pub y: u32,
+    pub result: RenderResult,
+}
+
+/// 批量缩略图中的单页结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageThumbnail {
+    pub page: u32,
+    pub result: RenderResult,
+}
+
+/// 联系表中单页缩略图所在的像素区域
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContactSheetCell {
+    pub page: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
 }
 
+/// 多页缩略图拼成的联系表，`cells` 与请求的页码顺序一致
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PdfContactSheet {
+    pub result: RenderResult,
+    pub cols: u32,
+    pub rows: u32,
+    pub cells: Vec<ContactSheetCell>,
+}
+
+/// 清理临时渲染文件的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TempCleanupStats {
+    pub removed_files: usize,
+    pub freed_bytes: u64,
+}
+
+/// 预渲染窗口中单页的缓存状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageCacheStatus {
+    /// 请求前已在缓存中
+    Cached,
+    /// 本次渲染后写入缓存
+    Rendered,
+    /// 窗口已移走，渲染开始前被取消
+    Cancelled,
+    Failed,
+}
+
+/// 预渲染窗口中的单页结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageCacheState {
+    pub page: u32,
+    pub status: PageCacheStatus,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ImageFormat {
     Png,
     Jpeg,
     WebP,
+    Avif,
 }
 
 impl ImageFormat {
@@ -198,6 +631,7 @@ impl ImageFormat {
             ImageFormat::Png => "image/png",
             ImageFormat::Jpeg => "image/jpeg",
             ImageFormat::WebP => "image/webp",
+            ImageFormat::Avif => "image/avif",
         }
     }
 
@@ -206,6 +640,18 @@ impl ImageFormat {
             ImageFormat::Png => "png",
             ImageFormat::Jpeg => "jpg",
             ImageFormat::WebP => "webp",
+            ImageFormat::Avif => "avif",
+        }
+    }
+
+    /// 按名称解析（不区分大小写），未知名称返回 None
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::WebP),
+            "avif" => Some(ImageFormat::Avif),
+            _ => None,
         }
     }
 }
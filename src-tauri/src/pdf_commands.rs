@@ -1,9 +1,20 @@
-use tauri::State;
-use std::sync::Arc;
+use tauri::{AppHandle, Emitter, Manager, State};
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
 use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
 
-use crate::pdf::PdfEngineManager;
+use crate::pdf::preload_predictor::PredictorStatistics;
+use crate::commands::book::DbState;
+use crate::commands::settings;
+use crate::pdf::{
+    cleanup_render_temp_files, cleanup_stale_render_files, remote, temp_render_usage, PdfEngineManager,
+    PdfRuntimeConfig, TileGrid, DEFAULT_TEMP_RENDER_MAX_AGE_DAYS,
+};
 use crate::pdf::types::*;
 use crate::formats::BookRenderCache;
 
@@ -23,6 +34,15 @@ pub struct RenderPageResponse {
     pub image_data: Option<Vec<u8>>,
     pub width: Option<u32>,
     pub height: Option<u32>,
+    /// 图像 MIME 类型，随输出格式变化
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    /// 实际的顺时针旋转角度，横页自动旋转时为 90，前端据此换算触摸坐标
+    #[serde(default)]
+    pub rotation: Option<u32>,
+    /// 渲染结果的内容版本标识，前端用作图片缓存键，见 `RenderResult.etag`
+    #[serde(default)]
+    pub etag: Option<String>,
     pub error: Option<String>,
 }
 
@@ -50,7 +70,7 @@ pub async fn pdf_load_document(
     match manager.get_or_create_engine(&file_path).await {
         Ok(engine) => {
             let engine = engine.read().await;
-            let info = engine.get_document_info().cloned();
+            let info = engine.get_document_info();
             
             Ok(LoadPdfResponse {
                 success: true,
@@ -66,7 +86,82 @@ pub async fn pdf_load_document(
     }
 }
 
+/// 远程 PDF 下载进度事件
+const REMOTE_PDF_PROGRESS_EVENT: &str = "goread:remote-pdf:progress";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RemotePdfProgressEvent {
+    pub url: String,
+    pub downloaded: u64,
+    /// 服务器未返回长度时为 None
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoadRemotePdfResponse {
+    pub success: bool,
+    /// 下载到缓存目录后的本地路径，后续渲染命令使用该路径
+    pub file_path: Option<String>,
+    pub info: Option<PdfDocumentInfo>,
+    pub error: Option<String>,
+}
+
+/// 打开网络 PDF：下载到应用缓存目录（进度通过 `goread:remote-pdf:progress` 事件推送）后按本地文件加载
+/// 同一 URL 只下载一次，中断后再次调用会尝试断点续传
+#[tauri::command]
+pub async fn pdf_load_remote(
+    app_handle: AppHandle,
+    url: String,
+    manager: State<'_, PdfManagerState>,
+) -> Result<LoadRemotePdfResponse, String> {
+    let cache_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("获取缓存目录失败: {}", e))?
+        .join("remote_pdf");
+
+    // 已打开的远程文档正在使用，缓存淘汰时跳过
+    let open_paths: HashSet<PathBuf> = {
+        let manager = manager.lock().await;
+        manager.open_file_paths().await.into_iter().map(PathBuf::from).collect()
+    };
+    let event_url = url.clone();
+    let downloaded = remote::download_pdf(&url, &cache_dir, open_paths, |downloaded, total| {
+        let payload = RemotePdfProgressEvent {
+            url: event_url.clone(),
+            downloaded,
+            total,
+        };
+        let _ = app_handle.emit(REMOTE_PDF_PROGRESS_EVENT, payload);
+    })
+    .await;
+    let local_path = match downloaded {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(e) => {
+            eprintln!("[PDF] 远程文件下载失败: url={}, {}", url, e);
+            return Ok(LoadRemotePdfResponse {
+                success: false,
+                file_path: None,
+                info: None,
+                error: Some(e),
+            });
+        }
+    };
+
+    let loaded = pdf_load_document(local_path.clone(), manager).await?;
+    Ok(LoadRemotePdfResponse {
+        success: loaded.success,
+        file_path: Some(local_path),
+        info: loaded.info,
+        error: loaded.error,
+    })
+}
+
+/// 渲染单页；`fallback_on_error` 为 true 时渲染失败返回占位图，`success` 仍为 true，真实错误放在 `error`
+/// `background_color` 为透明区域合成的背景色（RGBA），不传时为白色
+/// `auto_rotate_landscape` 为 true 时横向页面旋转 90 度渲染，实际角度见返回的 `rotation`
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pdf_render_page(
     file_path: String,
     page_number: u32,
@@ -74,18 +169,24 @@ pub async fn pdf_render_page(
     width: Option<u32>,
     height: Option<u32>,
     theme: Option<String>,
+    fallback_on_error: Option<bool>,
+    background_color: Option<[u8; 4]>,
+    auto_rotate_landscape: Option<bool>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<RenderPageResponse, String> {
-    let engine_arc = {
+    let (engine_arc, output_format, render_flags) = {
         let manager = manager.lock().await;
         match manager.get_or_create_engine(&file_path).await {
-            Ok(engine) => engine,
+            Ok(engine) => (engine, manager.output_format(), manager.render_flags()),
             Err(e) => {
                 return Ok(RenderPageResponse {
                     success: false,
                     image_data: None,
                     width: None,
                     height: None,
+                    mime_type: None,
+                    rotation: None,
+                    etag: None,
                     error: Some(e.to_string()),
                 });
             }
@@ -106,18 +207,114 @@ pub async fn pdf_render_page(
         quality: render_quality,
         width,
         height,
-        background_color: Some([255, 255, 255, 255]),
+        background_color: background_color.or(Some([255, 255, 255, 255])),
         fit_to_width: width.is_some(),
         fit_to_height: height.is_some(),
         theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: fallback_on_error.unwrap_or(false),
+        auto_rotate_landscape: auto_rotate_landscape.unwrap_or(false),
     };
     
-    match engine.render_page(page_number, options).await {
+    match engine.render_page(page_number, options.clone()).await {
+        Ok(result) => {
+            // 当前页渲染完成后，按翻页预测在后台预加载可能访问的页面
+            // 渲染任务不持有引擎读锁，避免阻塞随后的关闭/失效等写操作
+            if let Some(task) = engine.spawn_predicted_preload(page_number, options) {
+                tauri::async_runtime::spawn(async move {
+                    match task.await {
+                        Ok(Err(e)) => eprintln!("[PDF] 预测预加载失败: {}", e),
+                        Err(e) => eprintln!("[PDF] 预测预加载任务失败: {}", e),
+                        Ok(Ok(_)) => {}
+                    }
+                });
+            }
+            Ok(RenderPageResponse {
+                success: true,
+                image_data: Some(result.image_data),
+                width: Some(result.width),
+                height: Some(result.height),
+                mime_type: Some(result.format.mime_type().to_string()),
+                rotation: Some(result.rotation),
+                etag: Some(result.etag),
+                error: result.error,
+            })
+        }
+        Err(e) => Ok(RenderPageResponse {
+            success: false,
+            image_data: None,
+            width: None,
+            height: None,
+            mime_type: None,
+            rotation: None,
+            etag: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// 重试渲染失败的页面：清除该页所有尺寸的渲染缓存后重新渲染，仍失败时返回占位图与真实错误
+#[tauri::command]
+pub async fn pdf_retry_page(
+    file_path: String,
+    page: u32,
+    quality: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<RenderPageResponse, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager.get_or_create_engine(&file_path).await.map_err(|e| e.to_string())?
+    };
+    engine_arc.read().await.clear_page_cache(page).await;
+    println!("[PDF] 重试渲染页面: file={}, page={}", file_path, page);
+
+    let quality = quality.unwrap_or_else(|| "standard".to_string());
+    pdf_render_page(file_path, page, quality, width, height, theme, Some(true), None, None, manager).await
+}
+
+/// 自适应质量渲染：按设备 DPI 和视口宽度（CSS 像素）算出刚好铺满视口的目标像素宽度再渲染，
+/// 高分屏不糊、低端机不过度渲染；缓存按实际目标宽高区分
+#[tauri::command]
+pub async fn pdf_render_page_adaptive(
+    file_path: String,
+    page: u32,
+    device_dpi: f32,
+    viewport_width: u32,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<RenderPageResponse, String> {
+    let (engine_arc, output_format, render_flags) = {
+        let manager = manager.lock().await;
+        let engine = manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?;
+        (engine, manager.output_format(), manager.render_flags())
+    };
+    let engine = engine_arc.read().await;
+
+    let page_info = engine.get_page_info(page).await.map_err(|e| e.to_string())?;
+    let options = RenderOptions {
+        theme,
+        format: output_format,
+        flags: render_flags,
+        ..RenderOptions::adaptive(page_info.width, device_dpi, viewport_width)
+    };
+
+    match engine.render_page(page, options).await {
         Ok(result) => Ok(RenderPageResponse {
             success: true,
             image_data: Some(result.image_data),
             width: Some(result.width),
             height: Some(result.height),
+            mime_type: Some(result.format.mime_type().to_string()),
+            rotation: Some(result.rotation),
+            etag: Some(result.etag),
             error: None,
         }),
         Err(e) => Ok(RenderPageResponse {
@@ -125,12 +322,147 @@ pub async fn pdf_render_page(
             image_data: None,
             width: None,
             height: None,
+            mime_type: None,
+            rotation: None,
+            etag: None,
             error: Some(e.to_string()),
         }),
     }
 }
 
+/// 渐进式渲染推送的事件名
+const PROGRESSIVE_RENDER_EVENT: &str = "goread:render:progressive";
+
+/// 渐进式渲染请求序号生成器
+static PROGRESSIVE_RENDER_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 进行中的渐进式渲染请求：请求序号 -> (文件, 页码)
+/// 请求被取消、被同一页的新请求取代或文档关闭时从表中移除，对应请求发现后停止后续阶段
+static PROGRESSIVE_RENDER_ACTIVE: Lazy<StdMutex<HashMap<u64, (String, u32)>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+fn is_active_progressive_request(request_id: u64) -> bool {
+    PROGRESSIVE_RENDER_ACTIVE
+        .lock()
+        .map(|active| active.contains_key(&request_id))
+        .unwrap_or(true)
+}
+
+/// 文档关闭或失效时取消该文件所有进行中的渐进式渲染
+fn cancel_progressive_renders(file_path: &str) {
+    if let Ok(mut active) = PROGRESSIVE_RENDER_ACTIVE.lock() {
+        active.retain(|_, (path, _)| path != file_path);
+    }
+}
+
+/// 渐进式渲染的单个阶段
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressiveRenderEvent {
+    pub request_id: u64,
+    pub file_path: String,
+    pub page: u32,
+    /// thumbnail / standard / high / best
+    pub quality: String,
+    /// data URL
+    pub image: String,
+    pub width: u32,
+    pub height: u32,
+    /// 是否为最后一个阶段
+    pub is_final: bool,
+}
+
+/// 渐进式渲染页面（先糊后清）：按缩略图、标准、目标质量的顺序通过 `goread:render:progressive` 事件推送，
+/// 立即返回请求序号供前端匹配事件；同一页发起新请求后旧请求尚未开始的阶段会被取消，
+/// 其他页的请求互不影响，可通过 `pdf_cancel_progressive_render` 按序号取消
+/// `options` 为空时目标质量为 high
+#[tauri::command]
+pub async fn pdf_render_page_progressive(
+    app_handle: AppHandle,
+    file_path: String,
+    page: u32,
+    options: Option<RenderOptions>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<u64, String> {
+    let (engine_arc, output_format, render_flags) = {
+        let manager = manager.lock().await;
+        let engine = manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?;
+        (engine, manager.output_format(), manager.render_flags())
+    };
+
+    let mut options = options.unwrap_or(RenderOptions {
+        quality: RenderQuality::High,
+        flags: render_flags,
+        ..Default::default()
+    });
+    if options.format.is_none() {
+        options.format = output_format;
+    }
+    let final_quality = options.quality.clone();
+
+    let request_id = PROGRESSIVE_RENDER_SEQ.fetch_add(1, Ordering::Relaxed) + 1;
+    if let Ok(mut active) = PROGRESSIVE_RENDER_ACTIVE.lock() {
+        active.retain(|_, (path, active_page)| !(*path == file_path && *active_page == page));
+        active.insert(request_id, (file_path.clone(), page));
+    }
+
+    tauri::async_runtime::spawn(async move {
+        let engine = engine_arc.read().await;
+        if !is_active_progressive_request(request_id) {
+            return;
+        }
+
+        let event_path = file_path.clone();
+        let result = engine
+            .render_page_progressive(page, options, move |quality, result| {
+                // 每个阶段渲染完成后再确认一次，被取消或取代的请求不再推送
+                if !is_active_progressive_request(request_id) {
+                    return false;
+                }
+                let mime = result.format.mime_type().to_string();
+                let base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &result.image_data);
+                let payload = ProgressiveRenderEvent {
+                    request_id,
+                    file_path: event_path.clone(),
+                    page,
+                    quality: format!("{:?}", quality).to_lowercase(),
+                    image: format!("data:{};base64,{}", mime, base64),
+                    width: result.width,
+                    height: result.height,
+                    is_final: quality == final_quality,
+                };
+                let _ = app_handle.emit(PROGRESSIVE_RENDER_EVENT, payload);
+                quality != final_quality
+            })
+            .await;
+
+        if let Err(e) = result {
+            eprintln!("[PDF] 渐进式渲染失败: page={}, {}", page, e);
+        }
+        if let Ok(mut active) = PROGRESSIVE_RENDER_ACTIVE.lock() {
+            active.remove(&request_id);
+        }
+    });
+
+    Ok(request_id)
+}
+
+/// 取消指定序号的渐进式渲染，尚未推送的阶段不再推送；返回该请求是否仍在进行
 #[tauri::command]
+pub async fn pdf_cancel_progressive_render(request_id: u64) -> Result<bool, String> {
+    let mut active = PROGRESSIVE_RENDER_ACTIVE.lock().map_err(|e| e.to_string())?;
+    Ok(active.remove(&request_id).is_some())
+}
+
+/// 渲染页面并写入磁盘缓存，返回图片文件路径
+/// 适合大图或需要反复显示的页面：IPC 只传路径、前端按文件加载，内存占用低，但会占用临时目录空间，
+/// 超过 7 天未再读取的文件在启动和关闭文档时自动清理，也可调用 `clear_temp_renders` 手动清理；
+/// 移动端临时空间有限时优先使用 `pdf_render_page_base64`
+/// `background_color` 为透明区域合成的背景色（RGBA），不传时为白色
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn pdf_render_page_to_file(
     file_path: String,
     page_number: u32,
@@ -138,12 +470,14 @@ pub async fn pdf_render_page_to_file(
     width: Option<u32>,
     height: Option<u32>,
      theme: Option<String>,
+    background_color: Option<[u8; 4]>,
+    auto_rotate_landscape: Option<bool>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<String, String> {
-    let engine_arc = {
+    let (engine_arc, output_format, render_flags) = {
         let manager = manager.lock().await;
         match manager.get_or_create_engine(&file_path).await {
-            Ok(engine) => engine,
+            Ok(engine) => (engine, manager.output_format(), manager.render_flags()),
             Err(e) => {
                 return Err(e.to_string());
             }
@@ -164,10 +498,15 @@ pub async fn pdf_render_page_to_file(
         quality: render_quality,
         width,
         height,
-        background_color: Some([255, 255, 255, 255]),
+        background_color: background_color.or(Some([255, 255, 255, 255])),
         fit_to_width: width.is_some(),
         fit_to_height: height.is_some(),
         theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: auto_rotate_landscape.unwrap_or(false),
     };
 
     engine
@@ -176,6 +515,8 @@ pub async fn pdf_render_page_to_file(
         .map_err(|e| e.to_string())
 }
 
+/// 渲染页面并以 data URL 返回，全程只在内存中处理（内存缓存 + base64 编码），不写任何临时文件
+/// 代价是 base64 使 IPC 传输体积增加约三分之一，且前端需持有整张图片的字符串；适合移动端或临时空间受限的场景
 #[tauri::command]
 pub async fn pdf_render_page_base64(
     file_path: String,
@@ -186,11 +527,11 @@ pub async fn pdf_render_page_base64(
     theme: Option<String>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<String, String> {
-    let response = pdf_render_page(file_path, page_number, quality.clone(), width, height, theme, manager).await?;
+    let response = pdf_render_page(file_path, page_number, quality, width, height, theme, None, None, None, manager).await?;
     
     if response.success {
         if let Some(image_data) = response.image_data {
-            let mime = match quality.as_str() { "thumbnail" => "image/png", "best" => "image/png", _ => "image/webp" };
+            let mime = response.mime_type.as_deref().unwrap_or("image/webp");
             let base64 = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &image_data);
             Ok(format!("data:{};base64,{}", mime, base64))
         } else {
@@ -201,6 +542,39 @@ pub async fn pdf_render_page_base64(
     }
 }
 
+/// 清理遗留的临时渲染文件（`pdf_render_page_to_file` 的页面图片和旧版本的 `goread_*` 图片）
+#[tauri::command]
+pub async fn pdf_cleanup_temp_files() -> Result<TempCleanupStats, String> {
+    tokio::task::spawn_blocking(cleanup_render_temp_files)
+        .await
+        .map_err(|e| format!("清理临时文件失败: {}", e))
+}
+
+fn days_to_duration(days: u32) -> std::time::Duration {
+    std::time::Duration::from_secs(days as u64 * 24 * 60 * 60)
+}
+
+/// 当前临时渲染文件（`pdf_render_page_to_file` 的页面图片和旧版本的 `goread_*` 图片）总字节数
+#[tauri::command]
+pub async fn get_temp_usage() -> Result<u64, String> {
+    tokio::task::spawn_blocking(temp_render_usage)
+        .await
+        .map_err(|e| format!("统计临时文件失败: {}", e))
+}
+
+/// 清理超过 `max_age_days` 天未被读取的临时渲染文件；传 0 时清理全部，但仍保留 1 分钟内刚写入、前端可能尚未加载的文件；
+/// 已打开文档的渲染文件不会被删除
+#[tauri::command]
+pub async fn clear_temp_renders(
+    max_age_days: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<TempCleanupStats, String> {
+    let open_hashes = manager.lock().await.open_file_hashes().await;
+    tokio::task::spawn_blocking(move || cleanup_stale_render_files(days_to_duration(max_age_days), &open_hashes))
+        .await
+        .map_err(|e| format!("清理临时文件失败: {}", e))
+}
+
 #[tauri::command]
 pub async fn pdf_get_page_text(
     file_path: String,
@@ -236,6 +610,66 @@ pub async fn pdf_get_page_text(
     }
 }
 
+/// 按阅读顺序提取页面文本（双栏论文等按栏重排，过滤页眉页脚）
+#[tauri::command]
+pub async fn pdf_extract_reflow_text(
+    file_path: String,
+    page_number: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<TextResponse, String> {
+    let manager = manager.lock().await;
+
+    let Some(engine_arc) = manager.get_engine(&file_path).await else {
+        return Ok(TextResponse {
+            success: false,
+            text: None,
+            error: Some("PDF文档未加载".to_string()),
+        });
+    };
+
+    let engine = engine_arc.read().await;
+
+    match engine.extract_reflow_text(page_number) {
+        Ok(reflowed) => {
+            println!(
+                "[PDF] 重排文本: page={}, columns={}, removed_lines={}",
+                page_number, reflowed.columns, reflowed.removed_lines
+            );
+            Ok(TextResponse {
+                success: true,
+                text: Some(reflowed.text),
+                error: None,
+            })
+        }
+        Err(e) => Ok(TextResponse {
+            success: false,
+            text: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// 导出 PDF 文本到文件，返回写入的文件路径
+#[tauri::command]
+pub async fn pdf_export_text(
+    file_path: String,
+    out_path: String,
+    page_range: Option<(u32, u32)>,
+    with_page_markers: bool,
+    manager: State<'_, PdfManagerState>,
+) -> Result<String, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager.get_or_create_engine(&file_path).await
+            .map_err(|e| e.to_string())?
+    };
+
+    let engine = engine_arc.read().await;
+    engine
+        .export_text(&out_path, page_range, with_page_markers)
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn pdf_search_text(
     file_path: String,
@@ -291,7 +725,7 @@ pub async fn pdf_get_document_info(
     };
     
     let engine = engine_arc.read().await;
-    let info = engine.get_document_info().cloned();
+    let info = engine.get_document_info();
     
     Ok(LoadPdfResponse {
         success: true,
@@ -300,6 +734,21 @@ pub async fn pdf_get_document_info(
     })
 }
 
+/// 查询单页尺寸与旋转角度，后台尚未读取到的页即时查询
+#[tauri::command]
+pub async fn pdf_get_page_info(
+    file_path: String,
+    page_number: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PdfPageInfo, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager.get_engine(&file_path).await.ok_or("PDF文档未加载".to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.get_page_info(page_number).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn pdf_clear_cache(
     file_path: Option<String>,
@@ -319,6 +768,20 @@ pub async fn pdf_clear_cache(
     Ok(true)
 }
 
+/// 清除单页的渲染缓存（内存中所有质量/尺寸/主题变体及磁盘缓存），返回清除的条目数
+/// 旋转、裁剪等单页操作后调用，无需清空整本缓存
+#[tauri::command]
+pub async fn pdf_clear_page_cache(
+    file_path: String,
+    page: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<usize, String> {
+    let manager = manager.lock().await;
+    let cleared = manager.clear_page_cache(&file_path, page).await;
+    println!("[PDF] 清除页面缓存: file={}, page={}, cleared={}", file_path, page, cleared);
+    Ok(cleared)
+}
+
 #[tauri::command]
 pub async fn pdf_close_document(
     file_path: String,
@@ -326,9 +789,29 @@ pub async fn pdf_close_document(
 ) -> Result<bool, String> {
     let manager = manager.lock().await;
     manager.remove_engine(&file_path).await;
+    forget_render_window(&file_path);
+    cancel_progressive_renders(&file_path);
+    // 后台清理已关闭文档中长期未读的渲染文件，仍打开的文档不受影响
+    let open_hashes = manager.open_file_hashes().await;
+    tokio::task::spawn_blocking(move || {
+        cleanup_stale_render_files(days_to_duration(DEFAULT_TEMP_RENDER_MAX_AGE_DAYS), &open_hashes)
+    });
     Ok(true)
 }
 
+/// 手动使文档失效：丢弃已加载的引擎并清理该文件的内存与磁盘缓存，下次访问时重新加载
+/// 返回该文档之前是否已加载
+#[tauri::command]
+pub async fn pdf_invalidate_document(
+    file_path: String,
+    manager: State<'_, PdfManagerState>,
+) -> Result<bool, String> {
+    let manager = manager.lock().await;
+    forget_render_window(&file_path);
+    cancel_progressive_renders(&file_path);
+    Ok(manager.invalidate_document(&file_path).await)
+}
+
 #[tauri::command]
 pub async fn pdf_get_cache_stats(
     manager: State<'_, PdfManagerState>,
@@ -342,6 +825,11 @@ pub async fn pdf_get_cache_stats(
         "max_size": stats.max_size,
         "max_items": stats.max_items,
         "hit_rate": stats.hit_rate,
+        "hits": stats.hits,
+        "misses": stats.misses,
+        "usage_ratio": if stats.max_size > 0 { stats.total_size as f64 / stats.max_size as f64 } else { 0.0 },
+        "by_quality": stats.by_quality,
+        "oldest_entry_age_secs": stats.oldest_entry_age_secs,
     }))
 }
 
@@ -401,6 +889,134 @@ pub async fn pdf_warmup_cache(
     Ok(true)
 }
 
+/// 上报一次翻页（`timestamp` 为前端毫秒时间戳），返回当前识别的阅读模式与速度
+/// 之后的 `pdf_render_page` 会按预测结果在后台预加载
+#[tauri::command]
+pub async fn pdf_record_navigation(
+    file_path: String,
+    from_page: u32,
+    to_page: u32,
+    timestamp: u64,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Option<PredictorStatistics>, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager.get_or_create_engine(&file_path).await
+            .map_err(|e| e.to_string())?
+    };
+
+    let engine = engine_arc.read().await;
+    Ok(engine.record_navigation(from_page, to_page, timestamp))
+}
+
+/// 预渲染窗口请求序号生成器
+static RENDER_WINDOW_SEQ: AtomicU64 = AtomicU64::new(0);
+
+/// 预渲染窗口：(请求序号, 起始页, 结束页)
+type RenderWindow = (u64, u32, u32);
+
+/// 每个文件最新的预渲染窗口
+static RENDER_WINDOW_LATEST: Lazy<StdMutex<HashMap<String, RenderWindow>>> =
+    Lazy::new(|| StdMutex::new(HashMap::new()));
+
+/// 文档关闭或失效时移除其预渲染窗口记录，进行中的窗口随之取消尚未开始的页面
+fn forget_render_window(file_path: &str) {
+    if let Ok(mut latest) = RENDER_WINDOW_LATEST.lock() {
+        latest.remove(file_path);
+    }
+}
+
+/// 预渲染窗口半径上限，避免一次请求占满渲染缓存
+const MAX_RENDER_WINDOW_RADIUS: u32 = 10;
+
+/// 窗口内页码按与中心页的距离排序（中心、下一页、上一页……），近的先渲染
+fn window_pages(center_page: u32, radius: u32, page_count: u32) -> Vec<u32> {
+    let mut pages = vec![center_page];
+    for offset in 1..=radius {
+        if center_page + offset <= page_count {
+            pages.push(center_page + offset);
+        }
+        if center_page > offset {
+            pages.push(center_page - offset);
+        }
+    }
+    pages
+}
+
+/// 连续滚动预渲染：确保 [center_page - radius, center_page + radius] 内的页面都已在缓存中，返回每页的缓存状态
+/// 同一文件发起新窗口后，旧窗口中尚未开始渲染且不在新窗口内的页面会被取消
+#[tauri::command]
+pub async fn pdf_ensure_window_rendered(
+    file_path: String,
+    center_page: u32,
+    radius: u32,
+    quality: String,
+    width: Option<u32>,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Vec<PageCacheState>, String> {
+    let (engine_arc, output_format, render_flags) = {
+        let manager = manager.lock().await;
+        let engine = manager.get_or_create_engine(&file_path).await
+            .map_err(|e| e.to_string())?;
+        (engine, manager.output_format(), manager.render_flags())
+    };
+    let engine = engine_arc.read().await;
+
+    let page_count = engine.get_page_count();
+    if center_page < 1 || center_page > page_count {
+        return Err(PdfError::PageNotFound { page: center_page, total_pages: page_count }.to_string());
+    }
+    let pages = window_pages(center_page, radius.min(MAX_RENDER_WINDOW_RADIUS), page_count);
+    let (start_page, end_page) = (*pages.iter().min().unwrap(), *pages.iter().max().unwrap());
+
+    let request_id = RENDER_WINDOW_SEQ.fetch_add(1, Ordering::SeqCst) + 1;
+    if let Ok(mut latest) = RENDER_WINDOW_LATEST.lock() {
+        latest.insert(file_path.clone(), (request_id, start_page, end_page));
+    }
+
+    let render_quality = match quality.as_str() {
+        "thumbnail" => RenderQuality::Thumbnail,
+        "high" => RenderQuality::High,
+        "best" => RenderQuality::Best,
+        _ => RenderQuality::Standard,
+    };
+    let options = RenderOptions {
+        quality: render_quality,
+        width,
+        height: None,
+        background_color: Some([255, 255, 255, 255]),
+        fit_to_width: width.is_some(),
+        fit_to_height: false,
+        theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
+    };
+
+    let window_file = file_path.clone();
+    let states = engine
+        .ensure_pages_cached(pages, options, move |page| {
+            RENDER_WINDOW_LATEST
+                .lock()
+                .map(|latest| match latest.get(&window_file) {
+                    Some(&(latest_id, start, end)) => latest_id == request_id || (start..=end).contains(&page),
+                    // 记录已被移除说明文档已关闭
+                    None => false,
+                })
+                .unwrap_or(true)
+        })
+        .await;
+
+    let cancelled = states.iter().filter(|s| s.status == PageCacheStatus::Cancelled).count();
+    if cancelled > 0 {
+        println!("[PDF] 预渲染窗口 {}-{} 已被新窗口取代，取消 {} 页", start_page, end_page, cancelled);
+    }
+    Ok(states)
+}
+
 /// 预加载页面范围
 #[tauri::command]
 pub async fn pdf_preload_pages(
@@ -416,8 +1032,6 @@ pub async fn pdf_preload_pages(
             .map_err(|e| e.to_string())?
     };
     
-    let engine = engine_arc.read().await;
-    
     let render_quality = match quality.as_deref() {
         Some("thumbnail") => RenderQuality::Thumbnail,
         Some("high") => RenderQuality::High,
@@ -425,7 +1039,13 @@ pub async fn pdf_preload_pages(
         _ => RenderQuality::Standard,
     };
     
-    engine.preload_pages(start_page, end_page, render_quality).await
+    // 仅在创建任务时持有读锁，渲染期间不阻塞引擎写操作
+    let task = {
+        let engine = engine_arc.read().await;
+        engine.spawn_preload_pages(start_page, end_page, render_quality)
+    };
+    task.await
+        .map_err(|e| format!("预加载任务失败: {}", e))?
         .map_err(|e| e.to_string())?;
     
     Ok(true)
@@ -437,16 +1057,11 @@ pub async fn pdf_get_performance_metrics(
     file_path: String,
     manager: State<'_, PdfManagerState>,
 ) -> Result<serde_json::Value, String> {
-    // use crate::pdf::PerformanceMetrics;
-    
     let manager = manager.lock().await;
-    let _engine_arc = manager.get_engine(&file_path).await
+    let engine_arc = manager.get_engine(&file_path).await
         .ok_or("PDF未加载")?;
-    
-    // let engine = engine_arc.read().await;
-    
-    // 这里需要从renderer获取性能监控器
-    // 由于架构限制，我们返回缓存统计作为性能指标的一部分
+
+    let render_metrics = engine_arc.read().await.performance_monitor().get_metrics().await;
     let cache_stats = BookRenderCache::cache_stats(manager.get_cache_manager()).await;
     
     Ok(serde_json::json!({
@@ -454,12 +1069,15 @@ pub async fn pdf_get_performance_metrics(
         "cache_item_count": cache_stats.item_count,
         "cache_total_size": cache_stats.total_size,
         "cache_max_size": cache_stats.max_size,
+        "render": render_metrics,
     }))
 }
 
 /// 获取性能报告
+/// 指定 `file_path` 时只返回该文档的渲染报告，否则返回所有已加载文档的报告
 #[tauri::command]
 pub async fn pdf_get_performance_report(
+    file_path: Option<String>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<serde_json::Value, String> {
     let manager = manager.lock().await;
@@ -478,6 +1096,21 @@ pub async fn pdf_get_performance_report(
     if recommendations.is_empty() {
         recommendations.push("性能表现良好");
     }
+
+    let files = match file_path {
+        Some(path) => vec![path],
+        None => manager.get_loaded_files().await,
+    };
+    let mut reports = Vec::new();
+    for path in files {
+        if let Some(engine_arc) = manager.get_engine(&path).await {
+            let report = engine_arc.read().await.performance_monitor().get_report().await;
+            reports.push(serde_json::json!({
+                "file_path": path,
+                "report": report,
+            }));
+        }
+    }
     
     Ok(serde_json::json!({
         "cache_stats": {
@@ -487,6 +1120,7 @@ pub async fn pdf_get_performance_report(
             "max_size": cache_stats.max_size,
         },
         "recommendations": recommendations,
+        "reports": reports,
         "timestamp": chrono::Utc::now().to_rfc3339(),
     }))
 }
@@ -502,10 +1136,10 @@ pub async fn pdf_render_pages_parallel(
     theme: Option<String>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<Vec<RenderPageResponse>, String> {
-    let engine_arc = {
+    let (engine_arc, output_format, render_flags) = {
         let manager = manager.lock().await;
         match manager.get_engine(&file_path).await {
-            Some(engine) => engine,
+            Some(engine) => (engine, manager.output_format(), manager.render_flags()),
             None => {
                 return Err("PDF文档未加载".to_string());
             }
@@ -530,6 +1164,11 @@ pub async fn pdf_render_pages_parallel(
         fit_to_width: width.is_some(),
         fit_to_height: height.is_some(),
         theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
     };
     
     // 调用并行渲染
@@ -544,6 +1183,9 @@ pub async fn pdf_render_pages_parallel(
                 image_data: Some(render_result.image_data),
                 width: Some(render_result.width),
                 height: Some(render_result.height),
+                mime_type: Some(render_result.format.mime_type().to_string()),
+                rotation: Some(render_result.rotation),
+                etag: Some(render_result.etag),
                 error: None,
             },
             Err(e) => RenderPageResponse {
@@ -551,6 +1193,9 @@ pub async fn pdf_render_pages_parallel(
                 image_data: None,
                 width: None,
                 height: None,
+                mime_type: None,
+                rotation: None,
+                etag: None,
                 error: Some(e.to_string()),
             },
         })
@@ -587,10 +1232,10 @@ pub async fn pdf_render_pages_with_threads(
     theme: Option<String>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<Vec<RenderPageResponse>, String> {
-    let engine_arc = {
+    let (engine_arc, output_format, render_flags) = {
         let manager = manager.lock().await;
         match manager.get_engine(&file_path).await {
-            Some(engine) => engine,
+            Some(engine) => (engine, manager.output_format(), manager.render_flags()),
             None => {
                 return Err("PDF文档未加载".to_string());
             }
@@ -615,6 +1260,11 @@ pub async fn pdf_render_pages_with_threads(
         fit_to_width: width.is_some(),
         fit_to_height: height.is_some(),
         theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
     };
     
     // 调用自定义线程池渲染
@@ -629,6 +1279,9 @@ pub async fn pdf_render_pages_with_threads(
                 image_data: Some(render_result.image_data),
                 width: Some(render_result.width),
                 height: Some(render_result.height),
+                mime_type: Some(render_result.format.mime_type().to_string()),
+                rotation: Some(render_result.rotation),
+                etag: Some(render_result.etag),
                 error: None,
             },
             Err(e) => RenderPageResponse {
@@ -636,6 +1289,9 @@ pub async fn pdf_render_pages_with_threads(
                 image_data: None,
                 width: None,
                 height: None,
+                mime_type: None,
+                rotation: None,
+                etag: None,
                 error: Some(e.to_string()),
             },
         })
@@ -644,14 +1300,166 @@ pub async fn pdf_render_pages_with_threads(
     Ok(responses)
 }
 
+/// 批量渲染一段页码的缩略图（单次页数有上限，超出需分批请求）；渲染失败的页面返回带 `error` 的占位图
+#[tauri::command]
+pub async fn pdf_render_thumbnails(
+    file_path: String,
+    start_page: u32,
+    end_page: u32,
+    width: Option<u32>,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Vec<PageThumbnail>, String> {
+    let (engine_arc, render_flags) = {
+        let manager = manager.lock().await;
+        let engine = manager.get_or_create_engine(&file_path).await
+            .map_err(|e| e.to_string())?;
+        (engine, manager.render_flags())
+    };
+
+    let engine = engine_arc.read().await;
+
+    let options = RenderOptions {
+        quality: RenderQuality::Thumbnail,
+        width,
+        height: None,
+        background_color: Some([255, 255, 255, 255]),
+        fit_to_width: width.is_some(),
+        fit_to_height: false,
+        theme,
+        format: None,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
+    };
+
+    engine.render_thumbnails(start_page, end_page, options).await
+        .map_err(|e| e.to_string())
+}
+
+/// 把多页缩略图拼成一张联系表（JPEG）返回，附带每页在图中的像素区域，用于多页网格预览
+/// `cols` 为列数，`width` 为单页缩略图宽度；拼图总尺寸超过上限时等比缩小
+#[tauri::command]
+pub async fn pdf_render_contact_sheet(
+    file_path: String,
+    pages: Vec<u32>,
+    cols: u32,
+    width: Option<u32>,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PdfContactSheet, String> {
+    let (engine_arc, render_flags) = {
+        let manager = manager.lock().await;
+        let engine = manager.get_or_create_engine(&file_path).await
+            .map_err(|e| e.to_string())?;
+        (engine, manager.render_flags())
+    };
+
+    let engine = engine_arc.read().await;
+
+    let options = RenderOptions {
+        quality: RenderQuality::Thumbnail,
+        width,
+        height: None,
+        background_color: Some([255, 255, 255, 255]),
+        fit_to_width: width.is_some(),
+        fit_to_height: false,
+        theme,
+        format: None,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
+    };
+
+    engine.render_contact_sheet(pages, cols, options).await
+        .map_err(|e| e.to_string())
+}
+
+/// PDF 运行时配置在设置表中的键
+const PDF_RUNTIME_SETTING_KEY: &str = "pdf_runtime";
+
+/// 读取持久化的 PDF 运行时配置，未保存过时使用默认值
+pub async fn load_pdf_runtime_config(pool: &SqlitePool) -> PdfRuntimeConfig {
+    settings::load_setting::<PdfRuntimeConfig>(pool, PDF_RUNTIME_SETTING_KEY)
+        .await
+        .unwrap_or_default()
+        .normalized()
+}
+
 // 初始化PDF管理器
-pub fn init_pdf_manager() -> PdfManagerState {
-    // 设置缓存限制：100MB，最多50个页面
-    let manager = PdfEngineManager::with_cache_limits(100 * 1024 * 1024, 50)
+pub async fn init_pdf_manager(config: PdfRuntimeConfig) -> PdfManagerState {
+    let config = config.normalized();
+    let manager = PdfEngineManager::with_cache_limits(config.max_cache_bytes, config.max_cache_items)
         .expect("Failed to initialize PDF manager");
+    manager.apply_runtime_config(&config).await;
+    // 启动时清理长期未读的临时渲染文件，此时还没有打开的文档
+    tokio::task::spawn_blocking(|| {
+        cleanup_stale_render_files(days_to_duration(DEFAULT_TEMP_RENDER_MAX_AGE_DAYS), &Default::default())
+    });
+    println!(
+        "[PDF] 运行时配置: cache={}MB, items={}, concurrency={}",
+        config.max_cache_bytes / 1024 / 1024,
+        config.max_cache_items,
+        config.max_render_concurrency
+    );
     Arc::new(Mutex::new(manager))
 }
 
+/// 运行时调整 PDF 渲染缓存上限（字节数、条目数）与并行渲染并发数，未传的项保持不变
+/// 超出范围的值会被限制到允许区间；调整后立即淘汰超额缓存，并保存到设置表供下次启动使用
+#[tauri::command]
+pub async fn pdf_configure(
+    max_cache_bytes: Option<u64>,
+    max_cache_items: Option<u32>,
+    max_render_concurrency: Option<u32>,
+    manager: State<'_, PdfManagerState>,
+    db: DbState<'_>,
+) -> Result<PdfRuntimeConfig, String> {
+    update_runtime_config(
+        &manager,
+        &db,
+        max_cache_bytes.map(|v| v as usize),
+        max_cache_items.map(|v| v as usize),
+        max_render_concurrency.map(|v| v as usize),
+    )
+    .await
+}
+
+/// 合并、规范化并应用运行时配置，再保存到设置表；所有修改运行时配置的命令都经过这里
+async fn update_runtime_config(
+    manager: &Mutex<PdfEngineManager>,
+    db: &Mutex<SqlitePool>,
+    max_cache_bytes: Option<usize>,
+    max_cache_items: Option<usize>,
+    max_render_concurrency: Option<usize>,
+) -> Result<PdfRuntimeConfig, String> {
+    let (config, evicted) = {
+        let manager = manager.lock().await;
+        let current = manager.runtime_config();
+        let config = PdfRuntimeConfig {
+            max_cache_bytes: max_cache_bytes.unwrap_or(current.max_cache_bytes),
+            max_cache_items: max_cache_items.unwrap_or(current.max_cache_items),
+            max_render_concurrency: max_render_concurrency.unwrap_or(current.max_render_concurrency),
+        }
+        .normalized();
+        let evicted = manager.apply_runtime_config(&config).await;
+        (config, evicted)
+    };
+
+    let pool = db.lock().await;
+    settings::save_setting(&pool, PDF_RUNTIME_SETTING_KEY, &config).await?;
+    println!(
+        "[PDF] 更新运行时配置: cache={}MB, items={}, concurrency={}, evicted={}",
+        config.max_cache_bytes / 1024 / 1024,
+        config.max_cache_items,
+        config.max_render_concurrency,
+        evicted
+    );
+    Ok(config)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenderTileRequestRegion {
     pub x: f32,
@@ -671,16 +1479,19 @@ pub async fn pdf_render_page_tile(
     theme: Option<String>,
     manager: State<'_, PdfManagerState>,
 ) -> Result<RenderPageResponse, String> {
-    let engine_arc = {
+    let (engine_arc, output_format, render_flags) = {
         let manager = manager.lock().await;
         match manager.get_engine(&file_path).await {
-            Some(engine) => engine,
+            Some(engine) => (engine, manager.output_format(), manager.render_flags()),
             None => {
                 return Ok(RenderPageResponse {
                     success: false,
                     image_data: None,
                     width: None,
                     height: None,
+                    mime_type: None,
+                    rotation: None,
+                    etag: None,
                     error: Some("PDF文档未加载".to_string()),
                 });
             }
@@ -705,6 +1516,11 @@ pub async fn pdf_render_page_tile(
         fit_to_width: width.is_some(),
         fit_to_height: height.is_some(),
         theme,
+        format: output_format,
+        max_pixels: None,
+        flags: render_flags,
+        fallback_on_error: false,
+        auto_rotate_landscape: false,
     };
 
     let rr = RenderRegion { x: region.x, y: region.y, width: region.width, height: region.height };
@@ -715,6 +1531,9 @@ pub async fn pdf_render_page_tile(
             image_data: Some(result.image_data),
             width: Some(result.width),
             height: Some(result.height),
+            mime_type: Some(result.format.mime_type().to_string()),
+            rotation: Some(result.rotation),
+            etag: Some(result.etag),
             error: None,
         }),
         Err(e) => Ok(RenderPageResponse {
@@ -722,11 +1541,111 @@ pub async fn pdf_render_page_tile(
             image_data: None,
             width: None,
             height: None,
+            mime_type: None,
+            rotation: None,
+            etag: None,
             error: Some(e.to_string()),
         }),
     }
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TileData {
+    pub row: u32,
+    pub column: u32,
+    /// 分块在缩放后整页位图中的像素偏移
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub image_data: Vec<u8>,
+    pub mime_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RenderTilesResponse {
+    pub success: bool,
+    /// 实际使用的缩放比例与分块边长（超出范围的参数会被截断）
+    pub zoom: Option<f32>,
+    pub tile_size: Option<u32>,
+    pub tiles: Vec<TileData>,
+    pub error: Option<String>,
+}
+
+/// 一次渲染覆盖视口的分块，单次最多 64 块（按行优先），视口过大时需拆分后分批请求
+/// `zoom` 为每个 PDF 点对应的像素数，`viewport_rect` 单位为 PDF 点（左上角为原点），`tile_size` 默认 512 像素
+#[tauri::command]
+pub async fn pdf_render_tiles(
+    file_path: String,
+    page_number: u32,
+    zoom: f32,
+    viewport_rect: RenderTileRequestRegion,
+    tile_size: Option<u32>,
+    theme: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<RenderTilesResponse, String> {
+    let failed = |error: String| RenderTilesResponse {
+        success: false,
+        zoom: None,
+        tile_size: None,
+        tiles: Vec::new(),
+        error: Some(error),
+    };
+
+    let (engine_arc, output_format, render_flags) = {
+        let manager = manager.lock().await;
+        match manager.get_or_create_engine(&file_path).await {
+            Ok(engine) => (engine, manager.output_format(), manager.render_flags()),
+            Err(e) => return Ok(failed(e.to_string())),
+        }
+    };
+
+    let engine = engine_arc.read().await;
+
+    let grid = TileGrid::new(
+        zoom,
+        RenderRegion {
+            x: viewport_rect.x,
+            y: viewport_rect.y,
+            width: viewport_rect.width,
+            height: viewport_rect.height,
+        },
+        tile_size,
+    );
+    let (zoom, tile_size) = (grid.zoom, grid.tile_size);
+
+    let options = RenderOptions {
+        quality: RenderQuality::High,
+        theme,
+        format: output_format,
+        flags: render_flags,
+        ..RenderOptions::default()
+    };
+
+    match engine.render_page_tiles(page_number, grid, options).await {
+        Ok(tiles) => Ok(RenderTilesResponse {
+            success: true,
+            zoom: Some(zoom),
+            tile_size: Some(tile_size),
+            tiles: tiles
+                .into_iter()
+                .map(|tile| TileData {
+                    row: tile.row,
+                    column: tile.column,
+                    x: tile.x,
+                    y: tile.y,
+                    width: tile.result.width,
+                    height: tile.result.height,
+                    mime_type: tile.result.format.mime_type().to_string(),
+                    image_data: tile.result.image_data,
+                })
+                .collect(),
+            error: None,
+        }),
+        Err(e) => Ok(failed(e.to_string())),
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OutlineResponse {
     pub success: bool,
@@ -753,14 +1672,182 @@ pub async fn pdf_get_outline(
     }
 }
 
-/// 动态设置 PDF 内存缓存上限（MB），由前端统一下发
+/// 当前页所属章节及章内进度（按 outline 中起始页不大于当前页的最后一个条目），没有目录时返回 None
+#[tauri::command]
+pub async fn pdf_get_current_chapter(
+    file_path: String,
+    page: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Option<PdfChapterProgress>, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager.get_or_create_engine(&file_path).await.map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.current_chapter(page).map_err(|e| e.to_string())
+}
+
+/// 读取页面表单字段（文本框、复选框、下拉框等）及当前值，没有表单的文档返回空列表
+#[tauri::command]
+pub async fn pdf_get_form_fields(
+    file_path: String,
+    page: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PageFormFields, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.get_form_fields(page).map_err(|e| e.to_string())
+}
+
+/// 读取页面每个字符的矩形（坐标与渲染图一致，原点在左上角），用于划词选择；
+/// `region` 不为空时只返回与该区域相交的字符，减少大页面的数据量
+#[tauri::command]
+pub async fn pdf_get_char_boxes(
+    file_path: String,
+    page: u32,
+    region: Option<FormFieldRect>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PageCharBoxes, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.get_char_boxes(page, region.as_ref()).map_err(|e| e.to_string())
+}
+
+/// 对扫描版页面做 OCR，返回识别文本与每个词的矩形（坐标同 `pdf_get_char_boxes`）；
+/// `lang` 为 tesseract 语言包，默认 `chi_sim+eng`。需构建时启用 `ocr` feature，缺少语言包时返回错误；
+/// 结果缓存到磁盘，之后 `pdf_search_text` 对无文字层的页面使用该结果
+#[tauri::command]
+pub async fn pdf_ocr_page(
+    file_path: String,
+    page: u32,
+    lang: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PageOcrResult, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.ocr_page(page, lang.as_deref()).await.map_err(|e| e.to_string())
+}
+
+/// 读取页面注释（高亮、下划线、便签、方框等）的类型、颜色、位置和文字内容，没有注释的页面返回空列表
+#[tauri::command]
+pub async fn pdf_get_annotations(
+    file_path: String,
+    page: u32,
+    manager: State<'_, PdfManagerState>,
+) -> Result<PageAnnotations, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.get_annotations(page).map_err(|e| e.to_string())
+}
+
+/// 列出文档嵌入文件（附件）的名称和大小，没有附件的文档返回空列表
+#[tauri::command]
+pub async fn pdf_list_attachments(
+    file_path: String,
+    manager: State<'_, PdfManagerState>,
+) -> Result<Vec<PdfAttachmentInfo>, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    engine.list_attachments().map_err(|e| e.to_string())
+}
+
+/// 按名称导出附件到 `out_path`，返回写入的字节数
+#[tauri::command]
+pub async fn pdf_extract_attachment(
+    file_path: String,
+    name: String,
+    out_path: String,
+    manager: State<'_, PdfManagerState>,
+) -> Result<u64, String> {
+    let engine_arc = {
+        let manager = manager.lock().await;
+        manager
+            .get_or_create_engine(&file_path)
+            .await
+            .map_err(|e| e.to_string())?
+    };
+    let engine = engine_arc.read().await;
+    let written = engine.extract_attachment(&name, &out_path).map_err(|e| e.to_string())?;
+    println!("[PDF] 导出附件: {} -> {} ({} bytes)", name, out_path, written);
+    Ok(written)
+}
+
+/// 动态设置 PDF 内存缓存上限（MB），由前端统一下发；与 `pdf_configure` 一样限制到允许区间并保存到设置表
 #[tauri::command]
 pub async fn pdf_set_cache_max_size(
     max_size_mb: u32,
     manager: State<'_, PdfManagerState>,
+    db: DbState<'_>,
 ) -> Result<bool, String> {
-    let mut manager = manager.lock().await;
     let bytes = (max_size_mb as usize) * 1024 * 1024;
-    manager.get_cache_manager_mut().set_max_size(bytes);
+    update_runtime_config(&manager, &db, Some(bytes), None, None).await?;
     Ok(true)
-}
\ No newline at end of file
+}
+
+/// 设置 PDF 页面输出格式（png/jpeg/webp/avif），传空恢复按渲染质量自动选择
+/// AVIF 体积更小但编码较慢，前端应先确认 WebView 能解码 AVIF 再下发
+#[tauri::command]
+pub async fn pdf_set_output_format(
+    format: Option<String>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<bool, String> {
+    let format = match format.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+        Some(name) => Some(
+            ImageFormat::from_name(name).ok_or_else(|| format!("不支持的输出格式: {}", name))?,
+        ),
+        None => None,
+    };
+    let mut manager = manager.lock().await;
+    manager.set_output_format(format);
+    Ok(true)
+}
+
+/// 设置 PDF 渲染标志（注释、文本/图形抗锯齿、LCD 文本、灰度），传空恢复默认
+/// 标志计入渲染缓存键，切换后重新渲染而不会读到旧设置下的缓存
+#[tauri::command]
+pub async fn pdf_set_render_flags(
+    flags: Option<RenderFlags>,
+    manager: State<'_, PdfManagerState>,
+) -> Result<bool, String> {
+    let mut manager = manager.lock().await;
+    manager.set_render_flags(flags.unwrap_or_default());
+    Ok(true)
+}
+
+/// 设置单页渲染的最大像素数（前端按设备内存下发），传空恢复默认的 32M 像素
+/// 超大页面超过上限时自动降低缩放，宁可略糊也不因分配位图而崩溃
+#[tauri::command]
+pub async fn pdf_set_max_render_pixels(max_pixels: Option<u64>) -> Result<bool, String> {
+    crate::pdf::renderer::set_max_render_pixels(max_pixels.map(|v| v as usize));
+    Ok(true)
+}
@@ -0,0 +1,288 @@
+//! 章节预取
+//! 顺序阅读时在后台提前加载当前章前后若干章：TXT 批量解码章节文本，EPUB 从磁盘章节缓存读入内存，
+//! 之后 `txt_load_chapter` / `epub_load_section` 命中内存即可直接返回，翻到下一章时不再等待 IO。
+//! 每次预取请求递增代号，旧请求发现代号过期后立即停止，避免快速跳章时旧预取与新预取互相争抢
+
+use crate::epub_commands::EpubCacheState;
+use crate::formats::epub::SectionCacheData;
+use crate::formats::txt::{TxtBookMeta, TxtChapterContent, TxtEngine};
+use crate::formats::BookFormat;
+use crate::txt_commands::load_cached_meta;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// 预取内容占用的内存上限（字节）
+const MAX_PREFETCH_BYTES: usize = 16 * 1024 * 1024;
+
+/// 当前章前后各自最多预取的章节数
+const MAX_PREFETCH_RADIUS: u32 = 5;
+
+/// 预取请求代号，新请求递增后旧请求自动作废
+static PREFETCH_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// 预取结果缓存，同一时间只保留一本书当前阅读窗口内的章节
+static PREFETCH_CACHE: Lazy<Mutex<PrefetchCache>> = Lazy::new(|| Mutex::new(PrefetchCache::default()));
+
+#[derive(Clone)]
+enum PrefetchedChapter {
+    Txt {
+        /// 解码时使用的编码，编码切换后缓存失效
+        encoding: String,
+        content: TxtChapterContent,
+    },
+    Epub(SectionCacheData),
+}
+
+impl PrefetchedChapter {
+    fn size_bytes(&self) -> usize {
+        match self {
+            PrefetchedChapter::Txt { content, .. } => content.content.len(),
+            PrefetchedChapter::Epub(data) => {
+                data.html.len() + data.styles.iter().map(String::len).sum::<usize>()
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct PrefetchCache {
+    /// TXT 为文件路径，EPUB 为 book_id
+    key: String,
+    chapters: HashMap<u32, PrefetchedChapter>,
+    total_bytes: usize,
+}
+
+impl PrefetchCache {
+    /// 切换到新的书籍或阅读窗口：换书时清空，同一本书只保留窗口内的章节
+    fn retain_window(&mut self, key: &str, window: &[u32]) {
+        if self.key != key {
+            self.key = key.to_string();
+            self.chapters.clear();
+        } else {
+            self.chapters.retain(|index, _| window.contains(index));
+        }
+        self.total_bytes = self.chapters.values().map(PrefetchedChapter::size_bytes).sum();
+    }
+
+    /// 写入一章，超出内存上限时放弃并返回 false
+    fn insert(&mut self, key: &str, index: u32, chapter: PrefetchedChapter) -> bool {
+        if self.key != key {
+            return false;
+        }
+        let size = chapter.size_bytes();
+        if self.total_bytes + size > MAX_PREFETCH_BYTES {
+            return false;
+        }
+        self.total_bytes += size;
+        if let Some(old) = self.chapters.insert(index, chapter) {
+            self.total_bytes -= old.size_bytes();
+        }
+        true
+    }
+
+    fn get(&self, key: &str, index: u32) -> Option<&PrefetchedChapter> {
+        (self.key == key).then(|| self.chapters.get(&index)).flatten()
+    }
+}
+
+/// 预取顺序：当前章优先，其次向后章节，最后向前章节，均由近到远
+fn prefetch_order(current: u32, ahead: u32, behind: u32, total: u32) -> Vec<u32> {
+    if current >= total {
+        return Vec::new();
+    }
+    let ahead = ahead.min(MAX_PREFETCH_RADIUS);
+    let behind = behind.min(MAX_PREFETCH_RADIUS);
+    let mut order = vec![current];
+    order.extend((1..=ahead).map(|d| current + d).filter(|&i| i < total));
+    order.extend((1..=behind).filter_map(|d| current.checked_sub(d)));
+    order
+}
+
+fn is_current(generation: u64) -> bool {
+    PREFETCH_GENERATION.load(Ordering::SeqCst) == generation
+}
+
+/// 读取已预取的 TXT 章节，章节偏移或编码与当前元数据不一致时视为未命中
+pub(crate) fn take_prefetched_txt(file_path: &str, index: u32, meta: &TxtBookMeta) -> Option<TxtChapterContent> {
+    let cache = PREFETCH_CACHE.lock().ok()?;
+    let Some(PrefetchedChapter::Txt { encoding, content }) = cache.get(file_path, index) else {
+        return None;
+    };
+    let chapter = meta.chapters.get(index as usize)?;
+    (*encoding == meta.encoding && content.char_start == chapter.char_start && content.char_end == chapter.char_end)
+        .then(|| content.clone())
+}
+
+/// 读取已预取的 EPUB 章节
+pub(crate) fn take_prefetched_section(book_id: &str, index: u32) -> Option<SectionCacheData> {
+    let cache = PREFETCH_CACHE.lock().ok()?;
+    match cache.get(book_id, index) {
+        Some(PrefetchedChapter::Epub(data)) => Some(data.clone()),
+        _ => None,
+    }
+}
+
+/// 书籍内容变化（重新解析、清理缓存）时丢弃对应的预取结果
+pub(crate) fn invalidate_prefetch(key: &str) {
+    if let Ok(mut cache) = PREFETCH_CACHE.lock() {
+        if cache.key == key {
+            *cache = PrefetchCache::default();
+        }
+    }
+}
+
+/// 丢弃全部预取结果并作废进行中的预取（如切换 HTML 清洗开关后，已预取的章节清洗状态已过时）
+pub(crate) fn clear_prefetch() {
+    PREFETCH_GENERATION.fetch_add(1, Ordering::SeqCst);
+    if let Ok(mut cache) = PREFETCH_CACHE.lock() {
+        *cache = PrefetchCache::default();
+    }
+}
+
+/// 预取 TXT 章节：按内存上限挑选未缓存的章节后一次性批量解码
+async fn prefetch_txt(file_path: String, order: Vec<u32>, generation: u64) -> Result<(), String> {
+    let meta = load_cached_meta(&file_path, None)?;
+    let mut budget = {
+        let cache = PREFETCH_CACHE.lock().map_err(|e| e.to_string())?;
+        MAX_PREFETCH_BYTES.saturating_sub(cache.total_bytes)
+    };
+    let mut missing = Vec::new();
+    for &index in &order {
+        if take_prefetched_txt(&file_path, index, &meta).is_some() {
+            continue;
+        }
+        let Some(chapter) = meta.chapters.get(index as usize) else {
+            continue;
+        };
+        let estimate = (chapter.byte_end - chapter.byte_start) as usize;
+        if estimate > budget {
+            break;
+        }
+        budget -= estimate;
+        missing.push(index);
+    }
+    if missing.is_empty() || !is_current(generation) {
+        return Ok(());
+    }
+
+    let path = file_path.clone();
+    let encoding = meta.encoding.clone();
+    let chapters = tokio::task::spawn_blocking(move || TxtEngine::load_chapters(&path, &missing, &meta, None))
+        .await
+        .map_err(|e| format!("章节预取任务失败: {}", e))?
+        .map_err(|e| e.to_string())?;
+
+    if !is_current(generation) {
+        return Ok(());
+    }
+    let mut cache = PREFETCH_CACHE.lock().map_err(|e| e.to_string())?;
+    for content in chapters {
+        let index = content.index;
+        let chapter = PrefetchedChapter::Txt {
+            encoding: encoding.clone(),
+            content,
+        };
+        if !cache.insert(&file_path, index, chapter) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// 预取 EPUB 章节：逐章从磁盘缓存读取，每章之间检查请求是否已被新的预取取代
+async fn prefetch_epub(state: &EpubCacheState, book_id: &str, order: Vec<u32>, generation: u64) -> Result<(), String> {
+    for index in order {
+        if !is_current(generation) {
+            break;
+        }
+        if take_prefetched_section(book_id, index).is_some() {
+            continue;
+        }
+        // 每章单独加锁，不阻塞用户主动加载章节
+        let section = state.lock().await.load_section(book_id, index).await?;
+        let Some(section) = section else {
+            continue;
+        };
+        if !is_current(generation) {
+            break;
+        }
+        let mut cache = PREFETCH_CACHE.lock().map_err(|e| e.to_string())?;
+        if !cache.insert(book_id, index, PrefetchedChapter::Epub(section)) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// 后台预取当前章前后若干章（各自最多 5 章），返回已就绪的章节索引
+/// 新的预取请求会使进行中的旧请求提前结束；EPUB 需要传入 `book_id` 以定位章节缓存
+#[tauri::command]
+pub async fn prefetch_chapters<R: Runtime>(
+    app: AppHandle<R>,
+    file_path: String,
+    current_index: u32,
+    ahead: u32,
+    behind: u32,
+    book_id: Option<String>,
+) -> Result<Vec<u32>, String> {
+    let generation = PREFETCH_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+
+    let format = BookFormat::from_path(&file_path);
+    let (key, total) = match format {
+        Some(BookFormat::Txt) => {
+            let meta = load_cached_meta(&file_path, None)?;
+            (file_path.clone(), meta.chapters.len() as u32)
+        }
+        Some(BookFormat::Epub) => {
+            let book_id = book_id.ok_or_else(|| "EPUB 章节预取需要 book_id".to_string())?;
+            let state = app
+                .try_state::<EpubCacheState>()
+                .ok_or_else(|| "EPUB 缓存未初始化".to_string())?;
+            let total = state
+                .lock()
+                .await
+                .load_metadata(&book_id)
+                .await?
+                .map(|entry| entry.section_count)
+                .ok_or_else(|| format!("EPUB 元数据未缓存: {}", book_id))?;
+            (book_id, total)
+        }
+        _ => return Err(format!("不支持章节预取的格式: {}", file_path)),
+    };
+
+    let order = prefetch_order(current_index, ahead, behind, total);
+    PREFETCH_CACHE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .retain_window(&key, &order);
+
+    let result = match (format, app.try_state::<EpubCacheState>()) {
+        (Some(BookFormat::Epub), Some(state)) => prefetch_epub(&state, &key, order.clone(), generation).await,
+        _ => prefetch_txt(file_path, order.clone(), generation).await,
+    };
+    if let Err(e) = result {
+        eprintln!("[Prefetch] 章节预取失败: key={}, error={}", key, e);
+    }
+
+    let cache = PREFETCH_CACHE.lock().map_err(|e| e.to_string())?;
+    let mut ready: Vec<u32> = order.into_iter().filter(|i| cache.get(&key, *i).is_some()).collect();
+    ready.sort_unstable();
+    Ok(ready)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefetch_order() {
+        assert_eq!(prefetch_order(3, 2, 2, 10), vec![3, 4, 5, 2, 1]);
+        assert_eq!(prefetch_order(0, 2, 3, 2), vec![0, 1]);
+        assert_eq!(prefetch_order(9, 20, 0, 10), vec![9]);
+        assert_eq!(prefetch_order(10, 2, 2, 10), Vec::<u32>::new());
+        assert_eq!(prefetch_order(0, 20, 0, 100).len(), 1 + MAX_PREFETCH_RADIUS as usize);
+    }
+}
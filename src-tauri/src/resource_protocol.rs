@@ -0,0 +1,180 @@
+//! 书籍资源按需加载
+//! 章节 HTML 中的资源占位符可改写为 `goread-res` 自定义协议地址，由 WebView 按需请求，
+//! 后端从 EPUB/MOBI 磁盘缓存（本地 HTML 则从其所在目录）中读取资源字节，避免把整章图片随章节一次性序列化返回
+
+use crate::epub_commands::EpubCacheState;
+use crate::formats::epub::percent_decode_path;
+use crate::formats::html::resources::load_registered_resource;
+use crate::mobi_commands::MobiCacheState;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{AppHandle, Manager, Runtime, State, UriSchemeContext, UriSchemeResponder};
+
+/// 自定义协议名
+pub const RESOURCE_SCHEME: &str = "goread-res";
+
+/// EPUB/MOBI 章节缓存中的资源占位符
+static PLACEHOLDER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"__(?:EPUB|MOBI)_RES__:([^\s"'()>]+)"#).unwrap());
+
+/// 自定义协议在各平台上的访问前缀
+fn resource_base_url() -> String {
+    if cfg!(any(target_os = "windows", target_os = "android")) {
+        format!("http://{}.localhost/", RESOURCE_SCHEME)
+    } else {
+        format!("{}://localhost/", RESOURCE_SCHEME)
+    }
+}
+
+/// 百分号编码 URL 路径片段；`keep_slash` 为 true 时保留 `/` 作为层级分隔
+fn percent_encode(segment: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for &b in segment.as_bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(b as char)
+            }
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// 生成资源的按需加载地址
+pub fn resource_url(book_id: &str, resource_path: &str) -> String {
+    format!(
+        "{}{}/{}",
+        resource_base_url(),
+        percent_encode(book_id, false),
+        percent_encode(resource_path, true)
+    )
+}
+
+/// 将章节 HTML/CSS 中的 `__EPUB_RES__:`/`__MOBI_RES__:` 占位符替换为按需加载地址
+pub fn rewrite_resource_placeholders(content: &str, book_id: &str) -> String {
+    PLACEHOLDER_RE
+        .replace_all(content, |caps: &regex::Captures| resource_url(book_id, &caps[1]))
+        .into_owned()
+}
+
+/// 依次从 EPUB、MOBI 磁盘缓存和已登记的 HTML 目录中查找资源
+async fn load_cached_resource<R: Runtime>(
+    app: &AppHandle<R>,
+    book_id: &str,
+    resource_path: &str,
+) -> Result<Option<(Vec<u8>, String)>, String> {
+    if let Some(state) = app.try_state::<EpubCacheState>() {
+        let manager = state.lock().await;
+        if let Some(found) = manager.load_resource(book_id, resource_path).await? {
+            return Ok(Some(found));
+        }
+    }
+    if let Some(state) = app.try_state::<MobiCacheState>() {
+        let manager = state.lock().await;
+        if let Some(found) = manager.load_resource(book_id, resource_path).await? {
+            return Ok(Some(found));
+        }
+    }
+    // 本地 HTML 的 book_id 为文件路径，资源从其所在目录读取
+    let (book_id, resource_path) = (book_id.to_string(), resource_path.to_string());
+    tokio::task::spawn_blocking(move || load_registered_resource(&book_id, &resource_path))
+        .await
+        .map_err(|e| format!("读取资源任务失败: {}", e))
+}
+
+/// 解析请求路径 `/<book_id>/<resource_path>`
+fn parse_request_path(path: &str) -> Option<(String, String)> {
+    let (book_id, resource_path) = path.trim_start_matches('/').split_once('/')?;
+    if book_id.is_empty() || resource_path.is_empty() {
+        return None;
+    }
+    Some((percent_decode_path(book_id), percent_decode_path(resource_path)))
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response<Vec<u8>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .body(message.as_bytes().to_vec())
+        .unwrap()
+}
+
+/// `goread-res` 协议处理器
+pub fn handle_resource_request<R: Runtime>(
+    ctx: UriSchemeContext<'_, R>,
+    request: Request<Vec<u8>>,
+    responder: UriSchemeResponder,
+) {
+    let app = ctx.app_handle().clone();
+    let path = request.uri().path().to_string();
+
+    tauri::async_runtime::spawn(async move {
+        let Some((book_id, resource_path)) = parse_request_path(&path) else {
+            responder.respond(error_response(StatusCode::BAD_REQUEST, "无效的资源地址"));
+            return;
+        };
+
+        let response = match load_cached_resource(&app, &book_id, &resource_path).await {
+            Ok(Some((data, mime_type))) => Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, mime_type)
+                .header(header::CACHE_CONTROL, "max-age=86400")
+                .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+                .body(data)
+                .unwrap(),
+            Ok(None) => error_response(StatusCode::NOT_FOUND, "资源不存在"),
+            Err(e) => {
+                eprintln!(
+                    "[Resource] 加载资源失败: book_id={}, resource_path={}, error={}",
+                    book_id, resource_path, e
+                );
+                error_response(StatusCode::INTERNAL_SERVER_ERROR, &e)
+            }
+        };
+        responder.respond(response);
+    });
+}
+
+/// 按需获取单个资源（原始字节，不经 JSON 数组序列化）
+/// 供无法使用自定义协议的场景调用；资源不存在时返回错误
+#[tauri::command]
+pub async fn get_book_resource(
+    book_id: String,
+    resource_path: String,
+    epub_state: State<'_, EpubCacheState>,
+    mobi_state: State<'_, MobiCacheState>,
+) -> Result<tauri::ipc::Response, String> {
+    {
+        let manager = epub_state.lock().await;
+        if let Some((data, _)) = manager.load_resource(&book_id, &resource_path).await? {
+            return Ok(tauri::ipc::Response::new(data));
+        }
+    }
+    let manager = mobi_state.lock().await;
+    match manager.load_resource(&book_id, &resource_path).await? {
+        Some((data, _)) => Ok(tauri::ipc::Response::new(data)),
+        None => Err(format!("资源不存在: {}", resource_path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_and_parse_roundtrip() {
+        let html = r#"<img src="__EPUB_RES__:OEBPS/images/图1.png"/><div style="background:url(__MOBI_RES__:img/a.jpg)">"#;
+        let rewritten = rewrite_resource_placeholders(html, "book#1");
+        assert!(!rewritten.contains("__EPUB_RES__"));
+        assert!(!rewritten.contains("__MOBI_RES__"));
+
+        let url = resource_url("book#1", "OEBPS/images/a b.png");
+        let path = url.split_once(".localhost/").or_else(|| url.split_once("://localhost/")).unwrap().1;
+        let (book_id, resource_path) = parse_request_path(&format!("/{}", path)).unwrap();
+        assert_eq!(book_id, "book#1");
+        assert_eq!(resource_path, "OEBPS/images/a b.png");
+    }
+}
@@ -0,0 +1,117 @@
+use crate::formats::html::HtmlEngine;
+use crate::formats::markdown::MarkdownEngine;
+use crate::formats::{epub, mobi, BookFormat};
+use crate::pdf::doc_cache::with_cached_document;
+use crate::pdf::types::PdfError;
+use crate::tts::dispatcher::html_text::extract_plain_text;
+use crate::tts::dispatcher::txt::{ensure_metadata, load_chapter_text};
+
+/// 结束时需要换行的块级标签，去标签前先替换为换行，保证段落之间能断句
+const BLOCK_CLOSE_TAGS: &[&str] = &[
+    "</p>", "</div>", "</li>", "</h1>", "</h2>", "</h3>", "</h4>", "</h5>", "</h6>", "</blockquote>", "</tr>",
+];
+
+/// 按格式读取单个章节（PDF 为页，从 0 开始）的纯文本
+///
+/// 不依赖前端的书籍缓存，每次都从文件读取：MOBI 需要完整解析整本书，较慢，调用方应在阻塞线程中执行。
+/// Markdown / HTML 整个文件视为一个章节，只接受索引 0
+pub fn load_section_text(file_path: &str, section_index: u32) -> Result<String, String> {
+    let format = BookFormat::from_path(file_path).ok_or_else(|| format!("无法识别的文件格式: {}", file_path))?;
+    match format {
+        BookFormat::Txt => {
+            let meta = ensure_metadata(file_path)?;
+            if section_index as usize >= meta.chapters.len() {
+                return Err(format!("TXT 章节不存在: {}", section_index));
+            }
+            load_chapter_text(file_path, section_index as i32, &meta)
+        }
+        BookFormat::Epub => {
+            let html = epub::engine::read_section_html(file_path, section_index)?;
+            Ok(html_to_text(&html))
+        }
+        BookFormat::Mobi | BookFormat::Azw3 => {
+            let book = mobi::engine::prepare_book(file_path)?;
+            let section = book
+                .sections
+                .iter()
+                .find(|s| s.index == section_index)
+                .ok_or_else(|| format!("MOBI 章节不存在: {}", section_index))?;
+            Ok(html_to_text(&section.html))
+        }
+        BookFormat::Pdf => load_pdf_page_text(file_path, section_index).map_err(|e| e.to_string()),
+        BookFormat::Markdown | BookFormat::Html if section_index != 0 => {
+            Err(format!("章节不存在: {}", section_index))
+        }
+        BookFormat::Markdown => {
+            let engine = MarkdownEngine::from_file(file_path).map_err(|e| e.to_string())?;
+            Ok(markdown_to_text(engine.get_content()))
+        }
+        BookFormat::Html => {
+            let engine = HtmlEngine::from_file(file_path).map_err(|e| e.to_string())?;
+            Ok(html_to_text(engine.get_content()))
+        }
+        other => Err(format!("不支持的朗读格式: {:?}", other)),
+    }
+}
+
+fn load_pdf_page_text(file_path: &str, page_index: u32) -> Result<String, PdfError> {
+    with_cached_document(file_path, |_pdfium, document| {
+        let page_number = page_index + 1;
+        let total_pages = document.pages().len() as u32;
+        if page_number > total_pages {
+            return Err(PdfError::PageNotFound {
+                page: page_number,
+                total_pages,
+            });
+        }
+        let page = document
+            .pages()
+            .get(page_index as u16)
+            .map_err(|e| PdfError::parse_error(Some(page_number), "获取页面失败", e.to_string()))?;
+        let text = page
+            .text()
+            .map_err(|e| PdfError::parse_error(Some(page_number), "提取文本失败", e.to_string()))?;
+        Ok(text.all())
+    })
+}
+
+/// 块级标签与 `<br>` 转为换行后再去标签
+fn html_to_text(html: &str) -> String {
+    let mut lower = html.to_ascii_lowercase();
+    let mut html = html.to_string();
+    for tag in BLOCK_CLOSE_TAGS.iter().copied().chain(["<br>", "<br/>", "<br />"]) {
+        let mut from = 0;
+        while let Some(rel) = lower[from..].find(tag) {
+            let at = from + rel;
+            html.replace_range(at..at + tag.len(), "\n");
+            lower.replace_range(at..at + tag.len(), "\n");
+            from = at + 1;
+        }
+    }
+    extract_plain_text(&html)
+}
+
+/// 去掉 Markdown 中常见的行首标记与强调符号，保留文字
+fn markdown_to_text(markdown: &str) -> String {
+    let mut out = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        let text = trimmed.trim_start_matches(['#', '>']).trim_start();
+        let text = text
+            .strip_prefix("- ")
+            .or_else(|| text.strip_prefix("* "))
+            .or_else(|| text.strip_prefix("+ "))
+            .unwrap_or(text);
+        out.push_str(&text.replace(['*', '`'], "").replace("__", ""));
+        out.push('\n');
+    }
+    out
+}
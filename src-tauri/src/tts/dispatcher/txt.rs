@@ -4,7 +4,7 @@ use crate::formats::txt::{TxtBookMeta, TxtEngine};
 use crate::tts::cursor::{decode_section_cursor, encode_section_cursor};
 use crate::tts::slicer::{find_anchor_start_byte, slice_text_to_segments, SliceOptions};
 use crate::tts::types::{TtsGetSegmentsRequest, TtsGetSegmentsResponse, TtsSegmentDto};
-use crate::txt_commands::METADATA_CACHE;
+use crate::txt_commands::load_cached_meta;
 
 /// 单批次最多跨越的 TXT 章节数
 const MAX_CHAPTERS_PER_BATCH: i32 = 4;
@@ -105,22 +105,13 @@ pub async fn get_segments<R: Runtime>(
 }
 
 /// 复用 txt_commands 的元数据缓存；未命中时自动解析并写入
-fn ensure_metadata(file_path: &str) -> Result<TxtBookMeta, String> {
-    {
-        let cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
-        if let Some(m) = cache.get(file_path) {
-            return Ok(m.clone());
-        }
-    }
-    let m = TxtEngine::load_metadata(file_path).map_err(|e| e.to_string())?;
-    let mut cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
-    cache.insert(file_path.to_string(), m.clone());
-    Ok(m)
+pub(crate) fn ensure_metadata(file_path: &str) -> Result<TxtBookMeta, String> {
+    load_cached_meta(file_path, None)
 }
 
 /// 加载指定章节文本
-fn load_chapter_text(file_path: &str, chapter_index: i32, meta: &TxtBookMeta) -> Result<String, String> {
-    let chapters = TxtEngine::load_chapters(file_path, &[chapter_index as u32], meta)
+pub(crate) fn load_chapter_text(file_path: &str, chapter_index: i32, meta: &TxtBookMeta) -> Result<String, String> {
+    let chapters = TxtEngine::load_chapters(file_path, &[chapter_index as u32], meta, None)
         .map_err(|e| e.to_string())?;
     Ok(chapters
         .into_iter()
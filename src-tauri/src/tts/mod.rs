@@ -1,4 +1,5 @@
 pub mod cursor;
+pub mod sentences;
 pub mod session_manager;
 pub mod slicer;
 pub mod types;
@@ -7,6 +8,7 @@ pub mod dispatcher {
     pub mod epub;
     pub mod html_text;
     pub mod mobi;
+    pub mod section_text;
     pub mod txt;
 }
 
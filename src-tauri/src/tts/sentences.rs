@@ -0,0 +1,175 @@
+use crate::tts::types::TtsSentenceDto;
+
+/// 一定结束句子的标点
+const HARD_END_CHARS: &[char] = &['。', '！', '？', '!', '?'];
+
+/// 省略号字符：单独出现时不结束句子（“他说……然后”），段落末尾仍会自然断句
+const ELLIPSIS_CHARS: &[char] = &['…', '⋯'];
+
+/// 紧跟句末标点的收尾符号，归入前一句
+const CLOSING_CHARS: &[char] = &['”', '’', '」', '』', '》', '）', ')', '】', ']', '"'];
+
+/// 成对引号：引号内的句末标点不单独断句，待引号闭合后再结束
+const QUOTE_PAIRS: &[(char, char)] = &[('“', '”'), ('「', '」'), ('『', '』'), ('‘', '’')];
+
+/// 按中英文句末标点和换行切句，返回每句文本及其在 text 中的字符区间（按 Unicode 字符计，左闭右开）
+///
+/// - 引号内的句末标点不断句，句末标点紧跟闭合引号时在引号后结束（`他说：“你好。走吧。”` 为一句）
+/// - 省略号（`……`、`...`）本身不断句，与其他句末标点连用时（`……！`）才结束
+/// - 数字中的点（`3.14`）、网址/缩写中紧跟字母的点（`www.a.com`、`e.g.`）不当作句号
+pub fn split_sentences(text: &str) -> Vec<TtsSentenceDto> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut sentences = Vec::new();
+    let mut start = 0usize;
+    let mut quote_stack: Vec<char> = Vec::new();
+    let mut in_straight_quote = false;
+    let mut i = 0usize;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\n' || c == '\r' {
+            push_sentence(&mut sentences, &chars, start, i);
+            quote_stack.clear();
+            in_straight_quote = false;
+            i += 1;
+            start = i;
+            continue;
+        }
+
+        if let Some(&(_, close)) = QUOTE_PAIRS.iter().find(|(open, _)| *open == c) {
+            quote_stack.push(close);
+            i += 1;
+            continue;
+        }
+        if quote_stack.last() == Some(&c) {
+            quote_stack.pop();
+            i += 1;
+            continue;
+        }
+        if c == '"' {
+            in_straight_quote = !in_straight_quote;
+            i += 1;
+            continue;
+        }
+
+        if !is_terminator(&chars, i) {
+            i += 1;
+            continue;
+        }
+
+        // 连续的句末标点（`？！`、`……`、`...`）作为一组处理
+        let mut end = i;
+        let mut hard = false;
+        while end < chars.len() && is_terminator(&chars, end) {
+            hard |= HARD_END_CHARS.contains(&chars[end]) || (chars[end] == '.' && !is_ellipsis_dot(&chars, end));
+            end += 1;
+        }
+
+        // 收尾的引号、括号归入本句，同时更新引号状态
+        while end < chars.len() && CLOSING_CHARS.contains(&chars[end]) {
+            let closing = chars[end];
+            if quote_stack.last() == Some(&closing) {
+                quote_stack.pop();
+            } else if closing == '"' {
+                if !in_straight_quote {
+                    break;
+                }
+                in_straight_quote = false;
+            }
+            end += 1;
+        }
+
+        if hard && quote_stack.is_empty() && !in_straight_quote {
+            push_sentence(&mut sentences, &chars, start, end);
+            start = end;
+        }
+        i = end;
+    }
+
+    push_sentence(&mut sentences, &chars, start, chars.len());
+    sentences
+}
+
+/// 当前字符是否为句末标点（含省略号）；数字或字母之间的点不算
+fn is_terminator(chars: &[char], i: usize) -> bool {
+    let c = chars[i];
+    if HARD_END_CHARS.contains(&c) || ELLIPSIS_CHARS.contains(&c) {
+        return true;
+    }
+    if c != '.' {
+        return false;
+    }
+    if is_ellipsis_dot(chars, i) {
+        return true;
+    }
+    let prev = i.checked_sub(1).map(|p| chars[p]);
+    let next = chars.get(i + 1).copied();
+    match (prev, next) {
+        // 3.14、v1.2
+        (Some(p), Some(n)) if p.is_ascii_digit() && n.is_ascii_digit() => false,
+        // www.example.com、e.g
+        (_, Some(n)) if n.is_ascii_alphanumeric() => false,
+        _ => true,
+    }
+}
+
+/// 是否属于连续两个及以上的点（英文省略号）
+fn is_ellipsis_dot(chars: &[char], i: usize) -> bool {
+    chars[i] == '.'
+        && (chars.get(i + 1) == Some(&'.') || i.checked_sub(1).map(|p| chars[p]) == Some('.'))
+}
+
+/// 去掉首尾空白后加入结果，空句跳过
+fn push_sentence(sentences: &mut Vec<TtsSentenceDto>, chars: &[char], start: usize, end: usize) {
+    let mut s = start;
+    let mut e = end;
+    while s < e && chars[s].is_whitespace() {
+        s += 1;
+    }
+    while e > s && chars[e - 1].is_whitespace() {
+        e -= 1;
+    }
+    if s < e {
+        sentences.push(TtsSentenceDto {
+            text: chars[s..e].iter().collect(),
+            char_start: s,
+            char_end: e,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<String> {
+        split_sentences(text).into_iter().map(|s| s.text).collect()
+    }
+
+    #[test]
+    fn test_split_sentences_punctuation() {
+        assert_eq!(
+            texts("他说：“你好。我们走吧！”然后离开了。圆周率约为3.14，对吗？"),
+            vec!["他说：“你好。我们走吧！”", "然后离开了。", "圆周率约为3.14，对吗？"]
+        );
+        assert_eq!(
+            texts("Visit www.example.com today. \"Stop!\" she said... Wait. Really?!"),
+            vec!["Visit www.example.com today.", "\"Stop!\"", "she said... Wait.", "Really?!"]
+        );
+        assert_eq!(texts("他犹豫了……终于开口。\n第二段没有句号"), vec!["他犹豫了……终于开口。", "第二段没有句号"]);
+    }
+
+    #[test]
+    fn test_split_sentences_ranges() {
+        let text = "  第一句。\n 第二句！";
+        let sentences = split_sentences(text);
+        let chars: Vec<char> = text.chars().collect();
+        assert_eq!(sentences.len(), 2);
+        for sentence in &sentences {
+            let slice: String = chars[sentence.char_start..sentence.char_end].iter().collect();
+            assert_eq!(slice, sentence.text);
+        }
+        assert_eq!((sentences[0].char_start, sentences[0].char_end), (2, 6));
+    }
+}
@@ -102,3 +102,11 @@ pub struct TtsManagedSessionStatus {
     pub buffer_seconds: f64,
 }
 
+
+/// 分句结果：字符区间按 Unicode 字符计，左闭右开
+#[derive(Debug, Clone, Serialize)]
+pub struct TtsSentenceDto {
+    pub text: String,
+    pub char_start: usize,
+    pub char_end: usize,
+}
@@ -1,7 +1,8 @@
 use tauri::{AppHandle, Runtime};
 
 use crate::tts::dispatcher;
-use crate::tts::types::{TtsGetSegmentsRequest, TtsGetSegmentsResponse};
+use crate::tts::sentences::split_sentences;
+use crate::tts::types::{TtsGetSegmentsRequest, TtsGetSegmentsResponse, TtsSentenceDto};
 
 /// 统一 TTS 取片入口：按 format 字段分发到对应 dispatcher
 #[tauri::command]
@@ -16,3 +17,14 @@ pub async fn tts_get_segments<R: Runtime>(
         other => Err(format!("不支持的 TTS 格式: {}", other)),
     }
 }
+
+/// 取出指定章节（PDF 为页，从 0 开始）的纯文本并切句，供朗读逐句高亮
+#[tauri::command]
+pub async fn get_sentences(file_path: String, section_index: u32) -> Result<Vec<TtsSentenceDto>, String> {
+    tokio::task::spawn_blocking(move || {
+        let text = dispatcher::section_text::load_section_text(&file_path, section_index)?;
+        Ok(split_sentences(&text))
+    })
+    .await
+    .map_err(|e| format!("分句任务失败: {}", e))?
+}
@@ -1,10 +1,16 @@
 //! TXT 相关的 Tauri 命令
 
-use crate::formats::txt::{TxtBookMeta, TxtChapterContent, TxtEngine};
+use crate::formats::txt::{
+    LocateBy, TocDiagnostics, TxtBookMeta, TxtChapterContent, TxtChapterNormalizeOptions, TxtEncodingCandidate,
+    TxtEngine, TxtFormatOptions, TxtLocation, TxtReadingEstimate, TxtRepairResult, repair_garbled_text,
+    to_vertical_text,
+};
 use std::time::Instant;
 use crate::formats::{BookMetadata, TocItem};
+use crate::commands::virtual_book::is_virtual_book_path;
+use crate::prefetch_commands::{invalidate_prefetch, take_prefetched_txt};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
 
@@ -13,6 +19,28 @@ pub(crate) static METADATA_CACHE: Lazy<Mutex<HashMap<String, TxtBookMeta>>> = La
     Mutex::new(HashMap::new())
 });
 
+/// 沿用原有章节划分（不做章节规整）的 TXT 路径：升级前已导入的书籍及其虚拟书成员，启动时从数据库加载
+static LEGACY_CHAPTER_LAYOUT: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// 记录沿用原有章节划分的路径，避免已保存的章节进度与书签错位
+pub(crate) fn set_legacy_chapter_layout(paths: Vec<String>) {
+    if let Ok(mut legacy) = LEGACY_CHAPTER_LAYOUT.lock() {
+        *legacy = paths.into_iter().collect();
+    }
+}
+
+/// 未显式指定时使用的章节规整选项：新书默认开启，升级前已导入的书籍关闭
+pub(crate) fn default_chapter_normalize(file_path: &str) -> TxtChapterNormalizeOptions {
+    let legacy = LEGACY_CHAPTER_LAYOUT
+        .lock()
+        .map(|legacy| legacy.contains(file_path))
+        .unwrap_or(false);
+    TxtChapterNormalizeOptions {
+        enabled: !legacy,
+        ..Default::default()
+    }
+}
+
 /// 加载 TXT 文档的结果（兼容旧 API）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TxtLoadResult {
@@ -42,25 +70,52 @@ pub async fn txt_load_document(file_path: String) -> Result<TxtLoadResult, Strin
     })
 }
 
+/// 判断缓存的元数据是否满足指定编码（未指定编码时总是满足）
+fn cached_meta_matches(meta: &TxtBookMeta, force_encoding: Option<&str>) -> bool {
+    match force_encoding {
+        Some(label) => encoding_rs::Encoding::for_label(label.trim().as_bytes())
+            .map(|enc| enc.name() == meta.encoding)
+            .unwrap_or(false),
+        None => true,
+    }
+}
+
 /// 快速加载 TXT 元数据（只解析目录，不返回全文内容）
+/// `force_encoding` 用于手动覆盖编码检测结果
+/// `chapter_normalize` 为章节规整选项（合并过短章节、拆分过长章节），不传时按书籍使用默认选项（见 `default_chapter_normalize`）
 #[tauri::command]
-pub async fn txt_load_metadata(file_path: String) -> Result<TxtBookMeta, String> {
+pub async fn txt_load_metadata(
+    file_path: String,
+    force_encoding: Option<String>,
+    chapter_normalize: Option<TxtChapterNormalizeOptions>,
+) -> Result<TxtBookMeta, String> {
+    ensure_real_txt_path(&file_path)?;
+    let force_encoding = force_encoding.as_deref();
+
     // 检查缓存
     {
         let cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
         if let Some(meta) = cache.get(&file_path) {
-            eprintln!("[TxtCommands] 元数据缓存命中: {}", file_path);
-            return Ok(meta.clone());
+            let normalize_matches = chapter_normalize
+                .as_ref()
+                .is_none_or(|options| *options == meta.chapter_normalize);
+            if cached_meta_matches(meta, force_encoding) && normalize_matches {
+                eprintln!("[TxtCommands] 元数据缓存命中: {}", file_path);
+                return Ok(meta.clone());
+            }
         }
     }
 
     // 解析元数据并记录耗时
     let start = Instant::now();
-    let meta = TxtEngine::load_metadata(&file_path).map_err(|e| e.to_string())?;
+    let chapter_normalize = chapter_normalize.unwrap_or_else(|| default_chapter_normalize(&file_path));
+    let meta = TxtEngine::load_metadata_with(&file_path, force_encoding, &chapter_normalize)
+        .map_err(|e| e.to_string())?;
     let elapsed = start.elapsed();
     println!(
-        "[TxtCommands] 元数据解析完成: file={}, chapters={}, total_chars={}, total_bytes={}, elapsed_ms={}",
+        "[TxtCommands] 元数据解析完成: file={}, encoding={}, chapters={}, total_chars={}, total_bytes={}, elapsed_ms={}",
         file_path,
+        meta.encoding,
         meta.chapters.len(),
         meta.total_chars,
         meta.total_bytes,
@@ -76,29 +131,45 @@ pub async fn txt_load_metadata(file_path: String) -> Result<TxtBookMeta, String>
     Ok(meta)
 }
 
+/// 虚拟书路径没有实体文件，需通过 `virtual_book_*` 命令按合并元数据读取
+pub(crate) fn ensure_real_txt_path(file_path: &str) -> Result<(), String> {
+    if is_virtual_book_path(file_path) {
+        Err(format!("虚拟书请使用 virtual_book_* 命令读取: {}", file_path))
+    } else {
+        Ok(())
+    }
+}
+
+/// 获取元数据：优先使用缓存（编码与指定编码不一致时视为未命中），未命中时解析并写入缓存
+/// 重新解析时沿用缓存中的章节规整选项，没有缓存时使用该书的默认选项
+pub(crate) fn load_cached_meta(file_path: &str, force_encoding: Option<&str>) -> Result<TxtBookMeta, String> {
+    ensure_real_txt_path(file_path)?;
+    let normalize = {
+        let cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
+        match cache.get(file_path) {
+            Some(meta) if cached_meta_matches(meta, force_encoding) => return Ok(meta.clone()),
+            Some(meta) => meta.chapter_normalize.clone(),
+            None => default_chapter_normalize(file_path),
+        }
+    };
+    let meta = TxtEngine::load_metadata_with(file_path, force_encoding, &normalize).map_err(|e| e.to_string())?;
+    let mut cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
+    cache.insert(file_path.to_string(), meta.clone());
+    Ok(meta)
+}
+
 /// 加载指定章节内容
+/// `format` 为排版选项（保留段首缩进、去除行尾空白、返回段落结构、竖排标点），不传时保持原文
 #[tauri::command]
 pub async fn txt_load_chapter(
     file_path: String,
     chapter_index: u32,
     extra_chapters: Option<Vec<u32>>,
+    force_encoding: Option<String>,
+    format: Option<TxtFormatOptions>,
 ) -> Result<Vec<TxtChapterContent>, String> {
-    // 获取元数据
-    let meta = {
-        let cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
-        cache.get(&file_path).cloned()
-    };
-
-    let meta = match meta {
-        Some(m) => m,
-        None => {
-            // 如果缓存中没有，先加载元数据
-            let m = TxtEngine::load_metadata(&file_path).map_err(|e| e.to_string())?;
-            let mut cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
-            cache.insert(file_path.clone(), m.clone());
-            m
-        }
-    };
+    let force_encoding = force_encoding.as_deref();
+    let meta = load_cached_meta(&file_path, force_encoding)?;
 
     // 收集需要加载的章节索引
     let mut indices = vec![chapter_index];
@@ -110,18 +181,95 @@ pub async fn txt_load_chapter(
         }
     }
 
-    // 批量加载章节
-    let chapters = TxtEngine::load_chapters(&file_path, &indices, &meta).map_err(|e| e.to_string())?;
+    // 优先使用后台预取的章节，其余批量加载
+    let mut loaded: HashMap<u32, TxtChapterContent> = indices
+        .iter()
+        .filter_map(|&idx| take_prefetched_txt(&file_path, idx, &meta).map(|c| (idx, c)))
+        .collect();
+    let missing: Vec<u32> = indices.iter().copied().filter(|idx| !loaded.contains_key(idx)).collect();
+    let fetched = TxtEngine::load_chapters(&file_path, &missing, &meta, force_encoding)
+        .map_err(|e| e.to_string())?;
+    loaded.extend(fetched.into_iter().map(|c| (c.index, c)));
+    let mut chapters: Vec<TxtChapterContent> = indices.iter().filter_map(|idx| loaded.remove(idx)).collect();
+    if let Some(options) = format {
+        for chapter in chapters.iter_mut() {
+            chapter.apply_format(&options);
+        }
+    }
     eprintln!("[TxtCommands] 加载章节完成: {} - {} 章", file_path, chapters.len());
 
     Ok(chapters)
 }
 
+/// 按全书百分比、原文行号或全文字符偏移快速定位，返回所在章节索引与章内偏移
+/// 供进度条拖动与“跳转到”使用，`force_encoding` 含义同 `txt_load_metadata`
+#[tauri::command]
+pub async fn txt_locate(
+    file_path: String,
+    by: LocateBy,
+    force_encoding: Option<String>,
+) -> Result<TxtLocation, String> {
+    let meta = load_cached_meta(&file_path, force_encoding.as_deref())?;
+    TxtEngine::locate(&file_path, &meta, by).map_err(|e| e.to_string())
+}
+
+/// 把文本中的破折号、省略号、括号、引号转换为竖排字形，供竖排阅读使用；逗号、句号等保持不变
+#[tauri::command]
+pub async fn txt_convert_for_vertical(content: String) -> Result<String, String> {
+    Ok(to_vertical_text(&content))
+}
+
+/// 检测文件可能的编码，返回按置信度降序排列的候选列表
+#[tauri::command]
+pub async fn txt_detect_encodings(file_path: String) -> Result<Vec<TxtEncodingCandidate>, String> {
+    tokio::task::spawn_blocking(move || TxtEngine::detect_encodings(&file_path))
+        .await
+        .map_err(|e| format!("编码检测任务失败: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// 修复文本中夹杂的乱码段：后端直接读取 `file_path` 的原始字节，`encoding` 为整体编码（不传时自动检测）
+/// 解码出错的连续行依次尝试 GBK/Big5/UTF-8 重新解码并择优，返回修复后的文本与各乱码段的位置
+#[tauri::command]
+pub async fn txt_repair_text(file_path: String, encoding: Option<String>) -> Result<TxtRepairResult, String> {
+    ensure_real_txt_path(&file_path)?;
+    tokio::task::spawn_blocking(move || {
+        let content = std::fs::read(&file_path).map_err(|e| format!("读取文件失败: {}", e))?;
+        repair_garbled_text(&content, encoding.as_deref()).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("乱码修复任务失败: {}", e))?
+}
+
+/// 诊断章节识别：返回各章节模式的命中次数、识别为章节的行及所用模式、疑似误匹配的条目
+/// `nest_subsections` 与加载元数据时的章内小节选项一致，不传时按未开启处理
+#[tauri::command]
+pub async fn txt_diagnose_toc(file_path: String, nest_subsections: Option<bool>) -> Result<TocDiagnostics, String> {
+    let nest_subsections = nest_subsections.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        TxtEngine::from_file(&file_path).map(|engine| engine.diagnose_toc(nest_subsections))
+    })
+        .await
+        .map_err(|e| format!("目录诊断任务失败: {}", e))?
+        .map_err(|e| e.to_string())
+}
+
+/// 按每分钟阅读字数预估全书和各章阅读时间
+#[tauri::command]
+pub async fn txt_get_reading_estimate(
+    file_path: String,
+    wpm: u32,
+) -> Result<TxtReadingEstimate, String> {
+    let meta = txt_load_metadata(file_path, None, None).await?;
+    meta.reading_estimate(wpm).map_err(|e| e.to_string())
+}
+
 /// 清除指定文件的元数据缓存
 #[tauri::command]
 pub async fn txt_clear_metadata_cache(file_path: String) -> Result<(), String> {
     let mut cache = METADATA_CACHE.lock().map_err(|e| e.to_string())?;
     cache.remove(&file_path);
+    invalidate_prefetch(&file_path);
     eprintln!("[TxtCommands] 元数据缓存已清除: {}", file_path);
     Ok(())
 }
@@ -22,6 +22,8 @@ import { logError, getInvoke } from '../../index';
 interface OutlineNode {
   title: string;
   page_number?: number;
+  /** 自身无目标时第一个可定位子节点的页码 */
+  fallback_page?: number | null;
   children?: OutlineNode[];
 }
 
@@ -118,7 +120,7 @@ export class PdfRenderer implements IBookRenderer {
   private _convertOutline(nodes: OutlineNode[], level = 0): TocItem[] {
     return (nodes || []).map((n) => ({
       title: n.title || '无标题',
-      location: n.page_number || 1,
+      location: n.page_number || n.fallback_page || 1,
       level,
       children: this._convertOutline(n.children || [], level + 1),
     }));